@@ -5,8 +5,16 @@ use std::{
     path::{Path, PathBuf},
 };
 
+#[path = "build/transitions.rs"]
+mod transitions;
+
 fn main() {
     let dir = env::current_dir().unwrap().join("resources");
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let mut missing_resources = Vec::new();
+    let mut optional = |name: &'static str, path: PathBuf| {
+        optional_resource(name, path, &out_dir, &mut missing_resources)
+    };
     let popup_yes = dir.join("popup_yes_ideal_ratio.png");
     let popup_ok_old = dir.join("popup_ok_old_ideal_ratio.png");
     let popup_ok_new = dir.join("popup_ok_new_ideal_ratio.png");
@@ -76,53 +84,146 @@ fn main() {
     let extreme_green_potion_buff = dir.join("extreme_green_potion_ideal_ratio.png");
     let extreme_gold_potion_buff = dir.join("extreme_gold_potion_ideal_ratio.png");
 
-    let hexa_booster = dir.join("hexa_booster_ideal_ratio.png");
-    let hexa_booster_number = dir.join("hexa_booster_number_ideal_ratio.png");
-    let hexa_booster_number_mask = dir.join("hexa_booster_number_mask_ideal_ratio.png");
-
-    let hexa_menu = dir.join("hexa_menu_ideal_ratio.png");
-    let hexa_quick_menu = dir.join("hexa_quick_menu_ideal_ratio.png");
-    let hexa_button_erda_conversion = dir.join("hexa_button_erda_conversion_ideal_ratio.png");
-    let hexa_button_hexa_booster = dir.join("hexa_button_hexa_booster_ideal_ratio.png");
-    let hexa_button_max = dir.join("hexa_button_max_ideal_ratio.png");
-    let hexa_button_convert = dir.join("hexa_button_convert_ideal_ratio.png");
-    let hexa_sol_erda = dir.join("hexa_sol_erda_ideal_ratio.png");
-    let hexa_sol_erda_full = dir.join("hexa_sol_erda_full_ideal_ratio.png");
-    let hexa_sol_erda_full_mask = dir.join("hexa_sol_erda_full_mask_ideal_ratio.png");
-    let hexa_sol_erda_empty = dir.join("hexa_sol_erda_empty_ideal_ratio.png");
-    let hexa_sol_erda_empty_mask = dir.join("hexa_sol_erda_empty_mask_ideal_ratio.png");
+    let hexa_booster = optional(
+        "HEXA_BOOSTER_TEMPLATE",
+        dir.join("hexa_booster_ideal_ratio.png"),
+    );
+    let hexa_booster_number = optional(
+        "HEXA_BOOSTER_NUMBER_TEMPLATE",
+        dir.join("hexa_booster_number_ideal_ratio.png"),
+    );
+    let hexa_booster_number_mask = optional(
+        "HEXA_BOOSTER_NUMBER_MASK_TEMPLATE",
+        dir.join("hexa_booster_number_mask_ideal_ratio.png"),
+    );
+
+    let hexa_menu = optional("HEXA_MENU_TEMPLATE", dir.join("hexa_menu_ideal_ratio.png"));
+    let hexa_quick_menu = optional(
+        "HEXA_QUICK_MENU_TEMPLATE",
+        dir.join("hexa_quick_menu_ideal_ratio.png"),
+    );
+    let hexa_button_erda_conversion = optional(
+        "HEXA_BUTTON_ERDA_CONVERSION_TEMPLATE",
+        dir.join("hexa_button_erda_conversion_ideal_ratio.png"),
+    );
+    let hexa_button_hexa_booster = optional(
+        "HEXA_BUTTON_HEXA_BOOSTER_TEMPLATE",
+        dir.join("hexa_button_hexa_booster_ideal_ratio.png"),
+    );
+    let hexa_button_max = optional(
+        "HEXA_BUTTON_MAX_TEMPLATE",
+        dir.join("hexa_button_max_ideal_ratio.png"),
+    );
+    let hexa_button_convert = optional(
+        "HEXA_BUTTON_CONVERT_TEMPLATE",
+        dir.join("hexa_button_convert_ideal_ratio.png"),
+    );
+    let hexa_sol_erda = optional(
+        "HEXA_SOL_ERDA_TEMPLATE",
+        dir.join("hexa_sol_erda_ideal_ratio.png"),
+    );
+    let hexa_sol_erda_full = optional(
+        "HEXA_SOL_ERDA_FULL_TEMPLATE",
+        dir.join("hexa_sol_erda_full_ideal_ratio.png"),
+    );
+    let hexa_sol_erda_full_mask = optional(
+        "HEXA_SOL_ERDA_FULL_MASK_TEMPLATE",
+        dir.join("hexa_sol_erda_full_mask_ideal_ratio.png"),
+    );
+    let hexa_sol_erda_empty = optional(
+        "HEXA_SOL_ERDA_EMPTY_TEMPLATE",
+        dir.join("hexa_sol_erda_empty_ideal_ratio.png"),
+    );
+    let hexa_sol_erda_empty_mask = optional(
+        "HEXA_SOL_ERDA_EMPTY_MASK_TEMPLATE",
+        dir.join("hexa_sol_erda_empty_mask_ideal_ratio.png"),
+    );
+
+    let level_up_popup = optional(
+        "LEVEL_UP_POPUP_TEMPLATE",
+        dir.join("level_up_popup_ideal_ratio.png"),
+    );
+    let new_skill_popup = optional(
+        "NEW_SKILL_POPUP_TEMPLATE",
+        dir.join("new_skill_popup_ideal_ratio.png"),
+    );
 
     let hp_bar_anchor = dir.join("hp_bar_anchor_ideal_ratio.png");
     let hp_separator = dir.join("hp_separator_ideal_ratio.png");
     let hp_shield = dir.join("hp_shield_ideal_ratio.png");
 
-    let familiar_button_save = dir.join("familiar_button_save_ideal_ratio.png");
-    let familiar_button_setup = dir.join("familiar_button_setup_ideal_ratio.png");
-    let familiar_button_level = dir.join("familiar_button_level_ideal_ratio.png");
-    let familiar_slot_free = dir.join("familiar_slot_free_ideal_ratio.png");
-    let familiar_slot_occupied = dir.join("familiar_slot_occupied_ideal_ratio.png");
-    let familiar_slot_occupied_mask = dir.join("familiar_slot_occupied_mask_ideal_ratio.png");
-    let familiar_level_5 = dir.join("familiar_level_5_ideal_ratio.png");
-    let familiar_level_5_mask = dir.join("familiar_level_5_mask_ideal_ratio.png");
-    let familiar_scrollbar = dir.join("familiar_scrollbar_ideal_ratio.png");
-    let familiar_card_rare = dir.join("familiar_card_rare_ideal_ratio.png");
-    let familiar_card_epic = dir.join("familiar_card_epic_ideal_ratio.png");
-    let familiar_card_mask = dir.join("familiar_card_mask_ideal_ratio.png");
+    let familiar_button_save = optional(
+        "FAMILIAR_BUTTON_SAVE_TEMPLATE",
+        dir.join("familiar_button_save_ideal_ratio.png"),
+    );
+    let familiar_button_setup = optional(
+        "FAMILIAR_BUTTON_SETUP_TEMPLATE",
+        dir.join("familiar_button_setup_ideal_ratio.png"),
+    );
+    let familiar_button_level = optional(
+        "FAMILIAR_BUTTON_LEVEL_TEMPLATE",
+        dir.join("familiar_button_level_ideal_ratio.png"),
+    );
+    let familiar_slot_free = optional(
+        "FAMILIAR_SLOT_FREE_TEMPLATE",
+        dir.join("familiar_slot_free_ideal_ratio.png"),
+    );
+    let familiar_slot_occupied = optional(
+        "FAMILIAR_SLOT_OCCUPIED_TEMPLATE",
+        dir.join("familiar_slot_occupied_ideal_ratio.png"),
+    );
+    let familiar_slot_occupied_mask = optional(
+        "FAMILIAR_SLOT_OCCUPIED_MASK_TEMPLATE",
+        dir.join("familiar_slot_occupied_mask_ideal_ratio.png"),
+    );
+    let familiar_level_5 = optional(
+        "FAMILIAR_LEVEL_5_TEMPLATE",
+        dir.join("familiar_level_5_ideal_ratio.png"),
+    );
+    let familiar_level_5_mask = optional(
+        "FAMILIAR_LEVEL_5_MASK_TEMPLATE",
+        dir.join("familiar_level_5_mask_ideal_ratio.png"),
+    );
+    let familiar_scrollbar = optional(
+        "FAMILIAR_SCROLLBAR_TEMPLATE",
+        dir.join("familiar_scrollbar_ideal_ratio.png"),
+    );
+    let familiar_card_rare = optional(
+        "FAMILIAR_CARD_RARE_TEMPLATE",
+        dir.join("familiar_card_rare_ideal_ratio.png"),
+    );
+    let familiar_card_epic = optional(
+        "FAMILIAR_CARD_EPIC_TEMPLATE",
+        dir.join("familiar_card_epic_ideal_ratio.png"),
+    );
+    let familiar_card_mask = optional(
+        "FAMILIAR_CARD_MASK_TEMPLATE",
+        dir.join("familiar_card_mask_ideal_ratio.png"),
+    );
     let familiar_buff = dir.join("familiar_buff_ideal_ratio.png");
-    let familiar_menu = dir.join("familiar_menu_ideal_ratio.png");
+    let familiar_menu = optional(
+        "FAMILIAR_MENU_TEMPLATE",
+        dir.join("familiar_menu_ideal_ratio.png"),
+    );
     let familiar_essence_deplete = dir.join("familiar_essence_deplete_ideal_ratio.png");
 
+    #[cfg(not(feature = "linux"))]
     let onnx_runtime = dir.join("onnxruntime/onnxruntime.dll");
+    #[cfg(feature = "linux")]
+    let onnx_runtime = dir.join("onnxruntime/libonnxruntime.so");
     #[cfg(feature = "gpu")]
     let onnx_runtime_cuda = dir.join("onnxruntime/onnxruntime_providers_cuda.dll");
     #[cfg(feature = "gpu")]
     let onnx_runtime_shared = dir.join("onnxruntime/onnxruntime_providers_shared.dll");
 
     let mob_model = dir.join("mob_nms.onnx");
-    let rune_model = dir.join("rune_nms.onnx");
-    let rune_spin_model = dir.join("rune_spin_nms.onnx");
+    let rune_model = optional("RUNE_MODEL", dir.join("rune_nms.onnx"));
+    let rune_spin_model = optional("RUNE_SPIN_MODEL", dir.join("rune_spin_nms.onnx"));
     let minimap_model = dir.join("minimap_nms.onnx");
-    let transparent_shape_model = dir.join("transparent_shape_nms.onnx");
+    let transparent_shape_model = optional(
+        "TRANSPARENT_SHAPE_MODEL",
+        dir.join("transparent_shape_nms.onnx"),
+    );
     let text_detection_model = dir.join("text_detection.onnx");
     let text_recognition_model = dir.join("text_recognition.onnx");
     let text_alphabet_txt = dir.join("alphabet_94.txt");
@@ -413,6 +514,15 @@ fn main() {
         hexa_sol_erda_empty_mask.to_str().unwrap()
     );
 
+    println!(
+        "cargo:rustc-env=LEVEL_UP_POPUP_TEMPLATE={}",
+        level_up_popup.to_str().unwrap()
+    );
+    println!(
+        "cargo:rustc-env=NEW_SKILL_POPUP_TEMPLATE={}",
+        new_skill_popup.to_str().unwrap()
+    );
+
     println!(
         "cargo:rustc-env=HP_BAR_ANCHOR_TEMPLATE={}",
         hp_bar_anchor.to_str().unwrap()
@@ -496,7 +606,11 @@ fn main() {
         .join("dx")
         .join("ui")
         .join(env::var("PROFILE").unwrap())
-        .join("windows")
+        .join(if cfg!(feature = "linux") {
+            "linux"
+        } else {
+            "windows"
+        })
         .join("app");
     let normal_exe_dir = target_dir
         .join(env::var("TARGET").unwrap())
@@ -588,6 +702,41 @@ fn main() {
         "cargo:rustc-env=TEXT_RECOGNITION_ALPHABET={}",
         text_alphabet_txt.to_str().unwrap()
     );
+    println!(
+        "cargo:rustc-env=MISSING_RESOURCES={}",
+        missing_resources.join(",")
+    );
+
+    // Player state machine transitions
+    let player_dir = env::current_dir().unwrap().join("src").join("player");
+    fs::write(
+        out_dir.join("player_transitions.rs"),
+        transitions::generate(&player_dir),
+    )
+    .unwrap();
+    println!("cargo:rerun-if-changed={}", player_dir.to_str().unwrap());
+    println!("cargo:rerun-if-changed=build/transitions.rs");
+}
+
+/// Replaces `path` of an optional resource `name` with an empty file if it does not exist.
+///
+/// Features depending on the missing resource are disabled at runtime instead of failing the build.
+fn optional_resource(
+    name: &'static str,
+    path: PathBuf,
+    out_dir: &Path,
+    missing_resources: &mut Vec<&'static str>,
+) -> PathBuf {
+    println!("cargo:rerun-if-changed={}", path.to_str().unwrap());
+    if path.exists() {
+        return path;
+    }
+
+    let placeholder = out_dir.join(path.file_name().unwrap());
+    fs::write(&placeholder, b"").unwrap();
+    println!("cargo:warning=optional resource {name} is missing, dependent features are disabled");
+    missing_resources.push(name);
+    placeholder
 }
 
 fn copy_file_to_dir(file: &PathBuf, dir: &Path) {
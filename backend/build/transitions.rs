@@ -0,0 +1,532 @@
+//! Derives the player contextual state transitions from the player module source.
+//!
+//! Each player state is updated by the function called from its arm in `update_positional_state`
+//! or `update_non_positional_state`. The states a state can transition to are the `Player::*`
+//! values constructed by that function and, transitively, by the functions and macros it calls
+//! within the player module. A state stored in `stalling_timeout_state` is transitioned to from
+//! [`Player::Stalling`] instead.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+    fs,
+    path::Path,
+};
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Ident(String),
+    Punct(&'static str),
+}
+
+/// A function or macro defined in the player module.
+struct Item {
+    file: String,
+    public: bool,
+    body: Vec<Token>,
+}
+
+/// Generates the Rust source of the `TRANSITIONS` table from the `player_dir` module.
+pub fn generate(player_dir: &Path) -> String {
+    let mut files = fs::read_dir(player_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "rs"))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut items = HashMap::<String, Vec<Item>>::new();
+    let mut mod_tokens = Vec::new();
+    for path in files {
+        let file = path.file_name().unwrap().to_str().unwrap().to_string();
+        let tokens = without_tests(tokenize(&fs::read_to_string(&path).unwrap()));
+        collect_items(&file, &tokens, &mut items);
+        if file == "mod.rs" {
+            mod_tokens = tokens;
+        }
+    }
+
+    let kinds = player_kinds(&mod_tokens);
+    let mut transitions = HashMap::<String, HashSet<String>>::new();
+    for dispatch in ["update_positional_state", "update_non_positional_state"] {
+        let body = &items[dispatch]
+            .iter()
+            .find(|item| item.file == "mod.rs")
+            .expect("dispatch function is in mod.rs")
+            .body;
+        for (froms, expr) in match_arms(body) {
+            let froms = froms
+                .into_iter()
+                .filter(|from| kinds.contains(from))
+                .collect::<Vec<_>>();
+            if froms.is_empty() {
+                continue;
+            }
+
+            let mut tos = HashSet::new();
+            let mut stalling_tos = HashSet::new();
+            collect_transitions(&kinds, &items, "mod.rs", &expr, &mut tos, &mut stalling_tos);
+            for from in froms {
+                transitions
+                    .entry(from)
+                    .or_default()
+                    .extend(tos.iter().cloned());
+            }
+            transitions
+                .entry("Stalling".to_string())
+                .or_default()
+                .extend(stalling_tos);
+        }
+    }
+
+    let mut source = String::new();
+    let _ = writeln!(
+        source,
+        "const TRANSITIONS: &[(PlayerKind, &[PlayerKind])] = &["
+    );
+    for from in &kinds {
+        let tos = transitions.get(from);
+        let _ = write!(source, "    (PlayerKind::{from}, &[");
+        for to in kinds
+            .iter()
+            .filter(|to| *to != from && tos.is_some_and(|tos| tos.contains(*to)))
+        {
+            let _ = write!(source, "PlayerKind::{to}, ");
+        }
+        let _ = writeln!(source, "]),");
+    }
+    let _ = writeln!(source, "];");
+
+    source
+}
+
+/// Collects the states constructed by `body` and the functions it calls into `tos`.
+///
+/// States stored in `stalling_timeout_state` are collected into `stalling_tos` instead.
+fn collect_transitions(
+    kinds: &[String],
+    items: &HashMap<String, Vec<Item>>,
+    file: &str,
+    body: &[Token],
+    tos: &mut HashSet<String>,
+    stalling_tos: &mut HashSet<String>,
+) {
+    let mut visited = HashSet::<(String, String)>::new();
+    let mut pending = vec![(file.to_string(), body.to_vec())];
+
+    while let Some((file, body)) = pending.pop() {
+        for (kind, stalling) in constructed_states(kinds, &body) {
+            if stalling {
+                stalling_tos.insert(kind);
+            } else {
+                tos.insert(kind);
+            }
+        }
+
+        for name in called_items(&body) {
+            let Some(definitions) = items.get(&name) else {
+                continue;
+            };
+            let same_file = definitions.iter().any(|item| item.file == file);
+            for item in definitions.iter().filter(|item| {
+                if same_file {
+                    item.file == file
+                } else {
+                    item.public
+                }
+            }) {
+                if visited.insert((item.file.clone(), name.clone())) {
+                    pending.push((item.file.clone(), item.body.clone()));
+                }
+            }
+        }
+    }
+}
+
+/// Returns the `Player::*` states constructed (i.e. not matched against) in `body`.
+///
+/// The returned flag is `true` if the state is stored in `stalling_timeout_state`.
+fn constructed_states(kinds: &[String], body: &[Token]) -> Vec<(String, bool)> {
+    let mut states = Vec::new();
+    let mut groups = Vec::<bool>::new();
+
+    for (index, token) in body.iter().enumerate() {
+        match token {
+            Token::Punct("(" | "[" | "{") => {
+                let is_matches = index >= 2
+                    && body[index - 1] == Token::Punct("!")
+                    && body[index - 2] == ident("matches");
+                groups.push(is_matches);
+            }
+            Token::Punct(")" | "]" | "}") => {
+                groups.pop();
+            }
+            _ => (),
+        }
+
+        if *token != ident("Player") || body.get(index + 1) != Some(&Token::Punct("::")) {
+            continue;
+        }
+        let Some(Token::Ident(kind)) = body.get(index + 2) else {
+            continue;
+        };
+        if !kinds.contains(kind) || groups.iter().any(|is_matches| *is_matches) {
+            continue;
+        }
+
+        let mut end = index + 3;
+        if matches!(body.get(end), Some(Token::Punct("(" | "{"))) {
+            end = group_end(body, end) + 1;
+        }
+        while matches!(body.get(end), Some(Token::Punct(")" | "]" | "}"))) {
+            end += 1;
+        }
+        let is_pattern = matches!(body.get(end), Some(Token::Punct("=>" | "|" | "=" | "@")))
+            || body.get(end) == Some(&ident("if"));
+        if is_pattern {
+            continue;
+        }
+
+        let stalling = index >= 4
+            && body[index - 4..index]
+                == [
+                    ident("stalling_timeout_state"),
+                    Token::Punct("="),
+                    ident("Some"),
+                    Token::Punct("("),
+                ];
+        states.push((kind.clone(), stalling));
+    }
+
+    states
+}
+
+/// Returns the names of the functions and macros called in `body`.
+///
+/// Macros are named with a trailing `!`.
+fn called_items(body: &[Token]) -> Vec<String> {
+    let mut names = Vec::new();
+
+    for (index, token) in body.iter().enumerate() {
+        let Token::Ident(name) = token else {
+            continue;
+        };
+        match (body.get(index + 1), body.get(index + 2)) {
+            (Some(Token::Punct("(")), _) => names.push(name.clone()),
+            (Some(Token::Punct("!")), Some(Token::Punct("(" | "[" | "{"))) => {
+                names.push(format!("{name}!"))
+            }
+            _ => (),
+        }
+    }
+
+    names
+}
+
+/// Collects the functions and `macro_rules!` macros defined in `tokens` of `file` into `items`.
+fn collect_items(file: &str, tokens: &[Token], items: &mut HashMap<String, Vec<Item>>) {
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let (name, public) = match (&tokens[index], tokens.get(index + 1), tokens.get(index + 2)) {
+            (Token::Ident(keyword), Some(Token::Ident(name)), _) if keyword == "fn" => {
+                (name.clone(), is_public(tokens, index))
+            }
+            (Token::Ident(keyword), Some(Token::Punct("!")), Some(Token::Ident(name)))
+                if keyword == "macro_rules" =>
+            {
+                (format!("{name}!"), true)
+            }
+            _ => {
+                index += 1;
+                continue;
+            }
+        };
+
+        let Some(start) = tokens[index..]
+            .iter()
+            .position(|token| matches!(token, Token::Punct("{" | ";")))
+            .map(|offset| index + offset)
+        else {
+            break;
+        };
+        if tokens[start] == Token::Punct(";") {
+            index = start + 1;
+            continue;
+        }
+
+        let end = group_end(tokens, start);
+        items.entry(name).or_default().push(Item {
+            file: file.to_string(),
+            public,
+            body: tokens[start..=end].to_vec(),
+        });
+        // Nested functions are also collected
+        index += 1;
+    }
+}
+
+/// Returns the pattern states and the expression of each arm of the first `match` in `body`.
+fn match_arms(body: &[Token]) -> Vec<(Vec<String>, Vec<Token>)> {
+    let mut arms = Vec::new();
+    let Some(start) = body
+        .iter()
+        .position(|token| *token == ident("match"))
+        .and_then(|index| {
+            body[index..]
+                .iter()
+                .position(|token| *token == Token::Punct("{"))
+                .map(|offset| index + offset)
+        })
+    else {
+        return arms;
+    };
+    let end = group_end(body, start);
+    let mut index = start + 1;
+
+    while index < end {
+        let Some(arrow) = body[index..end]
+            .iter()
+            .position(|token| *token == Token::Punct("=>"))
+            .map(|offset| index + offset)
+        else {
+            break;
+        };
+        let froms = body[index..arrow]
+            .windows(3)
+            .filter_map(|window| match window {
+                [Token::Ident(player), Token::Punct("::"), Token::Ident(kind)]
+                    if player == "Player" =>
+                {
+                    Some(kind.clone())
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut expr_end = arrow + 1;
+        if body[expr_end] == Token::Punct("{") {
+            expr_end = group_end(body, expr_end) + 1;
+        } else {
+            while expr_end < end && body[expr_end] != Token::Punct(",") {
+                if matches!(body[expr_end], Token::Punct("(" | "[" | "{")) {
+                    expr_end = group_end(body, expr_end);
+                }
+                expr_end += 1;
+            }
+        }
+        arms.push((froms, body[arrow + 1..expr_end].to_vec()));
+
+        index = expr_end;
+        if body.get(index) == Some(&Token::Punct(",")) {
+            index += 1;
+        }
+    }
+
+    arms
+}
+
+/// Returns the variant names of the `Player` enum in declaration order.
+fn player_kinds(tokens: &[Token]) -> Vec<String> {
+    let start = tokens
+        .windows(3)
+        .position(|window| window == [ident("enum"), ident("Player"), Token::Punct("{")])
+        .expect("has Player enum")
+        + 2;
+    let end = group_end(tokens, start);
+    let mut kinds = Vec::new();
+    let mut index = start + 1;
+
+    while index < end {
+        match &tokens[index] {
+            Token::Punct("#") => index = group_end(tokens, index + 1),
+            Token::Ident(kind) => {
+                kinds.push(kind.clone());
+                while index < end && tokens[index] != Token::Punct(",") {
+                    if matches!(tokens[index], Token::Punct("(" | "{")) {
+                        index = group_end(tokens, index);
+                    }
+                    index += 1;
+                }
+            }
+            _ => (),
+        }
+        index += 1;
+    }
+
+    kinds
+}
+
+/// Whether the item starting with `fn` at `index` has a `pub` visibility.
+fn is_public(tokens: &[Token], index: usize) -> bool {
+    let mut index = index;
+    while index > 0 {
+        index -= 1;
+        match &tokens[index] {
+            Token::Ident(keyword) if keyword == "pub" => return true,
+            Token::Ident(keyword)
+                if [
+                    "const", "unsafe", "async", "extern", "crate", "super", "self", "in",
+                ]
+                .contains(&keyword.as_str()) => {}
+            Token::Punct("(" | ")") => {}
+            _ => return false,
+        }
+    }
+
+    false
+}
+
+/// Returns the index of the closing token of the group opened at `start`.
+fn group_end(tokens: &[Token], start: usize) -> usize {
+    let mut depth = 0;
+
+    for (index, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::Punct("(" | "[" | "{") => depth += 1,
+            Token::Punct(")" | "]" | "}") => {
+                depth -= 1;
+                if depth == 0 {
+                    return index;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    tokens.len() - 1
+}
+
+/// Removes the `#[cfg(test)] mod tests` module and everything after it.
+fn without_tests(mut tokens: Vec<Token>) -> Vec<Token> {
+    let tests = [
+        Token::Punct("#"),
+        Token::Punct("["),
+        ident("cfg"),
+        Token::Punct("("),
+        ident("test"),
+        Token::Punct(")"),
+        Token::Punct("]"),
+        ident("mod"),
+    ];
+    if let Some(index) = tokens
+        .windows(tests.len())
+        .position(|window| window == tests)
+    {
+        tokens.truncate(index);
+    }
+
+    tokens
+}
+
+/// Splits `source` into identifiers and punctuations.
+///
+/// Comments, literals and lifetimes are skipped.
+fn tokenize(source: &str) -> Vec<Token> {
+    const PUNCTS: [&str; 31] = [
+        "::", "=>", "==", "!=", "<=", ">=", "||", "&&", "->", "..", "(", ")", "[", "]", "{", "}",
+        "#", "!", "=", "|", "@", ",", ";", ":", ".", "&", "<", ">", "+", "-", "*",
+    ];
+
+    let chars = source.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let char = chars[index];
+        let next = chars.get(index + 1).copied();
+
+        if char.is_whitespace() {
+            index += 1;
+        } else if char == '/' && next == Some('/') {
+            while index < chars.len() && chars[index] != '\n' {
+                index += 1;
+            }
+        } else if char == '/' && next == Some('*') {
+            index += 2;
+            while index + 1 < chars.len() && !(chars[index] == '*' && chars[index + 1] == '/') {
+                index += 1;
+            }
+            index += 2;
+        } else if char == '"' {
+            index = string_end(&chars, index + 1);
+        } else if char == 'r' && matches!(next, Some('"' | '#')) {
+            let hashes = chars[index + 1..]
+                .iter()
+                .take_while(|char| **char == '#')
+                .count();
+            index += 2 + hashes;
+            while index < chars.len()
+                && !(chars[index] == '"'
+                    && chars[index + 1..]
+                        .iter()
+                        .take(hashes)
+                        .all(|char| *char == '#'))
+            {
+                index += 1;
+            }
+            index += 1 + hashes;
+        } else if char == '\'' {
+            // Character literal or lifetime
+            if next == Some('\\') {
+                index += 3;
+                while index < chars.len() && chars[index] != '\'' {
+                    index += 1;
+                }
+                index += 1;
+            } else if chars.get(index + 2) == Some(&'\'') {
+                index += 3;
+            } else {
+                index += 1;
+                while index < chars.len() && is_ident_char(chars[index]) {
+                    index += 1;
+                }
+            }
+        } else if is_ident_char(char) {
+            let start = index;
+            while index < chars.len() && is_ident_char(chars[index]) {
+                index += 1;
+            }
+            if char == 'b' && index - start == 1 && chars.get(index) == Some(&'"') {
+                index = string_end(&chars, index + 1);
+                continue;
+            }
+            tokens.push(Token::Ident(chars[start..index].iter().collect()));
+        } else {
+            let punct = PUNCTS.iter().find(|punct| {
+                punct
+                    .chars()
+                    .enumerate()
+                    .all(|(offset, char)| chars.get(index + offset) == Some(&char))
+            });
+            match punct {
+                Some(punct) => {
+                    tokens.push(Token::Punct(punct));
+                    index += punct.len();
+                }
+                None => index += 1,
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Returns the index after the closing quote of a string literal with content starting at `index`.
+fn string_end(chars: &[char], mut index: usize) -> usize {
+    while index < chars.len() && chars[index] != '"' {
+        index += if chars[index] == '\\' { 2 } else { 1 };
+    }
+
+    index + 1
+}
+
+#[inline]
+fn is_ident_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '_'
+}
+
+#[inline]
+fn ident(name: &str) -> Token {
+    Token::Ident(name.to_string())
+}
@@ -0,0 +1,228 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+use crate::models::KeyBinding;
+
+/// Maximum size in bytes of a single audit file before it is rotated.
+const MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Maximum number of rotated audit files kept, excluding the current file.
+const MAX_ROTATED_FILES_COUNT: usize = 3;
+
+const FILE_NAME: &str = "input_audit";
+
+static AUDIT_DIR: LazyLock<PathBuf> =
+    LazyLock::new(|| env::current_exe().unwrap().parent().unwrap().join("audit"));
+
+/// The kind of key stroke recorded in an [`InputAudit`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display, Serialize, Deserialize)]
+pub enum InputAuditStroke {
+    Down,
+    Up,
+    Send,
+}
+
+/// A key stroke emitted by the input bridge.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct InputAudit {
+    /// The game tick the key stroke was emitted at.
+    pub tick: u64,
+    /// The wall-clock time in milliseconds since UNIX epoch the key stroke was emitted at.
+    pub timestamp_millis: u64,
+    pub key: KeyBinding,
+    pub stroke: InputAuditStroke,
+}
+
+/// Appends [`InputAudit`]s to a size-rotated file.
+#[derive(Debug)]
+pub struct InputAuditLog {
+    dir: PathBuf,
+    writer: Option<BufWriter<File>>,
+    size: u64,
+}
+
+impl Default for InputAuditLog {
+    fn default() -> Self {
+        Self::new(AUDIT_DIR.clone())
+    }
+}
+
+impl InputAuditLog {
+    /// Creates a log appending to files inside `dir`.
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            writer: None,
+            size: 0,
+        }
+    }
+
+    /// Records `stroke` of `key` at game `tick` with the current wall-clock time.
+    pub fn record(&mut self, tick: u64, key: KeyBinding, stroke: InputAuditStroke) {
+        let audit = InputAudit {
+            tick,
+            timestamp_millis: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64,
+            key,
+            stroke,
+        };
+        if let Err(err) = self.write(&audit) {
+            error!(target: "audit", "failed to record input audit {err}");
+            self.writer = None;
+        }
+    }
+
+    /// Flushes buffered [`InputAudit`]s to the file.
+    pub fn flush(&mut self) {
+        if let Some(writer) = self.writer.as_mut() {
+            let _ = writer.flush();
+        }
+    }
+
+    fn write(&mut self, audit: &InputAudit) -> anyhow::Result<()> {
+        if self.writer.is_none() || self.size >= MAX_FILE_SIZE {
+            self.open()?;
+        }
+
+        let mut line = serde_json::to_string(audit)?;
+        line.push('\n');
+        self.writer
+            .as_mut()
+            .expect("opened")
+            .write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+
+        Ok(())
+    }
+
+    /// Opens the current audit file for appending, rotating it first if it is full.
+    fn open(&mut self) -> anyhow::Result<()> {
+        self.flush();
+        self.writer = None;
+        fs::create_dir_all(&self.dir)?;
+
+        let path = file_path(&self.dir, 0);
+        let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        if size >= MAX_FILE_SIZE {
+            for index in (0..MAX_ROTATED_FILES_COUNT).rev() {
+                let from = file_path(&self.dir, index);
+                if from.exists() {
+                    fs::rename(from, file_path(&self.dir, index + 1))?;
+                }
+            }
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        self.size = file.metadata()?.len();
+        self.writer = Some(BufWriter::new(file));
+
+        Ok(())
+    }
+}
+
+/// Queries at most `limit` latest [`InputAudit`]s, optionally only of `key`.
+///
+/// The returned audits are ordered from the latest to the oldest.
+pub fn query_input_audits(key: Option<KeyBinding>, limit: usize) -> Vec<InputAudit> {
+    query_input_audits_from(&AUDIT_DIR, key, limit)
+}
+
+fn query_input_audits_from(dir: &Path, key: Option<KeyBinding>, limit: usize) -> Vec<InputAudit> {
+    let mut audits = Vec::new();
+
+    for index in 0..=MAX_ROTATED_FILES_COUNT {
+        let Ok(file) = File::open(file_path(dir, index)) else {
+            continue;
+        };
+        let mut file_audits = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<InputAudit>(&line).ok())
+            .filter(|audit| key.is_none_or(|key| audit.key == key))
+            .collect::<Vec<_>>();
+        file_audits.reverse();
+        audits.extend(file_audits);
+
+        if audits.len() >= limit {
+            break;
+        }
+    }
+
+    audits.truncate(limit);
+    audits
+}
+
+#[inline]
+fn file_path(dir: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        dir.join(format!("{FILE_NAME}.log"))
+    } else {
+        dir.join(format!("{FILE_NAME}.{index}.log"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("komari_{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn record_and_query_latest_first() {
+        let dir = temp_dir("record_and_query_latest_first");
+        let mut log = InputAuditLog::new(dir.clone());
+
+        log.record(1, KeyBinding::A, InputAuditStroke::Down);
+        log.record(2, KeyBinding::B, InputAuditStroke::Send);
+        log.record(3, KeyBinding::A, InputAuditStroke::Up);
+        log.flush();
+
+        let audits = query_input_audits_from(&dir, None, 2);
+        assert_eq!(audits.len(), 2);
+        assert_eq!(audits[0].tick, 3);
+        assert_eq!(audits[1].tick, 2);
+
+        let audits = query_input_audits_from(&dir, Some(KeyBinding::A), 10);
+        assert_eq!(
+            audits
+                .iter()
+                .map(|audit| (audit.tick, audit.stroke))
+                .collect::<Vec<_>>(),
+            vec![(3, InputAuditStroke::Up), (1, InputAuditStroke::Down)]
+        );
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn open_rotates_full_file() {
+        let dir = temp_dir("open_rotates_full_file");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(file_path(&dir, 0), vec![b'\n'; MAX_FILE_SIZE as usize]).unwrap();
+        let mut log = InputAuditLog::new(dir.clone());
+
+        log.record(1, KeyBinding::A, InputAuditStroke::Send);
+        log.flush();
+
+        assert!(file_path(&dir, 1).exists());
+        assert_eq!(query_input_audits_from(&dir, None, 10).len(), 1);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -2,9 +2,11 @@ use std::{
     cell::RefCell,
     collections::{HashMap, HashSet, hash_map::Entry},
     fmt::Debug,
+    time::Instant,
 };
 
 use anyhow::{Result, bail};
+use log::error;
 #[cfg(test)]
 use mockall::automock;
 #[cfg(windows)]
@@ -12,34 +14,37 @@ use platforms::capture::WindowsCaptureKind;
 use platforms::{
     CoordinateRelative, Error, Window,
     capture::{Capture as PlatformCapture, Frame},
+    clipboard,
+    gamepad::{Gamepad as PlatformGamepad, GamepadButton as PlatformGamepadButton},
     input::{
         Input as PlatformInput, InputKind as PlatformInputKind,
         InputReceiver as PlatformInputReceiver, KeyKind as PlatformKeyKind,
         KeyState as PlatformKeyState, MouseKind as PlatformMouseKind,
     },
 };
+use strum::IntoEnumIterator;
 
 use crate::{
-    models::{CaptureMode, KeyBinding, LinkKeyBinding},
+    audit::{InputAuditLog, InputAuditStroke},
+    focus::Focus,
+    metrics::record_input_stroke,
+    models::{
+        CaptureMode, FocusPolicy, GamepadButton, GamepadMapping, KeyBinding, KeyPressClass,
+        KeyPressDurations, KeyboardLayout, LinkKeyBinding,
+    },
     rng::Rng,
     rpc::{
         Coordinate as RpcCoordinate, InputService, Key as RpcKeyKind, KeyState as RpcKeyState,
         MouseAction as RpcMouseKind,
     },
-    run::MS_PER_TICK_F32,
+    tick::{self, millis_per_tick_f32},
 };
 
-/// Base mean in milliseconds to generate a pair from.
-const BASE_MEAN_MS_DELAY: f32 = 100.0;
-
-/// Base standard deviation in milliseconds to generate a pair from.
-const BASE_STD_MS_DELAY: f32 = 20.0;
-
-/// The rate at which generated standard deviation will revert to the base [`BASE_STD_MS_DELAY`]
-/// over time.
+/// The rate at which generated mean and standard deviation will revert to the base
+/// [`KeyPressDuration`](crate::KeyPressDuration) over time.
 const MEAN_STD_REVERSION_RATE: f32 = 0.2;
 
-/// The rate at which generated mean will revert to the base [`BASE_MEAN_MS_DELAY`] over time.
+/// The magnitude of random changes to generated mean and standard deviation over time.
 const MEAN_STD_VOLATILITY: f32 = 3.0;
 
 /// The current of key state.
@@ -77,6 +82,10 @@ pub enum MouseKind {
     Move,
     Click,
     Scroll,
+    /// Presses and holds the left mouse button for dragging.
+    Down,
+    /// Releases the left mouse button after dragging.
+    Up,
 }
 
 impl From<MouseKind> for RpcMouseKind {
@@ -85,6 +94,8 @@ impl From<MouseKind> for RpcMouseKind {
             MouseKind::Move => RpcMouseKind::Move,
             MouseKind::Click => RpcMouseKind::Click,
             MouseKind::Scroll => RpcMouseKind::ScrollDown,
+            MouseKind::Down => RpcMouseKind::Down,
+            MouseKind::Up => RpcMouseKind::Up,
         }
     }
 }
@@ -95,6 +106,31 @@ impl From<MouseKind> for PlatformMouseKind {
             MouseKind::Move => PlatformMouseKind::Move,
             MouseKind::Click => PlatformMouseKind::Click,
             MouseKind::Scroll => PlatformMouseKind::Scroll,
+            MouseKind::Down => PlatformMouseKind::Down,
+            MouseKind::Up => PlatformMouseKind::Up,
+        }
+    }
+}
+
+impl From<GamepadButton> for PlatformGamepadButton {
+    fn from(value: GamepadButton) -> Self {
+        match value {
+            GamepadButton::A => PlatformGamepadButton::A,
+            GamepadButton::B => PlatformGamepadButton::B,
+            GamepadButton::X => PlatformGamepadButton::X,
+            GamepadButton::Y => PlatformGamepadButton::Y,
+            GamepadButton::LeftShoulder => PlatformGamepadButton::LeftShoulder,
+            GamepadButton::RightShoulder => PlatformGamepadButton::RightShoulder,
+            GamepadButton::LeftTrigger => PlatformGamepadButton::LeftTrigger,
+            GamepadButton::RightTrigger => PlatformGamepadButton::RightTrigger,
+            GamepadButton::Back => PlatformGamepadButton::Back,
+            GamepadButton::Start => PlatformGamepadButton::Start,
+            GamepadButton::LeftThumb => PlatformGamepadButton::LeftThumb,
+            GamepadButton::RightThumb => PlatformGamepadButton::RightThumb,
+            GamepadButton::DpadUp => PlatformGamepadButton::DpadUp,
+            GamepadButton::DpadDown => PlatformGamepadButton::DpadDown,
+            GamepadButton::DpadLeft => PlatformGamepadButton::DpadLeft,
+            GamepadButton::DpadRight => PlatformGamepadButton::DpadRight,
         }
     }
 }
@@ -177,6 +213,7 @@ pub enum KeyKind {
     Comma,
     Period,
     Slash,
+    Minus,
     Esc,
     Shift,
     Alt,
@@ -252,6 +289,7 @@ impl From<KeyBinding> for KeyKind {
             KeyBinding::Comma => KeyKind::Comma,
             KeyBinding::Period => KeyKind::Period,
             KeyBinding::Slash => KeyKind::Slash,
+            KeyBinding::Minus => KeyKind::Minus,
             KeyBinding::Esc => KeyKind::Esc,
             KeyBinding::Shift => KeyKind::Shift,
             KeyBinding::Ctrl => KeyKind::Ctrl,
@@ -331,6 +369,7 @@ impl From<PlatformKeyKind> for KeyKind {
             PlatformKeyKind::Comma => KeyKind::Comma,
             PlatformKeyKind::Period => KeyKind::Period,
             PlatformKeyKind::Slash => KeyKind::Slash,
+            PlatformKeyKind::Minus => KeyKind::Minus,
             PlatformKeyKind::Esc => KeyKind::Esc,
             PlatformKeyKind::Shift => KeyKind::Shift,
             PlatformKeyKind::Alt => KeyKind::Alt,
@@ -409,6 +448,7 @@ impl From<KeyKind> for PlatformKeyKind {
             KeyKind::Comma => PlatformKeyKind::Comma,
             KeyKind::Period => PlatformKeyKind::Period,
             KeyKind::Slash => PlatformKeyKind::Slash,
+            KeyKind::Minus => PlatformKeyKind::Minus,
             KeyKind::Esc => PlatformKeyKind::Esc,
             KeyKind::Shift => PlatformKeyKind::Shift,
             KeyKind::Alt => PlatformKeyKind::Alt,
@@ -487,6 +527,7 @@ impl From<KeyKind> for RpcKeyKind {
             KeyKind::Comma => RpcKeyKind::Comma,
             KeyKind::Period => RpcKeyKind::Period,
             KeyKind::Slash => RpcKeyKind::Slash,
+            KeyKind::Minus => RpcKeyKind::Minus,
             KeyKind::Esc => RpcKeyKind::Esc,
             KeyKind::Shift => RpcKeyKind::Shift,
             KeyKind::Alt => RpcKeyKind::Alt,
@@ -564,6 +605,7 @@ impl From<KeyKind> for KeyBinding {
             KeyKind::Comma => KeyBinding::Comma,
             KeyKind::Period => KeyBinding::Period,
             KeyKind::Slash => KeyBinding::Slash,
+            KeyKind::Minus => KeyBinding::Minus,
             KeyKind::Esc => KeyBinding::Esc,
             KeyKind::Shift => KeyBinding::Shift,
             KeyKind::Ctrl => KeyBinding::Ctrl,
@@ -603,23 +645,46 @@ pub trait InputReceiver: Debug + 'static {
     fn set_window_and_input_kind(&mut self, window: Window, kind: PlatformInputKind);
 
     fn try_recv(&mut self) -> Result<KeyKind>;
+
+    /// Gets the last time a key or the mouse was physically used while the game is in the
+    /// foreground.
+    fn last_physical_input(&self) -> Option<Instant>;
+
+    /// Sets the `keys` that do not count as physical input for [`Self::last_physical_input`].
+    fn set_physical_input_ignored_keys(&mut self, keys: Vec<KeyKind>);
 }
 
 #[derive(Debug)]
 pub struct DefaultInputReceiver {
     inner: PlatformInputReceiver,
+    /// Whether this receiver receives keys regardless of the foreground window.
+    global: bool,
 }
 
 impl DefaultInputReceiver {
     pub fn new(window: Window, kind: PlatformInputKind) -> Self {
         Self {
             inner: PlatformInputReceiver::new(window, kind).expect("supported platform"),
+            global: false,
+        }
+    }
+
+    /// Creates a receiver that receives keys even when the game or this application is not
+    /// in the foreground.
+    pub fn new_global() -> Self {
+        Self {
+            inner: PlatformInputReceiver::new_global().expect("supported platform"),
+            global: true,
         }
     }
 }
 
 impl InputReceiver for DefaultInputReceiver {
     fn set_window_and_input_kind(&mut self, window: Window, kind: PlatformInputKind) {
+        if self.global {
+            return;
+        }
+
         self.inner = PlatformInputReceiver::new(window, kind).expect("supported platform")
     }
 
@@ -627,6 +692,17 @@ impl InputReceiver for DefaultInputReceiver {
     fn try_recv(&mut self) -> Result<KeyKind> {
         Ok(self.inner.try_recv()?.into())
     }
+
+    #[inline]
+    fn last_physical_input(&self) -> Option<Instant> {
+        self.inner.last_physical_input()
+    }
+
+    fn set_physical_input_ignored_keys(&mut self, keys: Vec<KeyKind>) {
+        platforms::input::set_physical_input_ignored_keys(
+            keys.into_iter().map(PlatformKeyKind::from).collect(),
+        );
+    }
 }
 
 /// Options for key down input.
@@ -652,6 +728,8 @@ impl InputKeyDownOptions {
 pub enum InputMethod {
     Rpc(Window, String),
     Default(Window, PlatformInputKind),
+    /// Sends keys as controller buttons through a virtual gamepad using the provided mappings.
+    Gamepad(Window, Vec<GamepadMapping>),
 }
 
 /// Inner kind of [`InputMethod`].
@@ -662,6 +740,10 @@ pub enum InputMethod {
 enum InputMethodInner {
     Rpc(Window, Option<RefCell<InputService>>),
     Default(PlatformInput),
+    Gamepad(
+        Option<PlatformGamepad>,
+        HashMap<KeyKind, PlatformGamepadButton>,
+    ),
 }
 
 /// States of input delay tracking.
@@ -681,6 +763,30 @@ pub trait Input: Debug {
     /// Overwrites the current input method with new `method`.
     fn set_method(&mut self, method: InputMethod);
 
+    /// Sets the game window focus `policy` to apply before sending inputs.
+    ///
+    /// `grace_period_millis` is the time to wait after the game window regains focus before
+    /// inputs are sent again.
+    fn set_focus_policy(&mut self, policy: FocusPolicy, grace_period_millis: u64);
+
+    /// Sets the distributions key press durations are drawn from for each key class.
+    fn set_press_durations(&mut self, durations: KeyPressDurations);
+
+    /// Sets whether inputs are only observed and not sent.
+    ///
+    /// While observing, key and mouse strokes are dropped except for releasing keys that are
+    /// still held down.
+    fn set_observer(&mut self, observer: bool);
+
+    /// Sets the keyboard `layout` used by the game machine.
+    ///
+    /// Keys are sent as the key with the same label on `layout` instead of the key at the same
+    /// position on a QWERTY keyboard.
+    fn set_keyboard_layout(&mut self, layout: KeyboardLayout);
+
+    /// Sets whether key strokes are recorded to the input audit log.
+    fn set_audit(&mut self, audit: bool);
+
     /// Sends mouse `kind` to `(x, y)` relative to the client coordinate (e.g. capture area).
     ///
     /// `(0, 0)` is top-left and `(width, height)` is bottom-right.
@@ -692,6 +798,15 @@ pub trait Input: Debug {
     /// Releases a held key `kind`.
     fn send_key_up(&self, kind: KeyKind);
 
+    /// Pastes `text` by replacing the clipboard content and pressing `Ctrl+V`.
+    ///
+    /// The previous clipboard text is restored on a later [`Self::update`] once the paste key is
+    /// released.
+    ///
+    /// Returns `false` if `text` could not be pasted (e.g. clipboard not supported or the input
+    /// method is `Rpc`).
+    fn send_paste(&self, text: &str) -> bool;
+
     /// Holds down key `kind`.
     ///
     /// This key stroke is sent with the default options.
@@ -707,24 +822,92 @@ pub trait Input: Debug {
 
     /// Whether all keys are cleared.
     fn all_keys_cleared(&self) -> bool;
+
+    /// Whether any key was sent a down stroke without a matching up stroke.
+    ///
+    /// Unlike [`Self::all_keys_cleared`], keys pending input delay are not considered held.
+    fn any_key_held(&self) -> bool;
+
+    /// Releases all keys currently held down, including keys pending input delay.
+    fn send_all_keys_up(&self);
 }
 
 /// Default implementation of [`Input`].
 #[derive(Debug)]
 pub struct DefaultInput {
     kind: InputMethodInner,
+    focus: Focus,
     delay_rng: Rng,
-    delay_mean_std_pair: (f32, f32),
+    /// The base key press duration of each [`KeyPressClass`].
+    delay_durations: KeyPressDurations,
+    /// The current mean and standard deviation of each [`KeyPressClass`] drifting around
+    /// [`Self::delay_durations`].
+    delay_mean_std_pairs: HashMap<KeyPressClass, (f32, f32)>,
     delay_map: RefCell<HashMap<KeyKind, (u32, bool)>>,
+    /// Keys that were sent a down stroke but not yet an up stroke.
+    held_keys: RefCell<HashSet<KeyKind>>,
+    /// The clipboard text before [`Input::send_paste`] to restore once the paste key is released.
+    clipboard_to_restore: RefCell<Option<String>>,
+    /// The last game tick from [`Self::update`] used for recording audits.
+    tick: u64,
+    audit: RefCell<InputAuditLog>,
+    /// Whether key strokes are recorded to [`Self::audit`].
+    audit_enabled: bool,
+    /// Whether inputs are dropped instead of sent.
+    observer: bool,
+    layout: KeyboardLayout,
 }
 
 impl DefaultInput {
     pub fn new(method: InputMethod, rng: Rng) -> Self {
+        let mut focus = Focus::default();
+        focus.set_window(focus_window_from(&method));
+
         Self {
             kind: input_method_inner_from(method, rng.rng_seed()),
+            focus,
             delay_rng: rng,
-            delay_mean_std_pair: (BASE_MEAN_MS_DELAY, BASE_STD_MS_DELAY),
+            delay_durations: KeyPressDurations::default(),
+            delay_mean_std_pairs: delay_mean_std_pairs_from(KeyPressDurations::default()),
             delay_map: RefCell::new(HashMap::new()),
+            held_keys: RefCell::new(HashSet::new()),
+            clipboard_to_restore: RefCell::new(None),
+            tick: 0,
+            audit: RefCell::new(InputAuditLog::default()),
+            audit_enabled: false,
+            observer: false,
+            layout: KeyboardLayout::default(),
+        }
+    }
+
+    /// Maps `kind` to the key sent through [`InputMethodInner::Rpc`].
+    ///
+    /// Custom inputs send layout-independent scan codes so the key is always mapped by position.
+    #[inline]
+    fn rpc_key_kind(&self, kind: KeyKind) -> KeyKind {
+        positional_key_kind(kind, self.layout)
+    }
+
+    /// Maps `kind` to the key sent through [`InputMethodInner::Default`].
+    ///
+    /// On Windows, the scan code is resolved from the virtual key using the active layout so
+    /// the key is already sent by label.
+    #[inline]
+    fn default_key_kind(&self, kind: KeyKind) -> KeyKind {
+        if cfg!(windows) {
+            kind
+        } else {
+            positional_key_kind(kind, self.layout)
+        }
+    }
+
+    #[inline]
+    fn record_audit(&self, kind: KeyKind, stroke: InputAuditStroke) {
+        record_input_stroke(stroke);
+        if self.audit_enabled {
+            self.audit
+                .borrow_mut()
+                .record(self.tick, kind.into(), stroke);
         }
     }
 
@@ -733,29 +916,67 @@ impl DefaultInput {
         match &self.kind {
             InputMethodInner::Rpc(_, service) => {
                 if let Some(cell) = service {
-                    Ok(cell.borrow_mut().key_state(kind.into())?.into())
+                    Ok(cell
+                        .borrow_mut()
+                        .key_state(self.rpc_key_kind(kind).into())?
+                        .into())
                 } else {
                     bail!("service not connected")
                 }
             }
-            InputMethodInner::Default(input) => Ok(input.key_state(kind.into())?.into()),
+            InputMethodInner::Default(input) => {
+                Ok(input.key_state(self.default_key_kind(kind).into())?.into())
+            }
+            InputMethodInner::Gamepad(gamepad, mapping) => {
+                let (gamepad, button) = gamepad_and_button_from(gamepad, mapping, kind)?;
+                Ok(gamepad.button_state(button)?.into())
+            }
         }
     }
 
     #[inline]
     fn send_key_inner(&self, kind: KeyKind) -> Result<()> {
+        if self.observer {
+            bail!("observing inputs only");
+        }
+        if !self.focus.can_send_input() {
+            bail!("game window not focused");
+        }
+
         match &self.kind {
             InputMethodInner::Rpc(_, service) => {
                 if let Some(cell) = service {
-                    cell.borrow_mut()
-                        .send_key(kind.into(), self.random_input_delay_tick_count().0)?;
+                    cell.borrow_mut().send_key(
+                        self.rpc_key_kind(kind).into(),
+                        self.random_input_delay_tick_count(kind).0,
+                    )?;
+                    self.record_audit(kind, InputAuditStroke::Send);
                 }
             }
-            InputMethodInner::Default(input) => match self.track_input_delay(kind) {
-                InputDelay::Untracked => input.send_key(kind.into())?,
-                InputDelay::Tracked => input.send_key_down(kind.into(), false)?,
+            InputMethodInner::Default(input) => match self.track_input_delay(kind, 0) {
+                InputDelay::Untracked => {
+                    input.send_key(self.default_key_kind(kind).into())?;
+                    self.record_audit(kind, InputAuditStroke::Send);
+                }
+                InputDelay::Tracked => {
+                    input.send_key_down(self.default_key_kind(kind).into(), false)?;
+                    self.record_audit(kind, InputAuditStroke::Down);
+                }
                 InputDelay::AlreadyTracked => (),
             },
+            InputMethodInner::Gamepad(gamepad, mapping) => {
+                let (gamepad, button) = gamepad_and_button_from(gamepad, mapping, kind)?;
+                // Held for at least a tick so that the game polling the controller state does not
+                // miss the press
+                match self.track_input_delay(kind, 1) {
+                    InputDelay::Untracked => unreachable!(),
+                    InputDelay::Tracked => {
+                        gamepad.send_button_down(button)?;
+                        self.record_audit(kind, InputAuditStroke::Down);
+                    }
+                    InputDelay::AlreadyTracked => (),
+                }
+            }
         }
 
         Ok(())
@@ -763,15 +984,29 @@ impl DefaultInput {
 
     #[inline]
     fn send_key_up_inner(&self, kind: KeyKind, forced: bool) -> Result<()> {
+        let was_held = self.held_keys.borrow_mut().remove(&kind);
+        if self.observer && !was_held && !self.has_input_delay(kind) {
+            return Ok(());
+        }
         match &self.kind {
             InputMethodInner::Rpc(_, service) => {
                 if let Some(cell) = service {
-                    cell.borrow_mut().send_key_up(kind.into())?;
+                    cell.borrow_mut()
+                        .send_key_up(self.rpc_key_kind(kind).into())?;
+                    self.record_audit(kind, InputAuditStroke::Up);
                 }
             }
             InputMethodInner::Default(input) => {
                 if forced || !self.has_input_delay(kind) {
-                    input.send_key_up(kind.into())?;
+                    input.send_key_up(self.default_key_kind(kind).into())?;
+                    self.record_audit(kind, InputAuditStroke::Up);
+                }
+            }
+            InputMethodInner::Gamepad(gamepad, mapping) => {
+                if forced || !self.has_input_delay(kind) {
+                    let (gamepad, button) = gamepad_and_button_from(gamepad, mapping, kind)?;
+                    gamepad.send_button_up(button)?;
+                    self.record_audit(kind, InputAuditStroke::Up);
                 }
             }
         }
@@ -781,21 +1016,40 @@ impl DefaultInput {
 
     #[inline]
     fn send_key_down_inner(&self, kind: KeyKind, repeatable: bool) -> Result<()> {
+        if self.observer {
+            bail!("observing inputs only");
+        }
+        if !self.focus.can_send_input() {
+            bail!("game window not focused");
+        }
+
         match &self.kind {
             // NOTE: For unknown reason, hardware custom input (e.g. KMBox, Arduino) seems to only
             // require sending down stroke once and it will continue correctly. But `SendInput`
             // requires repeatedly sending the stroke to simulate flying for some classes.
             InputMethodInner::Rpc(_, service) => {
                 if let Some(cell) = service {
-                    cell.borrow_mut().send_key_down(kind.into())?;
+                    cell.borrow_mut()
+                        .send_key_down(self.rpc_key_kind(kind).into())?;
+                    self.record_audit(kind, InputAuditStroke::Down);
                 }
             }
             InputMethodInner::Default(input) => {
                 if !self.has_input_delay(kind) {
-                    input.send_key_down(kind.into(), repeatable)?;
+                    input.send_key_down(self.default_key_kind(kind).into(), repeatable)?;
+                    self.record_audit(kind, InputAuditStroke::Down);
+                }
+            }
+            // A held controller button stays down without repeating the report.
+            InputMethodInner::Gamepad(gamepad, mapping) => {
+                if !self.has_input_delay(kind) {
+                    let (gamepad, button) = gamepad_and_button_from(gamepad, mapping, kind)?;
+                    gamepad.send_button_down(button)?;
+                    self.record_audit(kind, InputAuditStroke::Down);
                 }
             }
         }
+        self.held_keys.borrow_mut().insert(kind);
 
         Ok(())
     }
@@ -812,16 +1066,19 @@ impl DefaultInput {
     /// timed out. If [`InputDelay::Untracked`] is returned, it is expected that both down and up
     /// key strokes are sent.
     ///
+    /// The drawn delay is at least `min_delay_tick_count` ticks.
+    ///
     /// This function should only be used for [`Self::send_key`] as the other two should be handled
     /// by the external caller.
-    fn track_input_delay(&self, kind: KeyKind) -> InputDelay {
+    fn track_input_delay(&self, kind: KeyKind, min_delay_tick_count: u32) -> InputDelay {
         let mut map = self.delay_map.borrow_mut();
         let entry = map.entry(kind);
         if matches!(entry, Entry::Occupied(_)) {
             return InputDelay::AlreadyTracked;
         }
 
-        let (_, delay_tick_count) = self.random_input_delay_tick_count();
+        let (_, delay_tick_count) = self.random_input_delay_tick_count(kind);
+        let delay_tick_count = delay_tick_count.max(min_delay_tick_count);
         if delay_tick_count == 0 {
             return InputDelay::Untracked;
         }
@@ -830,21 +1087,41 @@ impl DefaultInput {
         InputDelay::Tracked
     }
 
-    /// Updates the input delay (key up timing) for held down keys and delay std/mean pair.
+    /// Updates the input delay (key up timing) for held down keys and delay std/mean pairs.
     #[inline]
     fn update(&mut self, game_tick: u64) {
-        const UPDATE_MEAN_STD_PAIR_INTERVAL: u64 = 200;
-
-        if game_tick > 0 && game_tick.is_multiple_of(UPDATE_MEAN_STD_PAIR_INTERVAL) {
-            let (mean, std) = self.delay_mean_std_pair;
-            self.delay_mean_std_pair = self.delay_rng.random_mean_std_pair(
-                BASE_MEAN_MS_DELAY,
-                mean,
-                BASE_STD_MS_DELAY,
-                std,
-                MEAN_STD_REVERSION_RATE,
-                MEAN_STD_VOLATILITY,
-            )
+        const UPDATE_MEAN_STD_PAIR_INTERVAL: u32 = 200;
+
+        self.tick = game_tick;
+        self.focus.update();
+        self.audit.get_mut().flush();
+
+        // Restored on a tick after the paste key is released so the game reads the pasted text
+        if !self.delay_map.get_mut().contains_key(&KeyKind::V)
+            && let Some(text) = self.clipboard_to_restore.get_mut().take()
+        {
+            let _ = clipboard::set_text(&text);
+        }
+
+        if game_tick > 0
+            && game_tick.is_multiple_of(tick::scaled(UPDATE_MEAN_STD_PAIR_INTERVAL) as u64)
+        {
+            // Iterates in a fixed order so that the seeded rng draws the same pairs across runs
+            for class in KeyPressClass::iter() {
+                let (mean, std) = self
+                    .delay_mean_std_pairs
+                    .get_mut(&class)
+                    .expect("has all classes");
+                let base = self.delay_durations.get(class);
+                (*mean, *std) = self.delay_rng.random_mean_std_pair(
+                    base.mean_millis as f32,
+                    *mean,
+                    base.std_millis as f32,
+                    *std,
+                    MEAN_STD_REVERSION_RATE,
+                    MEAN_STD_VOLATILITY,
+                );
+            }
         }
 
         let mut map = self.delay_map.borrow_mut();
@@ -873,10 +1150,15 @@ impl DefaultInput {
         }
     }
 
-    fn random_input_delay_tick_count(&self) -> (f32, u32) {
-        let (mean, std) = self.delay_mean_std_pair;
+    fn random_input_delay_tick_count(&self, kind: KeyKind) -> (f32, u32) {
+        let class = key_press_class(kind);
+        let base = self.delay_durations.get(class);
+        let (mean, std) = self.delay_mean_std_pairs[&class];
+        let min = base.mean_millis.saturating_sub(base.std_millis) as f32;
+        let max = (base.mean_millis + base.std_millis) as f32;
+
         self.delay_rng
-            .random_delay_tick_count(mean, std, MS_PER_TICK_F32, 80.0, 120.0)
+            .random_delay_tick_count(mean, std, millis_per_tick_f32(), min, max)
     }
 }
 
@@ -886,10 +1168,48 @@ impl Input for DefaultInput {
     }
 
     fn set_method(&mut self, method: InputMethod) {
+        self.focus.set_window(focus_window_from(&method));
         self.kind = input_method_inner_from(method, self.delay_rng.rng_seed());
     }
 
+    fn set_focus_policy(&mut self, policy: FocusPolicy, grace_period_millis: u64) {
+        self.focus.set_policy(policy, grace_period_millis);
+    }
+
+    fn set_press_durations(&mut self, durations: KeyPressDurations) {
+        if self.delay_durations != durations {
+            self.delay_durations = durations;
+            self.delay_mean_std_pairs = delay_mean_std_pairs_from(durations);
+        }
+    }
+
+    fn set_observer(&mut self, observer: bool) {
+        if !self.observer && observer {
+            self.send_all_keys_up();
+        }
+        self.observer = observer;
+    }
+
+    fn set_keyboard_layout(&mut self, layout: KeyboardLayout) {
+        if self.layout != layout {
+            // Releases keys sent with the previous layout so they are not stuck
+            self.send_all_keys_up();
+            self.layout = layout;
+        }
+    }
+
+    fn set_audit(&mut self, audit: bool) {
+        if self.audit_enabled && !audit {
+            self.audit.get_mut().flush();
+        }
+        self.audit_enabled = audit;
+    }
+
     fn send_mouse(&self, x: i32, y: i32, kind: MouseKind) {
+        if self.observer || !self.focus.can_send_input() {
+            return;
+        }
+
         match &self.kind {
             InputMethodInner::Rpc(window, service) => {
                 if let Some(cell) = service {
@@ -912,13 +1232,10 @@ impl Input for DefaultInput {
                 }
             }
             InputMethodInner::Default(keys) => {
-                let kind = match kind {
-                    MouseKind::Move => PlatformMouseKind::Move,
-                    MouseKind::Click => PlatformMouseKind::Click,
-                    MouseKind::Scroll => PlatformMouseKind::Scroll,
-                };
-                let _ = keys.send_mouse(x, y, kind);
+                let _ = keys.send_mouse(x, y, kind.into());
             }
+            // A controller has no mouse
+            InputMethodInner::Gamepad(_, _) => (),
         }
     }
 
@@ -930,6 +1247,34 @@ impl Input for DefaultInput {
         let _ = self.send_key_up_inner(kind, false);
     }
 
+    fn send_paste(&self, text: &str) -> bool {
+        if self.observer || !self.focus.can_send_input() {
+            return false;
+        }
+        // The clipboard set here is local while `Rpc` pastes on the game machine
+        if matches!(self.kind, InputMethodInner::Rpc(_, _)) {
+            return false;
+        }
+        // Keeps the text from before the first of consecutive pastes as the later ones would only
+        // save the previously pasted text
+        let mut to_restore = self.clipboard_to_restore.borrow_mut();
+        if to_restore.is_none() {
+            *to_restore = clipboard::text().ok().flatten();
+        }
+        drop(to_restore);
+        if clipboard::set_text(text).is_err() {
+            return false;
+        }
+
+        if self.send_key_down_inner(KeyKind::Ctrl, false).is_err() {
+            return false;
+        }
+        let result = self.send_key_inner(KeyKind::V);
+        let _ = self.send_key_up_inner(KeyKind::Ctrl, true);
+
+        result.is_ok()
+    }
+
     fn send_key_down_with_options(&self, kind: KeyKind, options: InputKeyDownOptions) {
         let _ = self.send_key_down_inner(kind, options.repeatable);
     }
@@ -942,6 +1287,32 @@ impl Input for DefaultInput {
     fn all_keys_cleared(&self) -> bool {
         self.delay_map.borrow().is_empty()
     }
+
+    fn any_key_held(&self) -> bool {
+        !self.held_keys.borrow().is_empty()
+    }
+
+    fn send_all_keys_up(&self) {
+        let keys = self
+            .held_keys
+            .borrow()
+            .iter()
+            .chain(self.delay_map.borrow().keys())
+            .copied()
+            .collect::<HashSet<_>>();
+        for key in keys {
+            let _ = self.send_key_up_inner(key, true);
+        }
+        self.delay_map.borrow_mut().clear();
+    }
+}
+
+impl Drop for DefaultInput {
+    /// Releases all held keys so that they are not left stuck when the main loop unwinds.
+    fn drop(&mut self) {
+        self.send_all_keys_up();
+        self.audit.get_mut().flush();
+    }
 }
 
 /// A trait for managing different capture modes.
@@ -1000,7 +1371,8 @@ impl Capture for DefaultCapture {
     fn set_mode(&mut self, mode: CaptureMode) {
         self.mode = mode;
 
-        if cfg!(windows) {
+        #[cfg(windows)]
+        {
             let kind = match mode {
                 CaptureMode::BitBlt => WindowsCaptureKind::BitBlt,
                 CaptureMode::WindowsGraphicsCapture => WindowsCaptureKind::Wgc,
@@ -1011,6 +1383,126 @@ impl Capture for DefaultCapture {
     }
 }
 
+#[inline]
+fn delay_mean_std_pairs_from(durations: KeyPressDurations) -> HashMap<KeyPressClass, (f32, f32)> {
+    KeyPressClass::iter()
+        .map(|class| {
+            let duration = durations.get(class);
+            (
+                class,
+                (duration.mean_millis as f32, duration.std_millis as f32),
+            )
+        })
+        .collect()
+}
+
+#[inline]
+fn key_press_class(kind: KeyKind) -> KeyPressClass {
+    match kind {
+        KeyKind::A
+        | KeyKind::B
+        | KeyKind::C
+        | KeyKind::D
+        | KeyKind::E
+        | KeyKind::F
+        | KeyKind::G
+        | KeyKind::H
+        | KeyKind::I
+        | KeyKind::J
+        | KeyKind::K
+        | KeyKind::L
+        | KeyKind::M
+        | KeyKind::N
+        | KeyKind::O
+        | KeyKind::P
+        | KeyKind::Q
+        | KeyKind::R
+        | KeyKind::S
+        | KeyKind::T
+        | KeyKind::U
+        | KeyKind::V
+        | KeyKind::W
+        | KeyKind::X
+        | KeyKind::Y
+        | KeyKind::Z => KeyPressClass::Letter,
+        KeyKind::Zero
+        | KeyKind::One
+        | KeyKind::Two
+        | KeyKind::Three
+        | KeyKind::Four
+        | KeyKind::Five
+        | KeyKind::Six
+        | KeyKind::Seven
+        | KeyKind::Eight
+        | KeyKind::Nine => KeyPressClass::Digit,
+        KeyKind::F1
+        | KeyKind::F2
+        | KeyKind::F3
+        | KeyKind::F4
+        | KeyKind::F5
+        | KeyKind::F6
+        | KeyKind::F7
+        | KeyKind::F8
+        | KeyKind::F9
+        | KeyKind::F10
+        | KeyKind::F11
+        | KeyKind::F12 => KeyPressClass::Function,
+        KeyKind::Up | KeyKind::Down | KeyKind::Left | KeyKind::Right => KeyPressClass::Arrow,
+        KeyKind::Ctrl | KeyKind::Shift | KeyKind::Alt => KeyPressClass::Modifier,
+        KeyKind::Home
+        | KeyKind::End
+        | KeyKind::PageUp
+        | KeyKind::PageDown
+        | KeyKind::Insert
+        | KeyKind::Delete
+        | KeyKind::Enter
+        | KeyKind::Space
+        | KeyKind::Tilde
+        | KeyKind::Quote
+        | KeyKind::Semicolon
+        | KeyKind::Comma
+        | KeyKind::Period
+        | KeyKind::Slash
+        | KeyKind::Minus
+        | KeyKind::Esc
+        | KeyKind::Backspace => KeyPressClass::Other,
+    }
+}
+
+/// Maps the key labeled `kind` on `layout` to the key at the same position on a QWERTY keyboard.
+#[inline]
+fn positional_key_kind(kind: KeyKind, layout: KeyboardLayout) -> KeyKind {
+    match (layout, kind) {
+        (KeyboardLayout::Qwerty, kind) => kind,
+        (KeyboardLayout::Azerty, KeyKind::A) => KeyKind::Q,
+        (KeyboardLayout::Azerty, KeyKind::Q) => KeyKind::A,
+        (KeyboardLayout::Azerty, KeyKind::Z) => KeyKind::W,
+        (KeyboardLayout::Azerty, KeyKind::W) => KeyKind::Z,
+        (KeyboardLayout::Azerty, KeyKind::M) => KeyKind::Semicolon,
+        (KeyboardLayout::Azerty, KeyKind::Comma) => KeyKind::M,
+        (KeyboardLayout::Azerty, KeyKind::Semicolon) => KeyKind::Comma,
+        (KeyboardLayout::Qwertz, KeyKind::Y) => KeyKind::Z,
+        (KeyboardLayout::Qwertz, KeyKind::Z) => KeyKind::Y,
+        (KeyboardLayout::Azerty, KeyKind::Minus) => KeyKind::Six,
+        (KeyboardLayout::Qwertz, KeyKind::Minus) => KeyKind::Slash,
+        (KeyboardLayout::Azerty | KeyboardLayout::Qwertz, kind) => kind,
+    }
+}
+
+/// Retrieves the game [`Window`] to apply focus policy on from `method`.
+///
+/// When the input is sent to the foreground window (e.g. area capture), the game window is not
+/// known so there is no [`Window`] to track.
+#[inline]
+fn focus_window_from(method: &InputMethod) -> Option<Window> {
+    match method {
+        InputMethod::Rpc(window, _)
+        | InputMethod::Gamepad(window, _)
+        | InputMethod::Default(window, PlatformInputKind::Focused) => Some(*window),
+        InputMethod::Default(_, PlatformInputKind::Foreground) => None,
+    }
+}
+
 #[inline]
 fn input_method_inner_from(method: InputMethod, seed: &[u8]) -> InputMethodInner {
     match method {
@@ -1025,14 +1517,52 @@ fn input_method_inner_from(method: InputMethod, seed: &[u8]) -> InputMethodInner
         InputMethod::Default(handle, kind) => {
             InputMethodInner::Default(PlatformInput::new(handle, kind).expect("supported platform"))
         }
+        InputMethod::Gamepad(_, mappings) => {
+            let gamepad = PlatformGamepad::new()
+                .inspect_err(|err| {
+                    error!(target: "input", "failed to create virtual gamepad, keys will not be sent: {err}")
+                })
+                .ok();
+
+            InputMethodInner::Gamepad(gamepad, gamepad_mapping_from(mappings))
+        }
     }
 }
 
+/// Converts `mappings` to a lookup from [`KeyKind`] to controller button.
+///
+/// When a key is mapped more than once, the first mapping is used.
+fn gamepad_mapping_from(mappings: Vec<GamepadMapping>) -> HashMap<KeyKind, PlatformGamepadButton> {
+    let mut mapping = HashMap::new();
+    for GamepadMapping { key, button } in mappings {
+        mapping.entry(key.into()).or_insert(button.into());
+    }
+
+    mapping
+}
+
+#[inline]
+fn gamepad_and_button_from<'a>(
+    gamepad: &'a Option<PlatformGamepad>,
+    mapping: &HashMap<KeyKind, PlatformGamepadButton>,
+    kind: KeyKind,
+) -> Result<(&'a PlatformGamepad, PlatformGamepadButton)> {
+    let Some(gamepad) = gamepad else {
+        bail!("gamepad not connected");
+    };
+    let Some(button) = mapping.get(&kind).copied() else {
+        bail!("key {kind:?} not mapped to a gamepad button");
+    };
+
+    Ok((gamepad, button))
+}
+
 #[cfg(test)]
 mod tests {
-    use std::assert_matches::assert_matches;
+    use std::{assert_matches::assert_matches, env, fs};
 
     use super::*;
+    use crate::models::KeyPressDuration;
 
     const SEED: [u8; 32] = [
         64, 241, 206, 219, 49, 21, 218, 145, 254, 152, 68, 176, 242, 238, 152, 14, 176, 241, 153,
@@ -1046,16 +1576,80 @@ mod tests {
         )
     }
 
+    #[test]
+    fn gamepad_mapping_from_uses_first_mapping() {
+        let mapping = gamepad_mapping_from(vec![
+            GamepadMapping {
+                key: KeyBinding::Space,
+                button: GamepadButton::A,
+            },
+            GamepadMapping {
+                key: KeyBinding::Space,
+                button: GamepadButton::B,
+            },
+            GamepadMapping {
+                key: KeyBinding::Up,
+                button: GamepadButton::DpadUp,
+            },
+        ]);
+
+        assert_eq!(mapping.len(), 2);
+        assert_eq!(
+            mapping.get(&KeyKind::Space),
+            Some(&PlatformGamepadButton::A)
+        );
+        assert_eq!(
+            mapping.get(&KeyKind::Up),
+            Some(&PlatformGamepadButton::DpadUp)
+        );
+    }
+
+    #[test]
+    fn send_key_gamepad_unmapped_key_not_tracked() {
+        let mut sender = test_key_sender();
+        sender.kind = InputMethodInner::Gamepad(None, HashMap::new());
+
+        assert!(sender.send_key_inner(KeyKind::Space).is_err());
+        assert!(!sender.has_input_delay(KeyKind::Space));
+    }
+
     #[test]
     fn track_input_delay_tracked() {
         let sender = test_key_sender();
 
         // Force rng to generate delay > 0
-        let result = sender.track_input_delay(KeyKind::Ctrl);
+        let result = sender.track_input_delay(KeyKind::Ctrl, 0);
         assert_matches!(result, InputDelay::Tracked);
         assert!(sender.has_input_delay(KeyKind::Ctrl));
     }
 
+    #[test]
+    fn track_input_delay_min_delay_tick_count() {
+        let mut sender = test_key_sender();
+        let duration = KeyPressDuration {
+            mean_millis: 0,
+            std_millis: 0,
+        };
+        sender.set_press_durations(KeyPressDurations {
+            letter: duration,
+            digit: duration,
+            function: duration,
+            arrow: duration,
+            modifier: duration,
+            other: duration,
+        });
+
+        assert_matches!(
+            sender.track_input_delay(KeyKind::Ctrl, 0),
+            InputDelay::Untracked
+        );
+        assert_matches!(
+            sender.track_input_delay(KeyKind::Ctrl, 1),
+            InputDelay::Tracked
+        );
+        assert_eq!(sender.delay_map.borrow()[&KeyKind::Ctrl], (1, false));
+    }
+
     #[test]
     fn track_input_delay_already_tracked() {
         let sender = test_key_sender();
@@ -1064,7 +1658,7 @@ mod tests {
             .borrow_mut()
             .insert(KeyKind::Ctrl, (3, false));
 
-        let result = sender.track_input_delay(KeyKind::Ctrl);
+        let result = sender.track_input_delay(KeyKind::Ctrl, 0);
         assert_matches!(result, InputDelay::AlreadyTracked);
     }
 
@@ -1084,17 +1678,138 @@ mod tests {
         assert!(!sender.has_input_delay(KeyKind::Ctrl));
     }
 
+    #[test]
+    fn update_mean_std_pairs_same_across_seeded_inputs() {
+        let mut first = test_key_sender();
+        let mut second = test_key_sender();
+        let tick = tick::scaled(200) as u64;
+
+        for game_tick in [tick, tick * 2, tick * 3] {
+            first.update(game_tick);
+            second.update(game_tick);
+        }
+
+        for class in KeyPressClass::iter() {
+            assert_eq!(
+                first.delay_mean_std_pairs[&class],
+                second.delay_mean_std_pairs[&class]
+            );
+        }
+    }
+
+    #[test]
+    fn send_key_observer_drops_inputs() {
+        let mut sender = test_key_sender();
+        sender.set_observer(true);
+
+        assert!(sender.send_key_inner(KeyKind::Ctrl).is_err());
+        assert!(sender.send_key_down_inner(KeyKind::Left, false).is_err());
+        assert!(sender.send_key_up_inner(KeyKind::Left, false).is_ok());
+        assert!(!sender.has_input_delay(KeyKind::Ctrl));
+        assert!(sender.held_keys.borrow().is_empty());
+    }
+
+    #[test]
+    fn positional_key_kind_maps_by_layout() {
+        assert_eq!(
+            positional_key_kind(KeyKind::A, KeyboardLayout::Qwerty),
+            KeyKind::A
+        );
+        assert_eq!(
+            positional_key_kind(KeyKind::A, KeyboardLayout::Azerty),
+            KeyKind::Q
+        );
+        assert_eq!(
+            positional_key_kind(KeyKind::Comma, KeyboardLayout::Azerty),
+            KeyKind::M
+        );
+        assert_eq!(
+            positional_key_kind(KeyKind::Y, KeyboardLayout::Qwertz),
+            KeyKind::Z
+        );
+        assert_eq!(
+            positional_key_kind(KeyKind::Minus, KeyboardLayout::Azerty),
+            KeyKind::Six
+        );
+        assert_eq!(
+            positional_key_kind(KeyKind::Minus, KeyboardLayout::Qwertz),
+            KeyKind::Slash
+        );
+        assert_eq!(
+            positional_key_kind(KeyKind::Space, KeyboardLayout::Qwertz),
+            KeyKind::Space
+        );
+    }
+
+    #[test]
+    fn record_audit_only_when_enabled() {
+        let dir = env::temp_dir().join("komari_record_audit_only_when_enabled");
+        let _ = fs::remove_dir_all(&dir);
+        let mut sender = test_key_sender();
+        sender.audit = RefCell::new(InputAuditLog::new(dir.clone()));
+
+        sender.record_audit(KeyKind::A, InputAuditStroke::Send);
+        assert!(!dir.exists());
+
+        sender.set_audit(true);
+        sender.record_audit(KeyKind::B, InputAuditStroke::Send);
+        // Flushes when disabled
+        sender.set_audit(false);
+        sender.record_audit(KeyKind::C, InputAuditStroke::Send);
+
+        let contents = fs::read_to_string(dir.join("input_audit.log")).unwrap();
+        assert_eq!(contents.lines().count(), 1);
+        assert!(contents.contains("\"key\":\"B\""));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn send_all_keys_up_clears_held_and_delayed_keys() {
+        let sender = test_key_sender();
+        sender.held_keys.borrow_mut().insert(KeyKind::Left);
+        sender
+            .delay_map
+            .borrow_mut()
+            .insert(KeyKind::Ctrl, (3, false));
+
+        assert!(sender.any_key_held());
+
+        sender.send_all_keys_up();
+
+        assert!(sender.held_keys.borrow().is_empty());
+        assert!(!sender.any_key_held());
+        assert!(sender.all_keys_cleared());
+    }
+
     #[test]
     fn update_input_delay_refresh_mean_std_pair_every_interval() {
         let mut sender = test_key_sender();
-        let original_pair = sender.delay_mean_std_pair;
+        let original_pairs = sender.delay_mean_std_pairs.clone();
 
         // Simulate tick before the interval: should NOT update
         sender.update(199);
-        assert_eq!(sender.delay_mean_std_pair, original_pair);
+        assert_eq!(sender.delay_mean_std_pairs, original_pairs);
 
         // Simulate tick AT the interval: should update
         sender.update(200);
-        assert_ne!(sender.delay_mean_std_pair, original_pair);
+        for (class, pair) in sender.delay_mean_std_pairs.iter() {
+            assert_ne!(*pair, original_pairs[class]);
+        }
+    }
+
+    #[test]
+    fn random_input_delay_tick_count_uses_key_press_class() {
+        let mut sender = test_key_sender();
+        let mut durations = KeyPressDurations::default();
+        durations.arrow = KeyPressDuration {
+            mean_millis: 300,
+            std_millis: 0,
+        };
+        sender.set_press_durations(durations);
+
+        let (arrow_millis, _) = sender.random_input_delay_tick_count(KeyKind::Left);
+        let (letter_millis, _) = sender.random_input_delay_tick_count(KeyKind::A);
+        assert_eq!(arrow_millis, 300.0);
+        assert!((80.0..=120.0).contains(&letter_millis));
     }
 }
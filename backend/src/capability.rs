@@ -0,0 +1,140 @@
+use std::sync::LazyLock;
+
+use strum::{Display, EnumIter, IntoEnumIterator};
+
+/// Names of the optional detection resources missing at build time separated by comma.
+///
+/// Missing resources are replaced with empty files by the build script so that the build does not
+/// fail. Any detection using them would fail or panic, so features depending on them must be
+/// disabled through [`is_capability_available`].
+const MISSING_RESOURCES: &str = env!("MISSING_RESOURCES");
+
+static CAPABILITY_STATUSES: LazyLock<Vec<CapabilityStatus>> = LazyLock::new(|| {
+    let missing = missing_resources_from(MISSING_RESOURCES);
+    Capability::iter()
+        .map(|capability| capability_status_from(capability, &missing))
+        .collect()
+});
+
+/// A feature that depends on optional detection resources.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display, EnumIter)]
+pub enum Capability {
+    RuneSolving,
+    TransparentShapeSolving,
+    FamiliarsSwapping,
+    HexaBooster,
+    LevelUpPopupsDismissal,
+}
+
+impl Capability {
+    /// Names of the resources required by this capability.
+    fn resources(self) -> &'static [&'static str] {
+        match self {
+            Capability::RuneSolving => &["RUNE_MODEL", "RUNE_SPIN_MODEL"],
+            Capability::TransparentShapeSolving => &["TRANSPARENT_SHAPE_MODEL"],
+            Capability::FamiliarsSwapping => &[
+                "FAMILIAR_BUTTON_SAVE_TEMPLATE",
+                "FAMILIAR_BUTTON_SETUP_TEMPLATE",
+                "FAMILIAR_BUTTON_LEVEL_TEMPLATE",
+                "FAMILIAR_SLOT_FREE_TEMPLATE",
+                "FAMILIAR_SLOT_OCCUPIED_TEMPLATE",
+                "FAMILIAR_SLOT_OCCUPIED_MASK_TEMPLATE",
+                "FAMILIAR_LEVEL_5_TEMPLATE",
+                "FAMILIAR_LEVEL_5_MASK_TEMPLATE",
+                "FAMILIAR_SCROLLBAR_TEMPLATE",
+                "FAMILIAR_CARD_RARE_TEMPLATE",
+                "FAMILIAR_CARD_EPIC_TEMPLATE",
+                "FAMILIAR_CARD_MASK_TEMPLATE",
+                "FAMILIAR_MENU_TEMPLATE",
+            ],
+            Capability::HexaBooster => &[
+                "HEXA_BOOSTER_TEMPLATE",
+                "HEXA_BOOSTER_NUMBER_TEMPLATE",
+                "HEXA_BOOSTER_NUMBER_MASK_TEMPLATE",
+                "HEXA_MENU_TEMPLATE",
+                "HEXA_QUICK_MENU_TEMPLATE",
+                "HEXA_BUTTON_ERDA_CONVERSION_TEMPLATE",
+                "HEXA_BUTTON_HEXA_BOOSTER_TEMPLATE",
+                "HEXA_BUTTON_MAX_TEMPLATE",
+                "HEXA_BUTTON_CONVERT_TEMPLATE",
+                "HEXA_SOL_ERDA_TEMPLATE",
+                "HEXA_SOL_ERDA_FULL_TEMPLATE",
+                "HEXA_SOL_ERDA_FULL_MASK_TEMPLATE",
+                "HEXA_SOL_ERDA_EMPTY_TEMPLATE",
+                "HEXA_SOL_ERDA_EMPTY_MASK_TEMPLATE",
+            ],
+            Capability::LevelUpPopupsDismissal => {
+                &["LEVEL_UP_POPUP_TEMPLATE", "NEW_SKILL_POPUP_TEMPLATE"]
+            }
+        }
+    }
+}
+
+/// The availability of a [`Capability`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct CapabilityStatus {
+    pub capability: Capability,
+    /// Names of the missing resources required by [`Self::capability`].
+    pub missing_resources: Vec<&'static str>,
+}
+
+impl CapabilityStatus {
+    #[inline]
+    pub fn is_available(&self) -> bool {
+        self.missing_resources.is_empty()
+    }
+}
+
+/// Gets the statuses of all [`Capability`].
+pub fn capability_statuses() -> Vec<CapabilityStatus> {
+    CAPABILITY_STATUSES.clone()
+}
+
+/// Whether all resources required by `capability` are available.
+pub fn is_capability_available(capability: Capability) -> bool {
+    CAPABILITY_STATUSES
+        .iter()
+        .find(|status| status.capability == capability)
+        .is_some_and(CapabilityStatus::is_available)
+}
+
+#[inline]
+fn missing_resources_from(list: &'static str) -> Vec<&'static str> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn capability_status_from(capability: Capability, missing: &[&'static str]) -> CapabilityStatus {
+    let missing_resources = capability
+        .resources()
+        .iter()
+        .copied()
+        .filter(|name| missing.contains(name))
+        .collect();
+
+    CapabilityStatus {
+        capability,
+        missing_resources,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capability_status_from_missing_resources() {
+        let missing = missing_resources_from("RUNE_SPIN_MODEL, ,HEXA_MENU_TEMPLATE");
+
+        assert_eq!(missing, vec!["RUNE_SPIN_MODEL", "HEXA_MENU_TEMPLATE"]);
+        assert_eq!(
+            capability_status_from(Capability::RuneSolving, &missing).missing_resources,
+            vec!["RUNE_SPIN_MODEL"]
+        );
+        assert!(capability_status_from(Capability::FamiliarsSwapping, &missing).is_available());
+        assert!(!capability_status_from(Capability::HexaBooster, &missing).is_available());
+        assert!(missing_resources_from("").is_empty());
+    }
+}
@@ -0,0 +1,53 @@
+//! The clock the systems use to measure wall-clock time.
+//!
+//! While replaying, the clock returns the recorded time of the current tick instead of the real
+//! time so that decisions based on [`Instant`] (e.g. action intervals and cooldowns) happen on the
+//! same ticks as when recording.
+
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+thread_local! {
+    static VIRTUAL_NOW: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Sets the [`Instant`] returned by [`now`] on the current thread.
+///
+/// Passing [`None`] goes back to the real clock.
+pub fn set_virtual_now(now: Option<Instant>) {
+    VIRTUAL_NOW.set(now);
+}
+
+/// The current [`Instant`] of the current thread.
+#[inline]
+pub fn now() -> Instant {
+    VIRTUAL_NOW.get().unwrap_or_else(Instant::now)
+}
+
+/// The [`Duration`] passed since `instant` according to [`now`].
+#[inline]
+pub fn elapsed(instant: Instant) -> Duration {
+    now().saturating_duration_since(instant)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn now_returns_virtual_now_until_cleared() {
+        let instant = Instant::now() + Duration::from_secs(60);
+
+        set_virtual_now(Some(instant));
+        assert_eq!(now(), instant);
+        assert_eq!(
+            elapsed(instant - Duration::from_secs(1)),
+            Duration::from_secs(1)
+        );
+
+        set_virtual_now(None);
+        assert!(now() < instant);
+    }
+}
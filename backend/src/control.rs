@@ -23,16 +23,18 @@ use tokio::{
     time::{Instant, sleep, timeout},
 };
 
-use crate::services::Event;
+use crate::{bridge::KeyKind, services::Event};
 
 #[derive(Debug, Clone)]
 pub enum CommandKind {
     Start,
     Stop { go_to_town: bool },
+    Finish,
     Suspend,
     Status,
     Chat { content: String },
     Action { action: BotAction, count: u32 },
+    RuneArrows { keys: [KeyKind; 4] },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, EnumString, EnumMessage, Display)]
@@ -41,6 +43,11 @@ enum InnerCommandKind {
     Start,
     #[strum(to_string = "stop", message = "Stop the bot actions")]
     Stop,
+    #[strum(
+        to_string = "finish",
+        message = "Stop the bot actions after the current action finishes"
+    )]
+    Finish,
     #[strum(
         to_string = "suspend",
         message = "Suspend the bot actions (stop completely if run/stop cycle not used)"
@@ -62,6 +69,11 @@ enum InnerCommandKind {
     StopStream,
     #[strum(to_string = "action", message = "Perform an action")]
     Action,
+    #[strum(
+        to_string = "rune",
+        message = "Submit the rune arrows when the bot requests help"
+    )]
+    Rune,
 }
 
 #[derive(Debug, Clone, Copy, EnumIter, EnumString, EnumMessage, Display)]
@@ -184,9 +196,19 @@ impl EventHandler for DefaultEventHandler {
 
                         command.add_option(kind).add_option(count)
                     }
+                    InnerCommandKind::Rune => command.add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "arrows",
+                            "The four arrows in order (e.g. up down left right or UDLR)",
+                        )
+                        .required(true)
+                        .min_length(4),
+                    ),
                     InnerCommandKind::StartStream
                     | InnerCommandKind::StopStream
                     | InnerCommandKind::Start
+                    | InnerCommandKind::Finish
                     | InnerCommandKind::Suspend
                     | InnerCommandKind::Status => command,
                 }
@@ -237,6 +259,15 @@ impl EventHandler for DefaultEventHandler {
                     )
                     .await;
                 }
+                InnerCommandKind::Finish => {
+                    single_command(
+                        &self.command_sender,
+                        &context,
+                        &command,
+                        CommandKind::Finish,
+                    )
+                    .await;
+                }
                 InnerCommandKind::Suspend => {
                     single_command(
                         &self.command_sender,
@@ -288,6 +319,25 @@ impl EventHandler for DefaultEventHandler {
                     )
                     .await;
                 }
+                InnerCommandKind::Rune => {
+                    let arrows = command.data.options[0].value.as_str().expect("has option");
+                    let Some(keys) = parse_rune_arrows(arrows) else {
+                        response_with(
+                            &context,
+                            &command,
+                            "Rune arrows must be exactly four of up, down, left or right.",
+                        )
+                        .await;
+                        return;
+                    };
+                    single_command(
+                        &self.command_sender,
+                        &context,
+                        &command,
+                        CommandKind::RuneArrows { keys },
+                    )
+                    .await;
+                }
             }
         }
     }
@@ -366,6 +416,43 @@ async fn stop_stream_command(
     response_with(&context, &command, content).await;
 }
 
+/// Parses four rune arrows from `arrows`.
+///
+/// Each arrow can be a word (e.g. `up`), a letter (e.g. `U`) or an arrow symbol (e.g. `↑`)
+/// optionally separated by whitespaces or commas.
+fn parse_rune_arrows(arrows: &str) -> Option<[KeyKind; 4]> {
+    let arrows = arrows.to_lowercase();
+    let mut keys = Vec::with_capacity(4);
+    let mut rest = arrows.as_str();
+    while let Some(char) = rest.chars().next() {
+        if char.is_whitespace() || char == ',' {
+            rest = &rest[char.len_utf8()..];
+            continue;
+        }
+
+        let (key, len) = [
+            ("up", KeyKind::Up),
+            ("down", KeyKind::Down),
+            ("left", KeyKind::Left),
+            ("right", KeyKind::Right),
+            ("u", KeyKind::Up),
+            ("d", KeyKind::Down),
+            ("l", KeyKind::Left),
+            ("r", KeyKind::Right),
+            ("↑", KeyKind::Up),
+            ("↓", KeyKind::Down),
+            ("←", KeyKind::Left),
+            ("→", KeyKind::Right),
+        ]
+        .into_iter()
+        .find_map(|(word, key)| rest.starts_with(word).then_some((key, word.len())))?;
+        keys.push(key);
+        rest = &rest[len..];
+    }
+
+    keys.try_into().ok()
+}
+
 #[inline]
 async fn response_with(
     context: &Context,
@@ -378,5 +465,17 @@ async fn response_with(
 
 #[cfg(test)]
 mod tests {
-    // TODO
+    use super::*;
+
+    #[test]
+    fn parse_rune_arrows_accepts_words_letters_and_symbols() {
+        let expected = [KeyKind::Up, KeyKind::Down, KeyKind::Left, KeyKind::Right];
+
+        assert_eq!(parse_rune_arrows("up down left right"), Some(expected));
+        assert_eq!(parse_rune_arrows("UDLR"), Some(expected));
+        assert_eq!(parse_rune_arrows("↑, ↓, ←, →"), Some(expected));
+        assert_eq!(parse_rune_arrows("up down left"), None);
+        assert_eq!(parse_rune_arrows("up down left right up"), None);
+        assert_eq!(parse_rune_arrows("up down left jump"), None);
+    }
 }
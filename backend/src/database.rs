@@ -1,14 +1,25 @@
 use std::{
-    env,
+    env, fs,
+    path::{Path, PathBuf},
     sync::{LazyLock, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::{Result, bail};
+use log::{error, info};
 use rusqlite::{Connection, Params, Statement, types::Null};
 use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
 use tokio::sync::broadcast::{Receiver, Sender, channel};
 
-use crate::models::{Character, Identifiable, Localization, Map, NavigationPaths, Seeds, Settings};
+use crate::{
+    error::{BackendError, report_error},
+    migration::{TableMigrations, backup, migrate, restore},
+    models::{
+        ActionCooldowns, Character, Identifiable, KeyTemplate, Localization, Map, NavigationPaths,
+        RiskyActionCounts, Seeds, Settings,
+    },
+};
 
 const MAPS: &str = "maps";
 const NAVIGATION_PATHS: &str = "navigation_paths";
@@ -16,15 +27,68 @@ const CHARACTERS: &str = "characters";
 const SETTINGS: &str = "settings";
 const SEEDS: &str = "seeds";
 const LOCALIZATIONS: &str = "localizations";
+const ACTION_COOLDOWNS: &str = "action_cooldowns";
+const KEY_TEMPLATES: &str = "key_templates";
+const RISKY_ACTION_COUNTS: &str = "risky_action_counts";
+
+/// Schema migrations of each table.
+///
+/// See [`crate::migration`] on how to add a migration when the shape of a model changes.
+const MIGRATIONS: [TableMigrations; 9] = [
+    TableMigrations {
+        table: MAPS,
+        migrations: &[],
+    },
+    TableMigrations {
+        table: NAVIGATION_PATHS,
+        migrations: &[],
+    },
+    TableMigrations {
+        table: CHARACTERS,
+        migrations: &[],
+    },
+    TableMigrations {
+        table: SETTINGS,
+        migrations: &[],
+    },
+    TableMigrations {
+        table: SEEDS,
+        migrations: &[],
+    },
+    TableMigrations {
+        table: LOCALIZATIONS,
+        migrations: &[localization_add_default_name],
+    },
+    TableMigrations {
+        table: ACTION_COOLDOWNS,
+        migrations: &[],
+    },
+    TableMigrations {
+        table: KEY_TEMPLATES,
+        migrations: &[],
+    },
+    TableMigrations {
+        table: RISKY_ACTION_COUNTS,
+        migrations: &[],
+    },
+];
+
+/// Name of the [`Localization`] profile created when there is none.
+const LOCALIZATION_DEFAULT_NAME: &str = "Default";
+
+/// Minimum interval between each periodic backup of the database.
+const PERIODIC_BACKUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Maximum number of backups kept, older backups are removed.
+const MAX_BACKUPS: usize = 20;
 
 static CONNECTION: LazyLock<Mutex<Connection>> = LazyLock::new(|| {
-    let path = env::current_exe()
-        .unwrap()
-        .parent()
-        .unwrap()
-        .join("local.db")
-        .to_path_buf();
-    let conn = Connection::open(path.to_str().unwrap()).expect("failed to open local.db");
+    let dir = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let path = dir.join("local.db");
+    let mut conn = Connection::open(path.to_str().unwrap()).expect("failed to open local.db");
+    // Write-ahead logging keeps the database intact if the process is killed mid-write
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+        .expect("failed to enable write-ahead logging");
     conn.execute_batch(
         format!(
             r#"
@@ -52,11 +116,35 @@ static CONNECTION: LazyLock<Mutex<Connection>> = LazyLock::new(|| {
                 id INTEGER PRIMARY KEY,
                 data TEXT NOT NULL
             );
+            CREATE TABLE IF NOT EXISTS {ACTION_COOLDOWNS} (
+                id INTEGER PRIMARY KEY,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS {KEY_TEMPLATES} (
+                id INTEGER PRIMARY KEY,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS {RISKY_ACTION_COUNTS} (
+                id INTEGER PRIMARY KEY,
+                data TEXT NOT NULL
+            );
             "#
         )
         .as_str(),
     )
     .unwrap();
+
+    // The failed migration is rolled back so a backup can still be restored from the UI
+    if let Err(err) = migrate(&mut conn, Some(&new_backup_path()), &MIGRATIONS) {
+        report_error(
+            "database",
+            BackendError::Database(format!("failed to migrate local.db {err}")),
+        );
+    }
+    if periodic_backup_due() && backup_to_new_path(&conn) {
+        prune_backups();
+    }
+
     Mutex::new(conn)
 });
 static EVENT: LazyLock<Sender<DatabaseEvent>> = LazyLock::new(|| channel(5).0);
@@ -70,8 +158,11 @@ pub enum DatabaseEvent {
     NavigationPathsDeleted,
     SettingsUpdated(Settings),
     LocalizationUpdated(Localization),
+    LocalizationDeleted(i64),
     CharacterUpdated(Character),
     CharacterDeleted(i64),
+    KeyTemplateUpdated(KeyTemplate),
+    KeyTemplateDeleted(i64),
 }
 
 pub fn database_event_receiver() -> Receiver<DatabaseEvent> {
@@ -84,28 +175,50 @@ pub fn query_and_upsert_seeds() -> Seeds {
         .into_iter()
         .next()
         .unwrap_or_default();
+    seeds.session_count += 1;
     upsert_to_table(SEEDS, &mut seeds).unwrap();
     seeds
 }
 
-pub fn query_or_upsert_localization() -> Localization {
-    let mut localization = query_from_table::<Localization>(LOCALIZATIONS)
-        .unwrap()
-        .into_iter()
-        .next()
-        .unwrap_or_default();
+/// Queries the [`Localization`] profile with `id`.
+///
+/// Falls back to the first profile if `id` is [`None`] or does not exist. A default profile is
+/// created if there is none.
+pub fn query_or_upsert_localization(id: Option<i64>) -> Localization {
+    let localizations = query_from_table::<Localization>(LOCALIZATIONS).unwrap();
+    let mut localization = localizations
+        .iter()
+        .find(|localization| id.is_some() && localization.id == id)
+        .or(localizations.first())
+        .cloned()
+        .unwrap_or_else(|| Localization {
+            name: LOCALIZATION_DEFAULT_NAME.to_string(),
+            ..Localization::default()
+        });
     if localization.id.is_none() {
         upsert_to_table(LOCALIZATIONS, &mut localization).unwrap();
     }
     localization
 }
 
+pub fn query_localizations() -> Result<Vec<Localization>> {
+    query_from_table(LOCALIZATIONS)
+}
+
 pub fn upsert_localization(localization: &mut Localization) -> Result<()> {
     upsert_to_table(LOCALIZATIONS, localization).inspect(|_| {
         let _ = EVENT.send(DatabaseEvent::LocalizationUpdated(localization.clone()));
     })
 }
 
+pub fn delete_localization(localization: &Localization) -> Result<()> {
+    delete_from_table(LOCALIZATIONS, localization).inspect(|_| {
+        let _ = EVENT.send(DatabaseEvent::LocalizationDeleted(
+            localization.id.expect("valid id if deleted"),
+        ));
+    })
+}
+
 pub fn query_settings() -> Settings {
     let mut settings = query_from_table::<Settings>(SETTINGS)
         .unwrap()
@@ -124,6 +237,28 @@ pub fn upsert_settings(settings: &mut Settings) -> Result<()> {
     })
 }
 
+/// Queries the persisted [`ActionCooldowns`] of the character with `character_id`.
+pub fn query_action_cooldowns(character_id: i64) -> Result<Option<ActionCooldowns>> {
+    Ok(query_from_table::<ActionCooldowns>(ACTION_COOLDOWNS)?
+        .into_iter()
+        .find(|cooldowns| cooldowns.character_id == character_id))
+}
+
+pub fn upsert_action_cooldowns(cooldowns: &mut ActionCooldowns) -> Result<()> {
+    upsert_to_table(ACTION_COOLDOWNS, cooldowns)
+}
+
+/// Queries the persisted daily [`RiskyActionCounts`].
+pub fn query_risky_action_counts() -> Result<Option<RiskyActionCounts>> {
+    Ok(query_from_table::<RiskyActionCounts>(RISKY_ACTION_COUNTS)?
+        .into_iter()
+        .next())
+}
+
+pub fn upsert_risky_action_counts(counts: &mut RiskyActionCounts) -> Result<()> {
+    upsert_to_table(RISKY_ACTION_COUNTS, counts)
+}
+
 pub fn query_characters() -> Result<Vec<Character>> {
     query_from_table(CHARACTERS)
 }
@@ -142,6 +277,64 @@ pub fn delete_character(character: &Character) -> Result<()> {
     })
 }
 
+pub fn query_key_templates() -> Result<Vec<KeyTemplate>> {
+    query_from_table(KEY_TEMPLATES)
+}
+
+pub fn upsert_key_template(template: &mut KeyTemplate) -> Result<()> {
+    upsert_to_table(KEY_TEMPLATES, template).inspect(|_| {
+        let _ = EVENT.send(DatabaseEvent::KeyTemplateUpdated(template.clone()));
+    })
+}
+
+pub fn delete_key_template(template: &KeyTemplate) -> Result<()> {
+    delete_from_table(KEY_TEMPLATES, template).inspect(|_| {
+        let _ = EVENT.send(DatabaseEvent::KeyTemplateDeleted(
+            template.id.expect("valid id if deleted"),
+        ));
+    })
+}
+
+/// Upgrades a legacy exported map `value` to the latest schema version.
+///
+/// Legacy exports do not record their schema version so all migrations are applied.
+pub fn upgrade_legacy_map(value: &mut Value) -> Result<()> {
+    upgrade_legacy_value(MAPS, value)
+}
+
+/// Upgrades a legacy exported character `value` to the latest schema version.
+///
+/// Legacy exports do not record their schema version so all migrations are applied.
+pub fn upgrade_legacy_character(value: &mut Value) -> Result<()> {
+    upgrade_legacy_value(CHARACTERS, value)
+}
+
+/// Upserts all `maps` and `characters` in a single transaction.
+///
+/// Nothing is written if any of the upserts fails. Events are only broadcasted after committing.
+pub fn upsert_maps_and_characters(maps: &mut [Map], characters: &mut [Character]) -> Result<()> {
+    with_periodic_backup(|conn| -> Result<()> {
+        let tx = conn.transaction()?;
+        for map in maps.iter_mut() {
+            upsert_to_table_with_connection(&tx, MAPS, map)?;
+        }
+        for character in characters.iter_mut() {
+            upsert_to_table_with_connection(&tx, CHARACTERS, character)?;
+        }
+        tx.commit()?;
+        Ok(())
+    })?;
+
+    for map in maps.iter() {
+        let _ = EVENT.send(DatabaseEvent::MapUpdated(map.clone()));
+    }
+    for character in characters.iter() {
+        let _ = EVENT.send(DatabaseEvent::CharacterUpdated(character.clone()));
+    }
+
+    Ok(())
+}
+
 pub fn query_maps() -> Result<Vec<Map>> {
     query_from_table(MAPS)
 }
@@ -160,6 +353,183 @@ pub fn delete_map(map: &Map) -> Result<()> {
     })
 }
 
+/// Queries the names of the database backups from newest to oldest.
+pub fn query_backups() -> Result<Vec<String>> {
+    Ok(backup_entries()?
+        .into_iter()
+        .map(|(_, name)| name)
+        .collect())
+}
+
+/// Backs up the database now.
+///
+/// Returns the name of the created backup.
+pub fn backup_database() -> Result<String> {
+    let conn = CONNECTION.lock().unwrap();
+    let path = new_backup_path();
+    backup(&conn, &path)?;
+    prune_backups();
+
+    Ok(path.file_name().unwrap().to_string_lossy().into_owned())
+}
+
+/// Restores the database from the backup with `name`.
+///
+/// The current database is backed up before restoring. Events are broadcasted for all restored
+/// and removed models.
+pub fn restore_backup(name: &str) -> Result<()> {
+    if !backup_entries()?.iter().any(|(_, entry)| entry == name) {
+        bail!("backup {name} does not exist");
+    }
+
+    import_database(&backups_dir().join(name))
+}
+
+/// Exports a snapshot of the database to a new file at `path`.
+pub fn export_database(path: &Path) -> Result<()> {
+    export_database_with_settings(path, |settings| settings)
+}
+
+/// Exports a snapshot of the database to a new file at `path` with the [`Settings`] replaced by
+/// the ones returned from `map_settings`.
+pub fn export_database_with_settings(
+    path: &Path,
+    map_settings: impl FnOnce(Settings) -> Settings,
+) -> Result<()> {
+    let json = serde_json::to_string(&map_settings(query_settings()))?;
+    {
+        let conn = CONNECTION.lock().unwrap();
+        backup(&conn, path)?;
+    }
+
+    let conn = Connection::open(path)?;
+    conn.execute(&format!("UPDATE {SETTINGS} SET data = ?1;"), [json])?;
+    Ok(())
+}
+
+/// Replaces the database with the snapshot at `path`.
+///
+/// The current database is backed up before importing. Events are broadcasted for all imported
+/// and removed models.
+pub fn import_database(path: &Path) -> Result<()> {
+    import_database_with_settings(path, |settings| settings)
+}
+
+/// Replaces the database with the snapshot at `path` and then the imported [`Settings`] with the
+/// ones returned from `map_settings`.
+///
+/// The settings are replaced before any event is broadcasted so that the imported settings are
+/// never observed.
+pub fn import_database_with_settings(
+    path: &Path,
+    map_settings: impl FnOnce(Settings) -> Settings,
+) -> Result<()> {
+    let map_ids = query_maps()?
+        .into_iter()
+        .filter_map(|map| map.id)
+        .collect::<Vec<_>>();
+    let character_ids = query_characters()?
+        .into_iter()
+        .filter_map(|character| character.id)
+        .collect::<Vec<_>>();
+    let localization_ids = query_localizations()?
+        .into_iter()
+        .filter_map(|localization| localization.id)
+        .collect::<Vec<_>>();
+
+    {
+        let mut conn = CONNECTION.lock().unwrap();
+        backup(&conn, &new_backup_path())?;
+        restore(&mut conn, path, &MIGRATIONS).inspect_err(|err| {
+            report_error(
+                "database",
+                BackendError::Database(format!(
+                    "failed to restore database from {} {err}",
+                    path.display()
+                )),
+            );
+        })?;
+    }
+    prune_backups();
+
+    let mut settings = map_settings(query_settings());
+    upsert_to_table(SETTINGS, &mut settings)?;
+
+    let maps = query_maps()?;
+    for id in map_ids {
+        if !maps.iter().any(|map| map.id == Some(id)) {
+            let _ = EVENT.send(DatabaseEvent::MapDeleted(id));
+        }
+    }
+    for map in maps {
+        let _ = EVENT.send(DatabaseEvent::MapUpdated(map));
+    }
+    let characters = query_characters()?;
+    for id in character_ids {
+        if !characters.iter().any(|character| character.id == Some(id)) {
+            let _ = EVENT.send(DatabaseEvent::CharacterDeleted(id));
+        }
+    }
+    for character in characters {
+        let _ = EVENT.send(DatabaseEvent::CharacterUpdated(character));
+    }
+    let localizations = query_localizations()?;
+    for id in localization_ids {
+        if !localizations
+            .iter()
+            .any(|localization| localization.id == Some(id))
+        {
+            let _ = EVENT.send(DatabaseEvent::LocalizationDeleted(id));
+        }
+    }
+    for localization in localizations {
+        let _ = EVENT.send(DatabaseEvent::LocalizationUpdated(localization));
+    }
+    let _ = EVENT.send(DatabaseEvent::NavigationPathsUpdated);
+    let _ = EVENT.send(DatabaseEvent::SettingsUpdated(settings));
+
+    Ok(())
+}
+
+/// Computes a fingerprint of the stored models for detecting changes.
+///
+/// Action cooldowns and risky action counts are excluded because they change whenever the bot
+/// runs. [`Settings`] are fingerprinted as returned from `map_settings` instead of as stored
+/// because stored secrets are encrypted differently on each save.
+pub fn data_fingerprint(map_settings: impl FnOnce(Settings) -> Settings) -> Result<u64> {
+    // 64-bit FNV-1a, which unlike the standard hasher is stable across Rust versions
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let settings = serde_json::to_string(&map_settings(query_settings()))?;
+    let conn = CONNECTION.lock().unwrap();
+    let mut hash = FNV_OFFSET_BASIS;
+    let mut write = |bytes: &[u8]| {
+        for byte in bytes {
+            hash = (hash ^ *byte as u64).wrapping_mul(FNV_PRIME);
+        }
+    };
+    for table in MIGRATIONS
+        .iter()
+        .map(|migrations| migrations.table)
+        .filter(|table| {
+            *table != ACTION_COOLDOWNS && *table != RISKY_ACTION_COUNTS && *table != SETTINGS
+        })
+    {
+        let mut stmt = conn.prepare(&format!("SELECT id, data FROM {table} ORDER BY id;"))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            write(table.as_bytes());
+            write(&row.get::<_, i64>(0)?.to_le_bytes());
+            write(row.get::<_, String>(1)?.as_bytes());
+        }
+    }
+    write(SETTINGS.as_bytes());
+    write(settings.as_bytes());
+
+    Ok(hash)
+}
+
 pub fn query_navigation_paths() -> Result<Vec<NavigationPaths>> {
     query_from_table(NAVIGATION_PATHS)
 }
@@ -176,6 +546,125 @@ pub fn delete_navigation_paths(paths: &NavigationPaths) -> Result<()> {
     })
 }
 
+#[inline]
+fn backups_dir() -> PathBuf {
+    env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("backups")
+}
+
+fn new_backup_path() -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    backups_dir().join(format!("local_{timestamp}.db"))
+}
+
+/// Lists the timestamps and names of the backups from newest to oldest.
+fn backup_entries() -> Result<Vec<(u128, String)>> {
+    let dir = backups_dir();
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut entries = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let timestamp = name
+                .strip_prefix("local_")?
+                .strip_suffix(".db")?
+                .parse::<u128>()
+                .ok()?;
+            Some((timestamp, name))
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|(first, _), (second, _)| second.cmp(first));
+
+    Ok(entries)
+}
+
+/// Whether the newest backup is older than [`PERIODIC_BACKUP_INTERVAL`].
+///
+/// This lists the backups directory so it is called before locking the connection.
+fn periodic_backup_due() -> bool {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let newest = backup_entries()
+        .ok()
+        .and_then(|entries| entries.first().map(|(timestamp, _)| *timestamp));
+
+    newest.is_none_or(|timestamp| {
+        now.saturating_sub(timestamp) >= PERIODIC_BACKUP_INTERVAL.as_millis()
+    })
+}
+
+/// Backs up the database with `conn` to a new backup.
+///
+/// Returns `false` if backing up failed.
+fn backup_to_new_path(conn: &Connection) -> bool {
+    if let Err(err) = backup(conn, &new_backup_path()) {
+        report_error(
+            "database",
+            BackendError::Database(format!("failed to back up database {err}")),
+        );
+        return false;
+    }
+
+    true
+}
+
+/// Runs `f` with the locked connection after backing up the database if due.
+///
+/// The backups directory is only listed while the connection is not locked.
+fn with_periodic_backup<R>(f: impl FnOnce(&mut Connection) -> R) -> R {
+    let backup_due = periodic_backup_due();
+    let mut conn = CONNECTION.lock().unwrap();
+    let backed_up = backup_due && backup_to_new_path(&conn);
+    let result = f(&mut conn);
+    drop(conn);
+
+    if backed_up {
+        prune_backups();
+    }
+    result
+}
+
+/// Removes the oldest backups exceeding [`MAX_BACKUPS`].
+fn prune_backups() {
+    let Ok(entries) = backup_entries() else {
+        return;
+    };
+    for (_, name) in entries.into_iter().skip(MAX_BACKUPS) {
+        if fs::remove_file(backups_dir().join(&name)).is_ok() {
+            info!(target: "database", "removed old backup {name}");
+        }
+    }
+}
+
+fn upgrade_legacy_value(table: &str, value: &mut Value) -> Result<()> {
+    MIGRATIONS
+        .iter()
+        .find(|migrations| migrations.table == table)
+        .expect("table has migrations")
+        .upgrade(value, 0)
+}
+
+/// Names the single [`Localization`] persisted before profiles were supported.
+fn localization_add_default_name(value: &mut Value) -> Result<()> {
+    if let Some(object) = value.as_object_mut() {
+        object
+            .entry("name")
+            .or_insert(Value::String(LOCALIZATION_DEFAULT_NAME.to_string()));
+    }
+    Ok(())
+}
+
 fn map_data<T>(mut stmt: Statement<'_>, params: impl Params) -> Result<Vec<T>>
 where
     T: DeserializeOwned + Identifiable + Default,
@@ -184,7 +673,11 @@ where
         .query_map::<T, _, _>(params, |row| {
             let id = row.get::<_, i64>(0).unwrap();
             let data = row.get::<_, String>(1).unwrap();
-            let mut value = serde_json::from_str::<'_, T>(data.as_str()).unwrap_or_default();
+            let mut value = serde_json::from_str::<'_, T>(data.as_str())
+                .inspect_err(|err| {
+                    error!(target: "database", "failed to deserialize row {id}, using default {err}")
+                })
+                .unwrap_or_default();
             value.set_id(id);
             Ok(value)
         })?
@@ -203,24 +696,38 @@ where
 }
 
 fn upsert_to_table<T>(table: &str, data: &mut T) -> Result<()>
+where
+    T: Serialize + Identifiable,
+{
+    with_periodic_backup(|conn| upsert_to_table_with_connection(conn, table, data))
+}
+
+fn upsert_to_table_with_connection<T>(conn: &Connection, table: &str, data: &mut T) -> Result<()>
 where
     T: Serialize + Identifiable,
 {
     let json = serde_json::to_string(&data).unwrap();
-    let conn = CONNECTION.lock().unwrap();
     let stmt = format!(
         "INSERT INTO {table} (id, data) VALUES (?1, ?2) ON CONFLICT (id) DO UPDATE SET data = ?2;",
     );
     match data.id() {
         Some(id) => {
-            if conn.execute(&stmt, (id, &json))? > 0 {
+            if conn
+                .execute(&stmt, (id, &json))
+                .inspect_err(report_database_error)?
+                > 0
+            {
                 Ok(())
             } else {
                 bail!("no row was updated")
             }
         }
         None => {
-            if conn.execute(&stmt, (Null, &json))? > 0 {
+            if conn
+                .execute(&stmt, (Null, &json))
+                .inspect_err(report_database_error)?
+                > 0
+            {
                 data.set_id(conn.last_insert_rowid());
                 Ok(())
             } else {
@@ -235,7 +742,9 @@ fn delete_from_table<T: Identifiable>(table: &str, data: &T) -> Result<()> {
         if let Some(id) = id {
             let conn = CONNECTION.lock().unwrap();
             let stmt = format!("DELETE FROM {table} WHERE id = ?1;");
-            let deleted = conn.execute(&stmt, [id])?;
+            let deleted = conn
+                .execute(&stmt, [id])
+                .inspect_err(report_database_error)?;
 
             if deleted > 0 {
                 return Ok(());
@@ -246,3 +755,7 @@ fn delete_from_table<T: Identifiable>(table: &str, data: &T) -> Result<()> {
 
     inner(table, data.id())
 }
+
+fn report_database_error(err: &rusqlite::Error) {
+    report_error("database", BackendError::Database(err.to_string()));
+}
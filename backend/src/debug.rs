@@ -1,25 +1,36 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
 use opencv::core::Point;
 use opencv::core::Point2d;
 use opencv::core::Rect;
 use opencv::core::Scalar;
 use opencv::core::Size;
 use opencv::core::{Mat, ToInputArray};
-use opencv::core::{MatTraitConst, Vector};
+use opencv::core::{MatTraitConst, ModifyInplace, Vector};
 use opencv::highgui::destroy_window;
 use opencv::highgui::{imshow, wait_key};
+use opencv::imgcodecs::{IMREAD_COLOR, imread, imwrite_def};
 use opencv::imgproc::arrowed_line;
 use opencv::imgproc::draw_contours_def;
 use opencv::imgproc::line_def;
 use opencv::imgproc::polylines;
 use opencv::imgproc::rectangle;
+use opencv::imgproc::{COLOR_BGR2BGRA, cvt_color_def};
 use opencv::imgproc::{FONT_HERSHEY_SIMPLEX, put_text_def};
 use opencv::imgproc::{LINE_8, circle_def};
 use rand::distr::{Alphanumeric, SampleString};
+use strum::IntoEnumIterator;
 
 use crate::bridge::KeyKind;
-use crate::detect::ArrowsComplete;
+use crate::detect::{ArrowsComplete, DefaultDetector, Detector};
+use crate::mat::OwnedMat;
+use crate::models::Localization;
 use crate::tracker::STrack;
 use crate::utils::{self, DatasetDir};
+use crate::{GameTemplate, TemplateTestReport, TemplateTestResult};
 
 #[allow(unused)]
 pub fn debug_spinning_arrows(
@@ -323,6 +334,83 @@ pub fn save_minimap_for_training<T: MatTraitConst + ToInputArray>(mat: &T, minim
     }
 }
 
+/// Name of the folder inside the tested screenshots folder to save annotated screenshots to.
+const TEMPLATE_TEST_ANNOTATED_DIR: &str = "annotated";
+
+/// Detects every [`GameTemplate`] in each screenshot inside `dir` using `localization`.
+///
+/// Screenshots are annotated with the detected templates bounding boxes and saved to the
+/// [`TEMPLATE_TEST_ANNOTATED_DIR`] folder inside `dir`.
+pub fn test_templates(dir: &Path, localization: Arc<Localization>) -> Result<TemplateTestReport> {
+    let annotated_dir = dir.join(TEMPLATE_TEST_ANNOTATED_DIR);
+    let mut paths = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| matches!(ext.to_lowercase().as_str(), "png" | "jpg" | "jpeg"))
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    fs::create_dir_all(&annotated_dir)?;
+
+    let mut images_count = 0;
+    let mut results = GameTemplate::iter()
+        .map(|template| TemplateTestResult {
+            template,
+            detected_images: vec![],
+        })
+        .collect::<Vec<_>>();
+    for path in paths {
+        let (Some(name), Some(path)) = (
+            path.file_name().and_then(|name| name.to_str()),
+            path.to_str(),
+        ) else {
+            continue;
+        };
+        let mut mat = imread(path, IMREAD_COLOR)?;
+        if mat.empty() {
+            continue;
+        }
+        unsafe {
+            mat.modify_inplace(|mat, mat_mut| cvt_color_def(mat, mat_mut, COLOR_BGR2BGRA))?;
+        }
+
+        let detector = DefaultDetector::new(OwnedMat::from(mat), localization.clone());
+        let mut annotated = detector.mat().try_clone()?;
+        for result in results.iter_mut() {
+            let Ok(bbox) = detector.detect_game_template(result.template) else {
+                continue;
+            };
+            let _ = rectangle(
+                &mut annotated,
+                bbox,
+                Scalar::new(0.0, 255.0, 0.0, 0.0),
+                1,
+                LINE_8,
+                0,
+            );
+            let _ = put_text_def(
+                &mut annotated,
+                &result.template.to_string(),
+                bbox.tl() - Point::new(0, 5),
+                FONT_HERSHEY_SIMPLEX,
+                0.4,
+                Scalar::new(0.0, 255.0, 0.0, 0.0),
+            );
+            result.detected_images.push(name.to_string());
+        }
+        imwrite_def(annotated_dir.join(name).to_str().unwrap(), &annotated)?;
+        images_count += 1;
+    }
+
+    Ok(TemplateTestReport {
+        images_count,
+        annotated_dir: annotated_dir.to_string_lossy().into_owned(),
+        results,
+    })
+}
+
 fn map_bbox_from_prediction(pred: &[f32], size: Size, w_ratio: f32, h_ratio: f32) -> Rect {
     let tl_x = (pred[0] / w_ratio).max(0.0).min(size.width as f32) as i32;
     let tl_y = (pred[1] / h_ratio).max(0.0).min(size.height as f32) as i32;
@@ -340,3 +428,86 @@ fn to_yolo_format(label: u32, size: Size, bbox: Rect) -> String {
     let height = bbox.height as f32 / size.height as f32;
     format!("{label} {x_center} {y_center} {width} {height}")
 }
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use opencv::core::{CV_8UC1, MatTrait};
+    use opencv::imgproc::COLOR_GRAY2BGR;
+
+    use super::*;
+    use crate::detect::POPUP_CONFIRM_TEMPLATE;
+
+    /// Creates a BGR noise-like screenshot so that no template matches by chance.
+    fn noise_screenshot(width: i32, height: i32) -> Mat {
+        let data = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                ((x * 37 + y * 91) ^ (x * y * 13)) as u8
+            })
+            .collect::<Vec<_>>();
+        let grayscale = Mat::new_rows_cols_with_data(height, width, &data)
+            .unwrap()
+            .clone_pointee();
+        let mut bgr = Mat::default();
+        cvt_color_def(&grayscale, &mut bgr, COLOR_GRAY2BGR).unwrap();
+        bgr
+    }
+
+    #[test]
+    fn test_templates_reports_detected_images() {
+        let dir = env::temp_dir().join("komari_test_templates_reports_detected_images");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let template = &*POPUP_CONFIRM_TEMPLATE;
+        assert_eq!(template.typ(), CV_8UC1);
+        let mut template_bgr = Mat::default();
+        cvt_color_def(template, &mut template_bgr, COLOR_GRAY2BGR).unwrap();
+        let mut with_confirm = noise_screenshot(800, 600);
+        template_bgr
+            .copy_to(
+                &mut with_confirm
+                    .roi_mut(Rect::new(100, 200, template.cols(), template.rows()))
+                    .unwrap(),
+            )
+            .unwrap();
+        imwrite_def(dir.join("confirm.png").to_str().unwrap(), &with_confirm).unwrap();
+        imwrite_def(
+            dir.join("noise.png").to_str().unwrap(),
+            &noise_screenshot(800, 600),
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "not a screenshot").unwrap();
+
+        let report = test_templates(&dir, Arc::new(Localization::default())).unwrap();
+
+        assert_eq!(report.images_count, 2);
+        assert_eq!(report.results.len(), GameTemplate::iter().count());
+        let confirm = report
+            .results
+            .iter()
+            .find(|result| result.template == GameTemplate::PopupConfirm)
+            .unwrap();
+        assert_eq!(confirm.detected_images, vec!["confirm.png".to_string()]);
+        assert!(
+            report
+                .results
+                .iter()
+                .all(|result| !result.detected_images.contains(&"noise.png".to_string()))
+        );
+        assert!(
+            dir.join(TEMPLATE_TEST_ANNOTATED_DIR)
+                .join("confirm.png")
+                .exists()
+        );
+        assert!(
+            dir.join(TEMPLATE_TEST_ANNOTATED_DIR)
+                .join("noise.png")
+                .exists()
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -2,7 +2,9 @@ use std::{
     collections::HashMap,
     env,
     fmt::Debug,
+    fs,
     sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
 };
 
 use anyhow::{Result, anyhow, bail};
@@ -13,11 +15,12 @@ use mockall::automock;
 use opencv::{
     boxed_ref::BoxedRef,
     core::{
-        BORDER_CONSTANT, CMP_EQ, CMP_GT, CV_8U, CV_32FC3, CV_32S, Mat, MatExprTraitConst, MatTrait,
-        MatTraitConst, MatTraitConstManual, ModifyInplace, Point, Range, Rect, Scalar, Size,
-        ToInputArray, Vec3b, Vector, add, add_weighted_def, bitwise_and_def, compare,
-        copy_make_border, divide2_def, extract_channel, find_non_zero, min_max_loc, no_array,
-        subtract_def, transpose_nd,
+        _InputArrayTraitConst, BORDER_CONSTANT, CMP_EQ, CMP_GT, CV_8U, CV_32FC3, CV_32S, Mat,
+        MatExprTraitConst, MatTrait, MatTraitConst, MatTraitConstManual, ModifyInplace, Point,
+        Range, Rect, Scalar, Size, ToInputArray, Vec3b, Vector, add, add_weighted_def,
+        bitwise_and_def, bitwise_or_def, compare, copy_make_border, count_non_zero, divide2_def,
+        extract_channel, find_non_zero, in_range, min_max_loc, no_array, subtract_def,
+        transpose_nd,
     },
     dnn::{
         ModelTrait, TextRecognitionModel, TextRecognitionModelTrait,
@@ -27,10 +30,11 @@ use opencv::{
     imgproc::{
         CC_STAT_AREA, CC_STAT_HEIGHT, CC_STAT_LEFT, CC_STAT_TOP, CC_STAT_WIDTH,
         CHAIN_APPROX_SIMPLE, COLOR_BGR2HSV_FULL, COLOR_BGR2RGB, COLOR_BGRA2BGR, COLOR_BGRA2GRAY,
-        INTER_CUBIC, INTER_LINEAR, MORPH_RECT, RETR_EXTERNAL, THRESH_BINARY, TM_CCOEFF_NORMED,
-        TM_SQDIFF_NORMED, bounding_rect, connected_components_with_stats, contour_area,
-        cvt_color_def, dilate_def, find_contours_def, get_structuring_element_def, match_template,
-        min_area_rect, min_enclosing_triangle, resize, threshold,
+        INTER_AREA, INTER_CUBIC, INTER_LINEAR, MORPH_OPEN, MORPH_RECT, RETR_EXTERNAL,
+        THRESH_BINARY, TM_CCOEFF_NORMED, TM_SQDIFF_NORMED, bounding_rect, canny_def,
+        connected_components_with_stats, contour_area, cvt_color_def, dilate_def,
+        find_contours_def, get_structuring_element_def, match_template, min_area_rect,
+        min_enclosing_triangle, morphology_ex_def, resize, threshold,
     },
 };
 use ort::{
@@ -41,12 +45,45 @@ use ort::{
 
 #[cfg(debug_assertions)]
 use crate::debug::{debug_mat, debug_spinning_arrows};
-use crate::{array::Array, mat::OwnedMat};
-use crate::{bridge::KeyKind, models::Localization};
+use crate::{GameTemplate, TemplateSuggestion};
+use crate::{array::Array, clock, mat::OwnedMat, plugin};
+use crate::{
+    bridge::KeyKind,
+    error::{BackendError, report_error},
+    models::{
+        ActionKeyResourceGate, ActionKeyResourceGateKind, AnnouncementKind, Localization,
+        MobColorSignature, MobDetection, MobDetectionKind, PlayerMarker,
+    },
+};
 
 const MAX_ARROWS: usize = 4;
 const MAX_SPIN_ARROWS: usize = 2; // PRAY
 
+/// The scales relative to the captured templates searched when templates stop matching.
+///
+/// Ordered by how close they are to the default scale so that a nearer scale is preferred.
+const TEMPLATE_SCALES: [f64; 7] = [1.0, 0.9, 1.1, 0.8, 1.2, 0.7, 1.3];
+
+/// Minimum duration between two searches of [`TEMPLATE_SCALES`].
+///
+/// Most templates legitimately do not match most of the time (e.g. popups), so the search is
+/// rate limited to keep its cost bounded.
+const TEMPLATE_SCALE_SEARCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Minimum match threshold for a match at another scale to be trusted as a UI scale change.
+///
+/// Lower thresholds can match unrelated regions.
+const TEMPLATE_SCALE_SEARCH_MIN_THRESHOLD: f64 = 0.7;
+
+/// Maximum number of templates with a cached [`TemplateScale`].
+///
+/// Most templates are statics but some are created on each detection (e.g. localized templates),
+/// so the cache is cleared once full to keep it bounded.
+const MAX_TEMPLATE_SCALES: usize = 256;
+
+static TEMPLATE_SCALES_CACHE: LazyLock<Mutex<HashMap<TemplateKey, TemplateScale>>> =
+    LazyLock::new(Mutex::default);
+
 /// Struct for storing information about the spinning arrows.
 #[derive(Debug, Copy, Clone)]
 struct SpinArrow {
@@ -151,6 +188,75 @@ pub enum SolErda {
     Empty,
 }
 
+/// Width and height of a [`RegionSignature`].
+const REGION_SIGNATURE_SIZE: usize = 8;
+
+/// A downscaled grayscale snapshot of a region for detecting pixel changes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RegionSignature([u8; REGION_SIGNATURE_SIZE * REGION_SIGNATURE_SIZE]);
+
+impl RegionSignature {
+    /// Gets the mean absolute difference between this and `other` signature.
+    pub fn difference(&self, other: &RegionSignature) -> u32 {
+        let sum = self
+            .0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| a.abs_diff(*b) as u32)
+            .sum::<u32>();
+        sum / self.0.len() as u32
+    }
+
+    #[cfg(test)]
+    pub fn filled(value: u8) -> Self {
+        Self([value; REGION_SIGNATURE_SIZE * REGION_SIGNATURE_SIZE])
+    }
+}
+
+/// Number of columns of a [`ScreenAnchor`].
+pub const SCREEN_ANCHOR_WIDTH: usize = 320;
+
+/// The maximum mean absolute difference between two [`ScreenAnchor`]s for a shift to match.
+const SCREEN_ANCHOR_MAX_DIFFERENCE: u32 = 16;
+
+/// A downscaled grayscale column profile of the middle of the screen.
+///
+/// The game camera follows the player so the background scrolls opposite to the player
+/// movement. Comparing two anchors estimates how far the screen scrolled horizontally without
+/// the minimap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenAnchor([u8; SCREEN_ANCHOR_WIDTH]);
+
+impl ScreenAnchor {
+    /// Estimates the number of columns the screen scrolled from `previous` to this anchor.
+    ///
+    /// The shift is positive when the screen scrolled to the right. Returns [`None`] if no shift
+    /// within `max_shift` columns matches.
+    pub fn shift_from(&self, previous: &ScreenAnchor, max_shift: usize) -> Option<i32> {
+        let width = SCREEN_ANCHOR_WIDTH as i32;
+        let max_shift = max_shift.min(SCREEN_ANCHOR_WIDTH / 2) as i32;
+        // Checks smaller shifts first so that ties prefer the smallest movement
+        (0..=max_shift)
+            .flat_map(|shift| [shift, -shift])
+            .map(|shift| {
+                let overlap = shift.max(0)..width + shift.min(0);
+                let sum = overlap
+                    .clone()
+                    .map(|i| self.0[i as usize].abs_diff(previous.0[(i - shift) as usize]) as u32)
+                    .sum::<u32>();
+                (shift, sum / overlap.len() as u32)
+            })
+            .reduce(|best, current| if current.1 < best.1 { current } else { best })
+            .filter(|(_, difference)| *difference <= SCREEN_ANCHOR_MAX_DIFFERENCE)
+            .map(|(shift, _)| shift)
+    }
+
+    #[cfg(test)]
+    pub fn new(columns: [u8; SCREEN_ANCHOR_WIDTH]) -> Self {
+        Self(columns)
+    }
+}
+
 /// A trait for detecting objects from provided frame.
 #[cfg_attr(test, automock)]
 pub trait Detector: Debug + Send + Sync {
@@ -163,7 +269,20 @@ pub trait Detector: Debug + Send + Sync {
     /// Detects a list of mobs.
     ///
     /// Returns a list of mobs coordinate relative to minimap coordinate.
-    fn detect_mobs(&self, minimap: Rect, bound: Rect, player: Point) -> Result<Vec<Point>>;
+    ///
+    /// The mobs are detected using `detection` from the current map.
+    fn detect_mobs(
+        &self,
+        minimap: Rect,
+        bound: Rect,
+        player: Point,
+        detection: &MobDetection,
+    ) -> Result<Vec<Point>>;
+
+    /// Detects a list of loots dropped on the ground near the player.
+    ///
+    /// Returns a list of loots coordinate relative to minimap coordinate.
+    fn detect_loots(&self, minimap: Rect, player: Point) -> Result<Vec<Point>>;
 
     /// Detects whether to press ESC for unstucking.
     fn detect_esc_settings(&self) -> bool;
@@ -177,6 +296,11 @@ pub trait Detector: Debug + Send + Sync {
     /// Detects whether there is an elite boss bar.
     fn detect_elite_boss_bar(&self) -> bool;
 
+    /// Detects whether the map-wide announcement banner `kind` is shown.
+    ///
+    /// Always `false` if there is no user-supplied template for `kind`.
+    fn detect_announcement(&self, kind: AnnouncementKind) -> bool;
+
     /// Detects the minimap.
     ///
     /// The `border_threshold` determines the "whiteness" (grayscale value from 0..255) of
@@ -186,6 +310,12 @@ pub trait Detector: Debug + Send + Sync {
     /// Detects the minimap name rectangle.
     fn detect_minimap_name(&self, minimap: Rect) -> Result<Rect>;
 
+    /// Detects candidate platforms from the walkable strips drawn inside `minimap`.
+    ///
+    /// Returns a list of `(x_start, x_end, y)` horizontal segments relative to minimap
+    /// coordinate.
+    fn detect_minimap_platforms(&self, minimap: Rect) -> Result<Vec<(i32, i32, i32)>>;
+
     /// Detects whether the given `minimap_snapshot` and `minimap_name_snapshot` matches the one
     /// cropped by `minimap_name_bbox` and `minimap_bbox` rectangles.
     fn detect_minimap_match(
@@ -207,13 +337,33 @@ pub trait Detector: Debug + Send + Sync {
     /// Returns `Rect` relative to `minimap` coordinate.
     fn detect_minimap_rune(&self, minimap: Rect) -> Result<Rect>;
 
+    /// Detects the rune in small regions around `hints` of the `minimap` rectangle.
+    ///
+    /// This uses a more lenient threshold than [`Self::detect_minimap_rune`] for when the rune
+    /// is partially occluded. `hints` are relative to `minimap` coordinate.
+    ///
+    /// Returns `Rect` relative to `minimap` coordinate.
+    fn detect_minimap_rune_near(&self, minimap: Rect, hints: &[Point]) -> Result<Rect>;
+
     /// Detects the player in the provided `minimap` rectangle.
     ///
     /// Returns `Rect` relative to `minimap` coordinate.
     fn detect_player(&self, minimap: Rect) -> Result<Rect>;
 
-    /// Detects whether a player of `kind` is in the minimap.
-    fn detect_player_kind(&self, minimap: Rect, kind: OtherPlayerKind) -> bool;
+    /// Detects the player with the custom `marker` in the provided `minimap` rectangle.
+    ///
+    /// Returns `Rect` relative to `minimap` coordinate.
+    fn detect_player_marker(&self, minimap: Rect, marker: PlayerMarker) -> Result<Rect>;
+
+    /// Samples a custom [`PlayerMarker`] at `point` in the provided `minimap` rectangle.
+    ///
+    /// `point` is relative to `minimap` coordinate.
+    fn sample_player_marker(&self, minimap: Rect, point: Point) -> Result<PlayerMarker>;
+
+    /// Detects a player of `kind` in the provided `minimap` rectangle.
+    ///
+    /// Returns `Rect` relative to `minimap` coordinate.
+    fn detect_player_kind(&self, minimap: Rect, kind: OtherPlayerKind) -> Result<Rect>;
 
     /// Detects whether the player is dead.
     fn detect_player_is_dead(&self) -> bool;
@@ -234,6 +384,9 @@ pub trait Detector: Debug + Send + Sync {
     /// Detects the player current health and max health.
     fn detect_player_health(&self, current_bar: Rect, max_bar: Rect) -> Result<(u32, u32)>;
 
+    /// Detects the player current EXP from the EXP bar at the bottom of the game window.
+    fn detect_player_exp(&self) -> Result<u64>;
+
     /// Detects whether the player has a buff specified by `kind`.
     fn detect_player_buff(&self, kind: BuffKind) -> bool;
 
@@ -287,6 +440,9 @@ pub trait Detector: Debug + Send + Sync {
     /// Detects whether there is a timer (e.g. from using booster).
     fn detect_timer_visible(&self) -> bool;
 
+    /// Detects whether the level up stat allocation or new skill popup is opened.
+    fn detect_level_up_popup(&self) -> bool;
+
     /// Detects the lie detector popup.
     fn detect_lie_detector(&self) -> Result<Rect>;
 
@@ -317,6 +473,21 @@ pub trait Detector: Debug + Send + Sync {
     ///
     /// The returned [`Rect`]s have coordinates relative to `region`.
     fn detect_transparent_shapes(&self, region: Rect) -> Vec<Rect>;
+
+    /// Detects using the user-provided plugin detector `name`.
+    fn detect_plugin(&self, name: &str) -> Result<bool>;
+
+    /// Detects the [`RegionSignature`] of the provided `region` for comparing pixel changes.
+    fn detect_region_signature(&self, region: Rect) -> Result<RegionSignature>;
+
+    /// Detects the [`ScreenAnchor`] for estimating the screen scrolling.
+    fn detect_screen_anchor(&self) -> Result<ScreenAnchor>;
+
+    /// Detects whether the region of the provided `gate` is satisfied.
+    fn detect_resource_gate(&self, gate: ActionKeyResourceGate) -> Result<bool>;
+
+    /// Detects the item count shown inside the quick slot `region`.
+    fn detect_quick_slot_count(&self, region: Rect) -> Result<u32>;
 }
 
 type MatFn = Box<dyn FnOnce() -> Mat + Send>;
@@ -357,6 +528,75 @@ impl DefaultDetector {
     fn bgr(&self) -> &Mat {
         &self.bgr
     }
+
+    /// Tracks the detection `result` of `template` and returns it as is.
+    ///
+    /// After [`TEMPLATE_SUGGESTION_MISSES_COUNT`] consecutive misses, the best matching region of
+    /// the current frame is suggested as a replacement if it scores at least
+    /// [`TEMPLATE_SUGGESTION_MIN_SCORE`].
+    fn track_game_template(&self, template: GameTemplate, result: Result<Rect>) -> Result<Rect> {
+        {
+            let mut suggestions = TEMPLATE_SUGGESTIONS.lock().unwrap();
+            if result.is_ok() {
+                suggestions.misses.remove(&template);
+                return result;
+            }
+
+            let misses = suggestions.misses.entry(template).or_default();
+            *misses += 1;
+            if *misses < TEMPLATE_SUGGESTION_MISSES_COUNT {
+                return result;
+            }
+            *misses = 0;
+        }
+
+        // Matches without holding the lock since the whole frame is searched
+        match suggest_game_template(self.grayscale(), self.bgr(), &self.localization, template) {
+            Ok(base64) => {
+                info!(target: "detect", "suggesting replacement template for {template}");
+                TEMPLATE_SUGGESTIONS
+                    .lock()
+                    .unwrap()
+                    .suggested
+                    .insert(template, base64);
+            }
+            Err(err) => {
+                debug!(target: "detect", "no replacement template for {template} {err}");
+            }
+        }
+
+        result
+    }
+
+    /// Detects the localized `template` and returns its bounding box.
+    #[cfg(debug_assertions)]
+    pub fn detect_game_template(&self, template: GameTemplate) -> Result<Rect> {
+        let grayscale = self.grayscale();
+        let bgr = self.bgr();
+        let localization = self.localization.as_ref();
+
+        match template {
+            GameTemplate::CashShop => detect_cash_shop(grayscale, localization),
+            GameTemplate::ChangeChannel => detect_change_channel_menu(grayscale, localization),
+            GameTemplate::Timer => detect_timer(grayscale, localization),
+            GameTemplate::PopupConfirm => detect_popup_confirm_button(grayscale, localization),
+            GameTemplate::PopupYes => detect_popup_yes_button(grayscale, localization),
+            GameTemplate::PopupNext => detect_popup_next_button(grayscale, localization),
+            GameTemplate::PopupEndChat => detect_popup_end_chat_button(grayscale, localization),
+            GameTemplate::PopupOkNew => detect_popup_ok_new_button(grayscale, localization),
+            GameTemplate::PopupOkOld => detect_popup_ok_old_button(grayscale, localization),
+            GameTemplate::PopupCancelNew => detect_popup_cancel_new_button(grayscale, localization),
+            GameTemplate::PopupCancelOld => detect_popup_cancel_old_button(grayscale, localization),
+            GameTemplate::FamiliarsLevelSort => detect_familiar_level_button(bgr, localization),
+            GameTemplate::FamiliarsSaveButton => detect_familiar_save_button(bgr, localization),
+            GameTemplate::HexaErdaConversionButton => {
+                detect_hexa_erda_conversion_button(bgr, localization)
+            }
+            GameTemplate::HexaBoosterButton => detect_hexa_booster_button(bgr, localization),
+            GameTemplate::HexaMaxButton => detect_hexa_max_button(bgr, localization),
+            GameTemplate::HexaConvertButton => detect_hexa_convert_button(bgr, localization),
+        }
+    }
 }
 
 impl Detector for DefaultDetector {
@@ -368,8 +608,18 @@ impl Detector for DefaultDetector {
         &self.grayscale
     }
 
-    fn detect_mobs(&self, minimap: Rect, bound: Rect, player: Point) -> Result<Vec<Point>> {
-        detect_mobs(self.bgr(), minimap, bound, player)
+    fn detect_mobs(
+        &self,
+        minimap: Rect,
+        bound: Rect,
+        player: Point,
+        detection: &MobDetection,
+    ) -> Result<Vec<Point>> {
+        detect_mobs(self.bgr(), minimap, bound, player, detection)
+    }
+
+    fn detect_loots(&self, minimap: Rect, player: Point) -> Result<Vec<Point>> {
+        detect_loots(self.bgr(), minimap, player)
     }
 
     fn detect_esc_settings(&self) -> bool {
@@ -377,17 +627,27 @@ impl Detector for DefaultDetector {
     }
 
     fn detect_popup_confirm_button(&self) -> Result<Rect> {
-        detect_popup_confirm_button(self.grayscale(), &self.localization)
+        self.track_game_template(
+            GameTemplate::PopupConfirm,
+            detect_popup_confirm_button(self.grayscale(), &self.localization),
+        )
     }
 
     fn detect_popup_ok_new_button(&self) -> Result<Rect> {
-        detect_popup_ok_new_button(self.grayscale(), &self.localization)
+        self.track_game_template(
+            GameTemplate::PopupOkNew,
+            detect_popup_ok_new_button(self.grayscale(), &self.localization),
+        )
     }
 
     fn detect_elite_boss_bar(&self) -> bool {
         detect_elite_boss_bar(self.grayscale())
     }
 
+    fn detect_announcement(&self, kind: AnnouncementKind) -> bool {
+        detect_announcement(self.grayscale(), &self.localization, kind)
+    }
+
     fn detect_minimap(&self, border_threshold: u8) -> Result<Rect> {
         detect_minimap(self.bgr(), border_threshold)
     }
@@ -396,6 +656,10 @@ impl Detector for DefaultDetector {
         detect_minimap_name(self.grayscale(), minimap)
     }
 
+    fn detect_minimap_platforms(&self, minimap: Rect) -> Result<Vec<(i32, i32, i32)>> {
+        detect_minimap_platforms(self.grayscale(), minimap)
+    }
+
     fn detect_minimap_match(
         &self,
         minimap_snapshot: &Mat,
@@ -420,14 +684,26 @@ impl Detector for DefaultDetector {
     }
 
     fn detect_minimap_rune(&self, minimap: Rect) -> Result<Rect> {
-        detect_minimap_rune(&self.bgr().roi(minimap).unwrap())
+        detect_minimap_rune(&self.bgr().roi(minimap).unwrap(), Point::default(), 0.75)
+    }
+
+    fn detect_minimap_rune_near(&self, minimap: Rect, hints: &[Point]) -> Result<Rect> {
+        detect_minimap_rune_near(&self.bgr().roi(minimap).unwrap(), hints)
     }
 
     fn detect_player(&self, minimap: Rect) -> Result<Rect> {
         detect_player(&self.bgr().roi(minimap).unwrap())
     }
 
-    fn detect_player_kind(&self, minimap: Rect, kind: OtherPlayerKind) -> bool {
+    fn detect_player_marker(&self, minimap: Rect, marker: PlayerMarker) -> Result<Rect> {
+        detect_player_marker(&self.bgr().roi(minimap).unwrap(), marker)
+    }
+
+    fn sample_player_marker(&self, minimap: Rect, point: Point) -> Result<PlayerMarker> {
+        sample_player_marker(&self.bgr().roi(minimap).unwrap(), point)
+    }
+
+    fn detect_player_kind(&self, minimap: Rect, kind: OtherPlayerKind) -> Result<Rect> {
         detect_player_kind(&self.bgr().roi(minimap).unwrap(), kind)
     }
 
@@ -451,6 +727,10 @@ impl Detector for DefaultDetector {
         detect_player_health(self.bgr(), current_bar, max_bar)
     }
 
+    fn detect_player_exp(&self) -> Result<u64> {
+        detect_player_exp(self.bgr())
+    }
+
     fn detect_player_buff(&self, kind: BuffKind) -> bool {
         let mat = match kind {
             BuffKind::Rune
@@ -486,11 +766,17 @@ impl Detector for DefaultDetector {
     }
 
     fn detect_familiar_save_button(&self) -> Result<Rect> {
-        detect_familiar_save_button(self.bgr(), &self.localization)
+        self.track_game_template(
+            GameTemplate::FamiliarsSaveButton,
+            detect_familiar_save_button(self.bgr(), &self.localization),
+        )
     }
 
     fn detect_familiar_level_button(&self) -> Result<Rect> {
-        detect_familiar_level_button(self.bgr(), &self.localization)
+        self.track_game_template(
+            GameTemplate::FamiliarsLevelSort,
+            detect_familiar_level_button(self.bgr(), &self.localization),
+        )
     }
 
     fn detect_familiar_slots(&self) -> Vec<(Rect, bool)> {
@@ -537,6 +823,10 @@ impl Detector for DefaultDetector {
         detect_timer_visible(self.grayscale(), &self.localization)
     }
 
+    fn detect_level_up_popup(&self) -> bool {
+        detect_level_up_popup(self.grayscale())
+    }
+
     fn detect_lie_detector(&self) -> Result<Rect> {
         detect_lie_detector(self.bgr())
     }
@@ -554,19 +844,31 @@ impl Detector for DefaultDetector {
     }
 
     fn detect_hexa_erda_conversion_button(&self) -> Result<Rect> {
-        detect_hexa_erda_conversion_button(self.bgr(), &self.localization)
+        self.track_game_template(
+            GameTemplate::HexaErdaConversionButton,
+            detect_hexa_erda_conversion_button(self.bgr(), &self.localization),
+        )
     }
 
     fn detect_hexa_booster_button(&self) -> Result<Rect> {
-        detect_hexa_booster_button(self.bgr(), &self.localization)
+        self.track_game_template(
+            GameTemplate::HexaBoosterButton,
+            detect_hexa_booster_button(self.bgr(), &self.localization),
+        )
     }
 
     fn detect_hexa_max_button(&self) -> Result<Rect> {
-        detect_hexa_max_button(self.bgr(), &self.localization)
+        self.track_game_template(
+            GameTemplate::HexaMaxButton,
+            detect_hexa_max_button(self.bgr(), &self.localization),
+        )
     }
 
     fn detect_hexa_convert_button(&self) -> Result<Rect> {
-        detect_hexa_convert_button(self.bgr(), &self.localization)
+        self.track_game_template(
+            GameTemplate::HexaConvertButton,
+            detect_hexa_convert_button(self.bgr(), &self.localization),
+        )
     }
 
     fn detect_hexa_sol_erda(&self) -> Result<SolErda> {
@@ -576,6 +878,136 @@ impl Detector for DefaultDetector {
     fn detect_transparent_shapes(&self, region: Rect) -> Vec<Rect> {
         detect_transparent_shapes(&self.bgr().roi(region).unwrap())
     }
+
+    fn detect_plugin(&self, name: &str) -> Result<bool> {
+        plugin::detect(name, &self.bgra())
+    }
+
+    fn detect_region_signature(&self, region: Rect) -> Result<RegionSignature> {
+        detect_region_signature(self.grayscale(), region)
+    }
+
+    fn detect_screen_anchor(&self) -> Result<ScreenAnchor> {
+        detect_screen_anchor(self.grayscale())
+    }
+
+    fn detect_resource_gate(&self, gate: ActionKeyResourceGate) -> Result<bool> {
+        detect_resource_gate(self.grayscale(), self.bgr(), gate)
+    }
+
+    fn detect_quick_slot_count(&self, region: Rect) -> Result<u32> {
+        detect_quick_slot_count(self.bgr(), region)
+    }
+}
+
+// These numbers are for scaling dx/dy on the screen to dx/dy on the minimap.
+// They are approximated in 1280x720 resolution by going from one point to another point
+// from the middle of the screen with both points visible on screen before traveling. Take
+// the distance traveled on the minimap and divide it by half of the resolution
+// (e.g. tralveled minimap x / 640). Whether it is correct or not, time will tell.
+const SCREEN_TO_MINIMAP_X_SCALE: f32 = 0.059_375;
+const SCREEN_TO_MINIMAP_Y_SCALE: f32 = 0.036_111;
+
+/// Screen margin added around the region from [`to_screen_region`] so that mobs partially
+/// outside of it are still detected.
+const MOB_SCREEN_REGION_MARGIN: i32 = 64;
+
+/// Approximates the object coordinate on screen to object coordinate on minimap.
+///
+/// This function tries to approximate the delta (dx, dy) that the player needs to move
+/// in relative to the minimap coordinate in order to reach the object. Returns the object
+/// coordinate on the minimap by adding the delta to the player position.
+///
+/// Note: It is not that accurate but that is that and this is this. Hey it seems better than
+/// the previous alchemy.
+#[inline]
+fn to_minimap_coordinate(bbox: Rect, minimap_bbox: Rect, player: Point, mat_size: Size) -> Point {
+    // The main idea is to calculate the offset of the detected object from the middle of screen
+    // and use that distance as dx/dy to move the player. This assumes the player will
+    // most of the time be near or very close to the middle of the screen. This is already
+    // not accurate in the sense that the camera will have a bit of lag before
+    // it is centered again on the player. And when the player is near edges of the map,
+    // this function is just plain wrong. For better accuracy, detecting where the player is
+    // on the screen and use that as the basis is required.
+    let x_screen_mid = mat_size.width / 2;
+    let x_mid = bbox.x + bbox.width / 2;
+    let x_screen_delta = x_screen_mid - x_mid;
+    let x_minimap_delta = (x_screen_delta as f32 * SCREEN_TO_MINIMAP_X_SCALE) as i32;
+
+    // For dy, if the whole bounding box is above the screen mid point, then the
+    // box top edge is used to increase the dy distance as to help the player move up. The same
+    // goes for moving down. If the bounding box overlaps with the screen mid point, the box
+    // mid point is used as to to help the player stay in place.
+    let y_screen_mid = mat_size.height / 2;
+    let y = if bbox.y + bbox.height < y_screen_mid {
+        bbox.y
+    } else if bbox.y > y_screen_mid {
+        bbox.y + bbox.height
+    } else {
+        bbox.y + bbox.height / 2
+    };
+    let y_screen_delta = y_screen_mid - y;
+    let y_minimap_delta = (y_screen_delta as f32 * SCREEN_TO_MINIMAP_Y_SCALE) as i32;
+
+    let point_x = if x_minimap_delta > 0 {
+        (player.x - x_minimap_delta).max(0)
+    } else {
+        (player.x - x_minimap_delta).min(minimap_bbox.width)
+    };
+    let point_y = (player.y + y_minimap_delta).max(0).min(minimap_bbox.height);
+    // Minus the y by minimap height to make it relative to the minimap top edge
+    Point::new(point_x, minimap_bbox.height - point_y)
+}
+
+/// Approximates the screen region of the minimap `bound` around `player`.
+///
+/// This is the inverse of [`to_minimap_coordinate`] for the corners of `bound` with
+/// [`MOB_SCREEN_REGION_MARGIN`] added. Since [`to_minimap_coordinate`] clamps to the minimap, a
+/// side of `bound` touching the minimap edge extends to the screen edge. Returns [`None`] if
+/// `bound` is entirely off screen.
+fn to_screen_region(
+    bound: Rect,
+    minimap_bbox: Rect,
+    player: Point,
+    mat_size: Size,
+) -> Option<Rect> {
+    let to_screen = |point: Point| {
+        // `point` y is relative to the minimap top edge while `player` y is relative to the bottom
+        let x_minimap_delta = player.x - point.x;
+        let y_minimap_delta = (minimap_bbox.height - point.y) - player.y;
+        Point::new(
+            mat_size.width / 2 - (x_minimap_delta as f32 / SCREEN_TO_MINIMAP_X_SCALE) as i32,
+            mat_size.height / 2 - (y_minimap_delta as f32 / SCREEN_TO_MINIMAP_Y_SCALE) as i32,
+        )
+    };
+    let tl = to_screen(bound.tl());
+    let br = to_screen(bound.br());
+
+    let left = if bound.x <= 0 {
+        0
+    } else {
+        (tl.x - MOB_SCREEN_REGION_MARGIN).max(0)
+    };
+    let top = if bound.y <= 0 {
+        0
+    } else {
+        (tl.y - MOB_SCREEN_REGION_MARGIN).max(0)
+    };
+    let right = if bound.x + bound.width >= minimap_bbox.width {
+        mat_size.width
+    } else {
+        (br.x + MOB_SCREEN_REGION_MARGIN).min(mat_size.width)
+    };
+    let bottom = if bound.y + bound.height >= minimap_bbox.height {
+        mat_size.height
+    } else {
+        (br.y + MOB_SCREEN_REGION_MARGIN).min(mat_size.height)
+    };
+    if right <= left || bottom <= top {
+        return None;
+    }
+
+    Some(Rect::new(left, top, right - left, bottom - top))
 }
 
 fn detect_mobs(
@@ -583,6 +1015,7 @@ fn detect_mobs(
     minimap: Rect,
     bound: Rect,
     player: Point,
+    detection: &MobDetection,
 ) -> Result<Vec<Point>> {
     static MOB_MODEL: LazyLock<Mutex<Session>> = LazyLock::new(|| {
         Mutex::new(
@@ -590,88 +1023,160 @@ fn detect_mobs(
                 .expect("build mob detection session successfully"),
         )
     });
-
-    /// Approximates the mob coordinate on screen to mob coordinate on minimap.
+    /// Custom mob detection models keyed by their path.
     ///
-    /// This function tries to approximate the delta (dx, dy) that the player needs to move
-    /// in relative to the minimap coordinate in order to reach the mob. Returns the mob
-    /// coordinate on the minimap by adding the delta to the player position.
-    ///
-    /// Note: It is not that accurate but that is that and this is this. Hey it seems better than
-    /// the previous alchemy.
-    #[inline]
-    fn to_minimap_coordinate(
-        mob_bbox: Rect,
-        minimap_bbox: Rect,
-        mobbing_bound: Rect,
-        player: Point,
-        mat_size: Size,
-    ) -> Option<Point> {
-        // These numbers are for scaling dx/dy on the screen to dx/dy on the minimap.
-        // They are approximated in 1280x720 resolution by going from one point to another point
-        // from the middle of the screen with both points visible on screen before traveling. Take
-        // the distance traveled on the minimap and divide it by half of the resolution
-        // (e.g. tralveled minimap x / 640). Whether it is correct or not, time will tell.
-        const X_SCALE: f32 = 0.059_375;
-        const Y_SCALE: f32 = 0.036_111;
-
-        // The main idea is to calculate the offset of the detected mob from the middle of screen
-        // and use that distance as dx/dy to move the player. This assumes the player will
-        // most of the time be near or very close to the middle of the screen. This is already
-        // not accurate in the sense that the camera will have a bit of lag before
-        // it is centered again on the player. And when the player is near edges of the map,
-        // this function is just plain wrong. For better accuracy, detecting where the player is
-        // on the screen and use that as the basis is required.
-        let x_screen_mid = mat_size.width / 2;
-        let x_mob_mid = mob_bbox.x + mob_bbox.width / 2;
-        let x_screen_delta = x_screen_mid - x_mob_mid;
-        let x_minimap_delta = (x_screen_delta as f32 * X_SCALE) as i32;
-
-        // For dy, if the whole mob bounding box is above the screen mid point, then the
-        // box top edge is used to increase the dy distance as to help the player move up. The same
-        // goes for moving down. If the bounding box overlaps with the screen mid point, the box
-        // mid point is used as to to help the player stay in place.
-        let y_screen_mid = mat_size.height / 2;
-        let y_mob = if mob_bbox.y + mob_bbox.height < y_screen_mid {
-            mob_bbox.y
-        } else if mob_bbox.y > y_screen_mid {
-            mob_bbox.y + mob_bbox.height
-        } else {
-            mob_bbox.y + mob_bbox.height / 2
-        };
-        let y_screen_delta = y_screen_mid - y_mob;
-        let y_minimap_delta = (y_screen_delta as f32 * Y_SCALE) as i32;
+    /// A failed model is also cached so that it is not loaded again on every detection.
+    static CUSTOM_MOB_MODELS: LazyLock<Mutex<HashMap<String, Option<Session>>>> =
+        LazyLock::new(Mutex::default);
 
-        let point_x = if x_minimap_delta > 0 {
-            (player.x - x_minimap_delta).max(0)
-        } else {
-            (player.x - x_minimap_delta).min(minimap_bbox.width)
-        };
-        let point_y = (player.y + y_minimap_delta).max(0).min(minimap_bbox.height);
-        // Minus the y by minimap height to make it relative to the minimap top edge
-        let point = Point::new(point_x, minimap_bbox.height - point_y);
-        if point.x < mobbing_bound.x
-            || point.x > mobbing_bound.x + mobbing_bound.width
-            || point.y < mobbing_bound.y
-            || point.y > mobbing_bound.y + mobbing_bound.height
-        {
-            None
-        } else {
-            Some(point)
+    let size = bgr.size().unwrap();
+    // Only mobs within `bound` are kept so the rest of the screen is not scanned
+    let Some(region) = to_screen_region(bound, minimap, player, size) else {
+        return Ok(vec![]);
+    };
+    let bgr = bgr.roi(region)?;
+    let bboxes = match detection.kind {
+        MobDetectionKind::Default => {
+            detect_mob_bboxes_with_model(&bgr, &mut MOB_MODEL.lock().unwrap())?
         }
-    }
+        MobDetectionKind::Model => {
+            let mut models = CUSTOM_MOB_MODELS.lock().unwrap();
+            let model = models
+                .entry(detection.model_path.clone())
+                .or_insert_with_key(|path| {
+                    fs::read(path)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|bytes| build_session(&bytes))
+                        .inspect_err(|err| {
+                            report_error(
+                                "detect",
+                                BackendError::ModelLoadFailed {
+                                    path: path.clone(),
+                                    reason: err.to_string(),
+                                },
+                            );
+                        })
+                        .ok()
+                })
+                .as_mut()
+                .ok_or(anyhow!("custom mob model not loaded"))?;
+            detect_mob_bboxes_with_model(&bgr, model)?
+        }
+        MobDetectionKind::ColorSignature => {
+            detect_mob_bboxes_with_color(&bgr, detection.color_signature)?
+        }
+    };
+    let points = bboxes
+        .into_iter()
+        .map(|bbox| to_minimap_coordinate(bbox + region.tl(), minimap, player, size))
+        .filter(|point| {
+            point.x >= bound.x
+                && point.x <= bound.x + bound.width
+                && point.y >= bound.y
+                && point.y <= bound.y + bound.height
+        })
+        .collect::<Vec<_>>();
+    Ok(points)
+}
 
+fn detect_mob_bboxes_with_model(
+    bgr: &impl MatTraitConst,
+    model: &mut Session,
+) -> Result<Vec<Rect>> {
     let size = bgr.size().unwrap();
     let (mat_in, w_ratio, h_ratio, left, top) = preprocess_for_yolo(bgr);
-    let mut model = MOB_MODEL.lock().unwrap();
-    let result = model.run([to_input_value(&mat_in)]).unwrap();
+    let result = model.run([to_input_value(&mat_in)])?;
     let result = from_output_value(&result);
     // SAFETY: 0..result.rows() is within Mat bounds
-    let points = (0..result.rows())
+    let bboxes = (0..result.rows())
         .map(|i| unsafe { result.at_row_unchecked::<f32>(i).unwrap() })
         .filter(|pred| pred[4] >= 0.5)
         .map(|pred| remap_from_yolo(pred, size, w_ratio, h_ratio, left, top))
-        .filter_map(|bbox| to_minimap_coordinate(bbox, minimap, bound, player, size))
+        .collect::<Vec<_>>();
+    Ok(bboxes)
+}
+
+fn detect_mob_bboxes_with_color(
+    bgr: &impl MatTraitConst,
+    signature: MobColorSignature,
+) -> Result<Vec<Rect>> {
+    let hsv = to_hsv(bgr);
+    let in_hue_range = |hue_min: u8, hue_max: u8| -> Result<Mat> {
+        let mut mask = Mat::default();
+        in_range(
+            &hsv,
+            &Scalar::new(
+                hue_min as f64,
+                signature.saturation_min as f64,
+                signature.value_min as f64,
+                0.0,
+            ),
+            &Scalar::new(hue_max as f64, 255.0, 255.0, 0.0),
+            &mut mask,
+        )?;
+        Ok(mask)
+    };
+    let mask = if signature.hue_min <= signature.hue_max {
+        in_hue_range(signature.hue_min, signature.hue_max)?
+    } else {
+        let mut mask = Mat::default();
+        bitwise_or_def(
+            &in_hue_range(signature.hue_min, u8::MAX)?,
+            &in_hue_range(0, signature.hue_max)?,
+            &mut mask,
+        )?;
+        mask
+    };
+
+    let mut contours = Vector::<Vector<Point>>::new();
+    find_contours_def(&mask, &mut contours, RETR_EXTERNAL, CHAIN_APPROX_SIMPLE)?;
+    let bboxes = contours
+        .into_iter()
+        .map(|contour| bounding_rect(&contour).unwrap())
+        .filter(|bbox| (signature.area_min..=signature.area_max).contains(&bbox.area()))
+        .collect::<Vec<_>>();
+    Ok(bboxes)
+}
+
+fn detect_loots(bgr: &impl MatTraitConst, minimap: Rect, player: Point) -> Result<Vec<Point>> {
+    /// Minimum saturation and value of a loot pixel in HSV.
+    ///
+    /// Dropped items and mesos are small, brightly colored icons that stand out against most map
+    /// backgrounds. This is a best-effort color blob detection.
+    const LOOT_MIN_SATURATION: f64 = 130.0;
+    const LOOT_MIN_VALUE: f64 = 170.0;
+    const LOOT_MIN_AREA: i32 = 40;
+    const LOOT_MAX_AREA: i32 = 1200;
+    const LOOT_MAX_SIDE: i32 = 48;
+
+    // Crop to a band around the player feet, assuming the player is near the middle of the screen
+    let size = bgr.size().unwrap();
+    let crop_bbox = Rect::new(
+        size.width / 6,
+        size.height / 2 - size.height / 12,
+        size.width - size.width / 3,
+        size.height / 6,
+    );
+    let hsv = to_hsv(&bgr.roi(crop_bbox)?);
+    let mut mask = Mat::default();
+    in_range(
+        &hsv,
+        &Scalar::new(0.0, LOOT_MIN_SATURATION, LOOT_MIN_VALUE, 0.0),
+        &Scalar::new(255.0, 255.0, 255.0, 0.0),
+        &mut mask,
+    )?;
+
+    let mut contours = Vector::<Vector<Point>>::new();
+    find_contours_def(&mask, &mut contours, RETR_EXTERNAL, CHAIN_APPROX_SIMPLE)?;
+    let points = contours
+        .into_iter()
+        .map(|contour| bounding_rect(&contour).unwrap())
+        .filter(|bbox| {
+            (LOOT_MIN_AREA..=LOOT_MAX_AREA).contains(&bbox.area())
+                && bbox.width <= LOOT_MAX_SIDE
+                && bbox.height <= LOOT_MAX_SIDE
+        })
+        .map(|bbox| to_minimap_coordinate(bbox + crop_bbox.tl(), minimap, player, size))
         .collect::<Vec<_>>();
     Ok(points)
 }
@@ -915,6 +1420,28 @@ fn detect_popup_cancel_old_button(
     )
 }
 
+fn detect_announcement(
+    grayscale: &impl MatTraitConst,
+    localization: &Localization,
+    kind: AnnouncementKind,
+) -> bool {
+    let Some(template) = localization
+        .announcement_base64(kind)
+        .and_then(|base64| to_mat_from_base64(base64, true).ok())
+    else {
+        return false;
+    };
+
+    let size = grayscale.size().unwrap();
+    // crop to top half of the image where the banners are shown
+    let crop_bbox = Rect::new(0, 0, size.width, size.height / 2);
+    let Ok(banner) = grayscale.roi(crop_bbox) else {
+        return false;
+    };
+
+    detect_template(&banner, &template, Point::default(), 0.75).is_ok()
+}
+
 fn detect_elite_boss_bar(grayscale: &impl MatTraitConst) -> bool {
     /// TODO: Support default ratio
     static TEMPLATE_1: LazyLock<Mat> = LazyLock::new(|| {
@@ -1110,6 +1637,58 @@ fn detect_minimap_name(grayscale: &impl MatTraitConst, minimap: Rect) -> Result<
     Ok(name_bbox)
 }
 
+fn detect_minimap_platforms(
+    grayscale: &impl MatTraitConst,
+    minimap: Rect,
+) -> Result<Vec<(i32, i32, i32)>> {
+    /// Minimum width of a horizontal edge to be considered a platform.
+    const PLATFORM_MIN_WIDTH: i32 = 6;
+    /// Maximum y distance from an already found platform for an edge to be considered part of
+    /// the same walkable strip.
+    const PLATFORM_STRIP_MAX_HEIGHT: i32 = 3;
+
+    let minimap = grayscale.roi(minimap)?;
+    let mut edges = Mat::default();
+    canny_def(&minimap, &mut edges, 50.0, 150.0)?;
+
+    // Removes vertical edges (e.g. ropes) and horizontal edges too short to be platforms
+    let kernel = get_structuring_element_def(MORPH_RECT, Size::new(PLATFORM_MIN_WIDTH, 1))?;
+    let mut horizontal_edges = Mat::default();
+    morphology_ex_def(&edges, &mut horizontal_edges, MORPH_OPEN, &kernel)?;
+
+    let mut platforms = Vec::<(i32, i32, i32)>::new();
+    for y in 0..horizontal_edges.rows() {
+        let row = horizontal_edges.at_row::<u8>(y)?;
+        let mut x = 0;
+        while x < row.len() {
+            if row[x] == 0 {
+                x += 1;
+                continue;
+            }
+
+            let x_start = x as i32;
+            while x < row.len() && row[x] > 0 {
+                x += 1;
+            }
+            let x_end = x as i32 - 1;
+            if x_end - x_start + 1 < PLATFORM_MIN_WIDTH {
+                continue;
+            }
+
+            // Only the top edge of a walkable strip is walked on, the bottom edge is skipped
+            let is_same_strip = platforms.iter().any(|&(start, end, platform_y)| {
+                y - platform_y <= PLATFORM_STRIP_MAX_HEIGHT && x_start <= end && start <= x_end
+            });
+            if !is_same_strip {
+                platforms.push((x_start, x_end, y));
+            }
+        }
+    }
+    debug!(target: "minimap", "detected {} candidate platforms", platforms.len());
+
+    Ok(platforms)
+}
+
 fn detect_minimap_match<T: ToInputArray + MatTraitConst>(
     bgra: &impl MatTraitConst,
     grayscale: &impl MatTraitConst,
@@ -1186,7 +1765,11 @@ fn detect_minimap_portals<T: MatTraitConst + ToInputArray>(minimap_bgr: T) -> Ve
     .collect::<Vec<_>>()
 }
 
-fn detect_minimap_rune(minimap_bgr: &impl ToInputArray) -> Result<Rect> {
+fn detect_minimap_rune(
+    minimap_bgr: &impl ToInputArray,
+    offset: Point,
+    threshold: f64,
+) -> Result<Rect> {
     /// TODO: Support default ratio
     static TEMPLATE: LazyLock<Mat> = LazyLock::new(|| {
         imgcodecs::imdecode(include_bytes!(env!("RUNE_TEMPLATE")), IMREAD_COLOR).unwrap()
@@ -1197,14 +1780,28 @@ fn detect_minimap_rune(minimap_bgr: &impl ToInputArray) -> Result<Rect> {
 
     // Expands by 2 pixels to preserve previous position calculation. Previous template is 11x11
     // while the current template is 9x9.
-    detect_template_single(
-        minimap_bgr,
-        &*TEMPLATE,
-        &*TEMPLATE_MASK,
-        Point::default(),
-        0.75,
-    )
-    .map(|(bbox, _)| expand_bbox(None, bbox, 1))
+    detect_template_single(minimap_bgr, &*TEMPLATE, &*TEMPLATE_MASK, offset, threshold)
+        .map(|(bbox, _)| expand_bbox(None, bbox, 1))
+}
+
+fn detect_minimap_rune_near(minimap_bgr: &impl MatTraitConst, hints: &[Point]) -> Result<Rect> {
+    /// Half of the square region size around each hint to search for the rune.
+    const HINT_REGION_HALF_SIZE: i32 = 12;
+
+    let size = minimap_bgr.size()?;
+    hints
+        .iter()
+        .find_map(|hint| {
+            let x1 = (hint.x - HINT_REGION_HALF_SIZE).clamp(0, size.width);
+            let y1 = (hint.y - HINT_REGION_HALF_SIZE).clamp(0, size.height);
+            let x2 = (hint.x + HINT_REGION_HALF_SIZE).clamp(0, size.width);
+            let y2 = (hint.y + HINT_REGION_HALF_SIZE).clamp(0, size.height);
+            let region = Rect::new(x1, y1, x2 - x1, y2 - y1);
+            let region_bgr = minimap_bgr.roi(region).ok()?;
+
+            detect_minimap_rune(&region_bgr, region.tl(), 0.6).ok()
+        })
+        .ok_or(anyhow!("rune not found near hints"))
 }
 
 fn detect_player(minimap_bgr: &impl ToInputArray) -> Result<Rect> {
@@ -1304,7 +1901,90 @@ fn detect_player(minimap_bgr: &impl ToInputArray) -> Result<Rect> {
     Err(anyhow!("player not found"))
 }
 
-fn detect_player_kind(minimap_bgr: &impl ToInputArray, kind: OtherPlayerKind) -> bool {
+fn detect_player_marker(minimap_bgr: &impl ToInputArray, marker: PlayerMarker) -> Result<Rect> {
+    let (red, green, blue) = marker.color;
+    let tolerance = marker.tolerance as f64;
+    let mut mask = Mat::default();
+    in_range(
+        minimap_bgr,
+        &Scalar::new(
+            blue as f64 - tolerance,
+            green as f64 - tolerance,
+            red as f64 - tolerance,
+            0.0,
+        ),
+        &Scalar::new(
+            blue as f64 + tolerance,
+            green as f64 + tolerance,
+            red as f64 + tolerance,
+            255.0,
+        ),
+        &mut mask,
+    )?;
+
+    let mut contours = Vector::<Vector<Point>>::new();
+    find_contours_def(&mask, &mut contours, RETR_EXTERNAL, CHAIN_APPROX_SIMPLE)?;
+    let width_range = (marker.width / 2).max(1)..=marker.width * 2;
+    let height_range = (marker.height / 2).max(1)..=marker.height * 2;
+    contours
+        .into_iter()
+        .map(|contour| bounding_rect(&contour).unwrap())
+        .filter(|bbox| width_range.contains(&bbox.width) && height_range.contains(&bbox.height))
+        .min_by_key(|bbox| (bbox.width - marker.width).abs() + (bbox.height - marker.height).abs())
+        .map(|bbox| expand_bbox(None, bbox, 1))
+        .ok_or(anyhow!("player marker not found"))
+}
+
+fn sample_player_marker(minimap_bgr: &impl MatTraitConst, point: Point) -> Result<PlayerMarker> {
+    const SAMPLE_TOLERANCE: u8 = 20;
+    /// Maximum size of a sampled marker to avoid sampling the minimap background.
+    const SAMPLE_MAX_SIDE: i32 = 20;
+
+    let pixel = *minimap_bgr.at_2d::<Vec3b>(point.y, point.x)?;
+    let marker = PlayerMarker {
+        color: (pixel[2], pixel[1], pixel[0]),
+        tolerance: SAMPLE_TOLERANCE,
+        width: SAMPLE_MAX_SIDE,
+        height: SAMPLE_MAX_SIDE,
+    };
+    let tolerance = SAMPLE_TOLERANCE as f64;
+    let mut mask = Mat::default();
+    in_range(
+        minimap_bgr,
+        &Scalar::new(
+            pixel[0] as f64 - tolerance,
+            pixel[1] as f64 - tolerance,
+            pixel[2] as f64 - tolerance,
+            0.0,
+        ),
+        &Scalar::new(
+            pixel[0] as f64 + tolerance,
+            pixel[1] as f64 + tolerance,
+            pixel[2] as f64 + tolerance,
+            255.0,
+        ),
+        &mut mask,
+    )?;
+
+    let mut contours = Vector::<Vector<Point>>::new();
+    find_contours_def(&mask, &mut contours, RETR_EXTERNAL, CHAIN_APPROX_SIMPLE)?;
+    let bbox = contours
+        .into_iter()
+        .map(|contour| bounding_rect(&contour).unwrap())
+        .find(|bbox| bbox.contains(point))
+        .ok_or(anyhow!("player marker not found at sampled point"))?;
+    if bbox.width > SAMPLE_MAX_SIDE || bbox.height > SAMPLE_MAX_SIDE {
+        bail!("sampled region is too large for a player marker");
+    }
+
+    Ok(PlayerMarker {
+        width: bbox.width,
+        height: bbox.height,
+        ..marker
+    })
+}
+
+fn detect_player_kind(minimap_bgr: &impl ToInputArray, kind: OtherPlayerKind) -> Result<Rect> {
     /// TODO: Support default ratio
     static STRANGER_TEMPLATE: LazyLock<Mat> = LazyLock::new(|| {
         imgcodecs::imdecode(
@@ -1326,13 +2006,13 @@ fn detect_player_kind(minimap_bgr: &impl ToInputArray, kind: OtherPlayerKind) ->
 
     match kind {
         OtherPlayerKind::Stranger => {
-            detect_template(minimap_bgr, &*STRANGER_TEMPLATE, Point::default(), 0.85).is_ok()
+            detect_template(minimap_bgr, &*STRANGER_TEMPLATE, Point::default(), 0.85)
         }
         OtherPlayerKind::Guildie => {
-            detect_template(minimap_bgr, &*GUILDIE_TEMPLATE, Point::default(), 0.85).is_ok()
+            detect_template(minimap_bgr, &*GUILDIE_TEMPLATE, Point::default(), 0.85)
         }
         OtherPlayerKind::Friend => {
-            detect_template(minimap_bgr, &*FRIEND_TEMPLATE, Point::default(), 0.85).is_ok()
+            detect_template(minimap_bgr, &*FRIEND_TEMPLATE, Point::default(), 0.85)
         }
     }
 }
@@ -1352,6 +2032,10 @@ pub static CASH_SHOP_TEMPLATE: LazyLock<Mat> = LazyLock::new(|| {
 });
 
 fn detect_player_in_cash_shop(grayscale: &impl ToInputArray, localization: &Localization) -> bool {
+    detect_cash_shop(grayscale, localization).is_ok()
+}
+
+fn detect_cash_shop(grayscale: &impl ToInputArray, localization: &Localization) -> Result<Rect> {
     let template = localization
         .cash_shop_base64
         .as_ref()
@@ -1363,7 +2047,6 @@ fn detect_player_in_cash_shop(grayscale: &impl ToInputArray, localization: &Loca
         Point::default(),
         0.7,
     )
-    .is_ok()
 }
 
 fn detect_player_health_bar<T: MatTraitConst + ToInputArray>(grayscale: &T) -> Result<Rect> {
@@ -1497,6 +2180,54 @@ fn detect_player_health(
     Ok((current_health.min(max_health), max_health))
 }
 
+fn detect_player_exp(bgr: &impl MatTraitConst) -> Result<u64> {
+    /// TODO: Support default ratio
+    const EXP_BAR_HEIGHT: i32 = 14;
+
+    let size = bgr.size()?;
+    if size.height < EXP_BAR_HEIGHT {
+        bail!("frame is too small for EXP bar");
+    }
+    let exp_bar = Rect::new(0, size.height - EXP_BAR_HEIGHT, size.width, EXP_BAR_HEIGHT);
+    let (exp_bar_in, w_ratio, h_ratio) = preprocess_for_text_bboxes(&bgr.roi(exp_bar)?);
+    let bboxes = extract_text_bboxes(&exp_bar_in, w_ratio, h_ratio, exp_bar.x, exp_bar.y);
+
+    // The EXP bar text is `EXP. <exp> [<percent>%]` and the EXP is the only digits-only word
+    extract_texts(bgr, &bboxes)
+        .into_iter()
+        .find_map(|text| {
+            let digits = text.replace(',', "");
+            if digits.is_empty() || !digits.chars().all(|char| char.is_ascii_digit()) {
+                return None;
+            }
+            digits.parse::<u64>().ok()
+        })
+        .ok_or(anyhow!("cannot detect EXP"))
+}
+
+fn detect_quick_slot_count(bgr: &impl MatTraitConst, region: Rect) -> Result<u32> {
+    let size = bgr.size()?;
+    let region = region & Rect::new(0, 0, size.width, size.height);
+    if region.empty() {
+        bail!("region is outside of frame");
+    }
+
+    let (region_in, w_ratio, h_ratio) = preprocess_for_text_bboxes(&bgr.roi(region)?);
+    let bboxes = extract_text_bboxes(&region_in, w_ratio, h_ratio, region.x, region.y);
+
+    // The count may be grouped with commas when large
+    extract_texts(bgr, &bboxes)
+        .into_iter()
+        .find_map(|text| {
+            let digits = text.replace(',', "");
+            if digits.is_empty() || !digits.chars().all(|char| char.is_ascii_digit()) {
+                return None;
+            }
+            digits.parse::<u32>().ok()
+        })
+        .ok_or(anyhow!("cannot detect quick slot count"))
+}
+
 fn detect_player_buff<T: MatTraitConst + ToInputArray>(mat: &T, kind: BuffKind) -> bool {
     /// TODO: Support default ratio
     static RUNE_BUFF: LazyLock<Mat> = LazyLock::new(|| {
@@ -2371,6 +3102,13 @@ fn detect_change_channel_menu_opened(
     grayscale: &impl ToInputArray,
     localization: &Localization,
 ) -> bool {
+    detect_change_channel_menu(grayscale, localization).is_ok()
+}
+
+fn detect_change_channel_menu(
+    grayscale: &impl ToInputArray,
+    localization: &Localization,
+) -> Result<Rect> {
     let template = localization
         .change_channel_base64
         .as_ref()
@@ -2382,7 +3120,6 @@ fn detect_change_channel_menu_opened(
         Point::default(),
         0.75,
     )
-    .is_ok()
 }
 
 fn detect_chat_menu_opened(grayscale: &impl ToInputArray) -> bool {
@@ -2406,6 +3143,10 @@ pub static TIMER_TEMPLATE: LazyLock<Mat> = LazyLock::new(|| {
 });
 
 fn detect_timer_visible(grayscale: &impl ToInputArray, localization: &Localization) -> bool {
+    detect_timer(grayscale, localization).is_ok()
+}
+
+fn detect_timer(grayscale: &impl ToInputArray, localization: &Localization) -> Result<Rect> {
     let template = localization
         .timer_base64
         .as_ref()
@@ -2417,7 +3158,26 @@ fn detect_timer_visible(grayscale: &impl ToInputArray, localization: &Localizati
         Point::default(),
         0.75,
     )
-    .is_ok()
+}
+
+fn detect_level_up_popup(grayscale: &impl ToInputArray) -> bool {
+    static LEVEL_UP_TEMPLATE: LazyLock<Mat> = LazyLock::new(|| {
+        imgcodecs::imdecode(
+            include_bytes!(env!("LEVEL_UP_POPUP_TEMPLATE")),
+            IMREAD_GRAYSCALE,
+        )
+        .unwrap()
+    });
+    static NEW_SKILL_TEMPLATE: LazyLock<Mat> = LazyLock::new(|| {
+        imgcodecs::imdecode(
+            include_bytes!(env!("NEW_SKILL_POPUP_TEMPLATE")),
+            IMREAD_GRAYSCALE,
+        )
+        .unwrap()
+    });
+
+    detect_template(grayscale, &*LEVEL_UP_TEMPLATE, Point::default(), 0.75).is_ok()
+        || detect_template(grayscale, &*NEW_SKILL_TEMPLATE, Point::default(), 0.75).is_ok()
 }
 
 fn detect_lie_detector(bgr: &impl ToInputArray) -> Result<Rect> {
@@ -2682,6 +3442,103 @@ fn detect_hexa_sol_erda(grayscale: &impl ToInputArray) -> Result<SolErda> {
     bail!("sol erda tracker menu not visible")
 }
 
+fn detect_region_signature(grayscale: &Mat, region: Rect) -> Result<RegionSignature> {
+    let region = region & Rect::new(0, 0, grayscale.cols(), grayscale.rows());
+    if region.empty() {
+        bail!("region is outside of frame");
+    }
+
+    let size = REGION_SIGNATURE_SIZE as i32;
+    let mut resized = Mat::default();
+    resize(
+        &grayscale.roi(region)?,
+        &mut resized,
+        Size::new(size, size),
+        0.0,
+        0.0,
+        INTER_AREA,
+    )?;
+    let mut signature = [0; REGION_SIGNATURE_SIZE * REGION_SIGNATURE_SIZE];
+    signature.copy_from_slice(resized.data_bytes()?);
+
+    Ok(RegionSignature(signature))
+}
+
+fn detect_screen_anchor(grayscale: &Mat) -> Result<ScreenAnchor> {
+    // Skips the top and bottom of the screen where the UIs do not scroll with the camera
+    let top = grayscale.rows() / 5;
+    let height = grayscale.rows() * 3 / 5;
+    if height <= 0 || grayscale.cols() < SCREEN_ANCHOR_WIDTH as i32 {
+        bail!("frame is too small for screen anchor");
+    }
+
+    let mut resized = Mat::default();
+    resize(
+        &grayscale.roi(Rect::new(0, top, grayscale.cols(), height))?,
+        &mut resized,
+        Size::new(SCREEN_ANCHOR_WIDTH as i32, 1),
+        0.0,
+        0.0,
+        INTER_AREA,
+    )?;
+    let mut anchor = [0; SCREEN_ANCHOR_WIDTH];
+    anchor.copy_from_slice(resized.data_bytes()?);
+
+    Ok(ScreenAnchor(anchor))
+}
+
+fn detect_resource_gate(grayscale: &Mat, bgr: &Mat, gate: ActionKeyResourceGate) -> Result<bool> {
+    /// The minimum difference between the darkest and brightest pixels for a region to be
+    /// considered not empty.
+    const NOT_EMPTY_MIN_CONTRAST: f64 = 60.0;
+
+    let region = Rect::new(gate.x, gate.y, gate.width, gate.height)
+        & Rect::new(0, 0, grayscale.cols(), grayscale.rows());
+    if region.empty() {
+        bail!("region is outside of frame");
+    }
+
+    match gate.kind {
+        ActionKeyResourceGateKind::NotEmpty => {
+            let mut min = 0.0;
+            let mut max = 0.0;
+            min_max_loc(
+                &grayscale.roi(region)?,
+                Some(&mut min),
+                Some(&mut max),
+                None,
+                None,
+                &no_array(),
+            )?;
+
+            Ok(max - min >= NOT_EMPTY_MIN_CONTRAST)
+        }
+        ActionKeyResourceGateKind::ColorPresent => {
+            let (red, green, blue) = gate.color;
+            let tolerance = gate.tolerance as f64;
+            let mut mask = Mat::default();
+            in_range(
+                &bgr.roi(region)?,
+                &Scalar::new(
+                    blue as f64 - tolerance,
+                    green as f64 - tolerance,
+                    red as f64 - tolerance,
+                    0.0,
+                ),
+                &Scalar::new(
+                    blue as f64 + tolerance,
+                    green as f64 + tolerance,
+                    red as f64 + tolerance,
+                    255.0,
+                ),
+                &mut mask,
+            )?;
+
+            Ok(count_non_zero(&mask)? > 0)
+        }
+    }
+}
+
 fn detect_transparent_shapes(bgr: &impl MatTraitConst) -> Vec<Rect> {
     static MODEL: LazyLock<Mutex<Session>> = LazyLock::new(|| {
         Mutex::new(
@@ -2733,6 +3590,10 @@ fn detect_template_single<T: ToInputArray + MatTraitConst>(
 
 /// Detects multiple matches from `template` from the given BGR image `Mat` and returns up to
 /// `max_matches` best results.
+///
+/// Each template is matched at its own last found [`TemplateScale`]. When it does not match,
+/// other [`TEMPLATE_SCALES`] are searched in case the in-game UI scale has changed and the first
+/// matching scale is cached along with the resized template for later detections.
 #[inline]
 fn detect_template_multiple<T: ToInputArray + MatTraitConst>(
     mat: &impl ToInputArray,
@@ -2741,6 +3602,129 @@ fn detect_template_multiple<T: ToInputArray + MatTraitConst>(
     offset: Point,
     max_matches: usize,
     threshold: f64,
+) -> Vec<Result<(Rect, f64)>> {
+    let key = TemplateKey::new(template);
+    let (scale, resized) = TEMPLATE_SCALES_CACHE
+        .lock()
+        .unwrap()
+        .get(&key)
+        .map(|cached| (cached.scale, cached.resized.clone()))
+        .unwrap_or((1.0, None));
+    let matches = match resized.as_deref() {
+        Some((template, mask)) => {
+            detect_template_multiple_unscaled(mat, template, mask, offset, max_matches, threshold)
+        }
+        None => {
+            detect_template_multiple_unscaled(mat, template, &mask, offset, max_matches, threshold)
+        }
+    };
+    if matches.iter().any(Result::is_ok)
+        || threshold < TEMPLATE_SCALE_SEARCH_MIN_THRESHOLD
+        || !should_search_template_scales(key)
+    {
+        return matches;
+    }
+
+    for candidate in TEMPLATE_SCALES
+        .into_iter()
+        .filter(|candidate| *candidate != scale)
+    {
+        let resized = if candidate == 1.0 {
+            None
+        } else {
+            match resize_template_and_mask(template, &mask, candidate) {
+                Ok(resized) => Some(Arc::new(resized)),
+                Err(err) => {
+                    error!(target: "detect", "template scaling error {err}");
+                    return matches;
+                }
+            }
+        };
+        let candidate_matches = match resized.as_deref() {
+            Some((template, mask)) => detect_template_multiple_unscaled(
+                mat,
+                template,
+                mask,
+                offset,
+                max_matches,
+                threshold,
+            ),
+            None => detect_template_multiple_unscaled(
+                mat,
+                template,
+                &mask,
+                offset,
+                max_matches,
+                threshold,
+            ),
+        };
+        if candidate_matches.iter().any(Result::is_ok) {
+            info!(target: "detect", "template scale changed from {scale} to {candidate}");
+            if let Some(cached) = TEMPLATE_SCALES_CACHE.lock().unwrap().get_mut(&key) {
+                cached.scale = candidate;
+                cached.resized = resized;
+            }
+            return candidate_matches;
+        }
+    }
+
+    matches
+}
+
+/// Whether [`TEMPLATE_SCALES`] can be searched now for the template identified by `key`.
+fn should_search_template_scales(key: TemplateKey) -> bool {
+    let mut cache = TEMPLATE_SCALES_CACHE.lock().unwrap();
+    if cache.len() >= MAX_TEMPLATE_SCALES && !cache.contains_key(&key) {
+        cache.clear();
+    }
+
+    cache.entry(key).or_default().should_search()
+}
+
+/// Resizes `template` and `mask` by `scale`.
+///
+/// An empty `mask` stays empty.
+fn resize_template_and_mask(
+    template: &impl ToInputArray,
+    mask: &impl ToInputArray,
+    scale: f64,
+) -> Result<(Mat, Mat)> {
+    #[inline]
+    fn resized(mat: &impl ToInputArray, scale: f64) -> Result<Mat> {
+        let interpolation = if scale < 1.0 {
+            INTER_AREA
+        } else {
+            INTER_LINEAR
+        };
+        let mut resized = Mat::default();
+        resize(
+            mat,
+            &mut resized,
+            Size::default(),
+            scale,
+            scale,
+            interpolation,
+        )?;
+        Ok(resized)
+    }
+
+    let template = resized(template, scale)?;
+    let mut mask = mask.input_array()?.get_mat_def()?;
+    if !mask.empty() {
+        mask = resized(&mask, scale)?;
+    }
+
+    Ok((template, mask))
+}
+
+/// Detects multiple matches like [`detect_template_multiple`] without rescaling `template`.
+fn detect_template_multiple_unscaled<T: ToInputArray + MatTraitConst>(
+    mat: &impl ToInputArray,
+    template: &T,
+    mask: &impl ToInputArray,
+    offset: Point,
+    max_matches: usize,
+    threshold: f64,
 ) -> Vec<Result<(Rect, f64)>> {
     #[inline]
     fn clear_result(result: &mut Mat, loc: Point, template_size: Size) {
@@ -2792,7 +3776,7 @@ fn detect_template_multiple<T: ToInputArray + MatTraitConst>(
     }
 
     let mut result = Mat::default();
-    if let Err(err) = match_template(mat, template, &mut result, TM_CCOEFF_NORMED, &mask) {
+    if let Err(err) = match_template(mat, template, &mut result, TM_CCOEFF_NORMED, mask) {
         error!(target: "detect", "template detection error {err}");
         return vec![];
     }
@@ -3277,6 +4261,218 @@ pub fn to_base64_from_mat(mat: &Mat) -> Result<String> {
     Ok(BASE64_STANDARD.encode(bytes))
 }
 
+/// Identifies a template by the address and size of its pixel data.
+///
+/// The address is only stable for templates kept alive across detections, which most are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct TemplateKey {
+    data: usize,
+    size: (i32, i32),
+}
+
+impl TemplateKey {
+    fn new(template: &impl MatTraitConst) -> Self {
+        Self {
+            data: template.data() as usize,
+            size: (template.cols(), template.rows()),
+        }
+    }
+}
+
+/// The scale a template is matched at relative to its captured size.
+///
+/// The in-game UI scale setting resizes the UI the templates are captured from, so templates must
+/// be resized by the same ratio to keep matching. Each template tracks its own scale because only
+/// some UI elements may be resized.
+#[derive(Debug)]
+struct TemplateScale {
+    scale: f64,
+    /// The template and its mask resized by [`Self::scale`] or [`None`] at the captured size.
+    resized: Option<Arc<(Mat, Mat)>>,
+    /// The [`Instant`] [`TEMPLATE_SCALES`] were last searched.
+    last_search: Option<Instant>,
+}
+
+impl Default for TemplateScale {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            resized: None,
+            last_search: None,
+        }
+    }
+}
+
+impl TemplateScale {
+    /// Whether [`TEMPLATE_SCALES`] can be searched now.
+    ///
+    /// Updates the last search [`Instant`] when `true` is returned.
+    fn should_search(&mut self) -> bool {
+        if self
+            .last_search
+            .is_some_and(|instant| clock::elapsed(instant) < TEMPLATE_SCALE_SEARCH_INTERVAL)
+        {
+            return false;
+        }
+
+        self.last_search = Some(clock::now());
+        true
+    }
+}
+
+/// Number of consecutive failed matches of a [`GameTemplate`] before suggesting a replacement.
+const TEMPLATE_SUGGESTION_MISSES_COUNT: u32 = 30;
+
+/// Minimum match score of a frame region to be suggested as a replacement [`GameTemplate`].
+///
+/// Lower scores mostly match unrelated regions that only share the overall brightness.
+const TEMPLATE_SUGGESTION_MIN_SCORE: f64 = 0.8;
+
+static TEMPLATE_SUGGESTIONS: LazyLock<Mutex<TemplateSuggestions>> = LazyLock::new(Mutex::default);
+
+/// Consecutive misses and suggested replacements of [`GameTemplate`]s.
+#[derive(Debug, Default)]
+struct TemplateSuggestions {
+    misses: HashMap<GameTemplate, u32>,
+    /// The suggested base64 PNG of each template.
+    suggested: HashMap<GameTemplate, String>,
+}
+
+/// Retrieves the suggested replacements for [`GameTemplate`]s that repeatedly failed to match.
+pub fn template_suggestions() -> Vec<TemplateSuggestion> {
+    let mut suggestions = TEMPLATE_SUGGESTIONS
+        .lock()
+        .unwrap()
+        .suggested
+        .iter()
+        .map(|(template, base64)| TemplateSuggestion {
+            template: *template,
+            base64: base64.clone(),
+        })
+        .collect::<Vec<_>>();
+    suggestions.sort_by_key(|suggestion| suggestion.template as usize);
+
+    suggestions
+}
+
+/// Removes the suggested replacement for `template` if any.
+pub fn remove_template_suggestion(template: GameTemplate) {
+    let mut suggestions = TEMPLATE_SUGGESTIONS.lock().unwrap();
+    suggestions.suggested.remove(&template);
+    suggestions.misses.remove(&template);
+}
+
+/// Crops the best matching region of localized `template` as a base64 PNG.
+fn suggest_game_template(
+    grayscale: &Mat,
+    bgr: &Mat,
+    localization: &Localization,
+    template: GameTemplate,
+) -> Result<String> {
+    let (base64, default, is_grayscale) = localized_game_template(template, localization);
+    let localized = base64.and_then(|base64| to_mat_from_base64(base64, is_grayscale).ok());
+    let mat = if is_grayscale { grayscale } else { bgr };
+    let (bbox, _) = detect_template_single(
+        mat,
+        localized.as_ref().unwrap_or(default),
+        no_array(),
+        Point::default(),
+        TEMPLATE_SUGGESTION_MIN_SCORE,
+    )?;
+
+    to_base64_from_mat(&mat.roi(bbox)?.clone_pointee())
+}
+
+/// Retrieves the localized base64 PNG, the default `Mat` and whether `template` is grayscale.
+fn localized_game_template(
+    template: GameTemplate,
+    localization: &Localization,
+) -> (Option<&String>, &'static Mat, bool) {
+    match template {
+        GameTemplate::CashShop => (
+            localization.cash_shop_base64.as_ref(),
+            &*CASH_SHOP_TEMPLATE,
+            true,
+        ),
+        GameTemplate::ChangeChannel => (
+            localization.change_channel_base64.as_ref(),
+            &*CHANGE_CHANNEL_TEMPLATE,
+            true,
+        ),
+        GameTemplate::Timer => (localization.timer_base64.as_ref(), &*TIMER_TEMPLATE, true),
+        GameTemplate::PopupConfirm => (
+            localization.popup_confirm_base64.as_ref(),
+            &*POPUP_CONFIRM_TEMPLATE,
+            true,
+        ),
+        GameTemplate::PopupYes => (
+            localization.popup_yes_base64.as_ref(),
+            &*POPUP_YES_TEMPLATE,
+            true,
+        ),
+        GameTemplate::PopupNext => (
+            localization.popup_next_base64.as_ref(),
+            &*POPUP_NEXT_TEMPLATE,
+            true,
+        ),
+        GameTemplate::PopupEndChat => (
+            localization.popup_end_chat_base64.as_ref(),
+            &*POPUP_END_CHAT_TEMPLATE,
+            true,
+        ),
+        GameTemplate::PopupOkNew => (
+            localization.popup_ok_new_base64.as_ref(),
+            &*POPUP_OK_NEW_TEMPLATE,
+            true,
+        ),
+        GameTemplate::PopupOkOld => (
+            localization.popup_ok_old_base64.as_ref(),
+            &*POPUP_OK_OLD_TEMPLATE,
+            true,
+        ),
+        GameTemplate::PopupCancelNew => (
+            localization.popup_cancel_new_base64.as_ref(),
+            &*POPUP_CANCEL_NEW_TEMPLATE,
+            true,
+        ),
+        GameTemplate::PopupCancelOld => (
+            localization.popup_cancel_old_base64.as_ref(),
+            &*POPUP_CANCEL_OLD_TEMPLATE,
+            true,
+        ),
+        GameTemplate::FamiliarsLevelSort => (
+            localization.familiar_level_button_base64.as_ref(),
+            &*FAMILIAR_LEVEL_BUTTON_TEMPLATE,
+            false,
+        ),
+        GameTemplate::FamiliarsSaveButton => (
+            localization.familiar_save_button_base64.as_ref(),
+            &*FAMILIAR_SAVE_BUTTON_TEMPLATE,
+            false,
+        ),
+        GameTemplate::HexaErdaConversionButton => (
+            localization.hexa_erda_conversion_button_base64.as_ref(),
+            &*HEXA_ERDA_CONVERSION_BUTTON_TEMPLATE,
+            false,
+        ),
+        GameTemplate::HexaBoosterButton => (
+            localization.hexa_booster_button_base64.as_ref(),
+            &*HEXA_BOOSTER_BUTTON_TEMPLATE,
+            false,
+        ),
+        GameTemplate::HexaMaxButton => (
+            localization.hexa_max_button_base64.as_ref(),
+            &*HEXA_MAX_BUTTON_TEMPLATE,
+            false,
+        ),
+        GameTemplate::HexaConvertButton => (
+            localization.hexa_convert_button_base64.as_ref(),
+            &*HEXA_CONVERT_BUTTON_TEMPLATE,
+            false,
+        ),
+    }
+}
+
 /// Extracts a borrowed `Mat` from `SessionOutputs`.
 ///
 /// The returned `Mat` has shape `[..dims]` with batch size (1) removed.
@@ -3317,3 +4513,120 @@ fn build_session(model: &[u8]) -> Result<Session> {
         Ok(Session::builder()?.commit_from_memory(model)?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use opencv::core::CV_8UC1;
+
+    use super::*;
+
+    /// Creates a grayscale noise-like template so that it only matches at its own scale.
+    fn noise_template(size: i32) -> Mat {
+        let data = (0..size * size)
+            .map(|i| {
+                let (x, y) = (i % size, i / size);
+                ((x * 37 + y * 91) ^ (x * y * 13)) as u8
+            })
+            .collect::<Vec<_>>();
+        Mat::new_rows_cols_with_data(size, size, &data)
+            .unwrap()
+            .clone_pointee()
+    }
+
+    #[test]
+    fn detect_announcement_in_top_half_only() {
+        let template = noise_template(40);
+        let localization = Localization {
+            announcement_world_boss_base64: Some(to_base64_from_mat(&template).unwrap()),
+            ..Localization::default()
+        };
+        let mut mat = Mat::new_rows_cols_with_default(200, 200, CV_8UC1, Scalar::all(0.0)).unwrap();
+        template
+            .copy_to(&mut mat.roi_mut(Rect::new(80, 20, 40, 40)).unwrap())
+            .unwrap();
+
+        assert!(detect_announcement(
+            &mat,
+            &localization,
+            AnnouncementKind::WorldBoss
+        ));
+        // No template for invasion
+        assert!(!detect_announcement(
+            &mat,
+            &localization,
+            AnnouncementKind::Invasion
+        ));
+
+        let mut mat = Mat::new_rows_cols_with_default(200, 200, CV_8UC1, Scalar::all(0.0)).unwrap();
+        template
+            .copy_to(&mut mat.roi_mut(Rect::new(80, 140, 40, 40)).unwrap())
+            .unwrap();
+        assert!(!detect_announcement(
+            &mat,
+            &localization,
+            AnnouncementKind::WorldBoss
+        ));
+    }
+
+    #[test]
+    fn template_scale_search_rate_limited() {
+        let mut scale = TemplateScale::default();
+
+        assert!(scale.should_search());
+        assert!(!scale.should_search());
+    }
+
+    #[test]
+    fn detect_template_multiple_caches_scale_per_template() {
+        let template = noise_template(40);
+        let other = noise_template(30);
+        let (resized, _) = resize_template_and_mask(&template, &no_array(), 0.7).unwrap();
+        let mut mat = Mat::new_rows_cols_with_default(120, 120, CV_8UC1, Scalar::all(0.0)).unwrap();
+        resized
+            .copy_to(&mut mat.roi_mut(Rect::new(50, 60, 28, 28)).unwrap())
+            .unwrap();
+
+        let matches =
+            detect_template_multiple(&mat, &template, no_array(), Point::default(), 1, 0.95);
+        assert_eq!(matches[0].as_ref().unwrap().0, Rect::new(50, 60, 28, 28));
+
+        let cache = TEMPLATE_SCALES_CACHE.lock().unwrap();
+        let cached = cache.get(&TemplateKey::new(&template)).unwrap();
+        assert_eq!(cached.scale, 0.7);
+        assert_eq!(cached.resized.as_ref().unwrap().0.cols(), 28);
+        assert!(
+            cache
+                .get(&TemplateKey::new(&other))
+                .is_none_or(|cached| cached.scale == 1.0)
+        );
+        drop(cache);
+
+        // Matches at the cached scale without searching again
+        let matches =
+            detect_template_multiple(&mat, &template, no_array(), Point::default(), 1, 0.95);
+        assert!(matches[0].is_ok());
+    }
+
+    #[test]
+    fn to_screen_region_around_player() {
+        let size = Size::new(1280, 720);
+        let minimap = Rect::new(0, 0, 200, 100);
+        let player = Point::new(100, 50);
+
+        // The whole minimap covers the whole screen
+        assert_eq!(
+            to_screen_region(Rect::new(0, 0, 200, 100), minimap, player, size),
+            Some(Rect::new(0, 0, 1280, 720))
+        );
+        // A small bound around the player covers the middle of the screen
+        assert_eq!(
+            to_screen_region(Rect::new(90, 40, 20, 20), minimap, player, size),
+            Some(Rect::new(408, 20, 464, 680))
+        );
+        // A bound far away from the player is off screen
+        assert_eq!(
+            to_screen_region(Rect::new(0, 0, 10, 10), minimap, Point::new(190, 50), size),
+            None
+        );
+    }
+}
@@ -4,13 +4,23 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::Arc;
 
+use strum::IntoEnumIterator;
+
 use crate::services::Event;
 #[cfg(test)]
 use crate::{Settings, bridge::MockInput, detect::MockDetector};
 use crate::{
-    bridge::Input, buff::BuffEntities, detect::Detector, minimap::MinimapEntity,
-    notification::DiscordNotification, operation::Operation, player::PlayerEntity, rng::Rng,
-    skill::SkillEntities,
+    bridge::Input,
+    buff::{Buff, BuffContext, BuffEntities, BuffEntity, BuffKind},
+    detect::Detector,
+    minimap::{Minimap, MinimapContext, MinimapEntity},
+    models::AnnouncementKind,
+    notification::DiscordNotification,
+    operation::Operation,
+    player::{Player, PlayerContext, PlayerEntity},
+    replay::Replay,
+    rng::Rng,
+    skill::{Skill, SkillContext, SkillEntities, SkillEntity, SkillKind},
 };
 #[cfg(debug_assertions)]
 use crate::{debug::save_rune_for_training, detect::ArrowsComplete};
@@ -106,6 +116,10 @@ pub struct Debug {
     auto_save: RefCell<bool>,
     last_rune_detector: RefCell<Option<Arc<dyn Detector>>>,
     last_rune_result: RefCell<Option<ArrowsComplete>>,
+    /// Whether the world systems only advance through [`Self::step`].
+    stepping: RefCell<bool>,
+    /// Number of ticks the world systems can still advance while stepping.
+    pending_steps: RefCell<u32>,
 }
 
 #[cfg(debug_assertions)]
@@ -136,6 +150,38 @@ impl Debug {
         *self.last_rune_detector.borrow_mut() = Some(detector);
         *self.last_rune_result.borrow_mut() = Some(result);
     }
+
+    pub fn is_stepping(&self) -> bool {
+        *self.stepping.borrow()
+    }
+
+    pub fn set_stepping(&self, stepping: bool) {
+        *self.stepping.borrow_mut() = stepping;
+        *self.pending_steps.borrow_mut() = 0;
+    }
+
+    /// Allows the world systems to advance `ticks` more ticks while stepping.
+    pub fn step(&self, ticks: u32) {
+        if self.is_stepping() {
+            *self.pending_steps.borrow_mut() += ticks;
+        }
+    }
+
+    /// Whether the world systems can advance for the current tick.
+    ///
+    /// While stepping, this consumes one of the pending steps.
+    pub fn consume_tick(&self) -> bool {
+        if !self.is_stepping() {
+            return true;
+        }
+
+        let mut pending = self.pending_steps.borrow_mut();
+        if *pending == 0 {
+            return false;
+        }
+        *pending -= 1;
+        true
+    }
 }
 
 /// A struct containing shared resources.
@@ -158,6 +204,14 @@ pub struct Resources {
     pub operation: Operation,
     /// A resource indicating current tick.
     pub tick: u64,
+    /// A resource indicating the number of ticks elapsed since the previous update.
+    ///
+    /// This is more than one while the main loop is throttled by the adaptive tick rate.
+    pub tick_step: u32,
+    /// A resource for recording and replaying sessions.
+    pub replay: Replay,
+    /// A resource indicating whether the world systems are paused for the user to take over.
+    pub input_paused: bool,
 }
 
 impl Resources {
@@ -172,6 +226,9 @@ impl Resources {
             detector: detector.map(|detector| Arc::new(detector) as Arc<dyn Detector>),
             operation: Operation::Running,
             tick: 0,
+            tick_step: 1,
+            replay: Replay::default(),
+            input_paused: false,
         }
     }
 
@@ -204,10 +261,14 @@ pub enum WorldEvent {
     CycledToHalt,
     CycledToRun,
     PlayerDied,
+    PlayerStuck,
     MinimapChanged,
+    /// The minimap is detected again after [`WorldEvent::MinimapChanged`].
+    MinimapDetected,
     CaptureFailed,
     LieDetectorAppeared,
     EliteBossAppeared,
+    AnnouncementAppeared(AnnouncementKind),
 }
 
 impl Event for WorldEvent {}
@@ -220,3 +281,41 @@ pub struct World {
     pub skills: SkillEntities,
     pub buffs: BuffEntities,
 }
+
+impl Default for World {
+    fn default() -> Self {
+        let minimap = MinimapEntity {
+            state: Minimap::Detecting,
+            context: MinimapContext::default(),
+        };
+        let player = PlayerEntity {
+            state: Player::Idle,
+            context: PlayerContext::default(),
+        };
+        let skills = SkillKind::iter()
+            .map(SkillContext::new)
+            .map(|context| SkillEntity {
+                state: Skill::Detecting,
+                context,
+            })
+            .collect::<Vec<_>>()
+            .try_into()
+            .expect("matching size");
+        let buffs = BuffKind::iter()
+            .map(BuffContext::new)
+            .map(|context| BuffEntity {
+                state: Buff::No,
+                context,
+            })
+            .collect::<Vec<_>>()
+            .try_into()
+            .expect("matching size");
+
+        Self {
+            minimap,
+            player,
+            skills,
+            buffs,
+        }
+    }
+}
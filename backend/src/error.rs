@@ -0,0 +1,117 @@
+use std::{
+    fmt, mem,
+    sync::{LazyLock, Mutex},
+};
+
+use log::error;
+use tokio::sync::broadcast::{Receiver, Sender, channel, error::SendError};
+
+/// Maximum number of [`BackendError`]s kept while there is no subscriber.
+const MAX_UNDELIVERED_ERRORS: usize = 10;
+
+static ERROR: LazyLock<Sender<BackendError>> = LazyLock::new(|| channel(10).0);
+static UNDELIVERED_ERRORS: Mutex<Vec<BackendError>> = Mutex::new(Vec::new());
+
+/// A backend failure that needs the user attention.
+#[derive(Clone, PartialEq, Debug)]
+pub enum BackendError {
+    /// Frames could not be captured from the game window.
+    CaptureFailed,
+    /// The game window handle is no longer valid.
+    CaptureHandleLost,
+    /// A model at `path` could not be loaded.
+    ModelLoadFailed { path: String, reason: String },
+    /// The ONNX Runtime library at `path` could not be loaded.
+    RuntimeLoadFailed { path: String, reason: String },
+    /// A database operation failed.
+    Database(String),
+}
+
+impl BackendError {
+    /// A short hint on what the user can do to resolve this error.
+    pub fn action(&self) -> &'static str {
+        match self {
+            BackendError::CaptureFailed => {
+                "Make sure the game window is visible or try another capture mode."
+            }
+            BackendError::CaptureHandleLost => "Select the game window again in Settings.",
+            BackendError::ModelLoadFailed { .. } => "Check that the model path is a valid file.",
+            BackendError::RuntimeLoadFailed { .. } => {
+                "Make sure the ONNX Runtime library is next to the executable."
+            }
+            BackendError::Database(_) => "Check the logs or restore a database backup.",
+        }
+    }
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::CaptureFailed => write!(f, "Failed to capture the game window"),
+            BackendError::CaptureHandleLost => write!(f, "Game window handle lost"),
+            BackendError::ModelLoadFailed { path, reason } => {
+                write!(f, "Failed to load model {path}: {reason}")
+            }
+            BackendError::RuntimeLoadFailed { path, reason } => {
+                write!(f, "Failed to load ONNX Runtime {path}: {reason}")
+            }
+            BackendError::Database(reason) => write!(f, "Database error: {reason}"),
+        }
+    }
+}
+
+/// Subscribes to [`BackendError`] reported by the backend.
+pub fn error_receiver() -> Receiver<BackendError> {
+    ERROR.subscribe()
+}
+
+/// Takes the [`BackendError`]s reported while there was no subscriber (e.g. during startup).
+pub fn take_undelivered_errors() -> Vec<BackendError> {
+    mem::take(&mut *UNDELIVERED_ERRORS.lock().unwrap())
+}
+
+/// Logs and broadcasts `error` to all subscribers.
+///
+/// The error is kept for [`take_undelivered_errors`] if there is no subscriber.
+pub fn report_error(target: &str, error: BackendError) {
+    error!(target: target, "{error}");
+    if let Err(SendError(error)) = ERROR.send(error) {
+        push_undelivered_error(&mut UNDELIVERED_ERRORS.lock().unwrap(), error);
+    }
+}
+
+#[inline]
+fn push_undelivered_error(errors: &mut Vec<BackendError>, error: BackendError) {
+    if errors.len() < MAX_UNDELIVERED_ERRORS {
+        errors.push(error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_error_broadcasts_to_receiver() {
+        let mut rx = error_receiver();
+
+        report_error("test", BackendError::Database("disk full".to_string()));
+
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            BackendError::Database("disk full".to_string())
+        );
+    }
+
+    #[test]
+    fn push_undelivered_error_keeps_oldest_up_to_max() {
+        let mut errors = Vec::new();
+
+        for i in 0..MAX_UNDELIVERED_ERRORS + 1 {
+            push_undelivered_error(&mut errors, BackendError::Database(i.to_string()));
+        }
+
+        assert_eq!(errors.len(), MAX_UNDELIVERED_ERRORS);
+        assert_eq!(errors[0], BackendError::Database("0".to_string()));
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    collections::VecDeque,
+    env,
+    fmt::Write as _,
+    fs,
+    mem::{self, Discriminant},
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+};
+
+use anyhow::Result;
+use log::error;
+use opencv::core::Point;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+use crate::{ecs::World, minimap::Minimap, player::Player, rotator::unix_millis_now};
+
+/// Maximum number of [`SessionEvent`]s kept for the current session.
+const MAX_EVENTS_COUNT: usize = 200_000;
+
+const CSV_HEADER: &str =
+    "tick,timestamp_millis,kind,player_state,position_x,position_y,action_id,detail";
+
+static EVENT_LOG_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
+    env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("event_logs")
+});
+
+static EVENTS: LazyLock<Mutex<VecDeque<SessionEvent>>> = LazyLock::new(Mutex::default);
+
+/// The file format of an exported event log.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum EventLogFormat {
+    /// One JSON object per line.
+    #[default]
+    Jsonl,
+    /// Comma-separated values with a header row.
+    Csv,
+}
+
+/// The kind of a [`SessionEvent`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display, Serialize, Deserialize)]
+pub enum SessionEventKind {
+    /// The player transitioned to another state.
+    StateTransition,
+    /// The player started executing another action or finished all actions.
+    Action,
+    /// The minimap or a rune was detected or lost.
+    Detection,
+    /// The player moved to another position.
+    Position,
+}
+
+/// An event that happened while the bot was running in the current session.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SessionEvent {
+    /// The game tick the event happened at.
+    pub tick: u64,
+    /// The wall-clock time in milliseconds since UNIX epoch the event happened at.
+    pub timestamp_millis: u64,
+    pub kind: SessionEventKind,
+    /// The player state at the time of the event.
+    pub player_state: String,
+    /// The player position in minimap coordinates if known.
+    pub position_x: Option<i32>,
+    pub position_y: Option<i32>,
+    /// The id of the action the player was executing if any.
+    pub action_id: Option<u32>,
+    /// The details of the event (e.g. what was detected).
+    pub detail: String,
+}
+
+/// Records [`SessionEvent`]s by comparing the [`World`] between ticks.
+#[derive(Debug, Default)]
+pub struct SessionEventRecorder {
+    running: bool,
+    player_state: Option<Discriminant<Player>>,
+    action_id: Option<u32>,
+    minimap_idle: bool,
+    rune: Option<Point>,
+    position: Option<Point>,
+}
+
+impl SessionEventRecorder {
+    /// Records the changes of `world` since the last update at game `tick`.
+    ///
+    /// Events are only recorded while not `halting`. The previous session events are cleared
+    /// when the bot starts running again.
+    pub fn update(&mut self, tick: u64, world: &World, halting: bool) {
+        if halting {
+            self.running = false;
+            return;
+        }
+        if !self.running {
+            *self = Self {
+                running: true,
+                ..Self::default()
+            };
+            EVENTS.lock().unwrap().clear();
+        }
+
+        let player_state = world.player.state.to_string();
+        let position = world.player.context.last_known_pos;
+        let action_id = world
+            .player
+            .context
+            .priority_action_id()
+            .or(world.player.context.normal_action_id());
+        let event = |kind, detail: String| SessionEvent {
+            tick,
+            timestamp_millis: unix_millis_now(),
+            kind,
+            player_state: player_state.clone(),
+            position_x: position.map(|point| point.x),
+            position_y: position.map(|point| point.y),
+            action_id,
+            detail,
+        };
+        let mut events = Vec::new();
+
+        let discriminant = mem::discriminant(&world.player.state);
+        if self.player_state != Some(discriminant) {
+            self.player_state = Some(discriminant);
+            events.push(event(SessionEventKind::StateTransition, String::new()));
+        }
+
+        if self.action_id != action_id {
+            self.action_id = action_id;
+            let detail = if action_id.is_some() {
+                String::new()
+            } else {
+                "No action".to_string()
+            };
+            events.push(event(SessionEventKind::Action, detail));
+        }
+
+        let (minimap_idle, rune) = match world.minimap.state {
+            Minimap::Detecting => (false, None),
+            Minimap::Idle(idle) => (true, idle.rune()),
+        };
+        if self.minimap_idle != minimap_idle {
+            self.minimap_idle = minimap_idle;
+            let detail = if minimap_idle {
+                "Minimap detected"
+            } else {
+                "Minimap lost"
+            };
+            events.push(event(SessionEventKind::Detection, detail.to_string()));
+        }
+        if self.rune != rune {
+            self.rune = rune;
+            let detail = match rune {
+                Some(rune) => format!("Rune detected at {}, {}", rune.x, rune.y),
+                None => "Rune lost".to_string(),
+            };
+            events.push(event(SessionEventKind::Detection, detail));
+        }
+
+        if self.position != position {
+            self.position = position;
+            events.push(event(SessionEventKind::Position, String::new()));
+        }
+
+        if !events.is_empty() {
+            push_events(&mut EVENTS.lock().unwrap(), events);
+        }
+    }
+}
+
+/// Exports the current session events to a new file in `format`.
+///
+/// Returns the path of the exported file or [`None`] if the export failed.
+pub fn export_session_events(format: EventLogFormat) -> Option<String> {
+    let events = EVENTS.lock().unwrap().iter().cloned().collect::<Vec<_>>();
+
+    match export_session_events_to(&EVENT_LOG_DIR, &events, format) {
+        Ok(path) => Some(path.to_string_lossy().to_string()),
+        Err(err) => {
+            error!(target: "event_log", "failed to export event log {err}");
+            None
+        }
+    }
+}
+
+fn export_session_events_to(
+    dir: &Path,
+    events: &[SessionEvent],
+    format: EventLogFormat,
+) -> Result<PathBuf> {
+    let (extension, content) = match format {
+        EventLogFormat::Jsonl => ("jsonl", to_jsonl(events)?),
+        EventLogFormat::Csv => ("csv", to_csv(events)),
+    };
+    fs::create_dir_all(dir)?;
+
+    let path = dir.join(format!("session_{}.{extension}", unix_millis_now()));
+    fs::write(&path, content)?;
+
+    Ok(path)
+}
+
+fn push_events(log: &mut VecDeque<SessionEvent>, events: Vec<SessionEvent>) {
+    log.extend(events);
+    while log.len() > MAX_EVENTS_COUNT {
+        log.pop_front();
+    }
+}
+
+fn to_jsonl(events: &[SessionEvent]) -> Result<String> {
+    let mut content = String::new();
+    for event in events {
+        content.push_str(&serde_json::to_string(event)?);
+        content.push('\n');
+    }
+
+    Ok(content)
+}
+
+fn to_csv(events: &[SessionEvent]) -> String {
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    let mut content = String::from(CSV_HEADER);
+    content.push('\n');
+
+    for event in events {
+        let _ = writeln!(
+            content,
+            "{},{},{},{},{},{},{},{}",
+            event.tick,
+            event.timestamp_millis,
+            event.kind,
+            csv_field(&event.player_state),
+            optional(event.position_x.map(|x| x.to_string())),
+            optional(event.position_y.map(|y| y.to_string())),
+            optional(event.action_id.map(|id| id.to_string())),
+            csv_field(&event.detail),
+        );
+    }
+
+    content
+}
+
+/// Quotes `value` if it contains characters that need escaping in CSV.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(tick: u64, detail: &str) -> SessionEvent {
+        SessionEvent {
+            tick,
+            timestamp_millis: 1000,
+            kind: SessionEventKind::Detection,
+            player_state: "Idle".to_string(),
+            position_x: Some(10),
+            position_y: None,
+            action_id: None,
+            detail: detail.to_string(),
+        }
+    }
+
+    #[test]
+    fn to_csv_escapes_fields() {
+        let csv = to_csv(&[event(1, "Rune detected at 1, 2"), event(2, "say \"hi\"")]);
+
+        assert_eq!(
+            csv,
+            format!(
+                "{CSV_HEADER}\n\
+                 1,1000,Detection,Idle,10,,,\"Rune detected at 1, 2\"\n\
+                 2,1000,Detection,Idle,10,,,\"say \"\"hi\"\"\"\n"
+            )
+        );
+    }
+
+    #[test]
+    fn push_events_keeps_latest() {
+        let mut log = VecDeque::from_iter((0..MAX_EVENTS_COUNT as u64).map(|i| event(i, "")));
+
+        push_events(&mut log, vec![event(MAX_EVENTS_COUNT as u64, "")]);
+
+        assert_eq!(log.len(), MAX_EVENTS_COUNT);
+        assert_eq!(log.front().unwrap().tick, 1);
+        assert_eq!(log.back().unwrap().tick, MAX_EVENTS_COUNT as u64);
+    }
+
+    #[test]
+    fn export_session_events_to_jsonl() {
+        let dir = env::temp_dir().join(format!("komari_event_log_{}", unix_millis_now()));
+        let events = [event(1, "Minimap detected")];
+
+        let path = export_session_events_to(&dir, &events, EventLogFormat::Jsonl).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(
+            serde_json::from_str::<SessionEvent>(content.trim_end()).unwrap(),
+            events[0]
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::cell::Cell;
+
+use log::debug;
+use platforms::Window;
+
+use crate::{
+    FocusPolicy,
+    tick::{self, ticks_from_millis},
+};
+
+/// Number of ticks to wait before retrying to bring the game window to the foreground.
+const FOCUS_RETRY_TICKS: u32 = 30;
+
+/// The current focus state of the game window.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum FocusState {
+    Focused,
+    Unfocused,
+    /// The game window has just regained focus and inputs are held for the remaining ticks.
+    Grace(u32),
+}
+
+/// Tracks the game window focus and decides whether inputs can be sent per [`FocusPolicy`].
+#[derive(Debug)]
+pub struct Focus {
+    /// The game window to track.
+    ///
+    /// Is [`None`] when the inputs are not sent directly to a known window (e.g. area capture).
+    window: Option<Window>,
+    policy: FocusPolicy,
+    grace_ticks: u32,
+    state: FocusState,
+    /// Number of ticks left before another focus attempt can be made.
+    focus_retry_ticks: Cell<u32>,
+}
+
+impl Default for Focus {
+    fn default() -> Self {
+        Self {
+            window: None,
+            policy: FocusPolicy::None,
+            grace_ticks: 0,
+            state: FocusState::Focused,
+            focus_retry_ticks: Cell::new(0),
+        }
+    }
+}
+
+impl Focus {
+    pub fn set_window(&mut self, window: Option<Window>) {
+        self.window = window;
+        self.state = FocusState::Focused;
+    }
+
+    pub fn set_policy(&mut self, policy: FocusPolicy, grace_period_millis: u64) {
+        self.policy = policy;
+        self.grace_ticks = ticks_from_millis(grace_period_millis);
+        self.state = FocusState::Focused;
+    }
+
+    /// Updates the focus state from the current foreground window.
+    pub fn update(&mut self) {
+        if matches!(self.policy, FocusPolicy::None) {
+            return;
+        }
+        let Some(window) = self.window else {
+            return;
+        };
+
+        let foreground = window
+            .is_foreground()
+            .inspect_err(|err| {
+                debug!(target: "focus", "failed to check game window foreground {err}");
+            })
+            .unwrap_or_default();
+        let state = next_focus_state(self.state, foreground, self.grace_ticks);
+        if state != self.state {
+            debug!(target: "focus", "game window focus changed {:?} -> {state:?}", self.state);
+        }
+        self.state = state;
+        self.focus_retry_ticks
+            .set(self.focus_retry_ticks.get().saturating_sub(1));
+    }
+
+    /// Whether inputs can be sent to the game window.
+    ///
+    /// With [`FocusPolicy::AutoFocus`], this also attempts to bring the game window to the
+    /// foreground when it is not focused. The input is still refused until the grace period ends.
+    pub fn can_send_input(&self) -> bool {
+        if matches!(self.policy, FocusPolicy::None) || self.window.is_none() {
+            return true;
+        }
+
+        if matches!(self.state, FocusState::Unfocused)
+            && matches!(self.policy, FocusPolicy::AutoFocus)
+            && self.focus_retry_ticks.get() == 0
+        {
+            self.focus_retry_ticks.set(tick::scaled(FOCUS_RETRY_TICKS));
+            let _ = self.window.expect("has window").focus().inspect_err(|err| {
+                debug!(target: "focus", "failed to focus game window {err}");
+            });
+        }
+
+        matches!(self.state, FocusState::Focused)
+    }
+}
+
+#[inline]
+fn next_focus_state(state: FocusState, foreground: bool, grace_ticks: u32) -> FocusState {
+    if !foreground {
+        return FocusState::Unfocused;
+    }
+
+    match state {
+        FocusState::Focused | FocusState::Grace(0) => FocusState::Focused,
+        FocusState::Unfocused if grace_ticks == 0 => FocusState::Focused,
+        FocusState::Unfocused => FocusState::Grace(grace_ticks),
+        FocusState::Grace(ticks) => FocusState::Grace(ticks - 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_focus_state_unfocused_when_not_foreground() {
+        assert_eq!(
+            next_focus_state(FocusState::Focused, false, 10),
+            FocusState::Unfocused
+        );
+        assert_eq!(
+            next_focus_state(FocusState::Grace(5), false, 10),
+            FocusState::Unfocused
+        );
+    }
+
+    #[test]
+    fn next_focus_state_grace_period_before_focused() {
+        let mut state = next_focus_state(FocusState::Unfocused, true, 2);
+        assert_eq!(state, FocusState::Grace(2));
+
+        state = next_focus_state(state, true, 2);
+        assert_eq!(state, FocusState::Grace(1));
+        state = next_focus_state(state, true, 2);
+        assert_eq!(state, FocusState::Grace(0));
+        state = next_focus_state(state, true, 2);
+        assert_eq!(state, FocusState::Focused);
+    }
+
+    #[test]
+    fn next_focus_state_no_grace_period() {
+        assert_eq!(
+            next_focus_state(FocusState::Unfocused, true, 0),
+            FocusState::Focused
+        );
+    }
+
+    #[test]
+    fn can_send_input_policy_none() {
+        let focus = Focus {
+            state: FocusState::Unfocused,
+            ..Focus::default()
+        };
+
+        assert!(focus.can_send_input());
+    }
+
+    #[test]
+    fn can_send_input_require_focused() {
+        let mut focus = Focus {
+            window: Some(Window::new("Handle")),
+            ..Focus::default()
+        };
+        focus.set_policy(FocusPolicy::RequireFocused, 1000);
+        assert!(focus.can_send_input());
+
+        focus.state = FocusState::Grace(3);
+        assert!(!focus.can_send_input());
+
+        focus.state = FocusState::Unfocused;
+        assert!(!focus.can_send_input());
+    }
+}
@@ -0,0 +1,437 @@
+//! Imports maps and characters from legacy JSON exports.
+//!
+//! A legacy export is either a single exported [`Map`] or [`Character`], an array of them or an
+//! object with `maps` and `characters` arrays. Each record is first upgraded by the table
+//! migrations. Top-level fields missing from the record are then filled with the model defaults
+//! and unknown fields are ignored. Records replace the existing ones with the same name.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+use log::info;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use strum::Display;
+
+use crate::{
+    database,
+    models::{Character, Map},
+};
+
+/// The kind of a [`LegacyImportRecord`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display)]
+pub enum LegacyRecordKind {
+    Map,
+    Character,
+}
+
+/// The result of converting a single legacy record.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LegacyImportRecord {
+    /// The kind of the record or [`None`] if it is not recognized.
+    pub kind: Option<LegacyRecordKind>,
+    pub name: String,
+    /// Whether the record replaces an existing one with the same name.
+    pub replacing: bool,
+    /// The human-readable changes made to the existing record and the ignored legacy fields.
+    pub changes: Vec<String>,
+    /// The reason the record cannot be converted if any.
+    pub error: Option<String>,
+}
+
+/// The report of a legacy import.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct LegacyImportReport {
+    pub records: Vec<LegacyImportRecord>,
+    /// Whether the records were written to the database.
+    ///
+    /// This is always `false` for a dry run or when any of the records cannot be converted.
+    pub committed: bool,
+}
+
+#[derive(Debug)]
+enum LegacyRecord {
+    Map(Map),
+    Character(Character),
+}
+
+/// Converts the legacy JSON export `content` and writes the records to the database unless
+/// `dry_run`.
+///
+/// Nothing is written if any of the records cannot be converted or written. The database is
+/// backed up before writing all records in a single transaction.
+pub fn import_legacy(content: &str, dry_run: bool) -> Result<LegacyImportReport> {
+    let value = serde_json::from_str::<Value>(content)?;
+    let maps = database::query_maps()?;
+    let characters = database::query_characters()?;
+    let converted = convert_legacy(value, &maps, &characters)?;
+
+    let mut report = LegacyImportReport {
+        records: converted.iter().map(|(record, _)| record.clone()).collect(),
+        committed: false,
+    };
+    if dry_run || report.records.iter().any(|record| record.error.is_some()) {
+        return Ok(report);
+    }
+
+    let mut maps = vec![];
+    let mut characters = vec![];
+    for record in converted.into_iter().filter_map(|(_, record)| record) {
+        match record {
+            LegacyRecord::Map(map) => maps.push(map),
+            LegacyRecord::Character(character) => characters.push(character),
+        }
+    }
+    database::backup_database()?;
+    database::upsert_maps_and_characters(&mut maps, &mut characters)?;
+    info!(target: "legacy", "imported {} legacy records", report.records.len());
+    report.committed = true;
+
+    Ok(report)
+}
+
+fn convert_legacy(
+    value: Value,
+    maps: &[Map],
+    characters: &[Character],
+) -> Result<Vec<(LegacyImportRecord, Option<LegacyRecord>)>> {
+    let mut converted = Vec::new();
+
+    for value in legacy_values(value)? {
+        let kind = match legacy_record_kind(&value) {
+            Ok(kind) => kind,
+            Err(err) => {
+                converted.push((
+                    LegacyImportRecord {
+                        kind: None,
+                        name: legacy_name(&value),
+                        replacing: false,
+                        changes: vec![],
+                        error: Some(err.to_string()),
+                    },
+                    None,
+                ));
+                continue;
+            }
+        };
+        let name = legacy_name(&value);
+
+        let result = match kind {
+            LegacyRecordKind::Map => {
+                convert_record(value, database::upgrade_legacy_map, |mut map: Map| {
+                    let existing = maps.iter().find(|existing| existing.name == map.name);
+                    map.id = existing.and_then(|existing| existing.id);
+                    (existing.cloned(), LegacyRecord::Map(map))
+                })
+            }
+            LegacyRecordKind::Character => convert_record(
+                value,
+                database::upgrade_legacy_character,
+                |mut character: Character| {
+                    let existing = characters
+                        .iter()
+                        .find(|existing| existing.name == character.name);
+                    character.id = existing.and_then(|existing| existing.id);
+                    (existing.cloned(), LegacyRecord::Character(character))
+                },
+            ),
+        };
+        converted.push(match result {
+            Ok((replacing, changes, record)) => (
+                LegacyImportRecord {
+                    kind: Some(kind),
+                    name,
+                    replacing,
+                    changes,
+                    error: None,
+                },
+                Some(record),
+            ),
+            Err(err) => (
+                LegacyImportRecord {
+                    kind: Some(kind),
+                    name,
+                    replacing: false,
+                    changes: vec![],
+                    error: Some(err.to_string()),
+                },
+                None,
+            ),
+        });
+    }
+
+    Ok(converted)
+}
+
+/// Converts a single legacy record `value` into `T`.
+///
+/// `upgrade` is the table migrations and `resolve` finds the existing `T` with the same name.
+/// Returns whether an existing record is replaced, the changes and the converted record.
+fn convert_record<T>(
+    mut value: Value,
+    upgrade: fn(&mut Value) -> Result<()>,
+    resolve: impl FnOnce(T) -> (Option<T>, LegacyRecord),
+) -> Result<(bool, Vec<String>, LegacyRecord)>
+where
+    T: Serialize + DeserializeOwned + Default + PartialEq,
+{
+    upgrade(&mut value)?;
+    if let Some(object) = value.as_object_mut() {
+        object.remove("id");
+    }
+    let model = deserialize_with_defaults::<T>(&value)?;
+    let ignored = ignored_fields::<T>(&value, &model);
+
+    let (existing, record) = resolve(model);
+    let converted = match &record {
+        LegacyRecord::Map(map) => serde_json::to_value(map)?,
+        LegacyRecord::Character(character) => serde_json::to_value(character)?,
+    };
+    let mut changes = match &existing {
+        Some(existing) => diff(&serde_json::to_value(existing)?, &converted),
+        None => vec![],
+    };
+    changes.extend(
+        ignored
+            .into_iter()
+            .map(|field| format!("ignored unknown field `{field}`")),
+    );
+
+    Ok((existing.is_some(), changes, record))
+}
+
+/// Deserializes `value` into `T` and falls back to filling the missing top-level fields with
+/// the defaults of `T` if that fails.
+fn deserialize_with_defaults<T>(value: &Value) -> Result<T>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    if let Ok(model) = serde_json::from_value::<T>(value.clone()) {
+        return Ok(model);
+    }
+
+    let mut filled = value.clone();
+    if let (Some(object), Value::Object(defaults)) =
+        (filled.as_object_mut(), serde_json::to_value(T::default())?)
+    {
+        for (key, default) in defaults {
+            object.entry(key).or_insert(default);
+        }
+    }
+
+    Ok(serde_json::from_value::<T>(filled)?)
+}
+
+/// Finds the top-level fields of `value` that do not contribute to the converted `model`.
+fn ignored_fields<T>(value: &Value, model: &T) -> Vec<String>
+where
+    T: Serialize + DeserializeOwned + Default + PartialEq,
+{
+    let Some(object) = value.as_object() else {
+        return vec![];
+    };
+    let Ok(Value::Object(converted)) = serde_json::to_value(model) else {
+        return vec![];
+    };
+
+    object
+        .keys()
+        .filter(|key| !converted.contains_key(*key))
+        .filter(|key| {
+            // Aliased fields do not appear in the converted model but still contribute to it
+            let mut without = object.clone();
+            without.remove(*key);
+            deserialize_with_defaults::<T>(&Value::Object(without))
+                .is_ok_and(|without| &without == model)
+        })
+        .cloned()
+        .collect()
+}
+
+fn legacy_values(value: Value) -> Result<Vec<Value>> {
+    match value {
+        Value::Array(values) => Ok(values),
+        Value::Object(mut object)
+            if object.contains_key("maps") || object.contains_key("characters") =>
+        {
+            let mut values = Vec::new();
+            for key in ["maps", "characters"] {
+                match object.remove(key) {
+                    Some(Value::Array(records)) => values.extend(records),
+                    Some(_) => bail!("`{key}` is not an array"),
+                    None => (),
+                }
+            }
+            Ok(values)
+        }
+        Value::Object(_) => Ok(vec![value]),
+        _ => bail!("export is not a JSON object or array"),
+    }
+}
+
+fn legacy_record_kind(value: &Value) -> Result<LegacyRecordKind> {
+    const MAP_FIELDS: [&str; 2] = ["platforms", "actions"];
+    const CHARACTER_FIELDS: [&str; 3] = ["jump_key", "feed_pet_key", "potion_key"];
+
+    let Some(object) = value.as_object() else {
+        bail!("record is not a JSON object");
+    };
+    if MAP_FIELDS.iter().any(|field| object.contains_key(*field)) {
+        Ok(LegacyRecordKind::Map)
+    } else if CHARACTER_FIELDS
+        .iter()
+        .any(|field| object.contains_key(*field))
+    {
+        Ok(LegacyRecordKind::Character)
+    } else {
+        bail!("record is neither a map nor a character")
+    }
+}
+
+fn legacy_name(value: &Value) -> String {
+    value
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Describes the leaf changes from `old` to `new`.
+fn diff(old: &Value, new: &Value) -> Vec<String> {
+    let mut old_leaves = BTreeMap::new();
+    let mut new_leaves = BTreeMap::new();
+    flatten(String::new(), old, &mut old_leaves);
+    flatten(String::new(), new, &mut new_leaves);
+
+    let mut changes = Vec::new();
+    for (path, old) in &old_leaves {
+        match new_leaves.get(path) {
+            Some(new) if new != old => changes.push(format!("changed `{path}`: {old} -> {new}")),
+            Some(_) => (),
+            None => changes.push(format!("removed `{path}`: {old}")),
+        }
+    }
+    for (path, new) in &new_leaves {
+        if !old_leaves.contains_key(path) {
+            changes.push(format!("added `{path}`: {new}"));
+        }
+    }
+
+    changes
+}
+
+fn flatten(path: String, value: &Value, leaves: &mut BTreeMap<String, Value>) {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object {
+                flatten(join(key), value, leaves);
+            }
+        }
+        Value::Array(values) if !values.is_empty() => {
+            for (i, value) in values.iter().enumerate() {
+                flatten(format!("{path}[{i}]"), value, leaves);
+            }
+        }
+        _ => {
+            leaves.insert(path, value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn no_upgrade(_: &mut Value) -> Result<()> {
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_values_accepts_single_array_and_wrapper() {
+        assert_eq!(legacy_values(json!({"name": "a"})).unwrap().len(), 1);
+        assert_eq!(
+            legacy_values(json!([{"name": "a"}, {"name": "b"}]))
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            legacy_values(json!({"maps": [{"name": "a"}], "characters": [{"name": "b"}]}))
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(legacy_values(json!("map")).is_err());
+    }
+
+    #[test]
+    fn legacy_record_kind_detects_maps_and_characters() {
+        assert_eq!(
+            legacy_record_kind(&json!({"platforms": []})).unwrap(),
+            LegacyRecordKind::Map
+        );
+        assert_eq!(
+            legacy_record_kind(&json!({"jump_key": {}})).unwrap(),
+            LegacyRecordKind::Character
+        );
+        assert!(legacy_record_kind(&json!({"name": "a"})).is_err());
+    }
+
+    #[test]
+    fn convert_record_fills_missing_fields_and_reports_ignored() {
+        let existing = Map {
+            id: Some(1),
+            name: "a".to_string(),
+            width: 10,
+            ..Map::default()
+        };
+        let value = json!({"name": "a", "width": 20, "legacy_field": true});
+
+        let (replacing, changes, record) = convert_record(value, no_upgrade, |mut map: Map| {
+            map.id = existing.id;
+            (Some(existing.clone()), LegacyRecord::Map(map))
+        })
+        .unwrap();
+
+        assert!(replacing);
+        assert_eq!(
+            changes,
+            vec![
+                "changed `width`: 10 -> 20".to_string(),
+                "ignored unknown field `legacy_field`".to_string(),
+            ]
+        );
+        let LegacyRecord::Map(map) = record else {
+            panic!("record is not a map");
+        };
+        assert_eq!(map.id, Some(1));
+        assert_eq!(map.width, 20);
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed_leaves() {
+        let changes = diff(
+            &json!({"a": 1, "b": [1, 2], "c": {"d": "x"}}),
+            &json!({"a": 2, "b": [1], "c": {"d": "x", "e": true}}),
+        );
+
+        assert_eq!(
+            changes,
+            vec![
+                "changed `a`: 1 -> 2".to_string(),
+                "removed `b[1]`: 2".to_string(),
+                "added `c.e`: true".to_string(),
+            ]
+        );
+    }
+}
@@ -11,10 +11,11 @@
 
 use std::{
     sync::{LazyLock, Mutex},
+    thread,
     time::{Duration, Instant},
 };
 
-use strum::Display;
+use strum::{Display, EnumIter};
 use tokio::{
     sync::{
         broadcast, mpsc,
@@ -24,15 +25,24 @@ use tokio::{
 };
 
 mod array;
+mod audit;
 mod bridge;
 mod buff;
+mod capability;
+mod clock;
 mod control;
 mod database;
 #[cfg(debug_assertions)]
 mod debug;
 mod detect;
 mod ecs;
+mod error;
+mod event_log;
+mod focus;
+mod legacy;
 mod mat;
+mod metrics;
+mod migration;
 mod minimap;
 mod models;
 mod navigator;
@@ -40,22 +50,49 @@ mod notification;
 mod operation;
 mod pathing;
 mod player;
+mod plugin;
+mod preview;
+mod replay;
 mod rng;
 mod rotator;
+mod route;
 mod rpc;
 mod run;
+#[cfg(test)]
+mod sandbox;
 mod services;
 mod skill;
+mod sync;
 mod task;
+mod tick;
+mod timelapse;
 mod tracker;
 mod utils;
+mod validation;
+mod webhook;
 
 pub use {
+    audit::{InputAudit, InputAuditStroke},
+    capability::{Capability, CapabilityStatus},
     database::{DatabaseEvent, database_event_receiver},
+    error::{BackendError, error_receiver, take_undelivered_errors},
+    event_log::EventLogFormat,
+    legacy::{LegacyImportRecord, LegacyImportReport, LegacyRecordKind},
+    metrics::{TickSystem, TickSystemProfile},
+    minimap::MAX_RUNE_SPAWN_HINTS_COUNT,
     models::*,
     pathing::MAX_PLATFORMS_COUNT,
+    player::{
+        MAX_DANGER_ZONES_COUNT, MAX_LEVEL_UP_AUTO_ASSIGN_KEYS_COUNT, MAX_ROPES_COUNT,
+        StateMachineFormat,
+    },
+    preview::{PreviewFrame, preview_frame},
+    replay::{ReplayMismatch, ReplayStatus},
     run::init,
     strum::{EnumMessage, IntoEnumIterator, ParseError},
+    sync::{SyncDirection, SyncStatus},
+    tick::{MAX_TICK_RATE, MIN_TICK_RATE},
+    validation::{KeyBindingUsage, KeyBindingWarning, MapWarning, validate_key_bindings},
 };
 
 type PendingRequest = (Request, Sender<Response>);
@@ -100,10 +137,14 @@ enum Request {
     UpdateOperation(BotOperationUpdate),
     CreateMap(String),
     UpdateMap(Option<String>, Option<Map>),
+    DetectMapPlatforms,
+    SamplePlayerMarker(i32, i32),
+    MovePlayerTo(i32, i32),
     CreateNavigationPath,
     RecaptureNavigationPath(NavigationPath),
     NavigationSnapshotAsGrayscale(String),
     UpdateCharacter(Option<Character>),
+    CalibrateMovement,
     RedetectMinimap,
     GameStateReceiver,
     KeyReceiver,
@@ -113,6 +154,13 @@ enum Request {
     QueryTemplate(GameTemplate),
     ConvertImageToBase64(Vec<u8>, bool),
     SaveCaptureImage(bool),
+    Shutdown,
+    RunGridLayout(GridLayout),
+    StopGridLayout,
+    StartRecordingReplay,
+    StartReplaying(String),
+    StopReplay,
+    QueryReplayStatus,
     #[cfg(debug_assertions)]
     DebugStateReceiver,
     #[cfg(debug_assertions)]
@@ -125,6 +173,10 @@ enum Request {
     RecordImages(bool),
     #[cfg(debug_assertions)]
     TestSpinRune,
+    #[cfg(debug_assertions)]
+    UpdateStepping(bool),
+    #[cfg(debug_assertions)]
+    Step(u32),
 }
 
 /// Represents response to UI [`Request`].
@@ -136,10 +188,14 @@ enum Response {
     UpdateOperation,
     CreateMap(Option<Map>),
     UpdateMap,
+    DetectMapPlatforms(Vec<Platform>),
+    SamplePlayerMarker(Option<PlayerMarker>),
+    MovePlayerTo(bool),
     CreateNavigationPath(Option<NavigationPath>),
     RecaptureNavigationPath(NavigationPath),
     NavigationSnapshotAsGrayscale(String),
     UpdateCharacter,
+    CalibrateMovement,
     RedetectMinimap,
     GameStateReceiver(broadcast::Receiver<GameState>),
     KeyReceiver(broadcast::Receiver<KeyBinding>),
@@ -149,6 +205,13 @@ enum Response {
     QueryTemplate(String),
     ConvertImageToBase64(Option<String>),
     SaveCaptureImage,
+    Shutdown,
+    RunGridLayout(bool),
+    StopGridLayout,
+    StartRecordingReplay(Option<String>),
+    StartReplaying(bool),
+    StopReplay,
+    QueryReplayStatus(ReplayStatus),
     #[cfg(debug_assertions)]
     DebugStateReceiver(broadcast::Receiver<DebugState>),
     #[cfg(debug_assertions)]
@@ -161,9 +224,13 @@ enum Response {
     RecordImages,
     #[cfg(debug_assertions)]
     TestSpinRune,
+    #[cfg(debug_assertions)]
+    UpdateStepping,
+    #[cfg(debug_assertions)]
+    Step,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, EnumIter, Display)]
 pub enum GameTemplate {
     CashShop,
     ChangeChannel,
@@ -184,6 +251,14 @@ pub enum GameTemplate {
     HexaConvertButton,
 }
 
+/// A suggested replacement for a [`GameTemplate`] that repeatedly failed to match.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TemplateSuggestion {
+    pub template: GameTemplate,
+    /// The base64 PNG cropped from the frame the template failed to match.
+    pub base64: String,
+}
+
 /// The four quads of a bound.
 #[derive(Clone, Copy, Debug, Display)]
 pub enum BoundQuadrant {
@@ -199,6 +274,37 @@ pub enum BoundQuadrant {
 pub struct DebugState {
     pub is_recording: bool,
     pub is_rune_auto_saving: bool,
+    /// Whether the main loop only advances by stepping.
+    pub is_stepping: bool,
+    pub tick: u64,
+    pub player_state: String,
+    /// The transition the player has planned next such as the action to start or the destination
+    /// to move to.
+    pub planned_transition: Option<String>,
+    /// Names of the states the current player state can transition to.
+    pub next_states: Vec<String>,
+    /// Names of the actions waiting to be rotated in order.
+    pub queued_actions: Vec<String>,
+}
+
+/// The result of testing a [`GameTemplate`] against a folder of screenshots.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg(debug_assertions)]
+pub struct TemplateTestResult {
+    pub template: GameTemplate,
+    /// File names of the screenshots the template is detected in.
+    pub detected_images: Vec<String>,
+}
+
+/// A report of testing all [`GameTemplate`]s against a folder of screenshots.
+#[derive(Clone, PartialEq, Default, Debug)]
+#[cfg(debug_assertions)]
+pub struct TemplateTestReport {
+    /// Number of screenshots tested.
+    pub images_count: usize,
+    /// The folder the annotated screenshots are saved to.
+    pub annotated_dir: String,
+    pub results: Vec<TemplateTestResult>,
 }
 
 /// A struct for storing game information.
@@ -206,16 +312,26 @@ pub struct DebugState {
 pub struct GameState {
     pub position: Option<(i32, i32)>,
     pub health: Option<(u32, u32)>,
+    /// The recent and session average EXP gain per hour.
+    pub exp_rates: Option<(u64, u64)>,
+    /// Whether the recent EXP gain rate dropped below the alert fraction of the session average.
+    pub exp_rate_dropped: bool,
     pub state: String,
     pub normal_action: Option<String>,
     pub priority_action: Option<String>,
     pub erda_shower_state: String,
     pub destinations: Vec<(i32, i32)>,
+    /// The recent player positions along with their number of visits.
+    pub position_trail: Vec<(i32, i32, u32)>,
+    /// The last recognized pattern of the player being stuck.
+    pub stuck_pattern: Option<String>,
     pub operation: BotOperation,
-    pub frame: Option<(Vec<u8>, usize, usize)>,
+    pub frame: Option<PreviewFrame>,
     pub platforms_bound: Option<Bound>,
     pub portals: Vec<Bound>,
     pub auto_mob_quadrant: Option<BoundQuadrant>,
+    pub map_id: Option<i64>,
+    pub character_id: Option<i64>,
 }
 
 #[derive(PartialEq, Clone, Copy, Debug)]
@@ -225,12 +341,15 @@ pub enum BotOperation {
     HaltUntil(Instant),
     Running,
     RunUntil(Instant),
+    Stopping,
 }
 
 #[derive(PartialEq, Clone, Copy, Debug)]
 pub enum BotOperationUpdate {
     Halt,
     TemporaryHalt,
+    /// Halts after the player finishes the currently executing action.
+    SoftHalt,
     Run,
 }
 
@@ -239,11 +358,28 @@ pub async fn update_operation(update: BotOperationUpdate) {
     send_request!(UpdateOperation(update))
 }
 
-/// Queries localization from the database.
+/// Queries the statuses of features depending on optional detection resources.
+///
+/// A feature is disabled if any of its required resources is missing.
+pub async fn query_capabilities() -> Vec<CapabilityStatus> {
+    capability::capability_statuses()
+}
+
+/// Queries the currently in use localization profile from the database.
 pub async fn query_localization() -> Localization {
-    spawn_blocking(database::query_or_upsert_localization)
+    spawn_blocking(|| {
+        database::query_or_upsert_localization(database::query_settings().localization_id)
+    })
+    .await
+    .unwrap()
+}
+
+/// Queries all localization profiles from the database.
+pub async fn query_localizations() -> Option<Vec<Localization>> {
+    spawn_blocking(database::query_localizations)
         .await
         .unwrap()
+        .ok()
 }
 
 /// Upserts `localization` to the database.
@@ -258,6 +394,45 @@ pub async fn upsert_localization(mut localization: Localization) -> Localization
     .unwrap()
 }
 
+/// Deletes `localization` from the database.
+///
+/// Returns `true` if `localization` was deleted.
+pub async fn delete_localization(localization: Localization) -> bool {
+    spawn_blocking(move || database::delete_localization(&localization).is_ok())
+        .await
+        .unwrap()
+}
+
+/// Queries all key templates from the database.
+pub async fn query_key_templates() -> Option<Vec<KeyTemplate>> {
+    spawn_blocking(database::query_key_templates)
+        .await
+        .unwrap()
+        .ok()
+}
+
+/// Upserts `template` to the database.
+///
+/// Returns the updated [`KeyTemplate`] or original if fails. Actions referencing `template` are
+/// rebuilt with the updated values.
+pub async fn upsert_key_template(mut template: KeyTemplate) -> KeyTemplate {
+    spawn_blocking(move || {
+        let _ = database::upsert_key_template(&mut template);
+        template
+    })
+    .await
+    .unwrap()
+}
+
+/// Deletes `template` from the database.
+///
+/// Returns `true` if `template` was deleted.
+pub async fn delete_key_template(template: KeyTemplate) -> bool {
+    spawn_blocking(move || database::delete_key_template(&template).is_ok())
+        .await
+        .unwrap()
+}
+
 /// Queries settings from the database.
 pub async fn query_settings() -> Settings {
     spawn_blocking(database::query_settings).await.unwrap()
@@ -275,6 +450,58 @@ pub async fn upsert_settings(mut settings: Settings) -> Settings {
     .unwrap()
 }
 
+/// Queries the names of the database backups from newest to oldest.
+pub async fn query_database_backups() -> Vec<String> {
+    spawn_blocking(database::query_backups)
+        .await
+        .unwrap()
+        .unwrap_or_default()
+}
+
+/// Backs up the database.
+///
+/// Returns the name of the created backup on success.
+pub async fn backup_database() -> Option<String> {
+    spawn_blocking(database::backup_database)
+        .await
+        .unwrap()
+        .ok()
+}
+
+/// Restores the database from the backup with `name`.
+///
+/// The current database is backed up before restoring. Returns `true` if restored.
+pub async fn restore_database_backup(name: String) -> bool {
+    spawn_blocking(move || database::restore_backup(&name).is_ok())
+        .await
+        .unwrap()
+}
+
+/// Syncs the database with the server of [`Settings::config_sync`] in `direction`.
+///
+/// Changes made on the other side since the last sync are only overwritten when `force` is
+/// `true`. Returns [`None`] if syncing failed.
+pub async fn sync_database(direction: SyncDirection, force: bool) -> Option<SyncStatus> {
+    spawn_blocking(move || {
+        let config = database::query_settings().config_sync;
+        sync::sync_database(&config, direction, force)
+    })
+    .await
+    .unwrap()
+    .ok()
+}
+
+/// Imports maps and characters from the legacy JSON export `content`.
+///
+/// When `dry_run` is `true`, nothing is written and the report only describes the changes.
+/// Returns [`None`] if `content` is not a valid export or writing to the database failed.
+pub async fn import_legacy_records(content: String, dry_run: bool) -> Option<LegacyImportReport> {
+    spawn_blocking(move || legacy::import_legacy(&content, dry_run))
+        .await
+        .unwrap()
+        .ok()
+}
+
 /// Queries maps from the database.
 pub async fn query_maps() -> Option<Vec<Map>> {
     spawn_blocking(database::query_maps).await.unwrap().ok()
@@ -304,6 +531,49 @@ pub async fn update_map(preset: Option<String>, map: Option<Map>) {
     send_request!(UpdateMap(preset, map))
 }
 
+/// Detects candidate platforms from the currently detected map.
+///
+/// Platforms overlapping with the ones of the current map are excluded. The detected platforms
+/// are not added to the map.
+pub async fn detect_map_platforms() -> Vec<Platform> {
+    send_request!(DetectMapPlatforms => (platforms))
+}
+
+/// Reorders the positioned normal `actions` of `map` to a near-optimal visiting order.
+///
+/// The travel time between actions is estimated along the platforms of `map` using the calibrated
+/// movement speeds of `character`. Priority actions keep their positions and linked actions move
+/// along with the action they are linked to. The reordered actions are not saved to the map.
+pub async fn optimize_actions_route(
+    map: Map,
+    actions: Vec<Action>,
+    character: Option<Character>,
+) -> Vec<Action> {
+    spawn_blocking(move || {
+        let model = route::RouteCostModel::new(character.as_ref());
+        let rng = rng::Rng::new(rand::random(), rand::random());
+        route::optimize_actions_route(&actions, &map.platforms, model, &rng)
+    })
+    .await
+    .unwrap()
+}
+
+/// Samples a custom player marker at `x` and `y` of the currently detected map.
+///
+/// The `y` is in bottom-left coordinate similar to the player position. The sampled marker is not
+/// added to the map.
+pub async fn sample_player_marker(x: i32, y: i32) -> Option<PlayerMarker> {
+    send_request!(SamplePlayerMarker(x, y) => (marker))
+}
+
+/// Queues a one-shot move of the player to `x` and `y` of the currently detected map.
+///
+/// The `y` is in bottom-left coordinate similar to the player position. Returns `false` if the
+/// minimap is not detected or the position is outside of it.
+pub async fn move_player_to(x: i32, y: i32) -> bool {
+    send_request!(MovePlayerTo(x, y) => (queued))
+}
+
 /// Deletes `map` from the database.
 ///
 /// Returns `true` if the map was deleted.
@@ -313,6 +583,47 @@ pub async fn delete_map(map: Map) -> bool {
         .unwrap()
 }
 
+/// Enables or disables all actions of `preset` in `map` tagged with `tag` and upserts `map`.
+///
+/// Returns the updated [`Map`] on success.
+pub async fn update_actions_enabled_by_tag(
+    mut map: Map,
+    preset: String,
+    tag: String,
+    enabled: bool,
+) -> Option<Map> {
+    map.set_actions_enabled_by_tag(&preset, &tag, enabled);
+    upsert_map(map).await
+}
+
+/// Offsets the positions of all actions of `preset` in `map` by `dx` and `dy` and upserts `map`.
+///
+/// Only actions tagged with `tag` are offset if provided. Returns the updated [`Map`] on success.
+pub async fn shift_actions(
+    mut map: Map,
+    preset: String,
+    tag: Option<String>,
+    dx: i32,
+    dy: i32,
+) -> Option<Map> {
+    map.shift_actions(&preset, tag.as_deref(), dx, dy);
+    upsert_map(map).await
+}
+
+/// Clones the actions of `preset` in `from` to `to_preset` in `to` and upserts `to`.
+///
+/// Existing actions of `to_preset` are replaced. Returns the updated `to` on success.
+pub async fn clone_actions(
+    from: Map,
+    preset: String,
+    mut to: Map,
+    to_preset: String,
+) -> Option<Map> {
+    let actions = from.actions.get(&preset)?.clone();
+    to.actions.insert(to_preset, actions);
+    upsert_map(to).await
+}
+
 /// Queries navigation paths from the database.
 pub async fn query_navigation_paths() -> Option<Vec<NavigationPaths>> {
     spawn_blocking(database::query_navigation_paths)
@@ -391,6 +702,13 @@ pub async fn update_character(character: Option<Character>) {
     send_request!(UpdateCharacter(character))
 }
 
+/// Starts calibrating the movement speeds of the current character.
+///
+/// The bot must be halting. The result is saved to the current character when completed.
+pub async fn calibrate_movement() {
+    send_request!(CalibrateMovement)
+}
+
 /// Deletes `character` from the database.
 ///
 /// Returns `true` if the `character` was deleted.
@@ -428,6 +746,16 @@ pub async fn query_template(template: GameTemplate) -> String {
     send_request!(QueryTemplate(template) => (base64))
 }
 
+/// Queries the suggested replacements for templates that repeatedly failed to match.
+pub async fn query_template_suggestions() -> Vec<TemplateSuggestion> {
+    detect::template_suggestions()
+}
+
+/// Dismisses the suggested replacement for `template` after it is accepted or rejected.
+pub async fn dismiss_template_suggestion(template: GameTemplate) {
+    detect::remove_template_suggestion(template)
+}
+
 pub async fn convert_image_to_base64(image: Vec<u8>, is_grayscale: bool) -> Option<String> {
     send_request!(ConvertImageToBase64(image, is_grayscale) => (base64))
 }
@@ -436,6 +764,128 @@ pub async fn save_capture_image(is_grayscale: bool) {
     send_request!(SaveCaptureImage(is_grayscale))
 }
 
+/// Queries the names of all detectors loaded from plugins.
+pub async fn query_plugin_detectors() -> Vec<String> {
+    spawn_blocking(plugin::detector_names).await.unwrap()
+}
+
+/// Validates the actions `preset` of `map` with `character`, `settings` and `localization` before
+/// running.
+///
+/// Key templates and plugin detectors referenced by the actions are checked against the ones
+/// currently in the database and loaded.
+pub async fn validate_map(
+    map: Map,
+    preset: String,
+    character: Option<Character>,
+    settings: Settings,
+    localization: Localization,
+) -> Vec<MapWarning> {
+    spawn_blocking(move || {
+        let key_templates = database::query_key_templates().unwrap_or_default();
+        let plugin_detectors = plugin::detector_names();
+        validation::validate_map(validation::MapValidationArgs {
+            map: &map,
+            preset: &preset,
+            character: character.as_ref(),
+            settings: &settings,
+            localization: &localization,
+            key_templates: &key_templates,
+            plugin_detectors: &plugin_detectors,
+        })
+    })
+    .await
+    .unwrap()
+}
+
+/// Releases all keys held down by the bot and persists the pending action cooldowns.
+///
+/// This function blocks and is intended to be called right before the process exits. It waits at
+/// most one second for the main loop to respond in case the main loop is no longer running.
+pub fn shutdown() {
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    let (tx, mut rx) = oneshot::channel();
+    if REQUESTS.0.send((Request::Shutdown, tx)).is_err() {
+        return;
+    }
+
+    let instant = Instant::now();
+    while instant.elapsed() < TIMEOUT {
+        if rx.try_recv().is_ok() {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Fills the grid `layout` by dragging each placement with the mouse.
+///
+/// Returns `false` without filling if the bot is not halting. Filling stops once the bot runs.
+pub async fn run_grid_layout(layout: GridLayout) -> bool {
+    send_request!(RunGridLayout(layout) => (started))
+}
+
+/// Stops filling the grid layout started by [`run_grid_layout`].
+pub async fn stop_grid_layout() {
+    send_request!(StopGridLayout)
+}
+
+/// Starts recording the session for replaying later.
+///
+/// The systems are reset with new random seeds before recording. Returns the folder the recording
+/// is saved to or [`None`] if a session is already being recorded or replayed.
+pub async fn start_recording_replay() -> Option<String> {
+    send_request!(StartRecordingReplay => (dir))
+}
+
+/// Starts replaying the session recorded in the folder `dir` without the game.
+///
+/// The currently selected map, preset, character and tick rate must match the recorded ones.
+/// Returns `false` if the recording cannot be replayed.
+pub async fn start_replaying(dir: String) -> bool {
+    send_request!(StartReplaying(dir) => (started))
+}
+
+/// Stops recording or replaying started by [`start_recording_replay`] or [`start_replaying`].
+pub async fn stop_replay() {
+    send_request!(StopReplay)
+}
+
+pub async fn query_replay_status() -> ReplayStatus {
+    send_request!(QueryReplayStatus => (status))
+}
+
+/// Exports the state transitions, actions, detections and positions of the current session in
+/// `format` for offline analysis.
+///
+/// Returns the path of the exported file or [`None`] if the export failed.
+pub async fn export_event_log(format: EventLogFormat) -> Option<String> {
+    spawn_blocking(move || event_log::export_session_events(format))
+        .await
+        .unwrap()
+}
+
+/// Exports the player contextual states and the transitions between them as a diagram in
+/// `format`.
+pub async fn export_player_state_machine(format: StateMachineFormat) -> String {
+    player::export_state_machine(format)
+}
+
+/// Queries the rolling duration breakdown of each part of the main loop tick.
+pub async fn query_tick_system_profiles() -> Vec<TickSystemProfile> {
+    metrics::tick_system_profiles()
+}
+
+/// Queries at most `limit` latest key strokes sent by the bot, optionally only of `key`.
+///
+/// The returned audits are ordered from the latest to the oldest.
+pub async fn query_input_audits(key: Option<KeyBinding>, limit: usize) -> Vec<InputAudit> {
+    spawn_blocking(move || audit::query_input_audits(key, limit))
+        .await
+        .unwrap()
+}
+
 #[cfg(debug_assertions)]
 pub async fn debug_state_receiver() -> broadcast::Receiver<DebugState> {
     send_request!(DebugStateReceiver => (receiver))
@@ -466,6 +916,30 @@ pub async fn test_spin_rune() {
     send_request!(TestSpinRune)
 }
 
+/// Tests all localized templates against the screenshots inside the folder `dir`.
+///
+/// The templates are detected using the localization from the database. Returns [`None`] if the
+/// folder cannot be read or a screenshot fails to be saved.
+#[cfg(debug_assertions)]
+pub async fn test_templates(dir: String) -> Option<TemplateTestReport> {
+    let localization = std::sync::Arc::new(query_localization().await);
+    spawn_blocking(move || debug::test_templates(std::path::Path::new(&dir), localization).ok())
+        .await
+        .unwrap()
+}
+
+/// Pauses the main loop systems so that they only advance through [`step`].
+#[cfg(debug_assertions)]
+pub async fn update_stepping(stepping: bool) {
+    send_request!(UpdateStepping(stepping))
+}
+
+/// Advances the paused main loop systems by `ticks`.
+#[cfg(debug_assertions)]
+pub async fn step(ticks: u32) {
+    send_request!(Step(ticks))
+}
+
 fn poll_request() -> Option<PendingRequest> {
     LazyLock::force(&REQUESTS).1.lock().unwrap().try_recv().ok()
 }
@@ -0,0 +1,318 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt::Write,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use log::{error, info};
+use strum::{Display, EnumIter, IntoEnumIterator};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    spawn,
+    task::JoinHandle,
+    time::sleep,
+};
+
+use crate::InputAuditStroke;
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// Number of latest durations of each [`TickSystem`] kept for [`tick_system_profiles`].
+const SYSTEM_DURATIONS_COUNT: usize = 300;
+
+/// A part of the main loop tick whose duration is profiled.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display, EnumIter)]
+pub enum TickSystem {
+    /// Grabbing the game frame.
+    Capture,
+    /// Background detection tasks running outside of the main loop.
+    Detection,
+    Minimap,
+    Player,
+    #[strum(to_string = "Skills and buffs")]
+    SkillsAndBuffs,
+    /// Navigating and rotating actions.
+    Rotator,
+    /// Handling requests and events.
+    Services,
+}
+
+/// The rolling duration breakdown of a [`TickSystem`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TickSystemProfile {
+    pub system: TickSystem,
+    /// The average duration of the latest runs.
+    pub average: Duration,
+    /// The maximum duration of the latest runs.
+    pub max: Duration,
+}
+
+/// Sum and count of observed durations exported as a Prometheus summary without quantiles.
+#[derive(Debug, Default)]
+struct DurationSummary {
+    sum_micros: AtomicU64,
+    count: AtomicU64,
+}
+
+impl DurationSummary {
+    fn observe(&self, duration: Duration) {
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn write(&self, out: &mut String, name: &str, help: &str) {
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let count = self.count.load(Ordering::Relaxed);
+
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} summary");
+        let _ = writeln!(out, "{name}_sum {sum}");
+        let _ = writeln!(out, "{name}_count {count}");
+    }
+}
+
+/// Metrics of the bot health collected since started.
+#[derive(Debug, Default)]
+struct Metrics {
+    tick_duration: DurationSummary,
+    detection_duration: DurationSummary,
+    /// Number of transitions into each player state.
+    player_transitions: Mutex<BTreeMap<String, u64>>,
+    /// Number of key strokes sent for each [`InputAuditStroke`].
+    input_strokes: Mutex<BTreeMap<String, u64>>,
+    /// The latest durations of each [`TickSystem`].
+    system_durations: Mutex<HashMap<TickSystem, VecDeque<Duration>>>,
+}
+
+impl Metrics {
+    fn render(&self) -> String {
+        let mut out = String::new();
+
+        self.tick_duration.write(
+            &mut out,
+            "komari_tick_duration_seconds",
+            "Duration of each main loop tick.",
+        );
+        self.detection_duration.write(
+            &mut out,
+            "komari_detection_duration_seconds",
+            "Duration of each background detection task.",
+        );
+        write_counters(
+            &mut out,
+            "komari_player_transitions_total",
+            "Number of transitions into each player state.",
+            "state",
+            &self.player_transitions.lock().unwrap(),
+        );
+        write_counters(
+            &mut out,
+            "komari_input_strokes_total",
+            "Number of key strokes sent to the game.",
+            "stroke",
+            &self.input_strokes.lock().unwrap(),
+        );
+
+        out
+    }
+}
+
+/// Records the duration of a main loop tick.
+pub fn record_tick_duration(duration: Duration) {
+    METRICS.tick_duration.observe(duration);
+}
+
+/// Records the duration of a background detection task.
+pub fn record_detection_duration(duration: Duration) {
+    METRICS.detection_duration.observe(duration);
+    record_system_duration(TickSystem::Detection, duration);
+}
+
+/// Runs `f` and records its duration for `system`.
+#[inline]
+pub fn profile_system<T>(system: TickSystem, f: impl FnOnce() -> T) -> T {
+    let instant = Instant::now();
+    let result = f();
+    record_system_duration(system, instant.elapsed());
+    result
+}
+
+fn record_system_duration(system: TickSystem, duration: Duration) {
+    let mut durations = METRICS.system_durations.lock().unwrap();
+    let durations = durations.entry(system).or_default();
+    if durations.len() >= SYSTEM_DURATIONS_COUNT {
+        durations.pop_front();
+    }
+    durations.push_back(duration);
+}
+
+/// Retrieves the rolling duration breakdown of each [`TickSystem`] that has run.
+pub fn tick_system_profiles() -> Vec<TickSystemProfile> {
+    let durations = METRICS.system_durations.lock().unwrap();
+
+    TickSystem::iter()
+        .filter_map(|system| {
+            let durations = durations
+                .get(&system)
+                .filter(|durations| !durations.is_empty())?;
+            let total = durations.iter().sum::<Duration>();
+
+            Some(TickSystemProfile {
+                system,
+                average: total / durations.len() as u32,
+                max: durations.iter().max().copied().unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// Records a transition of the player into `state`.
+pub fn record_player_transition(state: String) {
+    *METRICS
+        .player_transitions
+        .lock()
+        .unwrap()
+        .entry(state)
+        .or_default() += 1;
+}
+
+/// Records a key `stroke` sent to the game.
+pub fn record_input_stroke(stroke: InputAuditStroke) {
+    *METRICS
+        .input_strokes
+        .lock()
+        .unwrap()
+        .entry(stroke.to_string())
+        .or_default() += 1;
+}
+
+/// Delay before accepting connections again after failing to accept one.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Serves the metrics in Prometheus text format on `port`.
+///
+/// The server only listens on localhost unless `all_interfaces` is `true`.
+pub fn serve_metrics(port: u16, all_interfaces: bool) -> JoinHandle<()> {
+    let ip = if all_interfaces {
+        Ipv4Addr::UNSPECIFIED
+    } else {
+        Ipv4Addr::LOCALHOST
+    };
+    let address = SocketAddr::from((ip, port));
+
+    spawn(async move {
+        let listener = match TcpListener::bind(address).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                error!(target: "metrics", "failed to listen on {address}: {err}");
+                return;
+            }
+        };
+        info!(target: "metrics", "serving metrics on {address}");
+
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    spawn(respond(stream));
+                }
+                Err(err) => {
+                    // Errors such as running out of file descriptors persist for a while
+                    error!(target: "metrics", "failed to accept connection: {err}");
+                    sleep(ACCEPT_RETRY_DELAY).await;
+                }
+            }
+        }
+    })
+}
+
+async fn respond(mut stream: TcpStream) {
+    // The request is not parsed because every path serves the same metrics
+    let mut buf = [0; 1024];
+    let _ = stream.read(&mut buf).await;
+
+    let body = METRICS.render();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+fn write_counters(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label: &str,
+    counters: &BTreeMap<String, u64>,
+) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} counter");
+    for (value, count) in counters {
+        let value = escape_label_value(value);
+        let _ = writeln!(out, "{name}{{{label}=\"{value}\"}} {count}");
+    }
+}
+
+/// Escapes `\`, `"` and line feed in label `value` as required by the text exposition format.
+fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_prometheus_text_format() {
+        let metrics = Metrics::default();
+        metrics.tick_duration.observe(Duration::from_millis(30));
+        metrics.tick_duration.observe(Duration::from_millis(20));
+        metrics
+            .player_transitions
+            .lock()
+            .unwrap()
+            .insert("Idle".to_string(), 2);
+
+        let text = metrics.render();
+
+        assert!(text.contains("# TYPE komari_tick_duration_seconds summary\n"));
+        assert!(text.contains("komari_tick_duration_seconds_sum 0.05\n"));
+        assert!(text.contains("komari_tick_duration_seconds_count 2\n"));
+        assert!(text.contains("komari_detection_duration_seconds_count 0\n"));
+        assert!(text.contains("komari_player_transitions_total{state=\"Idle\"} 2\n"));
+        assert!(text.contains("# TYPE komari_input_strokes_total counter\n"));
+    }
+
+    #[test]
+    fn render_escapes_label_values() {
+        let metrics = Metrics::default();
+        metrics
+            .player_transitions
+            .lock()
+            .unwrap()
+            .insert("a\"b\\c\nd".to_string(), 1);
+
+        let text = metrics.render();
+
+        assert!(text.contains("komari_player_transitions_total{state=\"a\\\"b\\\\c\\nd\"} 1\n"));
+        assert_eq!(escape_label_value("Idle"), "Idle");
+    }
+}
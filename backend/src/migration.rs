@@ -0,0 +1,393 @@
+//! Versioned schema migrations for persisted models.
+//!
+//! Each table stores its models as JSON in the `data` column. The schema version of a table is
+//! the number of migrations already applied to it and is tracked in the [`SCHEMA_VERSIONS`]
+//! table. When a table is behind its latest version, the database is first backed up and then
+//! every row is upgraded by running the pending migrations in order.
+//!
+//! To change the shape of a persisted model, append a new [`Migration`] to the table list instead
+//! of modifying the existing ones. Migrations must never be removed or reordered.
+
+use std::{fs, path::Path};
+
+use anyhow::{Result, bail};
+use log::{error, info};
+use rusqlite::{Connection, OptionalExtension, Transaction};
+use serde_json::Value;
+
+const SCHEMA_VERSIONS: &str = "schema_versions";
+
+/// A migration upgrading a single persisted JSON value by one version.
+pub type Migration = fn(&mut Value) -> Result<()>;
+
+/// The ordered migrations of a table.
+#[derive(Debug)]
+pub struct TableMigrations {
+    pub table: &'static str,
+    pub migrations: &'static [Migration],
+}
+
+impl TableMigrations {
+    #[inline]
+    fn latest_version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+
+    /// Upgrades a single persisted `value` from `version` to the latest version.
+    pub fn upgrade(&self, value: &mut Value, version: u32) -> Result<()> {
+        for migration in &self.migrations[version as usize..] {
+            migration(value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Upgrades all `tables` to their latest schema versions.
+///
+/// If any table with existing rows needs upgrading, the database is backed up to `backup_path`
+/// before migrating. All tables are migrated in a single transaction so a failed migration
+/// leaves the database untouched.
+pub fn migrate(
+    conn: &mut Connection,
+    backup_path: Option<&Path>,
+    tables: &[TableMigrations],
+) -> Result<()> {
+    create_schema_versions_table(conn)?;
+
+    let mut pending = Vec::new();
+    let mut has_pending_rows = false;
+    for table in tables {
+        let version = schema_version(conn, table.table)?;
+        if version > table.latest_version() {
+            bail!(
+                "table {} schema version {version} is newer than supported version {}",
+                table.table,
+                table.latest_version()
+            );
+        }
+        if version < table.latest_version() {
+            has_pending_rows |= rows_count(conn, table.table)? > 0;
+            pending.push((table, version));
+        }
+    }
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    if has_pending_rows && let Some(path) = backup_path {
+        backup(conn, path)?;
+    }
+
+    let tx = conn.transaction()?;
+    for (table, version) in pending {
+        migrate_table(&tx, table, version)?;
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// Restores all `tables` from the database backup at `path` and upgrades them to their latest
+/// schema versions.
+///
+/// Tables missing from the backup are left untouched. All tables are restored in a single
+/// transaction so a failed restore leaves the database untouched.
+pub fn restore(conn: &mut Connection, path: &Path, tables: &[TableMigrations]) -> Result<()> {
+    if !path.is_file() {
+        bail!("backup {} does not exist", path.display());
+    }
+    let Some(path_str) = path.to_str() else {
+        bail!("invalid backup path {}", path.display());
+    };
+    create_schema_versions_table(conn)?;
+
+    conn.execute("ATTACH DATABASE ?1 AS backup;", [path_str])?;
+    let result = restore_attached_backup(conn, tables);
+    conn.execute("DETACH DATABASE backup;", [])?;
+    result?;
+    info!(target: "migration", "restored database from {path_str}");
+
+    migrate(conn, None, tables)
+}
+
+fn restore_attached_backup(conn: &mut Connection, tables: &[TableMigrations]) -> Result<()> {
+    let tx = conn.transaction()?;
+    let has_schema_versions = backup_has_table(&tx, SCHEMA_VERSIONS)?;
+    for table in tables {
+        let name = table.table;
+        if !backup_has_table(&tx, name)? {
+            continue;
+        }
+
+        // Backups without schema versions were made before any migration existed
+        let version = if has_schema_versions {
+            tx.query_row(
+                &format!("SELECT version FROM backup.{SCHEMA_VERSIONS} WHERE name = ?1;"),
+                [name],
+                |row| row.get::<_, u32>(0),
+            )
+            .optional()?
+            .unwrap_or_default()
+        } else {
+            0
+        };
+        if version > table.latest_version() {
+            bail!(
+                "backup table {name} schema version {version} is newer than supported version {}",
+                table.latest_version()
+            );
+        }
+
+        tx.execute_batch(&format!(
+            "DELETE FROM main.{name}; \
+             INSERT INTO main.{name} (id, data) SELECT id, data FROM backup.{name};"
+        ))?;
+        tx.execute(
+            &format!(
+                "INSERT INTO main.{SCHEMA_VERSIONS} (name, version) VALUES (?1, ?2) \
+                 ON CONFLICT (name) DO UPDATE SET version = ?2;"
+            ),
+            (name, version),
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+fn backup_has_table(conn: &Connection, table: &str) -> Result<bool> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) FROM backup.sqlite_master WHERE type = 'table' AND name = ?1;",
+        [table],
+        |row| row.get::<_, u32>(0),
+    )? > 0)
+}
+
+fn create_schema_versions_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {SCHEMA_VERSIONS} (
+                name TEXT PRIMARY KEY,
+                version INTEGER NOT NULL
+            );
+            "#
+        )
+        .as_str(),
+    )?;
+
+    Ok(())
+}
+
+fn migrate_table(tx: &Transaction<'_>, table: &TableMigrations, version: u32) -> Result<()> {
+    let name = table.table;
+    let latest = table.latest_version();
+    info!(target: "migration", "migrating table {name} from version {version} to {latest}");
+
+    let rows = {
+        let mut stmt = tx.prepare(&format!("SELECT id, data FROM {name};"))?;
+        stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?
+    };
+    let update = format!("UPDATE {name} SET data = ?2 WHERE id = ?1;");
+    for (id, data) in rows {
+        let mut value = match serde_json::from_str::<Value>(&data) {
+            Ok(value) => value,
+            Err(err) => {
+                error!(target: "migration", "skipping unreadable row {id} in {name} {err}");
+                continue;
+            }
+        };
+        table.upgrade(&mut value, version)?;
+        tx.execute(&update, (id, serde_json::to_string(&value)?))?;
+    }
+
+    let upsert_version = format!(
+        "INSERT INTO {SCHEMA_VERSIONS} (name, version) VALUES (?1, ?2) \
+         ON CONFLICT (name) DO UPDATE SET version = ?2;"
+    );
+    tx.execute(&upsert_version, (name, latest))?;
+
+    Ok(())
+}
+
+fn schema_version(conn: &Connection, table: &str) -> Result<u32> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT version FROM {SCHEMA_VERSIONS} WHERE name = ?1;"),
+            [table],
+            |row| row.get::<_, u32>(0),
+        )
+        .optional()?
+        .unwrap_or_default())
+}
+
+fn rows_count(conn: &Connection, table: &str) -> Result<u32> {
+    Ok(
+        conn.query_row(&format!("SELECT COUNT(*) FROM {table};"), [], |row| {
+            row.get::<_, u32>(0)
+        })?,
+    )
+}
+
+/// Backs up the database of `conn` to `path`.
+///
+/// The parent directories of `path` are created if they do not exist.
+pub fn backup(conn: &Connection, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let Some(path_str) = path.to_str() else {
+        bail!("invalid backup path {}", path.display());
+    };
+    conn.execute("VACUUM INTO ?1;", [path_str])?;
+    info!(target: "migration", "backed up database to {path_str}");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    const TABLE: &str = "models";
+
+    fn rename_name_to_label(value: &mut Value) -> Result<()> {
+        if let Some(object) = value.as_object_mut()
+            && let Some(name) = object.remove("name")
+        {
+            object.insert("label".to_string(), name);
+        }
+        Ok(())
+    }
+
+    fn add_enabled(value: &mut Value) -> Result<()> {
+        if let Some(object) = value.as_object_mut() {
+            object.entry("enabled").or_insert(Value::Bool(true));
+        }
+        Ok(())
+    }
+
+    fn connection_with_rows(rows: &[&str]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!(
+            "CREATE TABLE {TABLE} (id INTEGER PRIMARY KEY, data TEXT NOT NULL);"
+        ))
+        .unwrap();
+        for row in rows {
+            conn.execute(&format!("INSERT INTO {TABLE} (data) VALUES (?1);"), [row])
+                .unwrap();
+        }
+        conn
+    }
+
+    fn data(conn: &Connection) -> Vec<Value> {
+        let mut stmt = conn
+            .prepare(&format!("SELECT data FROM {TABLE} ORDER BY id;"))
+            .unwrap();
+        stmt.query_map([], |row| row.get::<_, String>(0))
+            .unwrap()
+            .map(|data| serde_json::from_str(&data.unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn migrate_runs_pending_migrations_in_order() {
+        let mut conn = connection_with_rows(&[r#"{"name":"a"}"#]);
+        let first = [TableMigrations {
+            table: TABLE,
+            migrations: &[rename_name_to_label],
+        }];
+        let second = [TableMigrations {
+            table: TABLE,
+            migrations: &[rename_name_to_label, add_enabled],
+        }];
+
+        migrate(&mut conn, None, &first).unwrap();
+        assert_eq!(schema_version(&conn, TABLE).unwrap(), 1);
+        assert_eq!(data(&conn), vec![serde_json::json!({"label": "a"})]);
+
+        migrate(&mut conn, None, &second).unwrap();
+        assert_eq!(schema_version(&conn, TABLE).unwrap(), 2);
+        assert_eq!(
+            data(&conn),
+            vec![serde_json::json!({"label": "a", "enabled": true})]
+        );
+    }
+
+    #[test]
+    fn migrate_rejects_newer_schema_version() {
+        let mut conn = connection_with_rows(&[]);
+        let tables = [TableMigrations {
+            table: TABLE,
+            migrations: &[add_enabled],
+        }];
+        migrate(&mut conn, None, &tables).unwrap();
+
+        let older = [TableMigrations {
+            table: TABLE,
+            migrations: &[],
+        }];
+        assert!(migrate(&mut conn, None, &older).is_err());
+    }
+
+    #[test]
+    fn migrate_backs_up_only_when_rows_need_upgrading() {
+        let dir = env::temp_dir().join("komari_migrate_backs_up_only_when_rows_need_upgrading");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("backup.db");
+        let tables = [TableMigrations {
+            table: TABLE,
+            migrations: &[add_enabled],
+        }];
+
+        let mut conn = connection_with_rows(&[]);
+        migrate(&mut conn, Some(&path), &tables).unwrap();
+        assert!(!path.exists());
+
+        let mut conn = connection_with_rows(&[r#"{}"#]);
+        migrate(&mut conn, Some(&path), &tables).unwrap();
+        assert!(path.exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn restore_replaces_rows_and_migrates_backup() {
+        let dir = env::temp_dir().join("komari_restore_replaces_rows_and_migrates_backup");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("backup.db");
+        let first = [TableMigrations {
+            table: TABLE,
+            migrations: &[rename_name_to_label],
+        }];
+        let second = [TableMigrations {
+            table: TABLE,
+            migrations: &[rename_name_to_label, add_enabled],
+        }];
+
+        let mut conn = connection_with_rows(&[r#"{"name":"a"}"#]);
+        migrate(&mut conn, None, &first).unwrap();
+        backup(&conn, &path).unwrap();
+        conn.execute(
+            &format!("INSERT INTO {TABLE} (data) VALUES (?1);"),
+            [r#"{"label":"b"}"#],
+        )
+        .unwrap();
+        migrate(&mut conn, None, &second).unwrap();
+
+        restore(&mut conn, &path, &second).unwrap();
+        assert_eq!(schema_version(&conn, TABLE).unwrap(), 2);
+        assert_eq!(
+            data(&conn),
+            vec![serde_json::json!({"label": "a", "enabled": true})]
+        );
+        assert!(restore(&mut conn, &dir.join("missing.db"), &second).is_err());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -24,6 +24,17 @@ use crate::{
 const MINIMAP_BORDER_WHITENESS_THRESHOLD: u8 = 160;
 const MAX_PORTALS_COUNT: usize = 16;
 
+/// Maximum number of rune spawn hints per map.
+pub const MAX_RUNE_SPAWN_HINTS_COUNT: usize = 8;
+
+/// Maximum distance between a detected rune and a rune spawn hint for the rune to be snapped to
+/// that hint.
+const RUNE_SPAWN_HINT_SNAP_DISTANCE: i32 = 6;
+
+/// Maximum y distance between the player and another player to be considered on the same
+/// platform.
+const OTHER_PLAYER_SAME_PLATFORM_Y_THRESHOLD: i32 = 4;
+
 /// A wrapper struct for [`Rect`] that implements [`Hash`].
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 struct HashedRect {
@@ -39,6 +50,15 @@ impl Hash for HashedRect {
     }
 }
 
+/// How close another player is to the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtherPlayerProximity {
+    /// Another player is on the same platform as the player.
+    SamePlatform,
+    /// Another player is in the map but not on the same platform.
+    Far,
+}
+
 /// A minimap entity.
 #[derive(Debug)]
 pub struct MinimapEntity {
@@ -63,12 +83,14 @@ pub struct MinimapContext {
     /// Task to detect elite boss.
     has_elite_boss_task: Option<Task<Result<()>>>,
     /// Task to detect guildie player(s) in the minimap.
-    has_guildie_player_task: Option<Task<Result<()>>>,
+    has_guildie_player_task: Option<Task<Result<Point>>>,
     /// Task to detect stranger player(s) in the minimap.
-    has_stranger_player_task: Option<Task<Result<()>>>,
+    has_stranger_player_task: Option<Task<Result<Point>>>,
     /// Task to detect firend player(s) in the minimap.
-    has_friend_player_task: Option<Task<Result<()>>>,
+    has_friend_player_task: Option<Task<Result<Point>>>,
 
+    /// Known rune spawn spots in player-relative coordinate.
+    rune_spawn_hints: Arc<[Point]>,
     platforms: Vec<Platform>,
     /// Whether to update the [`MinimapIdle::platforms`].
     ///
@@ -86,6 +108,10 @@ impl MinimapContext {
         self.platforms = platforms;
         self.platforms_dirty = true;
     }
+
+    pub fn set_rune_spawn_hints(&mut self, hints: Vec<Point>) {
+        self.rune_spawn_hints = hints.into();
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -133,12 +159,18 @@ pub struct MinimapIdle {
     ///
     /// The rune position is in player-relative coordinate, which is bottom-left.
     rune: Threshold<Point>,
-    /// Whether there is a guildie.
-    has_guildie_player: Threshold<()>,
-    /// Whether there is a stranger.
-    has_stranger_player: Threshold<()>,
-    /// Whether there is a friend.
-    has_friend_player: Threshold<()>,
+    /// The guildie position if there is any.
+    ///
+    /// The position is in player-relative coordinate, which is bottom-left.
+    has_guildie_player: Threshold<Point>,
+    /// The stranger position if there is any.
+    ///
+    /// The position is in player-relative coordinate, which is bottom-left.
+    has_stranger_player: Threshold<Point>,
+    /// The friend position if there is any.
+    ///
+    /// The position is in player-relative coordinate, which is bottom-left.
+    has_friend_player: Threshold<Point>,
     /// The portal positions.
     ///
     /// The portals are in player-relative coordinate, which is bottom-left.
@@ -178,7 +210,36 @@ impl MinimapIdle {
 
     #[cfg(test)]
     pub fn set_has_any_other_player(&mut self, has_any_other_player: bool) {
-        self.has_stranger_player.value = has_any_other_player.then_some(());
+        self.has_stranger_player.value = has_any_other_player.then_some(Point::default());
+    }
+
+    #[cfg(test)]
+    pub fn set_other_player(&mut self, pos: Point) {
+        self.has_stranger_player.value = Some(pos);
+    }
+
+    /// Gets the proximity of the closest other player to the player at `pos`.
+    ///
+    /// Returns [`None`] if there is no other player. The other player is always far away if `pos`
+    /// is unknown.
+    pub fn other_player_proximity(&self, pos: Option<Point>) -> Option<OtherPlayerProximity> {
+        let positions = [
+            self.has_guildie_player.value,
+            self.has_stranger_player.value,
+            self.has_friend_player.value,
+        ];
+        let mut positions = positions.into_iter().flatten().peekable();
+        positions.peek()?;
+
+        let Some(pos) = pos else {
+            return Some(OtherPlayerProximity::Far);
+        };
+        if positions.any(|other| (other.y - pos.y).abs() <= OTHER_PLAYER_SAME_PLATFORM_Y_THRESHOLD)
+        {
+            Some(OtherPlayerProximity::SamePlatform)
+        } else {
+            Some(OtherPlayerProximity::Far)
+        }
     }
 
     #[inline]
@@ -305,6 +366,7 @@ fn update_idle_state(
         bbox,
         player_state,
         rune,
+        minimap.context.rune_spawn_hints.clone(),
     );
     let has_guildie_player = update_other_player_task(
         resources,
@@ -386,6 +448,7 @@ fn update_rune_task(
     minimap_bbox: Rect,
     player_state: Player,
     rune: Threshold<Point>,
+    hints: Arc<[Point]>,
 ) -> Threshold<Point> {
     let was_none = rune.value.is_none();
     if matches!(player_state, Player::SolvingRune(_)) && !was_none {
@@ -393,9 +456,23 @@ fn update_rune_task(
     }
 
     let rune = update_threshold_detection(resources, 5000, rune, task, move |detector| {
-        detector
-            .detect_minimap_rune(minimap_bbox)
-            .map(|rune| center_of_bbox(rune, minimap_bbox))
+        match detector.detect_minimap_rune(minimap_bbox) {
+            Ok(rune) => Ok(snap_rune_to_hints(
+                center_of_bbox(rune, minimap_bbox),
+                &hints,
+            )),
+            Err(err) if hints.is_empty() => Err(err),
+            Err(_) => {
+                // The rune might be occluded by other minimap icons, so look around the hints
+                let hints_top_left = hints
+                    .iter()
+                    .map(|hint| Point::new(hint.x, minimap_bbox.height - hint.y))
+                    .collect::<Vec<_>>();
+                detector
+                    .detect_minimap_rune_near(minimap_bbox, &hints_top_left)
+                    .map(|rune| snap_rune_to_hints(center_of_bbox(rune, minimap_bbox), &hints))
+            }
+        }
     });
 
     if was_none && rune.value.is_some() && !resources.operation.halting() {
@@ -407,21 +484,33 @@ fn update_rune_task(
     rune
 }
 
+/// Snaps the detected `rune` to the nearest of `hints` within [`RUNE_SPAWN_HINT_SNAP_DISTANCE`].
+///
+/// Returns `rune` as-is if there is no such hint.
+fn snap_rune_to_hints(rune: Point, hints: &[Point]) -> Point {
+    hints
+        .iter()
+        .copied()
+        .map(|hint| (hint, (hint.x - rune.x).abs().max((hint.y - rune.y).abs())))
+        .filter(|(_, distance)| *distance <= RUNE_SPAWN_HINT_SNAP_DISTANCE)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(hint, _)| hint)
+        .unwrap_or(rune)
+}
+
 #[inline]
 fn update_other_player_task(
     resources: &Resources,
-    task: &mut Option<Task<Result<()>>>,
+    task: &mut Option<Task<Result<Point>>>,
     minimap: Rect,
-    threshold: Threshold<()>,
+    threshold: Threshold<Point>,
     kind: OtherPlayerKind,
-) -> Threshold<()> {
+) -> Threshold<Point> {
     let has_player = threshold.value.is_some();
     let threshold = update_threshold_detection(resources, 3000, threshold, task, move |detector| {
-        if detector.detect_player_kind(minimap, kind) {
-            Ok(())
-        } else {
-            Err(anyhow!("player not found"))
-        }
+        detector
+            .detect_player_kind(minimap, kind)
+            .map(|bbox| center_of_bbox(bbox, minimap))
     });
     if !resources.operation.halting() && !has_player && threshold.value.is_some() {
         info!(target: "minimap", "sending {kind:?} notification...");
@@ -897,4 +986,42 @@ mod tests {
         assert_eq!(result.len(), 0);
         assert!(map.is_empty());
     }
+
+    #[test]
+    fn other_player_proximity_same_platform_or_far() {
+        let mut idle = MinimapIdle::default();
+        assert_eq!(idle.other_player_proximity(Some(Point::new(10, 20))), None);
+
+        idle.set_other_player(Point::new(50, 22));
+        assert_eq!(
+            idle.other_player_proximity(Some(Point::new(10, 20))),
+            Some(OtherPlayerProximity::SamePlatform)
+        );
+        assert_eq!(
+            idle.other_player_proximity(Some(Point::new(10, 40))),
+            Some(OtherPlayerProximity::Far)
+        );
+        assert_eq!(
+            idle.other_player_proximity(None),
+            Some(OtherPlayerProximity::Far)
+        );
+    }
+
+    #[test]
+    fn snap_rune_to_hints_nearest_within_distance() {
+        let hints = [Point::new(50, 20), Point::new(54, 20), Point::new(100, 40)];
+
+        assert_eq!(
+            snap_rune_to_hints(Point::new(53, 22), &hints),
+            Point::new(54, 20)
+        );
+        assert_eq!(
+            snap_rune_to_hints(Point::new(80, 30), &hints),
+            Point::new(80, 30)
+        );
+        assert_eq!(
+            snap_rune_to_hints(Point::new(53, 22), &[]),
+            Point::new(53, 22)
+        );
+    }
 }
@@ -1,7 +1,12 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumIter, EnumString};
 
-use super::{KeyBinding, LinkKeyBinding, deserialize_with_ok_or_default};
+use super::{
+    KeyBinding, LinkKeyBinding, LinkKeyChainStep, MAX_LINK_KEY_CHAIN_STEPS,
+    deserialize_with_ok_or_default,
+};
 
 /// A persistent model representing a user-provided action for the bot to perform.
 #[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize, EnumIter, Display, EnumString)]
@@ -32,14 +37,98 @@ impl Action {
             }),
         }
     }
+
+    pub fn enabled(&self) -> bool {
+        match self {
+            Action::Move(action) => action.enabled,
+            Action::Key(action) => action.enabled,
+        }
+    }
+
+    pub fn with_enabled(&self, enabled: bool) -> Action {
+        match self {
+            Action::Move(action) => Action::Move(ActionMove { enabled, ..*action }),
+            Action::Key(action) => Action::Key(ActionKey { enabled, ..*action }),
+        }
+    }
+
+    pub fn note(&self) -> ActionNote {
+        match self {
+            Action::Move(action) => action.note,
+            Action::Key(action) => action.note,
+        }
+    }
+
+    pub fn color(&self) -> ActionColor {
+        match self {
+            Action::Move(action) => action.color,
+            Action::Key(action) => action.color,
+        }
+    }
+
+    pub fn tags(&self) -> ActionTags {
+        match self {
+            Action::Move(action) => action.tags,
+            Action::Key(action) => action.tags,
+        }
+    }
+
+    /// Returns this action with its position offset by `dx` and `dy`.
+    ///
+    /// The offset position is clamped between `0` and `width`/`height`. Actions without a
+    /// position are returned as is.
+    pub fn with_position_offset(&self, dx: i32, dy: i32, width: i32, height: i32) -> Action {
+        let offset = |position: Position| Position {
+            x: (position.x + dx).clamp(0, width.max(0)),
+            y: (position.y + dy).clamp(0, height.max(0)),
+            ..position
+        };
+
+        match self {
+            Action::Move(action) => Action::Move(ActionMove {
+                position: offset(action.position),
+                ..*action
+            }),
+            Action::Key(action) => Action::Key(ActionKey {
+                position: action.position.map(offset),
+                ..*action
+            }),
+        }
+    }
 }
 
 /// A persistent model for the [`Action::Move`] action.
-#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub struct ActionMove {
     pub position: Position,
     pub condition: ActionCondition,
     pub wait_after_move_millis: u64,
+    /// A user note shown in the actions list.
+    #[serde(default)]
+    pub note: ActionNote,
+    /// The tags used to select this action in bulk operations.
+    #[serde(default)]
+    pub tags: ActionTags,
+    /// The color shown in the actions list.
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub color: ActionColor,
+    /// Whether this action is used by the rotator.
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+}
+
+impl Default for ActionMove {
+    fn default() -> Self {
+        Self {
+            position: Position::default(),
+            condition: ActionCondition::default(),
+            wait_after_move_millis: 0,
+            note: ActionNote::default(),
+            tags: ActionTags::default(),
+            color: ActionColor::default(),
+            enabled: enabled_default(),
+        }
+    }
 }
 
 /// A persistent model for the [`Action::Key`] action.
@@ -50,14 +139,27 @@ pub struct ActionKey {
     pub key_hold_millis: u64,
     #[serde(default)]
     pub key_hold_buffered_to_wait_after: bool,
+    /// Whether to move in [`Self::direction`] while holding the key for [`Self::key_hold_millis`].
+    #[serde(default)]
+    pub key_hold_moving: bool,
     #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
     pub link_key: LinkKeyBinding,
+    /// The link keys used after [`Self::link_key`] for combos needing more than one link key.
+    #[serde(default)]
+    pub link_key_chain: [LinkKeyChainStep; MAX_LINK_KEY_CHAIN_STEPS],
     #[serde(default = "count_default")]
     pub count: u32,
     pub position: Option<Position>,
     pub condition: ActionCondition,
     pub direction: ActionKeyDirection,
     pub with: ActionKeyWith,
+    /// Milliseconds to wait after double jumping before using the key when [`Self::with`] is
+    /// [`ActionKeyWith::DoubleJump`].
+    ///
+    /// The key is used right after double jumping if `0`. Otherwise, the facing direction is locked
+    /// until the action completes so that the key is cast at the apex in the same direction.
+    #[serde(default)]
+    pub double_jump_apex_millis: u64,
     pub wait_before_use_millis: u64,
     pub wait_before_use_millis_random_range: u64,
     pub wait_after_use_millis: u64,
@@ -65,6 +167,49 @@ pub struct ActionKey {
     #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
     pub wait_after_buffered: WaitAfterBuffered,
     pub queue_to_front: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub burst_buff: ActionKeyBurstBuff,
+    #[serde(default = "burst_max_wait_millis_default")]
+    pub burst_max_wait_millis: u64,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub group: ActionKeyGroup,
+    /// Whether using this action also resets the cooldown of other actions in the same group.
+    #[serde(default)]
+    pub group_shares_cooldown: bool,
+    /// Milliseconds this action must wait after any action in the same group was used.
+    #[serde(default)]
+    pub group_exclusive_millis: u64,
+    /// The plugin detector that must detect before this action can be used.
+    #[serde(default)]
+    pub plugin_detector: Option<PluginDetectorName>,
+    /// Whether this action is paused when other player reaction is
+    /// [`super::OtherPlayerReaction::PauseLoudSkills`].
+    #[serde(default)]
+    pub loud: bool,
+    /// The region to verify that this action was actually cast.
+    #[serde(default)]
+    pub cast_verification: Option<ActionKeyCastVerification>,
+    /// The region that must contain the resource consumed by this action before it can be used.
+    #[serde(default)]
+    pub resource_gate: Option<ActionKeyResourceGate>,
+    /// The name of the [`super::ConditionWebhook`] that must allow before this action can be used.
+    #[serde(default)]
+    pub condition_webhook: Option<ConditionWebhookName>,
+    /// A user note shown in the actions list.
+    #[serde(default)]
+    pub note: ActionNote,
+    /// The tags used to select this action in bulk operations.
+    #[serde(default)]
+    pub tags: ActionTags,
+    /// The color shown in the actions list.
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub color: ActionColor,
+    /// Whether this action is used by the rotator.
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+    /// The id of the [`super::KeyTemplate`] overriding the key, link keys, count and waits.
+    #[serde(default)]
+    pub template_id: Option<i64>,
 }
 
 impl Default for ActionKey {
@@ -73,18 +218,36 @@ impl Default for ActionKey {
             key: KeyBinding::default(),
             key_hold_millis: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyBinding::None,
+            link_key_chain: [LinkKeyChainStep::default(); MAX_LINK_KEY_CHAIN_STEPS],
             count: count_default(),
             position: None,
             condition: ActionCondition::default(),
             direction: ActionKeyDirection::default(),
             with: ActionKeyWith::default(),
+            double_jump_apex_millis: 0,
             wait_before_use_millis: 0,
             wait_before_use_millis_random_range: 0,
             wait_after_use_millis: 0,
             wait_after_use_millis_random_range: 0,
             wait_after_buffered: WaitAfterBuffered::None,
             queue_to_front: None,
+            burst_buff: ActionKeyBurstBuff::default(),
+            burst_max_wait_millis: burst_max_wait_millis_default(),
+            group: ActionKeyGroup::default(),
+            group_shares_cooldown: false,
+            group_exclusive_millis: 0,
+            plugin_detector: None,
+            loud: false,
+            cast_verification: None,
+            resource_gate: None,
+            condition_webhook: None,
+            note: ActionNote::default(),
+            tags: ActionTags::default(),
+            color: ActionColor::default(),
+            enabled: enabled_default(),
+            template_id: None,
         }
     }
 }
@@ -93,6 +256,93 @@ fn count_default() -> u32 {
     1
 }
 
+fn enabled_default() -> bool {
+    true
+}
+
+/// A screen region checked for pixel changes after using an [`ActionKey`].
+///
+/// If the region does not change after the key is used, the key is assumed to be dropped and
+/// is used again up to [`Self::max_recast_count`] times.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ActionKeyCastVerification {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub max_recast_count: u32,
+}
+
+impl Default for ActionKeyCastVerification {
+    fn default() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 100,
+            max_recast_count: 2,
+        }
+    }
+}
+
+/// A screen region checked before queuing an [`ActionKey`].
+///
+/// The action is held until the region satisfies [`Self::kind`]. This is useful for skills
+/// consuming resources such as bullets or arrows that do nothing when depleted.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ActionKeyResourceGate {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub kind: ActionKeyResourceGateKind,
+    /// The RGB color to look for when [`Self::kind`] is
+    /// [`ActionKeyResourceGateKind::ColorPresent`].
+    pub color: (u8, u8, u8),
+    /// The maximum difference per channel from [`Self::color`].
+    pub tolerance: u8,
+}
+
+impl Default for ActionKeyResourceGate {
+    fn default() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width: 50,
+            height: 20,
+            kind: ActionKeyResourceGateKind::default(),
+            color: (255, 255, 255),
+            tolerance: 20,
+        }
+    }
+}
+
+/// How an [`ActionKeyResourceGate`] region is checked.
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Debug,
+    Serialize,
+    Deserialize,
+    EnumIter,
+    Display,
+    EnumString,
+)]
+pub enum ActionKeyResourceGateKind {
+    /// Satisfied if the region is not a flat background (e.g. a bullet count is shown).
+    #[default]
+    NotEmpty,
+    /// Satisfied if any pixel in the region matches the color (e.g. an icon is shown).
+    ColorPresent,
+}
+
+fn burst_max_wait_millis_default() -> u64 {
+    10000
+}
+
 #[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Position {
     pub x: i32,
@@ -132,6 +382,214 @@ pub enum ActionKeyDirection {
     Right,
 }
 
+/// The buff an [`ActionKey`] waits for before being queued to align with a burst window.
+///
+/// The buff must be enabled in the character for it to be detected.
+#[derive(
+    Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum ActionKeyBurstBuff {
+    #[default]
+    None,
+    ForTheGuild,
+    HardHitter,
+    ExtremeRedPotion,
+    ExtremeBluePotion,
+    ExtremeGreenPotion,
+    ExtremeGoldPotion,
+}
+
+/// The group an [`ActionKey`] belongs to.
+///
+/// Actions in the same group can share cooldown or be prevented from being used back-to-back.
+/// It only applies to actions with [`ActionCondition::EveryMillis`] or
+/// [`ActionCondition::ErdaShowerOffCooldown`] condition.
+#[derive(
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Serialize,
+    Deserialize,
+    EnumIter,
+    Display,
+    EnumString,
+)]
+pub enum ActionKeyGroup {
+    #[default]
+    None,
+    A,
+    B,
+    C,
+    D,
+}
+
+/// A string stored inline with a fixed capacity of `N` bytes.
+///
+/// Used instead of [`String`] so that [`Action`] stays [`Copy`]. Strings longer than
+/// [`Self::MAX_LEN`] bytes are rejected instead of being cut short.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct InlineStr<const N: usize> {
+    bytes: [u8; N],
+    len: u8,
+}
+
+impl<const N: usize> InlineStr<N> {
+    pub const MAX_LEN: usize = N;
+
+    pub const EMPTY: InlineStr<N> = InlineStr {
+        bytes: [0; N],
+        len: 0,
+    };
+
+    /// Creates a string from `value`.
+    ///
+    /// Returns [`None`] if `value` is longer than [`Self::MAX_LEN`] bytes.
+    pub fn new(value: &str) -> Option<Self> {
+        const { assert!(N <= u8::MAX as usize) };
+
+        let len = value.len();
+        if len > N {
+            return None;
+        }
+
+        let mut bytes = [0; N];
+        bytes[..len].copy_from_slice(value.as_bytes());
+        Some(Self {
+            bytes,
+            len: len as u8,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len as usize]).expect("valid utf-8")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<const N: usize> Default for InlineStr<N> {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl<const N: usize> TryFrom<String> for InlineStr<N> {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(&value).ok_or_else(|| format!("{value} is longer than {N} bytes"))
+    }
+}
+
+impl<const N: usize> From<InlineStr<N>> for String {
+    fn from(value: InlineStr<N>) -> Self {
+        value.as_str().to_string()
+    }
+}
+
+impl<const N: usize> fmt::Display for InlineStr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> fmt::Debug for InlineStr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// The name of a plugin detector.
+///
+/// Names longer than [`InlineStr::MAX_LEN`] bytes are rejected so that two detectors cannot
+/// collide.
+pub type PluginDetectorName = InlineStr<32>;
+
+/// The name of a [`super::ConditionWebhook`].
+pub type ConditionWebhookName = InlineStr<32>;
+
+/// A user note of an [`Action`].
+pub type ActionNote = InlineStr<64>;
+
+/// The comma-separated tags of an [`Action`].
+///
+/// Stored as an [`ActionNote`] so that [`Action`] stays [`Copy`] and shares its length limit. Tags
+/// are trimmed and compared case-insensitively.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ActionTags(ActionNote);
+
+impl ActionTags {
+    pub const EMPTY: ActionTags = ActionTags(ActionNote::EMPTY);
+
+    /// Creates tags from comma-separated `tags`.
+    ///
+    /// Returns [`None`] if `tags` is longer than [`ActionNote::MAX_LEN`] bytes.
+    pub fn new(tags: &str) -> Option<Self> {
+        ActionNote::new(tags).map(Self)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.as_str()
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+    }
+
+    pub fn contains(&self, tag: &str) -> bool {
+        let tag = tag.trim();
+        self.iter().any(|other| other.eq_ignore_ascii_case(tag))
+    }
+}
+
+impl fmt::Display for ActionTags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for ActionTags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// The color of an [`Action`] shown in the actions list.
+#[derive(
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Debug,
+    Serialize,
+    Deserialize,
+    EnumIter,
+    Display,
+    EnumString,
+)]
+pub enum ActionColor {
+    #[default]
+    None,
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
 #[derive(
     Clone, Copy, Display, EnumString, EnumIter, PartialEq, Debug, Serialize, Deserialize, Default,
 )]
@@ -208,6 +666,9 @@ impl From<ActionConfiguration> for Action {
             wait_after_use_millis: value.wait_after_millis,
             wait_after_use_millis_random_range: value.wait_after_millis_random_range,
             wait_after_buffered: value.wait_after_buffered,
+            burst_buff: ActionKeyBurstBuff::None,
+            burst_max_wait_millis: burst_max_wait_millis_default(),
+            ..ActionKey::default()
         })
     }
 }
@@ -223,3 +684,75 @@ impl Default for ActionConfigurationCondition {
         ActionConfigurationCondition::EveryMillis(180000)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_str_try_from_reports_max_len() {
+        let long = "a".repeat(5);
+
+        assert_eq!(
+            InlineStr::<4>::try_from(long.clone()),
+            Err(format!("{long} is longer than 4 bytes"))
+        );
+        assert_eq!(
+            InlineStr::<4>::try_from("abcd".to_string())
+                .unwrap()
+                .as_str(),
+            "abcd"
+        );
+        assert!(InlineStr::<4>::default().is_empty());
+    }
+
+    #[test]
+    fn plugin_detector_name_rejects_long_name() {
+        let max = "a".repeat(PluginDetectorName::MAX_LEN);
+        let long = "a".repeat(PluginDetectorName::MAX_LEN + 1);
+
+        assert_eq!(PluginDetectorName::new(&max).unwrap().as_str(), max);
+        assert!(PluginDetectorName::new(&long).is_none());
+        assert!(PluginDetectorName::new("").unwrap().as_str().is_empty());
+    }
+
+    #[test]
+    fn plugin_detector_name_serde_rejects_long_name() {
+        let name = PluginDetectorName::new("detector").unwrap();
+        let json = serde_json::to_string(&name).unwrap();
+        let long = format!("\"{}\"", "a".repeat(PluginDetectorName::MAX_LEN + 1));
+
+        assert_eq!(json, "\"detector\"");
+        assert_eq!(
+            serde_json::from_str::<PluginDetectorName>(&json).unwrap(),
+            name
+        );
+        assert!(serde_json::from_str::<PluginDetectorName>(&long).is_err());
+    }
+
+    #[test]
+    fn action_note_rejects_long_note() {
+        let max = "a".repeat(ActionNote::MAX_LEN);
+        let long = "a".repeat(ActionNote::MAX_LEN + 1);
+        // Multi-byte characters count by bytes
+        let long_multi_byte = "é".repeat(ActionNote::MAX_LEN / 2 + 1);
+
+        assert_eq!(ActionNote::new(&max).unwrap().as_str(), max);
+        assert!(ActionNote::new(&long).is_none());
+        assert!(ActionNote::new(&long_multi_byte).is_none());
+        assert!(ActionNote::new("").unwrap().is_empty());
+        assert!(ActionTags::new(&long).is_none());
+    }
+
+    #[test]
+    fn action_note_and_tags_serde_reject_long_input() {
+        let note = ActionNote::new("note").unwrap();
+        let json = serde_json::to_string(&note).unwrap();
+        let long = format!("\"{}\"", "a".repeat(ActionNote::MAX_LEN + 1));
+
+        assert_eq!(json, "\"note\"");
+        assert_eq!(serde_json::from_str::<ActionNote>(&json).unwrap(), note);
+        assert!(serde_json::from_str::<ActionNote>(&long).is_err());
+        assert!(serde_json::from_str::<ActionTags>(&long).is_err());
+    }
+}
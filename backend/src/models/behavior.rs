@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Action, ActionKeyBurstBuff};
+
+/// A node of a behavior tree rotation used instead of the normal actions list.
+///
+/// Each time the player has no normal action, the tree is ticked from the root and all
+/// [`BehaviorNode::Action`]s reached by succeeding nodes are rotated in order.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum BehaviorNode {
+    /// Ticks the children in order and succeeds on the first child that succeeds.
+    Selector(Vec<BehaviorNode>),
+    /// Ticks the children in order and fails on the first child that fails.
+    ///
+    /// Actions from the children ticked before the failing child are discarded.
+    Sequence(Vec<BehaviorNode>),
+    /// Succeeds if the condition is satisfied.
+    Condition(BehaviorCondition),
+    /// Always succeeds and rotates the action.
+    ///
+    /// The [`ActionCondition`](super::ActionCondition) of the action is ignored.
+    Action(Action),
+}
+
+/// The condition of a [`BehaviorNode::Condition`].
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum BehaviorCondition {
+    /// Satisfied if at least the milliseconds have passed since this condition last succeeded as
+    /// part of a succeeding tick.
+    EveryMillis(u64),
+    /// Satisfied if Erda Shower is off cooldown.
+    ErdaShowerOffCooldown,
+    /// Satisfied if the buff is active.
+    BurstBuffActive(ActionKeyBurstBuff),
+}
@@ -17,6 +17,9 @@ pub struct Character {
     pub teleport_key: Option<KeyBindingConfiguration>,
     #[serde(default = "jump_key_default")]
     pub jump_key: KeyBindingConfiguration,
+    /// Keys used in place of the arrow keys for moving.
+    #[serde(default)]
+    pub movement_keys: MovementKeys,
     pub up_jump_key: Option<KeyBindingConfiguration>,
     #[serde(default = "key_default")]
     pub interact_key: KeyBindingConfiguration,
@@ -81,11 +84,39 @@ pub struct Character {
     pub up_jump_is_flight: bool,
     #[serde(default)]
     pub up_jump_specific_key_should_jump: bool,
+    #[serde(default)]
+    pub horizontal_teleporting: bool,
+    /// The key held to glide for classes with sustained horizontal flight.
+    #[serde(default)]
+    pub flight_key: Option<KeyBindingConfiguration>,
+    #[serde(default)]
+    pub horizontal_flight: bool,
+    /// Calibrated horizontal walk speed in pixels per tick at the base tick rate.
+    #[serde(default)]
+    pub walk_speed: Option<f32>,
+    /// Calibrated horizontal double jump speed in pixels per tick at the base tick rate.
+    #[serde(default)]
+    pub double_jump_speed: Option<f32>,
+    /// Calibrated horizontal teleport distance in minimap pixels.
+    #[serde(default)]
+    pub teleport_x_distance: Option<f32>,
+    /// Calibrated vertical teleport distance in minimap pixels.
+    #[serde(default)]
+    pub teleport_y_distance: Option<f32>,
     pub actions: Vec<ActionConfiguration>,
     #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
     pub elite_boss_behavior: EliteBossBehavior,
     #[serde(default)]
     pub elite_boss_behavior_key: KeyBinding,
+    /// Whether level up stat allocation and new skill popups are dismissed when detected.
+    #[serde(default)]
+    pub dismiss_level_up_popups: bool,
+    /// Keys pressed in order to auto-assign points before dismissing level up popups.
+    #[serde(default)]
+    pub level_up_auto_assign_keys: Vec<KeyBinding>,
+    /// Buys a consumable from an NPC shop when its quick slot count runs low.
+    #[serde(default)]
+    pub shop_purchase: ShopPurchase,
 }
 
 impl_identifiable!(Character);
@@ -98,6 +129,7 @@ impl Default for Character {
             ropelift_key: None,
             teleport_key: None,
             jump_key: jump_key_default(),
+            movement_keys: MovementKeys::default(),
             up_jump_key: None,
             interact_key: key_default(),
             cash_shop_key: None,
@@ -142,9 +174,19 @@ impl Default for Character {
             disable_teleport_on_fall: false,
             up_jump_is_flight: false,
             up_jump_specific_key_should_jump: false,
+            horizontal_teleporting: false,
+            flight_key: None,
+            horizontal_flight: false,
+            walk_speed: None,
+            double_jump_speed: None,
+            teleport_x_distance: None,
+            teleport_y_distance: None,
             actions: vec![],
             elite_boss_behavior_key: KeyBinding::default(),
             elite_boss_behavior: EliteBossBehavior::default(),
+            dismiss_level_up_popups: false,
+            level_up_auto_assign_keys: vec![],
+            shop_purchase: ShopPurchase::default(),
         }
     }
 }
@@ -205,6 +247,78 @@ pub enum ExchangeHexaBoosterCondition {
     AtLeastOne,
 }
 
+/// The keys bound to moving in-game.
+///
+/// Defaults to the arrow keys.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MovementKeys {
+    pub left: KeyBinding,
+    pub right: KeyBinding,
+    pub up: KeyBinding,
+    pub down: KeyBinding,
+}
+
+impl Default for MovementKeys {
+    fn default() -> Self {
+        Self {
+            left: KeyBinding::Left,
+            right: KeyBinding::Right,
+            up: KeyBinding::Up,
+            down: KeyBinding::Down,
+        }
+    }
+}
+
+/// Buys a consumable from an NPC shop when its count in the quick slots runs low.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShopPurchase {
+    pub enabled: bool,
+    /// The screen region of the item count in the quick slots.
+    pub count_x: i32,
+    pub count_y: i32,
+    pub count_width: i32,
+    pub count_height: i32,
+    /// Buys when the item count drops below this.
+    pub count_threshold: u32,
+    /// The navigation path of the map with the NPC or [`None`] if it is on the current map.
+    pub paths_id_index: Option<(i64, usize)>,
+    /// The NPC position in player relative coordinate.
+    pub npc_x: i32,
+    pub npc_y: i32,
+    /// The key to open the shop when standing next to the NPC.
+    pub open_key: KeyBinding,
+    /// The screen position of the shop tab with the item.
+    pub tab_x: i32,
+    pub tab_y: i32,
+    /// The screen position of the item inside the shop tab.
+    pub item_x: i32,
+    pub item_y: i32,
+    /// The number of items to buy.
+    pub quantity: u32,
+}
+
+impl Default for ShopPurchase {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            count_x: 0,
+            count_y: 0,
+            count_width: 0,
+            count_height: 0,
+            count_threshold: 50,
+            paths_id_index: None,
+            npc_x: 0,
+            npc_y: 0,
+            open_key: KeyBinding::default(),
+            tab_x: 0,
+            tab_y: 0,
+            item_x: 0,
+            item_y: 0,
+            quantity: 100,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Familiars {
     pub enable_familiars_swapping: bool,
@@ -212,6 +326,9 @@ pub struct Familiars {
     pub swap_check_millis: u64,
     pub swappable_familiars: SwappableFamiliars,
     pub swappable_rarities: HashSet<FamiliarRarity>,
+    /// The familiar buff duration after summoning, `0` to disable re-summoning ahead of expiry.
+    #[serde(default)]
+    pub buff_duration_millis: u64,
 }
 
 impl Default for Familiars {
@@ -221,6 +338,7 @@ impl Default for Familiars {
             swap_check_millis: familiars_swap_check_millis(),
             swappable_familiars: SwappableFamiliars::default(),
             swappable_rarities: HashSet::default(),
+            buff_duration_millis: 0,
         }
     }
 }
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+use super::impl_identifiable;
+
+/// The last queued times of a character's actions persisted across restarts.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct ActionCooldowns {
+    #[serde(skip_serializing, default)]
+    pub id: Option<i64>,
+    pub character_id: i64,
+    #[serde(default)]
+    pub cooldowns: Vec<ActionCooldown>,
+}
+
+impl_identifiable!(ActionCooldowns);
+
+/// The last time an action identified by `key` was queued.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionCooldown {
+    /// Identifies the action, changes when the action is edited.
+    pub key: String,
+    /// Milliseconds since the Unix epoch.
+    pub queued_at_millis: u64,
+}
+
+/// The daily uses of risky actions persisted across restarts so that the daily caps hold.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RiskyActionCounts {
+    #[serde(skip_serializing, default)]
+    pub id: Option<i64>,
+    /// The local date the counts are counted on in `YYYY-MM-DD` format.
+    pub date: String,
+    #[serde(default)]
+    pub change_channel: u32,
+    #[serde(default)]
+    pub go_to_town: u32,
+    #[serde(default)]
+    pub cash_shop: u32,
+}
+
+impl_identifiable!(RiskyActionCounts);
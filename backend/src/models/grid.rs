@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+/// A declarative layout of a grid-based UI (e.g. Legion or Union board) to fill by mouse drags.
+///
+/// All coordinates are relative to the client area of the game window.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GridLayout {
+    /// The x coordinate of the top-left corner of the grid.
+    #[serde(default)]
+    pub x: i32,
+    /// The y coordinate of the top-left corner of the grid.
+    #[serde(default)]
+    pub y: i32,
+    #[serde(default = "cell_size_default")]
+    pub cell_width: i32,
+    #[serde(default = "cell_size_default")]
+    pub cell_height: i32,
+    /// The delay between each mouse input.
+    #[serde(default = "step_millis_default")]
+    pub step_millis: u64,
+    #[serde(default)]
+    pub placements: Vec<GridPlacement>,
+}
+
+impl Default for GridLayout {
+    fn default() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            cell_width: cell_size_default(),
+            cell_height: cell_size_default(),
+            step_millis: step_millis_default(),
+            placements: vec![],
+        }
+    }
+}
+
+/// A drag from a source point (e.g. a character block) onto a grid cell.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+pub struct GridPlacement {
+    #[serde(default)]
+    pub from_x: i32,
+    #[serde(default)]
+    pub from_y: i32,
+    #[serde(default)]
+    pub column: u32,
+    #[serde(default)]
+    pub row: u32,
+}
+
+fn cell_size_default() -> i32 {
+    16
+}
+
+fn step_millis_default() -> u64 {
+    100
+}
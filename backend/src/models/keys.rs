@@ -80,6 +80,7 @@ pub enum KeyBinding {
     Comma,
     Period,
     Slash,
+    Minus,
     Esc,
     Shift,
     Ctrl,
@@ -121,6 +122,24 @@ impl LinkKeyBinding {
     }
 }
 
+/// The maximum number of [`LinkKeyChainStep`]s following the link key of an action.
+pub const MAX_LINK_KEY_CHAIN_STEPS: usize = 3;
+
+/// A link key used after the link key of an action to form a multi-key combo.
+///
+/// Steps are used in the order of [`LinkKeyBinding::Before`], [`LinkKeyBinding::AtTheSame`]
+/// and then [`LinkKeyBinding::After`]. [`LinkKeyBinding::Along`] is not supported and ignored.
+#[derive(Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub struct LinkKeyChainStep {
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub link_key: LinkKeyBinding,
+    /// Milliseconds to wait after using a before key or before using an after key.
+    ///
+    /// Uses the character link key timing if zero.
+    #[serde(default)]
+    pub delay_millis: u64,
+}
+
 #[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub struct MobbingKey {
     pub key: KeyBinding,
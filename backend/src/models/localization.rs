@@ -1,11 +1,15 @@
 use serde::{Deserialize, Serialize};
 
-use super::impl_identifiable;
+use super::{AnnouncementKind, impl_identifiable};
+use crate::GameTemplate;
 
 #[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
 pub struct Localization {
     #[serde(skip_serializing, default)]
     pub id: Option<i64>,
+    /// The profile name (e.g. the client region such as GMS, KMS).
+    #[serde(default)]
+    pub name: String,
     pub cash_shop_base64: Option<String>,
     pub change_channel_base64: Option<String>,
     pub timer_base64: Option<String>,
@@ -23,6 +27,45 @@ pub struct Localization {
     pub hexa_erda_conversion_button_base64: Option<String>,
     pub hexa_booster_button_base64: Option<String>,
     pub hexa_max_button_base64: Option<String>,
+    /// The grayscale announcement banner templates with no default template.
+    #[serde(default)]
+    pub announcement_world_boss_base64: Option<String>,
+    #[serde(default)]
+    pub announcement_invasion_base64: Option<String>,
 }
 
 impl_identifiable!(Localization);
+
+impl Localization {
+    /// Sets the base64-encoded PNG of `template` with [`None`] to use the default template.
+    pub fn set_template_base64(&mut self, template: GameTemplate, base64: Option<String>) {
+        let field = match template {
+            GameTemplate::CashShop => &mut self.cash_shop_base64,
+            GameTemplate::ChangeChannel => &mut self.change_channel_base64,
+            GameTemplate::Timer => &mut self.timer_base64,
+            GameTemplate::PopupConfirm => &mut self.popup_confirm_base64,
+            GameTemplate::PopupYes => &mut self.popup_yes_base64,
+            GameTemplate::PopupNext => &mut self.popup_next_base64,
+            GameTemplate::PopupEndChat => &mut self.popup_end_chat_base64,
+            GameTemplate::PopupOkNew => &mut self.popup_ok_new_base64,
+            GameTemplate::PopupOkOld => &mut self.popup_ok_old_base64,
+            GameTemplate::PopupCancelNew => &mut self.popup_cancel_new_base64,
+            GameTemplate::PopupCancelOld => &mut self.popup_cancel_old_base64,
+            GameTemplate::FamiliarsLevelSort => &mut self.familiar_level_button_base64,
+            GameTemplate::FamiliarsSaveButton => &mut self.familiar_save_button_base64,
+            GameTemplate::HexaErdaConversionButton => &mut self.hexa_erda_conversion_button_base64,
+            GameTemplate::HexaBoosterButton => &mut self.hexa_booster_button_base64,
+            GameTemplate::HexaMaxButton => &mut self.hexa_max_button_base64,
+            GameTemplate::HexaConvertButton => &mut self.hexa_convert_button_base64,
+        };
+        *field = base64;
+    }
+
+    /// Retrieves the base64-encoded PNG of the announcement banner `kind` if provided.
+    pub fn announcement_base64(&self, kind: AnnouncementKind) -> Option<&String> {
+        match kind {
+            AnnouncementKind::WorldBoss => self.announcement_world_boss_base64.as_ref(),
+            AnnouncementKind::Invasion => self.announcement_invasion_base64.as_ref(),
+        }
+    }
+}
@@ -4,7 +4,7 @@ use opencv::core::Rect;
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumIter, EnumString};
 
-use super::{Action, MobbingKey, deserialize_with_ok_or_default, impl_identifiable};
+use super::{Action, BehaviorNode, MobbingKey, deserialize_with_ok_or_default, impl_identifiable};
 use crate::pathing;
 
 /// A persistent model representing a map-related data.
@@ -19,20 +19,67 @@ pub struct Map {
     pub rotation_mode: RotationMode,
     #[serde(default)]
     pub rotation_ping_pong_bound: Bound,
+    /// Whether ping pong bounces vertically between the top and bottom of
+    /// [`Self::rotation_ping_pong_bound`] instead of horizontally.
+    #[serde(default)]
+    pub rotation_ping_pong_vertical: bool,
+    /// Whether horizontal ping pong continues by estimating the player movement from the screen
+    /// scrolling when the minimap cannot be detected.
+    #[serde(default)]
+    pub rotation_ping_pong_screen_fallback: bool,
     #[serde(default)]
     pub rotation_auto_mob_bound: Bound,
+    /// Named auto mobbing regions used instead of [`Self::rotation_auto_mob_bound`] if not empty.
+    #[serde(default)]
+    pub rotation_auto_mob_regions: Vec<MobbingRegion>,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub rotation_auto_mob_region_selection: MobbingRegionSelection,
     #[serde(default)]
     pub rotation_mobbing_key: MobbingKey,
+    /// The behavior tree used instead of the normal actions when [`Self::rotation_mode`] is
+    /// [`RotationMode::BehaviorTree`].
+    #[serde(default)]
+    pub rotation_behavior_tree: Option<BehaviorNode>,
     pub platforms: Vec<Platform>,
+    #[serde(default)]
+    pub ropes: Vec<Rope>,
+    #[serde(default)]
+    pub danger_zones: Vec<DangerZone>,
+    /// Map-wide attacks that periodically require the player to avoid them.
+    #[serde(default)]
+    pub hazards: Vec<MapHazard>,
+    /// Known rune spawn spots used to resolve ambiguous or occluded rune detections.
+    #[serde(default)]
+    pub rune_spawn_hints: Vec<RuneSpawnHint>,
     pub rune_platforms_pathing: bool,
     pub rune_platforms_pathing_up_jump_only: bool,
     pub auto_mob_platforms_pathing: bool,
     pub auto_mob_platforms_pathing_up_jump_only: bool,
     pub auto_mob_platforms_bound: bool,
+    /// The x distance from both edges of a platform that destinations on it are kept away from.
+    #[serde(default)]
+    pub platform_edge_margin: i32,
     #[serde(default)]
     pub auto_mob_use_key_when_pathing: bool,
     #[serde(default)]
     pub auto_mob_use_key_when_pathing_update_millis: u64,
+    /// Whether auto mobbing pauses to walk over nearby loots before continuing.
+    #[serde(default)]
+    pub auto_mob_loot_pickup: bool,
+    /// Maximum x distance from the player for a loot to be picked up with `0` for no limit.
+    #[serde(default)]
+    pub auto_mob_loot_pickup_radius: i32,
+    /// Solidified auto-mobbing reachable y(s) learned from previous sessions.
+    #[serde(default)]
+    pub auto_mob_reachable_ys: Vec<i32>,
+    /// How mobs are detected for auto mobbing in this map.
+    #[serde(default)]
+    pub mob_detection: MobDetection,
+    /// The custom player marker for servers with a non-standard minimap player marker.
+    ///
+    /// The default player marker templates are used if [`None`].
+    #[serde(default)]
+    pub player_marker: Option<PlayerMarker>,
     pub actions_any_reset_on_erda_condition: bool,
     pub actions: HashMap<String, Vec<Action>>,
     // Not FK, loose coupling to another navigation paths and its index
@@ -42,6 +89,49 @@ pub struct Map {
 
 impl_identifiable!(Map);
 
+impl Map {
+    /// Enables or disables all actions of `preset` tagged with `tag`.
+    ///
+    /// Returns the number of matched actions.
+    pub fn set_actions_enabled_by_tag(&mut self, preset: &str, tag: &str, enabled: bool) -> usize {
+        let Some(actions) = self.actions.get_mut(preset) else {
+            return 0;
+        };
+
+        let mut count = 0;
+        for action in actions
+            .iter_mut()
+            .filter(|action| action.tags().contains(tag))
+        {
+            *action = action.with_enabled(enabled);
+            count += 1;
+        }
+
+        count
+    }
+
+    /// Offsets the positions of all actions of `preset` by `dx` and `dy`.
+    ///
+    /// Offset positions are clamped to the map bounds. Only actions tagged with `tag` are offset
+    /// if provided. Returns the number of matched actions.
+    pub fn shift_actions(&mut self, preset: &str, tag: Option<&str>, dx: i32, dy: i32) -> usize {
+        let Some(actions) = self.actions.get_mut(preset) else {
+            return 0;
+        };
+
+        let mut count = 0;
+        for action in actions
+            .iter_mut()
+            .filter(|action| tag.is_none_or(|tag| action.tags().contains(tag)))
+        {
+            *action = action.with_position_offset(dx, dy, self.width, self.height);
+            count += 1;
+        }
+
+        count
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize)]
 pub struct Bound {
     pub x: i32,
@@ -68,6 +158,76 @@ impl From<Rect> for Bound {
     }
 }
 
+/// A named auto mobbing bound.
+#[derive(Clone, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub struct MobbingRegion {
+    pub name: String,
+    pub bound: Bound,
+}
+
+/// How the next [`MobbingRegion`] is selected when the current one runs out of mobs.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum MobbingRegionSelection {
+    #[default]
+    Cycle,
+    Random,
+}
+
+/// The mob detection used by auto mobbing.
+#[derive(Clone, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub struct MobDetection {
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub kind: MobDetectionKind,
+    /// Path to a custom ONNX YOLO model used when [`Self::kind`] is [`MobDetectionKind::Model`].
+    #[serde(default)]
+    pub model_path: String,
+    /// Color signature used when [`Self::kind`] is [`MobDetectionKind::ColorSignature`].
+    #[serde(default)]
+    pub color_signature: MobColorSignature,
+}
+
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum MobDetectionKind {
+    /// The built-in generic mob detection model.
+    #[default]
+    Default,
+    /// A custom mob detection model trained for specific mob types.
+    Model,
+    /// A HSV color range matching the mob sprites.
+    ColorSignature,
+}
+
+/// A HSV color range with the bounding box area range of a mob.
+///
+/// Hue, saturation and value are all in `0..=255`. If `hue_min` is greater than `hue_max`, the
+/// hue range wraps around (e.g. for red).
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MobColorSignature {
+    pub hue_min: u8,
+    pub hue_max: u8,
+    pub saturation_min: u8,
+    pub value_min: u8,
+    pub area_min: i32,
+    pub area_max: i32,
+}
+
+impl Default for MobColorSignature {
+    fn default() -> Self {
+        Self {
+            hue_min: 0,
+            hue_max: 255,
+            saturation_min: 100,
+            value_min: 100,
+            area_min: 200,
+            area_max: 20000,
+        }
+    }
+}
+
 #[derive(
     Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
 )]
@@ -77,6 +237,7 @@ pub enum RotationMode {
     StartToEndThenReverse,
     AutoMobbing,
     PingPong,
+    BehaviorTree,
 }
 
 #[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
@@ -86,9 +247,232 @@ pub struct Platform {
     pub y: i32,
 }
 
+/// A rope or ladder that can be climbed.
+///
+/// The rope is in player-relative coordinate, which is bottom-left.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Rope {
+    pub x: i32,
+    /// The bottom `y` of the rope.
+    pub y_start: i32,
+    /// The top `y` of the rope.
+    pub y_end: i32,
+}
+
+/// A custom player marker on the minimap matched by color.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct PlayerMarker {
+    /// The marker color in `(red, green, blue)`.
+    pub color: (u8, u8, u8),
+    /// The maximum difference of each color channel for a pixel to be part of the marker.
+    pub tolerance: u8,
+    /// The marker width in pixels.
+    pub width: i32,
+    /// The marker height in pixels.
+    pub height: i32,
+}
+
+impl Default for PlayerMarker {
+    fn default() -> Self {
+        Self {
+            color: (255, 255, 136),
+            tolerance: 20,
+            width: 6,
+            height: 6,
+        }
+    }
+}
+
+/// A damaging floor region (e.g. lava) that the player should avoid standing in.
+///
+/// The zone is in player-relative coordinate, which is bottom-left.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct DangerZone {
+    pub x_start: i32,
+    pub x_end: i32,
+    /// The floor `y` of the zone.
+    pub y: i32,
+}
+
+/// A map-wide attack (e.g. a shockwave) that hits the whole map every
+/// [`Self::interval_millis`].
+///
+/// The timing starts over whenever the actions are rebuilt (e.g. the map is detected).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct MapHazard {
+    pub enabled: bool,
+    /// The milliseconds between each hazard.
+    pub interval_millis: u64,
+    /// The milliseconds from when the timing starts until the first hazard.
+    pub first_millis: u64,
+    /// The milliseconds before each hazard to start avoiding it.
+    pub lead_millis: u64,
+    pub avoidance: MapHazardAvoidance,
+    /// The safe zone `x` when [`Self::avoidance`] is [`MapHazardAvoidance::MoveToSafeZone`].
+    pub safe_x: i32,
+    /// The safe zone `y` when [`Self::avoidance`] is [`MapHazardAvoidance::MoveToSafeZone`].
+    pub safe_y: i32,
+    /// The milliseconds to stay in the safe zone after reaching it.
+    pub safe_wait_millis: u64,
+}
+
+impl Default for MapHazard {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_millis: 60000,
+            first_millis: 60000,
+            lead_millis: 1000,
+            avoidance: MapHazardAvoidance::default(),
+            safe_x: 0,
+            safe_y: 0,
+            safe_wait_millis: 2000,
+        }
+    }
+}
+
+/// How the player avoids a [`MapHazard`].
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Debug,
+    Serialize,
+    Deserialize,
+    EnumIter,
+    Display,
+    EnumString,
+)]
+pub enum MapHazardAvoidance {
+    /// Jumps in place right before the hazard hits.
+    #[default]
+    Jump,
+    /// Moves to the safe zone and waits there until the hazard passes.
+    #[strum(to_string = "Move to safe zone")]
+    MoveToSafeZone,
+}
+
+/// A known rune spawn spot in a map.
+///
+/// The spot is in player-relative coordinate, which is bottom-left.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct RuneSpawnHint {
+    pub x: i32,
+    pub y: i32,
+}
+
 // TODO: Should be part of pathing logics, not here
 impl From<Platform> for pathing::Platform {
     fn from(value: Platform) -> Self {
         Self::new(value.x_start..value.x_end, value.y)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ActionKey, ActionMove, ActionTags, Position};
+
+    const PRESET: &str = "preset";
+
+    fn map_with_actions(actions: Vec<Action>) -> Map {
+        Map {
+            width: 100,
+            height: 50,
+            actions: HashMap::from([(PRESET.to_string(), actions)]),
+            ..Map::default()
+        }
+    }
+
+    fn move_action(x: i32, y: i32, tags: &str) -> Action {
+        Action::Move(ActionMove {
+            position: Position {
+                x,
+                y,
+                ..Position::default()
+            },
+            tags: ActionTags::new(tags).unwrap(),
+            ..ActionMove::default()
+        })
+    }
+
+    fn position(action: &Action) -> Option<(i32, i32)> {
+        match action {
+            Action::Move(action) => Some((action.position.x, action.position.y)),
+            Action::Key(action) => action.position.map(|position| (position.x, position.y)),
+        }
+    }
+
+    #[test]
+    fn set_actions_enabled_by_tag_empty_tag_matches_nothing() {
+        let mut map = map_with_actions(vec![move_action(0, 0, ""), move_action(0, 0, "a, ,b")]);
+
+        assert_eq!(map.set_actions_enabled_by_tag(PRESET, "", false), 0);
+        assert_eq!(map.set_actions_enabled_by_tag(PRESET, "  ", false), 0);
+        assert!(map.actions[PRESET].iter().all(Action::enabled));
+    }
+
+    #[test]
+    fn set_actions_enabled_by_tag_matches_case_insensitively() {
+        let mut map = map_with_actions(vec![
+            move_action(0, 0, "Buff"),
+            move_action(0, 0, "mob, buff "),
+            move_action(0, 0, "mob"),
+        ]);
+
+        assert_eq!(map.set_actions_enabled_by_tag(PRESET, " BUFF", false), 2);
+        let enabled = map.actions[PRESET]
+            .iter()
+            .map(Action::enabled)
+            .collect::<Vec<_>>();
+        assert_eq!(enabled, vec![false, false, true]);
+    }
+
+    #[test]
+    fn set_actions_enabled_by_tag_unknown_preset() {
+        let mut map = map_with_actions(vec![move_action(0, 0, "buff")]);
+
+        assert_eq!(map.set_actions_enabled_by_tag("unknown", "buff", false), 0);
+        assert!(map.actions[PRESET][0].enabled());
+    }
+
+    #[test]
+    fn shift_actions_by_tag_or_all() {
+        let mut map = map_with_actions(vec![move_action(10, 10, "a"), move_action(20, 20, "b")]);
+
+        assert_eq!(map.shift_actions(PRESET, Some("A"), 5, -5), 1);
+        assert_eq!(position(&map.actions[PRESET][0]), Some((15, 5)));
+        assert_eq!(position(&map.actions[PRESET][1]), Some((20, 20)));
+
+        assert_eq!(map.shift_actions(PRESET, None, 1, 1), 2);
+        assert_eq!(position(&map.actions[PRESET][0]), Some((16, 6)));
+        assert_eq!(position(&map.actions[PRESET][1]), Some((21, 21)));
+
+        assert_eq!(map.shift_actions(PRESET, Some(""), 1, 1), 0);
+        assert_eq!(map.shift_actions("unknown", None, 1, 1), 0);
+    }
+
+    #[test]
+    fn shift_actions_past_ends_clamped_to_map_bounds() {
+        let mut map = map_with_actions(vec![move_action(10, 10, ""), move_action(90, 40, "")]);
+
+        assert_eq!(map.shift_actions(PRESET, None, -50, -50), 2);
+        assert_eq!(position(&map.actions[PRESET][0]), Some((0, 0)));
+        assert_eq!(position(&map.actions[PRESET][1]), Some((40, 0)));
+
+        assert_eq!(map.shift_actions(PRESET, None, 100, 100), 2);
+        assert_eq!(position(&map.actions[PRESET][0]), Some((100, 50)));
+        assert_eq!(position(&map.actions[PRESET][1]), Some((100, 50)));
+    }
+
+    #[test]
+    fn shift_actions_key_without_position_unchanged() {
+        let key = Action::Key(ActionKey::default());
+        let mut map = map_with_actions(vec![key]);
+
+        assert_eq!(map.shift_actions(PRESET, None, 5, 5), 1);
+        assert_eq!(map.actions[PRESET][0], key);
+    }
+}
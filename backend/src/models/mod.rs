@@ -1,23 +1,34 @@
-use serde::{Deserialize, Deserializer};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use log::warn;
+use platforms::crypto;
+use serde::{Deserialize, Deserializer, Serializer};
 use serde_json::Value;
 
 mod actions;
+mod behavior;
 mod character;
+mod cooldowns;
+mod grid;
 mod keys;
 mod localization;
 mod map;
 mod navigation;
 mod seeds;
 mod settings;
+mod template;
 
 pub use actions::*;
+pub use behavior::*;
 pub use character::*;
+pub use cooldowns::*;
+pub use grid::*;
 pub use keys::*;
 pub use localization::*;
 pub use map::*;
 pub use navigation::*;
 pub use seeds::*;
 pub use settings::*;
+pub use template::*;
 
 pub trait Identifiable {
     fn id(&self) -> Option<i64>;
@@ -49,3 +60,102 @@ where
     let value = Value::deserialize(deserializer)?;
     Ok(T::deserialize(value).unwrap_or_default())
 }
+
+/// Prefix of a sensitive value encrypted at rest.
+const SECRET_PREFIX: &str = "enc:";
+
+/// Whether the sensitive `value` is still encrypted because it could not be decrypted by
+/// [`deserialize_secret`].
+///
+/// Such value should be treated as unset.
+pub(crate) fn is_undecrypted_secret(value: &str) -> bool {
+    value.starts_with(SECRET_PREFIX)
+}
+
+/// Serializes a sensitive `value` encrypted for the current user.
+///
+/// Falls back to plaintext if encryption is not available so the value is not lost. Values that
+/// could not be decrypted are stored as-is.
+fn serialize_secret<S>(value: &str, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if value.is_empty() || is_undecrypted_secret(value) {
+        return serializer.serialize_str(value);
+    }
+
+    match crypto::protect(value.as_bytes()) {
+        Ok(data) => {
+            serializer.serialize_str(&format!("{SECRET_PREFIX}{}", BASE64_STANDARD.encode(data)))
+        }
+        Err(err) => {
+            warn!(target: "database", "failed to encrypt sensitive value, storing as-is {err}");
+            serializer.serialize_str(value)
+        }
+    }
+}
+
+/// Deserializes a sensitive value serialized by [`serialize_secret`].
+///
+/// Plaintext values from older versions are returned as-is and will be encrypted on the next
+/// save. Values that cannot be decrypted (e.g. database copied from another user or machine)
+/// are kept encrypted so that they are not lost on the next save and can be checked with
+/// [`is_undecrypted_secret`].
+fn deserialize_secret<'a, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    let value = String::deserialize(deserializer)?;
+    let Some(encoded) = value.strip_prefix(SECRET_PREFIX) else {
+        return Ok(value);
+    };
+
+    let decrypted = BASE64_STANDARD
+        .decode(encoded)
+        .ok()
+        .and_then(|data| crypto::unprotect(&data).ok())
+        .and_then(|data| String::from_utf8(data).ok());
+    if decrypted.is_none() {
+        warn!(target: "database", "failed to decrypt sensitive value, keeping it encrypted");
+    }
+
+    Ok(decrypted.unwrap_or(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Secret {
+        #[serde(
+            serialize_with = "serialize_secret",
+            deserialize_with = "deserialize_secret"
+        )]
+        value: String,
+    }
+
+    #[test]
+    fn secret_round_trip() {
+        let secret = Secret {
+            value: "token".to_string(),
+        };
+
+        let json = serde_json::to_string(&secret).unwrap();
+        let deserialized = serde_json::from_str::<Secret>(&json).unwrap();
+
+        assert_eq!(deserialized, secret);
+        assert!(!is_undecrypted_secret(&deserialized.value));
+    }
+
+    #[test]
+    fn secret_undecryptable_kept_encrypted() {
+        let json = format!(r#"{{"value":"{SECRET_PREFIX}bm90IGVuY3J5cHRlZA=="}}"#);
+
+        let deserialized = serde_json::from_str::<Secret>(&json).unwrap();
+        assert!(is_undecrypted_secret(&deserialized.value));
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+    }
+}
@@ -31,6 +31,11 @@ pub struct NavigationPoint {
     pub x: i32,
     pub y: i32,
     pub transition: NavigationTransition,
+    /// The longest time in milliseconds the [`NavigationTransition::TimedPortal`] can stay closed.
+    ///
+    /// For example, this is the time an elevator takes for a round trip.
+    #[serde(default)]
+    pub transition_window_millis: u64,
 }
 
 #[derive(
@@ -39,4 +44,7 @@ pub struct NavigationPoint {
 pub enum NavigationTransition {
     #[default]
     Portal,
+    /// A portal that only opens periodically such as an elevator or a moving platform.
+    #[strum(to_string = "Timed portal")]
+    TimedPortal,
 }
@@ -10,6 +10,12 @@ pub struct Seeds {
     pub rng_seed: [u8; 32],
     #[serde(default = "perlin_seed_default")]
     pub perlin_seed: u32,
+    /// The number of sessions started so far.
+    ///
+    /// This is incremented on every launch so that each session derives a different seed from
+    /// [`Self::rng_seed`].
+    #[serde(default)]
+    pub session_count: u64,
 }
 
 impl_identifiable!(Seeds);
@@ -20,6 +26,7 @@ impl Default for Seeds {
             id: None,
             rng_seed: rand::random(),
             perlin_seed: perlin_seed_default(),
+            session_count: 0,
         }
     }
 }
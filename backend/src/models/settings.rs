@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumIter, EnumString};
 
-use super::{deserialize_with_ok_or_default, impl_identifiable};
+use super::{
+    deserialize_secret, deserialize_with_ok_or_default, impl_identifiable, serialize_secret,
+};
 use crate::{KeyBinding, KeyBindingConfiguration};
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -9,12 +11,31 @@ pub struct Settings {
     #[serde(skip_serializing, default)]
     pub id: Option<i64>,
     pub capture_mode: CaptureMode,
+    /// The game window chosen for capture and input.
+    ///
+    /// The default window is used if [`None`] or no window matches.
+    #[serde(default)]
+    pub capture_window: Option<CaptureWindowSelection>,
     #[serde(default = "enable_solving_default")]
     pub enable_rune_solving: bool,
     #[serde(default = "enable_solving_default")]
     pub enable_transparent_shape_solving: bool,
+    #[serde(default)]
+    pub remote_rune_solving: RemoteRuneSolving,
     pub enable_panic_mode: bool,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub other_player_same_platform_reaction: OtherPlayerReaction,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub other_player_far_reaction: OtherPlayerReaction,
+    /// Milliseconds other player must stay before [`OtherPlayerReaction::ChangeChannel`].
+    #[serde(default = "other_player_change_channel_millis_default")]
+    pub other_player_change_channel_millis: u64,
+    /// How the bot reacts to each recognized map-wide announcement banner.
+    #[serde(default)]
+    pub announcement_reactions: AnnouncementReactions,
     pub stop_on_fail_or_change_map: bool,
+    #[serde(default)]
+    pub auto_switch_map: bool,
     #[serde(default = "stop_on_player_die_default")]
     pub stop_on_player_die: bool,
     #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
@@ -24,8 +45,41 @@ pub struct Settings {
     #[serde(default = "cycle_stop_duration_millis_default")]
     pub cycle_stop_duration_millis: u64,
     pub input_method: InputMethod,
+    #[serde(
+        default,
+        serialize_with = "serialize_secret",
+        deserialize_with = "deserialize_secret"
+    )]
     pub input_method_rpc_server_url: String,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub focus_policy: FocusPolicy,
+    #[serde(default = "focus_grace_period_millis_default")]
+    pub focus_grace_period_millis: u64,
+    /// Whether the bot runs with detection only and without sending any input.
+    #[serde(default)]
+    pub observer_mode: bool,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub keyboard_layout: KeyboardLayout,
+    /// Whether chat messages are pasted through the clipboard instead of typed key by key.
+    #[serde(default)]
+    pub chat_clipboard_paste: bool,
+    /// Whether key strokes sent are recorded to the input audit log.
+    #[serde(default)]
+    pub input_audit: bool,
+    #[serde(default = "tick_rate_default")]
+    pub tick_rate: u32,
+    /// Whether the main loop runs slower while the bot is idle or the player is only waiting to
+    /// reduce CPU usage.
+    #[serde(default)]
+    pub adaptive_tick_rate: bool,
+    /// Whether interchangeable normal actions are shuffled differently on each session.
     #[serde(default)]
+    pub jitter_action_order: bool,
+    #[serde(
+        default,
+        serialize_with = "serialize_secret",
+        deserialize_with = "deserialize_secret"
+    )]
     pub discord_bot_access_token: String,
     pub notifications: Notifications,
     #[serde(default = "toggle_actions_key_default")]
@@ -36,6 +90,48 @@ pub struct Settings {
     pub platform_end_key: KeyBindingConfiguration,
     #[serde(default = "platform_add_key_default")]
     pub platform_add_key: KeyBindingConfiguration,
+    #[serde(default = "panic_key_default")]
+    pub panic_key: KeyBindingConfiguration,
+    #[serde(default)]
+    pub panic_go_to_town: bool,
+    #[serde(default)]
+    pub hotkeys: Vec<Hotkey>,
+    /// Keys not found in this list are not sent when using [`InputMethod::Gamepad`].
+    #[serde(default = "gamepad_mappings_default")]
+    pub gamepad_mappings: Vec<GamepadMapping>,
+    /// How long keys are held down for each press.
+    #[serde(default)]
+    pub key_press_durations: KeyPressDurations,
+    #[serde(default)]
+    pub schedules: Vec<Schedule>,
+    #[serde(default)]
+    pub reset_routine: ResetRoutine,
+    #[serde(default)]
+    pub session_limits: SessionLimits,
+    #[serde(default)]
+    pub unstuck_limits: UnstuckLimits,
+    #[serde(default)]
+    pub exp_rate_alert: ExpRateAlert,
+    /// The HTTP endpoints polled by actions to gate their use.
+    #[serde(default)]
+    pub condition_webhooks: Vec<ConditionWebhook>,
+    #[serde(default)]
+    pub map_failover: MapFailover,
+    /// The id of the [`Localization`](super::Localization) profile in use.
+    ///
+    /// The first profile is used if [`None`] or the profile no longer exists.
+    #[serde(default)]
+    pub localization_id: Option<i64>,
+    #[serde(default)]
+    pub metrics_export: MetricsExport,
+    #[serde(default)]
+    pub config_sync: ConfigSync,
+    #[serde(default)]
+    pub session_timelapse: SessionTimelapse,
+    #[serde(default)]
+    pub risky_action_limits: RiskyActionLimits,
+    #[serde(default)]
+    pub pause_on_input: PauseOnInput,
 }
 
 impl Default for Settings {
@@ -43,12 +139,28 @@ impl Default for Settings {
         Self {
             id: None,
             capture_mode: CaptureMode::default(),
+            capture_window: None,
             enable_rune_solving: enable_solving_default(),
             enable_transparent_shape_solving: enable_solving_default(),
+            remote_rune_solving: RemoteRuneSolving::default(),
             enable_panic_mode: false,
+            other_player_same_platform_reaction: OtherPlayerReaction::default(),
+            other_player_far_reaction: OtherPlayerReaction::default(),
+            other_player_change_channel_millis: other_player_change_channel_millis_default(),
+            announcement_reactions: AnnouncementReactions::default(),
             input_method: InputMethod::default(),
             input_method_rpc_server_url: String::default(),
+            focus_policy: FocusPolicy::default(),
+            focus_grace_period_millis: focus_grace_period_millis_default(),
+            observer_mode: false,
+            keyboard_layout: KeyboardLayout::default(),
+            chat_clipboard_paste: false,
+            input_audit: false,
+            tick_rate: tick_rate_default(),
+            adaptive_tick_rate: false,
+            jitter_action_order: false,
             stop_on_fail_or_change_map: false,
+            auto_switch_map: false,
             stop_on_player_die: stop_on_player_die_default(),
             cycle_run_stop: CycleRunStopMode::default(),
             cycle_run_duration_millis: cycle_run_duration_millis_default(),
@@ -59,6 +171,24 @@ impl Default for Settings {
             platform_start_key: platform_start_key_default(),
             platform_end_key: platform_end_key_default(),
             platform_add_key: platform_add_key_default(),
+            panic_key: panic_key_default(),
+            panic_go_to_town: false,
+            hotkeys: vec![],
+            gamepad_mappings: gamepad_mappings_default(),
+            key_press_durations: KeyPressDurations::default(),
+            schedules: vec![],
+            reset_routine: ResetRoutine::default(),
+            session_limits: SessionLimits::default(),
+            unstuck_limits: UnstuckLimits::default(),
+            exp_rate_alert: ExpRateAlert::default(),
+            condition_webhooks: vec![],
+            map_failover: MapFailover::default(),
+            localization_id: None,
+            metrics_export: MetricsExport::default(),
+            config_sync: ConfigSync::default(),
+            session_timelapse: SessionTimelapse::default(),
+            risky_action_limits: RiskyActionLimits::default(),
+            pause_on_input: PauseOnInput::default(),
         }
     }
 }
@@ -77,6 +207,18 @@ fn cycle_stop_duration_millis_default() -> u64 {
     3600000 // 1 hour
 }
 
+fn other_player_change_channel_millis_default() -> u64 {
+    15000
+}
+
+fn focus_grace_period_millis_default() -> u64 {
+    1000
+}
+
+fn tick_rate_default() -> u32 {
+    30
+}
+
 fn enable_solving_default() -> bool {
     true
 }
@@ -109,6 +251,27 @@ fn platform_add_key_default() -> KeyBindingConfiguration {
     }
 }
 
+fn gamepad_mappings_default() -> Vec<GamepadMapping> {
+    [
+        (KeyBinding::Up, GamepadButton::DpadUp),
+        (KeyBinding::Down, GamepadButton::DpadDown),
+        (KeyBinding::Left, GamepadButton::DpadLeft),
+        (KeyBinding::Right, GamepadButton::DpadRight),
+        (KeyBinding::Esc, GamepadButton::Back),
+        (KeyBinding::Enter, GamepadButton::Start),
+    ]
+    .into_iter()
+    .map(|(key, button)| GamepadMapping { key, button })
+    .collect()
+}
+
+fn panic_key_default() -> KeyBindingConfiguration {
+    KeyBindingConfiguration {
+        key: KeyBinding::F12,
+        enabled: false,
+    }
+}
+
 #[derive(
     Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
 )]
@@ -116,6 +279,341 @@ pub enum InputMethod {
     #[default]
     Default,
     Rpc,
+    /// Sends inputs through a virtual controller using [`Settings::gamepad_mappings`].
+    Gamepad,
+}
+
+/// A button on the virtual controller of [`InputMethod::Gamepad`].
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum GamepadButton {
+    #[default]
+    A,
+    B,
+    X,
+    Y,
+    #[strum(to_string = "Left shoulder")]
+    LeftShoulder,
+    #[strum(to_string = "Right shoulder")]
+    RightShoulder,
+    #[strum(to_string = "Left trigger")]
+    LeftTrigger,
+    #[strum(to_string = "Right trigger")]
+    RightTrigger,
+    Back,
+    Start,
+    #[strum(to_string = "Left thumb")]
+    LeftThumb,
+    #[strum(to_string = "Right thumb")]
+    RightThumb,
+    #[strum(to_string = "D-pad up")]
+    DpadUp,
+    #[strum(to_string = "D-pad down")]
+    DpadDown,
+    #[strum(to_string = "D-pad left")]
+    DpadLeft,
+    #[strum(to_string = "D-pad right")]
+    DpadRight,
+}
+
+/// Maps a keyboard `key` to a controller `button` for [`InputMethod::Gamepad`].
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct GamepadMapping {
+    pub key: KeyBinding,
+    pub button: GamepadButton,
+}
+
+/// A class of keys sharing the same [`KeyPressDuration`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, EnumIter, Display)]
+pub enum KeyPressClass {
+    /// `A` to `Z`.
+    Letter,
+    /// `0` to `9`.
+    Digit,
+    /// `F1` to `F12`.
+    Function,
+    /// Arrow keys.
+    Arrow,
+    /// `Ctrl`, `Shift` and `Alt`.
+    Modifier,
+    /// Any other key.
+    Other,
+}
+
+/// A normal distribution a key press duration is drawn from.
+///
+/// The duration is kept within one standard deviation of the mean.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyPressDuration {
+    pub mean_millis: u64,
+    pub std_millis: u64,
+}
+
+impl Default for KeyPressDuration {
+    fn default() -> Self {
+        Self {
+            mean_millis: 100,
+            std_millis: 20,
+        }
+    }
+}
+
+/// The [`KeyPressDuration`] of each [`KeyPressClass`].
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct KeyPressDurations {
+    #[serde(default)]
+    pub letter: KeyPressDuration,
+    #[serde(default)]
+    pub digit: KeyPressDuration,
+    #[serde(default)]
+    pub function: KeyPressDuration,
+    #[serde(default)]
+    pub arrow: KeyPressDuration,
+    #[serde(default)]
+    pub modifier: KeyPressDuration,
+    #[serde(default)]
+    pub other: KeyPressDuration,
+}
+
+impl KeyPressDurations {
+    pub fn get(&self, class: KeyPressClass) -> KeyPressDuration {
+        match class {
+            KeyPressClass::Letter => self.letter,
+            KeyPressClass::Digit => self.digit,
+            KeyPressClass::Function => self.function,
+            KeyPressClass::Arrow => self.arrow,
+            KeyPressClass::Modifier => self.modifier,
+            KeyPressClass::Other => self.other,
+        }
+    }
+
+    pub fn get_mut(&mut self, class: KeyPressClass) -> &mut KeyPressDuration {
+        match class {
+            KeyPressClass::Letter => &mut self.letter,
+            KeyPressClass::Digit => &mut self.digit,
+            KeyPressClass::Function => &mut self.function,
+            KeyPressClass::Arrow => &mut self.arrow,
+            KeyPressClass::Modifier => &mut self.modifier,
+            KeyPressClass::Other => &mut self.other,
+        }
+    }
+}
+
+/// How the bot reacts when other player is detected in the minimap.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum OtherPlayerReaction {
+    /// Continues as if there is no other player.
+    Continue,
+    /// Stops using actions marked as loud.
+    #[strum(to_string = "Pause loud skills")]
+    PauseLoudSkills,
+    /// Moves to another auto mobbing region.
+    #[strum(to_string = "Move to another region")]
+    MoveToAnotherRegion,
+    /// Changes channel after other player stays for a while.
+    #[default]
+    #[strum(to_string = "Change channel")]
+    ChangeChannel,
+}
+
+/// A map-wide announcement banner recognized through the user-supplied templates in
+/// [`Localization`](super::Localization).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize, EnumIter, Display)]
+pub enum AnnouncementKind {
+    #[strum(to_string = "World boss")]
+    WorldBoss,
+    Invasion,
+}
+
+/// How the bot reacts when an [`AnnouncementKind`] banner appears.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum AnnouncementReaction {
+    /// Continues as if there is no announcement.
+    #[default]
+    Continue,
+    /// Temporarily halts the bot.
+    Pause,
+    /// Changes channel.
+    #[strum(to_string = "Change channel")]
+    ChangeChannel,
+}
+
+/// The [`AnnouncementReaction`] of each [`AnnouncementKind`].
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct AnnouncementReactions {
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub world_boss: AnnouncementReaction,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub invasion: AnnouncementReaction,
+}
+
+impl AnnouncementReactions {
+    pub fn get(&self, kind: AnnouncementKind) -> AnnouncementReaction {
+        match kind {
+            AnnouncementKind::WorldBoss => self.world_boss,
+            AnnouncementKind::Invasion => self.invasion,
+        }
+    }
+}
+
+/// A global hotkey that triggers an action even when the game or this application is not focused.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Hotkey {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub key: KeyBinding,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub kind: HotkeyKind,
+    /// The character to switch to for [`HotkeyKind::SwitchCharacter`].
+    #[serde(default)]
+    pub character_id: Option<i64>,
+}
+
+/// What a [`Hotkey`] does when pressed.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum HotkeyKind {
+    /// Switches to a character.
+    #[default]
+    #[strum(to_string = "Switch character")]
+    SwitchCharacter,
+    /// Switches the current map rotation mode to auto-mobbing and back.
+    #[strum(to_string = "Toggle auto-mobbing")]
+    ToggleAutoMobbing,
+    /// Solves the rune now if there is one.
+    #[strum(to_string = "Solve rune now")]
+    SolveRune,
+}
+
+/// A time-of-day window that automatically controls the bot.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Schedule {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub kind: ScheduleKind,
+    /// The local time the schedule starts.
+    #[serde(default)]
+    pub start: ScheduleTime,
+    /// The local time the schedule ends.
+    ///
+    /// Ignored for [`ScheduleKind::SwitchMap`].
+    #[serde(default)]
+    pub end: ScheduleTime,
+    /// The map to switch to for [`ScheduleKind::SwitchMap`].
+    #[serde(default)]
+    pub map_id: Option<i64>,
+}
+
+/// What a [`Schedule`] does when its window starts and ends.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum ScheduleKind {
+    /// Runs the bot when the window starts and halts it when the window ends.
+    #[default]
+    Run,
+    /// Halts the bot when the window starts and runs it when the window ends.
+    Stop,
+    /// Switches to a map when the window starts.
+    #[strum(to_string = "Switch map")]
+    SwitchMap,
+}
+
+/// A routine run right after the daily server reset.
+///
+/// The weekly reset happens at the same time as a daily reset so it is handled the same way.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResetRoutine {
+    #[serde(default)]
+    pub enabled: bool,
+    /// The server reset time in UTC.
+    #[serde(default)]
+    pub time: ScheduleTime,
+    /// Minutes before and after the reset during which changing channel is avoided.
+    #[serde(default = "reset_window_minutes_default")]
+    pub window_minutes: u32,
+    /// Whether to use boosters again after the reset.
+    #[serde(default = "reset_routine_step_default")]
+    pub reapply_boosters: bool,
+    /// Whether to redo familiars swapping and essence replenishing after the reset.
+    #[serde(default = "reset_routine_step_default")]
+    pub reopen_familiars: bool,
+    /// Whether to restart the rotation from the first action after the reset.
+    #[serde(default = "reset_routine_step_default")]
+    pub restart_rotation: bool,
+}
+
+impl Default for ResetRoutine {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            time: ScheduleTime::default(),
+            window_minutes: reset_window_minutes_default(),
+            reapply_boosters: true,
+            reopen_familiars: true,
+            restart_rotation: true,
+        }
+    }
+}
+
+fn reset_window_minutes_default() -> u32 {
+    5
+}
+
+fn reset_routine_step_default() -> bool {
+    true
+}
+
+/// A local time of day with minute precision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ScheduleTime {
+    pub hour: u32,
+    pub minute: u32,
+}
+
+impl ScheduleTime {
+    /// Gets the minutes since midnight.
+    pub fn minutes_of_day(self) -> u32 {
+        (self.hour % 24) * 60 + self.minute % 60
+    }
+}
+
+/// How the game window focus is handled before sending inputs.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum FocusPolicy {
+    /// Inputs are sent as-is without checking the foreground window.
+    #[default]
+    None,
+    /// Brings the game window to the foreground before sending inputs.
+    #[strum(to_string = "Auto focus")]
+    AutoFocus,
+    /// Refuses to send inputs while another window is in the foreground.
+    #[strum(to_string = "Require focused")]
+    RequireFocused,
+}
+
+/// The keyboard layout of the game machine used to map keys by label.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum KeyboardLayout {
+    #[default]
+    #[strum(to_string = "QWERTY")]
+    Qwerty,
+    #[strum(to_string = "AZERTY")]
+    Azerty,
+    #[strum(to_string = "QWERTZ")]
+    Qwertz,
 }
 
 #[derive(
@@ -128,6 +626,23 @@ pub enum CycleRunStopMode {
     Repeat,
 }
 
+/// Identifies a chosen game window across restarts when multiple clients are running.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct CaptureWindowSelection {
+    /// The executable path of the process owning the window.
+    ///
+    /// Any process matches if [`None`].
+    #[serde(default)]
+    pub process_path: Option<String>,
+    /// A text the window title must contain.
+    #[serde(default)]
+    pub title_pattern: String,
+    /// The index among the windows matching both [`Self::process_path`] and
+    /// [`Self::title_pattern`].
+    #[serde(default)]
+    pub index: usize,
+}
+
 #[derive(
     Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
 )]
@@ -141,6 +656,11 @@ pub enum CaptureMode {
 
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct Notifications {
+    #[serde(
+        default,
+        serialize_with = "serialize_secret",
+        deserialize_with = "deserialize_secret"
+    )]
     pub discord_webhook_url: String,
     pub discord_user_id: String,
     pub notify_on_fail_or_change_map: bool,
@@ -154,4 +674,298 @@ pub struct Notifications {
     pub notify_on_lie_detector_appear: bool,
     #[serde(default)]
     pub notify_on_cycle_run_stop: bool,
+    #[serde(default)]
+    pub notify_on_session_limit: bool,
+    #[serde(default)]
+    pub notify_on_player_stuck: bool,
+    #[serde(default)]
+    pub notify_on_exp_rate_drop: bool,
+}
+
+/// Limits of a bot session after which the bot halts.
+///
+/// A session starts when the bot runs from halting and ends when it halts. A limit of `0` means
+/// no limit.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct SessionLimits {
+    pub enabled: bool,
+    /// Maximum elapsed milliseconds of a session.
+    pub max_duration_millis: u64,
+    /// Maximum number of runes solved in a session.
+    pub max_runes_solved: u32,
+    /// Maximum number of player deaths in a session.
+    pub max_deaths: u32,
+    /// Maximum number of channel changes in a session.
+    pub max_channel_changes: u32,
+    /// Whether to go to town after halting.
+    pub go_to_town: bool,
+}
+
+/// Saves annotated frames while running and stitches them into videos at the end of a session.
+///
+/// Besides the periodic snapshots, a frame is also saved for each notable event such as solving a
+/// rune or the player dying.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SessionTimelapse {
+    pub enabled: bool,
+    /// Milliseconds between each periodic snapshot.
+    pub interval_millis: u64,
+}
+
+impl Default for SessionTimelapse {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_millis: 10000,
+        }
+    }
+}
+
+/// Limits how often actions that are more likely to be flagged are used.
+///
+/// The limits apply regardless of what triggers the action (e.g. panic mode, announcements,
+/// unstucking or failed rune solving). A limited action is skipped.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RiskyActionLimits {
+    pub enabled: bool,
+    pub change_channel: RiskyActionLimit,
+    pub go_to_town: RiskyActionLimit,
+    /// Entering the cash shop after failing to solve runes.
+    pub cash_shop: RiskyActionLimit,
+}
+
+/// The limit of a single action in [`RiskyActionLimits`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RiskyActionLimit {
+    /// Minimum milliseconds between two uses.
+    pub min_interval_millis: u64,
+    /// Maximum random milliseconds added to [`Self::min_interval_millis`] after each use.
+    pub interval_jitter_millis: u64,
+    /// Maximum number of uses per day with `0` for no limit.
+    pub max_per_day: u32,
+}
+
+impl Default for RiskyActionLimit {
+    fn default() -> Self {
+        Self {
+            min_interval_millis: 300000,
+            interval_jitter_millis: 120000,
+            max_per_day: 0,
+        }
+    }
+}
+
+/// Temporarily halts the bot when a key or the mouse is physically used in the game.
+///
+/// This allows taking over the game for a moment without fighting the bot for control. The bot
+/// resumes once there is no physical input for [`Self::grace_millis`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PauseOnInput {
+    pub enabled: bool,
+    /// Milliseconds without physical input before resuming.
+    pub grace_millis: u64,
+}
+
+impl Default for PauseOnInput {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            grace_millis: 3000,
+        }
+    }
+}
+
+/// Alerts when the EXP gain rate drops compared to the session average.
+///
+/// A rate drop usually means the player is stuck somewhere that is not detected. The EXP is read
+/// from the EXP bar at the bottom of the game window. Mesos are not tracked as they are only shown
+/// in the inventory.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExpRateAlert {
+    pub enabled: bool,
+    /// The percentage of the session average rate the recent rate must stay above.
+    pub min_rate_percent: u32,
+    /// Milliseconds of the most recent EXP gain used to compute the recent rate.
+    pub window_millis: u64,
+}
+
+impl Default for ExpRateAlert {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_rate_percent: 50,
+            window_millis: 300000,
+        }
+    }
+}
+
+/// A user-provided HTTP endpoint that gates the use of actions referencing it by name.
+///
+/// The endpoint is polled with a `GET` request and allows the actions when it responds with a
+/// success status and a body other than `false` or `0`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConditionWebhook {
+    pub name: String,
+    pub url: String,
+    /// Milliseconds to wait for the response before treating the gate as closed.
+    pub timeout_millis: u64,
+    /// Milliseconds the last response is reused before polling the endpoint again.
+    pub cache_millis: u64,
+}
+
+impl Default for ConditionWebhook {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            url: String::new(),
+            timeout_millis: 2000,
+            cache_millis: 30000,
+        }
+    }
+}
+
+/// Requests the rune arrows from the user through the Discord bot after failing to solve runes.
+///
+/// A notification with the rune screenshot is sent and the bot waits for the arrows submitted
+/// with the `/rune` command before pressing them.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RemoteRuneSolving {
+    pub enabled: bool,
+    /// Number of consecutive failed rune solving before requesting the arrows.
+    pub failed_count: u32,
+    /// Milliseconds to wait for the arrows before giving up.
+    pub wait_millis: u64,
+}
+
+impl Default for RemoteRuneSolving {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            failed_count: 3,
+            wait_millis: 30000,
+        }
+    }
+}
+
+/// Number of attempts of each escalating unstucking stage.
+///
+/// The stages are tried in order from repositioning to returning to town. A stage with `0`
+/// attempts is skipped. The bot notifies and halts once all stages are exhausted.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnstuckLimits {
+    /// Attempts to move out of the minimap edges.
+    pub reposition_attempts: u32,
+    /// Attempts to press ESC to close any dialog.
+    pub esc_attempts: u32,
+    /// Attempts to jump in random directions.
+    pub random_jump_attempts: u32,
+    /// Attempts to go to town for navigation to bring the player back.
+    pub return_to_town_attempts: u32,
+    /// Whether moving back and forth or falling repeatedly without getting closer to the
+    /// destination also starts unstucking.
+    #[serde(default)]
+    pub detect_stuck_patterns: bool,
+}
+
+impl Default for UnstuckLimits {
+    fn default() -> Self {
+        Self {
+            reposition_attempts: 2,
+            esc_attempts: 2,
+            random_jump_attempts: 3,
+            return_to_town_attempts: 1,
+            detect_stuck_patterns: false,
+        }
+    }
+}
+
+/// Moves to the next farming map when the current map stays contested by other players.
+///
+/// The maps are cycled through in order using their linked navigation paths.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MapFailover {
+    pub enabled: bool,
+    /// Milliseconds other players must stay in the current map before moving to the next map.
+    pub contested_millis: u64,
+    /// The ids of the maps to cycle through.
+    pub map_ids: Vec<i64>,
+}
+
+impl Default for MapFailover {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            contested_millis: 600000, // 10 minutes
+            map_ids: vec![],
+        }
+    }
+}
+
+/// An optional WebDAV file the database is pushed to and pulled from for sharing the
+/// configuration between machines.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct ConfigSync {
+    /// The URL of the database file on the server, whose parent folder must already exist.
+    pub url: String,
+    pub username: String,
+    #[serde(
+        default,
+        serialize_with = "serialize_secret",
+        deserialize_with = "deserialize_secret"
+    )]
+    pub password: String,
+}
+
+/// An optional HTTP endpoint serving the bot metrics in Prometheus text format.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MetricsExport {
+    pub enabled: bool,
+    pub port: u16,
+    /// Whether to listen on all network interfaces instead of only localhost.
+    pub all_interfaces: bool,
+}
+
+impl Default for MetricsExport {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 9185,
+            all_interfaces: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn announcement_reactions_get_by_kind() {
+        let reactions = AnnouncementReactions {
+            world_boss: AnnouncementReaction::Pause,
+            invasion: AnnouncementReaction::ChangeChannel,
+        };
+
+        assert_eq!(
+            reactions.get(AnnouncementKind::WorldBoss),
+            AnnouncementReaction::Pause
+        );
+        assert_eq!(
+            reactions.get(AnnouncementKind::Invasion),
+            AnnouncementReaction::ChangeChannel
+        );
+    }
+
+    #[test]
+    fn announcement_reactions_deserialize_unknown_as_default() {
+        let reactions = serde_json::from_str::<AnnouncementReactions>(
+            r#"{"world_boss":"Pause","invasion":"Unknown"}"#,
+        )
+        .unwrap();
+        assert_eq!(reactions.world_boss, AnnouncementReaction::Pause);
+        assert_eq!(reactions.invasion, AnnouncementReaction::Continue);
+
+        let reactions = serde_json::from_str::<AnnouncementReactions>("{}").unwrap();
+        assert_eq!(reactions, AnnouncementReactions::default());
+    }
 }
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ActionKey, KeyBinding, LinkKeyBinding, LinkKeyChainStep, MAX_LINK_KEY_CHAIN_STEPS,
+    deserialize_with_ok_or_default, impl_identifiable,
+};
+
+/// A reusable key template shared by [`ActionKey`]s across maps.
+///
+/// An [`ActionKey`] referencing a template through [`ActionKey::template_id`] uses the key, link
+/// keys, count and waits of the template instead of its own so that updating the template
+/// updates all referencing actions.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct KeyTemplate {
+    #[serde(skip_serializing, default)]
+    pub id: Option<i64>,
+    pub name: String,
+    pub key: KeyBinding,
+    #[serde(default, deserialize_with = "deserialize_with_ok_or_default")]
+    pub link_key: LinkKeyBinding,
+    #[serde(default)]
+    pub link_key_chain: [LinkKeyChainStep; MAX_LINK_KEY_CHAIN_STEPS],
+    pub count: u32,
+    pub wait_before_use_millis: u64,
+    pub wait_before_use_millis_random_range: u64,
+    pub wait_after_use_millis: u64,
+    pub wait_after_use_millis_random_range: u64,
+}
+
+impl_identifiable!(KeyTemplate);
+
+impl KeyTemplate {
+    /// Overwrites the templated fields of `key` with this template.
+    pub fn apply_to(&self, key: &mut ActionKey) {
+        key.key = self.key;
+        key.link_key = self.link_key;
+        key.link_key_chain = self.link_key_chain;
+        key.count = self.count;
+        key.wait_before_use_millis = self.wait_before_use_millis;
+        key.wait_before_use_millis_random_range = self.wait_before_use_millis_random_range;
+        key.wait_after_use_millis = self.wait_after_use_millis;
+        key.wait_after_use_millis_random_range = self.wait_after_use_millis_random_range;
+    }
+}
+
+impl Default for KeyTemplate {
+    fn default() -> Self {
+        let key = ActionKey::default();
+
+        Self {
+            id: None,
+            name: String::new(),
+            key: key.key,
+            link_key: key.link_key,
+            link_key_chain: key.link_key_chain,
+            count: key.count,
+            wait_before_use_millis: key.wait_before_use_millis,
+            wait_before_use_millis_random_range: key.wait_before_use_millis_random_range,
+            wait_after_use_millis: key.wait_after_use_millis,
+            wait_after_use_millis_random_range: key.wait_after_use_millis_random_range,
+        }
+    }
+}
@@ -3,6 +3,7 @@ use std::{
     collections::{HashMap, HashSet},
     fmt::{Debug, Formatter},
     hash::Hash,
+    mem,
     rc::Rc,
     time::Instant,
 };
@@ -19,14 +20,14 @@ use opencv::{
 use tokio::sync::broadcast::Receiver;
 
 use crate::{
-    ActionKeyDirection, ActionKeyWith, NavigationPaths, Position, WaitAfterBuffered,
-    bridge::{KeyKind, LinkKeyKind},
+    NavigationPaths, clock,
     database::query_navigation_paths,
     detect::Detector,
     ecs::{Resources, WorldEvent},
     minimap::Minimap,
     models::{NavigationPath, NavigationTransition},
-    player::{Key, PlayerAction, PlayerContext},
+    player::{EnterPortal, PlayerAction, PlayerContext, Shop},
+    task::{Task, Update, update_task},
 };
 
 /// A data source to query [`NavigationPath`].
@@ -70,6 +71,25 @@ impl Debug for Path {
     }
 }
 
+/// Snapshots of a [`Path`] for matching against the current minimap on a background thread.
+struct PathSnapshot {
+    id: String,
+    minimap_snapshot_base64: String,
+    minimap_snapshot_grayscale: bool,
+    name_snapshot_base64: String,
+}
+
+/// Where a candidate base path to update the current path from comes from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PathCandidateSource {
+    /// The next path of the last navigation point.
+    NextPath,
+    /// The previous [`DefaultNavigator::base_path`].
+    BasePath,
+    /// A path tree built from database.
+    Database,
+}
+
 /// Internal representation of [`NavigationPoint`].
 #[derive(Debug, Clone)]
 struct Point {
@@ -77,15 +97,23 @@ struct Point {
     x: i32,
     y: i32,
     transition: NavigationTransition,
+    transition_window_millis: u64,
 }
 
-/// Next point computation state to navigate the player to [`Navigator::destination_path_id`].
+/// Next point computation state to navigate the player to [`Navigator::detour_path_id`] or
+/// [`Navigator::destination_path_id`].
 #[derive(Debug, Clone)]
 enum PointState {
     Dirty,
     Completed,
     Unreachable,
-    Next(i32, i32, NavigationTransition, Option<Rc<RefCell<Path>>>),
+    Next(
+        i32,
+        i32,
+        NavigationTransition,
+        u64,
+        Option<Rc<RefCell<Path>>>,
+    ),
 }
 
 /// Update state when [`Navigator::path_dirty`] is `true`.
@@ -101,6 +129,9 @@ enum UpdateState {
 pub trait Navigator: Debug + 'static {
     /// Navigates the player to the currently set [`Self::destination_path_id`].
     ///
+    /// If there is a [`PlayerContext::shop_detour`], the player is navigated to the NPC map first
+    /// and the [`PlayerAction::Shop`] is set again once reached.
+    ///
     /// Returns `true` if the player has reached the destination.
     fn navigate_player(
         &mut self,
@@ -138,11 +169,20 @@ pub struct DefaultNavigator {
     path_dirty: bool,
     /// Number of times to retry updating when paths are dirty.
     path_dirty_retry_count: u32,
-    /// Last time an update attempt was made.
+    /// Last time an update attempt was completed.
     path_last_update: Instant,
+    /// Task to match the current minimap against [`Self::path_candidates`].
+    ///
+    /// The result is the index of the matched candidate and the matched path id.
+    path_task: Option<Task<Result<Option<(usize, String)>>>>,
+    /// Candidate base paths to update the current path from in order of preference.
+    path_candidates: Vec<(Rc<RefCell<Path>>, PathCandidateSource)>,
     /// Cached next point navigation computation.
     last_point_state: Option<PointState>,
     destination_path_id: Option<String>,
+    /// The path of the [`PlayerContext::shop_detour`] map to navigate to before
+    /// [`Self::destination_path_id`].
+    detour_path_id: Option<String>,
     event_receiver: Receiver<WorldEvent>,
 }
 
@@ -161,9 +201,12 @@ impl DefaultNavigator {
             current_path: None,
             path_dirty: true,
             path_dirty_retry_count: 0,
-            path_last_update: Instant::now(),
+            path_last_update: clock::now(),
+            path_task: None,
+            path_candidates: vec![],
             last_point_state: None,
             destination_path_id: None,
+            detour_path_id: None,
             event_receiver,
         }
     }
@@ -248,12 +291,16 @@ impl DefaultNavigator {
         // Re-use cached point
         if matches!(
             self.last_point_state,
-            Some(PointState::Next(_, _, _, _) | PointState::Completed | PointState::Unreachable)
+            Some(PointState::Next(_, _, _, _, _) | PointState::Completed | PointState::Unreachable)
         ) {
             return self.last_point_state.clone().expect("has value");
         }
 
-        let path_id = self.destination_path_id.clone().expect("has value");
+        let path_id = self
+            .detour_path_id
+            .clone()
+            .or_else(|| self.destination_path_id.clone())
+            .expect("has value");
         if self
             .current_path
             .as_ref()
@@ -267,11 +314,16 @@ impl DefaultNavigator {
             .clone()
             .and_then(|path| search_point(path, path_id))
             .map_or(PointState::Unreachable, |point| {
-                PointState::Next(point.x, point.y, point.transition, point.next_path.clone())
+                PointState::Next(
+                    point.x,
+                    point.y,
+                    point.transition,
+                    point.transition_window_millis,
+                    point.next_path.clone(),
+                )
             })
     }
 
-    // TODO: Do this on background thread?
     fn update_current_path_from_current_location(
         &mut self,
         resources: &Resources,
@@ -283,88 +335,104 @@ impl DefaultNavigator {
             Minimap::Idle(idle) => idle.bbox,
             Minimap::Detecting => return UpdateState::Pending,
         };
-        let instant = Instant::now();
-        if instant.duration_since(self.path_last_update).as_secs() < UPDATE_INTERVAL_SECS {
-            return UpdateState::Pending;
-        }
-        self.path_last_update = instant;
-        debug!(target: "navigator", "updating current path from current location...");
-
-        let detector = resources
-            .detector
+        let has_pending_task = self
+            .path_task
             .as_ref()
-            .expect("detector must available because minimap is idle")
-            .as_ref();
-        let Ok(minimap_name_bbox) = detector.detect_minimap_name(minimap_bbox) else {
-            return UpdateState::NoMatch;
-        };
-
-        // Try from next_path if previously exists due to player navigating
-        if let Some(PointState::Next(_, _, _, Some(next_path))) = self.last_point_state.take()
-            && let Ok(current_path) =
-                find_current_from_base_path(next_path, detector, minimap_bbox, minimap_name_bbox)
+            .is_some_and(|task| !task.completed());
+        if !has_pending_task
+            && clock::now().duration_since(self.path_last_update).as_secs() < UPDATE_INTERVAL_SECS
         {
-            info!(target: "navigator", "current path updated from previous point's next path");
-            self.current_path = Some(current_path);
-            return UpdateState::Completed;
+            return UpdateState::Pending;
         }
 
-        // Try from base_path if previously exists
-        if let Some(base_path) = self.base_path.clone() {
-            if let Ok(current_path) =
-                find_current_from_base_path(base_path, detector, minimap_bbox, minimap_name_bbox)
-            {
-                info!(target: "navigator", "current path updated from previous base path");
-                self.current_path = Some(current_path);
-                return UpdateState::Completed;
-            } else {
-                self.base_path = None;
-                self.current_path = None;
-            }
+        // The matching is done on a background thread with only the snapshots because paths are
+        // not sendable. The candidates are kept to resolve the matched path id when completed.
+        let update = update_task(
+            0,
+            &mut self.path_task,
+            || {
+                debug!(target: "navigator", "updating current path from current location...");
+                self.path_candidates = path_candidates(
+                    self.last_point_state.take(),
+                    self.base_path.clone(),
+                    self.source.as_ref(),
+                );
+                let snapshots = self
+                    .path_candidates
+                    .iter()
+                    .map(|(path, _)| path_snapshots(path.clone()))
+                    .collect::<Vec<_>>();
+
+                (resources.detector_cloned(), snapshots)
+            },
+            move |(detector, snapshots)| {
+                let minimap_name_bbox = detector.detect_minimap_name(minimap_bbox)?;
+
+                Ok(snapshots.iter().enumerate().find_map(|(index, snapshots)| {
+                    let id = find_best_matching_snapshot_id(
+                        snapshots,
+                        detector.as_ref(),
+                        minimap_bbox,
+                        minimap_name_bbox,
+                    )
+                    .ok()?;
+                    Some((index, id))
+                }))
+            },
+        );
+        if matches!(update, Update::Pending) {
+            return UpdateState::Pending;
         }
+        self.path_last_update = clock::now();
+
+        let candidates = mem::take(&mut self.path_candidates);
+        match update {
+            Update::Ok(Some((index, id))) => {
+                let (base_path, source) = candidates.into_iter().nth(index).expect("exists");
+                match source {
+                    PathCandidateSource::NextPath => {
+                        info!(target: "navigator", "current path updated from previous point's next path");
+                    }
+                    PathCandidateSource::BasePath => {
+                        info!(target: "navigator", "current path updated from previous base path");
+                    }
+                    PathCandidateSource::Database => {
+                        info!(target: "navigator", "current path updated from database");
+                        self.base_path = Some(base_path.clone());
+                    }
+                }
+                self.current_path = find_path(base_path, &id);
 
-        // Query from database
-        let paths = self
-            .source
-            .query_paths()
-            .unwrap_or_default()
-            .into_iter()
-            .flat_map(|paths| {
-                let paths_id = paths.id.expect("valid id");
-                paths
-                    .paths
-                    .into_iter()
-                    .enumerate()
-                    .map(move |(index, path)| (path_id_from_paths_id_index(paths_id, index), path))
-            })
-            .collect::<HashMap<_, _>>();
-        let mut visited_ids = HashSet::new();
+                UpdateState::Completed
+            }
+            Update::Ok(None) => {
+                if candidates
+                    .iter()
+                    .any(|(_, source)| *source == PathCandidateSource::BasePath)
+                {
+                    self.base_path = None;
+                    self.current_path = None;
+                }
 
-        for path_id in paths.keys() {
-            if !visited_ids.insert(path_id.clone()) {
-                continue;
+                UpdateState::NoMatch
             }
-            let Ok((base_path, visited)) = build_base_path_from(&paths, path_id.clone()) else {
-                continue;
-            };
-            visited_ids.extend(visited);
-
-            let Ok(current_path) = find_current_from_base_path(
-                base_path.clone(),
-                detector,
-                minimap_bbox,
-                minimap_name_bbox,
-            ) else {
-                continue;
-            };
-            info!(target: "navigator", "current path updated from database");
-
-            self.base_path = Some(base_path);
-            self.current_path = Some(current_path);
-            return UpdateState::Completed;
+            Update::Err(_) => UpdateState::NoMatch,
+            Update::Pending => unreachable!(),
         }
+    }
 
-        UpdateState::NoMatch
+    /// Updates [`Self::detour_path_id`] from the `shop` detour.
+    ///
+    /// The paths are recomputed when the detour changed.
+    #[inline]
+    fn update_detour(&mut self, shop: Option<Shop>) {
+        let detour_path_id = shop
+            .and_then(|shop| shop.paths_id_index)
+            .map(|(id, index)| path_id_from_paths_id_index(id, index));
+        if detour_path_id != self.detour_path_id {
+            self.detour_path_id = detour_path_id;
+            self.mark_dirty(false);
+        }
     }
 
     #[inline]
@@ -383,7 +451,11 @@ impl Navigator for DefaultNavigator {
         player_context: &mut PlayerContext,
         minimap_state: Minimap,
     ) -> bool {
-        if self.destination_path_id.is_none() || resources.operation.halting() {
+        let detour = player_context.shop_detour();
+        self.update_detour(detour);
+        if (self.destination_path_id.is_none() && self.detour_path_id.is_none())
+            || resources.operation.halting()
+        {
             return true;
         }
 
@@ -403,35 +475,36 @@ impl Navigator for DefaultNavigator {
                 }
                 false
             }
-            PointState::Completed | PointState::Unreachable => true,
-            PointState::Next(x, y, transition, _) => {
-                match transition {
-                    NavigationTransition::Portal => {
-                        if !player_context.has_priority_action() {
-                            let position = Position {
-                                x,
-                                y,
-                                x_random_range: 0,
-                                allow_adjusting: true,
-                            };
-                            let key = Key {
-                                key: KeyKind::Up,
-                                key_hold_ticks: 0,
-                                key_hold_buffered_to_wait_after: false,
-                                link_key: LinkKeyKind::None,
-                                count: 1,
-                                position: Some(position),
-                                direction: ActionKeyDirection::Any,
-                                with: ActionKeyWith::Stationary,
-                                wait_before_use_ticks: 5,
-                                wait_before_use_ticks_random_range: 0,
-                                wait_after_use_ticks: 0,
-                                wait_after_use_ticks_random_range: 0,
-                                wait_after_buffered: WaitAfterBuffered::None,
-                            };
-                            player_context.set_priority_action(None, PlayerAction::Key(key));
-                        }
-                    }
+            PointState::Completed => {
+                let Some(shop) = detour else {
+                    return true;
+                };
+                // Queues the shop action again now that the player is on the NPC map
+                if !player_context.has_priority_action() {
+                    player_context.set_priority_action(None, PlayerAction::Shop(shop));
+                }
+
+                false
+            }
+            PointState::Unreachable => {
+                if detour.is_some() {
+                    info!(target: "navigator", "shop NPC map is unreachable, skipping shop");
+                    player_context.clear_shop_detour();
+                }
+
+                true
+            }
+            PointState::Next(x, y, transition, window_millis, _) => {
+                if !player_context.has_priority_action() {
+                    let window_millis = match transition {
+                        NavigationTransition::Portal => 0,
+                        NavigationTransition::TimedPortal => window_millis,
+                    };
+                    let portal = EnterPortal {
+                        position: opencv::core::Point::new(x, y),
+                        window_millis,
+                    };
+                    player_context.set_priority_action(None, PlayerAction::EnterPortal(portal));
                 }
 
                 false
@@ -443,7 +516,7 @@ impl Navigator for DefaultNavigator {
     fn was_last_point_available_or_completed(&self) -> bool {
         matches!(
             self.last_point_state,
-            Some(PointState::Next(_, _, _, _) | PointState::Completed)
+            Some(PointState::Next(_, _, _, _, _) | PointState::Completed)
         )
     }
 
@@ -451,6 +524,9 @@ impl Navigator for DefaultNavigator {
     fn mark_dirty(&mut self, invalidate_cache: bool) {
         self.path_dirty = true;
         self.path_dirty_retry_count = 0;
+        // Any pending match is for the previous minimap
+        self.path_task = None;
+        self.path_candidates.clear();
         if invalidate_cache {
             self.base_path = None;
             self.current_path = None;
@@ -526,6 +602,7 @@ fn build_base_path_from(
                     x: point.x,
                     y: point.y,
                     transition: point.transition,
+                    transition_window_millis: point.transition_window_millis,
                 });
             }
 
@@ -539,13 +616,119 @@ fn build_base_path_from(
     ))
 }
 
-fn find_current_from_base_path(
-    base_path: Rc<RefCell<Path>>,
+/// Collects the candidate base paths to update the current path from in order of preference.
+///
+/// The candidates are the next path of `last_point_state`, `base_path` and then all path trees
+/// built from the paths queried from `source`.
+fn path_candidates(
+    last_point_state: Option<PointState>,
+    base_path: Option<Rc<RefCell<Path>>>,
+    source: &dyn NavigatorDataSource,
+) -> Vec<(Rc<RefCell<Path>>, PathCandidateSource)> {
+    let mut candidates = vec![];
+    if let Some(PointState::Next(_, _, _, _, Some(next_path))) = last_point_state {
+        candidates.push((next_path, PathCandidateSource::NextPath));
+    }
+    if let Some(base_path) = base_path {
+        candidates.push((base_path, PathCandidateSource::BasePath));
+    }
+
+    let paths = source
+        .query_paths()
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|paths| {
+            let paths_id = paths.id.expect("valid id");
+            paths
+                .paths
+                .into_iter()
+                .enumerate()
+                .map(move |(index, path)| (path_id_from_paths_id_index(paths_id, index), path))
+        })
+        .collect::<HashMap<_, _>>();
+    let mut visited_ids = HashSet::new();
+
+    for path_id in paths.keys() {
+        if !visited_ids.insert(path_id.clone()) {
+            continue;
+        }
+        let Ok((base_path, visited)) = build_base_path_from(&paths, path_id.clone()) else {
+            continue;
+        };
+        visited_ids.extend(visited);
+        candidates.push((base_path, PathCandidateSource::Database));
+    }
+
+    candidates
+}
+
+/// Collects the [`PathSnapshot`]s of all paths reachable from `base_path`.
+fn path_snapshots(base_path: Rc<RefCell<Path>>) -> Vec<PathSnapshot> {
+    let mut snapshots = vec![];
+
+    dfs(
+        base_path,
+        |path| path.borrow().id.clone(),
+        |path| {
+            path.borrow()
+                .points
+                .iter()
+                .filter_map(|point| point.next_path.clone())
+                .collect()
+        },
+        |path| {
+            let path = path.borrow();
+            snapshots.push(PathSnapshot {
+                id: path.id.clone(),
+                minimap_snapshot_base64: path.minimap_snapshot_base64.clone(),
+                minimap_snapshot_grayscale: path.minimap_snapshot_grayscale,
+                name_snapshot_base64: path.name_snapshot_base64.clone(),
+            });
+
+            true
+        },
+    );
+
+    snapshots
+}
+
+/// Finds the id of the snapshot in `snapshots` that best matches the current minimap.
+fn find_best_matching_snapshot_id(
+    snapshots: &[PathSnapshot],
     detector: &dyn Detector,
     minimap_bbox: Rect,
     minimap_name_bbox: Rect,
-) -> Result<Rc<RefCell<Path>>> {
-    let mut matches = vec![];
+) -> Result<String> {
+    snapshots
+        .iter()
+        .filter_map(|snapshot| {
+            let name_mat = decode_base64_to_mat(&snapshot.name_snapshot_base64, true).ok()?;
+            let minimap_mat = decode_base64_to_mat(
+                &snapshot.minimap_snapshot_base64,
+                snapshot.minimap_snapshot_grayscale,
+            )
+            .ok()?;
+            let score = detector
+                .detect_minimap_match(
+                    &minimap_mat,
+                    snapshot.minimap_snapshot_grayscale,
+                    &name_mat,
+                    minimap_bbox,
+                    minimap_name_bbox,
+                )
+                .ok()?;
+            debug!(target: "navigator", "candidate path found with score {score}");
+
+            Some((score, snapshot))
+        })
+        .max_by(|(first_score, _), (second_score, _)| first_score.total_cmp(second_score))
+        .map(|(_, snapshot)| snapshot.id.clone())
+        .ok_or(anyhow!("unable to determine current path"))
+}
+
+/// Finds the path with `id` reachable from `base_path`.
+fn find_path(base_path: Rc<RefCell<Path>>, id: &str) -> Option<Rc<RefCell<Path>>> {
+    let mut found = None;
 
     dfs(
         base_path,
@@ -558,37 +741,60 @@ fn find_current_from_base_path(
                 .collect()
         },
         |path| {
-            let path_borrow = path.borrow();
-            let Ok(name_mat) = decode_base64_to_mat(&path_borrow.name_snapshot_base64, true) else {
+            if path.borrow().id == id {
+                found = Some(path.clone());
                 return false;
-            };
-            let Ok(minimap_mat) = decode_base64_to_mat(
-                &path_borrow.minimap_snapshot_base64,
-                path_borrow.minimap_snapshot_grayscale,
-            ) else {
-                return false;
-            };
-
-            if let Ok(score) = detector.detect_minimap_match(
-                &minimap_mat,
-                path_borrow.minimap_snapshot_grayscale,
-                &name_mat,
-                minimap_bbox,
-                minimap_name_bbox,
-            ) {
-                debug!(target: "navigator", "candidate path found with score {score}");
-                matches.push((score, path.clone()));
             }
 
             true
         },
     );
 
-    matches
-        .into_iter()
+    found
+}
+
+/// Finds the [`NavigationPath`] in `paths` whose snapshots best match the current minimap.
+///
+/// Returns the `(paths_id, index)` of the best matched [`NavigationPath`].
+pub fn find_best_matching_paths_id_index(
+    paths: &[NavigationPaths],
+    detector: &dyn Detector,
+    minimap_bbox: Rect,
+    minimap_name_bbox: Rect,
+) -> Result<(i64, usize)> {
+    paths
+        .iter()
+        .filter_map(|paths| Some((paths.id?, paths)))
+        .flat_map(|(paths_id, paths)| {
+            paths
+                .paths
+                .iter()
+                .enumerate()
+                .map(move |(index, path)| ((paths_id, index), path))
+        })
+        .filter_map(|(paths_id_index, path)| {
+            let name_mat = decode_base64_to_mat(&path.name_snapshot_base64, true).ok()?;
+            let minimap_mat = decode_base64_to_mat(
+                &path.minimap_snapshot_base64,
+                path.minimap_snapshot_grayscale,
+            )
+            .ok()?;
+            let score = detector
+                .detect_minimap_match(
+                    &minimap_mat,
+                    path.minimap_snapshot_grayscale,
+                    &name_mat,
+                    minimap_bbox,
+                    minimap_name_bbox,
+                )
+                .ok()?;
+            debug!(target: "navigator", "candidate path {paths_id_index:?} found with score {score}");
+
+            Some((score, paths_id_index))
+        })
         .max_by(|(first_score, _), (second_score, _)| first_score.total_cmp(second_score))
-        .map(|(_, path)| path)
-        .ok_or(anyhow!("unable to determine current path"))
+        .map(|(_, paths_id_index)| paths_id_index)
+        .ok_or(anyhow!("unable to find matching path"))
 }
 
 fn decode_base64_to_mat(base64: &str, grayscale: bool) -> Result<Mat> {
@@ -641,7 +847,10 @@ mod tests {
     use tokio::sync::broadcast::channel;
 
     use super::*;
-    use crate::{detect::MockDetector, minimap::MinimapIdle, models::NavigationPoint};
+    use crate::{
+        detect::MockDetector, minimap::MinimapIdle, models::NavigationPoint,
+        task::set_synchronous_tasks,
+    };
 
     impl Default for DefaultNavigator {
         fn default() -> Self {
@@ -676,6 +885,7 @@ mod tests {
             x: 30,
             y: 30,
             transition: NavigationTransition::Portal,
+            transition_window_millis: 0,
         }]);
 
         let path_a_id = 1;
@@ -687,12 +897,14 @@ mod tests {
                 x: 20,
                 y: 20,
                 transition: NavigationTransition::Portal,
+                transition_window_millis: 0,
             },
             NavigationPoint {
                 next_paths_id_index: Some((path_a_id, 0)),
                 x: 10,
                 y: 10,
                 transition: NavigationTransition::Portal,
+                transition_window_millis: 0,
             },
         ]);
 
@@ -703,12 +915,14 @@ mod tests {
                 x: 11,
                 y: 10,
                 transition: NavigationTransition::Portal,
+                transition_window_millis: 0,
             },
             NavigationPoint {
                 next_paths_id_index: Some((path_b_id, 0)),
                 x: 10,
                 y: 10,
                 transition: NavigationTransition::Portal,
+                transition_window_millis: 0,
             },
         ]);
 
@@ -818,6 +1032,7 @@ mod tests {
             x: 100,
             y: 200,
             transition: NavigationTransition::Portal,
+            transition_window_millis: 0,
             next_path: Some(Rc::new(RefCell::new(target_path.clone()))),
         };
         let path = Path {
@@ -834,7 +1049,7 @@ mod tests {
         let result = navigator.compute_next_point();
 
         match result {
-            PointState::Next(x, y, transition, Some(next_path)) => {
+            PointState::Next(x, y, transition, _, Some(next_path)) => {
                 assert_eq!(x, 100);
                 assert_eq!(y, 200);
                 assert_eq!(transition, NavigationTransition::Portal);
@@ -864,6 +1079,57 @@ mod tests {
         assert!(matches!(result, PointState::Unreachable));
     }
 
+    #[test]
+    fn compute_next_point_prefers_detour_over_destination() {
+        let mut navigator = DefaultNavigator::default();
+        let detour_path = Path {
+            id: path_id_from_paths_id_index(2, 0),
+            minimap_snapshot_base64: "".into(),
+            name_snapshot_base64: "".into(),
+            minimap_snapshot_grayscale: false,
+            points: vec![],
+        };
+        let path = Path {
+            id: path_id_from_paths_id_index(1, 0),
+            minimap_snapshot_base64: "".into(),
+            name_snapshot_base64: "".into(),
+            minimap_snapshot_grayscale: false,
+            points: vec![Point {
+                x: 10,
+                y: 20,
+                transition: NavigationTransition::Portal,
+                transition_window_millis: 0,
+                next_path: Some(Rc::new(RefCell::new(detour_path))),
+            }],
+        };
+        navigator.current_path = Some(Rc::new(RefCell::new(path)));
+        navigator.destination_path_id = Some(path_id_from_paths_id_index(1, 0));
+        navigator.path_dirty = false;
+        let shop = Shop {
+            position: opencv::core::Point::new(5, 5),
+            paths_id_index: Some((2, 0)),
+            open_key: crate::bridge::KeyKind::Y,
+            tab: opencv::core::Point::default(),
+            item: opencv::core::Point::default(),
+            quantity: 1,
+        };
+
+        assert_matches!(navigator.compute_next_point(), PointState::Completed);
+
+        navigator.update_detour(Some(shop));
+        assert!(navigator.path_dirty);
+
+        navigator.path_dirty = false;
+        assert_matches!(
+            navigator.compute_next_point(),
+            PointState::Next(10, 20, NavigationTransition::Portal, 0, Some(_))
+        );
+
+        navigator.update_detour(None);
+        assert!(navigator.detour_path_id.is_none());
+        assert!(navigator.path_dirty);
+    }
+
     #[test]
     fn update_current_path_from_current_location_success() {
         let minimap_bbox = Rect::new(0, 0, 10, 10);
@@ -885,6 +1151,7 @@ mod tests {
             x: 5,
             y: 5,
             transition: NavigationTransition::Portal,
+            transition_window_millis: 0,
         };
 
         let mock_path = mock_navigation_path(vec![point]);
@@ -905,9 +1172,14 @@ mod tests {
         // Force update
         navigator.path_last_update = Instant::now() - std::time::Duration::from_secs(10);
 
+        set_synchronous_tasks(true);
+        let pending =
+            navigator.update_current_path_from_current_location(&resources, Minimap::Idle(minimap));
         let result =
             navigator.update_current_path_from_current_location(&resources, Minimap::Idle(minimap));
+        set_synchronous_tasks(false);
 
+        assert_matches!(pending, UpdateState::Pending);
         assert_matches!(result, UpdateState::Completed);
         assert!(navigator.current_path.is_some());
         assert!(navigator.base_path.is_some());
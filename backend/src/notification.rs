@@ -21,7 +21,7 @@ use tokio::{
     time::{Instant, sleep},
 };
 
-use crate::Settings;
+use crate::{Settings, models::is_undecrypted_secret};
 
 static TRUE: bool = true;
 static FALSE: bool = false;
@@ -39,6 +39,10 @@ pub enum NotificationKind {
     LieDetectorAppear,
     CycledToHalt,
     CycledToRun,
+    SessionLimitReached,
+    PlayerStuck,
+    RuneArrowsRequested,
+    ExpRateDropped,
 }
 
 impl NotificationKind {
@@ -65,6 +69,10 @@ impl NotificationKind {
             NotificationKind::CycledToHalt | NotificationKind::CycledToRun => {
                 settings.notifications.notify_on_cycle_run_stop
             }
+            NotificationKind::SessionLimitReached => settings.notifications.notify_on_session_limit,
+            NotificationKind::PlayerStuck => settings.notifications.notify_on_player_stuck,
+            NotificationKind::ExpRateDropped => settings.notifications.notify_on_exp_rate_drop,
+            NotificationKind::RuneArrowsRequested => settings.remote_rune_solving.enabled,
         }
     }
 
@@ -114,6 +122,20 @@ impl NotificationKind {
             NotificationKind::CycledToHalt => {
                 format!("{user_id}Bot has cycled to stop.")
             }
+            NotificationKind::SessionLimitReached => {
+                format!("{user_id}Bot has stopped because a session limit is reached.")
+            }
+            NotificationKind::PlayerStuck => {
+                format!("{user_id}Bot has stopped because the player is stuck.")
+            }
+            NotificationKind::RuneArrowsRequested => {
+                format!(
+                    "{user_id}Bot needs help solving the rune. Submit the arrows with `/rune` (e.g. `up down left right`)."
+                )
+            }
+            NotificationKind::ExpRateDropped => {
+                format!("{user_id}The EXP rate has dropped below the session average")
+            }
         }
     }
 
@@ -125,12 +147,21 @@ impl NotificationKind {
             ],
             NotificationKind::CycledToHalt
             | NotificationKind::CycledToRun
+            | NotificationKind::SessionLimitReached
+            | NotificationKind::PlayerStuck
+            | NotificationKind::ExpRateDropped
             | NotificationKind::EliteBossAppear
             | NotificationKind::PlayerIsDead
             | NotificationKind::PlayerGuildieAppear
             | NotificationKind::PlayerStrangerAppear
             | NotificationKind::PlayerFriendAppear => vec![ScheduledFrame::new_deadline(2)],
-            NotificationKind::RuneAppear | NotificationKind::LieDetectorAppear => {
+            // Captures the exact frame the rune is detected in and another one shortly after
+            // to help deciding whether to intervene remotely
+            NotificationKind::RuneAppear => vec![
+                ScheduledFrame::new_deadline(0),
+                ScheduledFrame::new_deadline(2),
+            ],
+            NotificationKind::LieDetectorAppear | NotificationKind::RuneArrowsRequested => {
                 vec![ScheduledFrame::new_deadline(1)]
             }
         }
@@ -141,13 +172,16 @@ impl NotificationKind {
             NotificationKind::FailOrMapChange => 5,
             NotificationKind::CycledToHalt
             | NotificationKind::CycledToRun
+            | NotificationKind::SessionLimitReached
+            | NotificationKind::PlayerStuck
+            | NotificationKind::ExpRateDropped
             | NotificationKind::EliteBossAppear
             | NotificationKind::PlayerIsDead
             | NotificationKind::PlayerGuildieAppear
             | NotificationKind::PlayerStrangerAppear
             | NotificationKind::PlayerFriendAppear
             | NotificationKind::RuneAppear => 3,
-            NotificationKind::LieDetectorAppear => 2,
+            NotificationKind::LieDetectorAppear | NotificationKind::RuneArrowsRequested => 2,
         };
 
         Duration::from_secs(secs)
@@ -241,6 +275,9 @@ impl DiscordNotification {
         if settings.notifications.discord_webhook_url.is_empty() {
             bail!("webhook url not provided");
         }
+        if is_undecrypted_secret(&settings.notifications.discord_webhook_url) {
+            bail!("webhook url cannot be decrypted");
+        }
 
         {
             let mut pending = self.pending.lock().unwrap();
@@ -454,4 +491,38 @@ mod test {
         assert!(scheduled.frames[1].inner.is_some());
         assert!(scheduled.frames[2].inner.is_some());
     }
+
+    #[tokio::test(start_paused = true)]
+    #[allow(clippy::await_holding_lock)]
+    async fn update_rune_appear_captures_detection_frame() {
+        let noti = DiscordNotification::new(Rc::new(RefCell::new(Settings {
+            notifications: Notifications {
+                discord_webhook_url: "https://discord.com/api/webhooks/foo/bar".to_string(),
+                notify_on_rune_appear: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        })));
+
+        assert!(
+            noti.schedule_notification(NotificationKind::RuneAppear)
+                .is_ok()
+        );
+        noti.update(Some(
+            &OwnedMat::from(Mat::zeros(1, 1, CV_8UC4).unwrap().to_mat().unwrap()).as_mat(),
+        ));
+        let scheduled_guard = noti.scheduled.lock().unwrap();
+        let scheduled = scheduled_guard.first().unwrap();
+        assert!(scheduled.frames[0].inner.is_some());
+        assert!(scheduled.frames[1].inner.is_none());
+        drop(scheduled_guard);
+
+        advance(Duration::from_secs(1)).await;
+        noti.update(Some(
+            &OwnedMat::from(Mat::zeros(1, 1, CV_8UC4).unwrap().to_mat().unwrap()).as_mat(),
+        ));
+        let scheduled = noti.scheduled.lock().unwrap();
+        let scheduled = scheduled.first().unwrap();
+        assert!(scheduled.frames[1].inner.is_some());
+    }
 }
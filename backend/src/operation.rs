@@ -6,6 +6,7 @@ use std::time::Instant;
 
 use crate::BotOperationUpdate;
 use crate::CycleRunStopMode;
+use crate::clock;
 
 /// Current operating state of the bot.
 #[derive(Debug, Clone, Copy)]
@@ -29,6 +30,8 @@ pub enum Operation {
         stop_duration_millis: u64,
         once: bool,
     },
+    /// Running until the player finishes the currently executing action then halts.
+    Stopping,
 }
 
 impl Operation {
@@ -59,7 +62,7 @@ impl Operation {
                 } = self
                 {
                     Operation::TemporaryHalting {
-                        resume: instant.saturating_duration_since(Instant::now()),
+                        resume: instant.saturating_duration_since(clock::now()),
                         run_duration_millis,
                         stop_duration_millis: update_from_bot_update_and_mode,
                         once,
@@ -77,7 +80,7 @@ impl Operation {
                 } = self
                 {
                     Operation::RunUntil {
-                        instant: Instant::now() + resume,
+                        instant: clock::now() + resume,
                         run_duration_millis,
                         stop_duration_millis,
                         once,
@@ -91,6 +94,13 @@ impl Operation {
                 }
             }
             (BotOperationUpdate::Run, CycleRunStopMode::None) => Operation::Running,
+            (BotOperationUpdate::SoftHalt, _) => {
+                if self.halting() {
+                    self
+                } else {
+                    Operation::Stopping
+                }
+            }
         }
     }
 
@@ -132,6 +142,7 @@ impl Operation {
                 }
             }
             Operation::Halting => Operation::Halting,
+            Operation::Stopping => Operation::Stopping,
             Operation::Running | Operation::RunUntil { .. } => match mode {
                 CycleRunStopMode::None => Operation::Running,
                 CycleRunStopMode::Once | CycleRunStopMode::Repeat => run_until(
@@ -144,7 +155,7 @@ impl Operation {
     }
 
     pub fn update_tick(self) -> Operation {
-        let now = Instant::now();
+        let now = clock::now();
         match self {
             Operation::HaltUntil {
                 instant,
@@ -171,7 +182,10 @@ impl Operation {
                     halt_until(run_duration_millis, stop_duration_millis)
                 }
             }
-            Operation::Halting | Operation::TemporaryHalting { .. } | Operation::Running => self,
+            Operation::Halting
+            | Operation::TemporaryHalting { .. }
+            | Operation::Running
+            | Operation::Stopping => self,
         }
     }
 }
@@ -189,6 +203,7 @@ impl Display for Operation {
             ),
             Operation::Halting => write!(f, "Halting"),
             Operation::Running => write!(f, "Running"),
+            Operation::Stopping => write!(f, "Stopping after current action"),
             Operation::RunUntil { instant, .. } => {
                 write!(f, "Running for {}", duration_from_instant(instant))
             }
@@ -199,7 +214,7 @@ impl Display for Operation {
 #[inline]
 fn halt_until(run_duration_millis: u64, stop_duration_millis: u64) -> Operation {
     Operation::HaltUntil {
-        instant: Instant::now() + Duration::from_millis(stop_duration_millis),
+        instant: clock::now() + Duration::from_millis(stop_duration_millis),
         run_duration_millis,
         stop_duration_millis,
     }
@@ -208,7 +223,7 @@ fn halt_until(run_duration_millis: u64, stop_duration_millis: u64) -> Operation
 #[inline]
 fn run_until(run_duration_millis: u64, stop_duration_millis: u64, once: bool) -> Operation {
     Operation::RunUntil {
-        instant: Instant::now() + Duration::from_millis(run_duration_millis),
+        instant: clock::now() + Duration::from_millis(run_duration_millis),
         run_duration_millis,
         stop_duration_millis,
         once,
@@ -217,7 +232,7 @@ fn run_until(run_duration_millis: u64, stop_duration_millis: u64, once: bool) ->
 
 #[inline]
 fn duration_from_instant(instant: Instant) -> String {
-    duration_from(instant.saturating_duration_since(Instant::now()))
+    duration_from(instant.saturating_duration_since(clock::now()))
 }
 
 #[inline]
@@ -10,11 +10,12 @@ use crate::{
     ecs::{Resources, transition, transition_if},
     minimap::Minimap,
     models::{
-        Action, ActionKey, ActionKeyDirection, ActionKeyWith, ActionMove, FamiliarRarity, Position,
-        SwappableFamiliars, WaitAfterBuffered,
+        Action, ActionKey, ActionKeyCastVerification, ActionKeyDirection, ActionKeyWith,
+        ActionMove, FamiliarRarity, LinkKeyBinding, LinkKeyChainStep, MAX_LINK_KEY_CHAIN_STEPS,
+        Position, SwappableFamiliars, WaitAfterBuffered,
     },
     player::PlayerEntity,
-    run::MS_PER_TICK,
+    tick::ticks_from_millis,
 };
 
 /// The minimum x distance required to transition to [`Player::UseKey`] in auto mob action.
@@ -23,6 +24,19 @@ pub const AUTO_MOB_USE_KEY_X_THRESHOLD: i32 = 16;
 /// The minimum y distance required to transition to [`Player::UseKey`] in auto mob action.
 pub const AUTO_MOB_USE_KEY_Y_THRESHOLD: i32 = 8;
 
+/// A link key used after [`Key::link_key`].
+///
+/// Converted from [`LinkKeyChainStep`].
+#[derive(Clone, Copy, Debug)]
+pub struct LinkKeyStep {
+    /// Either [`LinkKeyKind::Before`], [`LinkKeyKind::AtTheSame`] or [`LinkKeyKind::After`].
+    pub link_key: LinkKeyKind,
+    /// The ticks to wait after a before key or before an after key.
+    ///
+    /// Uses the link key timing if [`None`].
+    pub delay_ticks: Option<u32>,
+}
+
 /// Represents the fixed key action.
 ///
 /// Converted from [`ActionKey`] without fields used by [`Rotator`]
@@ -31,16 +45,22 @@ pub struct Key {
     pub key: KeyKind,
     pub key_hold_ticks: u32,
     pub key_hold_buffered_to_wait_after: bool,
+    pub key_hold_moving: bool,
     pub link_key: LinkKeyKind,
+    /// The chained link keys ordered by before, at the same and after keys.
+    pub link_key_chain: Array<LinkKeyStep, MAX_LINK_KEY_CHAIN_STEPS>,
     pub count: u32,
     pub position: Option<Position>,
     pub direction: ActionKeyDirection,
     pub with: ActionKeyWith,
+    /// The ticks to wait after double jumping before using the key.
+    pub double_jump_apex_ticks: u32,
     pub wait_before_use_ticks: u32,
     pub wait_before_use_ticks_random_range: u32,
     pub wait_after_use_ticks: u32,
     pub wait_after_use_ticks_random_range: u32,
     pub wait_after_buffered: WaitAfterBuffered,
+    pub cast_verification: Option<ActionKeyCastVerification>,
 }
 
 impl From<ActionKey> for Key {
@@ -49,46 +69,94 @@ impl From<ActionKey> for Key {
             key,
             key_hold_millis,
             key_hold_buffered_to_wait_after,
+            key_hold_moving,
             link_key,
+            link_key_chain,
             count,
             position,
             direction,
             with,
+            double_jump_apex_millis,
             wait_before_use_millis,
             wait_before_use_millis_random_range,
             wait_after_use_millis,
             wait_after_use_millis_random_range,
             wait_after_buffered,
+            cast_verification,
             ..
         }: ActionKey,
     ) -> Self {
         let count = count.max(1);
-        let key_hold_ticks = (key_hold_millis / MS_PER_TICK) as u32;
-        let wait_before_use_ticks = (wait_before_use_millis / MS_PER_TICK) as u32;
+        let key_hold_ticks = ticks_from_millis(key_hold_millis);
+        let double_jump_apex_ticks = match with {
+            ActionKeyWith::DoubleJump => ticks_from_millis(double_jump_apex_millis),
+            ActionKeyWith::Any | ActionKeyWith::Stationary => 0,
+        };
+        let wait_before_use_ticks = ticks_from_millis(wait_before_use_millis);
         let wait_before_use_ticks_random_range =
-            (wait_before_use_millis_random_range / MS_PER_TICK) as u32;
-        let wait_after_use_ticks = (wait_after_use_millis / MS_PER_TICK) as u32;
+            ticks_from_millis(wait_before_use_millis_random_range);
+        let wait_after_use_ticks = ticks_from_millis(wait_after_use_millis);
         let wait_after_use_ticks_random_range =
-            (wait_after_use_millis_random_range / MS_PER_TICK) as u32;
+            ticks_from_millis(wait_after_use_millis_random_range);
+        let link_key_chain = link_key_steps(link_key, link_key_chain);
 
         Self {
             key: key.into(),
             key_hold_ticks,
             key_hold_buffered_to_wait_after,
+            key_hold_moving,
             link_key: link_key.into(),
+            link_key_chain,
             count,
             position,
             direction,
             with,
+            double_jump_apex_ticks,
             wait_before_use_ticks,
             wait_before_use_ticks_random_range,
             wait_after_use_ticks,
             wait_after_use_ticks_random_range,
             wait_after_buffered,
+            cast_verification,
         }
     }
 }
 
+/// Converts `chain` to [`LinkKeyStep`]s ordered by before, at the same and after keys.
+///
+/// The chain is ignored when `link_key` is [`LinkKeyBinding::Along`] because the actual key is
+/// pressed while holding the link key.
+fn link_key_steps(
+    link_key: LinkKeyBinding,
+    chain: [LinkKeyChainStep; MAX_LINK_KEY_CHAIN_STEPS],
+) -> Array<LinkKeyStep, MAX_LINK_KEY_CHAIN_STEPS> {
+    if matches!(link_key, LinkKeyBinding::Along(_)) {
+        return Array::new();
+    }
+
+    let mut steps = chain
+        .into_iter()
+        .filter(|step| {
+            !matches!(
+                step.link_key,
+                LinkKeyBinding::None | LinkKeyBinding::Along(_)
+            )
+        })
+        .map(|step| LinkKeyStep {
+            link_key: step.link_key.into(),
+            delay_ticks: (step.delay_millis > 0).then(|| ticks_from_millis(step.delay_millis)),
+        })
+        .collect::<Vec<_>>();
+    // Stable sort keeps the configured order of the same kind
+    steps.sort_by_key(|step| match step.link_key {
+        LinkKeyKind::Before(_) => 0,
+        LinkKeyKind::AtTheSame(_) => 1,
+        LinkKeyKind::After(_) | LinkKeyKind::Along(_) | LinkKeyKind::None => 2,
+    });
+
+    steps.into_iter().collect()
+}
+
 /// Represents the fixed move action.
 ///
 /// Converted from [`ActionMove`] without fields used by [`Rotator`].
@@ -108,7 +176,7 @@ impl From<ActionMove> for Move {
     ) -> Self {
         Self {
             position,
-            wait_after_move_ticks: (wait_after_move_millis / MS_PER_TICK) as u32,
+            wait_after_move_ticks: ticks_from_millis(wait_after_move_millis),
         }
     }
 }
@@ -161,12 +229,37 @@ pub struct PingPong {
     pub direction: PingPongDirection,
 }
 
+impl PingPong {
+    /// Whether `pos` has reached the edge of [`Self::bound`] in [`Self::direction`].
+    #[inline]
+    pub fn hit_bound_edge(&self, pos: Point) -> bool {
+        let bound = self.bound;
+        match self.direction {
+            PingPongDirection::Left => pos.x - bound.x <= 0,
+            PingPongDirection::Right => pos.x - bound.x - bound.width >= 0,
+            PingPongDirection::Up => pos.y - bound.y - bound.height >= 0,
+            PingPongDirection::Down => pos.y - bound.y <= 0,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(test, derive(Default))]
 pub enum PingPongDirection {
     #[cfg_attr(test, default)]
     Left,
     Right,
+    /// Bounces up to the top of the bound by up jumping or grappling.
+    Up,
+    /// Bounces down to the bottom of the bound by falling.
+    Down,
+}
+
+impl PingPongDirection {
+    #[inline]
+    pub fn is_vertical(self) -> bool {
+        matches!(self, PingPongDirection::Up | PingPongDirection::Down)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -208,6 +301,36 @@ pub struct ExchangeBooster {
     pub all: bool,
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct EnterPortal {
+    /// Position of the portal.
+    ///
+    /// This position is in player relative coordinate.
+    pub position: Point,
+    /// The longest time in milliseconds the portal can stay closed.
+    ///
+    /// This is `0` for a portal that is always open.
+    pub window_millis: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Shop {
+    /// Position of the NPC.
+    ///
+    /// This position is in player relative coordinate.
+    pub position: Point,
+    /// The navigation path of the map with the NPC or [`None`] if it is on the current map.
+    pub paths_id_index: Option<(i64, usize)>,
+    /// The key to open the shop when standing next to the NPC.
+    pub open_key: KeyKind,
+    /// The screen position of the shop tab with the item.
+    pub tab: Point,
+    /// The screen position of the item inside the shop tab.
+    pub item: Point,
+    /// The number of items to buy.
+    pub quantity: u32,
+}
+
 /// Represents an action the [`Rotator`] can use.
 #[derive(Clone, Debug, Display)]
 pub enum PlayerAction {
@@ -236,6 +359,12 @@ pub enum PlayerAction {
     ExchangeBooster(ExchangeBooster),
     /// Unstucking by pressing ESC.
     Unstuck,
+    /// Dismisses the level up popups.
+    DismissPopup,
+    /// Enters a portal action.
+    EnterPortal(EnterPortal),
+    /// Buys an item from an NPC shop action.
+    Shop(Shop),
 }
 
 impl From<Action> for PlayerAction {
@@ -290,6 +419,8 @@ macro_rules! transition_from_action {
 
         match next_action(&$player.context).expect("has action") {
             PlayerAction::SolveRune
+            | PlayerAction::EnterPortal(_)
+            | PlayerAction::Shop(_)
             | PlayerAction::PingPong(_)
             | PlayerAction::Move(_)
             | PlayerAction::Key(Key {
@@ -314,6 +445,8 @@ macro_rules! transition_from_action {
         if $is_terminal {
             match next_action(&$player.context).expect("has action") {
                 PlayerAction::SolveRune
+                | PlayerAction::EnterPortal(_)
+                | PlayerAction::Shop(_)
                 | PlayerAction::PingPong(_)
                 | PlayerAction::Move(_)
                 | PlayerAction::Key(Key {
@@ -351,23 +484,28 @@ pub(super) fn update_from_ping_pong_action(
 ) {
     let direction = ping_pong.direction;
     let bound = ping_pong.bound;
-    let hit_x_bound_edge = match direction {
-        PingPongDirection::Left => cur_pos.x - bound.x <= 0,
-        PingPongDirection::Right => cur_pos.x - bound.x - bound.width >= 0,
-    };
-    if hit_x_bound_edge {
+    let hit_bound_edge = ping_pong.hit_bound_edge(cur_pos);
+    if hit_bound_edge && direction.is_vertical() {
+        // Uses the key at the bounced edge before completing
+        release_movement_keys(resources, &player.context);
+        transition!(player, Player::UseKey(UseKey::from_ping_pong(ping_pong)));
+    }
+    if hit_bound_edge {
         transition_from_action!(player, Player::Idle);
     }
 
-    release_arrow_keys(resources);
+    release_movement_keys(resources, &player.context);
     let minimap_width = match minimap_state {
         Minimap::Idle(idle) => idle.bbox.width,
         _ => unreachable!(),
     };
-    let y = cur_pos.y; // y doesn't matter in ping pong
+    let x = cur_pos.x;
+    let y = cur_pos.y; // y doesn't matter in horizontal ping pong
     let moving = match direction {
         PingPongDirection::Left => Player::Moving(Point::new(0, y), false, None),
         PingPongDirection::Right => Player::Moving(Point::new(minimap_width, y), false, None),
+        PingPongDirection::Up => Player::Moving(Point::new(x, bound.y + bound.height), false, None),
+        PingPongDirection::Down => Player::Moving(Point::new(x, bound.y), false, None),
     };
     transition!(player, moving)
 }
@@ -406,7 +544,10 @@ pub(super) fn update_from_auto_mob_action(
     };
     let should_check_pathing = matches!(
         player.state,
-        Player::DoubleJumping(_) | Player::Adjusting(_)
+        Player::DoubleJumping(_)
+            | Player::Teleporting(_)
+            | Player::Gliding(_)
+            | Player::Adjusting(_)
     );
 
     transition_if!(
@@ -417,7 +558,7 @@ pub(super) fn update_from_auto_mob_action(
                 .context
                 .auto_mob_pathing_should_use_key(resources, minimap_state),
         {
-            release_arrow_keys(resources);
+            release_movement_keys(resources, &player.context);
         }
     );
     transition_if!(
@@ -426,14 +567,14 @@ pub(super) fn update_from_auto_mob_action(
         should_terminate,
         {
             player.context.last_known_direction = ActionKeyDirection::Any;
-            release_arrow_keys(resources);
+            release_movement_keys(resources, &player.context);
         }
     );
 }
 
-fn release_arrow_keys(resources: &Resources) {
-    resources.input.send_key_up(KeyKind::Down);
-    resources.input.send_key_up(KeyKind::Up);
-    resources.input.send_key_up(KeyKind::Left);
-    resources.input.send_key_up(KeyKind::Right);
+fn release_movement_keys(resources: &Resources, context: &PlayerContext) {
+    resources.input.send_key_up(context.config.down_key);
+    resources.input.send_key_up(context.config.up_key);
+    resources.input.send_key_up(context.config.left_key);
+    resources.input.send_key_up(context.config.right_key);
 }
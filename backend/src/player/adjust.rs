@@ -7,13 +7,14 @@ use super::{
     use_key::UseKey,
 };
 use crate::{
-    ActionKeyDirection, ActionKeyWith,
+    ActionKeyDirection, ActionKeyWith, DangerZone,
     bridge::KeyKind,
     ecs::{Resources, transition, transition_if},
     minimap::Minimap,
     player::{
         Player, PlayerEntity,
         actions::update_from_auto_mob_action,
+        danger::find_danger_zone_ahead,
         double_jump::DoubleJumping,
         moving::MOVE_TIMEOUT,
         next_action,
@@ -21,6 +22,7 @@ use crate::{
         timeout::{ChangeAxis, MovingLifecycle, Timeout, next_moving_lifecycle_with_axis},
         transition_to_moving, transition_to_moving_if,
     },
+    tick,
 };
 
 /// Minimum x distance from the destination required to perform small movement.
@@ -31,10 +33,44 @@ pub const ADJUSTING_MEDIUM_THRESHOLD: i32 = 3;
 
 const ADJUSTING_SHORT_TIMEOUT: u32 = MOVE_TIMEOUT + 3;
 
+/// Maximum number of times the adjusting direction can flip before accepting a position within
+/// the small movement range of the destination.
+///
+/// The small movement range starts at [`ADJUSTING_SHORT_THRESHOLD`] and ends before the medium
+/// threshold at which the player walks instead.
+///
+/// Prevents rapid left and right alternation when the player keeps overshooting the destination.
+const ADJUSTING_MAX_DIRECTION_FLIPS: u32 = 2;
+
+/// Number of ticks to wait after reaching an exact destination before completing.
+///
+/// The player can still drift after stopping (e.g. ice floors or knockback), so the position is
+/// re-checked while waiting.
+const SETTLE_TICKS: u32 = 5;
+
+/// Maximum number of corrections after drifting from an exact destination while settling.
+const MAX_SETTLE_CORRECTIONS: u32 = 2;
+
+/// Minimum y distance from an exact destination while settling to move to it again.
+///
+/// Adjusting only walks horizontally so a vertical drift (e.g. knocked back onto another
+/// platform) is handed back to [`Player::Moving`].
+const SETTLE_Y_DRIFT_THRESHOLD: i32 = 2;
+
 #[derive(Clone, Copy, Debug)]
 pub struct Adjusting {
     pub moving: Moving,
     adjust_timeout: Timeout,
+    /// The last danger zone jumped over while walking.
+    jumped_danger_zone: Option<DangerZone>,
+    /// The last direction pressed to move towards the destination.
+    last_direction: Option<ActionKeyDirection>,
+    /// Number of times the pressed direction has flipped.
+    direction_flips: u32,
+    /// Timeout for settling at the exact destination.
+    settle_timeout: Timeout,
+    /// Number of times the player drifted from the exact destination while settling.
+    settle_corrections: u32,
 }
 
 impl Adjusting {
@@ -42,6 +78,11 @@ impl Adjusting {
         Self {
             moving,
             adjust_timeout: Timeout::default(),
+            jumped_danger_zone: None,
+            last_direction: None,
+            direction_flips: 0,
+            settle_timeout: Timeout::default(),
+            settle_corrections: 0,
         }
     }
 
@@ -49,26 +90,53 @@ impl Adjusting {
         Adjusting { moving, ..self }
     }
 
+    fn update_direction(&mut self, direction: ActionKeyDirection) {
+        if self.last_direction.is_some_and(|last| last != direction) {
+            self.direction_flips += 1;
+        }
+        self.last_direction = Some(direction);
+    }
+
+    /// Whether the player is oscillating around the destination and should stop adjusting.
+    ///
+    /// Oscillating is only accepted while `x_distance` is within the small movement range below
+    /// `medium_threshold` so that overshooting by more than [`ADJUSTING_SHORT_THRESHOLD`] does not
+    /// keep flipping until timing out.
+    #[inline]
+    fn is_oscillating(&self, x_distance: i32, medium_threshold: i32) -> bool {
+        self.direction_flips >= ADJUSTING_MAX_DIRECTION_FLIPS
+            && x_distance >= ADJUSTING_SHORT_THRESHOLD
+            && x_distance < medium_threshold
+    }
+
     fn update_adjusting(&mut self, resources: &Resources, keys: Option<(KeyKind, KeyKind)>) {
-        self.adjust_timeout =
-            match next_timeout_lifecycle(self.adjust_timeout, ADJUSTING_SHORT_TIMEOUT) {
-                Lifecycle::Started(timeout) => {
-                    if let Some((up_key, down_key)) = keys {
-                        resources.input.send_key_up(up_key);
-                        resources.input.send_key(down_key);
-                    }
-                    timeout
+        self.adjust_timeout = match next_timeout_lifecycle(
+            self.adjust_timeout,
+            tick::scaled(ADJUSTING_SHORT_TIMEOUT),
+        ) {
+            Lifecycle::Started(timeout) => {
+                if let Some((up_key, down_key)) = keys {
+                    resources.input.send_key_up(up_key);
+                    resources.input.send_key(down_key);
                 }
-                Lifecycle::Ended => Timeout::default(),
-                Lifecycle::Updated(timeout) => timeout,
-            };
+                timeout
+            }
+            Lifecycle::Ended => Timeout::default(),
+            Lifecycle::Updated(timeout) => timeout,
+        };
     }
 }
 
 /// Updates the [`Player::Adjusting`] contextual state.
 ///
 /// This state just walks towards the destination. If [`Moving::exact`] is true,
-/// then it will perform small movement to ensure the `x` is as close as possible.
+/// then it will perform small movement to ensure the `x` is as close as possible. When walking
+/// and there is a danger zone ahead, it will jump over the zone. If the player keeps overshooting
+/// the destination back and forth, a position close enough is accepted as completed.
+///
+/// After reaching an exact destination, it waits for [`SETTLE_TICKS`] before completing and
+/// corrects the position again if the player drifted in the meantime. A vertical drift moves to
+/// the destination again through [`Player::Moving`].
 pub fn update_adjusting_state(
     resources: &Resources,
     player: &mut PlayerEntity,
@@ -79,18 +147,25 @@ pub fn update_adjusting_state(
     };
     let context = &mut player.context;
     let cur_pos = context.last_known_pos.expect("in positional state");
+    let left_key = context.config.left_key;
+    let right_key = context.config.right_key;
 
     let moving = adjusting.moving;
     let is_intermediate = moving.is_destination_intermediate();
 
-    match next_moving_lifecycle_with_axis(moving, cur_pos, MOVE_TIMEOUT, ChangeAxis::Both) {
+    match next_moving_lifecycle_with_axis(
+        moving,
+        cur_pos,
+        tick::scaled(MOVE_TIMEOUT),
+        ChangeAxis::Both,
+    ) {
         MovingLifecycle::Started(moving) => {
             context.last_movement = Some(LastMovement::Adjusting);
             transition!(player, Player::Adjusting(adjusting.moving(moving)))
         }
         MovingLifecycle::Ended(moving) => transition_to_moving!(player, moving, {
-            resources.input.send_key_up(KeyKind::Right);
-            resources.input.send_key_up(KeyKind::Left);
+            resources.input.send_key_up(right_key);
+            resources.input.send_key_up(left_key);
         }),
         MovingLifecycle::Updated(mut moving) => {
             let mut adjusting = adjusting;
@@ -103,6 +178,14 @@ pub fn update_adjusting_state(
                 !context.config.disable_double_jumping && x_distance >= threshold
             );
 
+            // Drifted vertically while settling
+            let (y_distance, _) = moving.y_distance_direction_from(true, moving.pos);
+            transition_to_moving_if!(
+                player,
+                moving,
+                adjusting.settle_timeout.started && y_distance >= SETTLE_Y_DRIFT_THRESHOLD
+            );
+
             // Movement logics
             if !moving.completed {
                 let adjusting_started = adjusting.adjust_timeout.started;
@@ -112,16 +195,15 @@ pub fn update_adjusting_state(
                 }
 
                 let should_adjust_medium =
-                    !adjusting_started && x_distance >= ADJUSTING_MEDIUM_THRESHOLD;
-                let should_adjust_short =
-                    adjusting_started || (moving.exact && x_distance >= ADJUSTING_SHORT_THRESHOLD);
+                    !adjusting_started && x_distance >= context.config.adjusting_medium_threshold;
+                let should_adjust_short = adjusting_started
+                    || (moving.exact
+                        && x_distance >= ADJUSTING_SHORT_THRESHOLD
+                        && !adjusting
+                            .is_oscillating(x_distance, context.config.adjusting_medium_threshold));
                 let direction = match x_direction.cmp(&0) {
-                    Ordering::Greater => {
-                        Some((KeyKind::Right, KeyKind::Left, ActionKeyDirection::Right))
-                    }
-                    Ordering::Less => {
-                        Some((KeyKind::Left, KeyKind::Right, ActionKeyDirection::Left))
-                    }
+                    Ordering::Greater => Some((right_key, left_key, ActionKeyDirection::Right)),
+                    Ordering::Less => Some((left_key, right_key, ActionKeyDirection::Left)),
                     _ => None,
                 };
 
@@ -130,8 +212,22 @@ pub fn update_adjusting_state(
                         resources.input.send_key_up(up_key);
                         resources.input.send_key_down(down_key);
                         context.last_known_direction = dir;
+                        adjusting.update_direction(dir);
+
+                        if let Some(zone) = find_danger_zone_ahead(
+                            context.config.danger_zones.as_slice(),
+                            moving.pos,
+                            moving.dest,
+                        ) && adjusting.jumped_danger_zone != Some(zone)
+                        {
+                            resources.input.send_key(context.config.jump_key);
+                            adjusting.jumped_danger_zone = Some(zone);
+                        }
                     }
                     (false, true, Some((down_key, up_key, dir))) => {
+                        if !adjusting_started {
+                            adjusting.update_direction(dir);
+                        }
                         adjusting.update_adjusting(resources, Some((up_key, down_key)));
                         context.last_known_direction = dir;
                     }
@@ -139,8 +235,8 @@ pub fn update_adjusting_state(
                         if adjusting_started {
                             adjusting.update_adjusting(resources, None);
                         } else {
-                            resources.input.send_key_up(KeyKind::Left);
-                            resources.input.send_key_up(KeyKind::Right);
+                            resources.input.send_key_up(left_key);
+                            resources.input.send_key_up(right_key);
                             moving = moving.completed(true);
                         }
                     }
@@ -148,15 +244,38 @@ pub fn update_adjusting_state(
             }
 
             // Computes and sets initial next state first
+            let mut settling = false;
             let next_moving = if !moving.completed {
                 moving
-            } else if moving.exact && x_distance >= ADJUSTING_SHORT_THRESHOLD {
-                // Exact adjusting incomplete
+            } else if moving.exact
+                && x_distance >= ADJUSTING_SHORT_THRESHOLD
+                && !adjusting.is_oscillating(x_distance, context.config.adjusting_medium_threshold)
+            {
+                // Exact adjusting incomplete or drifted while settling
+                if adjusting.settle_timeout.started {
+                    adjusting.settle_timeout = Timeout::default();
+                    adjusting.settle_corrections += 1;
+                }
                 moving.completed(false).timeout_current(0)
+            } else if moving.exact && adjusting.settle_corrections < MAX_SETTLE_CORRECTIONS {
+                match next_timeout_lifecycle(adjusting.settle_timeout, tick::scaled(SETTLE_TICKS)) {
+                    Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
+                        // Do not allow timing out while settling
+                        settling = true;
+                        adjusting.settle_timeout = timeout;
+                        moving.timeout_current(moving.timeout.current.saturating_sub(1))
+                    }
+                    Lifecycle::Ended => {
+                        adjusting.settle_timeout = Timeout::default();
+                        moving.timeout_current(tick::scaled(MOVE_TIMEOUT))
+                    }
+                }
             } else {
-                moving.timeout_current(MOVE_TIMEOUT)
+                moving.timeout_current(tick::scaled(MOVE_TIMEOUT))
             };
             player.state = Player::Adjusting(adjusting.moving(next_moving));
+            // Actions are only continued after settling
+            transition_if!(settling);
 
             update_from_action(resources, player, minimap_state, moving);
         }
@@ -223,6 +342,8 @@ fn update_from_action(
                 ..
             })
             | PlayerAction::SolveRune
+            | PlayerAction::EnterPortal(_)
+            | PlayerAction::Shop(_)
             | PlayerAction::Move(_),
         ) => (),
         _ => unreachable!(),
@@ -238,6 +359,7 @@ mod tests {
 
     use super::*;
     use crate::{
+        array::Array,
         bridge::MockInput,
         player::{Player, PlayerContext},
     };
@@ -318,6 +440,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn update_adjusting_state_updated_jumps_over_danger_zone_once() {
+        let mut keys = MockInput::default();
+        keys.expect_send_key_up().with(eq(KeyKind::Left)).times(2);
+        keys.expect_send_key_down()
+            .with(eq(KeyKind::Right))
+            .times(2);
+        keys.expect_send_key().with(eq(KeyKind::Space)).once();
+
+        let resources = Resources::new(Some(keys), None);
+
+        let pos = Point { x: 0, y: 0 };
+        let dest = Point { x: 20, y: 0 };
+        let mut player = mock_player_entity(pos);
+        player.context.config.jump_key = KeyKind::Space;
+        player.context.config.danger_zones = Array::from_iter([DangerZone {
+            x_start: 3,
+            x_end: 10,
+            y: 0,
+        }]);
+        player.state = Player::Adjusting(Adjusting::new(
+            Moving::new(pos, dest, false, None).timeout_started(true),
+        ));
+
+        update_adjusting_state(&resources, &mut player, Minimap::Detecting);
+        update_adjusting_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(
+            player.state,
+            Player::Adjusting(Adjusting {
+                jumped_danger_zone: Some(_),
+                ..
+            })
+        );
+    }
+
     #[test]
     fn update_adjusting_state_updated_completes_when_no_direction_and_no_adjustment() {
         let mut keys = MockInput::default();
@@ -404,7 +562,8 @@ mod tests {
                     timeout: Timeout { current: 3, .. },
                     ..
                 },
-                adjust_timeout: Timeout { current: 2, .. }
+                adjust_timeout: Timeout { current: 2, .. },
+                ..
             })
         );
     }
@@ -441,5 +600,189 @@ mod tests {
         );
     }
 
+    #[test]
+    fn update_adjusting_state_updated_counts_direction_flips() {
+        let mut keys = MockInput::default();
+        keys.expect_send_key_up().return_const(());
+        keys.expect_send_key().return_const(());
+
+        let resources = Resources::new(Some(keys), None);
+
+        let pos = Point { x: 2, y: 0 };
+        let dest = Point { x: 1, y: 0 };
+        let mut player = mock_player_entity(pos);
+        let mut adjusting =
+            Adjusting::new(Moving::new(pos, dest, true, None).timeout_started(true));
+        adjusting.last_direction = Some(ActionKeyDirection::Right);
+        player.state = Player::Adjusting(adjusting);
+
+        update_adjusting_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(
+            player.state,
+            Player::Adjusting(Adjusting {
+                last_direction: Some(ActionKeyDirection::Left),
+                direction_flips: 1,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn update_adjusting_state_updated_oscillating_completes_close_enough() {
+        let mut keys = MockInput::default();
+        keys.expect_send_key_up().with(eq(KeyKind::Left)).once();
+        keys.expect_send_key_up().with(eq(KeyKind::Right)).once();
+        keys.expect_send_key().never();
+
+        let resources = Resources::new(Some(keys), None);
+
+        let pos = Point { x: 2, y: 0 };
+        let dest = Point { x: 1, y: 0 };
+        let mut player = mock_player_entity(pos);
+        let mut adjusting =
+            Adjusting::new(Moving::new(pos, dest, true, None).timeout_started(true));
+        adjusting.direction_flips = ADJUSTING_MAX_DIRECTION_FLIPS;
+        player.state = Player::Adjusting(adjusting);
+
+        update_adjusting_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(
+            player.state,
+            Player::Adjusting(Adjusting {
+                moving: Moving {
+                    completed: true,
+                    ..
+                },
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn update_adjusting_state_updated_oscillating_completes_within_short_range() {
+        let mut keys = MockInput::default();
+        keys.expect_send_key_up().with(eq(KeyKind::Left)).once();
+        keys.expect_send_key_up().with(eq(KeyKind::Right)).once();
+        keys.expect_send_key().never();
+
+        let resources = Resources::new(Some(keys), None);
+
+        let pos = Point { x: 3, y: 0 };
+        let dest = Point { x: 1, y: 0 };
+        let mut player = mock_player_entity(pos);
+        let mut adjusting =
+            Adjusting::new(Moving::new(pos, dest, true, None).timeout_started(true));
+        adjusting.direction_flips = ADJUSTING_MAX_DIRECTION_FLIPS;
+        player.state = Player::Adjusting(adjusting);
+
+        update_adjusting_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(
+            player.state,
+            Player::Adjusting(Adjusting {
+                moving: Moving {
+                    completed: true,
+                    ..
+                },
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn is_oscillating_only_within_short_range() {
+        let mut adjusting =
+            Adjusting::new(Moving::new(Point::default(), Point::default(), true, None));
+        assert!(!adjusting.is_oscillating(2, ADJUSTING_MEDIUM_THRESHOLD));
+
+        adjusting.direction_flips = ADJUSTING_MAX_DIRECTION_FLIPS;
+        assert!(!adjusting.is_oscillating(0, ADJUSTING_MEDIUM_THRESHOLD));
+        assert!(adjusting.is_oscillating(1, ADJUSTING_MEDIUM_THRESHOLD));
+        assert!(adjusting.is_oscillating(2, ADJUSTING_MEDIUM_THRESHOLD));
+        assert!(!adjusting.is_oscillating(ADJUSTING_MEDIUM_THRESHOLD, ADJUSTING_MEDIUM_THRESHOLD));
+    }
+
+    #[test]
+    fn update_adjusting_state_updated_settles_then_corrects_drift() {
+        let resources = Resources::new(None, None);
+        let pos = Point { x: 1, y: 0 };
+        let dest = Point { x: 1, y: 0 };
+        let mut player = mock_player_entity(pos);
+        let moving = Moving::new(pos, dest, true, None)
+            .completed(true)
+            .timeout_current(2)
+            .timeout_started(true);
+        player.state = Player::Adjusting(Adjusting::new(moving));
+
+        update_adjusting_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(
+            player.state,
+            Player::Adjusting(Adjusting {
+                moving: Moving {
+                    completed: true,
+                    timeout: Timeout { current: 2, .. },
+                    ..
+                },
+                settle_timeout: Timeout { started: true, .. },
+                settle_corrections: 0,
+                ..
+            })
+        );
+
+        // Drifted away from the destination while settling
+        player.context.last_known_pos = Some(Point { x: 3, y: 0 });
+        update_adjusting_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(
+            player.state,
+            Player::Adjusting(Adjusting {
+                moving: Moving {
+                    completed: false,
+                    ..
+                },
+                settle_timeout: Timeout { started: false, .. },
+                settle_corrections: 1,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn update_adjusting_state_updated_settling_drifted_vertically_moves_again() {
+        let resources = Resources::new(None, None);
+        let pos = Point { x: 1, y: 10 };
+        let dest = Point { x: 1, y: 10 };
+        let mut player = mock_player_entity(pos);
+        let moving = Moving::new(pos, dest, true, None)
+            .completed(true)
+            .timeout_current(2)
+            .timeout_started(true);
+        player.state = Player::Adjusting(Adjusting::new(moving));
+
+        update_adjusting_state(&resources, &mut player, Minimap::Detecting);
+        assert_matches!(
+            player.state,
+            Player::Adjusting(Adjusting {
+                settle_timeout: Timeout { started: true, .. },
+                ..
+            })
+        );
+
+        // Within the threshold keeps settling
+        player.context.last_known_pos = Some(Point { x: 1, y: 11 });
+        update_adjusting_state(&resources, &mut player, Minimap::Detecting);
+        assert_matches!(player.state, Player::Adjusting(_));
+
+        // Knocked back vertically while settling
+        player.context.last_known_pos = Some(Point {
+            x: 1,
+            y: 10 + SETTLE_Y_DRIFT_THRESHOLD,
+        });
+        update_adjusting_state(&resources, &mut player, Minimap::Detecting);
+        assert_matches!(player.state, Player::Moving(dest, true, None) if dest == Point { x: 1, y: 10 });
+    }
+
     // TODO: add tests for on_action
 }
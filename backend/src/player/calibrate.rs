@@ -0,0 +1,559 @@
+use log::info;
+
+use super::{
+    Player, PlayerContext,
+    double_jump::{DOUBLE_JUMP_AUTO_MOB_THRESHOLD, DOUBLE_JUMP_THRESHOLD},
+    fall::TELEPORT_FALL_THRESHOLD,
+    teleporting::{TELEPORTING_AUTO_MOB_THRESHOLD, TELEPORTING_THRESHOLD},
+    timeout::{Lifecycle, Timeout, next_timeout_lifecycle},
+    up_jump::{TELEPORT_WITH_JUMP_THRESHOLD, UP_JUMP_AND_TELEPORT_THRESHOLD},
+};
+use crate::{
+    bridge::KeyKind,
+    ecs::{Resources, transition},
+    minimap::Minimap,
+    player::{PlayerEntity, adjust::ADJUSTING_MEDIUM_THRESHOLD},
+    tick::{self, BASE_TICK_RATE, to_base_tick_rate_speed},
+};
+
+/// Approximate walk speed in pixels per tick at [`BASE_TICK_RATE`] that the default walk
+/// thresholds are tuned at.
+pub const REFERENCE_WALK_SPEED: f32 = 0.5;
+
+/// Approximate double jump speed in pixels per tick at [`BASE_TICK_RATE`] that the default double
+/// jump thresholds are tuned at.
+pub const REFERENCE_DOUBLE_JUMP_SPEED: f32 = 2.0;
+
+/// Approximate horizontal teleport distance in minimap pixels that the default horizontal
+/// teleport thresholds are tuned at.
+const REFERENCE_TELEPORT_X_DISTANCE: f32 = 12.0;
+
+/// Approximate vertical teleport distance in minimap pixels that the default vertical teleport
+/// thresholds are tuned at.
+const REFERENCE_TELEPORT_Y_DISTANCE: f32 = 16.0;
+
+/// Number of ticks to walk before starting to measure to skip the acceleration.
+const WALK_WARMUP_TICKS: u32 = 5;
+
+/// Number of ticks to walk including [`WALK_WARMUP_TICKS`].
+const WALK_TICKS: u32 = 35;
+
+/// Number of ticks to wait for the player to stop after walking.
+const STOPPING_TICKS: u32 = 20;
+
+/// Number of ticks to measure the double jump.
+const DOUBLE_JUMP_TICKS: u32 = 25;
+
+/// The tick to send the second jump key of a double jump.
+const DOUBLE_JUMP_SECOND_PRESS_TICK: u32 = 3;
+
+/// Number of ticks to measure a single teleport.
+const TELEPORT_TICKS: u32 = 10;
+
+/// The measured movement speeds and distances of the current character.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MovementCalibration {
+    /// Walk speed in pixels per tick at [`BASE_TICK_RATE`].
+    pub walk_speed: f32,
+    /// Double jump speed in pixels per tick at [`BASE_TICK_RATE`].
+    pub double_jump_speed: f32,
+    /// Horizontal and vertical teleport distances in pixels if the character has a teleport key.
+    pub teleport_distance: Option<(f32, f32)>,
+}
+
+/// States of calibrating movement.
+#[derive(Clone, Copy, Debug)]
+enum State {
+    /// Walks towards the further side of the minimap and measures the distance.
+    Walking { timeout: Timeout, start_x: i32 },
+    /// Waits for the player to stop.
+    Stopping { timeout: Timeout, walk_speed: f32 },
+    /// Double jumps back and measures the peak velocity.
+    DoubleJumping {
+        timeout: Timeout,
+        walk_speed: f32,
+        peak_speed: f32,
+    },
+    /// Waits for the player to land before teleporting.
+    Landing {
+        timeout: Timeout,
+        walk_speed: f32,
+        double_jump_speed: f32,
+    },
+    /// Teleports horizontally once and measures the furthest `x` distance.
+    TeleportingHorizontal {
+        timeout: Timeout,
+        walk_speed: f32,
+        double_jump_speed: f32,
+        start_x: i32,
+        distance: i32,
+    },
+    /// Teleports up once and measures the furthest `y` distance.
+    TeleportingVertical {
+        timeout: Timeout,
+        walk_speed: f32,
+        double_jump_speed: f32,
+        x_distance: i32,
+        start_y: i32,
+        distance: i32,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Calibrating {
+    state: State,
+    /// The direction key to walk in.
+    ///
+    /// The opposite direction is used for double jumping.
+    direction: KeyKind,
+}
+
+impl Default for Calibrating {
+    fn default() -> Self {
+        Self {
+            state: State::Walking {
+                timeout: Timeout::default(),
+                start_x: 0,
+            },
+            direction: KeyKind::Right,
+        }
+    }
+}
+
+/// Updates the [`Player::Calibrating`] contextual state.
+///
+/// This state walks for a fixed number of ticks and then double jumps back to measure the
+/// horizontal movement speeds of the current character. If the character has a teleport key, it
+/// then teleports horizontally and up once to measure the teleport distances. The result is
+/// stored in
+/// [`PlayerContext::movement_calibration`] to be persisted to the character. It is only
+/// meant to be used while the bot is halting and will cancel if the bot starts running.
+pub fn update_calibrating_state(
+    resources: &Resources,
+    player: &mut PlayerEntity,
+    minimap_state: Minimap,
+) {
+    let Player::Calibrating(mut calibrating) = player.state else {
+        panic!("state is not calibrating")
+    };
+    let cur_pos = player.context.last_known_pos.expect("in positional state");
+    let left_key = player.context.config.left_key;
+    let right_key = player.context.config.right_key;
+    let up_key = player.context.config.up_key;
+
+    if !resources.operation.halting() {
+        transition!(player, Player::Idle, {
+            resources.input.send_key_up(left_key);
+            resources.input.send_key_up(right_key);
+            resources.input.send_key_up(up_key);
+        });
+    }
+
+    match calibrating.state {
+        State::Walking { timeout, start_x } => {
+            match next_timeout_lifecycle(timeout, tick::scaled(WALK_TICKS)) {
+                Lifecycle::Started(timeout) => {
+                    let Minimap::Idle(idle) = minimap_state else {
+                        transition!(player, Player::Idle);
+                    };
+                    calibrating.direction = if cur_pos.x < idle.bbox.width / 2 {
+                        right_key
+                    } else {
+                        left_key
+                    };
+                    calibrating.state = State::Walking {
+                        timeout,
+                        start_x: cur_pos.x,
+                    };
+                    resources.input.send_key_down(calibrating.direction);
+                }
+                Lifecycle::Ended => {
+                    let ticks = tick::scaled(WALK_TICKS) - tick::scaled(WALK_WARMUP_TICKS);
+                    let distance = (cur_pos.x - start_x).abs() as f32;
+                    calibrating.state = State::Stopping {
+                        timeout: Timeout::default(),
+                        walk_speed: to_base_tick_rate_speed(distance / ticks as f32),
+                    };
+                    resources.input.send_key_up(calibrating.direction);
+                }
+                Lifecycle::Updated(timeout) => {
+                    let start_x = if timeout.current == tick::scaled(WALK_WARMUP_TICKS) {
+                        cur_pos.x
+                    } else {
+                        start_x
+                    };
+                    calibrating.state = State::Walking { timeout, start_x };
+                }
+            }
+        }
+        State::Stopping {
+            timeout,
+            walk_speed,
+        } => match next_timeout_lifecycle(timeout, tick::scaled(STOPPING_TICKS)) {
+            Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
+                calibrating.state = State::Stopping {
+                    timeout,
+                    walk_speed,
+                };
+            }
+            Lifecycle::Ended => {
+                calibrating.state = State::DoubleJumping {
+                    timeout: Timeout::default(),
+                    walk_speed,
+                    peak_speed: 0.0,
+                };
+            }
+        },
+        State::DoubleJumping {
+            timeout,
+            walk_speed,
+            peak_speed,
+        } => {
+            let direction = opposite_direction(calibrating.direction, left_key, right_key);
+            let peak_speed = peak_speed.max(player.context.velocity.0);
+
+            match next_timeout_lifecycle(timeout, tick::scaled(DOUBLE_JUMP_TICKS)) {
+                Lifecycle::Started(timeout) => {
+                    resources.input.send_key_down(direction);
+                    resources.input.send_key(player.context.config.jump_key);
+                    calibrating.state = State::DoubleJumping {
+                        timeout,
+                        walk_speed,
+                        peak_speed,
+                    };
+                }
+                Lifecycle::Ended => {
+                    let double_jump_speed = peak_speed;
+                    resources.input.send_key_up(direction);
+                    if player.context.config.teleport_key.is_none() {
+                        transition!(player, Player::Idle, {
+                            complete_calibration(
+                                &mut player.context,
+                                walk_speed,
+                                double_jump_speed,
+                                None,
+                            );
+                        });
+                    }
+                    calibrating.state = State::Landing {
+                        timeout: Timeout::default(),
+                        walk_speed,
+                        double_jump_speed,
+                    };
+                }
+                Lifecycle::Updated(timeout) => {
+                    if timeout.current == tick::scaled(DOUBLE_JUMP_SECOND_PRESS_TICK) {
+                        resources.input.send_key(player.context.config.jump_key);
+                    }
+                    calibrating.state = State::DoubleJumping {
+                        timeout,
+                        walk_speed,
+                        peak_speed,
+                    };
+                }
+            }
+        }
+        State::Landing {
+            timeout,
+            walk_speed,
+            double_jump_speed,
+        } => match next_timeout_lifecycle(timeout, tick::scaled(STOPPING_TICKS)) {
+            Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
+                calibrating.state = State::Landing {
+                    timeout,
+                    walk_speed,
+                    double_jump_speed,
+                };
+            }
+            Lifecycle::Ended => {
+                calibrating.state = State::TeleportingHorizontal {
+                    timeout: Timeout::default(),
+                    walk_speed,
+                    double_jump_speed,
+                    start_x: cur_pos.x,
+                    distance: 0,
+                };
+            }
+        },
+        State::TeleportingHorizontal {
+            timeout,
+            walk_speed,
+            double_jump_speed,
+            start_x,
+            distance,
+        } => {
+            let teleport_key = player
+                .context
+                .config
+                .teleport_key
+                .expect("has teleport key");
+            let distance = distance.max((cur_pos.x - start_x).abs());
+
+            match next_timeout_lifecycle(timeout, tick::scaled(TELEPORT_TICKS)) {
+                Lifecycle::Started(timeout) => {
+                    resources.input.send_key_down(calibrating.direction);
+                    resources.input.send_key(teleport_key);
+                    calibrating.state = State::TeleportingHorizontal {
+                        timeout,
+                        walk_speed,
+                        double_jump_speed,
+                        start_x,
+                        distance,
+                    };
+                }
+                Lifecycle::Ended => {
+                    resources.input.send_key_up(calibrating.direction);
+                    calibrating.state = State::TeleportingVertical {
+                        timeout: Timeout::default(),
+                        walk_speed,
+                        double_jump_speed,
+                        x_distance: distance,
+                        start_y: cur_pos.y,
+                        distance: 0,
+                    };
+                }
+                Lifecycle::Updated(timeout) => {
+                    calibrating.state = State::TeleportingHorizontal {
+                        timeout,
+                        walk_speed,
+                        double_jump_speed,
+                        start_x,
+                        distance,
+                    };
+                }
+            }
+        }
+        State::TeleportingVertical {
+            timeout,
+            walk_speed,
+            double_jump_speed,
+            x_distance,
+            start_y,
+            distance,
+        } => {
+            let teleport_key = player
+                .context
+                .config
+                .teleport_key
+                .expect("has teleport key");
+            let distance = distance.max((cur_pos.y - start_y).abs());
+
+            match next_timeout_lifecycle(timeout, tick::scaled(TELEPORT_TICKS)) {
+                Lifecycle::Started(timeout) => {
+                    resources.input.send_key_down(up_key);
+                    resources.input.send_key(teleport_key);
+                    calibrating.state = State::TeleportingVertical {
+                        timeout,
+                        walk_speed,
+                        double_jump_speed,
+                        x_distance,
+                        start_y,
+                        distance,
+                    };
+                }
+                Lifecycle::Ended => transition!(player, Player::Idle, {
+                    resources.input.send_key_up(up_key);
+                    complete_calibration(
+                        &mut player.context,
+                        walk_speed,
+                        double_jump_speed,
+                        Some((x_distance as f32, distance as f32)),
+                    );
+                }),
+                Lifecycle::Updated(timeout) => {
+                    calibrating.state = State::TeleportingVertical {
+                        timeout,
+                        walk_speed,
+                        double_jump_speed,
+                        x_distance,
+                        start_y,
+                        distance,
+                    };
+                }
+            }
+        }
+    }
+
+    player.state = Player::Calibrating(calibrating);
+}
+
+/// Applies calibrated `walk_speed` and `double_jump_speed` to the movement thresholds of
+/// `context`.
+///
+/// The thresholds are reset to the defaults when the speed is [`None`].
+pub fn apply_movement_calibration(
+    context: &mut PlayerContext,
+    walk_speed: Option<f32>,
+    double_jump_speed: Option<f32>,
+) {
+    context.config.adjusting_medium_threshold =
+        calibrated_threshold(ADJUSTING_MEDIUM_THRESHOLD, walk_speed, REFERENCE_WALK_SPEED);
+    context.config.double_jump_threshold = calibrated_threshold(
+        DOUBLE_JUMP_THRESHOLD,
+        double_jump_speed,
+        REFERENCE_DOUBLE_JUMP_SPEED,
+    );
+    context.config.double_jump_auto_mob_threshold = calibrated_threshold(
+        DOUBLE_JUMP_AUTO_MOB_THRESHOLD,
+        double_jump_speed,
+        REFERENCE_DOUBLE_JUMP_SPEED,
+    );
+}
+
+/// Applies calibrated horizontal `teleport_x_distance` and vertical `teleport_y_distance` to the
+/// teleport thresholds of `context`.
+///
+/// The thresholds are reset to the defaults when the distance is [`None`].
+pub fn apply_teleport_calibration(
+    context: &mut PlayerContext,
+    teleport_x_distance: Option<f32>,
+    teleport_y_distance: Option<f32>,
+) {
+    context.config.teleporting_threshold = calibrated_threshold(
+        TELEPORTING_THRESHOLD,
+        teleport_x_distance,
+        REFERENCE_TELEPORT_X_DISTANCE,
+    );
+    context.config.teleporting_auto_mob_threshold = calibrated_threshold(
+        TELEPORTING_AUTO_MOB_THRESHOLD,
+        teleport_x_distance,
+        REFERENCE_TELEPORT_X_DISTANCE,
+    );
+    context.config.teleport_with_jump_threshold = calibrated_threshold(
+        TELEPORT_WITH_JUMP_THRESHOLD,
+        teleport_y_distance,
+        REFERENCE_TELEPORT_Y_DISTANCE,
+    );
+    context.config.up_jump_and_teleport_threshold = calibrated_threshold(
+        UP_JUMP_AND_TELEPORT_THRESHOLD,
+        teleport_y_distance,
+        REFERENCE_TELEPORT_Y_DISTANCE,
+    );
+    context.config.teleport_fall_threshold = calibrated_threshold(
+        TELEPORT_FALL_THRESHOLD,
+        teleport_y_distance,
+        REFERENCE_TELEPORT_Y_DISTANCE,
+    );
+}
+
+fn complete_calibration(
+    context: &mut PlayerContext,
+    walk_speed: f32,
+    double_jump_speed: f32,
+    teleport_distance: Option<(f32, f32)>,
+) {
+    if walk_speed <= 0.0 || double_jump_speed <= walk_speed {
+        info!(target: "player", "movement calibration failed walk {walk_speed}, double jump {double_jump_speed}");
+        return;
+    }
+
+    // Keeps the walk and double jump speeds even if teleporting did not move the player
+    let teleport_distance = teleport_distance.filter(|(x, y)| *x > 0.0 && *y > 0.0);
+    info!(target: "player", "movement calibrated walk {walk_speed}, double jump {double_jump_speed}, teleport {teleport_distance:?}");
+    context.movement_calibration = Some(MovementCalibration {
+        walk_speed,
+        double_jump_speed,
+        teleport_distance,
+    });
+}
+
+/// Rescales `threshold` tuned at `reference_speed` to the calibrated `speed`.
+#[inline]
+pub fn calibrated_threshold(threshold: i32, speed: Option<f32>, reference_speed: f32) -> i32 {
+    match speed {
+        Some(speed) if speed > 0.0 => {
+            ((threshold as f32 * speed / reference_speed).round() as i32).max(1)
+        }
+        _ => threshold,
+    }
+}
+
+#[inline]
+fn opposite_direction(direction: KeyKind, left_key: KeyKind, right_key: KeyKind) -> KeyKind {
+    if direction == right_key {
+        left_key
+    } else {
+        right_key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calibrated_threshold_uncalibrated() {
+        assert_eq!(calibrated_threshold(25, None, 2.0), 25);
+        assert_eq!(calibrated_threshold(25, Some(0.0), 2.0), 25);
+    }
+
+    #[test]
+    fn calibrated_threshold_rescaled() {
+        assert_eq!(calibrated_threshold(25, Some(2.0), 2.0), 25);
+        assert_eq!(calibrated_threshold(25, Some(1.0), 2.0), 13);
+        assert_eq!(calibrated_threshold(3, Some(1.0), 0.5), 6);
+        assert_eq!(calibrated_threshold(3, Some(0.01), 0.5), 1);
+    }
+
+    #[test]
+    fn complete_calibration_rejects_invalid_speeds() {
+        let mut context = PlayerContext::default();
+
+        complete_calibration(&mut context, 0.0, 2.0, None);
+        assert_eq!(context.take_movement_calibration(), None);
+
+        complete_calibration(&mut context, 1.0, 0.5, None);
+        assert_eq!(context.take_movement_calibration(), None);
+
+        complete_calibration(&mut context, 0.5, 2.0, None);
+        assert_eq!(
+            context.take_movement_calibration(),
+            Some(MovementCalibration {
+                walk_speed: 0.5,
+                double_jump_speed: 2.0,
+                teleport_distance: None,
+            })
+        );
+    }
+
+    #[test]
+    fn complete_calibration_ignores_invalid_teleport_distance() {
+        let mut context = PlayerContext::default();
+
+        complete_calibration(&mut context, 0.5, 2.0, Some((0.0, 15.0)));
+        assert_eq!(
+            context
+                .take_movement_calibration()
+                .unwrap()
+                .teleport_distance,
+            None
+        );
+
+        complete_calibration(&mut context, 0.5, 2.0, Some((10.0, 15.0)));
+        assert_eq!(
+            context
+                .take_movement_calibration()
+                .unwrap()
+                .teleport_distance,
+            Some((10.0, 15.0))
+        );
+    }
+
+    #[test]
+    fn apply_teleport_calibration_rescales_thresholds() {
+        let mut context = PlayerContext::default();
+
+        apply_teleport_calibration(&mut context, Some(24.0), Some(8.0));
+        assert_eq!(context.config.teleporting_threshold, 24);
+        assert_eq!(context.config.teleporting_auto_mob_threshold, 16);
+        assert_eq!(context.config.teleport_with_jump_threshold, 10);
+        assert_eq!(context.config.teleport_fall_threshold, 8);
+
+        apply_teleport_calibration(&mut context, None, None);
+        assert_eq!(context.config.teleporting_threshold, TELEPORTING_THRESHOLD);
+        assert_eq!(
+            context.config.teleport_fall_threshold,
+            TELEPORT_FALL_THRESHOLD
+        );
+    }
+}
@@ -8,6 +8,7 @@ use crate::{
     bridge::KeyKind,
     ecs::{Resources, transition, transition_if, try_some_transition},
     player::PlayerEntity,
+    tick,
 };
 
 #[derive(Clone, Copy, Debug)]
@@ -83,7 +84,7 @@ fn update_entering(resources: &Resources, cash_shop: &mut CashShop, key: KeyKind
 
 fn update_entered(cash_shop: &mut CashShop, timeout: Timeout) {
     // Exit after 10 secs
-    match next_timeout_lifecycle(timeout, 305) {
+    match next_timeout_lifecycle(timeout, tick::scaled(305)) {
         Lifecycle::Ended => transition!(cash_shop, State::Exitting),
         Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
             transition!(cash_shop, State::Entered(timeout))
@@ -104,7 +105,7 @@ fn update_exitting(resources: &Resources, cash_shop: &mut CashShop) {
 
 fn update_stalling(cash_shop: &mut CashShop, timeout: Timeout) {
     // Return after 3 secs
-    match next_timeout_lifecycle(timeout, 90) {
+    match next_timeout_lifecycle(timeout, tick::scaled(90)) {
         Lifecycle::Ended => transition!(cash_shop, State::Completed),
         Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
             transition!(cash_shop, State::Stalling(timeout))
@@ -1,4 +1,5 @@
 use crate::{
+    KeyboardLayout,
     array::Array,
     bridge::KeyKind,
     ecs::{Resources, transition, transition_if, try_some_transition},
@@ -7,6 +8,7 @@ use crate::{
         timeout::{Lifecycle, Timeout, next_timeout_lifecycle},
         transition_from_action,
     },
+    tick,
 };
 
 const MAX_RETRY: u32 = 3;
@@ -27,6 +29,8 @@ impl ChattingContent {
 enum State {
     OpeningMenu(Timeout, u32),
     Typing(Timeout, usize),
+    /// Waits for the whole content pasted through the clipboard to be entered.
+    Pasting(Timeout),
     Completing(Timeout, bool),
 }
 
@@ -51,8 +55,17 @@ pub fn update_chatting_state(
     mut chatting: Chatting,
 ) {
     match chatting.state {
-        State::OpeningMenu(_, _) => update_opening_menu(resources, &mut chatting),
-        State::Typing(_, _) => update_typing(resources, &mut chatting),
+        State::OpeningMenu(_, _) => update_opening_menu(
+            resources,
+            &mut chatting,
+            player.context.config.chat_clipboard_paste,
+        ),
+        State::Typing(_, _) => update_typing(
+            resources,
+            &mut chatting,
+            player.context.config.keyboard_layout,
+        ),
+        State::Pasting(_) => update_pasting(resources, &mut chatting),
         State::Completing(_, _) => update_completing(resources, &mut chatting),
     };
 
@@ -72,21 +85,26 @@ pub fn update_chatting_state(
     }
 }
 
-fn update_opening_menu(resources: &Resources, chatting: &mut Chatting) {
+fn update_opening_menu(resources: &Resources, chatting: &mut Chatting, paste: bool) {
     let State::OpeningMenu(timeout, retry_count) = chatting.state else {
         panic!("chatting state is not opening menu");
     };
 
-    match next_timeout_lifecycle(timeout, 35) {
+    match next_timeout_lifecycle(timeout, tick::scaled(35)) {
         Lifecycle::Started(timeout) => {
             transition!(chatting, State::OpeningMenu(timeout, retry_count), {
                 resources.input.send_key(KeyKind::Enter);
             })
         }
         Lifecycle::Ended => {
+            let next_state = if paste {
+                State::Pasting(Timeout::default())
+            } else {
+                State::Typing(Timeout::default(), 0)
+            };
             transition_if!(
                 chatting,
-                State::Typing(Timeout::default(), 0),
+                next_state,
                 resources.detector().detect_chat_menu_opened()
             );
             transition_if!(
@@ -102,12 +120,12 @@ fn update_opening_menu(resources: &Resources, chatting: &mut Chatting) {
     }
 }
 
-fn update_typing(resources: &Resources, chatting: &mut Chatting) {
+fn update_typing(resources: &Resources, chatting: &mut Chatting, layout: KeyboardLayout) {
     let State::Typing(timeout, index) = chatting.state else {
         panic!("chatting state is not typing");
     };
 
-    match next_timeout_lifecycle(timeout, 3) {
+    match next_timeout_lifecycle(timeout, tick::scaled(3)) {
         Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
             transition!(chatting, State::Typing(timeout, index))
         }
@@ -120,7 +138,7 @@ fn update_typing(resources: &Resources, chatting: &mut Chatting) {
                     .as_slice()
                     .get(index)
                     .copied()
-                    .and_then(to_key_kind)
+                    .and_then(|character| to_key_kind(character, layout))
             );
             resources.input.send_key(key);
             transition_if!(
@@ -136,12 +154,39 @@ fn update_typing(resources: &Resources, chatting: &mut Chatting) {
     }
 }
 
+/// Updates [`State::Pasting`] contextual state.
+///
+/// Falls back to [`State::Typing`] if the content could not be pasted.
+fn update_pasting(resources: &Resources, chatting: &mut Chatting) {
+    let State::Pasting(timeout) = chatting.state else {
+        panic!("chatting state is not pasting");
+    };
+
+    match next_timeout_lifecycle(timeout, tick::scaled(10)) {
+        Lifecycle::Started(timeout) => {
+            let content = chatting.content.iter().collect::<String>();
+            transition_if!(
+                chatting,
+                State::Pasting(timeout),
+                State::Typing(Timeout::default(), 0),
+                resources.input.send_paste(&content)
+            );
+        }
+        Lifecycle::Updated(timeout) => transition!(chatting, State::Pasting(timeout)),
+        Lifecycle::Ended => {
+            transition!(chatting, State::Completing(Timeout::default(), false), {
+                resources.input.send_key(KeyKind::Enter);
+            })
+        }
+    }
+}
+
 fn update_completing(resources: &Resources, chatting: &mut Chatting) {
     let State::Completing(timeout, _) = chatting.state else {
         panic!("chatting state is not completing");
     };
 
-    match next_timeout_lifecycle(timeout, 35) {
+    match next_timeout_lifecycle(timeout, tick::scaled(35)) {
         Lifecycle::Updated(timeout) | Lifecycle::Started(timeout) => {
             transition!(chatting, State::Completing(timeout, false));
         }
@@ -153,10 +198,13 @@ fn update_completing(resources: &Resources, chatting: &mut Chatting) {
     }
 }
 
+/// Maps `character` to the key labeled with it on `layout`.
+///
+/// Characters that are typed with a modifier on `layout` are not supported.
 // TODO: Support non-ASCII characters and ASCII capital characters
 #[inline]
-fn to_key_kind(character: char) -> Option<KeyKind> {
-    match character {
+fn to_key_kind(character: char, layout: KeyboardLayout) -> Option<KeyKind> {
+    let key = match character {
         'A' | 'a' => Some(KeyKind::A),
         'B' | 'b' => Some(KeyKind::B),
         'C' | 'c' => Some(KeyKind::C),
@@ -202,8 +250,35 @@ fn to_key_kind(character: char) -> Option<KeyKind> {
         ',' => Some(KeyKind::Comma),
         '.' => Some(KeyKind::Period),
         '/' => Some(KeyKind::Slash),
+        '-' => Some(KeyKind::Minus),
 
         _ => None,
+    }?;
+
+    match (layout, key) {
+        (KeyboardLayout::Qwerty, key) => Some(key),
+        (
+            KeyboardLayout::Azerty,
+            KeyKind::Zero
+            | KeyKind::One
+            | KeyKind::Two
+            | KeyKind::Three
+            | KeyKind::Four
+            | KeyKind::Five
+            | KeyKind::Six
+            | KeyKind::Seven
+            | KeyKind::Eight
+            | KeyKind::Nine
+            | KeyKind::Tilde
+            | KeyKind::Quote
+            | KeyKind::Period
+            | KeyKind::Slash,
+        )
+        | (
+            KeyboardLayout::Qwertz,
+            KeyKind::Tilde | KeyKind::Quote | KeyKind::Semicolon | KeyKind::Slash,
+        ) => None,
+        (KeyboardLayout::Azerty | KeyboardLayout::Qwertz, key) => Some(key),
     }
 }
 
@@ -231,7 +306,7 @@ mod tests {
             0,
         );
 
-        update_opening_menu(&resources, &mut chatting);
+        update_opening_menu(&resources, &mut chatting, false);
 
         assert_matches!(chatting.state, State::Typing(_, 0));
     }
@@ -253,7 +328,7 @@ mod tests {
             0,
         );
 
-        update_opening_menu(&resources, &mut chatting);
+        update_opening_menu(&resources, &mut chatting, false);
 
         assert_matches!(chatting.state, State::OpeningMenu(_, 1));
     }
@@ -275,7 +350,7 @@ mod tests {
             MAX_RETRY,
         );
 
-        update_opening_menu(&resources, &mut chatting);
+        update_opening_menu(&resources, &mut chatting, false);
 
         assert_matches!(chatting.state, State::Completing(_, false));
     }
@@ -299,12 +374,90 @@ mod tests {
                 i,
             );
 
-            update_typing(&resources, &mut chatting);
+            update_typing(&resources, &mut chatting, KeyboardLayout::Qwerty);
 
             assert_matches!(chatting.state, State::Typing(_, index) if index == i + 1);
         }
     }
 
+    #[test]
+    fn update_opening_menu_transitions_to_pasting() {
+        let mut detector = MockDetector::default();
+        detector.expect_detect_chat_menu_opened().returning(|| true);
+        let resources = Resources::new(None, Some(detector));
+        let mut chatting = Chatting::new(Array::new());
+        chatting.state = State::OpeningMenu(
+            Timeout {
+                current: 35,
+                started: true,
+                ..Default::default()
+            },
+            0,
+        );
+
+        update_opening_menu(&resources, &mut chatting, true);
+
+        assert_matches!(chatting.state, State::Pasting(_));
+    }
+
+    #[test]
+    fn update_pasting_sends_paste_and_completes() {
+        let mut keys = MockInput::default();
+        keys.expect_send_paste()
+            .once()
+            .withf(|text| text == "Hé!")
+            .return_const(true);
+        keys.expect_send_key().once().with(eq(KeyKind::Enter));
+        let resources = Resources::new(Some(keys), None);
+        let mut chatting = Chatting::new(Array::from_iter(['H', 'é', '!']));
+        chatting.state = State::Pasting(Timeout::default());
+
+        update_pasting(&resources, &mut chatting);
+        assert_matches!(chatting.state, State::Pasting(_));
+
+        chatting.state = State::Pasting(Timeout {
+            current: tick::scaled(10),
+            started: true,
+            ..Default::default()
+        });
+        update_pasting(&resources, &mut chatting);
+        assert_matches!(chatting.state, State::Completing(_, false));
+    }
+
+    #[test]
+    fn update_pasting_falls_back_to_typing() {
+        let mut keys = MockInput::default();
+        keys.expect_send_paste().once().return_const(false);
+        let resources = Resources::new(Some(keys), None);
+        let mut chatting = Chatting::new(Array::from_iter(['a']));
+        chatting.state = State::Pasting(Timeout::default());
+
+        update_pasting(&resources, &mut chatting);
+
+        assert_matches!(chatting.state, State::Typing(_, 0));
+    }
+
+    #[test]
+    fn to_key_kind_by_layout() {
+        assert_eq!(to_key_kind('1', KeyboardLayout::Qwerty), Some(KeyKind::One));
+        assert_eq!(to_key_kind('1', KeyboardLayout::Azerty), None);
+        assert_eq!(to_key_kind('a', KeyboardLayout::Azerty), Some(KeyKind::A));
+        assert_eq!(
+            to_key_kind(';', KeyboardLayout::Azerty),
+            Some(KeyKind::Semicolon)
+        );
+        assert_eq!(to_key_kind(';', KeyboardLayout::Qwertz), None);
+        assert_eq!(to_key_kind('z', KeyboardLayout::Qwertz), Some(KeyKind::Z));
+        assert_eq!(
+            to_key_kind('-', KeyboardLayout::Azerty),
+            Some(KeyKind::Minus)
+        );
+        assert_eq!(
+            to_key_kind('-', KeyboardLayout::Qwertz),
+            Some(KeyKind::Minus)
+        );
+    }
+
     #[test]
     fn update_typing_finishes_after_last_character() {
         let mut keys = MockInput::default();
@@ -321,7 +474,7 @@ mod tests {
             0,
         );
 
-        update_typing(&resources, &mut chatting);
+        update_typing(&resources, &mut chatting, KeyboardLayout::Qwerty);
 
         assert_matches!(chatting.state, State::Completing(_, false));
     }
@@ -339,7 +492,7 @@ mod tests {
             0,
         );
 
-        update_typing(&resources, &mut chatting);
+        update_typing(&resources, &mut chatting, KeyboardLayout::Qwerty);
 
         assert_matches!(chatting.state, State::Completing(_, false));
     }
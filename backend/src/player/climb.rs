@@ -0,0 +1,361 @@
+use opencv::core::Point;
+
+use super::{
+    JUMP_THRESHOLD, Player,
+    moving::{MOVE_TIMEOUT, Moving},
+    state::LastMovement,
+    timeout::{ChangeAxis, MovingLifecycle, Timeout, next_moving_lifecycle_with_axis},
+};
+use crate::{
+    ActionKeyDirection, Rope,
+    bridge::KeyKind,
+    ecs::{Resources, transition},
+    player::{PlayerEntity, transition_to_moving},
+    tick,
+};
+
+/// Maximum number of ropes that can be used for climbing.
+pub const MAX_ROPES_COUNT: usize = 16;
+
+/// Maximum x distance from a rope for it to be considered reachable.
+const ROPE_X_RANGE: i32 = 10;
+
+/// Maximum x distance from a rope to start grabbing it.
+const GRAB_X_THRESHOLD: i32 = 1;
+
+/// Maximum y distance between the rope top and the destination for the rope to be usable.
+const ROPE_TOP_Y_THRESHOLD: i32 = 3;
+
+/// Maximum y distance from the destination to dismount when the rope continues above it.
+const DISMOUNT_Y_THRESHOLD: i32 = 1;
+
+/// Timeout for when walking to the rope and x position not changed.
+const APPROACHING_TIMEOUT: u32 = MOVE_TIMEOUT * 4;
+
+/// Timeout for when grabbing the rope and y position not changed.
+const GRABBING_TIMEOUT: u32 = MOVE_TIMEOUT * 3;
+
+/// The tick to retry grabbing the rope with a jump.
+const GRABBING_RETRY_TICK: u32 = MOVE_TIMEOUT;
+
+/// Timeout for when climbing or dismounting and y position not changed.
+const CLIMBING_TIMEOUT: u32 = MOVE_TIMEOUT * 2;
+
+/// States of climbing a rope.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    /// Walks to the rope x.
+    Approaching,
+    /// Holds up and jumps if needed to grab the rope.
+    Grabbing,
+    /// Holds up until the destination is reached.
+    Climbing,
+    /// Jumps off the rope sideways when the destination is below the rope top.
+    Dismounting,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Climbing {
+    pub moving: Moving,
+    rope: Rope,
+    state: State,
+}
+
+impl Climbing {
+    pub fn new(moving: Moving, rope: Rope) -> Self {
+        Self {
+            moving,
+            rope,
+            state: State::Approaching,
+        }
+    }
+
+    #[inline]
+    fn moving(mut self, moving: Moving) -> Self {
+        self.moving = moving;
+        self
+    }
+
+    #[inline]
+    fn state(mut self, state: State) -> Self {
+        self.state = state;
+        self
+    }
+}
+
+/// Updates the [`Player::Climbing`] contextual state.
+///
+/// This state can only be transitioned via [`Player::Moving`] when there is a reachable rope
+/// leading to the destination. It walks to the rope, grabs it with Up (jumping if the rope
+/// bottom is above the player), climbs until the destination `y` and dismounts with a side
+/// jump if the rope continues above the destination.
+pub fn update_climbing_state(resources: &Resources, player: &mut PlayerEntity) {
+    let Player::Climbing(climbing) = player.state else {
+        panic!("state is not climbing")
+    };
+    let prev_pos = climbing.moving.pos;
+    let left_key = player.context.config.left_key;
+    let right_key = player.context.config.right_key;
+    let up_key = player.context.config.up_key;
+    let (axis, timeout) = match climbing.state {
+        State::Approaching => (ChangeAxis::Horizontal, APPROACHING_TIMEOUT),
+        State::Grabbing => (ChangeAxis::Vertical, GRABBING_TIMEOUT),
+        State::Climbing | State::Dismounting => (ChangeAxis::Vertical, CLIMBING_TIMEOUT),
+    };
+
+    match next_moving_lifecycle_with_axis(
+        climbing.moving,
+        player.context.last_known_pos.expect("in positional state"),
+        tick::scaled(timeout),
+        axis,
+    ) {
+        MovingLifecycle::Started(moving) => {
+            transition!(player, Player::Climbing(climbing.moving(moving)), {
+                player.context.last_movement = Some(LastMovement::Climbing);
+            })
+        }
+        MovingLifecycle::Ended(moving) => transition_to_moving!(player, moving, {
+            resources.input.send_key_up(up_key);
+            resources.input.send_key_up(left_key);
+            resources.input.send_key_up(right_key);
+        }),
+        MovingLifecycle::Updated(moving) => {
+            let cur_pos = moving.pos;
+            let rope = climbing.rope;
+
+            match climbing.state {
+                State::Approaching => {
+                    let x_direction = rope.x - cur_pos.x;
+                    if x_direction.abs() <= GRAB_X_THRESHOLD {
+                        resources.input.send_key_up(left_key);
+                        resources.input.send_key_up(right_key);
+                        resources.input.send_key_down(up_key);
+                        if rope.y_start > cur_pos.y {
+                            resources.input.send_key(player.context.config.jump_key);
+                        }
+                        transition!(
+                            player,
+                            Player::Climbing(
+                                climbing
+                                    .moving(
+                                        moving.timeout(Timeout::default()).timeout_started(true)
+                                    )
+                                    .state(State::Grabbing)
+                            )
+                        );
+                    }
+
+                    let (key_down, key_up, direction) = if x_direction > 0 {
+                        (right_key, left_key, ActionKeyDirection::Right)
+                    } else {
+                        (left_key, right_key, ActionKeyDirection::Left)
+                    };
+                    resources.input.send_key_up(key_up);
+                    resources.input.send_key_down(key_down);
+                    player.context.last_known_direction = direction;
+                }
+                State::Grabbing => {
+                    if cur_pos.y > prev_pos.y {
+                        transition!(
+                            player,
+                            Player::Climbing(climbing.moving(moving).state(State::Climbing))
+                        );
+                    }
+                    if moving.timeout.current == tick::scaled(GRABBING_RETRY_TICK) {
+                        resources.input.send_key(player.context.config.jump_key);
+                    }
+                }
+                State::Climbing => {
+                    let (y_distance, y_direction) = moving.y_distance_direction_from(true, cur_pos);
+                    if y_direction <= 0 {
+                        resources.input.send_key_up(up_key);
+                        transition_to_moving!(player, moving);
+                    }
+
+                    if y_distance <= DISMOUNT_Y_THRESHOLD
+                        && rope.y_end - moving.dest.y > ROPE_TOP_Y_THRESHOLD
+                    {
+                        let key = dismount_key(moving.dest, rope, left_key, right_key);
+                        resources.input.send_key_up(up_key);
+                        resources.input.send_key_down(key);
+                        resources.input.send_key(player.context.config.jump_key);
+                        transition!(
+                            player,
+                            Player::Climbing(climbing.moving(moving).state(State::Dismounting))
+                        );
+                    }
+                }
+                State::Dismounting => {
+                    if cur_pos.x != prev_pos.x && cur_pos.y == prev_pos.y {
+                        resources.input.send_key_up(dismount_key(
+                            moving.dest,
+                            rope,
+                            left_key,
+                            right_key,
+                        ));
+                        transition_to_moving!(player, moving);
+                    }
+                }
+            }
+
+            player.state = Player::Climbing(climbing.moving(moving));
+        }
+    }
+}
+
+/// Finds the closest rope within [`ROPE_X_RANGE`] of `cur_pos` that can be climbed to `dest`.
+///
+/// A rope is climbable if its bottom is within a jump above `cur_pos` and its top reaches
+/// `dest`.
+pub fn find_climbable_rope(ropes: &[Rope], cur_pos: Point, dest: Point) -> Option<Rope> {
+    ropes
+        .iter()
+        .copied()
+        .filter(|rope| {
+            (rope.x - cur_pos.x).abs() <= ROPE_X_RANGE
+                && rope.y_start <= cur_pos.y + JUMP_THRESHOLD
+                && rope.y_end > cur_pos.y
+                && rope.y_end >= dest.y - ROPE_TOP_Y_THRESHOLD
+        })
+        .min_by_key(|rope| (rope.x - cur_pos.x).abs())
+}
+
+#[inline]
+fn dismount_key(dest: Point, rope: Rope, left_key: KeyKind, right_key: KeyKind) -> KeyKind {
+    if dest.x >= rope.x {
+        right_key
+    } else {
+        left_key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::bridge::MockInput;
+    use crate::player::PlayerContext;
+
+    fn mock_rope() -> Rope {
+        Rope {
+            x: 10,
+            y_start: 5,
+            y_end: 40,
+        }
+    }
+
+    #[test]
+    fn find_climbable_rope_closest() {
+        let ropes = [
+            mock_rope(),
+            Rope {
+                x: 3,
+                y_start: 0,
+                y_end: 40,
+            },
+        ];
+
+        assert_eq!(
+            find_climbable_rope(&ropes, Point::new(0, 0), Point::new(0, 40)),
+            Some(ropes[1])
+        );
+    }
+
+    #[test]
+    fn find_climbable_rope_none() {
+        let ropes = [mock_rope()];
+
+        // Too far x-wise
+        assert_eq!(
+            find_climbable_rope(&ropes, Point::new(30, 0), Point::new(30, 40)),
+            None
+        );
+        // Rope bottom is not reachable with a jump
+        assert_eq!(
+            find_climbable_rope(
+                &[Rope {
+                    y_start: 20,
+                    ..mock_rope()
+                }],
+                Point::new(10, 0),
+                Point::new(10, 40)
+            ),
+            None
+        );
+        // Rope top does not reach destination
+        assert_eq!(
+            find_climbable_rope(&ropes, Point::new(10, 0), Point::new(10, 60)),
+            None
+        );
+    }
+
+    #[test]
+    fn update_climbing_state_approaching_grabs_rope() {
+        let mut keys = MockInput::new();
+        keys.expect_send_key_up().with(eq(KeyKind::Left)).once();
+        keys.expect_send_key_up().with(eq(KeyKind::Right)).once();
+        keys.expect_send_key_down().with(eq(KeyKind::Up)).once();
+        keys.expect_send_key().with(eq(KeyKind::Space)).once();
+        let resources = Resources::new(Some(keys), None);
+
+        let pos = Point::new(10, 0);
+        let mut context = PlayerContext::default();
+        context.last_known_pos = Some(pos);
+        context.config.jump_key = KeyKind::Space;
+        let mut player = PlayerEntity {
+            state: Player::Climbing(Climbing::new(
+                Moving::new(pos, Point::new(10, 40), false, None).timeout_started(true),
+                mock_rope(),
+            )),
+            context,
+        };
+
+        update_climbing_state(&resources, &mut player);
+
+        assert_matches!(
+            player.state,
+            Player::Climbing(Climbing {
+                state: State::Grabbing,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn update_climbing_state_climbing_dismounts_below_rope_top() {
+        let mut keys = MockInput::new();
+        keys.expect_send_key_up().with(eq(KeyKind::Up)).once();
+        keys.expect_send_key_down().with(eq(KeyKind::Right)).once();
+        keys.expect_send_key().with(eq(KeyKind::Space)).once();
+        let resources = Resources::new(Some(keys), None);
+
+        let pos = Point::new(10, 20);
+        let mut context = PlayerContext::default();
+        context.last_known_pos = Some(pos);
+        context.config.jump_key = KeyKind::Space;
+        let mut player = PlayerEntity {
+            state: Player::Climbing(
+                Climbing::new(
+                    Moving::new(Point::new(10, 19), Point::new(15, 20), false, None)
+                        .timeout_started(true),
+                    mock_rope(),
+                )
+                .state(State::Climbing),
+            ),
+            context,
+        };
+
+        update_climbing_state(&resources, &mut player);
+
+        assert_matches!(
+            player.state,
+            Player::Climbing(Climbing {
+                state: State::Dismounting,
+                ..
+            })
+        );
+    }
+}
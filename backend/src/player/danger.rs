@@ -0,0 +1,204 @@
+use opencv::core::Point;
+
+use crate::DangerZone;
+
+/// Maximum number of danger zones that can be avoided.
+pub const MAX_DANGER_ZONES_COUNT: usize = 16;
+
+/// Maximum y distance from a danger zone floor for a position to be considered on the zone.
+const FLOOR_Y_THRESHOLD: i32 = 2;
+
+/// Extra x distance past a danger zone edge to move to when escaping the zone.
+const ESCAPE_X_MARGIN: i32 = 3;
+
+/// Maximum x distance from a danger zone edge to start jumping over it when walking.
+const JUMP_OVER_X_THRESHOLD: i32 = 4;
+
+/// Finds the danger zone the player at `pos` is standing in.
+pub fn find_danger_zone(zones: &[DangerZone], pos: Point) -> Option<DangerZone> {
+    zones
+        .iter()
+        .copied()
+        .find(|zone| contains_x(*zone, pos.x) && is_on_floor(*zone, pos.y))
+}
+
+/// Finds the closest point on the same floor outside of the danger zone `pos` is standing in.
+///
+/// Returns [`None`] if `pos` is not inside any danger zone or if both edges of the zone are
+/// inside other danger zones.
+pub fn find_danger_zone_escape_point(zones: &[DangerZone], pos: Point) -> Option<Point> {
+    let zone = find_danger_zone(zones, pos)?;
+    let mut points = [
+        Point::new(zone.x_start - ESCAPE_X_MARGIN, pos.y),
+        Point::new(zone.x_end + ESCAPE_X_MARGIN, pos.y),
+    ];
+    points.sort_by_key(|point| (point.x - pos.x).abs());
+
+    points
+        .into_iter()
+        .find(|point| find_danger_zone(zones, *point).is_none())
+}
+
+/// Whether the player at `cur_pos` should adjust `x` toward `dest` before moving vertically.
+///
+/// This is the case when the player is standing in or would land in a danger zone that does not
+/// contain `dest`.
+pub fn should_adjust_out_of_danger_zone(zones: &[DangerZone], cur_pos: Point, dest: Point) -> bool {
+    zones.iter().copied().any(|zone| {
+        contains_x(zone, cur_pos.x)
+            && !contains_x(zone, dest.x)
+            && (is_on_floor(zone, cur_pos.y) || is_on_floor(zone, dest.y))
+    })
+}
+
+/// Finds the closest danger zone ahead within [`JUMP_OVER_X_THRESHOLD`] when walking from `pos`
+/// to `dest`.
+///
+/// The danger zone must be on the same floor as `pos` and lie between `pos` and `dest`.
+pub fn find_danger_zone_ahead(zones: &[DangerZone], pos: Point, dest: Point) -> Option<DangerZone> {
+    let direction = (dest.x - pos.x).signum();
+    let edge_distance = |zone: DangerZone| {
+        if direction > 0 {
+            zone.x_start - pos.x
+        } else {
+            pos.x - zone.x_end
+        }
+    };
+    let is_dest_past = |zone: DangerZone| {
+        if direction > 0 {
+            dest.x > zone.x_end
+        } else {
+            dest.x < zone.x_start
+        }
+    };
+
+    if direction == 0 {
+        return None;
+    }
+    zones
+        .iter()
+        .copied()
+        .filter(|zone| {
+            let distance = edge_distance(*zone);
+            is_on_floor(*zone, pos.y)
+                && is_dest_past(*zone)
+                && distance > 0
+                && distance <= JUMP_OVER_X_THRESHOLD
+        })
+        .min_by_key(|zone| edge_distance(*zone))
+}
+
+#[inline]
+fn contains_x(zone: DangerZone, x: i32) -> bool {
+    (zone.x_start..=zone.x_end).contains(&x)
+}
+
+#[inline]
+fn is_on_floor(zone: DangerZone, y: i32) -> bool {
+    (zone.y - y).abs() <= FLOOR_Y_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_zone() -> DangerZone {
+        DangerZone {
+            x_start: 20,
+            x_end: 40,
+            y: 10,
+        }
+    }
+
+    #[test]
+    fn find_danger_zone_escape_point_closest_edge() {
+        let zones = [mock_zone()];
+
+        assert_eq!(
+            find_danger_zone_escape_point(&zones, Point::new(25, 11)),
+            Some(Point::new(17, 11))
+        );
+        assert_eq!(
+            find_danger_zone_escape_point(&zones, Point::new(38, 10)),
+            Some(Point::new(43, 10))
+        );
+        // Different floor
+        assert_eq!(
+            find_danger_zone_escape_point(&zones, Point::new(25, 20)),
+            None
+        );
+    }
+
+    #[test]
+    fn find_danger_zone_escape_point_skips_edges_in_other_zones() {
+        let left = DangerZone {
+            x_start: 0,
+            x_end: 19,
+            y: 10,
+        };
+        let right = DangerZone {
+            x_start: 41,
+            x_end: 60,
+            y: 10,
+        };
+
+        // Closest edge inside another zone escapes to the other edge
+        assert_eq!(
+            find_danger_zone_escape_point(&[mock_zone(), left], Point::new(25, 10)),
+            Some(Point::new(43, 10))
+        );
+        // Both edges inside other zones
+        assert_eq!(
+            find_danger_zone_escape_point(&[mock_zone(), left, right], Point::new(25, 10)),
+            None
+        );
+    }
+
+    #[test]
+    fn should_adjust_out_of_danger_zone_standing_or_landing() {
+        let zones = [mock_zone()];
+
+        // Standing in zone and destination above outside of zone
+        assert!(should_adjust_out_of_danger_zone(
+            &zones,
+            Point::new(39, 10),
+            Point::new(41, 30)
+        ));
+        // Falling into zone
+        assert!(should_adjust_out_of_danger_zone(
+            &zones,
+            Point::new(21, 30),
+            Point::new(19, 10)
+        ));
+        // Destination is inside zone
+        assert!(!should_adjust_out_of_danger_zone(
+            &zones,
+            Point::new(21, 30),
+            Point::new(22, 10)
+        ));
+    }
+
+    #[test]
+    fn find_danger_zone_ahead_between_position_and_destination() {
+        let zones = [mock_zone()];
+
+        assert_eq!(
+            find_danger_zone_ahead(&zones, Point::new(17, 10), Point::new(60, 10)),
+            Some(zones[0])
+        );
+        assert_eq!(
+            find_danger_zone_ahead(&zones, Point::new(44, 10), Point::new(0, 10)),
+            Some(zones[0])
+        );
+        // Too far from the edge
+        assert_eq!(
+            find_danger_zone_ahead(&zones, Point::new(10, 10), Point::new(60, 10)),
+            None
+        );
+        // Destination before the zone
+        assert_eq!(
+            find_danger_zone_ahead(&zones, Point::new(17, 10), Point::new(19, 10)),
+            None
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{
+    array::Array,
+    bridge::KeyKind,
+    ecs::{Resources, transition, transition_if, try_some_transition},
+    player::{
+        Player, PlayerEntity, next_action,
+        timeout::{Lifecycle, Timeout, next_timeout_lifecycle},
+        transition_from_action,
+    },
+    tick,
+};
+
+/// Maximum number of keys pressed to auto-assign points before dismissing a level up popup.
+pub const MAX_LEVEL_UP_AUTO_ASSIGN_KEYS_COUNT: usize = 8;
+
+const MAX_RETRY: u32 = 3;
+
+/// States of dismissing popup.
+#[derive(Debug, Clone, Copy)]
+enum State {
+    /// Pressing the auto-assign key at the index.
+    Assigning(Timeout, usize),
+    /// Closing the popup by pressing ESC with the number of retries.
+    Closing(Timeout, u32),
+    /// Terminal state.
+    Completing,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DismissingPopup {
+    state: State,
+}
+
+impl Default for DismissingPopup {
+    fn default() -> Self {
+        Self {
+            state: State::Assigning(Timeout::default(), 0),
+        }
+    }
+}
+
+/// Updates [`Player::DismissingPopup`] contextual state.
+///
+/// The level up popups are dismissed by first pressing the configured auto-assign keys in order
+/// and then ESC until the popups are no longer detected.
+pub fn update_dismissing_popup_state(resources: &Resources, player: &mut PlayerEntity) {
+    let Player::DismissingPopup(mut dismissing) = player.state else {
+        panic!("state is not dismissing popup")
+    };
+    let keys = player.context.config.level_up_auto_assign_keys;
+
+    match dismissing.state {
+        State::Assigning(_, _) => update_assigning(resources, &mut dismissing, keys),
+        State::Closing(_, _) => update_closing(resources, &mut dismissing),
+        State::Completing => (),
+    };
+
+    let player_next_state = if matches!(dismissing.state, State::Completing) {
+        Player::Idle
+    } else {
+        Player::DismissingPopup(dismissing)
+    };
+
+    match next_action(&player.context) {
+        Some(_) => transition_from_action!(
+            player,
+            player_next_state,
+            matches!(player_next_state, Player::Idle)
+        ),
+        None => transition!(player, Player::Idle), // Force cancel if not from action
+    }
+}
+
+fn update_assigning(
+    resources: &Resources,
+    dismissing: &mut DismissingPopup,
+    keys: Array<KeyKind, MAX_LEVEL_UP_AUTO_ASSIGN_KEYS_COUNT>,
+) {
+    let State::Assigning(timeout, index) = dismissing.state else {
+        panic!("dismissing popup state is not assigning")
+    };
+
+    match next_timeout_lifecycle(timeout, tick::scaled(10)) {
+        Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
+            transition!(dismissing, State::Assigning(timeout, index))
+        }
+        Lifecycle::Ended => {
+            let key = try_some_transition!(
+                dismissing,
+                State::Closing(Timeout::default(), 0),
+                keys.as_slice().get(index).copied()
+            );
+            transition!(
+                dismissing,
+                State::Assigning(Timeout::default(), index + 1),
+                {
+                    resources.input.send_key(key);
+                }
+            );
+        }
+    }
+}
+
+fn update_closing(resources: &Resources, dismissing: &mut DismissingPopup) {
+    let State::Closing(timeout, retry_count) = dismissing.state else {
+        panic!("dismissing popup state is not closing")
+    };
+
+    match next_timeout_lifecycle(timeout, tick::scaled(30)) {
+        Lifecycle::Started(timeout) => {
+            transition!(dismissing, State::Closing(timeout, retry_count), {
+                resources.input.send_key(KeyKind::Esc);
+            })
+        }
+        Lifecycle::Updated(timeout) => {
+            transition!(dismissing, State::Closing(timeout, retry_count))
+        }
+        Lifecycle::Ended => {
+            transition_if!(
+                dismissing,
+                State::Closing(Timeout::default(), retry_count + 1),
+                State::Completing,
+                retry_count < MAX_RETRY && resources.detector().detect_level_up_popup()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::{bridge::MockInput, detect::MockDetector};
+
+    #[test]
+    fn update_assigning_sends_keys_in_order_then_closes() {
+        let mut keys = MockInput::default();
+        keys.expect_send_key().once().with(eq(KeyKind::A));
+        keys.expect_send_key().once().with(eq(KeyKind::B));
+        let resources = Resources::new(Some(keys), None);
+        let assign_keys = Array::from_iter([KeyKind::A, KeyKind::B]);
+        let mut dismissing = DismissingPopup::default();
+
+        for i in 0..3 {
+            dismissing.state = State::Assigning(
+                Timeout {
+                    current: 10,
+                    started: true,
+                    ..Default::default()
+                },
+                i,
+            );
+
+            update_assigning(&resources, &mut dismissing, assign_keys);
+        }
+
+        assert_matches!(dismissing.state, State::Closing(_, 0));
+    }
+
+    #[test]
+    fn update_closing_retries_while_popup_detected() {
+        let mut detector = MockDetector::default();
+        detector.expect_detect_level_up_popup().returning(|| true);
+        let resources = Resources::new(None, Some(detector));
+        let mut dismissing = DismissingPopup {
+            state: State::Closing(
+                Timeout {
+                    current: 30,
+                    started: true,
+                    ..Default::default()
+                },
+                0,
+            ),
+        };
+
+        update_closing(&resources, &mut dismissing);
+
+        assert_matches!(dismissing.state, State::Closing(_, 1));
+
+        dismissing.state = State::Closing(
+            Timeout {
+                current: 30,
+                started: true,
+                ..Default::default()
+            },
+            MAX_RETRY,
+        );
+
+        update_closing(&resources, &mut dismissing);
+
+        assert_matches!(dismissing.state, State::Completing);
+    }
+
+    #[test]
+    fn update_closing_sends_esc_on_start() {
+        let mut keys = MockInput::default();
+        keys.expect_send_key().once().with(eq(KeyKind::Esc));
+        let resources = Resources::new(Some(keys), None);
+        let mut dismissing = DismissingPopup {
+            state: State::Closing(Timeout::default(), 0),
+        };
+
+        update_closing(&resources, &mut dismissing);
+
+        assert_matches!(
+            dismissing.state,
+            State::Closing(Timeout { started: true, .. }, 0)
+        );
+    }
+}
@@ -3,7 +3,7 @@ use std::cmp::Ordering;
 use opencv::core::Point;
 
 use super::{
-    Key, PingPongDirection, Player, PlayerAction,
+    Key, Player, PlayerAction,
     actions::{PingPong, update_from_auto_mob_action},
     moving::Moving,
     timeout::{
@@ -27,6 +27,7 @@ use crate::{
         timeout::{ChangeAxis, Timeout},
         transition_from_action, transition_to_moving,
     },
+    tick,
 };
 
 /// Minimum x distance from the destination required to perform a double jump.
@@ -35,9 +36,6 @@ pub const DOUBLE_JUMP_THRESHOLD: i32 = 25;
 /// Minimum x distance from the destination required to perform a double jump in auto mobbing.
 pub const DOUBLE_JUMP_AUTO_MOB_THRESHOLD: i32 = 15;
 
-/// Minimum x distance from the destination required to transition to [`Player::UseKey`].
-const USE_KEY_X_THRESHOLD: i32 = DOUBLE_JUMP_THRESHOLD;
-
 /// Minimum y distance from the destination required to transition to [`Player::UseKey`].
 const USE_KEY_Y_THRESHOLD: i32 = 10;
 
@@ -97,7 +95,7 @@ impl DoubleJumping {
     #[inline]
     fn update_jump_cooldown(&mut self) {
         self.cooldown_timeout =
-            match next_timeout_lifecycle(self.cooldown_timeout, COOLDOWN_TIMEOUT) {
+            match next_timeout_lifecycle(self.cooldown_timeout, tick::scaled(COOLDOWN_TIMEOUT)) {
                 Lifecycle::Started(timeout) => timeout,
                 Lifecycle::Ended => Timeout::default(),
                 Lifecycle::Updated(timeout) => timeout,
@@ -129,13 +127,15 @@ pub fn update_double_jumping_state(
         panic!("state is not double jumping")
     };
     let moving = double_jumping.moving;
+    let left_key = player.context.config.left_key;
+    let right_key = player.context.config.right_key;
     let ignore_grappling = double_jumping.forced || player.context.should_disable_grappling();
     let is_intermediate = moving.is_destination_intermediate();
-    let timeout = if double_jumping.forced {
+    let timeout = tick::scaled(if double_jumping.forced {
         TIMEOUT_FORCED
     } else {
         TIMEOUT
-    };
+    });
     let axis = if double_jumping.forced {
         // This ensures it won't double jump forever when jumping towards either
         // edges of the map.
@@ -165,8 +165,8 @@ pub fn update_double_jumping_state(
             transition!(player, Player::DoubleJumping(double_jumping.moving(moving)));
         }
         MovingLifecycle::Ended(moving) => transition_to_moving!(player, moving, {
-            resources.input.send_key_up(KeyKind::Right);
-            resources.input.send_key_up(KeyKind::Left);
+            resources.input.send_key_up(right_key);
+            resources.input.send_key_up(left_key);
         }),
         MovingLifecycle::Updated(mut moving) => {
             let (x_distance, x_direction) = moving.x_distance_direction_from(true, moving.pos);
@@ -176,16 +176,16 @@ pub fn update_double_jumping_state(
             if !moving.completed {
                 if !double_jumping.forced || player.context.config.teleport_key.is_some() {
                     let option = match x_direction.cmp(&0) {
-                        Ordering::Greater => {
-                            Some((KeyKind::Right, KeyKind::Left, ActionKeyDirection::Right))
-                        }
-                        Ordering::Less => {
-                            Some((KeyKind::Left, KeyKind::Right, ActionKeyDirection::Left))
-                        }
+                        Ordering::Greater => Some((right_key, left_key, ActionKeyDirection::Right)),
+                        Ordering::Less => Some((left_key, right_key, ActionKeyDirection::Left)),
                         _ => {
                             // Mage teleportation requires a direction
                             if player.context.config.teleport_key.is_some() {
-                                get_mage_teleport_direction(player.context.last_known_direction)
+                                get_mage_teleport_direction(
+                                    player.context.last_known_direction,
+                                    left_key,
+                                    right_key,
+                                )
                             } else {
                                 None
                             }
@@ -217,8 +217,8 @@ pub fn update_double_jumping_state(
                         double_jumping.update_jump_cooldown();
                     }
                 } else {
-                    resources.input.send_key_up(KeyKind::Right);
-                    resources.input.send_key_up(KeyKind::Left);
+                    resources.input.send_key_up(right_key);
+                    resources.input.send_key_up(left_key);
                     moving.completed = true;
                 }
             }
@@ -252,7 +252,7 @@ fn next_updated_state(
     }
 
     if moving.completed {
-        Player::DoubleJumping(double_jumping.moving(moving.timeout_current(TIMEOUT)))
+        Player::DoubleJumping(double_jumping.moving(moving.timeout_current(tick::scaled(TIMEOUT))))
     } else {
         Player::DoubleJumping(double_jumping.moving(moving))
     }
@@ -306,7 +306,7 @@ fn update_from_action(
                 Player::UseKey(UseKey::from_key(key)),
                 forced
                     || (!moving.exact
-                        && x_distance <= USE_KEY_X_THRESHOLD
+                        && x_distance <= player.context.config.double_jump_threshold
                         && y_distance <= USE_KEY_Y_THRESHOLD)
             );
         }
@@ -317,6 +317,8 @@ fn update_from_action(
                 ..
             })
             | PlayerAction::SolveRune
+            | PlayerAction::EnterPortal(_)
+            | PlayerAction::Shop(_)
             | PlayerAction::Move { .. },
         ) => (),
         _ => unreachable!(),
@@ -339,11 +341,7 @@ fn update_from_ping_pong_action(
     double_jumped: bool,
 ) {
     let bound = ping_pong.bound;
-    let hit_x_bound_edge = match ping_pong.direction {
-        PingPongDirection::Left => cur_pos.x - bound.x <= 0,
-        PingPongDirection::Right => cur_pos.x - bound.x - bound.width >= 0,
-    };
-    if hit_x_bound_edge {
+    if ping_pong.hit_bound_edge(cur_pos) {
         transition_from_action!(player, Player::Idle);
     }
     transition_if!(!double_jumped);
@@ -354,8 +352,8 @@ fn update_from_ping_pong_action(
         player.context.stalling_buffered.stalling()
     );
 
-    resources.input.send_key_up(KeyKind::Left);
-    resources.input.send_key_up(KeyKind::Right);
+    resources.input.send_key_up(player.context.config.left_key);
+    resources.input.send_key_up(player.context.config.right_key);
     let bound_y_max = bound.y + bound.height;
     let bound_y_mid = bound.y + bound.height / 2;
 
@@ -402,6 +400,8 @@ fn update_from_ping_pong_action(
 /// Gets the mage teleport direction when the player is already at destination.
 fn get_mage_teleport_direction(
     last_known_direction: ActionKeyDirection,
+    left_key: KeyKind,
+    right_key: KeyKind,
 ) -> Option<(KeyKind, KeyKind, ActionKeyDirection)> {
     // FIXME: Currently, PlayerActionKey with double jump + has position + has direction:
     //  1. Double jump near proximity
@@ -415,10 +415,8 @@ fn get_mage_teleport_direction(
     match last_known_direction {
         // Clueless
         ActionKeyDirection::Any => None,
-        ActionKeyDirection::Right => {
-            Some((KeyKind::Right, KeyKind::Left, ActionKeyDirection::Right))
-        }
-        ActionKeyDirection::Left => Some((KeyKind::Left, KeyKind::Right, ActionKeyDirection::Left)),
+        ActionKeyDirection::Right => Some((right_key, left_key, ActionKeyDirection::Right)),
+        ActionKeyDirection::Left => Some((left_key, right_key, ActionKeyDirection::Left)),
     }
 }
 
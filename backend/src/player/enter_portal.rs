@@ -0,0 +1,432 @@
+use opencv::core::{Point, Rect};
+
+use super::{
+    Player, PlayerAction,
+    actions::next_action,
+    load_map::LoadingMap,
+    timeout::{Lifecycle, Timeout, next_timeout_lifecycle},
+};
+use crate::{
+    ecs::{Resources, transition, transition_if},
+    minimap::Minimap,
+    player::{PlayerEntity, transition_from_action},
+    tick,
+};
+
+/// Number of ticks to wait for stationary before pressing the up key.
+const PRECONDITION_TIMEOUT: u32 = 10;
+
+/// Number of ticks to wait for the map or position change after pressing the up key.
+///
+/// This also acts as the cooldown between up key presses because the game ignores portal
+/// usage shortly after a previous one.
+const CONFIRM_TIMEOUT: u32 = 40;
+
+/// Base number of ticks to wait before retrying.
+const RETRY_COOLDOWN_TIMEOUT: u32 = 10;
+
+/// Maximum number of random ticks added to [`RETRY_COOLDOWN_TIMEOUT`].
+const RETRY_COOLDOWN_JITTER: u32 = 10;
+
+/// Maximum number of retries before giving up.
+const MAX_RETRY_COUNT: u32 = 3;
+
+/// Number of ticks to wait before pressing the up key again while a timed portal is closed.
+const WINDOW_RETRY_TIMEOUT: u32 = 20;
+
+/// Minimum distance the player must move to be considered entered a same-map portal.
+const POSITION_CHANGED_THRESHOLD: i32 = 10;
+
+/// Representing the current stage of entering a portal.
+#[derive(Debug, Clone, Copy)]
+enum State {
+    /// Ensures stationary and all keys cleared before pressing the up key.
+    Precondition(Timeout),
+    /// Presses the up key and waits for the confirmation.
+    Confirming(Timeout),
+    /// Waits for a random cooldown and nudges the player before retrying.
+    Cooldown(Timeout, u32),
+    /// Waits in place for a timed portal to open before pressing the up key again.
+    WaitingWindow(Timeout),
+    /// Terminal stage with whether the portal was entered.
+    Completed(bool),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EnteringPortal {
+    state: State,
+    /// The player position and minimap bounding box when the up key was pressed.
+    pressed: Option<(Point, Rect)>,
+    retry_count: u32,
+    /// The longest number of ticks a timed portal can stay closed.
+    ///
+    /// Failed up key presses within this window do not count as retries.
+    window_ticks: u32,
+    /// Number of ticks spent in this state.
+    elapsed_ticks: u32,
+}
+
+impl EnteringPortal {
+    pub fn new(window_ticks: u32) -> Self {
+        Self {
+            window_ticks,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the up key was pressed and waiting for the confirmation.
+    #[inline]
+    pub fn is_confirming(&self) -> bool {
+        matches!(self.state, State::Confirming(_))
+    }
+}
+
+impl Default for EnteringPortal {
+    fn default() -> Self {
+        Self {
+            state: State::Precondition(Timeout::default()),
+            pressed: None,
+            retry_count: 0,
+            window_ticks: 0,
+            elapsed_ticks: 0,
+        }
+    }
+}
+
+/// Updates the [`Player::EnteringPortal`] contextual state.
+///
+/// This state is transitioned from [`Player::Moving`] after reaching the portal. It presses the up
+/// key and confirms the portal is entered by either:
+/// - The minimap changed, indicating a map change
+/// - The player detection failed, indicating the map is loading
+/// - The player position changed significantly, indicating a same-map portal
+///
+/// If none happens, it will retry after a jittered cooldown for up to [`MAX_RETRY_COUNT`]. For a
+/// timed portal, it instead keeps pressing the up key in place until the portal window has passed
+/// before retrying.
+pub fn update_entering_portal_state(
+    resources: &Resources,
+    player: &mut PlayerEntity,
+    minimap_state: Minimap,
+    failed_to_detect_player: bool,
+) {
+    let Player::EnteringPortal(mut entering) = player.state else {
+        panic!("state is not entering portal");
+    };
+    entering.elapsed_ticks = entering.elapsed_ticks.saturating_add(1);
+
+    match entering.state {
+        State::Precondition(timeout) => {
+            update_precondition(resources, player, &mut entering, minimap_state, timeout)
+        }
+        State::Confirming(timeout) => update_confirming(
+            resources,
+            player,
+            &mut entering,
+            minimap_state,
+            failed_to_detect_player,
+            timeout,
+        ),
+        State::Cooldown(timeout, max_timeout) => {
+            update_cooldown(resources, player, &mut entering, timeout, max_timeout)
+        }
+        State::WaitingWindow(timeout) => update_waiting_window(&mut entering, timeout),
+        State::Completed(_) => unreachable!(),
+    }
+
+    let (player_next_state, is_terminal) = match entering.state {
+        State::Completed(true) => (Player::LoadingMap(LoadingMap::default()), true),
+        State::Completed(false) => (Player::Idle, true),
+        _ => (Player::EnteringPortal(entering), false),
+    };
+
+    match next_action(&player.context) {
+        Some(PlayerAction::EnterPortal(_)) => {
+            transition_from_action!(player, player_next_state, is_terminal)
+        }
+        Some(_) => unreachable!(),
+        None => transition!(player, Player::Idle), // Force cancel if not from action
+    }
+}
+
+fn update_precondition(
+    resources: &Resources,
+    player: &PlayerEntity,
+    entering: &mut EnteringPortal,
+    minimap_state: Minimap,
+    timeout: Timeout,
+) {
+    match next_timeout_lifecycle(timeout, tick::scaled(PRECONDITION_TIMEOUT)) {
+        Lifecycle::Ended => {
+            let pressed = match (player.context.last_known_pos, minimap_state) {
+                (Some(pos), Minimap::Idle(idle)) => Some((pos, idle.bbox)),
+                _ => None,
+            };
+            transition_if!(entering, State::Completed(false), pressed.is_none());
+            transition_if!(
+                entering,
+                State::Precondition(timeout),
+                !player.context.is_stationary || !resources.input.all_keys_cleared()
+            );
+
+            entering.pressed = pressed;
+            transition!(entering, State::Confirming(Timeout::default()))
+        }
+        Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
+            transition!(entering, State::Precondition(timeout))
+        }
+    }
+}
+
+fn update_confirming(
+    resources: &Resources,
+    player: &PlayerEntity,
+    entering: &mut EnteringPortal,
+    minimap_state: Minimap,
+    failed_to_detect_player: bool,
+    timeout: Timeout,
+) {
+    let (pressed_pos, pressed_bbox) = entering.pressed.expect("has pressed");
+    let did_enter = match (minimap_state, player.context.last_known_pos) {
+        (Minimap::Detecting, _) => true,
+        (Minimap::Idle(idle), _) if idle.bbox != pressed_bbox => true,
+        _ if failed_to_detect_player => true,
+        (_, Some(pos)) => is_position_changed(pressed_pos, pos),
+        (_, None) => false,
+    };
+    transition_if!(entering, State::Completed(true), did_enter);
+
+    match next_timeout_lifecycle(timeout, tick::scaled(CONFIRM_TIMEOUT)) {
+        Lifecycle::Started(timeout) => {
+            transition!(entering, State::Confirming(timeout), {
+                resources.input.send_key(player.context.config.up_key);
+            })
+        }
+        Lifecycle::Ended => {
+            transition_if!(
+                entering,
+                State::WaitingWindow(Timeout::default()),
+                entering.elapsed_ticks < entering.window_ticks
+            );
+            transition_if!(
+                entering,
+                State::Completed(false),
+                entering.retry_count >= MAX_RETRY_COUNT
+            );
+
+            let jitter = resources.rng.random_range(0..=RETRY_COOLDOWN_JITTER);
+            entering.retry_count += 1;
+            transition!(
+                entering,
+                State::Cooldown(
+                    Timeout::default(),
+                    tick::scaled(RETRY_COOLDOWN_TIMEOUT + jitter)
+                )
+            )
+        }
+        Lifecycle::Updated(timeout) => transition!(entering, State::Confirming(timeout)),
+    }
+}
+
+fn update_cooldown(
+    resources: &Resources,
+    player: &PlayerEntity,
+    entering: &mut EnteringPortal,
+    timeout: Timeout,
+    max_timeout: u32,
+) {
+    match next_timeout_lifecycle(timeout, max_timeout) {
+        Lifecycle::Started(timeout) => {
+            transition!(entering, State::Cooldown(timeout, max_timeout), {
+                // Nudges slightly in case the player is standing on the portal edge
+                let key = if resources.rng.random_bool(0.5) {
+                    player.context.config.left_key
+                } else {
+                    player.context.config.right_key
+                };
+                resources.input.send_key(key);
+            })
+        }
+        Lifecycle::Ended => {
+            transition!(entering, State::Precondition(Timeout::default()))
+        }
+        Lifecycle::Updated(timeout) => {
+            transition!(entering, State::Cooldown(timeout, max_timeout))
+        }
+    }
+}
+
+fn update_waiting_window(entering: &mut EnteringPortal, timeout: Timeout) {
+    match next_timeout_lifecycle(timeout, tick::scaled(WINDOW_RETRY_TIMEOUT)) {
+        Lifecycle::Ended => {
+            transition!(entering, State::Precondition(Timeout::default()))
+        }
+        Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
+            transition!(entering, State::WaitingWindow(timeout))
+        }
+    }
+}
+
+#[inline]
+fn is_position_changed(from: Point, to: Point) -> bool {
+    (from.x - to.x).abs() >= POSITION_CHANGED_THRESHOLD
+        || (from.y - to.y).abs() >= POSITION_CHANGED_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::{
+        bridge::{KeyKind, MockInput},
+        minimap::MinimapIdle,
+        player::{EnterPortal, PlayerContext},
+    };
+
+    fn make_player(entering: EnteringPortal, pos: Point) -> PlayerEntity {
+        let mut context = PlayerContext::default();
+        context.last_known_pos = Some(pos);
+        context.set_priority_action(
+            None,
+            PlayerAction::EnterPortal(EnterPortal {
+                position: pos,
+                window_millis: 0,
+            }),
+        );
+
+        PlayerEntity {
+            state: Player::EnteringPortal(entering),
+            context,
+        }
+    }
+
+    fn make_minimap(bbox: Rect) -> Minimap {
+        let mut idle = MinimapIdle::default();
+        idle.bbox = bbox;
+        Minimap::Idle(idle)
+    }
+
+    fn confirming(pressed: (Point, Rect), timeout: Timeout) -> EnteringPortal {
+        EnteringPortal {
+            state: State::Confirming(timeout),
+            pressed: Some(pressed),
+            retry_count: 0,
+            window_ticks: 0,
+            elapsed_ticks: 0,
+        }
+    }
+
+    #[test]
+    fn update_confirming_presses_up_once() {
+        let bbox = Rect::new(0, 0, 100, 100);
+        let pos = Point::new(10, 10);
+        let mut player = make_player(confirming((pos, bbox), Timeout::default()), pos);
+        let mut keys = MockInput::new();
+        keys.expect_send_key().with(eq(KeyKind::Up)).once();
+        let resources = Resources::new(Some(keys), None);
+
+        update_entering_portal_state(&resources, &mut player, make_minimap(bbox), false);
+        update_entering_portal_state(&resources, &mut player, make_minimap(bbox), false);
+
+        assert_matches!(
+            player.state,
+            Player::EnteringPortal(EnteringPortal {
+                state: State::Confirming(_),
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn update_confirming_completes_on_minimap_or_position_changed() {
+        let bbox = Rect::new(0, 0, 100, 100);
+        let pos = Point::new(10, 10);
+        let resources = Resources::new(None, None);
+        let timeout = Timeout {
+            started: true,
+            ..Timeout::default()
+        };
+
+        let mut player = make_player(confirming((pos, bbox), timeout), pos);
+        update_entering_portal_state(&resources, &mut player, Minimap::Detecting, false);
+        assert_matches!(player.state, Player::LoadingMap(_));
+        assert!(!player.context.has_priority_action());
+
+        let mut player = make_player(confirming((pos, bbox), timeout), Point::new(60, 10));
+        update_entering_portal_state(&resources, &mut player, make_minimap(bbox), false);
+        assert_matches!(player.state, Player::LoadingMap(_));
+    }
+
+    #[test]
+    fn update_confirming_retries_then_gives_up() {
+        let bbox = Rect::new(0, 0, 100, 100);
+        let pos = Point::new(10, 10);
+        let timeout = Timeout {
+            started: true,
+            current: tick::scaled(CONFIRM_TIMEOUT),
+            ..Timeout::default()
+        };
+        let resources = Resources::new(None, None);
+
+        let mut player = make_player(confirming((pos, bbox), timeout), pos);
+        update_entering_portal_state(&resources, &mut player, make_minimap(bbox), false);
+        assert_matches!(
+            player.state,
+            Player::EnteringPortal(EnteringPortal {
+                state: State::Cooldown(_, _),
+                retry_count: 1,
+                ..
+            })
+        );
+
+        let mut entering = confirming((pos, bbox), timeout);
+        entering.retry_count = MAX_RETRY_COUNT;
+        let mut player = make_player(entering, pos);
+        update_entering_portal_state(&resources, &mut player, make_minimap(bbox), false);
+        assert_matches!(player.state, Player::Idle);
+        assert!(!player.context.has_priority_action());
+    }
+
+    #[test]
+    fn update_confirming_waits_for_timed_portal_window() {
+        let bbox = Rect::new(0, 0, 100, 100);
+        let pos = Point::new(10, 10);
+        let timeout = Timeout {
+            started: true,
+            current: tick::scaled(CONFIRM_TIMEOUT),
+            ..Timeout::default()
+        };
+        let resources = Resources::new(None, None);
+
+        let mut entering = confirming((pos, bbox), timeout);
+        entering.window_ticks = 1000;
+        entering.retry_count = MAX_RETRY_COUNT;
+        let mut player = make_player(entering, pos);
+        update_entering_portal_state(&resources, &mut player, make_minimap(bbox), false);
+        assert_matches!(
+            player.state,
+            Player::EnteringPortal(EnteringPortal {
+                state: State::WaitingWindow(_),
+                retry_count: MAX_RETRY_COUNT,
+                ..
+            })
+        );
+
+        let mut entering = confirming((pos, bbox), timeout);
+        entering.window_ticks = 1000;
+        entering.elapsed_ticks = 1000;
+        let mut player = make_player(entering, pos);
+        update_entering_portal_state(&resources, &mut player, make_minimap(bbox), false);
+        assert_matches!(
+            player.state,
+            Player::EnteringPortal(EnteringPortal {
+                state: State::Cooldown(_, _),
+                retry_count: 1,
+                ..
+            })
+        );
+    }
+}
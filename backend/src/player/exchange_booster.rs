@@ -11,6 +11,7 @@ use crate::{
         timeout::{Lifecycle, next_timeout_lifecycle},
         transition_from_action,
     },
+    tick,
 };
 
 /// States of exchanging HEXA booster.
@@ -132,7 +133,7 @@ fn update_open_hexa_menu(resources: &Resources, exchanging: &mut ExchangingBoost
         panic!("exchanging booster state is not opening hexa menu")
     };
 
-    match next_timeout_lifecycle(timeout, 20) {
+    match next_timeout_lifecycle(timeout, tick::scaled(20)) {
         Lifecycle::Started(timeout) => {
             let (x, y) = try_some_transition!(
                 exchanging,
@@ -169,7 +170,7 @@ fn update_open_exchanging_menu(resources: &Resources, exchanging: &mut Exchangin
         panic!("exchanging booster state is not opening exchanging menu")
     };
 
-    match next_timeout_lifecycle(timeout, 20) {
+    match next_timeout_lifecycle(timeout, tick::scaled(20)) {
         Lifecycle::Started(timeout) => {
             transition!(exchanging, State::OpenExchangingMenu(timeout, bbox), {
                 let (x, y) = bbox_click_point(bbox);
@@ -196,7 +197,7 @@ fn update_open_booster_menu(resources: &Resources, exchanging: &mut ExchangingBo
         panic!("exchanging booster state is not opening booster menu")
     };
 
-    match next_timeout_lifecycle(timeout, 20) {
+    match next_timeout_lifecycle(timeout, tick::scaled(20)) {
         Lifecycle::Started(timeout) => {
             transition!(exchanging, State::OpenBoosterMenu(timeout, bbox), {
                 let (x, y) = bbox_click_point(bbox);
@@ -225,7 +226,7 @@ fn update_exchanging(resources: &Resources, exchanging: &mut ExchangingBooster)
         panic!("exchanging booster state is not exchanging")
     };
     let amount = exchanging.amount;
-    let max_timeout = if amount.is_some() { 60 } else { 20 };
+    let max_timeout = tick::scaled(if amount.is_some() { 60 } else { 20 });
 
     match next_timeout_lifecycle(timeout, max_timeout) {
         Lifecycle::Started(timeout) => {
@@ -249,7 +250,7 @@ fn update_exchanging(resources: &Resources, exchanging: &mut ExchangingBooster)
         }
         Lifecycle::Updated(timeout) => {
             if let Some(amount) = amount
-                && timeout.current.is_multiple_of(TYPE_INTERVAL)
+                && timeout.current.is_multiple_of(tick::scaled(TYPE_INTERVAL))
                 && amount.index < amount.keys.len()
             {
                 exchanging.amount = Some(amount.increment_index());
@@ -265,7 +266,7 @@ fn update_confirming(resources: &Resources, exchanging: &mut ExchangingBooster)
         panic!("exchanging booster state is not confirming")
     };
 
-    match next_timeout_lifecycle(timeout, 20) {
+    match next_timeout_lifecycle(timeout, tick::scaled(20)) {
         Lifecycle::Started(timeout) => {
             transition!(exchanging, State::Confirming(timeout, bbox), {
                 let (x, y) = bbox_click_point(bbox);
@@ -286,7 +287,7 @@ fn update_completing(resources: &Resources, exchanging: &mut ExchangingBooster)
         panic!("exchanging booster state is not completing")
     };
 
-    match next_timeout_lifecycle(timeout, 20) {
+    match next_timeout_lifecycle(timeout, tick::scaled(20)) {
         Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
             transition!(exchanging, State::Completing(timeout, completed))
         }
@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+
+/// Minimum number of windows the session must span before comparing the recent rate.
+///
+/// The session average is too noisy to compare against right after starting.
+const MIN_SESSION_WINDOWS: u64 = 2;
+
+/// The EXP gain of the current session tracked for detecting a rate drop.
+#[derive(Debug, Default)]
+pub struct ExpRate {
+    /// The last detected EXP.
+    last_exp: Option<u64>,
+    /// The total EXP gained since [`Self::started_tick`].
+    gained: u64,
+    /// The tick the first EXP was detected at.
+    started_tick: Option<u64>,
+    /// The total gained EXP along with the tick it was detected at, oldest first.
+    samples: VecDeque<(u64, u64)>,
+    /// Whether the recent rate is currently below the allowed fraction of the session average.
+    dropped: bool,
+}
+
+impl ExpRate {
+    /// Pushes `exp` detected at `tick`.
+    ///
+    /// The EXP resets on leveling up, so a lower EXP than the last one only becomes the new
+    /// baseline without counting as a gain.
+    pub fn push(&mut self, exp: u64, tick: u64) {
+        if let Some(last_exp) = self.last_exp
+            && exp >= last_exp
+        {
+            self.gained += exp - last_exp;
+        }
+        self.last_exp = Some(exp);
+        self.started_tick.get_or_insert(tick);
+        self.samples.push_back((self.gained, tick));
+    }
+
+    /// Clears the tracked session.
+    pub fn clear(&mut self) {
+        *self = ExpRate::default();
+    }
+
+    /// Whether the recent rate was below the allowed fraction on the last update.
+    pub fn dropped(&self) -> bool {
+        self.dropped
+    }
+
+    /// Computes the recent and session average EXP gain per tick at `tick`.
+    ///
+    /// The recent rate is computed from the samples within `window_ticks` ending at `tick`.
+    pub fn rates(&self, tick: u64, window_ticks: u64) -> Option<(f32, f32)> {
+        let started_tick = self.started_tick?;
+        let (_, last_tick) = self.samples.back().copied()?;
+        let window_start = tick.saturating_sub(window_ticks);
+        let (window_gained, window_tick) = self
+            .samples
+            .iter()
+            .copied()
+            .find(|(_, sample_tick)| *sample_tick >= window_start)?;
+        if last_tick == started_tick || last_tick == window_tick {
+            return None;
+        }
+
+        let recent = (self.gained - window_gained) as f32 / (last_tick - window_tick) as f32;
+        let session = self.gained as f32 / (last_tick - started_tick) as f32;
+        Some((recent, session))
+    }
+
+    /// Updates whether the recent rate within `window_ticks` dropped below `min_rate_percent` of
+    /// the session average at `tick`.
+    ///
+    /// Returns `true` only when the rate has just dropped. Samples too old to be in the window are
+    /// removed.
+    pub fn update_dropped(&mut self, tick: u64, window_ticks: u64, min_rate_percent: u32) -> bool {
+        let window_start = tick.saturating_sub(window_ticks);
+        while self
+            .samples
+            .front()
+            .is_some_and(|(_, sample_tick)| *sample_tick < window_start)
+        {
+            self.samples.pop_front();
+        }
+
+        let session_long_enough = self.started_tick.is_some_and(|started_tick| {
+            tick.saturating_sub(started_tick) >= window_ticks * MIN_SESSION_WINDOWS
+        });
+        let dropped = session_long_enough
+            && self
+                .rates(tick, window_ticks)
+                .is_some_and(|(recent, session)| {
+                    session > 0.0 && recent < session * min_rate_percent as f32 / 100.0
+                });
+        let just_dropped = dropped && !self.dropped;
+        self.dropped = dropped;
+
+        just_dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate_from(samples: impl IntoIterator<Item = (u64, u64)>) -> ExpRate {
+        let mut rate = ExpRate::default();
+        for (exp, tick) in samples {
+            rate.push(exp, tick);
+        }
+        rate
+    }
+
+    #[test]
+    fn push_skips_level_up_reset() {
+        let rate = rate_from([(100, 0), (150, 10), (20, 20), (70, 30)]);
+
+        assert_eq!(rate.gained, 100);
+        assert_eq!(rate.rates(30, 100), Some((100.0 / 30.0, 100.0 / 30.0)));
+    }
+
+    #[test]
+    fn rates_uses_window_for_recent_rate() {
+        let rate = rate_from([(0, 0), (100, 10), (200, 20), (210, 30), (220, 40)]);
+
+        assert_eq!(rate.rates(40, 20), Some((1.0, 5.5)));
+    }
+
+    #[test]
+    fn update_dropped_once_rate_drops_below_fraction() {
+        let mut rate = ExpRate::default();
+        for tick in 0..=40 {
+            rate.push(tick * 10, tick);
+        }
+        assert!(!rate.update_dropped(40, 20, 50));
+
+        for tick in 41..=60 {
+            rate.push(400 + tick - 40, tick);
+        }
+        assert!(rate.update_dropped(60, 20, 50));
+        assert!(rate.dropped());
+        assert!(!rate.update_dropped(60, 20, 50));
+        assert!(rate.dropped());
+    }
+
+    #[test]
+    fn update_dropped_requires_long_enough_session() {
+        let mut rate = rate_from([(0, 0), (100, 10), (100, 20), (100, 30)]);
+
+        assert!(!rate.update_dropped(30, 20, 50));
+        assert!(!rate.dropped());
+    }
+}
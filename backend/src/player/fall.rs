@@ -8,7 +8,6 @@ use super::{
 };
 use crate::{
     ActionKeyWith,
-    bridge::KeyKind,
     ecs::{Resources, transition, transition_if},
     minimap::Minimap,
     player::{
@@ -16,6 +15,7 @@ use crate::{
         next_action, state::LastMovement, timeout::ChangeAxis, transition_to_moving,
         transition_to_moving_if,
     },
+    tick,
 };
 
 /// Minimum y distance from the destination required to perform a fall.
@@ -25,7 +25,7 @@ pub const FALLING_THRESHOLD: i32 = 4;
 /// a [`PlayerAction::Key`] with [`ActionKeyWith::Any`].
 const FALLING_TO_USE_KEY_THRESHOLD: i32 = 5;
 
-/// Tick to stop helding down [`KeyKind::Down`] at.
+/// Tick to stop helding down the down key at.
 const STOP_DOWN_KEY_TICK: u32 = 3;
 
 /// Maximum number of ticks before timing out.
@@ -33,7 +33,7 @@ const TIMEOUT: u32 = MOVE_TIMEOUT + 3;
 
 /// Maximum y distance from the destination allowed to skip normal falling and use teleportation
 /// for mage.
-const TELEPORT_FALL_THRESHOLD: i32 = 16;
+pub const TELEPORT_FALL_THRESHOLD: i32 = 16;
 
 #[derive(Clone, Copy, Debug)]
 pub struct Falling {
@@ -84,7 +84,7 @@ pub fn update_falling_state(
     match next_moving_lifecycle_with_axis(
         falling.moving,
         player.context.last_known_pos.expect("in positional state"),
-        TIMEOUT,
+        tick::scaled(TIMEOUT),
         ChangeAxis::Vertical,
     ) {
         MovingLifecycle::Started(moving) => {
@@ -117,9 +117,11 @@ pub fn update_falling_state(
             // Do the fall
             let can_teleport = !player.context.config.disable_teleport_on_fall
                 && player.context.config.teleport_key.is_some()
-                && y_distance < TELEPORT_FALL_THRESHOLD;
+                && y_distance < player.context.config.teleport_fall_threshold;
             player.context.last_movement = Some(LastMovement::Falling);
-            resources.input.send_key_down(KeyKind::Down);
+            resources
+                .input
+                .send_key_down(player.context.config.down_key);
             if can_teleport {
                 resources
                     .input
@@ -131,11 +133,11 @@ pub fn update_falling_state(
             transition!(player, Player::Falling(falling.moving(moving)))
         }
         MovingLifecycle::Ended(moving) => transition_to_moving!(player, moving, {
-            resources.input.send_key_up(KeyKind::Down);
+            resources.input.send_key_up(player.context.config.down_key);
         }),
         MovingLifecycle::Updated(mut moving) => {
-            if moving.timeout.total == STOP_DOWN_KEY_TICK {
-                resources.input.send_key_up(KeyKind::Down);
+            if moving.timeout.total == tick::scaled(STOP_DOWN_KEY_TICK) {
+                resources.input.send_key_up(player.context.config.down_key);
             }
             if !moving.completed {
                 let y_changed = moving.pos.y - falling.anchor.y;
@@ -143,7 +145,7 @@ pub fn update_falling_state(
                     moving.completed = true;
                 }
             } else if falling.timeout_on_complete {
-                moving.timeout.current = TIMEOUT;
+                moving.timeout.current = tick::scaled(TIMEOUT);
             }
             // Sets initial next state first
             player.state = Player::Falling(falling.moving(moving));
@@ -171,7 +173,7 @@ fn update_from_action(
                 moving,
                 moving.completed && moving.is_destination_intermediate() && y_direction >= 0,
                 {
-                    resources.input.send_key_up(KeyKind::Down);
+                    resources.input.send_key_up(player.context.config.down_key);
                 }
             );
             transition_if!(has_teleport_key && !moving.completed);
@@ -207,7 +209,9 @@ fn update_from_action(
             })
             | PlayerAction::PingPong(_)
             | PlayerAction::Move(_)
-            | PlayerAction::SolveRune,
+            | PlayerAction::SolveRune
+            | PlayerAction::EnterPortal(_)
+            | PlayerAction::Shop(_),
         )
         | None => (),
         _ => unreachable!(),
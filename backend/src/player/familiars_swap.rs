@@ -14,6 +14,7 @@ use crate::{
     ecs::{Resources, transition, transition_if, try_ok_transition, try_some_transition},
     models::{FamiliarRarity, SwappableFamiliars},
     player::{PlayerEntity, next_action, transition_from_action},
+    tick,
 };
 
 /// Number of familiar slots available.
@@ -160,7 +161,7 @@ fn update_open_menu(resources: &Resources, swapping: &mut FamiliarsSwapping, key
         panic!("familiars swapping state is not opening menu");
     };
 
-    match next_timeout_lifecycle(timeout, 60) {
+    match next_timeout_lifecycle(timeout, tick::scaled(60)) {
         Lifecycle::Started(timeout) => transition!(swapping, State::OpenMenu(timeout), {
             resources.input.send_mouse(
                 swapping.mouse_rest.x,
@@ -175,7 +176,7 @@ fn update_open_menu(resources: &Resources, swapping: &mut FamiliarsSwapping, key
             resources.detector().detect_familiar_menu_opened()
         ),
         Lifecycle::Updated(timeout) => transition!(swapping, State::OpenMenu(timeout), {
-            if timeout.current == 30 {
+            if timeout.current == tick::scaled(30) {
                 resources.input.send_key(key);
             }
         }),
@@ -277,7 +278,7 @@ fn update_free_slot(resources: &Resources, swapping: &mut FamiliarsSwapping) {
         panic!("familiars swapping state is not freeing slot")
     };
 
-    match next_timeout_lifecycle(timeout, FAMILIAR_FREE_SLOTS_TIMEOUT) {
+    match next_timeout_lifecycle(timeout, tick::scaled(FAMILIAR_FREE_SLOTS_TIMEOUT)) {
         Lifecycle::Started(timeout) => transition!(swapping, State::FreeSlot(timeout, index), {
             // On start, move mouse to hover over the familiar slot to check level
             let bbox = swapping.slots[index].bbox;
@@ -291,7 +292,7 @@ fn update_free_slot(resources: &Resources, swapping: &mut FamiliarsSwapping) {
             let detector = resources.detector();
 
             match timeout.current {
-                FAMILIAR_CHECK_LVL_5_TICK => {
+                current if current == tick::scaled(FAMILIAR_CHECK_LVL_5_TICK) => {
                     match detector.detect_familiar_hover_level() {
                         Ok(FamiliarLevel::Level5) => {
                             // Double click to free
@@ -328,10 +329,10 @@ fn update_free_slot(resources: &Resources, swapping: &mut FamiliarsSwapping) {
                         Err(_) => transition!(swapping, State::FreeSlots(index, true)),
                     }
                 }
-                FAMILIAR_CHECK_FREE_TICK => {
+                current if current == tick::scaled(FAMILIAR_CHECK_FREE_TICK) => {
                     if detector.detect_familiar_slot_is_free(bbox) {
                         // If familiar is free, timeout and set flag
-                        timeout.current = FAMILIAR_FREE_SLOTS_TIMEOUT;
+                        timeout.current = tick::scaled(FAMILIAR_FREE_SLOTS_TIMEOUT);
                         swapping.slots[index].is_free = true;
                     } else {
                         // After double clicking, previous slots will move forward so this loop
@@ -360,7 +361,7 @@ fn update_find_cards(resources: &Resources, swapping: &mut FamiliarsSwapping) {
     };
 
     // Timeout for ensuring sorting takes effect
-    match next_timeout_lifecycle(timeout, 5) {
+    match next_timeout_lifecycle(timeout, tick::scaled(5)) {
         Lifecycle::Ended => {
             if swapping.cards.is_empty() {
                 let vec = resources.detector().detect_familiar_cards();
@@ -405,7 +406,7 @@ fn update_swapping(resources: &Resources, swapping: &mut FamiliarsSwapping) {
         panic!("familiars swapping state is not swapping")
     };
 
-    match next_timeout_lifecycle(timeout, SWAPPING_TIMEOUT) {
+    match next_timeout_lifecycle(timeout, tick::scaled(SWAPPING_TIMEOUT)) {
         Lifecycle::Started(timeout) => transition!(swapping, State::Swapping(timeout, index), {
             let (x, y) = bbox_click_point(swapping.cards[index]);
             resources.input.send_mouse(x, y, MouseKind::Move);
@@ -435,7 +436,7 @@ fn update_swapping(resources: &Resources, swapping: &mut FamiliarsSwapping) {
             );
         }
         Lifecycle::Updated(timeout) => {
-            if timeout.current == SWAPPING_DETECT_LEVEL_TICK {
+            if timeout.current == tick::scaled(SWAPPING_DETECT_LEVEL_TICK) {
                 let rest = swapping.mouse_rest;
 
                 match resources.detector().detect_familiar_hover_level() {
@@ -483,7 +484,7 @@ fn update_scrolling(resources: &Resources, swapping: &mut FamiliarsSwapping) {
         panic!("familiars swapping state is not scrolling")
     };
 
-    match next_timeout_lifecycle(timeout, SCROLLING_TIMEOUT) {
+    match next_timeout_lifecycle(timeout, tick::scaled(SCROLLING_TIMEOUT)) {
         Lifecycle::Started(timeout) => {
             // TODO: recoverable?
             let scrollbar = try_ok_transition!(
@@ -527,7 +528,7 @@ fn update_scrolling(resources: &Resources, swapping: &mut FamiliarsSwapping) {
             );
         }
         Lifecycle::Updated(timeout) => {
-            if timeout.current == SCROLLING_REST_TICK {
+            if timeout.current == tick::scaled(SCROLLING_REST_TICK) {
                 let (x, y) = bbox_click_point(scrollbar.unwrap());
                 resources.input.send_mouse(x + 70, y, MouseKind::Move);
             }
@@ -543,7 +544,7 @@ fn update_saving(resources: &Resources, swapping: &mut FamiliarsSwapping) {
         panic!("familiars swapping state is not saving")
     };
 
-    match next_timeout_lifecycle(timeout, 20) {
+    match next_timeout_lifecycle(timeout, tick::scaled(20)) {
         Lifecycle::Started(timeout) => {
             let button = try_ok_transition!(
                 swapping,
@@ -574,7 +575,7 @@ fn update_completing(
     timeout: Timeout,
     completed: bool,
 ) {
-    match next_timeout_lifecycle(timeout, 20) {
+    match next_timeout_lifecycle(timeout, tick::scaled(20)) {
         Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
             transition!(swapping, State::Completing(timeout, completed))
         }
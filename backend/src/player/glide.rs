@@ -0,0 +1,309 @@
+use std::cmp::Ordering;
+
+use super::{
+    Key, Player, PlayerAction,
+    moving::Moving,
+    timeout::{MovingLifecycle, next_moving_lifecycle_with_axis},
+    use_key::UseKey,
+};
+use crate::{
+    ActionKeyDirection, ActionKeyWith,
+    ecs::{Resources, transition_if},
+    minimap::Minimap,
+    player::{
+        PlayerContext, PlayerEntity, actions::update_from_auto_mob_action, moving::MOVE_TIMEOUT,
+        next_action, state::LastMovement, timeout::ChangeAxis, transition_to_moving,
+    },
+    tick,
+};
+
+/// Minimum x distance from the destination required to glide horizontally.
+pub const GLIDING_THRESHOLD: i32 = 16;
+
+/// Maximum x distance from the destination to stop gliding.
+///
+/// This is smaller than [`GLIDING_THRESHOLD`] but not zero so that the remaining glide momentum
+/// does not overshoot the destination.
+const GLIDING_STOP_THRESHOLD: i32 = 6;
+
+/// Maximum y distance below the starting altitude before ascending.
+const ALTITUDE_TOLERANCE: i32 = 2;
+
+/// Player's `y` velocity to be considered as sinking while below the starting altitude.
+const SINKING_Y_VELOCITY_THRESHOLD: f32 = 0.3;
+
+/// Minimum y distance from the destination required to transition to [`Player::UseKey`].
+const USE_KEY_Y_THRESHOLD: i32 = 10;
+
+/// Maximum number of ticks before timing out.
+const TIMEOUT: u32 = MOVE_TIMEOUT;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Gliding {
+    pub moving: Moving,
+    /// The player `y` when gliding started to maintain.
+    altitude: i32,
+    /// Whether the up key is being held to regain [`Self::altitude`].
+    ascending: bool,
+}
+
+impl Gliding {
+    pub fn new(moving: Moving) -> Self {
+        Self {
+            moving,
+            altitude: moving.pos.y,
+            ascending: false,
+        }
+    }
+
+    #[inline]
+    fn moving(self, moving: Moving) -> Gliding {
+        Gliding { moving, ..self }
+    }
+}
+
+/// Updates the [`Player::Gliding`] contextual state.
+///
+/// This state replaces [`Player::DoubleJumping`] for classes with sustained horizontal flight
+/// when [`PlayerConfiguration::horizontal_flight`] is enabled. It jumps, then holds the flight
+/// and direction keys until the distance x-wise is `<` the gliding stop threshold. While gliding,
+/// it holds the up key whenever the player sinks below the starting altitude.
+///
+/// [`PlayerConfiguration::horizontal_flight`]: super::state::PlayerConfiguration::horizontal_flight
+pub fn update_gliding_state(
+    resources: &Resources,
+    player: &mut PlayerEntity,
+    minimap_state: Minimap,
+) {
+    let Player::Gliding(mut gliding) = player.state else {
+        panic!("state is not gliding")
+    };
+    let flight_key = player.context.config.flight_key.expect("has flight key");
+
+    match next_moving_lifecycle_with_axis(
+        gliding.moving,
+        player.context.last_known_pos.expect("in positional state"),
+        tick::scaled(TIMEOUT),
+        ChangeAxis::Both,
+    ) {
+        MovingLifecycle::Started(moving) => {
+            player.context.last_movement = Some(LastMovement::Gliding);
+            resources.input.send_key(player.context.config.jump_key);
+            resources.input.send_key_down(flight_key);
+            player.state = Player::Gliding(gliding.moving(moving));
+        }
+        MovingLifecycle::Ended(moving) => transition_to_moving!(player, moving, {
+            release_gliding_keys(resources, &player.context);
+        }),
+        MovingLifecycle::Updated(mut moving) => {
+            let (x_distance, x_direction) = moving.x_distance_direction_from(true, moving.pos);
+
+            if !moving.completed {
+                let direction = match x_direction.cmp(&0) {
+                    Ordering::Greater => Some((
+                        player.context.config.right_key,
+                        player.context.config.left_key,
+                        ActionKeyDirection::Right,
+                    )),
+                    Ordering::Less => Some((
+                        player.context.config.left_key,
+                        player.context.config.right_key,
+                        ActionKeyDirection::Left,
+                    )),
+                    Ordering::Equal => None,
+                };
+
+                if let Some((key_down, key_up, direction)) = direction
+                    && x_distance >= GLIDING_STOP_THRESHOLD
+                {
+                    resources.input.send_key_down(key_down);
+                    resources.input.send_key_up(key_up);
+                    player.context.last_known_direction = direction;
+
+                    let below_altitude = gliding.altitude - moving.pos.y > ALTITUDE_TOLERANCE;
+                    let sinking =
+                        below_altitude && player.context.velocity.1 >= SINKING_Y_VELOCITY_THRESHOLD;
+                    if sinking && !gliding.ascending {
+                        resources.input.send_key_down(player.context.config.up_key);
+                        gliding.ascending = true;
+                    } else if !below_altitude && gliding.ascending {
+                        resources.input.send_key_up(player.context.config.up_key);
+                        gliding.ascending = false;
+                    }
+                } else {
+                    release_gliding_keys(resources, &player.context);
+                    gliding.ascending = false;
+                    moving.completed = true;
+                }
+            }
+
+            // Computes and sets initial next state first
+            player.state = if moving.completed {
+                Player::Gliding(gliding.moving(moving.timeout_current(tick::scaled(TIMEOUT))))
+            } else {
+                Player::Gliding(gliding.moving(moving))
+            };
+            update_from_action(resources, player, minimap_state, moving);
+        }
+    }
+}
+
+#[inline]
+fn release_gliding_keys(resources: &Resources, context: &PlayerContext) {
+    resources
+        .input
+        .send_key_up(context.config.flight_key.expect("has flight key"));
+    resources.input.send_key_up(context.config.up_key);
+    resources.input.send_key_up(context.config.right_key);
+    resources.input.send_key_up(context.config.left_key);
+}
+
+/// Handles [`PlayerAction`] during gliding.
+///
+/// Unlike [`Player::Teleporting`], actions are only handled after gliding completed so that the
+/// flight key is not left held.
+fn update_from_action(
+    resources: &Resources,
+    player: &mut PlayerEntity,
+    minimap_state: Minimap,
+    moving: Moving,
+) {
+    transition_if!(!moving.completed);
+
+    let cur_pos = moving.pos;
+    let (x_distance, x_direction) = moving.x_distance_direction_from(false, cur_pos);
+    let (y_distance, _) = moving.y_distance_direction_from(false, cur_pos);
+
+    match next_action(&player.context) {
+        Some(PlayerAction::AutoMob(mob)) => update_from_auto_mob_action(
+            resources,
+            player,
+            minimap_state,
+            mob,
+            x_distance,
+            x_direction,
+            y_distance,
+        ),
+        Some(PlayerAction::Key(
+            key @ Key {
+                with: ActionKeyWith::DoubleJump | ActionKeyWith::Any,
+                ..
+            },
+        )) => {
+            transition_if!(
+                player,
+                Player::UseKey(UseKey::from_key(key)),
+                !moving.exact
+                    && x_distance <= GLIDING_THRESHOLD
+                    && y_distance <= USE_KEY_Y_THRESHOLD
+            );
+        }
+        None
+        | Some(
+            PlayerAction::Key(Key {
+                with: ActionKeyWith::Stationary,
+                ..
+            })
+            | PlayerAction::SolveRune
+            | PlayerAction::EnterPortal(_)
+            | PlayerAction::Shop(_)
+            | PlayerAction::PingPong(_)
+            | PlayerAction::Move { .. },
+        ) => (),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use mockall::predicate::eq;
+    use opencv::core::Point;
+
+    use super::*;
+    use crate::bridge::{KeyKind, MockInput};
+
+    fn make_player_with_state(state: Player) -> PlayerEntity {
+        let mut context = PlayerContext::default();
+        context.config.flight_key = Some(KeyKind::Shift);
+        context.config.horizontal_flight = true;
+
+        PlayerEntity { state, context }
+    }
+
+    #[test]
+    fn update_gliding_state_started_jumps_and_holds_flight_key() {
+        let pos = Point::new(0, 0);
+        let mut player = make_player_with_state(Player::Gliding(Gliding::new(Moving::new(
+            pos,
+            Point::new(30, 0),
+            false,
+            None,
+        ))));
+        player.context.last_known_pos = Some(pos);
+        let mut keys = MockInput::new();
+        keys.expect_send_key()
+            .with(eq(player.context.config.jump_key))
+            .once();
+        keys.expect_send_key_down().with(eq(KeyKind::Shift)).once();
+        let resources = Resources::new(Some(keys), None);
+
+        update_gliding_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(player.state, Player::Gliding(_));
+        assert_eq!(player.context.last_movement, Some(LastMovement::Gliding));
+    }
+
+    #[test]
+    fn update_gliding_state_ascends_when_sinking_below_altitude() {
+        let start = Point::new(100, 50);
+        let pos = Point::new(90, 45);
+        let mut player = make_player_with_state(Player::Gliding(Gliding::new(
+            Moving::new(start, Point::new(50, 50), false, None).timeout_started(true),
+        )));
+        player.context.last_known_pos = Some(pos);
+        player.context.velocity = (1.0, 1.0);
+        let mut keys = MockInput::new();
+        keys.expect_send_key_down().with(eq(KeyKind::Left)).once();
+        keys.expect_send_key_up().with(eq(KeyKind::Right)).once();
+        keys.expect_send_key_down().with(eq(KeyKind::Up)).once();
+        let resources = Resources::new(Some(keys), None);
+
+        update_gliding_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(player.state, Player::Gliding(Gliding { moving, ascending: true, .. })
+            if !moving.completed);
+        assert_eq!(
+            player.context.last_known_direction,
+            ActionKeyDirection::Left
+        );
+    }
+
+    #[test]
+    fn update_gliding_state_completes_within_stop_threshold() {
+        let pos = Point::new(100, 50);
+        let mut player = make_player_with_state(Player::Gliding(Gliding::new(
+            Moving::new(
+                pos,
+                Point::new(100 - GLIDING_STOP_THRESHOLD + 1, 50),
+                false,
+                None,
+            )
+            .timeout_started(true),
+        )));
+        player.context.last_known_pos = Some(pos);
+        let mut keys = MockInput::new();
+        keys.expect_send_key_up().with(eq(KeyKind::Shift)).once();
+        keys.expect_send_key_up().with(eq(KeyKind::Up)).once();
+        keys.expect_send_key_up().with(eq(KeyKind::Right)).once();
+        keys.expect_send_key_up().with(eq(KeyKind::Left)).once();
+        keys.expect_send_key_down().never();
+        let resources = Resources::new(Some(keys), None);
+
+        update_gliding_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(player.state, Player::Gliding(Gliding { moving, .. })
+            if moving.completed);
+    }
+}
@@ -0,0 +1,258 @@
+use std::fmt::Write as _;
+
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+use super::PlayerKind;
+#[cfg(debug_assertions)]
+use super::{
+    Adjusting, Climbing, DoubleJumping, Falling, Gliding, Grappling, Player, PlayerEntity,
+    Teleporting, UpJumping, next_action,
+};
+
+/// The node name of the pseudo state representing any state in the exported diagram.
+const ANY_STATE: &str = "AnyState";
+
+// The transitions of each player contextual state.
+//
+// Each entry is the state being updated followed by the states its update function can
+// transition to. The table is generated by `build/transitions.rs` from the `Player` values
+// constructed by the update functions so that the exported diagram follows the actual code.
+include!(concat!(env!("OUT_DIR"), "/player_transitions.rs"));
+
+/// The transitions that can happen regardless of the current state along with their reasons.
+///
+/// These happen in [`super::run_system`] before the current state is updated or from outside of
+/// the player system.
+const ANY_STATE_TRANSITIONS: &[(PlayerKind, &str)] = &[
+    (PlayerKind::Detecting, "player not detected"),
+    (PlayerKind::Unstucking, "player stuck"),
+    (PlayerKind::Idle, "reset to idle"),
+    (PlayerKind::CashShopThenExit, "rune cash shop"),
+    (PlayerKind::Panicking, "panic mode"),
+    (PlayerKind::Calibrating, "calibration requested"),
+    (PlayerKind::ScreenPingPonging, "minimap hidden"),
+];
+
+/// The file format of an exported player state machine diagram.
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum StateMachineFormat {
+    /// Graphviz DOT graph.
+    #[default]
+    Dot,
+    /// Mermaid state diagram.
+    Mermaid,
+}
+
+/// Exports the player contextual state machine as a diagram in `format`.
+///
+/// The diagram starts from [`PlayerKind::Detecting`] and includes the transitions that can happen
+/// from any state as dashed edges from a pseudo state.
+pub fn export_state_machine(format: StateMachineFormat) -> String {
+    let mut diagram = String::new();
+
+    match format {
+        StateMachineFormat::Dot => {
+            let _ = writeln!(diagram, "digraph Player {{");
+            let _ = writeln!(diagram, "    start [shape=point];");
+            let _ = writeln!(
+                diagram,
+                "    {ANY_STATE} [label=\"Any state\", shape=box, style=dashed];"
+            );
+            let _ = writeln!(diagram, "    start -> {};", PlayerKind::Detecting);
+            for (from, to) in transitions() {
+                let _ = writeln!(diagram, "    {from} -> {to};");
+            }
+            for (to, reason) in ANY_STATE_TRANSITIONS {
+                let _ = writeln!(
+                    diagram,
+                    "    {ANY_STATE} -> {to} [label=\"{reason}\", style=dashed];"
+                );
+            }
+            let _ = writeln!(diagram, "}}");
+        }
+        StateMachineFormat::Mermaid => {
+            let _ = writeln!(diagram, "stateDiagram-v2");
+            let _ = writeln!(diagram, "    state \"Any state\" as {ANY_STATE}");
+            let _ = writeln!(diagram, "    [*] --> {}", PlayerKind::Detecting);
+            for (from, to) in transitions() {
+                let _ = writeln!(diagram, "    {from} --> {to}");
+            }
+            for (to, reason) in ANY_STATE_TRANSITIONS {
+                let _ = writeln!(diagram, "    {ANY_STATE} --> {to}: {reason}");
+            }
+        }
+    }
+
+    diagram
+}
+
+/// The states `kind` can transition to from its own update function.
+#[cfg(debug_assertions)]
+pub fn next_states(kind: PlayerKind) -> &'static [PlayerKind] {
+    TRANSITIONS
+        .iter()
+        .find_map(|(from, tos)| (*from == kind).then_some(*tos))
+        .unwrap_or_default()
+}
+
+/// Describes the transition `player` has planned next.
+///
+/// When idle, this is the action the player starts next. When moving, this is the destination the
+/// movement states are heading to. The other states do not plan ahead and return [`None`].
+#[cfg(debug_assertions)]
+pub fn planned_transition(player: &PlayerEntity) -> Option<String> {
+    let dest = match &player.state {
+        Player::Idle => {
+            return next_action(&player.context).map(|action| format!("Idle -> {action}"));
+        }
+        Player::Moving(dest, _, _) => *dest,
+        Player::DoubleJumping(DoubleJumping { moving, .. })
+        | Player::Teleporting(Teleporting { moving, .. })
+        | Player::Gliding(Gliding { moving, .. })
+        | Player::Adjusting(Adjusting { moving, .. })
+        | Player::Grappling(Grappling { moving, .. })
+        | Player::Jumping(moving)
+        | Player::UpJumping(UpJumping { moving, .. })
+        | Player::Climbing(Climbing { moving, .. })
+        | Player::Falling(Falling { moving, .. }) => moving.dest,
+        _ => return None,
+    };
+    let kind = PlayerKind::from(&player.state);
+
+    Some(format!("{kind} -> ({}, {})", dest.x, dest.y))
+}
+
+#[inline]
+fn transitions() -> impl Iterator<Item = (PlayerKind, PlayerKind)> {
+    TRANSITIONS
+        .iter()
+        .flat_map(|(from, tos)| tos.iter().map(move |to| (*from, *to)))
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    #[test]
+    fn transitions_cover_all_states() {
+        for kind in PlayerKind::iter() {
+            assert!(
+                TRANSITIONS
+                    .iter()
+                    .any(|(from, tos)| *from == kind && !tos.is_empty()),
+                "{kind} has no transitions"
+            );
+            assert!(
+                transitions().any(|(_, to)| to == kind)
+                    || ANY_STATE_TRANSITIONS.iter().any(|(to, _)| *to == kind),
+                "{kind} is unreachable"
+            );
+        }
+    }
+
+    #[test]
+    fn transitions_have_no_duplicate_states() {
+        for (index, (from, tos)) in TRANSITIONS.iter().enumerate() {
+            assert!(
+                !TRANSITIONS[index + 1..]
+                    .iter()
+                    .any(|(other, _)| other == from)
+            );
+            assert!(!tos.contains(from), "{from} transitions to itself");
+            for (index, to) in tos.iter().enumerate() {
+                assert!(!tos[index + 1..].contains(to));
+            }
+        }
+    }
+
+    #[test]
+    fn transitions_derived_from_update_functions() {
+        let has = |from, to| transitions().any(|transition| transition == (from, to));
+
+        assert!(has(PlayerKind::Detecting, PlayerKind::Idle));
+        assert!(has(PlayerKind::Idle, PlayerKind::Moving));
+        assert!(has(PlayerKind::Moving, PlayerKind::Unstucking));
+        assert!(has(PlayerKind::UseKey, PlayerKind::Stalling));
+        assert!(has(PlayerKind::EnteringPortal, PlayerKind::LoadingMap));
+        assert!(has(PlayerKind::Unstucking, PlayerKind::Detecting));
+        // Stored in `stalling_timeout_state` by other states
+        assert!(has(PlayerKind::Stalling, PlayerKind::UseKey));
+        assert!(has(PlayerKind::Stalling, PlayerKind::Jumping));
+        assert!(!has(PlayerKind::UseKey, PlayerKind::Jumping));
+        assert!(!has(PlayerKind::Moving, PlayerKind::Calibrating));
+        assert!(!has(PlayerKind::Climbing, PlayerKind::Idle));
+        assert_eq!(
+            transitions()
+                .filter(|(from, _)| *from == PlayerKind::Detecting)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn next_states_from_table() {
+        assert!(next_states(PlayerKind::Idle).contains(&PlayerKind::Moving));
+        assert_eq!(next_states(PlayerKind::Detecting), &[PlayerKind::Idle]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn planned_transition_idle_and_moving() {
+        use opencv::core::Point;
+
+        use crate::player::{PingPong, PlayerAction, PlayerContext};
+
+        let mut player = PlayerEntity {
+            state: Player::Idle,
+            context: PlayerContext::default(),
+        };
+        assert_eq!(planned_transition(&player), None);
+
+        player
+            .context
+            .set_normal_action(None, PlayerAction::PingPong(PingPong::default()));
+        assert_eq!(
+            planned_transition(&player).as_deref(),
+            Some("Idle -> PingPong")
+        );
+
+        player.state = Player::Moving(Point::new(10, 20), false, None);
+        assert_eq!(
+            planned_transition(&player).as_deref(),
+            Some("Moving -> (10, 20)")
+        );
+
+        player.state = Player::Detecting;
+        assert_eq!(planned_transition(&player), None);
+    }
+
+    #[test]
+    fn export_state_machine_dot() {
+        let diagram = export_state_machine(StateMachineFormat::Dot);
+
+        assert!(diagram.starts_with("digraph Player {\n"));
+        assert!(diagram.ends_with("}\n"));
+        assert!(diagram.contains("    start -> Detecting;\n"));
+        assert!(diagram.contains("    Gliding -> UseKey;\n"));
+        assert!(
+            diagram
+                .contains("    AnyState -> Unstucking [label=\"player stuck\", style=dashed];\n")
+        );
+    }
+
+    #[test]
+    fn export_state_machine_mermaid() {
+        let diagram = export_state_machine(StateMachineFormat::Mermaid);
+
+        assert!(diagram.starts_with("stateDiagram-v2\n"));
+        assert!(diagram.contains("    [*] --> Detecting\n"));
+        assert!(diagram.contains("    EnteringPortal --> LoadingMap\n"));
+        assert!(diagram.contains("    AnyState --> Idle: reset to idle\n"));
+    }
+}
@@ -11,6 +11,7 @@ use crate::{
         MOVE_TIMEOUT, PlayerEntity, moving::Moving, next_action, timeout::ChangeAxis,
         transition_to_moving, transition_to_moving_if,
     },
+    tick,
 };
 
 /// Minimum y distance from the destination required to perform a grappling hook.
@@ -68,11 +69,11 @@ pub fn update_grappling_state(
         .grappling_key
         .expect("cannot transition if not set");
     let prev_pos = grappling.moving.pos;
-    let timeout = if grappling.did_y_changed {
+    let timeout = tick::scaled(if grappling.did_y_changed {
         STOPPING_TIMEOUT
     } else {
         INITIAL_TIMEOUT
-    };
+    });
 
     match next_moving_lifecycle_with_axis(
         grappling.moving,
@@ -155,7 +156,13 @@ pub fn update_grappling_state(
                     );
                 }
                 None
-                | Some(PlayerAction::Key(_) | PlayerAction::Move(_) | PlayerAction::SolveRune) => {}
+                | Some(
+                    PlayerAction::Key(_)
+                    | PlayerAction::Move(_)
+                    | PlayerAction::SolveRune
+                    | PlayerAction::EnterPortal(_)
+                    | PlayerAction::Shop(_),
+                ) => {}
                 _ => unreachable!(),
             }
         }
@@ -228,7 +235,7 @@ mod tests {
     fn update_grappling_state_updated_auto_complete_on_stopping_threshold() {
         let mut moving = mock_moving(POS);
         moving.timeout.started = true;
-        moving.timeout.current = STOPPING_TIMEOUT;
+        moving.timeout.current = tick::scaled(STOPPING_TIMEOUT);
         let mut player = mock_player_entity_with_grapple(moving.pos);
         player.state = Player::Grappling(Grappling::new(moving));
 
@@ -1,24 +1,24 @@
-use log::debug;
+use log::{debug, info};
 use opencv::core::Point;
 
 use super::{
-    AutoMob, Key, Move, Player, PlayerAction,
+    AutoMob, EnterPortal, Key, Move, Player, PlayerAction, Shop,
     actions::{next_action, update_from_ping_pong_action},
+    danger::find_danger_zone_escape_point,
     double_jump::DoubleJumping,
     familiars_swap::FamiliarsSwapping,
-    moving::{Moving, find_intermediate_points},
+    moving::{Moving, find_intermediate_points, find_platform_edge_safe_point},
     panic::Panicking,
     use_key::UseKey,
 };
 use crate::{
     ActionKeyDirection, ActionKeyWith, Position,
-    bridge::KeyKind,
     ecs::{Resources, transition, transition_if},
     minimap::Minimap,
     player::{
         ChattingContent, PlayerEntity, SolvingShape, chat::Chatting,
-        exchange_booster::ExchangingBooster, transition_from_action, unstuck::Unstucking,
-        use_booster::UsingBooster,
+        dismiss_popup::DismissingPopup, exchange_booster::ExchangingBooster,
+        transition_from_action, unstuck::Unstucking, use_booster::UsingBooster,
     },
     rng::Rng,
 };
@@ -26,16 +26,32 @@ use crate::{
 /// Updates [`Player::Idle`] contextual state.
 ///
 /// This state does not do much on its own except when auto mobbing. It acts as entry
-/// to other state when there is an action and helps clearing keys.
+/// to other state when there is an action and helps clearing keys. It also moves the player out
+/// of a danger zone before idling or using a key in place.
 pub fn update_idle_state(resources: &Resources, player: &mut PlayerEntity, minimap_state: Minimap) {
     player.context.last_destinations = None;
     player.context.last_movement = None;
     player.context.stalling_timeout_state = None;
     player.state = Player::Idle; // Sets initial next state first
-    resources.input.send_key_up(KeyKind::Up);
-    resources.input.send_key_up(KeyKind::Down);
-    resources.input.send_key_up(KeyKind::Left);
-    resources.input.send_key_up(KeyKind::Right);
+    resources.input.send_key_up(player.context.config.up_key);
+    resources.input.send_key_up(player.context.config.down_key);
+    resources.input.send_key_up(player.context.config.left_key);
+    resources.input.send_key_up(player.context.config.right_key);
+
+    let context = &player.context;
+    let is_in_place_action = matches!(
+        next_action(context),
+        None | Some(PlayerAction::Key(Key { position: None, .. }))
+    );
+    if is_in_place_action
+        && let Some(point) = find_danger_zone_escape_point(
+            context.config.danger_zones.as_slice(),
+            context.last_known_pos.expect("in positional state"),
+        )
+    {
+        info!(target: "player", "escaping danger zone to {point:?}");
+        transition!(player, Player::Moving(point, true, None));
+    }
 
     update_from_action(resources, player, minimap_state);
 }
@@ -46,7 +62,11 @@ fn update_from_action(resources: &Resources, player: &mut PlayerEntity, minimap_
 
     match action {
         Some(PlayerAction::AutoMob(AutoMob { position, .. })) => {
-            let point = Point::new(position.x, position.y);
+            let point = edge_safe_point(
+                minimap_state,
+                Point::new(position.x, position.y),
+                context.config.platform_edge_margin,
+            );
             let intermediates = if context.config.auto_mob_platforms_pathing {
                 match minimap_state {
                     Minimap::Idle(idle) => find_intermediate_points(
@@ -85,7 +105,11 @@ fn update_from_action(resources: &Resources, player: &mut PlayerEntity, minimap_
 
         Some(PlayerAction::Move(Move { position, .. })) => {
             let x = get_x_destination(&resources.rng, position);
-            let point = Point::new(x, position.y);
+            let point = edge_safe_point(
+                minimap_state,
+                Point::new(x, position.y),
+                context.config.platform_edge_margin,
+            );
 
             debug!(target: "player", "handling move: {point:?}");
             transition!(
@@ -99,7 +123,11 @@ fn update_from_action(resources: &Resources, player: &mut PlayerEntity, minimap_
             ..
         })) => {
             let x = get_x_destination(&resources.rng, position);
-            let point = Point::new(x, position.y);
+            let point = edge_safe_point(
+                minimap_state,
+                Point::new(x, position.y),
+                context.config.platform_edge_margin,
+            );
 
             debug!(target: "player", "handling move: {point:?}");
             transition!(
@@ -195,6 +223,12 @@ fn update_from_action(resources: &Resources, player: &mut PlayerEntity, minimap_
         ),
 
         Some(PlayerAction::Panic(panic)) => {
+            if !player
+                .context
+                .try_use_risky_action(resources, panic.to.into())
+            {
+                transition_from_action!(player, Player::Idle);
+            }
             transition!(player, Player::Panicking(Panicking::new(panic.to)))
         }
 
@@ -221,10 +255,32 @@ fn update_from_action(resources: &Resources, player: &mut PlayerEntity, minimap_
             transition!(player, Player::Unstucking(Unstucking::new_esc()))
         }
 
+        Some(PlayerAction::DismissPopup) => {
+            transition!(player, Player::DismissingPopup(DismissingPopup::default()))
+        }
+
         Some(PlayerAction::SolveShape) => {
             transition!(player, Player::SolvingShape(SolvingShape::default()))
         }
 
+        Some(PlayerAction::EnterPortal(EnterPortal { position, .. })) => {
+            debug!(target: "player", "handling enter portal: {position:?}");
+            transition!(player, Player::Moving(position, true, None))
+        }
+
+        Some(PlayerAction::Shop(shop @ Shop { position, .. })) => {
+            if shop.paths_id_index.is_some() && context.shop_detour.is_none() {
+                // Completes the action for now, the navigator sets it again once the player
+                // reaches the NPC map
+                info!(target: "player", "navigating to shop NPC map");
+                context.shop_detour = Some(shop);
+                transition_from_action!(player, Player::Idle);
+            }
+
+            debug!(target: "player", "handling shop: {position:?}");
+            transition!(player, Player::Moving(position, true, None))
+        }
+
         None => (),
     }
 }
@@ -234,3 +290,11 @@ fn get_x_destination(rng: &Rng, position: Position) -> i32 {
     let x_max = position.x.saturating_add(position.x_random_range + 1);
     rng.random_range(x_min..x_max)
 }
+
+/// Keeps `dest` away from the edges of the platform it is on by `margin`.
+fn edge_safe_point(minimap_state: Minimap, dest: Point, margin: i32) -> Point {
+    match minimap_state {
+        Minimap::Idle(idle) => find_platform_edge_safe_point(&idle.platforms, dest, margin),
+        Minimap::Detecting => dest,
+    }
+}
@@ -7,6 +7,7 @@ use super::{
 use crate::{
     ecs::{Resources, transition},
     player::{PlayerEntity, transition_to_moving},
+    tick,
 };
 
 const TIMEOUT: u32 = MOVE_TIMEOUT + 3;
@@ -15,7 +16,7 @@ pub fn update_jumping_state(resources: &Resources, player: &mut PlayerEntity, mo
     match next_moving_lifecycle_with_axis(
         moving,
         player.context.last_known_pos.expect("in positional state"),
-        TIMEOUT,
+        tick::scaled(TIMEOUT),
         ChangeAxis::Vertical,
     ) {
         MovingLifecycle::Started(moving) => transition!(player, Player::Jumping(moving), {
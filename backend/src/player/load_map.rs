@@ -0,0 +1,145 @@
+use log::info;
+use opencv::core::Rect;
+
+use super::{
+    Player,
+    timeout::{Lifecycle, Timeout, next_timeout_lifecycle},
+};
+use crate::{ecs::transition, minimap::Minimap, player::PlayerEntity, tick};
+
+/// Number of consecutive ticks the minimap and player must stay detected to be stable.
+const STABLE_TICKS: u32 = 10;
+
+/// Maximum number of ticks to wait for the map to load before giving up.
+const TIMEOUT: u32 = 300;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoadingMap {
+    timeout: Timeout,
+    /// The minimap bounding box of the last update if detected along with the player.
+    last_bbox: Option<Rect>,
+    /// Number of consecutive updates with the same [`Self::last_bbox`].
+    stable_count: u32,
+}
+
+/// Updates [`Player::LoadingMap`] contextual state.
+///
+/// This state waits until the minimap bounding box stays the same and the player is detected
+/// for [`STABLE_TICKS`] before returning to [`Player::Idle`]. It also returns to
+/// [`Player::Idle`] when the map still has not loaded after [`TIMEOUT`].
+pub fn update_loading_map_state(
+    player: &mut PlayerEntity,
+    minimap_state: Minimap,
+    failed_to_detect_player: bool,
+) {
+    let Player::LoadingMap(mut loading) = player.state else {
+        panic!("state is not loading map");
+    };
+
+    match next_timeout_lifecycle(loading.timeout, tick::scaled(TIMEOUT)) {
+        Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => loading.timeout = timeout,
+        Lifecycle::Ended => {
+            info!(target: "player", "map did not finish loading in time, resuming anyway");
+            transition!(player, Player::Idle);
+        }
+    }
+
+    let bbox = match minimap_state {
+        Minimap::Idle(idle) if !failed_to_detect_player => Some(idle.bbox),
+        Minimap::Idle(_) | Minimap::Detecting => None,
+    };
+    if bbox.is_some() && bbox == loading.last_bbox {
+        loading.stable_count += 1;
+    } else {
+        loading.stable_count = 0;
+    }
+    loading.last_bbox = bbox;
+
+    if loading.stable_count >= tick::scaled(STABLE_TICKS) {
+        transition!(player, Player::Idle);
+    }
+    transition!(player, Player::LoadingMap(loading));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+    use crate::{minimap::MinimapIdle, player::PlayerContext};
+
+    fn make_player() -> PlayerEntity {
+        PlayerEntity {
+            state: Player::LoadingMap(LoadingMap::default()),
+            context: PlayerContext::default(),
+        }
+    }
+
+    fn make_minimap(bbox: Rect) -> Minimap {
+        let mut idle = MinimapIdle::default();
+        idle.bbox = bbox;
+        Minimap::Idle(idle)
+    }
+
+    #[test]
+    fn update_loading_map_state_waits_for_stable_minimap() {
+        let mut player = make_player();
+        let minimap = make_minimap(Rect::new(0, 0, 100, 100));
+
+        for _ in 0..tick::scaled(STABLE_TICKS) {
+            update_loading_map_state(&mut player, minimap, false);
+            assert_matches!(player.state, Player::LoadingMap(_));
+        }
+        update_loading_map_state(&mut player, minimap, false);
+
+        assert_matches!(player.state, Player::Idle);
+    }
+
+    #[test]
+    fn update_loading_map_state_resets_when_minimap_changed_or_player_not_detected() {
+        let mut player = make_player();
+        let minimap = make_minimap(Rect::new(0, 0, 100, 100));
+
+        for _ in 0..tick::scaled(STABLE_TICKS) {
+            update_loading_map_state(&mut player, minimap, false);
+        }
+        update_loading_map_state(&mut player, minimap, true);
+        assert_matches!(
+            player.state,
+            Player::LoadingMap(LoadingMap {
+                last_bbox: None,
+                stable_count: 0,
+                ..
+            })
+        );
+
+        update_loading_map_state(&mut player, minimap, false);
+        update_loading_map_state(&mut player, make_minimap(Rect::new(0, 0, 50, 50)), false);
+        assert_matches!(
+            player.state,
+            Player::LoadingMap(LoadingMap {
+                stable_count: 0,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn update_loading_map_state_gives_up_on_timeout() {
+        let mut player = PlayerEntity {
+            state: Player::LoadingMap(LoadingMap {
+                timeout: Timeout {
+                    started: true,
+                    current: tick::scaled(TIMEOUT),
+                    ..Timeout::default()
+                },
+                ..LoadingMap::default()
+            }),
+            context: PlayerContext::default(),
+        };
+
+        update_loading_map_state(&mut player, Minimap::Detecting, true);
+
+        assert_matches!(player.state, Player::Idle);
+    }
+}
@@ -1,68 +1,99 @@
 use actions::next_action;
 use adjust::{Adjusting, update_adjusting_state};
+use calibrate::update_calibrating_state;
 use cash_shop::{CashShop, update_cash_shop_state};
+use climb::{Climbing, update_climbing_state};
 use double_jump::{DoubleJumping, update_double_jumping_state};
+use enter_portal::{EnteringPortal, update_entering_portal_state};
 use fall::update_falling_state;
 use familiars_swap::{FamiliarsSwapping, update_familiars_swapping_state};
+use glide::{Gliding, update_gliding_state};
 use grapple::update_grappling_state;
 use idle::update_idle_state;
 use jump::update_jumping_state;
+use load_map::{LoadingMap, update_loading_map_state};
 use moving::{MOVE_TIMEOUT, Moving, MovingIntermediates, update_moving_state};
 use opencv::core::Point;
 use panic::update_panicking_state;
+use risky::RiskyAction;
+use screen_ping_pong::{ScreenPingPonging, update_screen_ping_ponging_state};
+use shop::{Shopping, update_shopping_state};
 use solve_rune::{SolvingRune, update_solving_rune_state};
 use stall::update_stalling_state;
 use state::LastMovement;
-use strum::Display;
+use strum::{Display, EnumDiscriminants, EnumIter};
+use teleporting::{Teleporting, update_teleporting_state};
 use timeout::Timeout;
 use unstuck::update_unstucking_state;
 use up_jump::{UpJumping, update_up_jumping_state};
 use use_key::{UseKey, update_use_key_state};
 
 use crate::{
-    bridge::KeyKind,
     buff::BuffEntities,
     ecs::{Resources, transition, transition_if},
     minimap::{Minimap, MinimapEntity},
     models::ActionKeyDirection,
     player::{
         chat::{Chatting, update_chatting_state},
+        dismiss_popup::{DismissingPopup, update_dismissing_popup_state},
         exchange_booster::{ExchangingBooster, update_exchanging_booster_state},
         fall::Falling,
         grapple::Grappling,
         solve_shape::{SolvingShape, update_solving_shape_state},
-        unstuck::Unstucking,
+        unstuck::{Unstucking, next_unstucking_state},
         use_booster::{UsingBooster, update_using_booster_state},
     },
 };
 
 mod actions;
 mod adjust;
+mod calibrate;
 mod cash_shop;
 mod chat;
+mod climb;
+mod danger;
+mod dismiss_popup;
 mod double_jump;
+mod enter_portal;
 mod exchange_booster;
+mod exp;
 mod fall;
 mod familiars_swap;
+mod glide;
+mod graph;
 mod grapple;
 mod idle;
 mod jump;
+mod load_map;
 mod moving;
 mod panic;
+mod risky;
+mod screen_ping_pong;
+mod shop;
 mod solve_rune;
 mod solve_shape;
 mod stall;
 mod state;
+mod teleporting;
 mod timeout;
+mod trail;
 mod unstuck;
 mod up_jump;
 mod use_booster;
 mod use_key;
 
 pub use actions::*;
+#[cfg(debug_assertions)]
+pub use graph::{next_states, planned_transition};
 pub use {
-    chat::ChattingContent, double_jump::DOUBLE_JUMP_THRESHOLD, grapple::GRAPPLING_MAX_THRESHOLD,
-    grapple::GRAPPLING_THRESHOLD, panic::Panicking, state::PlayerContext, state::Quadrant,
+    calibrate::Calibrating, calibrate::MovementCalibration, calibrate::REFERENCE_DOUBLE_JUMP_SPEED,
+    calibrate::REFERENCE_WALK_SPEED, calibrate::apply_movement_calibration,
+    calibrate::apply_teleport_calibration, calibrate::calibrated_threshold, chat::ChattingContent,
+    climb::MAX_ROPES_COUNT, danger::MAX_DANGER_ZONES_COUNT,
+    dismiss_popup::MAX_LEVEL_UP_AUTO_ASSIGN_KEYS_COUNT, double_jump::DOUBLE_JUMP_THRESHOLD,
+    graph::StateMachineFormat, graph::export_state_machine, grapple::GRAPPLING_MAX_THRESHOLD,
+    grapple::GRAPPLING_THRESHOLD, panic::Panicking, state::MAX_AUTO_MOB_REACHABLE_YS_COUNT,
+    state::PlayerContext, state::Quadrant, trail::StuckPattern,
 };
 
 /// Minimum y distance from the destination required to perform a jump.
@@ -75,7 +106,8 @@ pub struct PlayerEntity {
 }
 
 /// The player contextual states.
-#[derive(Clone, Debug, Display)]
+#[derive(Clone, Debug, Display, EnumDiscriminants)]
+#[strum_discriminants(name(PlayerKind), derive(EnumIter, Display))]
 #[allow(clippy::large_enum_variant)] // There is only ever a single instance of Player
 pub enum Player {
     /// Detects player on the minimap.
@@ -92,12 +124,18 @@ pub enum Player {
     Adjusting(Adjusting),
     /// Performs double jump action.
     DoubleJumping(DoubleJumping),
+    /// Performs repeated teleports horizontally in place of double jump.
+    Teleporting(Teleporting),
+    /// Glides horizontally by holding the flight key in place of double jump.
+    Gliding(Gliding),
     /// Performs a grappling action.
     Grappling(Grappling),
     /// Performs a normal jump.
     Jumping(Moving),
     /// Performs an up jump action.
     UpJumping(UpJumping),
+    /// Climbs a rope or ladder.
+    Climbing(Climbing),
     Falling(Falling),
     /// Unstucks when inside non-detecting position or because of [`PlayerState::unstuck_counter`].
     Unstucking(Unstucking),
@@ -107,6 +145,11 @@ pub enum Player {
     SolvingRune(SolvingRune),
     /// Tries to solve lie detector's transparent shape.
     SolvingShape(SolvingShape),
+    /// Enters a portal and confirms the map or position changed.
+    EnteringPortal(EnteringPortal),
+    /// Waits for the map to finish loading after changing channel, going to town or entering
+    /// a portal.
+    LoadingMap(LoadingMap),
     /// Enters the cash shop then exit after 10 seconds.
     CashShopThenExit(CashShop),
     #[strum(to_string = "FamiliarsSwapping({0})")]
@@ -115,6 +158,14 @@ pub enum Player {
     Chatting(Chatting),
     UsingBooster(UsingBooster),
     ExchangingBooster(ExchangingBooster),
+    /// Dismisses the level up popups.
+    DismissingPopup(DismissingPopup),
+    /// Measures the character movement speeds.
+    Calibrating(Calibrating),
+    /// Continues horizontal ping pong from the screen scrolling while the minimap is hidden.
+    ScreenPingPonging(ScreenPingPonging),
+    /// Buys an item from the NPC shop next to the player.
+    Shopping(Shopping),
 }
 
 impl Player {
@@ -136,6 +187,8 @@ impl Player {
                 forced: false,
                 ..
             })
+            | Player::Teleporting(Teleporting { moving, .. })
+            | Player::Gliding(Gliding { moving, .. })
             | Player::Adjusting(Adjusting { moving, .. }) => {
                 let (distance, _) =
                     moving.x_distance_direction_from(true, cur_pos.unwrap_or(moving.pos));
@@ -144,6 +197,7 @@ impl Player {
             Player::Grappling(Grappling { moving, .. })
             | Player::Jumping(moving)
             | Player::UpJumping(UpJumping { moving, .. })
+            | Player::Climbing(Climbing { moving, .. })
             | Player::Falling(Falling { moving, .. }) => moving.completed,
             Player::SolvingRune(_)
             | Player::CashShopThenExit(_)
@@ -155,7 +209,13 @@ impl Player {
             | Player::Panicking(_)
             | Player::UsingBooster(_)
             | Player::ExchangingBooster(_)
+            | Player::DismissingPopup(_)
+            | Player::Calibrating(_)
+            | Player::ScreenPingPonging(_)
+            | Player::Shopping(_)
             | Player::SolvingShape(_)
+            | Player::EnteringPortal(_)
+            | Player::LoadingMap(_)
             | Player::Stalling(_, _) => false,
         }
     }
@@ -167,15 +227,22 @@ pub fn run_system(
     minimap: &MinimapEntity,
     buffs: &BuffEntities,
 ) {
+    if player.context.rune_cash_shop
+        && !player
+            .context
+            .try_use_risky_action(resources, RiskyAction::CashShop)
+    {
+        player.context.rune_cash_shop = false;
+    }
     transition_if!(
         player,
         Player::CashShopThenExit(CashShop::new()),
         player.context.rune_cash_shop,
         {
-            resources.input.send_key_up(KeyKind::Up);
-            resources.input.send_key_up(KeyKind::Down);
-            resources.input.send_key_up(KeyKind::Left);
-            resources.input.send_key_up(KeyKind::Right);
+            resources.input.send_key_up(player.context.config.up_key);
+            resources.input.send_key_up(player.context.config.down_key);
+            resources.input.send_key_up(player.context.config.left_key);
+            resources.input.send_key_up(player.context.config.right_key);
             player.context.rune_cash_shop = false;
             player.context.reset_to_idle_next_update = false;
         }
@@ -200,16 +267,24 @@ pub fn run_system(
             true
         ));
 
+        if matches!(minimap.state, Minimap::Detecting)
+            && player.context.config.ping_pong_screen_fallback
+            && let Some(PlayerAction::PingPong(ping_pong)) = player.context.normal_action()
+            && !ping_pong.direction.is_vertical()
+        {
+            transition!(
+                player,
+                Player::ScreenPingPonging(ScreenPingPonging::new(ping_pong))
+            );
+        }
+
         let is_stucking = match minimap.state {
             Minimap::Detecting => false,
             Minimap::Idle(idle) => !idle.partially_overlapping,
         };
         transition_if!(
             player,
-            Player::Unstucking(Unstucking::new_movement(
-                Timeout::default(),
-                player.context.track_unstucking_transitioned()
-            )),
+            next_unstucking_state(resources, &mut player.context),
             is_stucking,
             {
                 player.context.last_known_direction = ActionKeyDirection::Any;
@@ -255,7 +330,7 @@ fn update_non_positional_state(
                 return false;
             }
 
-            update_stalling_state(player, timeout, max_timeout);
+            update_stalling_state(resources, player, timeout, max_timeout);
         }
         Player::SolvingRune(_) => {
             if failed_to_detect_player {
@@ -265,6 +340,17 @@ fn update_non_positional_state(
             update_solving_rune_state(resources, player);
         }
         Player::SolvingShape(_) => update_solving_shape_state(resources, player),
+        Player::EnteringPortal(entering) => {
+            // Only the confirmation needs to continue while the map is loading
+            if failed_to_detect_player && !entering.is_confirming() {
+                return false;
+            }
+
+            update_entering_portal_state(resources, player, minimap_state, failed_to_detect_player);
+        }
+        Player::LoadingMap(_) => {
+            update_loading_map_state(player, minimap_state, failed_to_detect_player)
+        }
         Player::CashShopThenExit(cash_shop) => {
             update_cash_shop_state(resources, player, cash_shop, failed_to_detect_player);
         }
@@ -274,15 +360,24 @@ fn update_non_positional_state(
         Player::Chatting(chatting) => update_chatting_state(resources, player, chatting),
         Player::UsingBooster(_) => update_using_booster_state(resources, player),
         Player::ExchangingBooster(_) => update_exchanging_booster_state(resources, player),
+        Player::DismissingPopup(_) => update_dismissing_popup_state(resources, player),
+        Player::ScreenPingPonging(_) => {
+            update_screen_ping_ponging_state(resources, player, minimap_state)
+        }
+        Player::Shopping(_) => update_shopping_state(resources, player),
         Player::Detecting
         | Player::Idle
         | Player::Moving(_, _, _)
         | Player::Adjusting(_)
         | Player::DoubleJumping(_)
+        | Player::Teleporting(_)
+        | Player::Gliding(_)
         | Player::Grappling(_)
         | Player::Jumping(_)
         | Player::UpJumping(_)
-        | Player::Falling(_) => return false,
+        | Player::Climbing(_)
+        | Player::Falling(_)
+        | Player::Calibrating(_) => return false,
     }
 
     true
@@ -301,10 +396,14 @@ fn update_positional_state(
         Player::Moving(_, _, _) => update_moving_state(resources, player, minimap_state),
         Player::Adjusting(_) => update_adjusting_state(resources, player, minimap_state),
         Player::DoubleJumping(_) => update_double_jumping_state(resources, player, minimap_state),
+        Player::Teleporting(_) => update_teleporting_state(resources, player, minimap_state),
+        Player::Gliding(_) => update_gliding_state(resources, player, minimap_state),
         Player::Grappling(_) => update_grappling_state(resources, player, minimap_state),
         Player::UpJumping(_) => update_up_jumping_state(resources, player, minimap_state),
         Player::Jumping(moving) => update_jumping_state(resources, player, moving),
+        Player::Climbing(_) => update_climbing_state(resources, player),
         Player::Falling(Falling { .. }) => update_falling_state(resources, player, minimap_state),
+        Player::Calibrating(_) => update_calibrating_state(resources, player, minimap_state),
         Player::UseKey(_)
         | Player::Unstucking(_)
         | Player::Stalling(_, _)
@@ -314,7 +413,12 @@ fn update_positional_state(
         | Player::Chatting(_)
         | Player::UsingBooster(_)
         | Player::ExchangingBooster(_)
+        | Player::DismissingPopup(_)
         | Player::SolvingShape(_)
+        | Player::EnteringPortal(_)
+        | Player::LoadingMap(_)
+        | Player::ScreenPingPonging(_)
+        | Player::Shopping(_)
         | Player::CashShopThenExit(_) => unreachable!(),
     }
 }
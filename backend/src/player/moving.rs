@@ -6,28 +6,34 @@ use opencv::core::Point;
 use super::{
     GRAPPLING_MAX_THRESHOLD, JUMP_THRESHOLD, Player, PlayerContext,
     actions::{Key, Move, PlayerAction},
+    climb::{Climbing, find_climbable_rope},
+    danger::should_adjust_out_of_danger_zone,
     double_jump::{DOUBLE_JUMP_THRESHOLD, DoubleJumping},
+    glide::{GLIDING_THRESHOLD, Gliding},
     state::LastMovement,
+    teleporting::Teleporting,
     timeout::Timeout,
     up_jump::UpJumping,
 };
 use crate::{
     ActionKeyDirection, ActionKeyWith, MAX_PLATFORMS_COUNT,
     array::Array,
-    bridge::KeyKind,
     ecs::{Resources, transition, transition_if},
     minimap::Minimap,
     pathing::{MovementHint, PlatformWithNeighbors, find_points_with},
     player::{
         Falling, PlayerEntity,
-        adjust::{ADJUSTING_MEDIUM_THRESHOLD, ADJUSTING_SHORT_THRESHOLD, Adjusting},
+        adjust::{ADJUSTING_SHORT_THRESHOLD, Adjusting},
+        enter_portal::EnteringPortal,
         grapple::{GRAPPLING_THRESHOLD, Grappling},
         next_action,
+        shop::Shopping,
         solve_rune::SolvingRune,
         transition_from_action,
-        unstuck::Unstucking,
+        unstuck::next_unstucking_state,
         use_key::UseKey,
     },
+    tick,
 };
 
 /// Maximum amount of ticks a change in x or y direction must be detected.
@@ -238,7 +244,7 @@ impl Moving {
         let y_within_jump = y_distance < JUMP_THRESHOLD;
 
         let can_skip_y = did_fall_down || did_up_jump || y_within_jump;
-        let can_skip_x = x_distance < DOUBLE_JUMP_THRESHOLD;
+        let can_skip_x = x_distance < context.config.double_jump_threshold;
 
         can_skip_x && can_skip_y
     }
@@ -251,8 +257,9 @@ impl Moving {
 /// state looping and advancing `intermediates` when the current destination is reached.
 ///
 /// It will first transition to [`Player::DoubleJumping`] and [`Player::Adjusting`] for
-/// matching `x` of `dest`. Then, [`Player::Grappling`], [`Player::UpJumping`], [`Player::Jumping`]
-/// or [`Player::Falling`] for matching `y` of `dest`. (e.g. horizontal then vertical)
+/// matching `x` of `dest`. If the player is standing in or would land in a danger zone, it will
+/// adjust to the exact `x` first. Then, [`Player::Grappling`], [`Player::Climbing`],
+/// [`Player::UpJumping`], [`Player::Jumping`] or [`Player::Falling`] for matching `y` of `dest`. (e.g. horizontal then vertical)
 ///
 /// In auto mob or intermediate destination, most of the movement thresholds are relaxed for
 /// more fluid movement.
@@ -269,11 +276,8 @@ pub fn update_moving_state(
     player.state = Player::Idle; // Sets initial next state first
     transition_if!(
         player,
-        Player::Unstucking(Unstucking::new_movement(
-            Timeout::default(),
-            context.track_unstucking_transitioned()
-        )),
-        context.track_unstucking()
+        next_unstucking_state(resources, context),
+        context.track_unstucking() || context.track_stuck_pattern(resources.tick, dest)
     );
 
     let cur_pos = context.last_known_pos.unwrap();
@@ -287,12 +291,40 @@ pub fn update_moving_state(
     let disable_double_jumping = context.config.disable_double_jumping;
     let disable_adjusting = context.config.disable_adjusting;
 
+    // Check to glide horizontally in place of double jump
+    if !skip_destination
+        && context.config.horizontal_flight
+        && context.config.flight_key.is_some()
+        && !context.has_horizontal_ping_pong_action_only()
+        && x_distance >= GLIDING_THRESHOLD
+    {
+        return abort_action_on_state_repeat(
+            player,
+            Player::Gliding(Gliding::new(moving)),
+            minimap_state,
+        );
+    }
+
+    // Check to teleport horizontally in place of double jump
+    if !skip_destination
+        && context.config.horizontal_teleporting
+        && context.config.teleport_key.is_some()
+        && !context.has_horizontal_ping_pong_action_only()
+        && x_distance >= context.teleporting_threshold(is_intermediate)
+    {
+        return abort_action_on_state_repeat(
+            player,
+            Player::Teleporting(Teleporting::new(moving)),
+            minimap_state,
+        );
+    }
+
     // Check to double jump
     if !skip_destination
         && !disable_double_jumping
         && x_distance >= context.double_jump_threshold(is_intermediate)
     {
-        let require_stationary = context.has_ping_pong_action_only()
+        let require_stationary = context.has_horizontal_ping_pong_action_only()
             && !matches!(
                 context.last_movement,
                 Some(LastMovement::Grappling | LastMovement::UpJumping)
@@ -306,7 +338,7 @@ pub fn update_moving_state(
 
     // Check to adjust and allow disabling adjusting only if `exact` is false
     if !skip_destination
-        && ((!disable_adjusting && x_distance >= ADJUSTING_MEDIUM_THRESHOLD)
+        && ((!disable_adjusting && x_distance >= context.config.adjusting_medium_threshold)
             || (exact && x_distance >= ADJUSTING_SHORT_THRESHOLD))
     {
         return abort_action_on_state_repeat(
@@ -316,6 +348,19 @@ pub fn update_moving_state(
         );
     }
 
+    // Check to adjust out of danger zone before moving vertically
+    if !skip_destination
+        && x_distance >= ADJUSTING_SHORT_THRESHOLD
+        && should_adjust_out_of_danger_zone(context.config.danger_zones.as_slice(), cur_pos, dest)
+    {
+        let moving = Moving::new(cur_pos, dest, true, intermediates);
+        return abort_action_on_state_repeat(
+            player,
+            Player::Adjusting(Adjusting::new(moving)),
+            minimap_state,
+        );
+    }
+
     // Check to grapple
     let has_teleport_key = context.config.teleport_key.is_some();
     if !skip_destination
@@ -331,6 +376,19 @@ pub fn update_moving_state(
         );
     }
 
+    // Check to climb
+    if !skip_destination
+        && y_direction > 0
+        && y_distance >= UP_JUMP_THRESHOLD
+        && let Some(rope) = find_climbable_rope(context.config.ropes.as_slice(), cur_pos, dest)
+    {
+        return abort_action_on_state_repeat(
+            player,
+            Player::Climbing(Climbing::new(moving, rope)),
+            minimap_state,
+        );
+    }
+
     // Check to up jump
     if !skip_destination && y_direction > 0 && y_distance >= UP_JUMP_THRESHOLD {
         // In auto mob with platforms pathing and up jump only, immediately aborts the action
@@ -378,7 +436,7 @@ pub fn update_moving_state(
         context.clear_last_movement();
         transition_if!(
             player,
-            Player::Stalling(Timeout::default(), 3),
+            Player::Stalling(Timeout::default(), tick::scaled(3)),
             matches!(moving.intermediate_hint(), Some(MovementHint::WalkAndJump)),
             {
                 // TODO: Any better way ???
@@ -390,9 +448,9 @@ pub fn update_moving_state(
                 )));
 
                 let key = if dest.x - cur_pos.x >= 0 {
-                    KeyKind::Right
+                    context.config.right_key
                 } else {
-                    KeyKind::Left
+                    context.config.left_key
                 };
                 resources.input.send_key_down(key);
             }
@@ -471,12 +529,30 @@ fn update_from_action(player: &mut PlayerEntity, moving: Moving) {
             transition!(player, Player::SolvingRune(SolvingRune::default()))
         }
 
+        Some(PlayerAction::EnterPortal(portal)) => {
+            transition!(
+                player,
+                Player::EnteringPortal(EnteringPortal::new(tick::ticks_from_millis(
+                    portal.window_millis
+                )))
+            )
+        }
+
+        Some(PlayerAction::Shop(shop)) => {
+            transition!(player, Player::Shopping(Shopping::new(shop)))
+        }
+
+        Some(PlayerAction::PingPong(ping_pong)) if ping_pong.direction.is_vertical() => {
+            transition!(player, Player::UseKey(UseKey::from_ping_pong(ping_pong)))
+        }
+
         Some(PlayerAction::PingPong(_)) => transition_from_action!(player, Player::Idle),
 
         Some(
             PlayerAction::Chat(_)
             | PlayerAction::SolveShape
             | PlayerAction::Unstuck
+            | PlayerAction::DismissPopup
             | PlayerAction::Panic(_)
             | PlayerAction::FamiliarsSwap(_)
             | PlayerAction::UseBooster(_)
@@ -489,6 +565,37 @@ fn update_from_action(player: &mut PlayerEntity, moving: Moving) {
     }
 }
 
+/// Moves `dest` away from the edges of the platform it is on by `margin`.
+///
+/// Returns `dest` as is if `margin` is not positive or `dest` is not on any platform. The x is
+/// the platform center if the platform is too narrow for `margin`.
+pub fn find_platform_edge_safe_point(
+    platforms: &Array<PlatformWithNeighbors, MAX_PLATFORMS_COUNT>,
+    dest: Point,
+    margin: i32,
+) -> Point {
+    if margin <= 0 {
+        return dest;
+    }
+    let Some(xs) = platforms
+        .iter()
+        .map(|platform| (platform.xs(), platform.y()))
+        .find_map(|(xs, y)| (y == dest.y && xs.contains(&dest.x)).then_some(xs))
+    else {
+        return dest;
+    };
+
+    let x_min = xs.start + margin;
+    let x_max = xs.end - 1 - margin;
+    let x = if x_min > x_max {
+        (xs.start + xs.end - 1) / 2
+    } else {
+        dest.x.clamp(x_min, x_max)
+    };
+
+    Point::new(x, dest.y)
+}
+
 #[inline]
 pub fn find_intermediate_points(
     platforms: &Array<PlatformWithNeighbors, MAX_PLATFORMS_COUNT>,
@@ -528,10 +635,16 @@ pub fn find_intermediate_points(
 mod tests {
     use std::assert_matches::assert_matches;
 
-    use opencv::core::Point;
+    use opencv::core::{Point, Rect};
 
     use super::*;
-    use crate::ecs::Resources;
+    use crate::{
+        DangerZone,
+        bridge::KeyKind,
+        ecs::Resources,
+        pathing::{Platform, find_neighbors},
+        player::{PingPong, PingPongDirection},
+    };
 
     fn setup_player(pos: Point, state: Player) -> PlayerEntity {
         let mut player = PlayerEntity {
@@ -553,6 +666,32 @@ mod tests {
         assert_matches!(player.state, Player::DoubleJumping(_));
     }
 
+    #[test]
+    fn update_moving_to_teleporting() {
+        let resources = Resources::new(None, None);
+        let dest = Point::new(100, 0);
+        let mut player = setup_player(Point::new(0, 0), Player::Moving(dest, false, None));
+        player.context.config.teleport_key = Some(KeyKind::Shift);
+        player.context.config.horizontal_teleporting = true;
+
+        update_moving_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(player.state, Player::Teleporting(_));
+    }
+
+    #[test]
+    fn update_moving_to_gliding() {
+        let resources = Resources::new(None, None);
+        let dest = Point::new(100, 0);
+        let mut player = setup_player(Point::new(0, 0), Player::Moving(dest, false, None));
+        player.context.config.flight_key = Some(KeyKind::Shift);
+        player.context.config.horizontal_flight = true;
+
+        update_moving_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(player.state, Player::Gliding(_));
+    }
+
     #[test]
     fn update_moving_to_adjusting() {
         let resources = Resources::new(None, None);
@@ -613,6 +752,57 @@ mod tests {
         assert_matches!(player.state, Player::Falling(_));
     }
 
+    #[test]
+    fn update_moving_to_adjusting_before_falling_into_danger_zone() {
+        let resources = Resources::new(None, None);
+        let cur_pos = Point::new(100, 100);
+        let dest = Point::new(98, 50);
+        let mut player = setup_player(cur_pos, Player::Moving(dest, false, None));
+        player.context.config.danger_zones = Array::from_iter([DangerZone {
+            x_start: 99,
+            x_end: 120,
+            y: 50,
+        }]);
+
+        update_moving_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(
+            player.state,
+            Player::Adjusting(Adjusting {
+                moving: Moving { exact: true, .. },
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn update_moving_vertical_ping_pong_up_jumps_then_uses_key() {
+        let resources = Resources::new(None, None);
+        let ping_pong = PingPong {
+            bound: Rect::new(0, 0, 100, 20),
+            direction: PingPongDirection::Up,
+            ..Default::default()
+        };
+        let mut player = setup_player(
+            Point::new(50, 0),
+            Player::Moving(Point::new(50, 20), false, None),
+        );
+        player
+            .context
+            .set_normal_action(None, PlayerAction::PingPong(ping_pong));
+
+        update_moving_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(player.state, Player::UpJumping(_));
+
+        player.context.last_known_pos = Some(Point::new(50, 20));
+        player.state = Player::Moving(Point::new(50, 20), false, None);
+
+        update_moving_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(player.state, Player::UseKey(_));
+    }
+
     #[test]
     fn update_moving_to_idle_when_destination_reached() {
         let resources = Resources::new(None, None);
@@ -641,4 +831,37 @@ mod tests {
 
         assert_matches!(player.state, Player::Moving(Point { x: 100, y: 0 }, _, _));
     }
+
+    #[test]
+    fn find_platform_edge_safe_point_keeps_away_from_edges() {
+        let platforms = Array::from_iter(find_neighbors(
+            &[Platform::new(10..50, 20), Platform::new(60..64, 20)],
+            25,
+            7,
+            41,
+        ));
+
+        assert_eq!(
+            find_platform_edge_safe_point(&platforms, Point::new(12, 20), 5),
+            Point::new(15, 20)
+        );
+        assert_eq!(
+            find_platform_edge_safe_point(&platforms, Point::new(48, 20), 5),
+            Point::new(44, 20)
+        );
+        assert_eq!(
+            find_platform_edge_safe_point(&platforms, Point::new(30, 20), 5),
+            Point::new(30, 20)
+        );
+        // Too narrow
+        assert_eq!(
+            find_platform_edge_safe_point(&platforms, Point::new(60, 20), 5),
+            Point::new(61, 20)
+        );
+        // Not on any platform
+        assert_eq!(
+            find_platform_edge_safe_point(&platforms, Point::new(12, 30), 5),
+            Point::new(12, 30)
+        );
+    }
 }
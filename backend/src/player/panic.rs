@@ -1,6 +1,8 @@
 use log::info;
 
-use super::{Player, actions::PanicTo, timeout::Timeout};
+use super::{
+    Player, actions::PanicTo, load_map::LoadingMap, state::PlayerConfiguration, timeout::Timeout,
+};
 use crate::{
     bridge::KeyKind,
     ecs::{Resources, transition, transition_if, try_some_transition},
@@ -10,6 +12,7 @@ use crate::{
         timeout::{Lifecycle, next_timeout_lifecycle},
         transition_from_action,
     },
+    tick,
 };
 
 const MAX_RETRY: u32 = 3;
@@ -42,6 +45,14 @@ impl Panicking {
     }
 }
 
+/// Whether the keys required by [`update_panicking_state`] are set in `config`.
+///
+/// Both keys are required regardless of [`PanicTo`] because the change channel key is checked
+/// first.
+pub fn has_panicking_keys(config: &PlayerConfiguration) -> bool {
+    config.change_channel_key.is_some() && config.to_town_key.is_some()
+}
+
 /// Updates [`Player::Panicking`] contextual state.
 pub fn update_panicking_state(
     resources: &Resources,
@@ -64,6 +75,7 @@ pub fn update_panicking_state(
             player.context.clear_action_completed();
         });
 
+    let was_changing_channel = matches!(panicking.state, State::ChangingChannel(_, _));
     match panicking.state {
         State::ChangingChannel(_, _) => {
             update_changing_channel(resources, &mut panicking, minimap_state, change_channel_key)
@@ -71,9 +83,12 @@ pub fn update_panicking_state(
         State::GoingToTown(_, _) => update_going_to_town(resources, &mut panicking, to_town_key),
         State::Completing(_, _) => update_completing(&mut panicking, minimap_state),
     };
+    if was_changing_channel && matches!(panicking.state, State::Completing(_, false)) {
+        player.context.track_channel_changed();
+    }
 
     let player_next_state = if matches!(panicking.state, State::Completing(_, true)) {
-        Player::Idle
+        Player::LoadingMap(LoadingMap::default())
     } else {
         Player::Panicking(panicking)
     };
@@ -82,7 +97,7 @@ pub fn update_panicking_state(
         Some(_) => transition_from_action!(
             player,
             player_next_state,
-            matches!(player_next_state, Player::Idle)
+            matches!(player_next_state, Player::LoadingMap(_))
         ),
         None => transition_if!(
             player,
@@ -112,11 +127,11 @@ fn update_changing_channel(
     let State::ChangingChannel(timeout, retry_count) = panicking.state else {
         panic!("panicking state is not changing channel")
     };
-    let max_timeout = if retry_count == 0 {
+    let max_timeout = tick::scaled(if retry_count == 0 {
         TIMEOUT_INITIAL
     } else {
         TIMEOUT_AFTER
-    };
+    });
     match next_timeout_lifecycle(timeout, max_timeout) {
         Lifecycle::Started(timeout) => {
             transition!(panicking, State::ChangingChannel(timeout, retry_count), {
@@ -145,6 +160,8 @@ fn update_changing_channel(
                 } else {
                     (PRESS_RIGHT_AT_AFTER, PRESS_ENTER_AT_AFTER)
                 };
+                let (press_right_at, press_enter_at) =
+                    (tick::scaled(press_right_at), tick::scaled(press_enter_at));
                 match timeout.current {
                     tick if tick == press_right_at => {
                         if resources.detector().detect_change_channel_menu_opened() {
@@ -168,7 +185,7 @@ fn update_going_to_town(resources: &Resources, panicking: &mut Panicking, key: K
         panic!("panicking state is not going to town")
     };
 
-    match next_timeout_lifecycle(timeout, 90) {
+    match next_timeout_lifecycle(timeout, tick::scaled(90)) {
         Lifecycle::Started(timeout) => {
             transition!(panicking, State::GoingToTown(timeout, retry_count), {
                 resources.input.send_key(key);
@@ -205,7 +222,7 @@ fn update_completing(panicking: &mut Panicking, minimap_state: Minimap) {
         matches!(panicking.to, PanicTo::Town)
     );
 
-    match next_timeout_lifecycle(timeout, 245) {
+    match next_timeout_lifecycle(timeout, tick::scaled(245)) {
         Lifecycle::Ended => match minimap_state {
             Minimap::Idle(idle) => transition_if!(
                 panicking,
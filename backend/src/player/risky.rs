@@ -0,0 +1,378 @@
+use std::time::{Duration, Instant};
+
+use chrono::{Local, NaiveDate};
+use strum::Display;
+
+use super::PanicTo;
+use crate::{
+    clock,
+    models::{RiskyActionCounts, RiskyActionLimit, RiskyActionLimits},
+    rng::Rng,
+};
+
+/// An action that is more likely to be flagged when used too often.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display)]
+pub enum RiskyAction {
+    #[strum(to_string = "change channel")]
+    ChangeChannel,
+    #[strum(to_string = "go to town")]
+    GoToTown,
+    #[strum(to_string = "cash shop")]
+    CashShop,
+}
+
+impl From<PanicTo> for RiskyAction {
+    fn from(to: PanicTo) -> Self {
+        match to {
+            PanicTo::Town => RiskyAction::GoToTown,
+            PanicTo::Channel => RiskyAction::ChangeChannel,
+        }
+    }
+}
+
+/// Why a [`RiskyAction`] is not allowed by [`RiskyActionLimiter`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RiskyActionLimited {
+    /// The minimum interval since the last use has not passed.
+    Interval,
+    /// The daily cap is reached, with `first` being whether this is the first time it is hit
+    /// today.
+    DailyCap { first: bool },
+}
+
+/// The uses of a [`RiskyAction`].
+#[derive(Clone, Copy, Default, Debug)]
+struct RiskyActionUsage {
+    /// The earliest instant the action can be used again.
+    next_allowed: Option<Instant>,
+    /// The day [`Self::count`] is counted on.
+    day: Option<NaiveDate>,
+    count: u32,
+    /// Whether the daily cap was already hit on [`Self::day`].
+    cap_hit: bool,
+}
+
+impl RiskyActionUsage {
+    #[inline]
+    fn count_on(&self, day: NaiveDate) -> u32 {
+        if self.day == Some(day) { self.count } else { 0 }
+    }
+}
+
+/// Limits the uses of [`RiskyAction`]s regardless of what triggered them.
+#[derive(Debug, Default)]
+pub struct RiskyActionLimiter {
+    change_channel: RiskyActionUsage,
+    go_to_town: RiskyActionUsage,
+    cash_shop: RiskyActionUsage,
+    /// Whether the daily counts changed since the last [`Self::take_counts`].
+    counts_changed: bool,
+}
+
+impl RiskyActionLimiter {
+    /// Restores the daily counts persisted from a previous session.
+    ///
+    /// Counts of a date that cannot be parsed are ignored.
+    pub fn restore_counts(&mut self, counts: &RiskyActionCounts) {
+        let Ok(day) = counts.date.parse::<NaiveDate>() else {
+            return;
+        };
+
+        for (usage, count) in [
+            (&mut self.change_channel, counts.change_channel),
+            (&mut self.go_to_town, counts.go_to_town),
+            (&mut self.cash_shop, counts.cash_shop),
+        ] {
+            usage.day = Some(day);
+            usage.count = count;
+            usage.cap_hit = false;
+        }
+    }
+
+    /// Takes the daily counts of today if they changed since the last take.
+    pub fn take_counts(&mut self) -> Option<RiskyActionCounts> {
+        self.take_counts_on(Local::now().date_naive())
+    }
+
+    fn take_counts_on(&mut self, today: NaiveDate) -> Option<RiskyActionCounts> {
+        if !self.counts_changed {
+            return None;
+        }
+
+        self.counts_changed = false;
+        Some(RiskyActionCounts {
+            id: None,
+            date: today.to_string(),
+            change_channel: self.change_channel.count_on(today),
+            go_to_town: self.go_to_town.count_on(today),
+            cash_shop: self.cash_shop.count_on(today),
+        })
+    }
+
+    /// Tries to use `action` under `limits`.
+    ///
+    /// Records the use if the action is allowed. The minimum interval until the next use is
+    /// randomized on each use.
+    pub fn try_use(
+        &mut self,
+        action: RiskyAction,
+        limits: &RiskyActionLimits,
+        rng: &Rng,
+    ) -> Result<(), RiskyActionLimited> {
+        self.try_use_at(action, limits, rng, clock::now(), Local::now().date_naive())
+    }
+
+    fn try_use_at(
+        &mut self,
+        action: RiskyAction,
+        limits: &RiskyActionLimits,
+        rng: &Rng,
+        now: Instant,
+        today: NaiveDate,
+    ) -> Result<(), RiskyActionLimited> {
+        if !limits.enabled {
+            return Ok(());
+        }
+
+        let (usage, limit) = self.usage_and_limit(action, limits);
+        if usage.day != Some(today) {
+            usage.day = Some(today);
+            usage.count = 0;
+            usage.cap_hit = false;
+        }
+        if usage.next_allowed.is_some_and(|instant| now < instant) {
+            return Err(RiskyActionLimited::Interval);
+        }
+        if limit.max_per_day > 0 && usage.count >= limit.max_per_day {
+            let first = !usage.cap_hit;
+            usage.cap_hit = true;
+            return Err(RiskyActionLimited::DailyCap { first });
+        }
+
+        let jitter_millis = if limit.interval_jitter_millis > 0 {
+            rng.random_range(0..=limit.interval_jitter_millis)
+        } else {
+            0
+        };
+        usage.next_allowed =
+            Some(now + Duration::from_millis(limit.min_interval_millis + jitter_millis));
+        usage.count += 1;
+        self.counts_changed = true;
+
+        Ok(())
+    }
+
+    #[inline]
+    fn usage_and_limit(
+        &mut self,
+        action: RiskyAction,
+        limits: &RiskyActionLimits,
+    ) -> (&mut RiskyActionUsage, RiskyActionLimit) {
+        match action {
+            RiskyAction::ChangeChannel => (&mut self.change_channel, limits.change_channel),
+            RiskyAction::GoToTown => (&mut self.go_to_town, limits.go_to_town),
+            RiskyAction::CashShop => (&mut self.cash_shop, limits.cash_shop),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: [u8; 32] = [
+        64, 241, 206, 219, 49, 21, 218, 145, 254, 152, 68, 176, 242, 238, 152, 14, 176, 241, 153,
+        64, 44, 192, 172, 191, 191, 157, 107, 206, 193, 55, 115, 68,
+    ];
+
+    fn limits(min_interval_millis: u64, max_per_day: u32) -> RiskyActionLimits {
+        RiskyActionLimits {
+            enabled: true,
+            change_channel: RiskyActionLimit {
+                min_interval_millis,
+                interval_jitter_millis: 1000,
+                max_per_day,
+            },
+            ..RiskyActionLimits::default()
+        }
+    }
+
+    #[test]
+    fn try_use_waits_for_randomized_interval() {
+        let rng = Rng::new(SEED, 1337);
+        let limits = limits(10_000, 0);
+        let mut limiter = RiskyActionLimiter::default();
+        let now = Instant::now();
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+
+        assert!(
+            limiter
+                .try_use_at(RiskyAction::ChangeChannel, &limits, &rng, now, today)
+                .is_ok()
+        );
+        assert_eq!(
+            limiter.try_use_at(
+                RiskyAction::ChangeChannel,
+                &limits,
+                &rng,
+                now + Duration::from_millis(9_999),
+                today
+            ),
+            Err(RiskyActionLimited::Interval)
+        );
+        assert!(
+            limiter
+                .try_use_at(RiskyAction::GoToTown, &limits, &rng, now, today)
+                .is_ok()
+        );
+        assert!(
+            limiter
+                .try_use_at(
+                    RiskyAction::ChangeChannel,
+                    &limits,
+                    &rng,
+                    now + Duration::from_millis(11_000),
+                    today
+                )
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn try_use_caps_daily_uses() {
+        let rng = Rng::new(SEED, 1337);
+        let limits = limits(0, 2);
+        let mut limiter = RiskyActionLimiter::default();
+        let now = Instant::now();
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let later = |millis| now + Duration::from_millis(millis);
+
+        assert!(
+            limiter
+                .try_use_at(RiskyAction::ChangeChannel, &limits, &rng, now, today)
+                .is_ok()
+        );
+        assert!(
+            limiter
+                .try_use_at(
+                    RiskyAction::ChangeChannel,
+                    &limits,
+                    &rng,
+                    later(2_000),
+                    today
+                )
+                .is_ok()
+        );
+        assert_eq!(
+            limiter.try_use_at(
+                RiskyAction::ChangeChannel,
+                &limits,
+                &rng,
+                later(4_000),
+                today
+            ),
+            Err(RiskyActionLimited::DailyCap { first: true })
+        );
+        // Only the first hit of the day is reported as first
+        assert_eq!(
+            limiter.try_use_at(
+                RiskyAction::ChangeChannel,
+                &limits,
+                &rng,
+                later(5_000),
+                today
+            ),
+            Err(RiskyActionLimited::DailyCap { first: false })
+        );
+        assert!(
+            limiter
+                .try_use_at(
+                    RiskyAction::ChangeChannel,
+                    &limits,
+                    &rng,
+                    later(6_000),
+                    today.succ_opt().unwrap()
+                )
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn counts_persisted_with_date_and_restored() {
+        let rng = Rng::new(SEED, 1337);
+        let limits = limits(0, 2);
+        let mut limiter = RiskyActionLimiter::default();
+        let now = Instant::now();
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+
+        assert_eq!(limiter.take_counts_on(today), None);
+        assert!(
+            limiter
+                .try_use_at(RiskyAction::ChangeChannel, &limits, &rng, now, today)
+                .is_ok()
+        );
+        let counts = limiter.take_counts_on(today).unwrap();
+        assert_eq!(counts.date, "2025-01-01");
+        assert_eq!(counts.change_channel, 1);
+        assert_eq!(counts.go_to_town, 0);
+        assert_eq!(limiter.take_counts_on(today), None);
+
+        // A restarted session keeps counting from the persisted counts of the same day
+        let mut restored = RiskyActionLimiter::default();
+        restored.restore_counts(&counts);
+        assert!(
+            restored
+                .try_use_at(RiskyAction::ChangeChannel, &limits, &rng, now, today)
+                .is_ok()
+        );
+        assert_eq!(
+            restored.try_use_at(
+                RiskyAction::ChangeChannel,
+                &limits,
+                &rng,
+                now + Duration::from_millis(2_000),
+                today
+            ),
+            Err(RiskyActionLimited::DailyCap { first: true })
+        );
+
+        // Counts of another day are reset
+        let mut restored = RiskyActionLimiter::default();
+        restored.restore_counts(&counts);
+        let tomorrow = today.succ_opt().unwrap();
+        assert_eq!(
+            restored.take_counts_on(tomorrow),
+            None,
+            "restoring is not a change"
+        );
+        assert!(
+            restored
+                .try_use_at(RiskyAction::ChangeChannel, &limits, &rng, now, tomorrow)
+                .is_ok()
+        );
+        assert_eq!(restored.take_counts_on(tomorrow).unwrap().change_channel, 1);
+    }
+
+    #[test]
+    fn try_use_always_allows_when_disabled() {
+        let rng = Rng::new(SEED, 1337);
+        let limits = RiskyActionLimits {
+            enabled: false,
+            ..limits(60_000, 1)
+        };
+        let mut limiter = RiskyActionLimiter::default();
+        let now = Instant::now();
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+
+        assert!(
+            limiter
+                .try_use_at(RiskyAction::ChangeChannel, &limits, &rng, now, today)
+                .is_ok()
+        );
+        assert!(
+            limiter
+                .try_use_at(RiskyAction::ChangeChannel, &limits, &rng, now, today)
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use log::debug;
+
+use super::{
+    PingPong, PingPongDirection, Player, PlayerEntity,
+    timeout::{Lifecycle, Timeout, next_timeout_lifecycle},
+};
+use crate::{
+    detect::ScreenAnchor,
+    ecs::{Resources, transition},
+    minimap::Minimap,
+    models::ActionKeyDirection,
+    tick,
+};
+
+/// Maximum number of ticks to move in one direction before bouncing.
+const MAX_TICKS: u32 = 300;
+
+/// Number of ticks without the screen scrolling for the player to be considered at the edge.
+const STALL_TICKS: u32 = 30;
+
+/// Maximum number of [`ScreenAnchor`] columns searched for the screen scrolling.
+const MAX_ANCHOR_SHIFT: usize = 40;
+
+/// Minimum number of [`ScreenAnchor`] columns scrolled in the moving direction to be moving.
+const MOVING_SHIFT: i32 = 2;
+
+/// Number of ticks between each use of the mobbing key.
+const USE_KEY_TICKS: u32 = 10;
+
+#[derive(Clone, Copy, Debug)]
+pub struct ScreenPingPonging {
+    ping_pong: PingPong,
+    timeout: Timeout,
+    /// The [`ScreenAnchor`] the scrolling is measured from.
+    anchor: Option<ScreenAnchor>,
+    /// Number of ticks since the last time the screen scrolled in the ping pong direction.
+    stalled_ticks: u32,
+}
+
+impl ScreenPingPonging {
+    pub fn new(ping_pong: PingPong) -> Self {
+        Self {
+            ping_pong,
+            timeout: Timeout::default(),
+            anchor: None,
+            stalled_ticks: 0,
+        }
+    }
+}
+
+/// Updates [`Player::ScreenPingPonging`] contextual state.
+///
+/// This state is a degraded horizontal ping pong used while the minimap cannot be detected. It
+/// holds the direction key and uses the mobbing key periodically while estimating the movement
+/// from the screen scrolling. Since the camera stops scrolling at the map edges, the direction
+/// is completed once the screen stops scrolling for [`STALL_TICKS`] or after [`MAX_TICKS`]. The
+/// [`Rotator`] then rotates the next action in the reverse direction.
+///
+/// The state returns to [`Player::Idle`] as soon as the minimap is detected again.
+pub fn update_screen_ping_ponging_state(
+    resources: &Resources,
+    player: &mut PlayerEntity,
+    minimap_state: Minimap,
+) {
+    let Player::ScreenPingPonging(mut ping_ponging) = player.state else {
+        panic!("state is not screen ping ponging");
+    };
+    let (key, opposite_key, direction) = match ping_ponging.ping_pong.direction {
+        PingPongDirection::Left => (
+            player.context.config.left_key,
+            player.context.config.right_key,
+            ActionKeyDirection::Left,
+        ),
+        PingPongDirection::Right => (
+            player.context.config.right_key,
+            player.context.config.left_key,
+            ActionKeyDirection::Right,
+        ),
+        PingPongDirection::Up | PingPongDirection::Down => unreachable!(),
+    };
+
+    if matches!(minimap_state, Minimap::Idle(_)) {
+        resources.input.send_key_up(key);
+        player.context.reset_normal_action();
+        transition!(player, Player::Idle);
+    }
+
+    match next_timeout_lifecycle(ping_ponging.timeout, tick::scaled(MAX_TICKS)) {
+        Lifecycle::Started(timeout) => {
+            resources.input.send_key_up(player.context.config.up_key);
+            resources.input.send_key_up(player.context.config.down_key);
+            resources.input.send_key_up(opposite_key);
+            resources.input.send_key_down(key);
+            player.context.last_known_direction = direction;
+            ping_ponging.timeout = timeout;
+            ping_ponging.anchor = resources.detector().detect_screen_anchor().ok();
+        }
+        Lifecycle::Updated(timeout) => {
+            ping_ponging.timeout = timeout;
+            update_stalled_ticks(resources, &mut ping_ponging);
+            if timeout.current % tick::scaled(USE_KEY_TICKS) == 0 {
+                resources.input.send_key(ping_ponging.ping_pong.key);
+            }
+        }
+        Lifecycle::Ended => {
+            resources.input.send_key_up(key);
+            player.context.reset_normal_action();
+            transition!(player, Player::Idle);
+        }
+    }
+
+    if ping_ponging.stalled_ticks >= tick::scaled(STALL_TICKS) {
+        debug!(target: "player", "screen ping pong stalled, completing direction");
+        resources.input.send_key_up(key);
+        player.context.reset_normal_action();
+        transition!(player, Player::Idle);
+    }
+
+    transition!(player, Player::ScreenPingPonging(ping_ponging));
+}
+
+/// Updates the stalled ticks of `ping_ponging` from the current screen scrolling.
+///
+/// The anchor is replaced whenever the screen scrolled far enough in either direction so that
+/// the next scrolling is still within [`MAX_ANCHOR_SHIFT`].
+#[inline]
+fn update_stalled_ticks(resources: &Resources, ping_ponging: &mut ScreenPingPonging) {
+    let Ok(anchor) = resources.detector().detect_screen_anchor() else {
+        ping_ponging.stalled_ticks += 1;
+        return;
+    };
+    let Some(previous) = ping_ponging.anchor else {
+        ping_ponging.anchor = Some(anchor);
+        return;
+    };
+    let Some(shift) = anchor.shift_from(&previous, MAX_ANCHOR_SHIFT) else {
+        // The screen changed too much (e.g. skill effects) to tell, starts over without stalling
+        ping_ponging.anchor = Some(anchor);
+        return;
+    };
+
+    // The screen scrolls opposite to the player movement
+    let moved = match ping_ponging.ping_pong.direction {
+        PingPongDirection::Left => shift,
+        PingPongDirection::Right => -shift,
+        PingPongDirection::Up | PingPongDirection::Down => unreachable!(),
+    };
+    if moved >= MOVING_SHIFT {
+        ping_ponging.stalled_ticks = 0;
+        ping_ponging.anchor = Some(anchor);
+    } else {
+        ping_ponging.stalled_ticks += 1;
+        if moved <= -MOVING_SHIFT {
+            ping_ponging.anchor = Some(anchor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::{
+        bridge::{KeyKind, MockInput},
+        detect::{MockDetector, SCREEN_ANCHOR_WIDTH},
+        minimap::MinimapIdle,
+        player::{PlayerAction, PlayerContext},
+    };
+
+    fn anchor(offset: usize) -> ScreenAnchor {
+        let mut columns = [0; SCREEN_ANCHOR_WIDTH];
+        for (i, column) in columns.iter_mut().enumerate() {
+            *column = ((i + offset) * 37 % 251) as u8;
+        }
+        ScreenAnchor::new(columns)
+    }
+
+    fn make_keys() -> MockInput {
+        let mut keys = MockInput::new();
+        keys.expect_send_key_up().returning(|_| ());
+        keys.expect_send_key_down().returning(|_| ());
+        keys.expect_send_key().returning(|_| ());
+        keys
+    }
+
+    fn make_player(direction: PingPongDirection) -> PlayerEntity {
+        let ping_pong = PingPong {
+            key: KeyKind::A,
+            direction,
+            ..PingPong::default()
+        };
+        let mut context = PlayerContext::default();
+        context.set_normal_action(None, PlayerAction::PingPong(ping_pong));
+
+        PlayerEntity {
+            state: Player::ScreenPingPonging(ScreenPingPonging::new(ping_pong)),
+            context,
+        }
+    }
+
+    #[test]
+    fn screen_anchor_shift_from_detects_scrolling() {
+        // The content at column i moves to column i + 5 when the screen scrolls right
+        assert_eq!(anchor(0).shift_from(&anchor(5), MAX_ANCHOR_SHIFT), Some(5));
+        assert_eq!(anchor(5).shift_from(&anchor(0), MAX_ANCHOR_SHIFT), Some(-5));
+        assert_eq!(anchor(0).shift_from(&anchor(0), MAX_ANCHOR_SHIFT), Some(0));
+        assert_eq!(
+            ScreenAnchor::new([0; SCREEN_ANCHOR_WIDTH]).shift_from(&anchor(0), MAX_ANCHOR_SHIFT),
+            None
+        );
+    }
+
+    #[test]
+    fn update_screen_ping_ponging_state_started_holds_direction_key() {
+        let mut keys = MockInput::new();
+        keys.expect_send_key_up().with(eq(KeyKind::Up)).once();
+        keys.expect_send_key_up().with(eq(KeyKind::Down)).once();
+        keys.expect_send_key_up().with(eq(KeyKind::Left)).once();
+        keys.expect_send_key_down().with(eq(KeyKind::Right)).once();
+        let mut detector = MockDetector::new();
+        detector
+            .expect_detect_screen_anchor()
+            .once()
+            .returning(|| Ok(anchor(0)));
+        let resources = Resources::new(Some(keys), Some(detector));
+        let mut player = make_player(PingPongDirection::Right);
+
+        update_screen_ping_ponging_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_eq!(
+            player.context.last_known_direction,
+            ActionKeyDirection::Right
+        );
+        assert_matches!(
+            player.state,
+            Player::ScreenPingPonging(ScreenPingPonging {
+                anchor: Some(_),
+                timeout: Timeout { started: true, .. },
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn update_screen_ping_ponging_state_keeps_moving_while_scrolling() {
+        let mut detector = MockDetector::new();
+        let mut offset = 0;
+        detector.expect_detect_screen_anchor().returning(move || {
+            // Moving left scrolls the screen right
+            let anchor = anchor(1000 - offset);
+            offset += 3;
+            Ok(anchor)
+        });
+        let resources = Resources::new(Some(make_keys()), Some(detector));
+        let mut player = make_player(PingPongDirection::Left);
+
+        for _ in 0..tick::scaled(STALL_TICKS) * 2 {
+            update_screen_ping_ponging_state(&resources, &mut player, Minimap::Detecting);
+        }
+
+        assert_matches!(
+            player.state,
+            Player::ScreenPingPonging(ScreenPingPonging {
+                stalled_ticks: 0,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn update_screen_ping_ponging_state_completes_when_screen_stops_scrolling() {
+        let mut detector = MockDetector::new();
+        detector
+            .expect_detect_screen_anchor()
+            .returning(|| Ok(anchor(0)));
+        let resources = Resources::new(Some(make_keys()), Some(detector));
+        let mut player = make_player(PingPongDirection::Left);
+
+        for _ in 0..tick::scaled(STALL_TICKS) {
+            update_screen_ping_ponging_state(&resources, &mut player, Minimap::Detecting);
+            assert_matches!(player.state, Player::ScreenPingPonging(_));
+        }
+        update_screen_ping_ponging_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(player.state, Player::Idle);
+        assert!(!player.context.has_normal_action());
+    }
+
+    #[test]
+    fn update_screen_ping_ponging_state_exits_when_minimap_detected() {
+        let mut keys = MockInput::new();
+        keys.expect_send_key_up().with(eq(KeyKind::Left)).once();
+        let resources = Resources::new(Some(keys), None);
+        let mut player = make_player(PingPongDirection::Left);
+
+        update_screen_ping_ponging_state(
+            &resources,
+            &mut player,
+            Minimap::Idle(MinimapIdle::default()),
+        );
+
+        assert_matches!(player.state, Player::Idle);
+        assert!(!player.context.has_normal_action());
+    }
+}
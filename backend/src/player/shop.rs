@@ -0,0 +1,341 @@
+use super::{Player, Shop, timeout::Timeout};
+use crate::{
+    array::Array,
+    bridge::{KeyKind, MouseKind},
+    ecs::{Resources, transition},
+    player::{
+        PlayerAction, PlayerEntity, next_action,
+        timeout::{Lifecycle, next_timeout_lifecycle},
+        transition_from_action,
+    },
+    tick,
+};
+
+/// The maximum number of items bought at once.
+const MAX_QUANTITY: u32 = 9999;
+
+/// Number of ticks between each key typed for the quantity.
+const TYPE_INTERVAL: u32 = 10;
+
+/// States of buying from an NPC shop.
+#[derive(Debug, Clone, Copy)]
+enum State {
+    /// Opening the shop by talking to the NPC.
+    OpenShop(Timeout),
+    /// Clicking the shop tab with the item.
+    SelectTab(Timeout),
+    /// Double clicking the item to open the quantity prompt.
+    SelectItem(Timeout),
+    /// Typing the quantity and confirming with the key at the index.
+    TypeQuantity(Timeout, usize),
+    /// Closing the shop.
+    Closing(Timeout),
+    /// Terminal state.
+    Completed,
+}
+
+/// The keys typed into the quantity prompt.
+type QuantityKeys = Array<KeyKind, 10>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Shopping {
+    state: State,
+    shop: Shop,
+    keys: QuantityKeys,
+}
+
+impl Shopping {
+    pub fn new(shop: Shop) -> Self {
+        let quantity = shop.quantity.clamp(1, MAX_QUANTITY);
+        let mut keys = QuantityKeys::from_iter([KeyKind::Backspace; 4]);
+        let keys_from_chars = quantity.to_string().chars().map(|char| match char {
+            '0' => KeyKind::Zero,
+            '1' => KeyKind::One,
+            '2' => KeyKind::Two,
+            '3' => KeyKind::Three,
+            '4' => KeyKind::Four,
+            '5' => KeyKind::Five,
+            '6' => KeyKind::Six,
+            '7' => KeyKind::Seven,
+            '8' => KeyKind::Eight,
+            '9' => KeyKind::Nine,
+            _ => unreachable!(),
+        });
+        for key in keys_from_chars {
+            keys.push(key);
+        }
+        keys.push(KeyKind::Enter);
+
+        Self {
+            state: State::OpenShop(Timeout::default()),
+            shop,
+            keys,
+        }
+    }
+}
+
+/// Updates [`Player::Shopping`] contextual state.
+///
+/// The player is expected to be standing next to the NPC. The shop is opened with
+/// [`Shop::open_key`], the item is bought by clicking through the configured screen positions and
+/// typing [`Shop::quantity`], and the shop is closed afterward. Completing also ends the
+/// [`Shop`] detour so that the [`Navigator`] can navigate the player back.
+pub fn update_shopping_state(resources: &Resources, player: &mut PlayerEntity) {
+    let Player::Shopping(mut shopping) = player.state else {
+        panic!("state is not shopping")
+    };
+
+    match shopping.state {
+        State::OpenShop(_) => update_open_shop(resources, &mut shopping),
+        State::SelectTab(_) => update_select_tab(resources, &mut shopping),
+        State::SelectItem(_) => update_select_item(resources, &mut shopping),
+        State::TypeQuantity(_, _) => update_type_quantity(resources, &mut shopping),
+        State::Closing(_) => update_closing(resources, &mut shopping),
+        State::Completed => unreachable!(),
+    }
+
+    let player_next_state = if matches!(shopping.state, State::Completed) {
+        Player::Idle
+    } else {
+        Player::Shopping(shopping)
+    };
+    let is_terminal = matches!(player_next_state, Player::Idle);
+
+    match next_action(&player.context) {
+        Some(PlayerAction::Shop(_)) => {
+            if is_terminal {
+                player.context.shop_detour = None;
+            }
+            transition_from_action!(player, player_next_state, is_terminal)
+        }
+        Some(_) => unreachable!(),
+        None => transition!(player, Player::Idle), // Force cancel if not from action
+    }
+}
+
+fn update_open_shop(resources: &Resources, shopping: &mut Shopping) {
+    let State::OpenShop(timeout) = shopping.state else {
+        panic!("shopping state is not opening shop")
+    };
+
+    match next_timeout_lifecycle(timeout, tick::scaled(30)) {
+        Lifecycle::Started(timeout) => transition!(shopping, State::OpenShop(timeout), {
+            resources.input.send_key(shopping.shop.open_key);
+        }),
+        Lifecycle::Ended => transition!(shopping, State::SelectTab(Timeout::default())),
+        Lifecycle::Updated(timeout) => transition!(shopping, State::OpenShop(timeout)),
+    }
+}
+
+fn update_select_tab(resources: &Resources, shopping: &mut Shopping) {
+    let State::SelectTab(timeout) = shopping.state else {
+        panic!("shopping state is not selecting tab")
+    };
+
+    match next_timeout_lifecycle(timeout, tick::scaled(20)) {
+        Lifecycle::Started(timeout) => transition!(shopping, State::SelectTab(timeout), {
+            let tab = shopping.shop.tab;
+            resources.input.send_mouse(tab.x, tab.y, MouseKind::Click);
+        }),
+        Lifecycle::Ended => transition!(shopping, State::SelectItem(Timeout::default())),
+        Lifecycle::Updated(timeout) => transition!(shopping, State::SelectTab(timeout)),
+    }
+}
+
+fn update_select_item(resources: &Resources, shopping: &mut Shopping) {
+    let State::SelectItem(timeout) = shopping.state else {
+        panic!("shopping state is not selecting item")
+    };
+    let item = shopping.shop.item;
+
+    match next_timeout_lifecycle(timeout, tick::scaled(20)) {
+        Lifecycle::Started(timeout) => transition!(shopping, State::SelectItem(timeout), {
+            resources.input.send_mouse(item.x, item.y, MouseKind::Click);
+        }),
+        Lifecycle::Ended => {
+            transition!(shopping, State::TypeQuantity(Timeout::default(), 0))
+        }
+        Lifecycle::Updated(timeout) => transition!(shopping, State::SelectItem(timeout), {
+            // The second click of the double click
+            if timeout.current == 1 {
+                resources.input.send_mouse(item.x, item.y, MouseKind::Click);
+            }
+        }),
+    }
+}
+
+fn update_type_quantity(resources: &Resources, shopping: &mut Shopping) {
+    let State::TypeQuantity(timeout, index) = shopping.state else {
+        panic!("shopping state is not typing quantity")
+    };
+    let max_timeout = tick::scaled(TYPE_INTERVAL) * (shopping.keys.len() as u32 + 1);
+
+    match next_timeout_lifecycle(timeout, max_timeout) {
+        Lifecycle::Started(timeout) => transition!(shopping, State::TypeQuantity(timeout, index)),
+        Lifecycle::Ended => transition!(shopping, State::Closing(Timeout::default())),
+        Lifecycle::Updated(timeout) => {
+            if timeout.current.is_multiple_of(tick::scaled(TYPE_INTERVAL))
+                && index < shopping.keys.len()
+            {
+                transition!(shopping, State::TypeQuantity(timeout, index + 1), {
+                    resources.input.send_key(shopping.keys[index]);
+                });
+            }
+
+            transition!(shopping, State::TypeQuantity(timeout, index))
+        }
+    }
+}
+
+fn update_closing(resources: &Resources, shopping: &mut Shopping) {
+    let State::Closing(timeout) = shopping.state else {
+        panic!("shopping state is not closing")
+    };
+
+    match next_timeout_lifecycle(timeout, tick::scaled(20)) {
+        Lifecycle::Started(timeout) => transition!(shopping, State::Closing(timeout), {
+            resources.input.send_key(KeyKind::Esc);
+        }),
+        Lifecycle::Ended => transition!(shopping, State::Completed, {
+            // The shop was not opened and the ESC opened the settings instead
+            if resources.detector().detect_esc_settings() {
+                resources.input.send_key(KeyKind::Esc);
+            }
+        }),
+        Lifecycle::Updated(timeout) => transition!(shopping, State::Closing(timeout)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use mockall::{Sequence, predicate::eq};
+    use opencv::core::Point;
+
+    use super::*;
+    use crate::{
+        bridge::MockInput,
+        detect::MockDetector,
+        player::{PlayerContext, timeout::Timeout},
+    };
+
+    fn shop(quantity: u32) -> Shop {
+        Shop {
+            position: Point::new(50, 20),
+            paths_id_index: Some((1, 0)),
+            open_key: KeyKind::Y,
+            tab: Point::new(100, 200),
+            item: Point::new(300, 400),
+            quantity,
+        }
+    }
+
+    #[test]
+    fn shopping_new_types_quantity_then_confirms() {
+        let shopping = Shopping::new(shop(25));
+        assert_eq!(
+            shopping.keys.as_slice(),
+            [
+                KeyKind::Backspace,
+                KeyKind::Backspace,
+                KeyKind::Backspace,
+                KeyKind::Backspace,
+                KeyKind::Two,
+                KeyKind::Five,
+                KeyKind::Enter
+            ]
+        );
+
+        let shopping = Shopping::new(shop(100000));
+        assert_eq!(shopping.keys.len(), 9);
+    }
+
+    #[test]
+    fn update_open_shop_starts_and_sends_open_key() {
+        let mut input = MockInput::default();
+        input.expect_send_key().with(eq(KeyKind::Y)).once();
+        let resources = Resources::new(Some(input), None);
+        let mut shopping = Shopping::new(shop(1));
+
+        update_open_shop(&resources, &mut shopping);
+
+        assert_matches!(
+            shopping.state,
+            State::OpenShop(Timeout { started: true, .. })
+        );
+    }
+
+    #[test]
+    fn update_select_item_double_clicks_item() {
+        let mut input = MockInput::default();
+        input
+            .expect_send_mouse()
+            .with(eq(300), eq(400), eq(MouseKind::Click))
+            .times(2);
+        let resources = Resources::new(Some(input), None);
+        let mut shopping = Shopping::new(shop(1));
+        shopping.state = State::SelectItem(Timeout::default());
+
+        for _ in 0..5 {
+            update_select_item(&resources, &mut shopping);
+        }
+
+        assert_matches!(shopping.state, State::SelectItem(_));
+    }
+
+    #[test]
+    fn update_type_quantity_types_keys_in_order() {
+        let mut sequence = Sequence::new();
+        let mut input = MockInput::default();
+        for key in [
+            KeyKind::Backspace,
+            KeyKind::Backspace,
+            KeyKind::Backspace,
+            KeyKind::Backspace,
+            KeyKind::Three,
+            KeyKind::Enter,
+        ] {
+            input
+                .expect_send_key()
+                .with(eq(key))
+                .once()
+                .in_sequence(&mut sequence);
+        }
+        let resources = Resources::new(Some(input), None);
+        let mut shopping = Shopping::new(shop(3));
+        shopping.state = State::TypeQuantity(Timeout::default(), 0);
+
+        while matches!(shopping.state, State::TypeQuantity(_, _)) {
+            update_type_quantity(&resources, &mut shopping);
+        }
+
+        assert_matches!(shopping.state, State::Closing(_));
+    }
+
+    #[test]
+    fn update_shopping_state_completes_and_ends_detour() {
+        let mut detector = MockDetector::default();
+        detector.expect_detect_esc_settings().return_const(false);
+        let resources = Resources::new(None, Some(detector));
+        let mut shopping = Shopping::new(shop(1));
+        shopping.state = State::Closing(Timeout {
+            current: tick::scaled(20),
+            started: true,
+            ..Default::default()
+        });
+        let mut context = PlayerContext::default();
+        context.set_priority_action(None, PlayerAction::Shop(shop(1)));
+        context.shop_detour = Some(shop(1));
+        let mut player = PlayerEntity {
+            state: Player::Shopping(shopping),
+            context,
+        };
+
+        update_shopping_state(&resources, &mut player);
+
+        assert_matches!(player.state, Player::Idle);
+        assert!(!player.context.has_priority_action());
+        assert!(player.context.shop_detour().is_none());
+    }
+}
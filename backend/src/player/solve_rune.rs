@@ -1,3 +1,5 @@
+use log::info;
+
 use super::{
     Player,
     actions::PlayerAction,
@@ -7,9 +9,17 @@ use crate::{
     bridge::KeyKind,
     detect::{ArrowsCalibrating, ArrowsState},
     ecs::{Resources, transition, transition_if, try_ok_transition},
+    notification::NotificationKind,
     player::{PlayerContext, PlayerEntity, next_action, timeout::Timeout, transition_from_action},
+    tick,
 };
 
+/// Maximum milliseconds for detecting the rune arrows before aborting.
+const SOLVE_BUDGET_MILLIS: u64 = 15_000;
+
+/// Milliseconds to wait before retrying an aborted rune.
+const RETRY_DELAY_MILLIS: u64 = 30_000;
+
 /// Representing the current state of rune solving.
 #[derive(Debug, Clone, Copy)]
 pub enum State {
@@ -19,6 +29,8 @@ pub enum State {
     Calibrating(ArrowsCalibrating, Timeout),
     /// Solves for the rune arrows that possibly include spinning arrows.
     Solving(ArrowsCalibrating, Timeout),
+    /// Waits for the rune arrows submitted remotely after failing to solve too many times.
+    WaitingForKeys(Timeout),
     /// Presses the keys.
     PressKeys(Timeout, [KeyKind; 4], usize),
     /// Terminal stage.
@@ -28,18 +40,32 @@ pub enum State {
 #[derive(Clone, Copy, Debug)]
 pub struct SolvingRune {
     state: State,
+    /// [`Timeout`] for the time budget of detecting the rune arrows.
+    budget_timeout: Timeout,
 }
 
 impl Default for SolvingRune {
     fn default() -> Self {
         Self {
             state: State::Precondition(Timeout::default()),
+            budget_timeout: Timeout::default(),
         }
     }
 }
 
+impl SolvingRune {
+    /// Whether this state is waiting for the rune arrows submitted remotely.
+    pub fn is_waiting_for_keys(&self) -> bool {
+        matches!(self.state, State::WaitingForKeys(_))
+    }
+}
+
 /// Updates the [`Player::SolvingRune`] contextual state.
 ///
+/// If the rune arrows are not detected within [`SOLVE_BUDGET_MILLIS`], solving is aborted and the
+/// rune is only retried after [`RETRY_DELAY_MILLIS`] so that flaky detection does not keep the
+/// player stuck in this state.
+///
 /// Note: This state does not use any [`Task`], so all detections are blocking. But this should be
 /// acceptable for this state.
 pub fn update_solving_rune_state(resources: &Resources, player: &mut PlayerEntity) {
@@ -47,7 +73,9 @@ pub fn update_solving_rune_state(resources: &Resources, player: &mut PlayerEntit
         panic!("state is not solving rune");
     };
 
+    let aborted = update_budget(resources, &mut player.context, &mut solving_rune);
     match solving_rune.state {
+        _ if aborted => (),
         State::Precondition(_) => {
             update_precondition(resources, &player.context, &mut solving_rune)
         }
@@ -57,11 +85,14 @@ pub fn update_solving_rune_state(resources: &Resources, player: &mut PlayerEntit
             player.context.config.interact_key,
         ),
         State::Solving(_, _) => update_solving(resources, &mut solving_rune),
+        State::WaitingForKeys(_) => {
+            update_waiting_for_keys(resources, &mut player.context, &mut solving_rune)
+        }
         State::PressKeys(_, _, _) => update_press_keys(resources, &mut solving_rune),
         State::Completed => unreachable!(),
     }
 
-    let player_next_state = if matches!(solving_rune.state, State::Completed) {
+    let player_next_state = if aborted || matches!(solving_rune.state, State::Completed) {
         Player::Idle
     } else {
         Player::SolvingRune(solving_rune)
@@ -70,7 +101,7 @@ pub fn update_solving_rune_state(resources: &Resources, player: &mut PlayerEntit
     match next_action(&player.context) {
         Some(PlayerAction::SolveRune) => {
             let is_terminal = matches!(player_next_state, Player::Idle);
-            if is_terminal {
+            if is_terminal && !aborted {
                 player.context.start_validating_rune();
             }
             transition_from_action!(player, player_next_state, is_terminal)
@@ -80,6 +111,38 @@ pub fn update_solving_rune_state(resources: &Resources, player: &mut PlayerEntit
     }
 }
 
+/// Updates the time budget of detecting the rune arrows.
+///
+/// Waiting for the remotely submitted arrows and pressing the keys are not limited by the budget.
+/// Returns `true` if solving is aborted because the budget is exceeded.
+fn update_budget(
+    resources: &Resources,
+    player_context: &mut PlayerContext,
+    solving_rune: &mut SolvingRune,
+) -> bool {
+    if !matches!(
+        solving_rune.state,
+        State::Precondition(_) | State::Calibrating(_, _) | State::Solving(_, _)
+    ) {
+        return false;
+    }
+
+    match next_timeout_lifecycle(
+        solving_rune.budget_timeout,
+        tick::ticks_from_millis(SOLVE_BUDGET_MILLIS),
+    ) {
+        Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
+            solving_rune.budget_timeout = timeout;
+            false
+        }
+        Lifecycle::Ended => {
+            info!(target: "rune", "solving exceeded time budget, retrying later");
+            player_context.abort_solving_rune(resources.tick, RETRY_DELAY_MILLIS);
+            true
+        }
+    }
+}
+
 fn update_precondition(
     resources: &Resources,
     player_context: &PlayerContext,
@@ -89,13 +152,18 @@ fn update_precondition(
         panic!("solving rune state is not precondition")
     };
 
-    match next_timeout_lifecycle(timeout, 15) {
+    match next_timeout_lifecycle(timeout, tick::scaled(15)) {
         Lifecycle::Ended => {
             transition_if!(
                 solving_rune,
-                State::Calibrating(ArrowsCalibrating::default(), Timeout::default()),
                 State::Precondition(timeout),
-                player_context.is_stationary && resources.input.all_keys_cleared()
+                !player_context.is_stationary || !resources.input.all_keys_cleared()
+            );
+            transition_if!(
+                solving_rune,
+                State::WaitingForKeys(Timeout::default()),
+                State::Calibrating(ArrowsCalibrating::default(), Timeout::default()),
+                player_context.should_request_rune_keys()
             )
         }
         Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
@@ -116,7 +184,7 @@ fn update_calibrating(
         panic!("solving rune state is not finding region")
     };
 
-    match next_timeout_lifecycle(timeout, COOLDOWN_AND_SOLVE_TIMEOUT) {
+    match next_timeout_lifecycle(timeout, tick::scaled(COOLDOWN_AND_SOLVE_TIMEOUT)) {
         Lifecycle::Started(timeout) => {
             transition!(solving_rune, State::Calibrating(calibrating, timeout), {
                 resources.input.send_key(interact_key);
@@ -125,7 +193,7 @@ fn update_calibrating(
 
         Lifecycle::Ended => transition!(solving_rune, State::Completed),
         Lifecycle::Updated(timeout) => {
-            if timeout.current.is_multiple_of(SOLVE_INTERVAL) {
+            if timeout.current.is_multiple_of(tick::scaled(SOLVE_INTERVAL)) {
                 let arrows_state = try_ok_transition!(
                     solving_rune,
                     State::Calibrating(ArrowsCalibrating::default(), timeout),
@@ -150,7 +218,7 @@ fn update_solving(resources: &Resources, solving_rune: &mut SolvingRune) {
         panic!("solving rune state is not solving")
     };
 
-    match next_timeout_lifecycle(timeout, 150) {
+    match next_timeout_lifecycle(timeout, tick::scaled(150)) {
         Lifecycle::Started(timeout) => {
             transition!(solving_rune, State::Solving(calibrating, timeout))
         }
@@ -180,6 +248,39 @@ fn update_solving(resources: &Resources, solving_rune: &mut SolvingRune) {
     }
 }
 
+fn update_waiting_for_keys(
+    resources: &Resources,
+    player_context: &mut PlayerContext,
+    solving_rune: &mut SolvingRune,
+) {
+    let State::WaitingForKeys(timeout) = solving_rune.state else {
+        panic!("solving rune state is not waiting for keys")
+    };
+    let wait_ticks = tick::ticks_from_millis(player_context.config.remote_rune_solving.wait_millis);
+
+    match next_timeout_lifecycle(timeout, wait_ticks) {
+        Lifecycle::Started(timeout) => {
+            transition!(solving_rune, State::WaitingForKeys(timeout), {
+                // Discards any arrows submitted before waiting
+                let _ = player_context.take_rune_keys();
+                resources.input.send_key(player_context.config.interact_key);
+                let _ = resources
+                    .notification
+                    .schedule_notification(NotificationKind::RuneArrowsRequested);
+            })
+        }
+        Lifecycle::Ended => transition!(solving_rune, State::Completed),
+        Lifecycle::Updated(timeout) => {
+            if let Some(keys) = player_context.take_rune_keys() {
+                info!(target: "rune", "pressing remotely submitted arrows {keys:?}");
+                transition!(solving_rune, State::PressKeys(Timeout::default(), keys, 0));
+            }
+
+            transition!(solving_rune, State::WaitingForKeys(timeout));
+        }
+    }
+}
+
 fn update_press_keys(resources: &Resources, solving_rune: &mut SolvingRune) {
     const PRESS_KEY_INTERVAL: u32 = 8;
 
@@ -187,7 +288,7 @@ fn update_press_keys(resources: &Resources, solving_rune: &mut SolvingRune) {
         panic!("solving rune state is not pressing keys")
     };
 
-    match next_timeout_lifecycle(timeout, PRESS_KEY_INTERVAL) {
+    match next_timeout_lifecycle(timeout, tick::scaled(PRESS_KEY_INTERVAL)) {
         Lifecycle::Started(timeout) => {
             transition!(solving_rune, State::PressKeys(timeout, keys, key_index), {
                 resources.input.send_key(keys[key_index]);
@@ -232,6 +333,7 @@ mod tests {
                 started: true,
                 ..Default::default()
             }),
+            budget_timeout: Timeout::default(),
         };
         let mut player = PlayerEntity {
             state: Player::SolvingRune(solving_rune),
@@ -245,11 +347,42 @@ mod tests {
         assert_matches!(
             player.state,
             Player::SolvingRune(SolvingRune {
-                state: State::Calibrating(_, _)
+                state: State::Calibrating(_, _),
+                ..
             })
         );
     }
 
+    #[test]
+    fn update_solving_rune_aborts_when_budget_exceeded() {
+        let resources = Resources::new(None, None);
+        let solving_rune = SolvingRune {
+            state: State::Precondition(Timeout::default()),
+            budget_timeout: Timeout {
+                current: tick::ticks_from_millis(SOLVE_BUDGET_MILLIS),
+                started: true,
+                ..Default::default()
+            },
+        };
+        let mut player = PlayerEntity {
+            state: Player::SolvingRune(solving_rune),
+            context: PlayerContext::default(),
+        };
+        player.context.priority_action = Some(PlayerAction::SolveRune);
+
+        update_solving_rune_state(&resources, &mut player);
+
+        assert_matches!(player.state, Player::Idle);
+        assert!(!player.context.has_priority_action());
+        assert!(!player.context.is_validating_rune());
+        assert!(!player.context.can_retry_solving_rune(resources.tick));
+        assert!(
+            player
+                .context
+                .can_retry_solving_rune(tick::ticks_from_millis(RETRY_DELAY_MILLIS) as u64)
+        );
+    }
+
     #[test]
     fn update_calibrating_to_solving_on_calibrating() {
         let mut detector = MockDetector::default();
@@ -266,6 +399,7 @@ mod tests {
                     ..Default::default()
                 },
             ),
+            budget_timeout: Timeout::default(),
         };
 
         update_calibrating(&resources, &mut solving_rune, KeyKind::A);
@@ -299,6 +433,7 @@ mod tests {
                     ..Default::default()
                 },
             ),
+            budget_timeout: Timeout::default(),
         };
 
         update_calibrating(&resources, &mut solving_rune, KeyKind::A);
@@ -321,6 +456,7 @@ mod tests {
                     ..Default::default()
                 },
             ),
+            budget_timeout: Timeout::default(),
         };
 
         update_solving(&resources, &mut solving_rune);
@@ -343,6 +479,7 @@ mod tests {
                     ..Default::default()
                 },
             ),
+            budget_timeout: Timeout::default(),
         };
 
         update_solving(&resources, &mut solving_rune);
@@ -373,6 +510,7 @@ mod tests {
                     ..Default::default()
                 },
             ),
+            budget_timeout: Timeout::default(),
         };
 
         update_solving(&resources, &mut solving_rune);
@@ -391,11 +529,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn update_waiting_for_keys_to_press_keys_on_submitted() {
+        let resources = Resources::new(None, None);
+        let mut context = PlayerContext::default();
+        let mut solving_rune = SolvingRune {
+            state: State::WaitingForKeys(Timeout {
+                started: true,
+                ..Default::default()
+            }),
+            budget_timeout: Timeout::default(),
+        };
+
+        update_waiting_for_keys(&resources, &mut context, &mut solving_rune);
+
+        assert!(solving_rune.is_waiting_for_keys());
+
+        context.submit_rune_keys([KeyKind::Up, KeyKind::Down, KeyKind::Left, KeyKind::Right]);
+        update_waiting_for_keys(&resources, &mut context, &mut solving_rune);
+
+        assert_matches!(
+            solving_rune.state,
+            State::PressKeys(
+                _,
+                [KeyKind::Up, KeyKind::Down, KeyKind::Left, KeyKind::Right],
+                0
+            )
+        );
+    }
+
     #[test]
     fn update_press_keys_to_completed_after_all_keys_sent() {
         let expected_keys = [KeyKind::A, KeyKind::S, KeyKind::D, KeyKind::F];
         let mut solving_rune = SolvingRune {
             state: State::PressKeys(Timeout::default(), expected_keys, 0),
+            budget_timeout: Timeout::default(),
         };
 
         for idx in 0..expected_keys.len() {
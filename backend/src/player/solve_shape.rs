@@ -9,6 +9,7 @@ use crate::{
         timeout::{Lifecycle, Timeout, next_timeout_lifecycle},
         transition_from_action,
     },
+    tick,
     tracker::{ByteTracker, Detection, STrack},
 };
 
@@ -128,7 +129,7 @@ fn update_solving(
         );
     }
 
-    match next_timeout_lifecycle(timeout, 545) {
+    match next_timeout_lifecycle(timeout, tick::scaled(545)) {
         Lifecycle::Ended => transition!(solving_shape, State::Completed),
         Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
             transition!(solving_shape, State::Solving(timeout), {
@@ -1,11 +1,11 @@
 use super::{
     AutoMob, Player, PlayerAction,
     actions::next_action,
-    timeout::{Lifecycle, Timeout, next_timeout_lifecycle},
+    timeout::{Lifecycle, Timeout, next_timeout_lifecycle_by},
 };
 use crate::{
     Position,
-    ecs::{transition, transition_if},
+    ecs::{Resources, transition, transition_if},
     player::{PlayerEntity, transition_from_action},
 };
 
@@ -19,8 +19,16 @@ use crate::{
 ///
 /// If this state timeout in auto mob with terminal state, it will perform
 /// auto mob reachable `y` solidifying if needed.
-pub fn update_stalling_state(player: &mut PlayerEntity, timeout: Timeout, max_timeout: u32) {
-    let next_state = match next_timeout_lifecycle(timeout, max_timeout) {
+///
+/// The timeout advances by [`Resources::tick_step`] so that stalling lasts the same duration while
+/// the main loop is throttled.
+pub fn update_stalling_state(
+    resources: &Resources,
+    player: &mut PlayerEntity,
+    timeout: Timeout,
+    max_timeout: u32,
+) {
+    let next_state = match next_timeout_lifecycle_by(timeout, max_timeout, resources.tick_step) {
         Lifecycle::Started(timeout) => Player::Stalling(timeout, max_timeout),
         Lifecycle::Ended => player
             .context
@@ -51,7 +59,10 @@ pub fn update_stalling_state(player: &mut PlayerEntity, timeout: Timeout, max_ti
         Some(PlayerAction::PingPong(_) | PlayerAction::Key(_) | PlayerAction::Move(_)) => {
             transition_from_action!(player, next_state, is_terminal);
         }
-        Some(PlayerAction::SolveRune) | None => transition!(player, next_state),
+        Some(PlayerAction::SolveRune | PlayerAction::EnterPortal(_) | PlayerAction::Shop(_))
+        | None => {
+            transition!(player, next_state)
+        }
         Some(_) => unreachable!(),
     }
 }
@@ -1,31 +1,49 @@
-use std::{collections::HashMap, range::Range};
+use std::{collections::HashMap, mem, range::Range, sync::Arc};
 
 use anyhow::Result;
 use log::{debug, info};
 use opencv::core::{Point, Rect};
 
 use super::{
-    DOUBLE_JUMP_THRESHOLD, JUMP_THRESHOLD, MOVE_TIMEOUT, Player, PlayerAction,
+    DOUBLE_JUMP_THRESHOLD, JUMP_THRESHOLD, MOVE_TIMEOUT, MovementCalibration, Player, PlayerAction,
+    adjust::ADJUSTING_MEDIUM_THRESHOLD,
+    climb::MAX_ROPES_COUNT,
+    danger::MAX_DANGER_ZONES_COUNT,
+    dismiss_popup::MAX_LEVEL_UP_AUTO_ASSIGN_KEYS_COUNT,
     double_jump::DOUBLE_JUMP_AUTO_MOB_THRESHOLD,
-    fall::FALLING_THRESHOLD,
+    exp::ExpRate,
+    fall::{FALLING_THRESHOLD, TELEPORT_FALL_THRESHOLD},
+    panic::has_panicking_keys,
+    risky::{RiskyAction, RiskyActionLimited, RiskyActionLimiter},
+    teleporting::{TELEPORTING_AUTO_MOB_THRESHOLD, TELEPORTING_THRESHOLD},
     timeout::{Lifecycle, Timeout, next_timeout_lifecycle},
+    trail::{PositionTrail, StuckPattern},
+    unstuck::UnstuckStage,
+    up_jump::{TELEPORT_WITH_JUMP_THRESHOLD, UP_JUMP_AND_TELEPORT_THRESHOLD},
 };
 use crate::{
-    ActionKeyDirection,
+    ActionKeyDirection, DangerZone, ExpRateAlert, KeyboardLayout, MobDetection, PlayerMarker,
+    RemoteRuneSolving, RiskyActionCounts, RiskyActionLimits, Rope, UnstuckLimits,
     array::Array,
     bridge::{KeyKind, MouseKind},
     buff::{Buff, BuffEntities, BuffKind},
     ecs::Resources,
     minimap::Minimap,
     notification::NotificationKind,
-    player::{AUTO_MOB_USE_KEY_X_THRESHOLD, AUTO_MOB_USE_KEY_Y_THRESHOLD, AutoMob, Booster},
-    run::FPS,
+    player::{AUTO_MOB_USE_KEY_X_THRESHOLD, AUTO_MOB_USE_KEY_Y_THRESHOLD, AutoMob, Booster, Shop},
     task::{Task, Update, update_detection_task},
+    tick::{self, tick_rate, to_base_tick_rate_speed},
     tracker::ByteTracker,
 };
 
 const STATIONARY_TIMEOUT: u32 = MOVE_TIMEOUT + 1;
 
+/// Milliseconds interval to update the EXP.
+const EXP_UPDATE_MILLIS: u64 = 10000;
+
+/// Milliseconds in an hour for converting the EXP gain rate.
+const EXP_RATE_HOUR_MILLIS: u64 = 3_600_000;
+
 /// The maximum number of times rune solving can fail before transition to
 /// [`Player::CashShopThenExit`].
 const MAX_RUNE_FAILED_COUNT: u32 = 8;
@@ -50,6 +68,9 @@ const VERTICAL_MOVEMENT_REPEAT_COUNT: u32 = 8;
 /// always a y that has platform(s).
 const AUTO_MOB_REACHABLE_Y_SOLIDIFY_COUNT: u32 = 4;
 
+/// Maximum number of solidified reachable y(s) persisted to the map.
+pub const MAX_AUTO_MOB_REACHABLE_YS_COUNT: usize = 32;
+
 /// The number of times an auto-mob position has made the player aborted the auto-mob action.
 ///
 /// If the count is reached, subsequent auto-mob position falling within the x range
@@ -77,8 +98,9 @@ const AUTO_MOB_VERTICAL_MOVEMENT_REPEAT_COUNT: u32 = 3;
 /// without changing position.
 const UNSTUCK_COUNT_THRESHOLD: u32 = 6;
 
-/// The number of times [`Player::Unstucking`] can be transitioned to before entering GAMBA MODE.
-const UNSTUCK_GAMBA_MODE_COUNT: u32 = 3;
+/// Milliseconds without transitioning to [`Player::Unstucking`] before the escalating unstucking
+/// stages start over from the first stage.
+const UNSTUCK_STAGE_RESET_MILLIS: u64 = 60000;
 
 /// The number of samples to store for approximating velocity.
 const VELOCITY_SAMPLES: usize = MOVE_TIMEOUT as usize;
@@ -107,10 +129,13 @@ impl Quadrant {
 pub enum LastMovement {
     Adjusting,
     DoubleJumping,
+    Teleporting,
+    Gliding,
     Falling,
     Grappling,
     UpJumping,
     Jumping,
+    Climbing,
 }
 
 #[derive(Debug, Copy, Clone, Default)]
@@ -176,6 +201,49 @@ pub struct PlayerConfiguration {
     pub disable_adjusting: bool,
     /// Whether to disable teleportation in [`Player::Falling`].
     pub disable_teleport_on_fall: bool,
+    /// Whether to use [`Player::Teleporting`] instead of [`Player::DoubleJumping`] for moving
+    /// horizontally.
+    ///
+    /// Only applicable when [`Self::teleport_key`] is set.
+    pub horizontal_teleporting: bool,
+    /// Whether to use [`Player::Gliding`] instead of [`Player::DoubleJumping`] for moving
+    /// horizontally.
+    ///
+    /// Only applicable when [`Self::flight_key`] is set.
+    pub horizontal_flight: bool,
+
+    /// Minimum x distance from the destination required to perform a double jump.
+    ///
+    /// Rescaled from [`DOUBLE_JUMP_THRESHOLD`] when the character movement is calibrated.
+    pub double_jump_threshold: i32,
+    /// Minimum x distance from the destination required to perform a double jump in auto mob.
+    ///
+    /// Rescaled from [`DOUBLE_JUMP_AUTO_MOB_THRESHOLD`] when the character movement is calibrated.
+    pub double_jump_auto_mob_threshold: i32,
+    /// Minimum x distance from the destination required to walk.
+    ///
+    /// Rescaled from [`ADJUSTING_MEDIUM_THRESHOLD`] when the character movement is calibrated.
+    pub adjusting_medium_threshold: i32,
+    /// Minimum x distance from the destination required to teleport horizontally.
+    ///
+    /// Rescaled from [`TELEPORTING_THRESHOLD`] when the teleport distance is calibrated.
+    pub teleporting_threshold: i32,
+    /// Minimum x distance from the destination required to teleport horizontally in auto mob.
+    ///
+    /// Rescaled from [`TELEPORTING_AUTO_MOB_THRESHOLD`] when the teleport distance is calibrated.
+    pub teleporting_auto_mob_threshold: i32,
+    /// Minimum y distance from the destination required to up jump using teleport with jump.
+    ///
+    /// Rescaled from [`TELEPORT_WITH_JUMP_THRESHOLD`] when the teleport distance is calibrated.
+    pub teleport_with_jump_threshold: i32,
+    /// Minimum y distance from the destination required to up jump and then teleport.
+    ///
+    /// Rescaled from [`UP_JUMP_AND_TELEPORT_THRESHOLD`] when the teleport distance is calibrated.
+    pub up_jump_and_teleport_threshold: i32,
+    /// Maximum y distance from the destination allowed to teleport down instead of falling.
+    ///
+    /// Rescaled from [`TELEPORT_FALL_THRESHOLD`] when the teleport distance is calibrated.
+    pub teleport_fall_threshold: i32,
 
     /// Enables platform pathing for rune.
     pub rune_platforms_pathing: bool,
@@ -190,8 +258,32 @@ pub struct PlayerConfiguration {
     ///
     /// TODO: This shouldn't be here...
     pub auto_mob_platforms_bound: bool,
+    /// The x distance from both platform edges that destinations are kept away from.
+    pub platform_edge_margin: i32,
     pub auto_mob_use_key_when_pathing: bool,
     pub auto_mob_use_key_when_pathing_update_millis: u64,
+    /// Pauses auto mobbing to walk over nearby loots.
+    pub auto_mob_loot_pickup: bool,
+    /// Maximum x distance from the player for a loot to be picked up with `0` for no limit.
+    pub auto_mob_loot_pickup_radius: i32,
+    /// Continues horizontal ping pong in [`Player::ScreenPingPonging`] when the minimap cannot be
+    /// detected.
+    pub ping_pong_screen_fallback: bool,
+    /// Whether the current map has an attached navigation path to bring the player back after
+    /// going to town.
+    pub can_navigate_back: bool,
+
+    /// Ropes or ladders of the current map that can be climbed.
+    pub ropes: Array<Rope, MAX_ROPES_COUNT>,
+
+    /// Damaging floor regions of the current map to avoid standing in.
+    pub danger_zones: Array<DangerZone, MAX_DANGER_ZONES_COUNT>,
+    /// Solidified auto-mobbing reachable y(s) persisted from previous sessions.
+    pub auto_mob_reachable_ys: Array<i32, MAX_AUTO_MOB_REACHABLE_YS_COUNT>,
+    /// The custom minimap player marker of the current map.
+    ///
+    /// The default player marker detection is used if [`None`].
+    pub player_marker: Option<PlayerMarker>,
 
     /// The interact key.
     pub interact_key: KeyKind,
@@ -199,10 +291,20 @@ pub struct PlayerConfiguration {
     pub grappling_key: Option<KeyKind>,
     /// The teleport key with [`None`] indicating double jump.
     pub teleport_key: Option<KeyKind>,
+    /// The key held to glide in [`Player::Gliding`].
+    pub flight_key: Option<KeyKind>,
     /// The jump key.
     ///
     /// Replaces the previously default [`KeyKind::Space`] key.
     pub jump_key: KeyKind,
+    /// The key for moving left, replacing [`KeyKind::Left`].
+    pub left_key: KeyKind,
+    /// The key for moving right, replacing [`KeyKind::Right`].
+    pub right_key: KeyKind,
+    /// The key for moving up, replacing [`KeyKind::Up`].
+    pub up_key: KeyKind,
+    /// The key for moving down, replacing [`KeyKind::Down`].
+    pub down_key: KeyKind,
     /// The up jump key with [`None`] indicating composite jump (Up arrow + Double Space).
     pub up_jump_key: Option<KeyKind>,
     /// The cash shop key.
@@ -223,6 +325,22 @@ pub struct PlayerConfiguration {
     pub generic_booster_key: KeyKind,
     /// HEXA Booster key.
     pub hexa_booster_key: KeyKind,
+    /// Keys pressed in order to auto-assign points before dismissing level up popups.
+    pub level_up_auto_assign_keys: Array<KeyKind, MAX_LEVEL_UP_AUTO_ASSIGN_KEYS_COUNT>,
+    /// Number of attempts of each escalating unstucking stage.
+    pub unstuck_limits: UnstuckLimits,
+    /// Alerts when the EXP gain rate drops.
+    pub exp_rate_alert: ExpRateAlert,
+    /// Requests the rune arrows remotely after failing to solve runes.
+    pub remote_rune_solving: RemoteRuneSolving,
+    /// Whether changing channel should be avoided (e.g. channels are unstable around reset).
+    pub avoid_changing_channel: bool,
+    /// Limits how often risky actions such as changing channel can be used.
+    pub risky_action_limits: RiskyActionLimits,
+    /// The keyboard layout used for typing characters in [`Player::Chatting`].
+    pub keyboard_layout: KeyboardLayout,
+    /// Whether [`Player::Chatting`] pastes the content through the clipboard.
+    pub chat_clipboard_paste: bool,
 }
 
 impl Default for PlayerConfiguration {
@@ -232,6 +350,16 @@ impl Default for PlayerConfiguration {
             disable_double_jumping: false,
             disable_adjusting: false,
             disable_teleport_on_fall: false,
+            horizontal_teleporting: false,
+            horizontal_flight: false,
+            double_jump_threshold: DOUBLE_JUMP_THRESHOLD,
+            double_jump_auto_mob_threshold: DOUBLE_JUMP_AUTO_MOB_THRESHOLD,
+            adjusting_medium_threshold: ADJUSTING_MEDIUM_THRESHOLD,
+            teleporting_threshold: TELEPORTING_THRESHOLD,
+            teleporting_auto_mob_threshold: TELEPORTING_AUTO_MOB_THRESHOLD,
+            teleport_with_jump_threshold: TELEPORT_WITH_JUMP_THRESHOLD,
+            up_jump_and_teleport_threshold: UP_JUMP_AND_TELEPORT_THRESHOLD,
+            teleport_fall_threshold: TELEPORT_FALL_THRESHOLD,
             up_jump_is_flight: false,
             up_jump_specific_key_should_jump: false,
             rune_platforms_pathing: false,
@@ -239,12 +367,26 @@ impl Default for PlayerConfiguration {
             auto_mob_platforms_pathing: false,
             auto_mob_platforms_pathing_up_jump_only: false,
             auto_mob_platforms_bound: false,
+            platform_edge_margin: 0,
             auto_mob_use_key_when_pathing: false,
             auto_mob_use_key_when_pathing_update_millis: 0,
+            auto_mob_loot_pickup: false,
+            auto_mob_loot_pickup_radius: 0,
+            ping_pong_screen_fallback: false,
+            can_navigate_back: false,
+            ropes: Array::new(),
+            danger_zones: Array::new(),
+            auto_mob_reachable_ys: Array::new(),
+            player_marker: None,
             interact_key: KeyKind::A,
             grappling_key: None,
             teleport_key: None,
+            flight_key: None,
             jump_key: KeyKind::A,
+            left_key: KeyKind::Left,
+            right_key: KeyKind::Right,
+            up_key: KeyKind::Up,
+            down_key: KeyKind::Down,
             up_jump_key: None,
             cash_shop_key: None,
             familiar_key: None,
@@ -255,6 +397,14 @@ impl Default for PlayerConfiguration {
             update_health_millis: None,
             generic_booster_key: KeyKind::A,
             hexa_booster_key: KeyKind::A,
+            level_up_auto_assign_keys: Array::new(),
+            unstuck_limits: UnstuckLimits::default(),
+            exp_rate_alert: ExpRateAlert::default(),
+            remote_rune_solving: RemoteRuneSolving::default(),
+            avoid_changing_channel: false,
+            risky_action_limits: RiskyActionLimits::default(),
+            keyboard_layout: KeyboardLayout::default(),
+            chat_clipboard_paste: false,
         }
     }
 }
@@ -267,6 +417,10 @@ impl Default for PlayerConfiguration {
 #[derive(Debug, Default)]
 pub struct PlayerContext {
     pub config: PlayerConfiguration,
+    /// The mob detection of the current map used by auto mobbing.
+    ///
+    /// This is not part of [`PlayerConfiguration`] to keep it [`Copy`].
+    pub mob_detection: Arc<MobDetection>,
 
     /// Optional id of current normal action provided by [`Rotator`].
     normal_action_id: Option<u32>,
@@ -287,6 +441,12 @@ pub struct PlayerContext {
     health_bar: Option<Rect>,
     /// The task for the health bar.
     health_bar_task: Option<Task<Result<Rect>>>,
+    /// The EXP gain of the current session.
+    exp_rate: ExpRate,
+    /// The uses of risky actions limited by [`PlayerConfiguration::risky_action_limits`].
+    risky_actions: RiskyActionLimiter,
+    /// The task to update EXP.
+    exp_task: Option<Task<Result<u64>>>,
 
     /// Track if the player moved within a specified ticks to determine if the player is
     /// stationary.
@@ -338,6 +498,9 @@ pub struct PlayerContext {
     ///
     /// A y is reachable if there is a platform the player can stand on.
     auto_mob_reachable_y_map: HashMap<i32, u32>,
+    /// Whether a y in [`Self::auto_mob_reachable_y_map`] has been solidified or unsolidified
+    /// since the last [`Self::take_auto_mob_reachable_ys`].
+    auto_mob_reachable_ys_changed: bool,
     /// Tracks a map of reachable y to x ranges that can be ignored.
     ///
     /// This will help auto-mobbing ignores positions that are known to be not reachable.
@@ -355,13 +518,28 @@ pub struct PlayerContext {
     ///
     /// Resets when a limit is reached (for unstucking) or position did change.
     unstuck_count: u32,
-    /// The number of times player transtioned to [`Player::Unstucking`].
+    /// The current escalating unstucking stage and the number of attempts in that stage.
     ///
-    /// Resets when threshold reached or position changed.
-    unstuck_transitioned_count: u32,
+    /// Unlike [`Self::unstuck_count`], this does not reset when the position changed but only
+    /// after a while without unstucking so that a bad spot cannot loop forever.
+    unstuck_stage: Option<(UnstuckStage, u32)>,
+    /// The tick of the last transition to [`Player::Unstucking`].
+    unstuck_stage_tick: u64,
+    /// Whether all escalating unstucking stages have been exhausted since the last take.
+    unstuck_exhausted: bool,
+    /// The rolling history of the player positions for recognizing [`StuckPattern`]s.
+    position_trail: PositionTrail,
+    /// The last [`StuckPattern`] recognized from [`Self::position_trail`].
+    last_stuck_pattern: Option<StuckPattern>,
 
     /// The number of times [`Player::SolvingRune`] failed.
     rune_failed_count: u32,
+    /// The number of runes solved since the last [`Self::take_runes_solved_count`].
+    runes_solved_count: u32,
+    /// The rune arrows submitted remotely while [`Player::SolvingRune`] is waiting for them.
+    rune_remote_keys: Option<[KeyKind; 4]>,
+    /// The number of channel changes since the last [`Self::take_channels_changed_count`].
+    channels_changed_count: u32,
     /// Indicates the state will be transitioned to [`Player::CashShopThenExit`] in the next tick.
     pub(super) rune_cash_shop: bool,
     /// [`Timeout`] for validating whether the rune is solved.
@@ -369,6 +547,8 @@ pub struct PlayerContext {
     /// This is [`Some`] when [`Player::SolvingRune`] successfully detects the rune
     /// and sends all the keys.
     rune_validate_timeout: Option<Timeout>,
+    /// The tick at which solving rune can be retried after an aborted [`Player::SolvingRune`].
+    rune_retry_tick: Option<u64>,
     shape_tracker: Option<ByteTracker>,
 
     /// A state to return to after stalling.
@@ -384,7 +564,11 @@ pub struct PlayerContext {
 
     /// Stores a list of [`(Point, u64)`] pair samples for approximating velocity.
     velocity_samples: Array<(Point, u64), VELOCITY_SAMPLES>,
-    /// Approximated player velocity.
+    /// Approximated player velocity in pixels per tick at
+    /// [`BASE_TICK_RATE`](crate::tick::BASE_TICK_RATE).
+    ///
+    /// It is independent of the current tick rate so that velocity thresholds tuned at the base
+    /// tick rate hold at other tick rates.
     pub(super) velocity: (f32, f32),
 
     /// The number of times [`Player::UsingBooster`] for Generic Booster failed.
@@ -394,6 +578,14 @@ pub struct PlayerContext {
 
     /// The number of times [`Player::FamiliarsSwapping`] failed.
     familiars_swap_failed_count: u32,
+
+    /// The [`Shop`] on another map the [`Navigator`] is navigating the player to.
+    ///
+    /// Clears when [`Player::Shopping`] completes or the actions are aborted.
+    pub(super) shop_detour: Option<Shop>,
+
+    /// The result of the last completed [`Player::Calibrating`].
+    pub(super) movement_calibration: Option<MovementCalibration>,
 }
 
 impl PlayerContext {
@@ -404,6 +596,10 @@ impl PlayerContext {
     pub fn reset(&mut self) {
         *self = PlayerContext {
             config: self.config,
+            mob_detection: self.mob_detection.clone(),
+            risky_actions: mem::take(&mut self.risky_actions),
+            // The detour spans multiple maps
+            shop_detour: self.shop_detour,
             reset_to_idle_next_update: true,
             ..PlayerContext::default()
         };
@@ -531,7 +727,7 @@ impl PlayerContext {
     }
 
     pub(super) fn reset_shape_tracker(&mut self) {
-        self.shape_tracker = Some(ByteTracker::new(FPS));
+        self.shape_tracker = Some(ByteTracker::new(tick_rate()));
     }
 
     /// Starts validating whether the rune is solved.
@@ -540,6 +736,40 @@ impl PlayerContext {
         self.rune_validate_timeout = Some(Timeout::default());
     }
 
+    /// Whether the rune arrows should be requested remotely instead of detected.
+    #[inline]
+    pub(super) fn should_request_rune_keys(&self) -> bool {
+        let remote = self.config.remote_rune_solving;
+        remote.enabled && self.rune_failed_count >= remote.failed_count.max(1)
+    }
+
+    /// Submits the rune arrows `keys` for [`Player::SolvingRune`] waiting for them.
+    #[inline]
+    pub fn submit_rune_keys(&mut self, keys: [KeyKind; 4]) {
+        self.rune_remote_keys = Some(keys);
+    }
+
+    /// Takes the rune arrows submitted by [`Self::submit_rune_keys`].
+    #[inline]
+    pub(super) fn take_rune_keys(&mut self) -> Option<[KeyKind; 4]> {
+        self.rune_remote_keys.take()
+    }
+
+    /// Aborts solving rune at `tick` so that it can only be retried after `retry_millis`.
+    ///
+    /// The abort is counted as a failed solve.
+    pub(super) fn abort_solving_rune(&mut self, tick: u64, retry_millis: u64) {
+        self.track_rune_fail_count();
+        self.rune_retry_tick = Some(tick + tick::ticks_from_millis(retry_millis) as u64);
+    }
+
+    /// Whether solving rune can be retried at `tick` after the last aborted solve.
+    #[inline]
+    pub fn can_retry_solving_rune(&self, tick: u64) -> bool {
+        self.rune_retry_tick
+            .is_none_or(|retry_tick| tick >= retry_tick)
+    }
+
     /// Whether the player is validating whether the rune is solved.
     #[inline]
     pub fn is_validating_rune(&self) -> bool {
@@ -558,10 +788,14 @@ impl PlayerContext {
         !self.has_priority_action() && matches!(self.normal_action, Some(PlayerAction::AutoMob(_)))
     }
 
-    /// Whether there is only ping pong action.
+    /// Whether there is only horizontal ping pong action.
     #[inline]
-    pub(super) fn has_ping_pong_action_only(&self) -> bool {
-        !self.has_priority_action() && matches!(self.normal_action, Some(PlayerAction::PingPong(_)))
+    pub(super) fn has_horizontal_ping_pong_action_only(&self) -> bool {
+        !self.has_priority_action()
+            && matches!(
+                self.normal_action,
+                Some(PlayerAction::PingPong(ping_pong)) if !ping_pong.direction.is_vertical()
+            )
     }
 
     /// Clears both on-going normal and priority actions due to being aborted and whether to reset
@@ -574,6 +808,19 @@ impl PlayerContext {
         self.reset_stalling_buffer_states_next_update = true;
         self.priority_action = None;
         self.normal_action = None;
+        self.shop_detour = None;
+    }
+
+    /// The [`Shop`] on another map the player is being navigated to.
+    #[inline]
+    pub fn shop_detour(&self) -> Option<Shop> {
+        self.shop_detour
+    }
+
+    /// Clears the [`Shop`] detour so that the player is navigated back.
+    #[inline]
+    pub fn clear_shop_detour(&mut self) {
+        self.shop_detour = None;
     }
 
     pub(super) fn clear_stalling_buffer_states(&mut self, resources: &Resources) {
@@ -615,13 +862,53 @@ impl PlayerContext {
     }
 
     #[inline]
-    pub(super) fn clear_unstucking(&mut self, include_transitioned_count: bool) {
+    pub(super) fn clear_unstucking(&mut self, include_stage: bool) {
         self.unstuck_count = 0;
-        if include_transitioned_count {
-            self.unstuck_transitioned_count = 0;
+        if include_stage {
+            self.unstuck_stage = None;
         }
     }
 
+    /// Takes whether all escalating unstucking stages have been exhausted since the last take.
+    #[inline]
+    pub fn take_unstuck_exhausted(&mut self) -> bool {
+        mem::take(&mut self.unstuck_exhausted)
+    }
+
+    /// The player positions in the rolling history along with their number of visits.
+    #[inline]
+    pub fn position_trail_visit_counts(&self) -> Vec<(Point, u32)> {
+        self.position_trail.visit_counts()
+    }
+
+    /// The last [`StuckPattern`] recognized from the player positions.
+    #[inline]
+    pub fn last_stuck_pattern(&self) -> Option<StuckPattern> {
+        self.last_stuck_pattern
+    }
+
+    /// The recent and session average EXP gain per hour at `tick`.
+    #[inline]
+    pub fn exp_rates_per_hour(&self, tick: u64) -> Option<(u64, u64)> {
+        let window_ticks = tick::ticks_from_millis(self.config.exp_rate_alert.window_millis) as u64;
+        let ticks_per_hour = tick::ticks_from_millis(EXP_RATE_HOUR_MILLIS) as f32;
+
+        self.exp_rate
+            .rates(tick, window_ticks)
+            .map(|(recent, session)| {
+                (
+                    (recent * ticks_per_hour) as u64,
+                    (session * ticks_per_hour) as u64,
+                )
+            })
+    }
+
+    /// Whether the recent EXP gain rate is below the allowed fraction of the session average.
+    #[inline]
+    pub fn exp_rate_dropped(&self) -> bool {
+        self.exp_rate.dropped()
+    }
+
     /// Whether fail count for using booster `kind` has reached limit.
     #[inline]
     pub fn is_booster_fail_count_limit_reached(&self, kind: Booster) -> bool {
@@ -650,7 +937,7 @@ impl PlayerContext {
 
     /// Resets booster `kind` usage fail count.
     #[inline]
-    pub(super) fn clear_booster_fail_count(&mut self, kind: Booster) {
+    pub fn clear_booster_fail_count(&mut self, kind: Booster) {
         match kind {
             Booster::Generic => {
                 self.generic_booster_failed_count = 0;
@@ -676,10 +963,99 @@ impl PlayerContext {
 
     /// Resets familiars swap fail count.
     #[inline]
-    pub(super) fn clear_familiars_swap_fail_count(&mut self) {
+    pub fn clear_familiars_swap_fail_count(&mut self) {
         self.familiars_swap_failed_count = 0;
     }
 
+    /// Takes the result of the last completed [`Player::Calibrating`].
+    #[inline]
+    pub fn take_movement_calibration(&mut self) -> Option<MovementCalibration> {
+        self.movement_calibration.take()
+    }
+
+    /// The number of runes solved since the last [`Self::take_runes_solved_count`].
+    #[inline]
+    pub fn runes_solved_count(&self) -> u32 {
+        self.runes_solved_count
+    }
+
+    /// Whether changing channel is allowed by [`PlayerConfiguration::avoid_changing_channel`].
+    #[inline]
+    pub fn can_change_channel(&self) -> bool {
+        !self.config.avoid_changing_channel
+    }
+
+    /// Tries to use the risky `action` under [`PlayerConfiguration::risky_action_limits`].
+    ///
+    /// Returns `false` if the action should be skipped. Reaching the daily cap is only logged
+    /// once per day since a skipped action can be retried on every update.
+    pub fn try_use_risky_action(&mut self, resources: &Resources, action: RiskyAction) -> bool {
+        match self
+            .risky_actions
+            .try_use(action, &self.config.risky_action_limits, &resources.rng)
+        {
+            Ok(()) => true,
+            Err(RiskyActionLimited::Interval) => {
+                debug!(target: "player", "skipped {action} because of risky action interval");
+                false
+            }
+            Err(RiskyActionLimited::DailyCap { first }) => {
+                if first {
+                    info!(target: "player", "skipped {action} because its daily cap is reached");
+                }
+                false
+            }
+        }
+    }
+
+    /// Restores the daily counts of risky actions persisted from a previous session.
+    #[inline]
+    pub fn restore_risky_action_counts(&mut self, counts: &RiskyActionCounts) {
+        self.risky_actions.restore_counts(counts);
+    }
+
+    /// Takes the daily counts of risky actions if they changed since the last take.
+    #[inline]
+    pub fn take_risky_action_counts(&mut self) -> Option<RiskyActionCounts> {
+        self.risky_actions.take_counts()
+    }
+
+    /// Takes the number of runes solved since the last take.
+    #[inline]
+    pub fn take_runes_solved_count(&mut self) -> u32 {
+        mem::take(&mut self.runes_solved_count)
+    }
+
+    /// Takes the number of channel changes since the last take.
+    #[inline]
+    pub fn take_channels_changed_count(&mut self) -> u32 {
+        mem::take(&mut self.channels_changed_count)
+    }
+
+    /// Tracks a successful channel change from [`Player::Panicking`].
+    #[inline]
+    pub(super) fn track_channel_changed(&mut self) {
+        self.channels_changed_count += 1;
+    }
+
+    /// Takes the solidified auto-mob reachable y(s) if they have changed since the last take.
+    ///
+    /// The returned y(s) are sorted and capped at [`MAX_AUTO_MOB_REACHABLE_YS_COUNT`].
+    pub fn take_auto_mob_reachable_ys(&mut self) -> Option<Vec<i32>> {
+        if !mem::take(&mut self.auto_mob_reachable_ys_changed) {
+            return None;
+        }
+
+        let mut ys = self
+            .auto_mob_reachable_y_map
+            .iter()
+            .filter_map(|(y, count)| (*count >= AUTO_MOB_REACHABLE_Y_SOLIDIFY_COUNT).then_some(*y))
+            .collect::<Vec<_>>();
+        ys.sort_unstable();
+        ys.truncate(MAX_AUTO_MOB_REACHABLE_YS_COUNT);
+        Some(ys)
+    }
+
     /// Increments the rune validation fail count and sets [`PlayerState::rune_cash_shop`]
     /// if needed.
     #[inline]
@@ -691,18 +1067,65 @@ impl PlayerContext {
         }
     }
 
-    /// Increments the unstucking transitioned counter.
+    /// Escalates to the next unstucking attempt at `tick`.
     ///
-    /// Returns `true` when [`Player::Unstucking`] should enter GAMBA MODE.
-    #[inline]
-    pub(super) fn track_unstucking_transitioned(&mut self) -> bool {
-        self.unstuck_transitioned_count += 1;
-        if self.unstuck_transitioned_count >= UNSTUCK_GAMBA_MODE_COUNT {
-            self.unstuck_transitioned_count = 0;
-            true
-        } else {
-            false
+    /// Each stage is attempted for its number of attempts in [`PlayerConfiguration::unstuck_limits`]
+    /// before moving on to the next stage. The stages start over if there has not been any
+    /// unstucking for [`UNSTUCK_STAGE_RESET_MILLIS`].
+    ///
+    /// [`UnstuckStage::ReturnToTown`] is skipped when panicking keys are not set or there is no
+    /// navigation path to bring the player back.
+    ///
+    /// Returns [`None`] and marks unstucking as exhausted when all stages have been attempted.
+    pub(super) fn track_unstucking_transitioned(&mut self, tick: u64) -> Option<UnstuckStage> {
+        let reset_ticks = tick::ticks_from_millis(UNSTUCK_STAGE_RESET_MILLIS) as u64;
+        if tick.saturating_sub(self.unstuck_stage_tick) >= reset_ticks {
+            self.unstuck_stage = None;
+        }
+        self.unstuck_stage_tick = tick;
+
+        let (mut stage, mut attempts) = self.unstuck_stage.unwrap_or_default();
+        loop {
+            let max_attempts = match stage {
+                UnstuckStage::ReturnToTown
+                    if !has_panicking_keys(&self.config) || !self.config.can_navigate_back =>
+                {
+                    0
+                }
+                stage => stage.max_attempts(&self.config.unstuck_limits),
+            };
+            if attempts < max_attempts {
+                self.unstuck_stage = Some((stage, attempts + 1));
+                return Some(stage);
+            }
+
+            let Some(next_stage) = stage.next() else {
+                self.unstuck_stage = None;
+                self.unstuck_exhausted = true;
+                return None;
+            };
+            stage = next_stage;
+            attempts = 0;
+        }
+    }
+
+    /// Analyzes the recent player positions at `tick` for a [`StuckPattern`] while moving to
+    /// `dest`.
+    ///
+    /// Returns `true` when a pattern is recognized and the player should transition to
+    /// [`Player::Unstucking`]. Always returns `false` if
+    /// [`UnstuckLimits::detect_stuck_patterns`] is disabled.
+    pub(super) fn track_stuck_pattern(&mut self, tick: u64, dest: Point) -> bool {
+        if !self.config.unstuck_limits.detect_stuck_patterns {
+            return false;
         }
+
+        let Some(pattern) = self.position_trail.analyze(tick, dest) else {
+            return false;
+        };
+        info!(target: "player", "recognized stuck pattern: {pattern}");
+        self.last_stuck_pattern = Some(pattern);
+        true
     }
 
     /// Increments the unstucking counter.
@@ -728,7 +1151,10 @@ impl PlayerContext {
 
         let last_movement = self.last_movement.unwrap();
         let count_max = match last_movement {
-            LastMovement::Adjusting | LastMovement::DoubleJumping => {
+            LastMovement::Adjusting
+            | LastMovement::DoubleJumping
+            | LastMovement::Teleporting
+            | LastMovement::Gliding => {
                 if self.has_auto_mob_action_only() {
                     AUTO_MOB_HORIZONTAL_MOVEMENT_REPEAT_COUNT
                 } else {
@@ -738,7 +1164,8 @@ impl PlayerContext {
             LastMovement::Falling
             | LastMovement::Grappling
             | LastMovement::UpJumping
-            | LastMovement::Jumping => {
+            | LastMovement::Jumping
+            | LastMovement::Climbing => {
                 if self.has_auto_mob_action_only() {
                     AUTO_MOB_VERTICAL_MOVEMENT_REPEAT_COUNT
                 } else {
@@ -777,17 +1204,30 @@ impl PlayerContext {
     /// Gets the double jump minimum `x` distance threshold.
     ///
     /// In auto mob and final destination, the threshold is relaxed for more
-    /// fluid movement. In ping pong, there is no threshold.
+    /// fluid movement. In horizontal ping pong, there is no threshold.
     #[inline]
     pub(super) fn double_jump_threshold(&self, is_intermediate: bool) -> i32 {
         if self.has_auto_mob_action_only() && !is_intermediate {
-            DOUBLE_JUMP_AUTO_MOB_THRESHOLD
-        } else if self.has_ping_pong_action_only() {
+            self.config.double_jump_auto_mob_threshold
+        } else if self.has_horizontal_ping_pong_action_only() {
             0 // Ping pong double jumps forever
         } else if self.config.teleport_key.is_some() {
-            DOUBLE_JUMP_THRESHOLD / 2 // Half the threshold for mage
+            self.config.double_jump_threshold / 2 // Half the threshold for mage
+        } else {
+            self.config.double_jump_threshold
+        }
+    }
+
+    /// Gets the teleporting minimum `x` distance threshold.
+    ///
+    /// In auto mob and final destination, the threshold is relaxed for more
+    /// fluid movement.
+    #[inline]
+    pub(super) fn teleporting_threshold(&self, is_intermediate: bool) -> i32 {
+        if self.has_auto_mob_action_only() && !is_intermediate {
+            self.config.teleporting_auto_mob_threshold
         } else {
-            DOUBLE_JUMP_THRESHOLD
+            self.config.teleporting_threshold
         }
     }
 
@@ -809,6 +1249,12 @@ impl PlayerContext {
         self.auto_mob_last_quadrant
     }
 
+    /// Gets the approximated direction the player is facing.
+    #[inline]
+    pub fn last_known_direction(&self) -> ActionKeyDirection {
+        self.last_known_direction
+    }
+
     #[inline]
     pub(super) fn auto_mob_clear_pathing_task(&mut self) {
         self.auto_mob_pathing_task = None;
@@ -842,6 +1288,7 @@ impl PlayerContext {
             Minimap::Detecting => return false,
         };
         let pos = self.last_known_pos.expect("in positional state");
+        let detection = self.mob_detection.clone();
         let Update::Ok(points) = update_detection_task(
             resources,
             self.config.auto_mob_use_key_when_pathing_update_millis,
@@ -851,6 +1298,7 @@ impl PlayerContext {
                     minimap_bbox,
                     Rect::new(0, 0, minimap_bbox.width, minimap_bbox.height),
                     pos,
+                    &detection,
                 )
             },
         ) else {
@@ -1094,6 +1542,10 @@ impl PlayerContext {
             }
             _ => unreachable!(),
         }
+        for y in self.config.auto_mob_reachable_ys {
+            self.auto_mob_reachable_y_map
+                .insert(y, AUTO_MOB_REACHABLE_Y_SOLIDIFY_COUNT);
+        }
         let _ = self.auto_mob_reachable_y_map.try_insert(
             self.last_known_pos.unwrap().y,
             AUTO_MOB_REACHABLE_Y_SOLIDIFY_COUNT - 1,
@@ -1115,6 +1567,9 @@ impl PlayerContext {
                     .get_mut(&y)
                     .expect("must contain");
 
+                if *count == AUTO_MOB_REACHABLE_Y_SOLIDIFY_COUNT {
+                    self.auto_mob_reachable_ys_changed = true;
+                }
                 *count = count.saturating_sub(1);
                 if *count == 0 {
                     self.auto_mob_reachable_y_map.remove(&y);
@@ -1124,6 +1579,9 @@ impl PlayerContext {
             let count = self.auto_mob_reachable_y_map.entry(pos.y).or_insert(0);
             if *count < AUTO_MOB_REACHABLE_Y_SOLIDIFY_COUNT {
                 *count += 1;
+                if *count == AUTO_MOB_REACHABLE_Y_SOLIDIFY_COUNT {
+                    self.auto_mob_reachable_ys_changed = true;
+                }
             }
             debug_assert!(*count <= AUTO_MOB_REACHABLE_Y_SOLIDIFY_COUNT);
 
@@ -1261,8 +1719,7 @@ impl PlayerContext {
     ///
     /// This function updates the player states including current position, health, whether the
     /// player is dead, stationary state and rune validation state. It also resets
-    /// [`PlayerState::unstuck_counter`] and [`PlayerState::unstuck_consecutive_counter`] when the
-    /// player position changes.
+    /// [`PlayerState::unstuck_counter`] when the player position changes.
     #[inline]
     pub(super) fn update_state(
         &mut self,
@@ -1273,6 +1730,7 @@ impl PlayerContext {
     ) -> bool {
         if self.update_position_state(resources, minimap_state) {
             self.update_health_state(resources, player_state);
+            self.update_exp_state(resources);
             self.update_rune_validating_state(
                 #[cfg(debug_assertions)]
                 resources,
@@ -1294,10 +1752,19 @@ impl PlayerContext {
     #[inline]
     fn update_position_state(&mut self, resources: &Resources, minimap_state: Minimap) -> bool {
         let minimap_bbox = match &minimap_state {
-            Minimap::Detecting => return false,
+            Minimap::Detecting => {
+                self.position_trail.clear();
+                return false;
+            }
             Minimap::Idle(idle) => idle.bbox,
         };
-        let Ok(player_bbox) = resources.detector().detect_player(minimap_bbox) else {
+        let player_bbox = match self.config.player_marker {
+            Some(marker) => resources
+                .detector()
+                .detect_player_marker(minimap_bbox, marker),
+            None => resources.detector().detect_player(minimap_bbox),
+        };
+        let Ok(player_bbox) = player_bbox else {
             return false;
         };
         let tl = player_bbox.tl();
@@ -1313,17 +1780,19 @@ impl PlayerContext {
         let last_known_pos = self.last_known_pos.unwrap_or(pos);
         if last_known_pos != pos {
             self.unstuck_count = 0;
-            self.unstuck_transitioned_count = 0;
             self.is_stationary_timeout = Timeout::default();
         }
         self.update_velocity(pos, resources.tick);
+        self.position_trail.push(pos, resources.tick);
 
-        let (is_stationary, is_stationary_timeout) =
-            match next_timeout_lifecycle(self.is_stationary_timeout, STATIONARY_TIMEOUT) {
-                Lifecycle::Started(timeout) => (false, timeout),
-                Lifecycle::Ended => (true, self.is_stationary_timeout),
-                Lifecycle::Updated(timeout) => (false, timeout),
-            };
+        let (is_stationary, is_stationary_timeout) = match next_timeout_lifecycle(
+            self.is_stationary_timeout,
+            tick::scaled(STATIONARY_TIMEOUT),
+        ) {
+            Lifecycle::Started(timeout) => (false, timeout),
+            Lifecycle::Ended => (true, self.is_stationary_timeout),
+            Lifecycle::Updated(timeout) => (false, timeout),
+        };
         self.is_stationary = is_stationary;
         self.is_stationary_timeout = is_stationary_timeout;
         self.last_known_pos = Some(pos);
@@ -1362,8 +1831,8 @@ impl PlayerContext {
                 });
 
             if total_weight > 0.0 {
-                let avg_dx = (weighted_sum.0 / total_weight).abs();
-                let avg_dy = (weighted_sum.1 / total_weight).abs();
+                let avg_dx = to_base_tick_rate_speed((weighted_sum.0 / total_weight).abs());
+                let avg_dy = to_base_tick_rate_speed((weighted_sum.1 / total_weight).abs());
 
                 let smoothed_dx = 0.5 * avg_dx + 0.5 * self.velocity.0;
                 let smoothed_dy = 0.5 * avg_dy + 0.5 * self.velocity.1;
@@ -1389,13 +1858,14 @@ impl PlayerContext {
         debug_assert!(self.rune_failed_count < MAX_RUNE_FAILED_COUNT);
         debug_assert!(!self.rune_cash_shop);
         self.rune_validate_timeout = self.rune_validate_timeout.and_then(|timeout| {
-            match next_timeout_lifecycle(timeout, VALIDATE_TIMEOUT) {
+            match next_timeout_lifecycle(timeout, tick::scaled(VALIDATE_TIMEOUT)) {
                 Lifecycle::Ended => {
                     if matches!(buffs[BuffKind::Rune].state, Buff::No) {
                         self.track_rune_fail_count();
                         info!(target: "rune", "failed to solve {} time(s)", self.rune_failed_count);
                     } else {
                         self.rune_failed_count = 0;
+                        self.runes_solved_count += 1;
                         #[cfg(debug_assertions)]
                         resources.debug.save_last_rune_result();
                     }
@@ -1499,6 +1969,40 @@ impl PlayerContext {
         self.is_dead = is_dead;
     }
 
+    /// Updates the EXP gain rate of the current session.
+    ///
+    /// The session is cleared while halting. An alert is notified when the recent rate has just
+    /// dropped below [`ExpRateAlert::min_rate_percent`] of the session average.
+    #[inline]
+    fn update_exp_state(&mut self, resources: &Resources) {
+        let alert = self.config.exp_rate_alert;
+        if !alert.enabled || resources.operation.halting() {
+            self.exp_rate.clear();
+            self.exp_task = None;
+            return;
+        }
+
+        if let Update::Ok(exp) = update_detection_task(
+            resources,
+            EXP_UPDATE_MILLIS,
+            &mut self.exp_task,
+            |detector| detector.detect_player_exp(),
+        ) {
+            self.exp_rate.push(exp, resources.tick);
+        }
+
+        let window_ticks = tick::ticks_from_millis(alert.window_millis) as u64;
+        if self
+            .exp_rate
+            .update_dropped(resources.tick, window_ticks, alert.min_rate_percent)
+        {
+            info!(target: "player", "EXP rate dropped below {}% of session average", alert.min_rate_percent);
+            let _ = resources
+                .notification
+                .schedule_notification(NotificationKind::ExpRateDropped);
+        }
+    }
+
     fn update_stalling_buffer_state(&mut self, resources: &Resources) {
         match self.stalling_buffered {
             BufferedStalling::None => (),
@@ -1553,14 +2057,17 @@ mod tests {
 
     use opencv::core::{Point, Rect};
 
+    use super::{UNSTUCK_STAGE_RESET_MILLIS, UnstuckStage};
     use crate::{
-        Position,
+        Position, UnstuckLimits,
         array::Array,
+        bridge::KeyKind,
         ecs::Resources,
         minimap::{Minimap, MinimapIdle},
         pathing::{Platform, find_neighbors},
         player::{AutoMob, PlayerAction, PlayerContext, Quadrant},
         rng::Rng,
+        tick,
     };
 
     const SEED: [u8; 32] = [
@@ -1640,6 +2147,41 @@ mod tests {
         assert_eq!(player.auto_mob_reachable_y_map.get(&120), Some(&3));
     }
 
+    #[test]
+    fn take_auto_mob_reachable_ys_when_solidified() {
+        let mut player = PlayerContext {
+            auto_mob_reachable_y_map: HashMap::from([
+                (100, AUTO_MOB_REACHABLE_Y_SOLIDIFY_COUNT),
+                (120, AUTO_MOB_REACHABLE_Y_SOLIDIFY_COUNT - 1), // Will be solidified
+                (50, 1),
+            ]),
+            last_known_pos: Some(Point::new(0, 120)),
+            ..Default::default()
+        };
+        assert_eq!(player.take_auto_mob_reachable_ys(), None);
+
+        player.auto_mob_track_reachable_y(120);
+
+        assert_eq!(player.take_auto_mob_reachable_ys(), Some(vec![100, 120]));
+        assert_eq!(player.take_auto_mob_reachable_ys(), None);
+    }
+
+    #[test]
+    fn take_auto_mob_reachable_ys_when_unsolidified() {
+        let mut player = PlayerContext {
+            auto_mob_reachable_y_map: HashMap::from([
+                (100, AUTO_MOB_REACHABLE_Y_SOLIDIFY_COUNT), // Will be unsolidified
+                (120, AUTO_MOB_REACHABLE_Y_SOLIDIFY_COUNT),
+            ]),
+            last_known_pos: Some(Point::new(0, 120)),
+            ..Default::default()
+        };
+
+        player.auto_mob_track_reachable_y(100);
+
+        assert_eq!(player.take_auto_mob_reachable_ys(), Some(vec![120]));
+    }
+
     #[test]
     fn auto_mob_track_ignore_xs_conditional_merge() {
         let y = 100;
@@ -1775,4 +2317,89 @@ mod tests {
         assert_eq!(point.y, 20); // 100 - 80
         assert_matches!(state.auto_mob_last_quadrant, Some(Quadrant::BottomLeft));
     }
+
+    #[test]
+    fn track_unstucking_transitioned_escalates_stages_until_exhausted() {
+        let mut state = PlayerContext::default();
+        state.config.unstuck_limits = UnstuckLimits {
+            reposition_attempts: 2,
+            esc_attempts: 0,
+            random_jump_attempts: 1,
+            return_to_town_attempts: 1,
+            detect_stuck_patterns: false,
+        };
+        state.config.change_channel_key = Some(KeyKind::C);
+        state.config.to_town_key = Some(KeyKind::T);
+        state.config.can_navigate_back = true;
+
+        assert_eq!(
+            state.track_unstucking_transitioned(1),
+            Some(UnstuckStage::Reposition)
+        );
+        assert_eq!(
+            state.track_unstucking_transitioned(2),
+            Some(UnstuckStage::Reposition)
+        );
+        assert_eq!(
+            state.track_unstucking_transitioned(3),
+            Some(UnstuckStage::RandomJump)
+        );
+        assert_eq!(
+            state.track_unstucking_transitioned(4),
+            Some(UnstuckStage::ReturnToTown)
+        );
+        assert!(!state.take_unstuck_exhausted());
+        assert_eq!(state.track_unstucking_transitioned(5), None);
+        assert!(state.take_unstuck_exhausted());
+        assert!(!state.take_unstuck_exhausted());
+    }
+
+    #[test]
+    fn track_unstucking_transitioned_skips_return_to_town_without_key_and_resets_after_a_while() {
+        let mut state = PlayerContext::default();
+        state.config.unstuck_limits = UnstuckLimits {
+            reposition_attempts: 1,
+            esc_attempts: 0,
+            random_jump_attempts: 0,
+            return_to_town_attempts: 1,
+            detect_stuck_patterns: false,
+        };
+
+        assert_eq!(
+            state.track_unstucking_transitioned(1),
+            Some(UnstuckStage::Reposition)
+        );
+        let reset_ticks = tick::ticks_from_millis(UNSTUCK_STAGE_RESET_MILLIS) as u64;
+        assert_eq!(
+            state.track_unstucking_transitioned(1 + reset_ticks),
+            Some(UnstuckStage::Reposition)
+        );
+        assert_eq!(state.track_unstucking_transitioned(2 + reset_ticks), None);
+    }
+
+    #[test]
+    fn track_unstucking_transitioned_skips_return_to_town_without_change_channel_key_or_path() {
+        let mut state = PlayerContext::default();
+        state.config.unstuck_limits = UnstuckLimits {
+            reposition_attempts: 0,
+            esc_attempts: 0,
+            random_jump_attempts: 0,
+            return_to_town_attempts: 1,
+            detect_stuck_patterns: false,
+        };
+        state.config.to_town_key = Some(KeyKind::T);
+        state.config.can_navigate_back = true;
+
+        assert_eq!(state.track_unstucking_transitioned(1), None);
+
+        state.config.change_channel_key = Some(KeyKind::C);
+        state.config.can_navigate_back = false;
+        assert_eq!(state.track_unstucking_transitioned(2), None);
+
+        state.config.can_navigate_back = true;
+        assert_eq!(
+            state.track_unstucking_transitioned(3),
+            Some(UnstuckStage::ReturnToTown)
+        );
+    }
 }
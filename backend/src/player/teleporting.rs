@@ -0,0 +1,299 @@
+use std::cmp::Ordering;
+
+use super::{
+    Key, Player, PlayerAction,
+    actions::update_from_auto_mob_action,
+    moving::Moving,
+    timeout::{
+        Lifecycle, MovingLifecycle, next_moving_lifecycle_with_axis, next_timeout_lifecycle,
+    },
+    use_key::UseKey,
+};
+use crate::{
+    ActionKeyDirection, ActionKeyWith,
+    ecs::{Resources, transition_if},
+    minimap::Minimap,
+    player::{
+        PlayerEntity,
+        moving::MOVE_TIMEOUT,
+        next_action,
+        state::LastMovement,
+        timeout::{ChangeAxis, Timeout},
+        transition_to_moving,
+    },
+    tick,
+};
+
+/// Minimum x distance from the destination required to teleport horizontally.
+pub const TELEPORTING_THRESHOLD: i32 = 12;
+
+/// Minimum x distance from the destination required to teleport horizontally in auto mobbing.
+pub const TELEPORTING_AUTO_MOB_THRESHOLD: i32 = 8;
+
+/// Minimum y distance from the destination required to transition to [`Player::UseKey`].
+const USE_KEY_Y_THRESHOLD: i32 = 10;
+
+/// Maximum number of ticks before timing out.
+const TIMEOUT: u32 = MOVE_TIMEOUT;
+
+/// Number of ticks to wait after a teleport before teleporting again.
+///
+/// Teleport has a short cast delay during which another teleport key would be ignored.
+const COOLDOWN_TIMEOUT: u32 = 6;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Teleporting {
+    pub moving: Moving,
+    /// Timeout for between teleport cooldown.
+    cooldown_timeout: Timeout,
+}
+
+impl Teleporting {
+    pub fn new(moving: Moving) -> Self {
+        Self {
+            moving,
+            cooldown_timeout: Timeout::default(),
+        }
+    }
+
+    #[inline]
+    fn moving(self, moving: Moving) -> Teleporting {
+        Teleporting { moving, ..self }
+    }
+
+    #[inline]
+    fn update_teleport_cooldown(&mut self) {
+        self.cooldown_timeout =
+            match next_timeout_lifecycle(self.cooldown_timeout, tick::scaled(COOLDOWN_TIMEOUT)) {
+                Lifecycle::Started(timeout) => timeout,
+                Lifecycle::Ended => Timeout::default(),
+                Lifecycle::Updated(timeout) => timeout,
+            };
+    }
+}
+
+/// Updates the [`Player::Teleporting`] contextual state.
+///
+/// This state replaces [`Player::DoubleJumping`] for teleport classes when
+/// [`PlayerConfiguration::horizontal_teleporting`] is enabled. It holds the direction key and
+/// repeatedly teleports as long as the distance x-wise is still `>=` the teleporting threshold.
+///
+/// [`PlayerConfiguration::horizontal_teleporting`]: super::state::PlayerConfiguration::horizontal_teleporting
+pub fn update_teleporting_state(
+    resources: &Resources,
+    player: &mut PlayerEntity,
+    minimap_state: Minimap,
+) {
+    let Player::Teleporting(teleporting) = player.state else {
+        panic!("state is not teleporting")
+    };
+    let is_intermediate = teleporting.moving.is_destination_intermediate();
+    let left_key = player.context.config.left_key;
+    let right_key = player.context.config.right_key;
+
+    match next_moving_lifecycle_with_axis(
+        teleporting.moving,
+        player.context.last_known_pos.expect("in positional state"),
+        tick::scaled(TIMEOUT),
+        ChangeAxis::Both,
+    ) {
+        MovingLifecycle::Started(moving) => {
+            player.context.last_movement = Some(LastMovement::Teleporting);
+            player.state = Player::Teleporting(teleporting.moving(moving));
+        }
+        MovingLifecycle::Ended(moving) => transition_to_moving!(player, moving, {
+            resources.input.send_key_up(right_key);
+            resources.input.send_key_up(left_key);
+        }),
+        MovingLifecycle::Updated(mut moving) => {
+            let (x_distance, x_direction) = moving.x_distance_direction_from(true, moving.pos);
+            let mut teleporting = teleporting;
+
+            if !moving.completed {
+                let direction = match x_direction.cmp(&0) {
+                    Ordering::Greater => Some((right_key, left_key, ActionKeyDirection::Right)),
+                    Ordering::Less => Some((left_key, right_key, ActionKeyDirection::Left)),
+                    Ordering::Equal => None,
+                };
+                let threshold = player.context.teleporting_threshold(is_intermediate);
+
+                if let Some((key_down, key_up, direction)) = direction
+                    && x_distance >= threshold
+                {
+                    resources.input.send_key_down(key_down);
+                    resources.input.send_key_up(key_up);
+                    player.context.last_known_direction = direction;
+                    if !teleporting.cooldown_timeout.started {
+                        resources.input.send_key(
+                            player
+                                .context
+                                .config
+                                .teleport_key
+                                .expect("has teleport key"),
+                        );
+                    }
+                    teleporting.update_teleport_cooldown();
+                } else {
+                    resources.input.send_key_up(right_key);
+                    resources.input.send_key_up(left_key);
+                    moving.completed = true;
+                }
+            }
+
+            // Computes and sets initial next state first
+            player.state = if moving.completed {
+                Player::Teleporting(
+                    teleporting.moving(moving.timeout_current(tick::scaled(TIMEOUT))),
+                )
+            } else {
+                Player::Teleporting(teleporting.moving(moving))
+            };
+            update_from_action(resources, player, minimap_state, moving, is_intermediate);
+        }
+    }
+}
+
+/// Handles [`PlayerAction`] during teleporting.
+///
+/// It currently handles action for auto mob and a key action with [`ActionKeyWith::Any`] or
+/// [`ActionKeyWith::DoubleJump`] the same way as [`Player::DoubleJumping`].
+fn update_from_action(
+    resources: &Resources,
+    player: &mut PlayerEntity,
+    minimap_state: Minimap,
+    moving: Moving,
+    is_intermediate: bool,
+) {
+    let cur_pos = moving.pos;
+    let (x_distance, x_direction) = moving.x_distance_direction_from(false, cur_pos);
+    let (y_distance, _) = moving.y_distance_direction_from(false, cur_pos);
+
+    match next_action(&player.context) {
+        Some(PlayerAction::AutoMob(mob)) => update_from_auto_mob_action(
+            resources,
+            player,
+            minimap_state,
+            mob,
+            x_distance,
+            x_direction,
+            y_distance,
+        ),
+        Some(PlayerAction::Key(
+            key @ Key {
+                with: ActionKeyWith::DoubleJump | ActionKeyWith::Any,
+                ..
+            },
+        )) => {
+            transition_if!(!moving.completed);
+            transition_if!(
+                player,
+                Player::UseKey(UseKey::from_key(key)),
+                !moving.exact
+                    && x_distance <= player.context.teleporting_threshold(is_intermediate)
+                    && y_distance <= USE_KEY_Y_THRESHOLD
+            );
+        }
+        None
+        | Some(
+            PlayerAction::Key(Key {
+                with: ActionKeyWith::Stationary,
+                ..
+            })
+            | PlayerAction::SolveRune
+            | PlayerAction::EnterPortal(_)
+            | PlayerAction::Shop(_)
+            | PlayerAction::PingPong(_)
+            | PlayerAction::Move { .. },
+        ) => (),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use mockall::predicate::eq;
+    use opencv::core::Point;
+
+    use super::*;
+    use crate::{
+        bridge::{KeyKind, MockInput},
+        player::{PlayerContext, moving::Moving},
+    };
+
+    fn make_player_with_state(state: Player) -> PlayerEntity {
+        let mut context = PlayerContext::default();
+        context.config.teleport_key = Some(KeyKind::Shift);
+        context.config.horizontal_teleporting = true;
+
+        PlayerEntity { state, context }
+    }
+
+    #[test]
+    fn update_teleporting_state_started_sets_last_movement() {
+        let pos = Point::new(0, 0);
+        let mut player = make_player_with_state(Player::Teleporting(Teleporting::new(
+            Moving::new(pos, Point::new(30, 0), false, None),
+        )));
+        player.context.last_known_pos = Some(pos);
+        let resources = Resources::new(None, None);
+
+        update_teleporting_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(player.state, Player::Teleporting(_));
+        assert_eq!(
+            player.context.last_movement,
+            Some(LastMovement::Teleporting)
+        );
+    }
+
+    #[test]
+    fn update_teleporting_state_teleports_toward_destination_with_cooldown() {
+        let pos = Point::new(100, 50);
+        let mut player = make_player_with_state(Player::Teleporting(Teleporting::new(
+            Moving::new(pos, Point::new(50, 50), false, None).timeout_started(true),
+        )));
+        player.context.last_known_pos = Some(pos);
+        let mut keys = MockInput::new();
+        keys.expect_send_key_down().with(eq(KeyKind::Left)).times(2);
+        keys.expect_send_key_up().with(eq(KeyKind::Right)).times(2);
+        keys.expect_send_key().with(eq(KeyKind::Shift)).once();
+        let resources = Resources::new(Some(keys), None);
+
+        update_teleporting_state(&resources, &mut player, Minimap::Detecting);
+        update_teleporting_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(player.state, Player::Teleporting(Teleporting { moving, cooldown_timeout })
+            if !moving.completed && cooldown_timeout.started);
+        assert_eq!(
+            player.context.last_known_direction,
+            ActionKeyDirection::Left
+        );
+    }
+
+    #[test]
+    fn update_teleporting_state_completes_within_threshold() {
+        let pos = Point::new(100, 50);
+        let mut player = make_player_with_state(Player::Teleporting(Teleporting::new(
+            Moving::new(
+                pos,
+                Point::new(100 - TELEPORTING_THRESHOLD + 1, 50),
+                false,
+                None,
+            )
+            .timeout_started(true),
+        )));
+        player.context.last_known_pos = Some(pos);
+        let mut keys = MockInput::new();
+        keys.expect_send_key_up().with(eq(KeyKind::Right)).once();
+        keys.expect_send_key_up().with(eq(KeyKind::Left)).once();
+        keys.expect_send_key().never();
+        let resources = Resources::new(Some(keys), None);
+
+        update_teleporting_state(&resources, &mut player, Minimap::Detecting);
+
+        assert_matches!(player.state, Player::Teleporting(Teleporting { moving, .. })
+            if moving.completed);
+    }
+}
@@ -63,6 +63,14 @@ impl Timeout {
 /// be timed out.
 #[inline]
 pub fn next_timeout_lifecycle(timeout: Timeout, max_timeout: u32) -> Lifecycle {
+    next_timeout_lifecycle_by(timeout, max_timeout, 1)
+}
+
+/// Gets the next [`Timeout`] lifecycle after `ticks` ticks have passed.
+///
+/// The current timeout tick is capped at `max_timeout`.
+#[inline]
+pub fn next_timeout_lifecycle_by(timeout: Timeout, max_timeout: u32, ticks: u32) -> Lifecycle {
     debug_assert!(max_timeout > 0, "max_timeout must be positive");
     debug_assert!(
         timeout.started || timeout == Timeout::default(),
@@ -80,8 +88,8 @@ pub fn next_timeout_lifecycle(timeout: Timeout, max_timeout: u32) -> Lifecycle {
         }),
         Timeout { current, .. } if current >= max_timeout => Lifecycle::Ended,
         timeout => Lifecycle::Updated(Timeout {
-            current: timeout.current + 1,
-            total: timeout.total + 1,
+            current: (timeout.current + ticks).min(max_timeout),
+            total: timeout.total + ticks,
             ..timeout
         }),
     }
@@ -164,6 +172,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn timeout_lifecycle_updated_by_ticks_capped() {
+        let timeout = make_timeout(2, 2, true);
+        match next_timeout_lifecycle_by(timeout, 5, 2) {
+            Lifecycle::Updated(t) => {
+                assert_eq!(t.current, 4);
+                assert_eq!(t.total, 4);
+            }
+            _ => panic!("Expected Updated variant"),
+        }
+
+        let timeout = make_timeout(4, 4, true);
+        match next_timeout_lifecycle_by(timeout, 5, 3) {
+            Lifecycle::Updated(t) => {
+                assert_eq!(t.current, 5);
+                assert_eq!(t.total, 7);
+            }
+            _ => panic!("Expected Updated variant"),
+        }
+    }
+
     #[test]
     fn timeout_lifecycle_ended() {
         let timeout = make_timeout(5, 10, true);
@@ -0,0 +1,350 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+};
+
+use opencv::core::Point;
+
+use crate::tick::ticks_from_millis;
+
+/// Maximum number of positions kept in a [`PositionTrail`].
+const MAX_TRAIL_LEN: usize = 300;
+
+/// Milliseconds of the most recent positions analyzed for a [`StuckPattern`].
+const ANALYSIS_WINDOW_MILLIS: u64 = 15_000;
+
+/// Minimum number of direction turns to be considered oscillating.
+const OSCILLATION_MIN_TURNS: usize = 6;
+
+/// Minimum x distance between the two turning x values to be considered oscillating.
+///
+/// This avoids treating detection jitter while standing still as oscillating.
+const OSCILLATION_MIN_X_DISTANCE: i32 = 4;
+
+/// Maximum x distance between the two turning x values to be considered oscillating.
+///
+/// This avoids treating intended patrolling (e.g. ping pong) as oscillating.
+const OSCILLATION_MAX_X_DISTANCE: i32 = 20;
+
+/// Maximum x distance from one of the two turning x values for a turn to belong to it.
+const OSCILLATION_X_TOLERANCE: i32 = 2;
+
+/// Minimum y distance of a continuous descent to be considered a fall.
+const FALL_MIN_Y_DISTANCE: i32 = 8;
+
+/// Minimum number of falls starting from the same spot to be considered repeated.
+const REPEATED_FALL_MIN_COUNT: usize = 3;
+
+/// Maximum distance between fall starting positions for them to be the same spot.
+const REPEATED_FALL_TOLERANCE: i32 = 3;
+
+/// Minimum decrease in distance to the destination over the analyzed positions to be considered
+/// getting closer.
+///
+/// A recognized pattern is ignored while getting closer because the player may still be making
+/// progress (e.g. falling down through platforms toward the destination).
+const PROGRESS_MIN_DISTANCE: i32 = 4;
+
+/// A recognized pattern of the player being stuck.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StuckPattern {
+    /// The player keeps moving back and forth between two x values.
+    Oscillating { from_x: i32, to_x: i32 },
+    /// The player keeps falling from the same position.
+    RepeatedFall(Point),
+}
+
+impl fmt::Display for StuckPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StuckPattern::Oscillating { from_x, to_x } => {
+                write!(f, "Oscillating between x {from_x} and {to_x}")
+            }
+            StuckPattern::RepeatedFall(point) => {
+                write!(f, "Falling repeatedly at {}, {}", point.x, point.y)
+            }
+        }
+    }
+}
+
+/// A rolling history of the player positions along with the ticks they were detected at.
+#[derive(Debug, Default)]
+pub struct PositionTrail {
+    positions: VecDeque<(Point, u64)>,
+    /// Positions detected at or before this tick are ignored by [`Self::analyze`].
+    ///
+    /// Set when a [`StuckPattern`] is recognized so that the same pattern is not recognized
+    /// again while the trail is still kept for displaying.
+    analyze_after_tick: u64,
+}
+
+impl PositionTrail {
+    /// Pushes `pos` detected at `tick` if it is different from the last position.
+    pub fn push(&mut self, pos: Point, tick: u64) {
+        if self.positions.back().is_some_and(|(last, _)| *last == pos) {
+            return;
+        }
+        if self.positions.len() == MAX_TRAIL_LEN {
+            self.positions.pop_front();
+        }
+        self.positions.push_back((pos, tick));
+    }
+
+    /// Clears all the positions.
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.analyze_after_tick = 0;
+    }
+
+    /// Counts the number of times each position was visited, oldest first.
+    pub fn visit_counts(&self) -> Vec<(Point, u32)> {
+        let mut indices = HashMap::<(i32, i32), usize>::new();
+        let mut counts = Vec::<(Point, u32)>::new();
+        for (pos, _) in self.positions.iter() {
+            match indices.get(&(pos.x, pos.y)) {
+                Some(index) => counts[*index].1 += 1,
+                None => {
+                    indices.insert((pos.x, pos.y), counts.len());
+                    counts.push((*pos, 1));
+                }
+            }
+        }
+
+        counts
+    }
+
+    /// Analyzes the positions within the analysis window ending at `tick` for a [`StuckPattern`]
+    /// while moving to `dest`.
+    ///
+    /// No pattern is recognized if the positions are getting closer to `dest`. Once a pattern is
+    /// recognized, the analyzed positions are not analyzed again.
+    pub fn analyze(&mut self, tick: u64, dest: Point) -> Option<StuckPattern> {
+        let window_start = tick.saturating_sub(ticks_from_millis(ANALYSIS_WINDOW_MILLIS) as u64);
+        let positions = self
+            .positions
+            .iter()
+            .filter(|(_, pos_tick)| {
+                *pos_tick >= window_start && *pos_tick > self.analyze_after_tick
+            })
+            .map(|(pos, _)| *pos)
+            .collect::<Vec<_>>();
+        if is_getting_closer(&positions, dest) {
+            return None;
+        }
+
+        let pattern = detect_oscillation(&positions).or_else(|| detect_repeated_fall(&positions));
+        if pattern.is_some() {
+            self.analyze_after_tick = tick;
+        }
+
+        pattern
+    }
+}
+
+fn is_getting_closer(positions: &[Point], dest: Point) -> bool {
+    let distance = |pos: &Point| (pos.x - dest.x).abs() + (pos.y - dest.y).abs();
+    match (positions.first(), positions.last()) {
+        (Some(first), Some(last)) => distance(first) - distance(last) >= PROGRESS_MIN_DISTANCE,
+        _ => false,
+    }
+}
+
+fn detect_oscillation(positions: &[Point]) -> Option<StuckPattern> {
+    let mut turns = Vec::new();
+    let mut last_direction = 0;
+    for window in positions.windows(2) {
+        let direction = (window[1].x - window[0].x).signum();
+        if direction == 0 {
+            continue;
+        }
+        if last_direction != 0 && direction != last_direction {
+            turns.push(window[0].x);
+        }
+        last_direction = direction;
+    }
+    if turns.len() < OSCILLATION_MIN_TURNS {
+        return None;
+    }
+
+    let turns = &turns[turns.len() - OSCILLATION_MIN_TURNS..];
+    let from_x = turns.iter().copied().min()?;
+    let to_x = turns.iter().copied().max()?;
+    let distance = to_x - from_x;
+    if !(OSCILLATION_MIN_X_DISTANCE..=OSCILLATION_MAX_X_DISTANCE).contains(&distance) {
+        return None;
+    }
+
+    turns
+        .iter()
+        .all(|x| x - from_x <= OSCILLATION_X_TOLERANCE || to_x - x <= OSCILLATION_X_TOLERANCE)
+        .then_some(StuckPattern::Oscillating { from_x, to_x })
+}
+
+fn detect_repeated_fall(positions: &[Point]) -> Option<StuckPattern> {
+    let mut fall_starts = Vec::new();
+    let mut start = None::<Point>;
+    let mut last = None::<Point>;
+    for pos in positions.iter().copied() {
+        match last {
+            Some(last) if pos.y < last.y => {
+                start.get_or_insert(last);
+            }
+            _ => {
+                if let Some(start) = start.take()
+                    && let Some(last) = last
+                    && start.y - last.y >= FALL_MIN_Y_DISTANCE
+                {
+                    fall_starts.push(start);
+                }
+            }
+        }
+        last = Some(pos);
+    }
+    if let Some(start) = start
+        && let Some(last) = last
+        && start.y - last.y >= FALL_MIN_Y_DISTANCE
+    {
+        fall_starts.push(start);
+    }
+
+    let latest = *fall_starts.last()?;
+    let count = fall_starts
+        .iter()
+        .filter(|start| {
+            (start.x - latest.x).abs() <= REPEATED_FALL_TOLERANCE
+                && (start.y - latest.y).abs() <= REPEATED_FALL_TOLERANCE
+        })
+        .count();
+
+    (count >= REPEATED_FALL_MIN_COUNT).then_some(StuckPattern::RepeatedFall(latest))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+
+    fn trail_from(points: impl IntoIterator<Item = (i32, i32)>) -> PositionTrail {
+        let mut trail = PositionTrail::default();
+        for (i, (x, y)) in points.into_iter().enumerate() {
+            trail.push(Point::new(x, y), i as u64 + 1);
+        }
+        trail
+    }
+
+    #[test]
+    fn push_skips_same_position_and_keeps_latest() {
+        let mut trail = PositionTrail::default();
+        trail.push(Point::new(1, 1), 1);
+        trail.push(Point::new(1, 1), 2);
+        assert_eq!(trail.positions.len(), 1);
+
+        for i in 0..MAX_TRAIL_LEN as i32 {
+            trail.push(Point::new(i + 2, 1), i as u64 + 3);
+        }
+        assert_eq!(trail.positions.len(), MAX_TRAIL_LEN);
+        assert_eq!(trail.positions.front().unwrap().0, Point::new(2, 1));
+    }
+
+    #[test]
+    fn visit_counts_groups_same_positions() {
+        let trail = trail_from([(1, 1), (2, 1), (1, 1), (3, 1), (1, 1)]);
+
+        assert_eq!(
+            trail.visit_counts(),
+            vec![
+                (Point::new(1, 1), 3),
+                (Point::new(2, 1), 1),
+                (Point::new(3, 1), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn analyze_oscillating_between_two_x() {
+        let mut points = Vec::new();
+        for _ in 0..4 {
+            points.extend((10..=16).map(|x| (x, 5)));
+            points.extend((10..16).rev().map(|x| (x, 5)));
+        }
+        let mut trail = trail_from(points);
+        let tick = trail.positions.back().unwrap().1;
+
+        assert_eq!(
+            trail.analyze(tick, Point::new(100, 5)),
+            Some(StuckPattern::Oscillating {
+                from_x: 10,
+                to_x: 16
+            })
+        );
+        assert_eq!(trail.analyze(tick, Point::new(100, 5)), None);
+    }
+
+    #[test]
+    fn analyze_ignores_pattern_while_getting_closer() {
+        let mut points = Vec::new();
+        for _ in 0..4 {
+            points.extend((10..=16).map(|x| (x, 5)));
+            points.extend((10..16).rev().map(|x| (x, 5)));
+        }
+        points.extend((11..=30).map(|x| (x, 5)));
+        let mut trail = trail_from(points);
+        let tick = trail.positions.back().unwrap().1;
+
+        assert_eq!(trail.analyze(tick, Point::new(40, 5)), None);
+        assert_matches!(
+            trail.analyze(tick, Point::new(0, 5)),
+            Some(StuckPattern::Oscillating { .. })
+        );
+    }
+
+    #[test]
+    fn analyze_ignores_jitter_and_long_patrol() {
+        let mut jitter = Vec::new();
+        let mut patrol = Vec::new();
+        for _ in 0..4 {
+            jitter.extend([(10, 5), (11, 5), (12, 5), (11, 5)]);
+            patrol.extend((10..=60).map(|x| (x, 5)));
+            patrol.extend((10..60).rev().map(|x| (x, 5)));
+        }
+
+        for points in [jitter, patrol] {
+            let mut trail = trail_from(points);
+            let tick = trail.positions.back().unwrap().1;
+
+            assert_eq!(trail.analyze(tick, Point::new(100, 5)), None);
+        }
+    }
+
+    #[test]
+    fn analyze_repeated_fall_at_same_spot() {
+        let mut points = Vec::new();
+        for i in 0..REPEATED_FALL_MIN_COUNT as i32 {
+            points.extend((20..=30).map(|y| (40, y)));
+            points.extend((10..30).rev().map(|y| (40 + i, y)));
+        }
+        let mut trail = trail_from(points);
+        let tick = trail.positions.back().unwrap().1;
+
+        assert_eq!(
+            trail.analyze(tick, Point::new(40, 100)),
+            Some(StuckPattern::RepeatedFall(Point::new(40, 30)))
+        );
+    }
+
+    #[test]
+    fn analyze_ignores_positions_outside_window() {
+        let mut points = Vec::new();
+        for _ in 0..4 {
+            points.extend((10..=16).map(|x| (x, 5)));
+            points.extend((10..16).rev().map(|x| (x, 5)));
+        }
+        let mut trail = trail_from(points);
+        let tick = trail.positions.back().unwrap().1
+            + ticks_from_millis(ANALYSIS_WINDOW_MILLIS) as u64
+            + 1;
+
+        assert_eq!(trail.analyze(tick, Point::new(100, 5)), None);
+    }
+}
@@ -2,14 +2,53 @@ use opencv::core::Point;
 
 use super::timeout::{Lifecycle, Timeout, next_timeout_lifecycle};
 use crate::{
+    UnstuckLimits,
     bridge::KeyKind,
     ecs::{Resources, transition},
     minimap::Minimap,
     player::{
-        MOVE_TIMEOUT, Player, PlayerAction, PlayerEntity, next_action, transition_from_action,
+        MOVE_TIMEOUT, PanicTo, Panicking, Player, PlayerAction, PlayerContext, PlayerEntity,
+        next_action, risky::RiskyAction, transition_from_action,
     },
+    tick,
 };
 
+/// Stages of escalating unstucking tried in order.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum UnstuckStage {
+    /// Moving out of the minimap edges.
+    #[default]
+    Reposition,
+    /// Pressing ESC to close any dialog.
+    Esc,
+    /// Jumping in random directions.
+    RandomJump,
+    /// Going to town for navigation to bring the player back.
+    ///
+    /// Only used when the current map has an attached navigation path.
+    ReturnToTown,
+}
+
+impl UnstuckStage {
+    pub fn max_attempts(self, limits: &UnstuckLimits) -> u32 {
+        match self {
+            UnstuckStage::Reposition => limits.reposition_attempts,
+            UnstuckStage::Esc => limits.esc_attempts,
+            UnstuckStage::RandomJump => limits.random_jump_attempts,
+            UnstuckStage::ReturnToTown => limits.return_to_town_attempts,
+        }
+    }
+
+    pub fn next(self) -> Option<UnstuckStage> {
+        match self {
+            UnstuckStage::Reposition => Some(UnstuckStage::Esc),
+            UnstuckStage::Esc => Some(UnstuckStage::RandomJump),
+            UnstuckStage::RandomJump => Some(UnstuckStage::ReturnToTown),
+            UnstuckStage::ReturnToTown => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum UnstuckingKind {
     Esc,
@@ -40,6 +79,31 @@ impl Unstucking {
     }
 }
 
+/// Escalates to the next unstucking stage and returns the [`Player`] state to transition to.
+///
+/// Returns [`Player::Idle`] when all stages have been exhausted so that the bot can notify and
+/// halt. Returning to town falls back to jumping randomly when limited by
+/// [`crate::RiskyActionLimits`].
+pub fn next_unstucking_state(resources: &Resources, context: &mut PlayerContext) -> Player {
+    match context.track_unstucking_transitioned(resources.tick) {
+        Some(UnstuckStage::Reposition) => {
+            Player::Unstucking(Unstucking::new_movement(Timeout::default(), false))
+        }
+        Some(UnstuckStage::Esc) => Player::Unstucking(Unstucking::new_esc()),
+        Some(UnstuckStage::RandomJump) => {
+            Player::Unstucking(Unstucking::new_movement(Timeout::default(), true))
+        }
+        Some(UnstuckStage::ReturnToTown) => {
+            if context.try_use_risky_action(resources, RiskyAction::GoToTown) {
+                Player::Panicking(Panicking::new(PanicTo::Town))
+            } else {
+                Player::Unstucking(Unstucking::new_movement(Timeout::default(), true))
+            }
+        }
+        None => Player::Idle,
+    }
+}
+
 /// A threshold to consider spamming falling action
 ///
 /// This is when the player is inside the top edge of minimap. At least for higher level maps, this
@@ -51,13 +115,11 @@ const Y_IGNORE_THRESHOLD: i32 = 18;
 /// This state can only be transitioned to when [`PlayerState::unstuck_counter`] reached the fixed
 /// threshold, when the player moved into the edges of the minimap or rotator detected an UI
 /// element blocking the player.
-/// If [`PlayerState::unstuck_consecutive_counter`] has not reached the threshold and the player
-/// moved into the left/right/top edges of the minimap, it will try to move
-/// out as appropriate. It will also try to press ESC key to exit any dialog.
 ///
-/// Each initial transition to [`Player::Unstucking`] increases
-/// the [`PlayerState::unstuck_consecutive_counter`] by one. If the threshold is reached, this
-/// state will just jump in random direction.
+/// Each transition escalates through the [`UnstuckStage`]s using [`next_unstucking_state`]. When
+/// repositioning and the player moved into the left/right/top edges of the minimap, it will try
+/// to move out as appropriate. The later stages press ESC key to exit any dialog and then just
+/// jump in random direction.
 pub fn update_unstucking_state(
     resources: &Resources,
     player: &mut PlayerEntity,
@@ -86,7 +148,7 @@ pub fn update_unstucking_state(
                 .map(|pos| Point::new(pos.x, idle.bbox.height - pos.y));
             let random = random || pos.is_none();
 
-            match next_timeout_lifecycle(timeout, MOVE_TIMEOUT) {
+            match next_timeout_lifecycle(timeout, tick::scaled(MOVE_TIMEOUT)) {
                 Lifecycle::Started(timeout) => {
                     let to_right = match (random, pos) {
                         (true, _) => resources.rng.random_bool(0.5),
@@ -100,9 +162,9 @@ pub fn update_unstucking_state(
                         (_, None) => unreachable!(),
                     };
                     if to_right {
-                        resources.input.send_key_down(KeyKind::Right);
+                        resources.input.send_key_down(context.config.right_key);
                     } else {
-                        resources.input.send_key_up(KeyKind::Left);
+                        resources.input.send_key_up(context.config.left_key);
                     }
 
                     transition!(
@@ -111,8 +173,8 @@ pub fn update_unstucking_state(
                     );
                 }
                 Lifecycle::Ended => transition!(player, Player::Detecting, {
-                    resources.input.send_key_up(KeyKind::Right);
-                    resources.input.send_key_up(KeyKind::Left);
+                    resources.input.send_key_up(context.config.right_key);
+                    resources.input.send_key_up(context.config.left_key);
                 }),
                 Lifecycle::Updated(timeout) => transition!(
                     player,
@@ -14,6 +14,7 @@ use crate::{
         MOVE_TIMEOUT, PlayerAction, PlayerEntity, actions::update_from_auto_mob_action,
         next_action, state::LastMovement, timeout::ChangeAxis, transition_to_moving,
     },
+    tick,
 };
 
 /// Number of ticks to wait before spamming jump key.
@@ -34,10 +35,10 @@ const X_NEAR_STATIONARY_THRESHOLD: f32 = 0.28;
 const Y_NEAR_STATIONARY_VELOCITY_THRESHOLD: f32 = 0.4;
 
 /// Minimum distance required to perform an up jump using teleport key with jump.
-const TELEPORT_WITH_JUMP_THRESHOLD: i32 = 20;
+pub const TELEPORT_WITH_JUMP_THRESHOLD: i32 = 20;
 
 /// Minimum distance required to perform an up jump and then teleport.
-const UP_JUMP_AND_TELEPORT_THRESHOLD: i32 = 23;
+pub const UP_JUMP_AND_TELEPORT_THRESHOLD: i32 = 23;
 
 const SOFT_UP_JUMP_THRESHOLD: i32 = 16;
 
@@ -78,14 +79,15 @@ impl UpJumping {
         let spam_delay = if !player_context.config.up_jump_specific_key_should_jump
             && y_distance <= SOFT_UP_JUMP_THRESHOLD
         {
-            SOFT_SPAM_DELAY
+            tick::scaled(SOFT_SPAM_DELAY)
         } else {
-            SPAM_DELAY
+            tick::scaled(SPAM_DELAY)
         };
         let auto_mob_wait_completion =
             player_context.has_auto_mob_action_only() && resources.rng.random_bool(0.5);
         let kind = up_jumping_kind(
             player_context.config.up_jump_key,
+            player_context.config.up_key,
             player_context.config.teleport_key.is_some(),
         );
 
@@ -123,6 +125,7 @@ pub fn update_up_jumping_state(
     let jump_key = player.context.config.jump_key;
     let should_jump = player.context.config.up_jump_specific_key_should_jump;
     let is_flight = player.context.config.up_jump_is_flight;
+    let up_key = player.context.config.up_key;
 
     match next_moving_lifecycle_with_axis(
         up_jumping.moving,
@@ -130,7 +133,7 @@ pub fn update_up_jumping_state(
             .context
             .last_known_pos
             .expect("in positional context"),
-        TIMEOUT,
+        tick::scaled(TIMEOUT),
         ChangeAxis::Vertical,
     ) {
         MovingLifecycle::Started(moving) => {
@@ -155,8 +158,8 @@ pub fn update_up_jumping_state(
             match &mut up_jumping.kind {
                 UpJumpingKind::Mage(mage) => {
                     let (y_distance, _) = moving.y_distance_direction_from(true, moving.pos);
-                    let teleport_after_up_jump =
-                        !is_flight && y_distance >= UP_JUMP_AND_TELEPORT_THRESHOLD;
+                    let teleport_after_up_jump = !is_flight
+                        && y_distance >= player.context.config.up_jump_and_teleport_threshold;
                     mage.state = if is_flight {
                         MageState::Flying
                     } else if teleport_after_up_jump {
@@ -165,9 +168,9 @@ pub fn update_up_jumping_state(
                         MageState::Teleporting
                     };
 
-                    resources.input.send_key_down(KeyKind::Up);
-                    let can_jump =
-                        y_distance >= TELEPORT_WITH_JUMP_THRESHOLD && up_jump_key.is_none();
+                    resources.input.send_key_down(up_key);
+                    let can_jump = y_distance >= player.context.config.teleport_with_jump_threshold
+                        && up_jump_key.is_none();
                     if is_flight || can_jump {
                         resources.input.send_key(jump_key);
                     }
@@ -176,11 +179,11 @@ pub fn update_up_jumping_state(
                     resources.input.send_key(jump_key);
                 }
                 UpJumpingKind::JumpKey => {
-                    resources.input.send_key_down(KeyKind::Up);
+                    resources.input.send_key_down(up_key);
                     resources.input.send_key(jump_key);
                 }
                 UpJumpingKind::SpecificKey => {
-                    resources.input.send_key_down(KeyKind::Up);
+                    resources.input.send_key_down(up_key);
                     if is_flight || should_jump {
                         resources.input.send_key(jump_key);
                     }
@@ -189,7 +192,7 @@ pub fn update_up_jumping_state(
             transition!(player, Player::UpJumping(up_jumping.moving(moving)));
         }
         MovingLifecycle::Ended(moving) => transition_to_moving!(player, moving, {
-            resources.input.send_key_up(KeyKind::Up);
+            resources.input.send_key_up(up_key);
         }),
         MovingLifecycle::Updated(mut moving) => {
             let cur_pos = moving.pos;
@@ -214,7 +217,7 @@ pub fn update_up_jumping_state(
                             && moving.is_destination_intermediate()
                             && y_direction <= 0,
                         {
-                            resources.input.send_key_up(KeyKind::Up);
+                            resources.input.send_key_up(up_key);
                         }
                     );
                     transition_if!(up_jumping.auto_mob_wait_completion && !moving.completed);
@@ -267,7 +270,9 @@ pub fn update_up_jumping_state(
                         ..
                     })
                     | PlayerAction::Move(_)
-                    | PlayerAction::SolveRune,
+                    | PlayerAction::SolveRune
+                    | PlayerAction::EnterPortal(_)
+                    | PlayerAction::Shop(_),
                 ) => (),
                 _ => unreachable!(),
             }
@@ -289,7 +294,7 @@ fn update_up_jump(
     let is_flight = context.config.up_jump_is_flight;
 
     if moving.completed {
-        resources.input.send_key_up(KeyKind::Up);
+        resources.input.send_key_up(context.config.up_key);
         return;
     }
 
@@ -312,7 +317,7 @@ fn update_up_jump(
                 // doesn't work.
                 if moving.timeout.total >= up_jumping.spam_delay {
                     if matches!(up_jumping.kind, UpJumpingKind::UpArrow) {
-                        resources.input.send_key(KeyKind::Up);
+                        resources.input.send_key(context.config.up_key);
                     } else {
                         resources.input.send_key(jump_key);
                     }
@@ -356,7 +361,7 @@ fn update_mage_up_jump(
 
     match mage.state {
         MageState::Teleporting => {
-            if y_direction > 0 && y_distance < TELEPORT_WITH_JUMP_THRESHOLD {
+            if y_direction > 0 && y_distance < context.config.teleport_with_jump_threshold {
                 resources.input.send_key(teleport_key);
                 moving.completed = true;
             }
@@ -398,12 +403,16 @@ fn update_flying(resources: &Resources, moving: &mut Moving, y_direction: i32, k
 }
 
 #[inline]
-fn up_jumping_kind(up_jump_key: Option<KeyKind>, has_teleport_key: bool) -> UpJumpingKind {
+fn up_jumping_kind(
+    up_jump_key: Option<KeyKind>,
+    up_key: KeyKind,
+    has_teleport_key: bool,
+) -> UpJumpingKind {
     match (up_jump_key, has_teleport_key) {
         (Some(_), true) | (None, true) => UpJumpingKind::Mage(Mage {
             state: MageState::Teleporting, // Overwrite later
         }),
-        (Some(KeyKind::Up), false) => UpJumpingKind::UpArrow,
+        (Some(key), false) if key == up_key => UpJumpingKind::UpArrow,
         (None, false) => UpJumpingKind::JumpKey,
         (Some(_), false) => UpJumpingKind::SpecificKey,
     }
@@ -7,6 +7,7 @@ use crate::{
         timeout::{Lifecycle, next_timeout_lifecycle},
         transition_from_action,
     },
+    tick,
 };
 
 /// States of using booster.
@@ -91,7 +92,7 @@ fn update_using(resources: &Resources, using: &mut UsingBooster, key: KeyKind) {
         panic!("using booster state is not using")
     };
 
-    match next_timeout_lifecycle(timeout, 60) {
+    match next_timeout_lifecycle(timeout, tick::scaled(60)) {
         Lifecycle::Started(timeout) => transition!(using, State::Using(timeout)),
         Lifecycle::Ended => transition_if!(
             using,
@@ -104,7 +105,7 @@ fn update_using(resources: &Resources, using: &mut UsingBooster, key: KeyKind) {
             resources.detector().detect_admin_visible()
         ),
         Lifecycle::Updated(timeout) => transition!(using, State::Using(timeout), {
-            if timeout.current == PRESS_KEY_AT {
+            if timeout.current == tick::scaled(PRESS_KEY_AT) {
                 resources.input.send_key(key);
             }
         }),
@@ -116,7 +117,7 @@ fn update_confirming(resources: &Resources, using: &mut UsingBooster) {
         panic!("using booster state is not confirming")
     };
 
-    match next_timeout_lifecycle(timeout, 30) {
+    match next_timeout_lifecycle(timeout, tick::scaled(30)) {
         Lifecycle::Started(timeout) => transition!(using, State::Confirming(timeout), {
             resources.input.send_key(KeyKind::Left);
         }),
@@ -133,7 +134,7 @@ fn update_confirming(resources: &Resources, using: &mut UsingBooster) {
         ),
         Lifecycle::Updated(timeout) => {
             transition!(using, State::Confirming(timeout), {
-                if timeout.current == 15 {
+                if timeout.current == tick::scaled(15) {
                     resources.input.send_key(KeyKind::Left);
                 }
             });
@@ -151,7 +152,7 @@ fn update_completing(resources: &Resources, using: &mut UsingBooster) {
         panic!("using booster state is not completing")
     };
 
-    match next_timeout_lifecycle(timeout, 20) {
+    match next_timeout_lifecycle(timeout, tick::scaled(20)) {
         Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
             transition!(
                 using,
@@ -1,14 +1,20 @@
 use std::assert_matches::assert_matches;
 
+use log::info;
+use opencv::core::Rect;
+
 use super::{
     AutoMob, PingPongDirection, PlayerContext, Timeout,
-    actions::{Key, PingPong, PlayerAction, update_from_ping_pong_action},
+    actions::{Key, LinkKeyStep, PingPong, PlayerAction, update_from_ping_pong_action},
     double_jump::DoubleJumping,
     timeout::{Lifecycle, next_timeout_lifecycle},
 };
 use crate::{
-    ActionKeyDirection, ActionKeyWith, Position, WaitAfterBuffered,
+    ActionKeyCastVerification, ActionKeyDirection, ActionKeyWith, MAX_LINK_KEY_CHAIN_STEPS,
+    Position, WaitAfterBuffered,
+    array::Array,
     bridge::{InputKeyDownOptions, KeyKind, LinkKeyKind},
+    detect::RegionSignature,
     ecs::{Resources, transition, transition_if},
     minimap::Minimap,
     player::{
@@ -16,7 +22,7 @@ use crate::{
         state::{BufferedStalling, BufferedStallingCallback},
         transition_from_action,
     },
-    run::MS_PER_TICK,
+    tick::{self, ticks_from_millis},
 };
 
 /// The total number of ticks for changing direction before timing out.
@@ -25,6 +31,14 @@ const CHANGE_DIRECTION_TIMEOUT: u32 = 3;
 /// The tick to which the actual key will be pressed for [`LinkKeyKind::Along`].
 const LINK_ALONG_PRESS_TICK: u32 = 2;
 
+/// The minimum total number of ticks to wait for the verification region to change before
+/// re-casting.
+const CAST_VERIFICATION_TIMEOUT: u32 = 15;
+
+/// The minimum [`RegionSignature::difference`] for the verification region to be considered
+/// changed.
+const CAST_VERIFICATION_CHANGE_THRESHOLD: u32 = 10;
+
 #[derive(Clone, Copy, Debug)]
 enum ActionInfo {
     AutoMobbing { should_terminate: bool },
@@ -45,11 +59,22 @@ enum State {
     /// Returns to [`State::Precondition`] if player is stationary or
     /// transfers to [`Player::DoubleJumping`].
     EnsuringUseWith,
+    /// Waits for [`UseKey::double_jump_apex_ticks`] after double jumping while keeping the
+    /// facing direction.
+    ///
+    /// Returns to [`State::Precondition`] upon timeout.
+    WaitingDoubleJumpApex(Timeout),
     /// Uses the actual key with optional [`LinkKeyKind`], [`UseKey::key_hold_ticks`] and stalls
     /// for [`UseKey::wait_after_use_ticks`].
     Using(Using),
     /// Ensures all [`UseKey::count`] times executed.
     Postcondition,
+    /// Waits for the [`CastVerification::region`] to change right after the last key use while
+    /// also stalling for [`UseKey::wait_after_use_ticks`].
+    ///
+    /// The [`bool`] indicates whether the region has changed. Returns to [`State::Precondition`]
+    /// to re-cast the key upon timeout.
+    Verifying(Timeout, bool),
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -58,6 +83,38 @@ struct Using {
     link_completed: bool,
     hold_timeout: Timeout,
     hold_completed: bool,
+    /// The index of the next [`UseKey::link_key_chain`] step.
+    chain_index: usize,
+    chain_timeout: Timeout,
+}
+
+/// Verifies that a key was actually cast by comparing pixels of a region before and after use.
+#[derive(Clone, Copy, Debug)]
+struct CastVerification {
+    region: Rect,
+    max_recast_count: u32,
+    recast_count: u32,
+    /// The [`RegionSignature`] captured before the first key use.
+    baseline: Option<RegionSignature>,
+}
+
+impl From<ActionKeyCastVerification> for CastVerification {
+    fn from(
+        ActionKeyCastVerification {
+            x,
+            y,
+            width,
+            height,
+            max_recast_count,
+        }: ActionKeyCastVerification,
+    ) -> Self {
+        Self {
+            region: Rect::new(x, y, width, height),
+            max_recast_count,
+            recast_count: 0,
+            baseline: None,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -73,7 +130,10 @@ pub struct UseKey {
     key: KeyKind,
     key_hold_ticks: u32,
     key_hold_buffered_to_wait_after: bool,
+    /// Whether to move in [`Self::direction`] while holding the key.
+    key_hold_moving: bool,
     link_key: LinkKeyKind,
+    link_key_chain: Array<LinkKeyStep, MAX_LINK_KEY_CHAIN_STEPS>,
     count: u32,
     current_count: u32,
     direction: ActionKeyDirection,
@@ -82,7 +142,17 @@ pub struct UseKey {
     wait_after_use_ticks: u32,
     wait_after_buffered: WaitAfterBuffered,
     pending_transition: PendingTransition,
+    /// The ticks to wait after double jumping before using the key.
+    double_jump_apex_ticks: u32,
+    /// Whether [`Self::double_jump_apex_ticks`] has passed since the last double jump.
+    double_jump_apex_reached: bool,
+    /// The facing direction locked from the start of [`State::WaitingDoubleJumpApex`] until the
+    /// action completes.
+    ///
+    /// [`ActionKeyDirection::Any`] if not locked.
+    direction_lock: ActionKeyDirection,
     action_info: Option<ActionInfo>,
+    verification: Option<CastVerification>,
     state: State,
 }
 
@@ -92,15 +162,19 @@ impl UseKey {
             key,
             key_hold_ticks,
             key_hold_buffered_to_wait_after,
+            key_hold_moving,
             link_key,
+            link_key_chain,
             count,
             direction,
             with,
+            double_jump_apex_ticks,
             wait_before_use_ticks,
             wait_before_use_ticks_random_range,
             wait_after_use_ticks,
             wait_after_use_ticks_random_range,
             wait_after_buffered,
+            cast_verification,
             ..
         } = key;
         let wait_before =
@@ -111,7 +185,9 @@ impl UseKey {
             key,
             key_hold_ticks,
             key_hold_buffered_to_wait_after,
+            key_hold_moving,
             link_key,
+            link_key_chain,
             count,
             current_count: 0,
             direction,
@@ -120,7 +196,11 @@ impl UseKey {
             wait_after_use_ticks: wait_after,
             wait_after_buffered,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
             action_info: None,
+            verification: cast_verification.map(CastVerification::from),
             state: State::Precondition,
         }
     }
@@ -138,7 +218,9 @@ impl UseKey {
             key: mob.key,
             key_hold_ticks: mob.key_hold_ticks,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: mob.link_key,
+            link_key_chain: Array::new(),
             count: mob.count,
             current_count: 0,
             direction,
@@ -147,7 +229,11 @@ impl UseKey {
             wait_after_use_ticks: wait_after,
             wait_after_buffered: WaitAfterBuffered::None,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
             action_info: Some(ActionInfo::AutoMobbing { should_terminate }),
+            verification: None,
             state: State::Precondition,
         }
     }
@@ -161,17 +247,19 @@ impl UseKey {
             ping_pong.wait_after_ticks,
             ping_pong.wait_after_ticks_random_range,
         );
-        let direction = if matches!(ping_pong.direction, PingPongDirection::Left) {
-            ActionKeyDirection::Left
-        } else {
-            ActionKeyDirection::Right
+        let direction = match ping_pong.direction {
+            PingPongDirection::Left => ActionKeyDirection::Left,
+            PingPongDirection::Right => ActionKeyDirection::Right,
+            PingPongDirection::Up | PingPongDirection::Down => ActionKeyDirection::Any,
         };
 
         Self {
             key: ping_pong.key,
             key_hold_ticks: ping_pong.key_hold_ticks,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: ping_pong.link_key,
+            link_key_chain: Array::new(),
             count: ping_pong.count,
             current_count: 0,
             direction,
@@ -180,7 +268,11 @@ impl UseKey {
             wait_after_use_ticks: wait_after,
             wait_after_buffered: WaitAfterBuffered::None,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
             action_info: None,
+            verification: None,
             state: State::Precondition,
         }
     }
@@ -200,6 +292,40 @@ impl UseKey {
             && self.has_wait_after_buffer()
             && self.key_hold_buffered_to_wait_after
     }
+
+    /// The direction key to hold along with the key if [`Self::key_hold_moving`] is enabled.
+    fn key_hold_moving_key(&self, context: &PlayerContext) -> Option<KeyKind> {
+        if !self.key_hold_moving || self.key_hold_ticks == 0 {
+            return None;
+        }
+        if self.is_direction_locked() && self.direction_lock != self.direction {
+            return None;
+        }
+
+        match self.direction {
+            ActionKeyDirection::Left => Some(context.config.left_key),
+            ActionKeyDirection::Right => Some(context.config.right_key),
+            ActionKeyDirection::Any => None,
+        }
+    }
+
+    #[inline]
+    fn is_direction_locked(&self) -> bool {
+        self.direction_lock != ActionKeyDirection::Any
+    }
+
+    fn should_wait_double_jump_apex(&self) -> bool {
+        matches!(self.with, ActionKeyWith::DoubleJump)
+            && self.double_jump_apex_ticks > 0
+            && !self.double_jump_apex_reached
+    }
+
+    fn should_verify(&self) -> bool {
+        self.is_last_key_use()
+            && self
+                .verification
+                .is_some_and(|verification| verification.baseline.is_some())
+    }
 }
 
 /// Updates the [`Player::UseKey`] contextual state.
@@ -235,6 +361,9 @@ pub fn update_use_key_state(
         State::ChangingDirection(_) => {
             update_changing_direction(resources, &mut player.context, &mut use_key);
         }
+        State::WaitingDoubleJumpApex(_) => {
+            update_waiting_double_jump_apex(resources, &player.context, &mut use_key);
+        }
         #[allow(unused_assignments)]
         State::EnsuringUseWith => {
             update_ensuring_use_with(&player.context, &mut use_key);
@@ -257,6 +386,7 @@ pub fn update_use_key_state(
             );
         }
         State::Using(_) => {
+            update_verification_baseline(resources, &mut use_key);
             update_using(resources, &player.context, &mut use_key);
 
             let has_transition = matches!(use_key.pending_transition, PendingTransition::WaitAfter);
@@ -270,13 +400,11 @@ pub fn update_use_key_state(
                 } else {
                     use_key.wait_after_use_ticks
                 };
+                let moving_key = use_key.key_hold_moving_key(&player.context);
                 let update_callback = if should_buffer_holding {
                     Some(BufferedStallingCallback::new(
                         move |resources: &Resources| {
-                            resources.input.send_key_down_with_options(
-                                use_key.key,
-                                InputKeyDownOptions::default().repeatable(),
-                            );
+                            send_holding_key_down(resources, &use_key, moving_key);
                         },
                     ))
                 } else {
@@ -285,7 +413,7 @@ pub fn update_use_key_state(
                 let end_callback = if should_buffer_holding {
                     Some(BufferedStallingCallback::new(
                         move |resources: &Resources| {
-                            resources.input.send_key_up(use_key.key);
+                            send_holding_key_up(resources, &use_key, moving_key);
                         },
                     ))
                 } else {
@@ -304,6 +432,9 @@ pub fn update_use_key_state(
                 };
                 player.context.stalling_timeout_buffered_update_callback = update_callback;
                 player.context.stalling_timeout_buffered_end_callback = end_callback;
+            } else if has_transition && use_key.should_verify() {
+                use_key.pending_transition = PendingTransition::None;
+                use_key.state = State::Verifying(Timeout::default(), false);
             } else {
                 transition_if!(
                     player,
@@ -323,6 +454,7 @@ pub fn update_use_key_state(
                 use_key.state = State::Precondition;
             }
         }
+        State::Verifying(_, _) => update_verifying(resources, &mut use_key),
     };
 
     let player_next_state = if use_key.current_count >= use_key.count {
@@ -353,7 +485,7 @@ pub fn update_use_key_state(
                 .auto_mob_track_ignore_xs(minimap_state, false);
             transition_if!(
                 player,
-                Player::Stalling(Timeout::default(), MOVE_TIMEOUT),
+                Player::Stalling(Timeout::default(), tick::scaled(MOVE_TIMEOUT)),
                 player.context.auto_mob_reachable_y_require_update(y)
             );
 
@@ -366,6 +498,10 @@ pub fn update_use_key_state(
             assert!(use_key.wait_after_buffered == WaitAfterBuffered::None);
 
             transition_if!(player, player_next_state, !is_terminal);
+            // Vertical ping pong only uses key at the bounced edge
+            if ping_pong.direction.is_vertical() {
+                transition_from_action!(player, player_next_state);
+            }
 
             player.context.clear_unstucking(true);
             update_from_ping_pong_action(
@@ -395,7 +531,7 @@ fn update_precondition(resources: &Resources, context: &mut PlayerContext, use_k
     transition_if!(
         use_key,
         State::ChangingDirection(Timeout::default()),
-        !ensure_direction(context, use_key.direction)
+        !use_key.is_direction_locked() && !ensure_direction(context, use_key.direction)
     );
 
     transition_if!(
@@ -404,6 +540,12 @@ fn update_precondition(resources: &Resources, context: &mut PlayerContext, use_k
         !ensure_use_with(context, use_key.with)
     );
 
+    transition_if!(
+        use_key,
+        State::WaitingDoubleJumpApex(Timeout::default()),
+        use_key.should_wait_double_jump_apex()
+    );
+
     transition_if!(
         use_key,
         State::Using(Using::default()),
@@ -435,66 +577,158 @@ fn ensure_use_with(context: &PlayerContext, with: ActionKeyWith) -> bool {
 }
 
 fn update_using(resources: &Resources, context: &PlayerContext, use_key: &mut UseKey) {
-    let State::Using(using) = use_key.state else {
+    let State::Using(mut using) = use_key.state else {
         panic!("use key state is not using");
     };
+    let link_key_timing_millis = context.config.link_key_timing_millis;
+
+    // Chained link keys are used after the before link key
+    if !matches!(use_key.link_key, LinkKeyKind::Before(_)) || using.link_completed {
+        if let Some(step) = next_link_key_step(use_key)
+            && matches!(step.link_key, LinkKeyKind::Before(_))
+        {
+            return update_chained_linking_key(resources, use_key, step, link_key_timing_millis);
+        }
+
+        while let Some(LinkKeyStep {
+            link_key: LinkKeyKind::AtTheSame(key),
+            ..
+        }) = next_link_key_step(use_key)
+        {
+            resources.input.send_key(key);
+            using.chain_index += 1;
+            use_key.state = State::Using(using);
+        }
+    }
 
     match use_key.link_key {
         LinkKeyKind::After(_) => {
             if !using.hold_completed {
-                update_holding_key(resources, use_key);
+                update_holding_key(resources, context, use_key);
                 transition_if!(use_key.key_hold_ticks > 0);
             }
 
             if !using.link_completed {
-                return update_linking_key(
-                    resources,
-                    use_key,
-                    context.config.link_key_timing_millis,
-                );
+                return update_linking_key(resources, use_key, link_key_timing_millis);
             }
         }
         LinkKeyKind::AtTheSame(key) => {
             resources.input.send_key(key);
             if !using.hold_completed {
-                update_holding_key(resources, use_key);
+                update_holding_key(resources, context, use_key);
                 transition_if!(use_key.key_hold_ticks > 0);
             }
         }
         LinkKeyKind::Along(_) => {
             if !using.link_completed {
-                return update_linking_key(
-                    resources,
-                    use_key,
-                    context.config.link_key_timing_millis,
-                );
+                return update_linking_key(resources, use_key, link_key_timing_millis);
             }
         }
         LinkKeyKind::Before(_) | LinkKeyKind::None => {
             if matches!(use_key.link_key, LinkKeyKind::Before(_)) && !using.link_completed {
-                return update_linking_key(
-                    resources,
-                    use_key,
-                    context.config.link_key_timing_millis,
-                );
+                return update_linking_key(resources, use_key, link_key_timing_millis);
             }
 
             if !using.hold_completed {
-                update_holding_key(resources, use_key);
+                update_holding_key(resources, context, use_key);
                 transition_if!(use_key.key_hold_ticks > 0);
             }
         }
     }
 
+    // Only after link keys are left in the chain
+    if let Some(step) = next_link_key_step(use_key) {
+        return update_chained_linking_key(resources, use_key, step, link_key_timing_millis);
+    }
+
+    let has_wait_after = use_key.wait_after_use_ticks > 0 || use_key.should_buffer_key_holding();
     transition_if!(
         use_key,
-        State::Postcondition,
-        use_key.wait_after_use_ticks == 0 && !use_key.should_buffer_key_holding()
+        State::Verifying(Timeout::default(), false),
+        !has_wait_after && use_key.should_verify()
     );
+    transition_if!(use_key, State::Postcondition, !has_wait_after);
 
     use_key.pending_transition = PendingTransition::WaitAfter;
 }
 
+/// Captures the verification baseline before the key is first used.
+///
+/// Verification is skipped when the wait after is buffered because the player leaves
+/// [`Player::UseKey`] right after the last key use.
+fn update_verification_baseline(resources: &Resources, use_key: &mut UseKey) {
+    let Some(verification) = use_key.verification.as_mut() else {
+        return;
+    };
+    if verification.baseline.is_some() {
+        return;
+    }
+
+    verification.baseline = resources
+        .detector
+        .as_ref()
+        .and_then(|detector| detector.detect_region_signature(verification.region).ok());
+}
+
+fn update_verifying(resources: &Resources, use_key: &mut UseKey) {
+    let State::Verifying(timeout, changed) = use_key.state else {
+        panic!("use key state is not verifying");
+    };
+    let mut verification = use_key.verification.expect("has verification");
+    let baseline = verification.baseline.expect("has verification baseline");
+    let changed = changed
+        || resources
+            .detector
+            .as_ref()
+            .and_then(|detector| detector.detect_region_signature(verification.region).ok())
+            .is_some_and(|signature| {
+                signature.difference(&baseline) >= CAST_VERIFICATION_CHANGE_THRESHOLD
+            });
+    let max_timeout = use_key
+        .wait_after_use_ticks
+        .max(tick::scaled(CAST_VERIFICATION_TIMEOUT));
+    match next_timeout_lifecycle(timeout, max_timeout) {
+        Lifecycle::Started(timeout) | Lifecycle::Updated(timeout) => {
+            // Keeps stalling for the wait after even when the cast is already verified
+            transition_if!(
+                changed && timeout.current >= use_key.wait_after_use_ticks,
+                {
+                    complete_verifying(use_key, verification);
+                }
+            );
+            transition!(use_key, State::Verifying(timeout, changed))
+        }
+        Lifecycle::Ended => {
+            transition_if!(changed, {
+                complete_verifying(use_key, verification);
+            });
+
+            verification.baseline = None;
+            if verification.recast_count >= verification.max_recast_count {
+                verification.recast_count = 0;
+                use_key.verification = Some(verification);
+                use_key.current_count = use_key.count;
+                return;
+            }
+
+            verification.recast_count += 1;
+            info!(target: "player", "key {:?} cast not verified, re-casting", use_key.key);
+            use_key.verification = Some(verification);
+            use_key.state = State::Precondition;
+        }
+    }
+}
+
+#[inline]
+fn complete_verifying(use_key: &mut UseKey, verification: CastVerification) {
+    use_key.verification = Some(CastVerification {
+        recast_count: 0,
+        baseline: None,
+        ..verification
+    });
+    use_key.current_count = use_key.count;
+}
+
 fn update_ensuring_use_with(context: &PlayerContext, use_key: &mut UseKey) {
     match use_key.with {
         ActionKeyWith::Any => unreachable!(),
@@ -510,6 +744,43 @@ fn update_ensuring_use_with(context: &PlayerContext, use_key: &mut UseKey) {
     }
 }
 
+/// Waits for the player to reach the apex of the double jump before using the key.
+///
+/// The direction key opposite to [`PlayerContext::last_known_direction`] is released upon
+/// starting so that a direction key still held down from moving does not turn the player around
+/// mid-air. The direction is then locked in [`UseKey::direction_lock`] so that no direction key
+/// is pressed until the action completes.
+fn update_waiting_double_jump_apex(
+    resources: &Resources,
+    context: &PlayerContext,
+    use_key: &mut UseKey,
+) {
+    let State::WaitingDoubleJumpApex(timeout) = use_key.state else {
+        panic!("use key state is not waiting double jump apex");
+    };
+
+    match next_timeout_lifecycle(timeout, use_key.double_jump_apex_ticks) {
+        Lifecycle::Started(timeout) => {
+            transition!(use_key, State::WaitingDoubleJumpApex(timeout), {
+                use_key.direction_lock = context.last_known_direction;
+                match context.last_known_direction {
+                    ActionKeyDirection::Left => {
+                        resources.input.send_key_up(context.config.right_key)
+                    }
+                    ActionKeyDirection::Right => {
+                        resources.input.send_key_up(context.config.left_key)
+                    }
+                    ActionKeyDirection::Any => (),
+                }
+            })
+        }
+        Lifecycle::Ended => transition!(use_key, State::Precondition, {
+            use_key.double_jump_apex_reached = true;
+        }),
+        Lifecycle::Updated(timeout) => transition!(use_key, State::WaitingDoubleJumpApex(timeout)),
+    }
+}
+
 fn update_changing_direction(
     resources: &Resources,
     context: &mut PlayerContext,
@@ -519,12 +790,12 @@ fn update_changing_direction(
         panic!("using key state is not changing direction");
     };
     let key = match use_key.direction {
-        ActionKeyDirection::Left => KeyKind::Left,
-        ActionKeyDirection::Right => KeyKind::Right,
+        ActionKeyDirection::Left => context.config.left_key,
+        ActionKeyDirection::Right => context.config.right_key,
         ActionKeyDirection::Any => unreachable!(),
     };
 
-    match next_timeout_lifecycle(timeout, CHANGE_DIRECTION_TIMEOUT) {
+    match next_timeout_lifecycle(timeout, tick::scaled(CHANGE_DIRECTION_TIMEOUT)) {
         Lifecycle::Started(timeout) => {
             transition_if!(
                 use_key,
@@ -543,10 +814,11 @@ fn update_changing_direction(
 }
 
 #[inline]
-fn update_holding_key(resources: &Resources, use_key: &mut UseKey) {
+fn update_holding_key(resources: &Resources, context: &PlayerContext, use_key: &mut UseKey) {
     let State::Using(using) = use_key.state else {
         panic!("use key state is not using");
     };
+    let moving_key = use_key.key_hold_moving_key(context);
 
     if use_key.key_hold_ticks == 0 {
         transition!(
@@ -571,10 +843,7 @@ fn update_holding_key(resources: &Resources, use_key: &mut UseKey) {
                     ..using
                 }),
                 {
-                    resources.input.send_key_down_with_options(
-                        use_key.key,
-                        InputKeyDownOptions::default().repeatable(),
-                    );
+                    send_holding_key_down(resources, use_key, moving_key);
                 }
             );
         }
@@ -585,10 +854,7 @@ fn update_holding_key(resources: &Resources, use_key: &mut UseKey) {
                 ..using
             }),
             {
-                resources.input.send_key_down_with_options(
-                    use_key.key,
-                    InputKeyDownOptions::default().repeatable(),
-                );
+                send_holding_key_down(resources, use_key, moving_key);
             }
         ),
         Lifecycle::Ended => {
@@ -599,13 +865,33 @@ fn update_holding_key(resources: &Resources, use_key: &mut UseKey) {
                     ..using
                 }),
                 {
-                    resources.input.send_key_up(use_key.key);
+                    send_holding_key_up(resources, use_key, moving_key);
                 }
             );
         }
     }
 }
 
+/// Holds down the key and `moving_key` from [`UseKey::key_hold_moving_key`].
+#[inline]
+fn send_holding_key_down(resources: &Resources, use_key: &UseKey, moving_key: Option<KeyKind>) {
+    if let Some(key) = moving_key {
+        resources.input.send_key_down(key);
+    }
+    resources
+        .input
+        .send_key_down_with_options(use_key.key, InputKeyDownOptions::default().repeatable());
+}
+
+/// Releases the key and `moving_key` from [`UseKey::key_hold_moving_key`].
+#[inline]
+fn send_holding_key_up(resources: &Resources, use_key: &UseKey, moving_key: Option<KeyKind>) {
+    resources.input.send_key_up(use_key.key);
+    if let Some(key) = moving_key {
+        resources.input.send_key_up(key);
+    }
+}
+
 #[inline]
 fn update_linking_key(resources: &Resources, use_key: &mut UseKey, link_key_timing_millis: u64) {
     let State::Using(using) = use_key.state else {
@@ -617,7 +903,7 @@ fn update_linking_key(resources: &Resources, use_key: &mut UseKey, link_key_timi
     } else {
         1
     };
-    let link_key_timeout = ((link_key_timing_millis / MS_PER_TICK) as u32).max(min_timeout);
+    let link_key_timeout = ticks_from_millis(link_key_timing_millis).max(min_timeout);
 
     match next_timeout_lifecycle(using.link_timeout, link_key_timeout) {
         Lifecycle::Started(timeout) => transition!(
@@ -667,7 +953,7 @@ fn update_linking_key(resources: &Resources, use_key: &mut UseKey, link_key_timi
                 }),
                 {
                     if matches!(link_key, LinkKeyKind::Along(_))
-                        && timeout.total == LINK_ALONG_PRESS_TICK
+                        && timeout.total == tick::scaled(LINK_ALONG_PRESS_TICK)
                     {
                         resources.input.send_key(use_key.key);
                     }
@@ -677,6 +963,73 @@ fn update_linking_key(resources: &Resources, use_key: &mut UseKey, link_key_timi
     }
 }
 
+#[inline]
+fn next_link_key_step(use_key: &UseKey) -> Option<LinkKeyStep> {
+    let State::Using(using) = use_key.state else {
+        panic!("use key state is not using");
+    };
+
+    use_key
+        .link_key_chain
+        .as_slice()
+        .get(using.chain_index)
+        .copied()
+}
+
+/// Updates a chained before or after link `step` of [`UseKey::link_key_chain`].
+///
+/// A before key is used first and then waits for the step delay. An after key waits for the
+/// step delay and then is used.
+fn update_chained_linking_key(
+    resources: &Resources,
+    use_key: &mut UseKey,
+    step: LinkKeyStep,
+    link_key_timing_millis: u64,
+) {
+    let State::Using(using) = use_key.state else {
+        panic!("use key state is not using");
+    };
+    let delay_ticks = step
+        .delay_ticks
+        .unwrap_or_else(|| ticks_from_millis(link_key_timing_millis))
+        .max(1);
+
+    match next_timeout_lifecycle(using.chain_timeout, delay_ticks) {
+        Lifecycle::Started(timeout) => transition!(
+            use_key,
+            State::Using(Using {
+                chain_timeout: timeout,
+                ..using
+            }),
+            {
+                if let LinkKeyKind::Before(key) = step.link_key {
+                    resources.input.send_key(key);
+                }
+            }
+        ),
+        Lifecycle::Ended => transition!(
+            use_key,
+            State::Using(Using {
+                chain_index: using.chain_index + 1,
+                chain_timeout: Timeout::default(),
+                ..using
+            }),
+            {
+                if let LinkKeyKind::After(key) = step.link_key {
+                    resources.input.send_key(key);
+                }
+            }
+        ),
+        Lifecycle::Updated(timeout) => transition!(
+            use_key,
+            State::Using(Using {
+                chain_timeout: timeout,
+                ..using
+            })
+        ),
+    }
+}
+
 #[inline]
 fn random_wait_ticks(wait_base_ticks: u32, wait_random_range: u32) -> u32 {
     // TODO: Replace rand with Rng
@@ -690,20 +1043,27 @@ mod tests {
     use std::assert_matches::assert_matches;
 
     use mockall::{Sequence, predicate::eq};
-    use opencv::core::Point;
+    use opencv::core::{Point, Rect};
 
     use super::LinkKeyKind;
     use crate::{
         ActionKeyDirection, ActionKeyWith, WaitAfterBuffered,
+        array::Array,
         bridge::{InputKeyDownOptions, KeyKind, MockInput},
+        detect::{MockDetector, RegionSignature},
         ecs::Resources,
         minimap::Minimap,
         player::{
-            Player, PlayerContext, PlayerEntity, Timeout,
+            LastMovement, Player, PlayerContext, PlayerEntity, Timeout,
+            actions::LinkKeyStep,
             double_jump::DoubleJumping,
             state::BufferedStalling,
-            use_key::{PendingTransition, State, UseKey, Using, update_use_key_state},
+            use_key::{
+                CAST_VERIFICATION_TIMEOUT, CastVerification, PendingTransition, State, UseKey,
+                Using, update_use_key_state,
+            },
         },
+        tick,
     };
 
     fn make_player(use_key: UseKey) -> PlayerEntity {
@@ -720,7 +1080,9 @@ mod tests {
             key: KeyKind::A,
             key_hold_ticks: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
             count: 1,
             current_count: 0,
             direction: ActionKeyDirection::Any,
@@ -728,9 +1090,13 @@ mod tests {
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 0,
             action_info: None,
+            verification: None,
             state: State::Precondition,
             wait_after_buffered: WaitAfterBuffered::None,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
         });
 
         // Start EnsuringUseWith
@@ -762,7 +1128,9 @@ mod tests {
             key: KeyKind::A,
             key_hold_ticks: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
             count: 1,
             current_count: 0,
             direction: ActionKeyDirection::Any,
@@ -770,9 +1138,13 @@ mod tests {
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 0,
             action_info: None,
+            verification: None,
             state: State::Precondition,
             wait_after_buffered: WaitAfterBuffered::None,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
         });
         player.context.last_known_pos = Some(Point::default());
 
@@ -794,6 +1166,133 @@ mod tests {
         );
     }
 
+    #[test]
+    fn update_use_key_state_waiting_double_jump_apex() {
+        let mut keys = MockInput::new();
+        keys.expect_send_key_up().with(eq(KeyKind::Right)).once();
+        let resources = Resources::new(Some(keys), None);
+        let mut player = make_player(UseKey {
+            key: KeyKind::A,
+            key_hold_ticks: 0,
+            key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
+            link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
+            count: 1,
+            current_count: 0,
+            direction: ActionKeyDirection::Any,
+            with: ActionKeyWith::DoubleJump,
+            wait_before_use_ticks: 0,
+            wait_after_use_ticks: 0,
+            action_info: None,
+            verification: None,
+            state: State::Precondition,
+            wait_after_buffered: WaitAfterBuffered::None,
+            pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 2,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
+        });
+        player.context.last_movement = Some(LastMovement::DoubleJumping);
+        player.context.last_known_direction = ActionKeyDirection::Left;
+
+        // Start waiting instead of using right after double jumped
+        update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        assert_matches!(
+            player.state,
+            Player::UseKey(UseKey {
+                state: State::WaitingDoubleJumpApex(Timeout { started: false, .. }),
+                ..
+            })
+        );
+
+        // Releases the opposite direction key on started and waits until timeout
+        for _ in 0..3 {
+            update_use_key_state(&resources, &mut player, Minimap::Detecting);
+            assert_matches!(
+                player.state,
+                Player::UseKey(UseKey {
+                    state: State::WaitingDoubleJumpApex(Timeout { started: true, .. }),
+                    ..
+                })
+            );
+        }
+        update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        assert_matches!(
+            player.state,
+            Player::UseKey(UseKey {
+                state: State::Precondition,
+                double_jump_apex_reached: true,
+                direction_lock: ActionKeyDirection::Left,
+                ..
+            })
+        );
+
+        // Uses the key at apex
+        update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        assert_matches!(
+            player.state,
+            Player::UseKey(UseKey {
+                state: State::Using(_),
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn update_use_key_state_direction_locked_until_completed() {
+        let mut keys = MockInput::new();
+        keys.expect_send_key().never();
+        keys.expect_send_key_down().never();
+        keys.expect_send_key_down_with_options().once();
+        let resources = Resources::new(Some(keys), None);
+        let mut player = make_player(UseKey {
+            key: KeyKind::A,
+            key_hold_ticks: 2,
+            key_hold_buffered_to_wait_after: false,
+            key_hold_moving: true,
+            link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
+            count: 1,
+            current_count: 0,
+            direction: ActionKeyDirection::Right,
+            with: ActionKeyWith::DoubleJump,
+            wait_before_use_ticks: 0,
+            wait_after_use_ticks: 0,
+            action_info: None,
+            verification: None,
+            state: State::Precondition,
+            wait_after_buffered: WaitAfterBuffered::None,
+            pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 2,
+            double_jump_apex_reached: true,
+            direction_lock: ActionKeyDirection::Left,
+        });
+        player.context.last_movement = Some(LastMovement::DoubleJumping);
+        player.context.last_known_direction = ActionKeyDirection::Left;
+
+        // Does not change direction while locked
+        update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        assert_matches!(
+            player.state,
+            Player::UseKey(UseKey {
+                state: State::Using(_),
+                ..
+            })
+        );
+
+        // Holds the key without the moving key of the other direction
+        update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        assert_matches!(
+            player.state,
+            Player::UseKey(UseKey {
+                state: State::Using(_),
+                direction_lock: ActionKeyDirection::Left,
+                ..
+            })
+        );
+    }
+
     #[test]
     fn update_use_key_state_changing_direction() {
         let mut keys = MockInput::new();
@@ -808,7 +1307,9 @@ mod tests {
             key: KeyKind::A,
             key_hold_ticks: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
             count: 1,
             current_count: 0,
             direction: ActionKeyDirection::Left,
@@ -816,9 +1317,13 @@ mod tests {
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 0,
             action_info: None,
+            verification: None,
             state: State::Precondition,
             wait_after_buffered: WaitAfterBuffered::None,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
         };
         let mut player = make_player(use_key);
 
@@ -874,7 +1379,9 @@ mod tests {
             key: KeyKind::A,
             key_hold_ticks: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
             count: 3,
             current_count: 0,
             direction: ActionKeyDirection::Any,
@@ -882,9 +1389,13 @@ mod tests {
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 0,
             action_info: None,
+            verification: None,
             state: State::Precondition,
             wait_after_buffered: WaitAfterBuffered::None,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
         };
         let mut player = make_player(use_key);
 
@@ -929,7 +1440,9 @@ mod tests {
             key: KeyKind::A,
             key_hold_ticks: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
             count: 1,
             current_count: 0,
             direction: ActionKeyDirection::Any,
@@ -937,9 +1450,13 @@ mod tests {
             wait_before_use_ticks: 5,
             wait_after_use_ticks: 0,
             action_info: None,
+            verification: None,
             state: State::Precondition,
             wait_after_buffered: WaitAfterBuffered::None,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
         };
         let mut player = make_player(use_key);
 
@@ -951,6 +1468,9 @@ mod tests {
             Some(Player::UseKey(UseKey {
                 state: State::Using(_),
                 pending_transition: PendingTransition::None,
+                double_jump_apex_ticks: 0,
+                double_jump_apex_reached: false,
+                direction_lock: ActionKeyDirection::Any,
                 ..
             }))
         );
@@ -967,7 +1487,9 @@ mod tests {
             key: KeyKind::A,
             key_hold_ticks: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
             count: 1,
             current_count: 0,
             direction: ActionKeyDirection::Any,
@@ -975,9 +1497,13 @@ mod tests {
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 7,
             action_info: None,
+            verification: None,
             wait_after_buffered: WaitAfterBuffered::None,
             state: State::Using(Using::default()),
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
         };
         let mut player = make_player(use_key);
 
@@ -989,6 +1515,9 @@ mod tests {
             Some(Player::UseKey(UseKey {
                 state: State::Postcondition,
                 pending_transition: PendingTransition::None,
+                double_jump_apex_ticks: 0,
+                double_jump_apex_reached: false,
+                direction_lock: ActionKeyDirection::Any,
                 ..
             }))
         );
@@ -1015,7 +1544,9 @@ mod tests {
             key: KeyKind::A,
             key_hold_ticks: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::Along(KeyKind::Alt),
+            link_key_chain: Array::new(),
             count: 1,
             current_count: 0,
             direction: ActionKeyDirection::Any,
@@ -1023,9 +1554,13 @@ mod tests {
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 0,
             action_info: None,
+            verification: None,
             state: State::Using(Using::default()),
             wait_after_buffered: WaitAfterBuffered::None,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
         };
         let mut player = make_player(use_key);
 
@@ -1105,7 +1640,9 @@ mod tests {
             key: KeyKind::A,
             key_hold_ticks: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::Before(KeyKind::Alt),
+            link_key_chain: Array::new(),
             count: 1,
             current_count: 0,
             direction: ActionKeyDirection::Any,
@@ -1113,9 +1650,13 @@ mod tests {
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 0,
             action_info: None,
+            verification: None,
             state: State::Using(Using::default()),
             wait_after_buffered: WaitAfterBuffered::None,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
         };
         let mut player = make_player(use_key);
 
@@ -1142,6 +1683,76 @@ mod tests {
         update_use_key_state(&resources, &mut player, Minimap::Detecting);
     }
 
+    #[test]
+    fn update_use_key_state_link_key_chain() {
+        let mut sequence = Sequence::new();
+        let mut keys = MockInput::new();
+        for key in [KeyKind::Alt, KeyKind::B, KeyKind::C, KeyKind::A, KeyKind::D] {
+            keys.expect_send_key()
+                .withf(move |k| *k == key)
+                .once()
+                .in_sequence(&mut sequence);
+        }
+        let resources = Resources::new(Some(keys), None);
+        let use_key = UseKey {
+            key: KeyKind::A,
+            key_hold_ticks: 0,
+            key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
+            link_key: LinkKeyKind::Before(KeyKind::Alt),
+            link_key_chain: Array::from_iter([
+                LinkKeyStep {
+                    link_key: LinkKeyKind::Before(KeyKind::B),
+                    delay_ticks: Some(2),
+                },
+                LinkKeyStep {
+                    link_key: LinkKeyKind::AtTheSame(KeyKind::C),
+                    delay_ticks: None,
+                },
+                LinkKeyStep {
+                    link_key: LinkKeyKind::After(KeyKind::D),
+                    delay_ticks: None,
+                },
+            ]),
+            count: 1,
+            current_count: 0,
+            direction: ActionKeyDirection::Any,
+            with: ActionKeyWith::Any,
+            wait_before_use_ticks: 0,
+            wait_after_use_ticks: 0,
+            action_info: None,
+            verification: None,
+            state: State::Using(Using::default()),
+            wait_after_buffered: WaitAfterBuffered::None,
+            pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
+        };
+        let mut player = make_player(use_key);
+
+        for _ in 0..20 {
+            if !matches!(
+                player.state,
+                Player::UseKey(UseKey {
+                    state: State::Using(_),
+                    ..
+                })
+            ) {
+                break;
+            }
+            update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        }
+
+        assert_matches!(
+            player.state,
+            Player::UseKey(UseKey {
+                state: State::Postcondition,
+                ..
+            })
+        );
+    }
+
     #[test]
     fn update_use_key_state_link_key_after() {
         let mut sequence = Sequence::new();
@@ -1160,7 +1771,9 @@ mod tests {
             key: KeyKind::A,
             key_hold_ticks: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::After(KeyKind::Alt),
+            link_key_chain: Array::new(),
             count: 1,
             current_count: 0,
             direction: ActionKeyDirection::Any,
@@ -1168,9 +1781,13 @@ mod tests {
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 0,
             action_info: None,
+            verification: None,
             state: State::Using(Using::default()),
             wait_after_buffered: WaitAfterBuffered::None,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
         };
         let mut player = make_player(use_key);
 
@@ -1220,7 +1837,9 @@ mod tests {
             key: KeyKind::A,
             key_hold_ticks: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::AtTheSame(KeyKind::Alt),
+            link_key_chain: Array::new(),
             count: 1,
             current_count: 0,
             direction: ActionKeyDirection::Any,
@@ -1228,9 +1847,13 @@ mod tests {
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 0,
             action_info: None,
+            verification: None,
             state: State::Using(Using::default()),
             wait_after_buffered: WaitAfterBuffered::None,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
         };
         let mut player = make_player(use_key);
 
@@ -1266,7 +1889,9 @@ mod tests {
             key: KeyKind::A,
             key_hold_ticks: 3,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
             count: 1,
             current_count: 0,
             direction: ActionKeyDirection::Any,
@@ -1275,7 +1900,11 @@ mod tests {
             wait_after_use_ticks: 0,
             wait_after_buffered: WaitAfterBuffered::None,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
             action_info: None,
+            verification: None,
             state: State::Using(Using::default()),
         };
 
@@ -1287,6 +1916,52 @@ mod tests {
         update_use_key_state(&resources, &mut player, Minimap::Detecting);
     }
 
+    #[test]
+    fn update_use_key_state_hold_key_moving() {
+        let mut keys = MockInput::new();
+        keys.expect_send_key_down()
+            .with(eq(KeyKind::Right))
+            .times(3);
+        keys.expect_send_key_down_with_options()
+            .with(
+                eq(KeyKind::A),
+                eq(InputKeyDownOptions::default().repeatable()),
+            )
+            .times(3);
+        keys.expect_send_key_up().with(eq(KeyKind::A)).once();
+        keys.expect_send_key_up().with(eq(KeyKind::Right)).once();
+
+        let resources = Resources::new(Some(keys), None);
+        let use_key = UseKey {
+            key: KeyKind::A,
+            key_hold_ticks: 2,
+            key_hold_buffered_to_wait_after: false,
+            key_hold_moving: true,
+            link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
+            count: 1,
+            current_count: 0,
+            direction: ActionKeyDirection::Right,
+            with: ActionKeyWith::Any,
+            wait_before_use_ticks: 0,
+            wait_after_use_ticks: 0,
+            wait_after_buffered: WaitAfterBuffered::None,
+            pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
+            action_info: None,
+            verification: None,
+            state: State::Using(Using::default()),
+        };
+
+        let mut player = make_player(use_key);
+
+        for _ in 0..4 {
+            update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        }
+    }
+
     #[test]
     fn update_use_key_state_buffer_hold_key() {
         let mut sequence = Sequence::new();
@@ -1305,7 +1980,9 @@ mod tests {
             key: KeyKind::A,
             key_hold_ticks: 2,
             key_hold_buffered_to_wait_after: true,
+            key_hold_moving: false,
             link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
             count: 1,
             current_count: 0,
             direction: ActionKeyDirection::Any,
@@ -1314,7 +1991,11 @@ mod tests {
             wait_after_use_ticks: 4,
             wait_after_buffered: WaitAfterBuffered::Interruptible,
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
             action_info: None,
+            verification: None,
             state: State::Using(Using::default()),
         };
 
@@ -1385,6 +2066,7 @@ mod tests {
             current_count: 0,
             key_hold_ticks: 1,
             key_hold_buffered_to_wait_after: true,
+            key_hold_moving: false,
 
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 5,
@@ -1393,9 +2075,14 @@ mod tests {
             direction: ActionKeyDirection::Any,
             with: ActionKeyWith::Any,
             link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
 
             pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
             action_info: None,
+            verification: None,
             state: State::Using(Using::default()),
         };
 
@@ -1450,4 +2137,163 @@ mod tests {
                 .is_some()
         );
     }
+
+    #[test]
+    fn update_use_key_state_recasts_until_region_changes() {
+        let timeout = tick::scaled(CAST_VERIFICATION_TIMEOUT);
+        let mut detector = MockDetector::new();
+        let mut sequence = Sequence::new();
+        // Baseline, verifying until timed out and re-cast baseline
+        detector
+            .expect_detect_region_signature()
+            .times(1 + (timeout as usize + 2) + 1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(RegionSignature::filled(0)));
+        detector
+            .expect_detect_region_signature()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(RegionSignature::filled(255)));
+        let mut keys = MockInput::new();
+        keys.expect_send_key().with(eq(KeyKind::A)).times(2);
+        let resources = Resources::new(Some(keys), Some(detector));
+        let mut player = make_player(UseKey {
+            key: KeyKind::A,
+            key_hold_ticks: 0,
+            key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
+            link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
+            count: 1,
+            current_count: 0,
+            direction: ActionKeyDirection::Any,
+            with: ActionKeyWith::Any,
+            wait_before_use_ticks: 0,
+            wait_after_use_ticks: 0,
+            wait_after_buffered: WaitAfterBuffered::None,
+            pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
+            action_info: None,
+            verification: Some(CastVerification {
+                region: Rect::new(0, 0, 10, 10),
+                max_recast_count: 1,
+                recast_count: 0,
+                baseline: None,
+            }),
+            state: State::Using(Using::default()),
+        });
+
+        // Captures baseline, uses key and starts verifying
+        update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        for _ in 0..timeout + 2 {
+            update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        }
+        assert_matches!(
+            player.state,
+            Player::UseKey(UseKey {
+                state: State::Precondition,
+                current_count: 0,
+                verification: Some(CastVerification {
+                    recast_count: 1,
+                    baseline: None,
+                    ..
+                }),
+                ..
+            })
+        );
+
+        // Re-casts and completes once the region changes
+        update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        assert_matches!(
+            player.state,
+            Player::UseKey(UseKey {
+                state: State::Verifying(_, false),
+                ..
+            })
+        );
+        update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        assert_matches!(player.state, Player::Idle);
+    }
+
+    #[test]
+    fn update_use_key_state_verifies_while_waiting_after() {
+        let mut detector = MockDetector::new();
+        let mut sequence = Sequence::new();
+        // Baseline and changed right after the key use
+        detector
+            .expect_detect_region_signature()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(RegionSignature::filled(0)));
+        detector
+            .expect_detect_region_signature()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(RegionSignature::filled(255)));
+        let mut keys = MockInput::new();
+        keys.expect_send_key().with(eq(KeyKind::A)).once();
+        let resources = Resources::new(Some(keys), Some(detector));
+        let mut player = make_player(UseKey {
+            key: KeyKind::A,
+            key_hold_ticks: 0,
+            key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
+            link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
+            count: 1,
+            current_count: 0,
+            direction: ActionKeyDirection::Any,
+            with: ActionKeyWith::Any,
+            wait_before_use_ticks: 0,
+            wait_after_use_ticks: 5,
+            wait_after_buffered: WaitAfterBuffered::None,
+            pending_transition: PendingTransition::None,
+            double_jump_apex_ticks: 0,
+            double_jump_apex_reached: false,
+            direction_lock: ActionKeyDirection::Any,
+            action_info: None,
+            verification: Some(CastVerification {
+                region: Rect::new(0, 0, 10, 10),
+                max_recast_count: 1,
+                recast_count: 0,
+                baseline: None,
+            }),
+            state: State::Using(Using::default()),
+        });
+
+        // Uses key and verifies without stalling first
+        update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        assert_matches!(
+            player.state,
+            Player::UseKey(UseKey {
+                state: State::Verifying(_, false),
+                ..
+            })
+        );
+        update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        assert_matches!(
+            player.state,
+            Player::UseKey(UseKey {
+                state: State::Verifying(_, true),
+                ..
+            })
+        );
+
+        // Keeps waiting after until the wait after ticks passed
+        for _ in 0..4 {
+            update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        }
+        assert_matches!(
+            player.state,
+            Player::UseKey(UseKey {
+                state: State::Verifying(_, true),
+                ..
+            })
+        );
+        update_use_key_state(&resources, &mut player, Minimap::Detecting);
+        assert_matches!(player.state, Player::Idle);
+    }
 }
@@ -0,0 +1,212 @@
+//! Loads user-provided detector plugins from dynamic libraries.
+//!
+//! A plugin is a dynamic library placed inside the `plugins` folder next to the executable that
+//! exports the following C ABI functions:
+//!
+//! ```c
+//! // Returns the number of detectors provided by this plugin.
+//! uint32_t komari_detector_count(void);
+//!
+//! // Returns the null-terminated UTF-8 name of the detector at `index`. The returned pointer
+//! // must stay valid for as long as the plugin is loaded.
+//! const char *komari_detector_name(uint32_t index);
+//!
+//! // Runs the detector at `index` on a BGRA frame with `stride` bytes per row. Returns a
+//! // positive value if detected, zero if not detected and a negative value on error.
+//! int32_t komari_detector_detect(uint32_t index, const uint8_t *pixels, uint32_t width,
+//!                                uint32_t height, uint32_t stride);
+//! ```
+//!
+//! Each detector is exposed by its name and can be used as an action condition. Names longer than
+//! [`PluginDetectorName::MAX_LEN`] bytes are skipped when loading.
+//!
+//! `komari_detector_count` and `komari_detector_name` are only called once while loading the
+//! plugin. `komari_detector_detect` is called from the detection worker threads and can be called
+//! concurrently, including for the same `index`, so it must be thread-safe. The `pixels` buffer is
+//! only valid for the duration of the call.
+
+use std::{
+    collections::HashMap,
+    env,
+    ffi::{CStr, c_char},
+    fs,
+    path::Path,
+    sync::LazyLock,
+};
+
+use anyhow::{Result, anyhow, bail};
+use libloading::{Library, Symbol};
+use log::{error, info, warn};
+use opencv::core::{Mat, MatTraitConst, MatTraitConstManual};
+
+use crate::models::PluginDetectorName;
+
+const DETECTOR_COUNT_SYMBOL: &[u8] = b"komari_detector_count\0";
+const DETECTOR_NAME_SYMBOL: &[u8] = b"komari_detector_name\0";
+const DETECTOR_DETECT_SYMBOL: &[u8] = b"komari_detector_detect\0";
+
+type DetectorCountFn = unsafe extern "C" fn() -> u32;
+type DetectorNameFn = unsafe extern "C" fn(index: u32) -> *const c_char;
+type DetectorDetectFn = unsafe extern "C" fn(
+    index: u32,
+    pixels: *const u8,
+    width: u32,
+    height: u32,
+    stride: u32,
+) -> i32;
+
+static PLUGINS: LazyLock<Plugins> = LazyLock::new(|| {
+    let dir = env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("plugins");
+    Plugins::load(&dir)
+});
+
+/// A named detector provided by a plugin.
+#[derive(Debug)]
+struct PluginDetector {
+    index: u32,
+    detect: DetectorDetectFn,
+}
+
+/// Loaded plugin libraries and their detectors.
+#[derive(Debug, Default)]
+struct Plugins {
+    detectors: HashMap<String, PluginDetector>,
+    /// Keeps the libraries loaded for as long as the detectors function pointers are used.
+    _libraries: Vec<Library>,
+}
+
+impl Plugins {
+    fn load(dir: &Path) -> Self {
+        let mut plugins = Plugins::default();
+        let Ok(entries) = fs::read_dir(dir) else {
+            return plugins;
+        };
+
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            if path
+                .extension()
+                .is_none_or(|ext| ext != env::consts::DLL_EXTENSION)
+            {
+                continue;
+            }
+
+            // SAFETY: Plugins are provided by the user and are trusted to follow the documented
+            // C ABI. Loading them runs their initialization routines.
+            match unsafe { plugins.load_library(&path) } {
+                Ok(count) => {
+                    info!(target: "plugin", "loaded {count} detectors from {}", path.display())
+                }
+                Err(err) => {
+                    error!(target: "plugin", "failed to load {} {err}", path.display())
+                }
+            }
+        }
+
+        plugins
+    }
+
+    /// Loads the library at `path` and registers its detectors.
+    ///
+    /// Returns the number of registered detectors. Detectors with a duplicated or too long name are
+    /// skipped.
+    unsafe fn load_library(&mut self, path: &Path) -> Result<usize> {
+        let library = unsafe { Library::new(path)? };
+        let (count, name, detect) = unsafe {
+            let count: Symbol<DetectorCountFn> = library.get(DETECTOR_COUNT_SYMBOL)?;
+            let name: Symbol<DetectorNameFn> = library.get(DETECTOR_NAME_SYMBOL)?;
+            let detect: Symbol<DetectorDetectFn> = library.get(DETECTOR_DETECT_SYMBOL)?;
+            (*count, *name, *detect)
+        };
+
+        let mut registered = 0;
+        for index in 0..unsafe { count() } {
+            let name = unsafe { name(index) };
+            if name.is_null() {
+                continue;
+            }
+            let name = unsafe { CStr::from_ptr(name) }
+                .to_string_lossy()
+                .into_owned();
+            if self.detectors.contains_key(&name) {
+                continue;
+            }
+            if PluginDetectorName::new(&name).is_none() {
+                warn!(
+                    target: "plugin",
+                    "skipped detector {name} from {} as its name is longer than {} bytes",
+                    path.display(),
+                    PluginDetectorName::MAX_LEN
+                );
+                continue;
+            }
+
+            self.detectors
+                .insert(name, PluginDetector { index, detect });
+            registered += 1;
+        }
+        self._libraries.push(library);
+
+        Ok(registered)
+    }
+}
+
+/// Gets the sorted names of all loaded plugin detectors.
+pub fn detector_names() -> Vec<String> {
+    let mut names = PLUGINS.detectors.keys().cloned().collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Runs the plugin detector `name` on the BGRA `mat`.
+pub fn detect(name: &str, mat: &Mat) -> Result<bool> {
+    let detector = PLUGINS
+        .detectors
+        .get(name)
+        .ok_or(anyhow!("plugin detector {name} not found"))?;
+    let continuous;
+    let mat = if mat.is_continuous() {
+        mat
+    } else {
+        continuous = mat.try_clone()?;
+        &continuous
+    };
+    let bytes = mat.data_bytes()?;
+    let width = mat.cols() as u32;
+    let height = mat.rows() as u32;
+    let stride = width * mat.elem_size()? as u32;
+
+    // SAFETY: `bytes` is a continuous BGRA buffer of `height` rows with `stride` bytes each and
+    // outlives the call.
+    let result =
+        unsafe { (detector.detect)(detector.index, bytes.as_ptr(), width, height, stride) };
+    if result < 0 {
+        bail!("plugin detector {name} failed with {result}");
+    }
+
+    Ok(result > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+
+    use super::*;
+
+    #[test]
+    fn load_missing_dir_no_detectors() {
+        let plugins = Plugins::load(&temp_dir().join("komari_missing_plugins"));
+
+        assert!(plugins.detectors.is_empty());
+    }
+
+    #[test]
+    fn detect_unknown_detector_errors() {
+        let mat = Mat::default();
+
+        assert!(detect("unknown", &mat).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::{Arc, LazyLock, Mutex};
+
+use anyhow::Result;
+use opencv::{
+    core::{Mat, MatTraitConst, MatTraitConstManual, Rect, Size},
+    imgproc::{COLOR_BGRA2RGB, COLOR_RGB2RGBA, INTER_AREA, cvt_color_def, resize},
+};
+
+/// Maximum width of a [`PreviewFrame`], wider minimaps are downscaled.
+const MAX_PREVIEW_WIDTH: i32 = 320;
+
+static PREVIEW: LazyLock<Mutex<Option<PreviewFrame>>> = LazyLock::new(|| Mutex::new(None));
+
+/// The latest minimap frame in RGBA shared with the UI for previewing.
+///
+/// The pixels are reference-counted so cloning this frame (e.g. through [`crate::GameState`])
+/// never copies them.
+#[derive(Clone, Debug)]
+pub struct PreviewFrame {
+    pub data: Arc<[u8]>,
+    /// Width of `data` in pixels.
+    pub width: usize,
+    /// Height of `data` in pixels.
+    pub height: usize,
+    /// Width of the minimap before downscaling.
+    pub source_width: usize,
+    /// Height of the minimap before downscaling.
+    pub source_height: usize,
+    /// Increases by one each time a new frame is published.
+    pub generation: u64,
+}
+
+/// Gets the latest published [`PreviewFrame`].
+pub fn preview_frame() -> Option<PreviewFrame> {
+    PREVIEW.lock().unwrap().clone()
+}
+
+/// Extracts the minimap at `bbox` from BGRA `mat` and publishes it as the latest
+/// [`PreviewFrame`].
+pub(crate) fn publish_preview_frame(mat: &impl MatTraitConst, bbox: Rect) -> Result<PreviewFrame> {
+    // Converts through RGB so that alpha is always opaque
+    let mut rgb = Mat::default();
+    cvt_color_def(&mat.roi(bbox)?, &mut rgb, COLOR_BGRA2RGB)?;
+    if bbox.width > MAX_PREVIEW_WIDTH {
+        let height = (bbox.height * MAX_PREVIEW_WIDTH / bbox.width).max(1);
+        let mut resized = Mat::default();
+        resize(
+            &rgb,
+            &mut resized,
+            Size::new(MAX_PREVIEW_WIDTH, height),
+            0.0,
+            0.0,
+            INTER_AREA,
+        )?;
+        rgb = resized;
+    }
+    let mut rgba = Mat::default();
+    cvt_color_def(&rgb, &mut rgba, COLOR_RGB2RGBA)?;
+
+    let mut preview = PREVIEW.lock().unwrap();
+    let frame = PreviewFrame {
+        data: Arc::from(rgba.data_bytes()?),
+        width: rgba.cols() as usize,
+        height: rgba.rows() as usize,
+        source_width: bbox.width as usize,
+        source_height: bbox.height as usize,
+        generation: preview.as_ref().map_or(0, |frame| frame.generation + 1),
+    };
+    *preview = Some(frame.clone());
+
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use opencv::core::{CV_8UC4, Scalar};
+
+    use super::*;
+
+    #[test]
+    fn publish_preview_frame_downscales_and_converts_to_rgba() {
+        let mat =
+            Mat::new_rows_cols_with_default(200, 800, CV_8UC4, Scalar::new(1.0, 2.0, 3.0, 0.0))
+                .unwrap();
+
+        let frame = publish_preview_frame(&mat, Rect::new(0, 0, 640, 160)).unwrap();
+        let next = publish_preview_frame(&mat, Rect::new(0, 0, 100, 50)).unwrap();
+
+        assert_eq!((frame.width, frame.height), (320, 80));
+        assert_eq!((frame.source_width, frame.source_height), (640, 160));
+        assert_eq!(frame.data.len(), 320 * 80 * 4);
+        assert_eq!(&frame.data[..4], &[3, 2, 1, 255]);
+        assert_eq!((next.width, next.height), (100, 50));
+        assert_eq!(next.generation, frame.generation + 1);
+        assert_eq!(preview_frame().unwrap().generation, next.generation);
+    }
+}
@@ -0,0 +1,757 @@
+//! Records and replays sessions deterministically without the game.
+//!
+//! A recording is a folder containing a `trace.json` [`ReplayTrace`] and the captured frame of
+//! each tick saved as a downscaled PNG image. Replaying a recording feeds the saved frames scaled
+//! back to their original size, the recorded random seeds, the recorded key states and the
+//! recorded [`clock`] time of each tick back into the systems instead of capturing the game and
+//! sending inputs. The player state of each replayed tick is compared against the recorded one to
+//! find the first tick that diverges.
+//!
+//! While recording or replaying, tasks run synchronously so that detections complete on the same
+//! ticks. Recorded frames are downscaled and saved on a separate thread. When the thread falls
+//! behind, the frame of a tick is skipped and the last saved frame is replayed in its place.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    env, fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::mpsc::{SyncSender, TrySendError, sync_channel},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, bail};
+use log::{debug, error, info};
+use opencv::{
+    core::{Mat, MatTraitConst, Size, Vector},
+    imgcodecs::{IMREAD_UNCHANGED, IMWRITE_PNG_COMPRESSION, imread, imwrite},
+    imgproc::{INTER_AREA, INTER_LINEAR, resize},
+};
+use platforms::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bridge::{Input, InputKeyDownOptions, InputMethod, KeyKind, MouseKind},
+    clock,
+    mat::OwnedMat,
+    models::{FocusPolicy, KeyPressDurations, KeyboardLayout},
+    player::Player,
+    rng::{PerlinSeed, RngSeed},
+    task::set_synchronous_tasks,
+    utils,
+};
+
+const TRACE_FILE: &str = "trace.json";
+
+/// Maximum number of recorded frames waiting to be saved before new frames are skipped.
+const MAX_PENDING_FRAMES: usize = 16;
+
+/// The scale recorded frames are saved at.
+const FRAME_SCALE: f64 = 0.5;
+
+/// The PNG compression level of recorded frames, favoring speed over size.
+const FRAME_PNG_COMPRESSION: i32 = 1;
+
+/// A recorded session.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayTrace {
+    pub rng_seed: RngSeed,
+    pub perlin_seed: PerlinSeed,
+    pub tick_rate: u32,
+    /// The tick the recording started at.
+    pub start_tick: u64,
+    /// Whether the bot was halting when the recording started.
+    pub halting: bool,
+    pub map_id: Option<i64>,
+    pub preset: Option<String>,
+    pub character_id: Option<i64>,
+    pub ticks: Vec<ReplayTick>,
+}
+
+/// A single recorded tick.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayTick {
+    /// The file name of the captured frame or [`None`] if capturing failed.
+    pub frame: Option<String>,
+    /// The size of the captured frame before it was downscaled for saving.
+    ///
+    /// Is [`None`] if the frame was saved at its original size.
+    #[serde(default)]
+    pub frame_size: Option<(i32, i32)>,
+    /// The results of querying whether keys are cleared in call order.
+    pub keys_cleared: Vec<bool>,
+    /// The player state after the tick.
+    pub player_state: String,
+    /// Microseconds passed since the recording started at the start of the tick.
+    #[serde(default)]
+    pub elapsed_micros: u64,
+}
+
+/// The first replayed tick with a player state different from the recorded one.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ReplayMismatch {
+    /// The index of the tick relative to the start of the recording.
+    pub tick: usize,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// The current status of [`Replay`].
+#[derive(Clone, PartialEq, Default, Debug)]
+pub enum ReplayStatus {
+    #[default]
+    Idle,
+    Recording {
+        dir: String,
+        ticks: usize,
+    },
+    Replaying {
+        dir: String,
+        tick: usize,
+        ticks: usize,
+    },
+    Finished {
+        dir: String,
+        ticks: usize,
+        mismatch: Option<ReplayMismatch>,
+    },
+}
+
+#[derive(Debug, Default)]
+enum ReplayState {
+    #[default]
+    Idle,
+    Recording {
+        dir: PathBuf,
+        trace: ReplayTrace,
+        writer: FrameWriter,
+        /// The [`Instant`] the recording started.
+        started: Instant,
+        /// Microseconds passed since [`Self::Recording::started`] at the start of the tick.
+        tick_elapsed_micros: u64,
+        /// The file name of the last saved frame.
+        last_frame: Option<String>,
+    },
+    Replaying {
+        dir: PathBuf,
+        trace: ReplayTrace,
+        index: usize,
+        mismatch: Option<ReplayMismatch>,
+        /// The [`Instant`] the replaying started.
+        started: Instant,
+    },
+    Finished {
+        dir: PathBuf,
+        ticks: usize,
+        mismatch: Option<ReplayMismatch>,
+    },
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+enum ReplayInputMode {
+    #[default]
+    Passthrough,
+    Recording,
+    Replaying,
+}
+
+#[derive(Debug, Default)]
+struct ReplayInputState {
+    mode: Cell<ReplayInputMode>,
+    /// Recorded or to be replayed results of querying whether keys are cleared.
+    keys_cleared: RefCell<VecDeque<bool>>,
+}
+
+/// A resource for recording and replaying sessions.
+#[derive(Debug, Default)]
+pub struct Replay {
+    state: RefCell<ReplayState>,
+    input: Rc<ReplayInputState>,
+}
+
+impl Replay {
+    /// Wraps `input` so that its key states can be recorded and replayed.
+    pub fn wrap_input(&self, input: impl Input + 'static) -> ReplayInput {
+        ReplayInput {
+            inner: Box::new(input),
+            state: self.input.clone(),
+        }
+    }
+
+    /// Whether a session is being recorded or replayed.
+    pub fn is_active(&self) -> bool {
+        matches!(
+            *self.state.borrow(),
+            ReplayState::Recording { .. } | ReplayState::Replaying { .. }
+        )
+    }
+
+    pub fn status(&self) -> ReplayStatus {
+        let to_string = |dir: &PathBuf| dir.to_string_lossy().into_owned();
+
+        match &*self.state.borrow() {
+            ReplayState::Idle => ReplayStatus::Idle,
+            ReplayState::Recording { dir, trace, .. } => ReplayStatus::Recording {
+                dir: to_string(dir),
+                ticks: trace.ticks.len(),
+            },
+            ReplayState::Replaying {
+                dir, trace, index, ..
+            } => ReplayStatus::Replaying {
+                dir: to_string(dir),
+                tick: *index,
+                ticks: trace.ticks.len(),
+            },
+            ReplayState::Finished {
+                dir,
+                ticks,
+                mismatch,
+            } => ReplayStatus::Finished {
+                dir: to_string(dir),
+                ticks: *ticks,
+                mismatch: mismatch.clone(),
+            },
+        }
+    }
+
+    /// Starts recording `trace` to a new folder inside the `replays` folder.
+    ///
+    /// Returns the folder the recording is saved to.
+    pub fn start_recording(&self, trace: ReplayTrace) -> Result<PathBuf> {
+        if self.is_active() {
+            bail!("a session is already being recorded or replayed");
+        }
+
+        let dir = env::current_exe()?
+            .parent()
+            .unwrap()
+            .join("replays")
+            .join(utils::epoch_millis_as_string());
+        fs::create_dir_all(&dir)?;
+        let writer = FrameWriter::new()?;
+        info!(target: "replay", "recording to {}", dir.display());
+
+        *self.state.borrow_mut() = ReplayState::Recording {
+            dir: dir.clone(),
+            trace,
+            writer,
+            started: Instant::now(),
+            tick_elapsed_micros: 0,
+            last_frame: None,
+        };
+        self.set_mode(ReplayInputMode::Recording);
+
+        Ok(dir)
+    }
+
+    /// Starts replaying `trace` loaded from `dir`.
+    pub fn start_replaying(&self, dir: PathBuf, trace: ReplayTrace) -> Result<()> {
+        if self.is_active() {
+            bail!("a session is already being recorded or replayed");
+        }
+        if trace.ticks.is_empty() {
+            bail!("recording has no tick");
+        }
+        info!(target: "replay", "replaying {} ticks from {}", trace.ticks.len(), dir.display());
+
+        *self.state.borrow_mut() = ReplayState::Replaying {
+            dir,
+            trace,
+            index: 0,
+            mismatch: None,
+            started: Instant::now(),
+        };
+        self.set_mode(ReplayInputMode::Replaying);
+
+        Ok(())
+    }
+
+    /// Stops the current recording or replaying.
+    ///
+    /// When recording, the trace is saved to the recording folder after all recorded frames are
+    /// saved.
+    pub fn stop(&self) -> Result<()> {
+        let state = self.state.take();
+        self.set_mode(ReplayInputMode::Passthrough);
+
+        match state {
+            ReplayState::Recording {
+                dir, trace, writer, ..
+            } => {
+                writer.finish();
+                fs::write(dir.join(TRACE_FILE), serde_json::to_string(&trace)?)?;
+                info!(target: "replay", "recorded {} ticks to {}", trace.ticks.len(), dir.display());
+                *self.state.borrow_mut() = ReplayState::Finished {
+                    dir,
+                    ticks: trace.ticks.len(),
+                    mismatch: None,
+                };
+            }
+            ReplayState::Replaying {
+                dir,
+                index,
+                mismatch,
+                ..
+            } => {
+                *self.state.borrow_mut() = ReplayState::Finished {
+                    dir,
+                    ticks: index,
+                    mismatch,
+                };
+            }
+            ReplayState::Idle | ReplayState::Finished { .. } => (),
+        }
+
+        Ok(())
+    }
+
+    /// Advances the [`clock`] to the current tick.
+    ///
+    /// Must be called at the start of each tick. While recording, the time of the tick is recorded.
+    /// While replaying, [`clock::now`] returns the recorded time of the tick instead of the real
+    /// time.
+    pub fn update_clock(&self) {
+        match &mut *self.state.borrow_mut() {
+            ReplayState::Recording {
+                started,
+                tick_elapsed_micros,
+                ..
+            } => {
+                *tick_elapsed_micros = started.elapsed().as_micros() as u64;
+            }
+            ReplayState::Replaying {
+                trace,
+                index,
+                started,
+                ..
+            } => {
+                let elapsed = Duration::from_micros(trace.ticks[*index].elapsed_micros);
+                clock::set_virtual_now(Some(*started + elapsed));
+            }
+            ReplayState::Idle | ReplayState::Finished { .. } => (),
+        }
+    }
+
+    /// Retrieves the recorded frame of the current tick if replaying.
+    ///
+    /// Returns [`None`] if not replaying so that the frame is captured from the game instead.
+    pub fn next_frame(&self) -> Option<Result<OwnedMat, Error>> {
+        let state = self.state.borrow();
+        let ReplayState::Replaying {
+            dir, trace, index, ..
+        } = &*state
+        else {
+            return None;
+        };
+        let tick = &trace.ticks[*index];
+
+        *self.input.keys_cleared.borrow_mut() = tick.keys_cleared.iter().copied().collect();
+        let frame = tick
+            .frame
+            .as_ref()
+            .and_then(|frame| dir.join(frame).to_str().map(str::to_string))
+            .and_then(|path| imread(&path, IMREAD_UNCHANGED).ok())
+            .filter(|mat| !mat.empty())
+            .and_then(|mat| match tick.frame_size {
+                Some((width, height)) => {
+                    let mut resized = Mat::default();
+                    let size = Size::new(width, height);
+                    resize(&mat, &mut resized, size, 0.0, 0.0, INTER_LINEAR).ok()?;
+                    Some(resized)
+                }
+                None => Some(mat),
+            })
+            .map(OwnedMat::from)
+            .ok_or(Error::WindowNotFound);
+
+        Some(frame)
+    }
+
+    /// Records or compares the current tick.
+    ///
+    /// `frame` is the frame the systems ran on or [`None`] if capturing failed. `player` is the
+    /// player state after running the systems.
+    pub fn update(&self, frame: Option<&Mat>, player: &Player) {
+        let mut state = self.state.borrow_mut();
+        let finished = match &mut *state {
+            ReplayState::Recording {
+                dir,
+                trace,
+                writer,
+                tick_elapsed_micros,
+                last_frame,
+                ..
+            } => {
+                let index = trace.ticks.len();
+                let frame_size = frame
+                    .and_then(|mat| mat.size().ok())
+                    .map(|size| (size.width, size.height));
+                let frame = frame.and_then(|mat| match writer.save(dir, index, mat) {
+                    Some(name) => {
+                        *last_frame = Some(name.clone());
+                        Some(name)
+                    }
+                    None => {
+                        debug!(target: "replay", "frame writer is behind, skipped frame of tick {index}");
+                        last_frame.clone()
+                    }
+                });
+                let keys_cleared = self.input.keys_cleared.borrow_mut().drain(..).collect();
+
+                trace.ticks.push(ReplayTick {
+                    frame_size: frame.as_ref().and(frame_size),
+                    frame,
+                    keys_cleared,
+                    player_state: player.to_string(),
+                    elapsed_micros: *tick_elapsed_micros,
+                });
+                false
+            }
+            ReplayState::Replaying {
+                trace,
+                index,
+                mismatch,
+                ..
+            } => {
+                let expected = &trace.ticks[*index].player_state;
+                let actual = player.to_string();
+                if mismatch.is_none() && *expected != actual {
+                    info!(target: "replay", "tick {index} diverged from {expected} to {actual}");
+                    *mismatch = Some(ReplayMismatch {
+                        tick: *index,
+                        expected: expected.clone(),
+                        actual,
+                    });
+                }
+
+                *index += 1;
+                *index >= trace.ticks.len()
+            }
+            ReplayState::Idle | ReplayState::Finished { .. } => false,
+        };
+
+        if finished {
+            drop(state);
+            let _ = self.stop();
+        }
+    }
+
+    fn set_mode(&self, mode: ReplayInputMode) {
+        self.input.mode.set(mode);
+        self.input.keys_cleared.borrow_mut().clear();
+        set_synchronous_tasks(!matches!(mode, ReplayInputMode::Passthrough));
+        if !matches!(mode, ReplayInputMode::Replaying) {
+            clock::set_virtual_now(None);
+        }
+    }
+}
+
+/// Loads the [`ReplayTrace`] recorded in `dir`.
+pub fn load_trace(dir: &Path) -> Result<ReplayTrace> {
+    let contents = fs::read_to_string(dir.join(TRACE_FILE))?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+/// Downscales and saves recorded frames on a separate thread so that encoding does not stall the
+/// main loop.
+#[derive(Debug)]
+struct FrameWriter {
+    tx: SyncSender<(PathBuf, Mat)>,
+    handle: JoinHandle<()>,
+}
+
+impl FrameWriter {
+    fn new() -> Result<FrameWriter> {
+        let (tx, rx) = sync_channel::<(PathBuf, Mat)>(MAX_PENDING_FRAMES);
+        let handle = thread::Builder::new()
+            .name("replay-frame-writer".to_string())
+            .spawn(move || {
+                let params = Vector::from_slice(&[IMWRITE_PNG_COMPRESSION, FRAME_PNG_COMPRESSION]);
+                for (path, mat) in rx {
+                    let mut resized = Mat::default();
+                    let saved = resize(
+                        &mat,
+                        &mut resized,
+                        Size::default(),
+                        FRAME_SCALE,
+                        FRAME_SCALE,
+                        INTER_AREA,
+                    )
+                    .is_ok()
+                        && path
+                            .to_str()
+                            .is_some_and(|path| imwrite(path, &resized, &params).unwrap_or(false));
+                    if !saved {
+                        error!(target: "replay", "failed to save frame {}", path.display());
+                    }
+                }
+            })?;
+
+        Ok(FrameWriter { tx, handle })
+    }
+
+    /// Queues `mat` to be saved as the frame of tick `index` in `dir`.
+    ///
+    /// Returns the file name of the frame or [`None`] if it could not be queued because there are
+    /// already [`MAX_PENDING_FRAMES`] waiting to be saved.
+    fn save(&self, dir: &Path, index: usize, mat: &Mat) -> Option<String> {
+        let name = format!("{index}.png");
+        let mat = mat.try_clone().ok()?;
+        match self.tx.try_send((dir.join(&name), mat)) {
+            Ok(()) => Some(name),
+            Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) => None,
+        }
+    }
+
+    /// Waits for all queued frames to be saved.
+    fn finish(self) {
+        drop(self.tx);
+        let _ = self.handle.join();
+    }
+}
+
+/// An [`Input`] wrapper that records key states while recording and replays them while
+/// replaying.
+///
+/// While replaying, no input is sent to the game.
+#[derive(Debug)]
+pub struct ReplayInput {
+    inner: Box<dyn Input>,
+    state: Rc<ReplayInputState>,
+}
+
+impl ReplayInput {
+    #[inline]
+    fn is_replaying(&self) -> bool {
+        matches!(self.state.mode.get(), ReplayInputMode::Replaying)
+    }
+
+    fn key_cleared(&self, cleared: impl FnOnce(&dyn Input) -> bool) -> bool {
+        match self.state.mode.get() {
+            ReplayInputMode::Passthrough => cleared(self.inner.as_ref()),
+            ReplayInputMode::Recording => {
+                let cleared = cleared(self.inner.as_ref());
+                self.state.keys_cleared.borrow_mut().push_back(cleared);
+                cleared
+            }
+            ReplayInputMode::Replaying => self
+                .state
+                .keys_cleared
+                .borrow_mut()
+                .pop_front()
+                .unwrap_or(true),
+        }
+    }
+}
+
+impl Input for ReplayInput {
+    fn update(&mut self, tick: u64) {
+        if !self.is_replaying() {
+            self.inner.update(tick);
+        }
+    }
+
+    fn set_method(&mut self, method: InputMethod) {
+        self.inner.set_method(method);
+    }
+
+    fn set_focus_policy(&mut self, policy: FocusPolicy, grace_period_millis: u64) {
+        self.inner.set_focus_policy(policy, grace_period_millis);
+    }
+
+    fn set_press_durations(&mut self, durations: KeyPressDurations) {
+        self.inner.set_press_durations(durations);
+    }
+
+    fn set_observer(&mut self, observer: bool) {
+        self.inner.set_observer(observer);
+    }
+
+    fn set_keyboard_layout(&mut self, layout: KeyboardLayout) {
+        self.inner.set_keyboard_layout(layout);
+    }
+
+    fn set_audit(&mut self, audit: bool) {
+        self.inner.set_audit(audit);
+    }
+
+    fn send_mouse(&self, x: i32, y: i32, kind: MouseKind) {
+        if !self.is_replaying() {
+            self.inner.send_mouse(x, y, kind);
+        }
+    }
+
+    fn send_key(&self, kind: KeyKind) {
+        if !self.is_replaying() {
+            self.inner.send_key(kind);
+        }
+    }
+
+    fn send_key_up(&self, kind: KeyKind) {
+        if !self.is_replaying() {
+            self.inner.send_key_up(kind);
+        }
+    }
+
+    fn send_paste(&self, text: &str) -> bool {
+        !self.is_replaying() && self.inner.send_paste(text)
+    }
+
+    fn send_key_down_with_options(&self, kind: KeyKind, options: InputKeyDownOptions) {
+        if !self.is_replaying() {
+            self.inner.send_key_down_with_options(kind, options);
+        }
+    }
+
+    fn is_key_cleared(&self, kind: KeyKind) -> bool {
+        self.key_cleared(|input| input.is_key_cleared(kind))
+    }
+
+    fn all_keys_cleared(&self) -> bool {
+        self.key_cleared(|input| input.all_keys_cleared())
+    }
+
+    fn any_key_held(&self) -> bool {
+        !self.is_replaying() && self.inner.any_key_held()
+    }
+
+    fn send_all_keys_up(&self) {
+        if !self.is_replaying() {
+            self.inner.send_all_keys_up();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use opencv::core::{CV_8UC3, Scalar};
+
+    use super::*;
+    use crate::bridge::MockInput;
+
+    fn mock_trace(ticks: Vec<ReplayTick>) -> ReplayTrace {
+        ReplayTrace {
+            rng_seed: [0; 32],
+            perlin_seed: 0,
+            tick_rate: 30,
+            start_tick: 0,
+            halting: false,
+            map_id: None,
+            preset: None,
+            character_id: None,
+            ticks,
+        }
+    }
+
+    #[test]
+    fn replay_input_replays_keys_cleared_without_sending() {
+        let mut inner = MockInput::default();
+        inner.expect_send_key().never();
+        inner.expect_is_key_cleared().never();
+        let replay = Replay::default();
+        let input = replay.wrap_input(inner);
+        let trace = mock_trace(vec![ReplayTick {
+            frame: None,
+            frame_size: None,
+            keys_cleared: vec![false, true],
+            player_state: Player::Idle.to_string(),
+            elapsed_micros: 0,
+        }]);
+
+        replay
+            .start_replaying(PathBuf::from("replay"), trace)
+            .unwrap();
+        assert_matches!(replay.next_frame(), Some(Err(Error::WindowNotFound)));
+
+        input.send_key(KeyKind::A);
+        assert!(!input.is_key_cleared(KeyKind::A));
+        assert!(input.all_keys_cleared());
+        replay.stop().unwrap();
+    }
+
+    #[test]
+    fn frame_writer_saves_queued_frames_on_finish() {
+        let dir = env::temp_dir().join("komari_replay_frame_writer");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mat = Mat::new_rows_cols_with_default(4, 4, CV_8UC3, Scalar::all(255.0)).unwrap();
+        let writer = FrameWriter::new().unwrap();
+
+        assert_eq!(writer.save(&dir, 0, &mat), Some("0.png".to_string()));
+        assert_eq!(writer.save(&dir, 1, &mat), Some("1.png".to_string()));
+        writer.finish();
+
+        let frame = imread(dir.join("0.png").to_str().unwrap(), IMREAD_UNCHANGED).unwrap();
+        assert_eq!(frame.size().unwrap(), Size::new(2, 2));
+        assert!(dir.join("1.png").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn replay_update_clock_replays_recorded_time() {
+        let tick = |elapsed_micros: u64| ReplayTick {
+            frame: None,
+            frame_size: None,
+            keys_cleared: vec![],
+            player_state: Player::Idle.to_string(),
+            elapsed_micros,
+        };
+        let replay = Replay::default();
+        let trace = mock_trace(vec![tick(0), tick(60_000_000)]);
+
+        replay
+            .start_replaying(PathBuf::from("replay"), trace)
+            .unwrap();
+        replay.update_clock();
+        let first = clock::now();
+        replay.update(None, &Player::Idle);
+        replay.update_clock();
+        assert_eq!(clock::now() - first, Duration::from_secs(60));
+
+        replay.update(None, &Player::Idle);
+        assert!(!replay.is_active());
+        assert!(clock::now() < first + Duration::from_secs(60));
+    }
+
+    #[test]
+    fn replay_update_finishes_with_first_mismatch() {
+        let tick = |state: Player| ReplayTick {
+            frame: None,
+            frame_size: None,
+            keys_cleared: vec![],
+            player_state: state.to_string(),
+            elapsed_micros: 0,
+        };
+        let replay = Replay::default();
+        let trace = mock_trace(vec![
+            tick(Player::Idle),
+            tick(Player::Detecting),
+            tick(Player::Idle),
+        ]);
+
+        replay
+            .start_replaying(PathBuf::from("replay"), trace)
+            .unwrap();
+        replay.update(None, &Player::Idle);
+        replay.update(None, &Player::Idle);
+        replay.update(None, &Player::Detecting);
+
+        assert_eq!(
+            replay.status(),
+            ReplayStatus::Finished {
+                dir: "replay".to_string(),
+                ticks: 3,
+                mismatch: Some(ReplayMismatch {
+                    tick: 1,
+                    expected: Player::Detecting.to_string(),
+                    actual: Player::Idle.to_string(),
+                }),
+            }
+        );
+        assert!(!replay.is_active());
+    }
+}
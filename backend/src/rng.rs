@@ -1,7 +1,11 @@
 use std::cell::RefCell;
 
 use noise::{NoiseFn, Perlin};
-use rand::{Rng as RandRng, SeedableRng, rngs::StdRng, seq::IteratorRandom};
+use rand::{
+    Rng as RandRng, SeedableRng,
+    rngs::StdRng,
+    seq::{IteratorRandom, SliceRandom},
+};
 use rand_distr::{
     Distribution, Normal,
     uniform::{SampleRange, SampleUniform},
@@ -68,6 +72,11 @@ impl Rng {
         iter.choose(&mut self.rng.borrow_mut())
     }
 
+    #[inline]
+    pub fn random_shuffle<T>(&self, slice: &mut [T]) {
+        slice.shuffle(&mut *self.rng.borrow_mut());
+    }
+
     /// Samples a random `(delay, tick count)` pair.
     ///
     /// The delay is sampled from a normal distribution with mean `mean_ms` and
@@ -124,9 +133,21 @@ impl Rng {
     }
 }
 
+/// Derives the seed of the `session`-th session from the persisted `rng_seed`.
+///
+/// Each session gets a different seed that can still be reproduced from the same `rng_seed` and
+/// `session`.
+pub fn session_seed(rng_seed: RngSeed, session: u64) -> RngSeed {
+    let mut seed = rng_seed;
+    for (byte, salt) in seed.iter_mut().zip(session.to_le_bytes()) {
+        *byte ^= salt;
+    }
+    seed
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Rng;
+    use super::{Rng, session_seed};
 
     const SEED: [u8; 32] = [
         64, 241, 206, 219, 49, 21, 218, 145, 254, 152, 68, 176, 242, 238, 152, 14, 176, 241, 153,
@@ -149,4 +170,12 @@ mod tests {
         assert!(mean - 84.88451 < 0.01);
         assert!(std - 29.786358 < 0.01);
     }
+
+    #[test]
+    fn session_seed_differs_per_session() {
+        assert_eq!(session_seed(SEED, 0), SEED);
+        assert_eq!(session_seed(SEED, 3), session_seed(SEED, 3));
+        assert_ne!(session_seed(SEED, 3), session_seed(SEED, 4));
+        assert_eq!(session_seed(SEED, 3)[8..], SEED[8..]);
+    }
 }
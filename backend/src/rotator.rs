@@ -1,16 +1,16 @@
 use std::{
     assert_matches::debug_assert_matches,
-    collections::VecDeque,
-    fmt::Debug,
+    collections::{HashMap, VecDeque},
+    fmt::{self, Debug},
     sync::{
         Arc,
         atomic::{AtomicU32, Ordering},
     },
-    time::Instant,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::Result;
-use log::{debug, info};
+use log::{debug, info, warn};
 #[cfg(test)]
 use mockall::{automock, concretize};
 use opencv::core::{Point, Rect};
@@ -21,26 +21,47 @@ use crate::{
     array::Array,
     bridge::{KeyKind, LinkKeyKind},
     buff::{Buff, BuffKind},
+    clock,
     detect::{Detector, QuickSlotsHexaBooster, SolErda},
     ecs::{Resources, World},
-    minimap::Minimap,
+    minimap::{Minimap, MinimapIdle, OtherPlayerProximity},
     models::{
-        Action, ActionCondition, ActionKey, ActionKeyDirection, ActionKeyWith, ActionMove,
-        EliteBossBehavior, ExchangeHexaBoosterCondition, Familiars, MobbingKey, Position,
-        WaitAfterBuffered,
+        Action, ActionCondition, ActionCooldown, ActionKey, ActionKeyBurstBuff, ActionKeyDirection,
+        ActionKeyGroup, ActionKeyResourceGate, ActionKeyWith, ActionMove, BehaviorCondition,
+        BehaviorNode, ConditionWebhook, EliteBossBehavior, ExchangeHexaBoosterCondition, Familiars,
+        MapHazard, MapHazardAvoidance, MobbingKey, MobbingRegionSelection, OtherPlayerReaction,
+        PluginDetectorName, Position, ShopPurchase, WaitAfterBuffered,
     },
+    operation::Operation,
     player::{
-        AutoMob, Booster, ExchangeBooster, FamiliarsSwap, GRAPPLING_THRESHOLD, Key, Panic, PanicTo,
-        PingPong, PingPongDirection, PlayerAction, PlayerContext, PlayerEntity, Quadrant,
-        UseBooster,
+        AutoMob, Booster, ExchangeBooster, FamiliarsSwap, GRAPPLING_THRESHOLD, Key, Move, Panic,
+        PanicTo, PingPong, PingPongDirection, PlayerAction, PlayerContext, PlayerEntity, Quadrant,
+        Shop, UseBooster,
     },
-    run::MS_PER_TICK,
     skill::{Skill, SkillKind},
     task::{Task, Update, update_detection_task},
+    tick::ticks_from_millis,
+    webhook::ConditionWebhookGate,
 };
 
 const AUTO_MOB_SAME_QUAD_THRESHOLD: u32 = 5;
 
+/// Maximum x distance from the player for a loot to be considered already picked up.
+const AUTO_MOB_LOOT_PICKED_X_THRESHOLD: i32 = 2;
+
+/// Maximum number of consecutive loot pickups before auto mobbing again.
+const AUTO_MOB_LOOT_MAX_CONSECUTIVE_COUNT: u32 = 3;
+
+/// Milliseconds before the familiar buff expires to re-summon.
+const FAMILIAR_RESUMMON_LEAD_MILLIS: u64 = 10000;
+
+/// Milliseconds the familiar buff must stay missing before falling back to re-saving the setup.
+const FAMILIAR_RESUMMON_FALLBACK_MILLIS: u64 = 60000;
+
+/// Maximum number of consecutive shop purchases that did not increase the item count before
+/// giving up on purchasing.
+const SHOP_PURCHASE_MAX_UNCHANGED_COUNT: u32 = 3;
+
 /// [`Condition`] evaluation result.
 #[derive(Debug)]
 enum ConditionResult {
@@ -88,19 +109,35 @@ struct PriorityAction {
     metadata: Option<ActionMetadata>,
     /// Whether to queue this action to the front of [`Rotator::priority_actions_queue`].
     queue_to_front: bool,
+    /// The group this action belongs to.
+    group: Option<ActionGroup>,
     queue_info: PriorityActionQueueInfo,
 }
 
+/// The group of a [`PriorityAction`] and its rules.
+#[derive(Debug, Clone, Copy)]
+struct ActionGroup {
+    kind: ActionKeyGroup,
+    /// Whether queuing this action also resets the cooldown of other actions in the same group.
+    shares_cooldown: bool,
+    /// Milliseconds this action must wait after any action in the same group was queued.
+    exclusive_millis: u64,
+}
+
 #[derive(Debug, Default)]
 struct PriorityActionQueueInfo {
     /// Whether this action is being ignored.
     ///
-    /// While ignored, [`Self::last_queued_time`] will be updated to [`Instant::now`].
+    /// While ignored, [`Self::last_queued_time`] will be updated to [`clock::now`].
     /// The action is ignored for as long as it is still in the queue or the player
     /// is still executing it.
     ignoring: bool,
     /// The last [`Instant`] when this action was queued
     last_queued_time: Option<Instant>,
+    /// The key to persist [`Self::last_queued_time`] across restarts.
+    ///
+    /// If [`None`], the action always starts off cooldown.
+    cooldown_key: Option<String>,
 }
 
 /// Action metadata to help identifying action type.
@@ -122,6 +159,15 @@ enum RotatorAction {
     Linked(LinkedAction),
 }
 
+impl fmt::Display for RotatorAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RotatorAction::Single(action) => write!(f, "{action}"),
+            RotatorAction::Linked(action) => write!(f, "{action}"),
+        }
+    }
+}
+
 /// A linked list of actions.
 #[derive(Clone, Debug)]
 struct LinkedAction {
@@ -129,6 +175,16 @@ struct LinkedAction {
     next: Option<Box<LinkedAction>>,
 }
 
+impl fmt::Display for LinkedAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.inner)?;
+        if let Some(next) = self.next.as_ref() {
+            write!(f, " -> {next}")?;
+        }
+        Ok(())
+    }
+}
+
 /// The rotator's rotation mode.
 #[derive(Default, Debug)]
 pub enum RotatorMode {
@@ -136,12 +192,98 @@ pub enum RotatorMode {
     #[default]
     StartToEndThenReverse,
     AutoMobbing(MobbingKey, Bound),
-    PingPong(MobbingKey, Bound),
+    /// Ping pong with the mobbing key, bound and whether to bounce vertically.
+    PingPong(MobbingKey, Bound, bool),
+    /// Ticks the behavior tree instead of rotating the normal actions.
+    ///
+    /// No normal action is rotated if there is no behavior tree.
+    BehaviorTree(Option<BehaviorNode>),
+}
+
+/// A compiled [`BehaviorNode`] ready to be ticked.
+#[derive(Debug)]
+enum BehaviorTreeNode {
+    Selector(Vec<BehaviorTreeNode>),
+    Sequence(Vec<BehaviorTreeNode>),
+    Condition {
+        condition: BehaviorCondition,
+        /// Index into [`DefaultRotator::behavior_tree_condition_times`].
+        index: usize,
+    },
+    Action {
+        id: u32,
+        action: PlayerAction,
+    },
+}
+
+impl BehaviorTreeNode {
+    /// Compiles `node` with condition indices starting from `condition_count`.
+    fn new(node: BehaviorNode, condition_count: &mut usize) -> Self {
+        match node {
+            BehaviorNode::Selector(children) => BehaviorTreeNode::Selector(
+                children
+                    .into_iter()
+                    .map(|child| BehaviorTreeNode::new(child, condition_count))
+                    .collect(),
+            ),
+            BehaviorNode::Sequence(children) => BehaviorTreeNode::Sequence(
+                children
+                    .into_iter()
+                    .map(|child| BehaviorTreeNode::new(child, condition_count))
+                    .collect(),
+            ),
+            BehaviorNode::Condition(condition) => {
+                let index = *condition_count;
+                *condition_count += 1;
+                BehaviorTreeNode::Condition { condition, index }
+            }
+            BehaviorNode::Action(action) => BehaviorTreeNode::Action {
+                id: next_action_id(),
+                action: action.into(),
+            },
+        }
+    }
+}
+
+/// Reactions to other players in the map based on their proximity to the player.
+#[derive(Clone, Copy, Debug)]
+pub struct OtherPlayerReactions {
+    /// The reaction when another player is on the same platform.
+    pub same_platform: OtherPlayerReaction,
+    /// The reaction when another player is in the map but not on the same platform.
+    pub far: OtherPlayerReaction,
+    /// The milliseconds to wait before changing channel.
+    pub change_channel_millis: u64,
+}
+
+impl Default for OtherPlayerReactions {
+    fn default() -> Self {
+        Self {
+            same_platform: OtherPlayerReaction::default(),
+            far: OtherPlayerReaction::default(),
+            change_channel_millis: 15000,
+        }
+    }
+}
+
+impl OtherPlayerReactions {
+    /// Gets the reaction to other players in `idle` for the player at `pos`.
+    ///
+    /// Returns [`None`] if there is no other player.
+    fn reaction(self, idle: MinimapIdle, pos: Option<Point>) -> Option<OtherPlayerReaction> {
+        idle.other_player_proximity(pos)
+            .map(|proximity| match proximity {
+                OtherPlayerProximity::SamePlatform => self.same_platform,
+                OtherPlayerProximity::Far => self.far,
+            })
+    }
 }
 
 #[derive(Debug)]
 pub struct RotatorBuildArgs<'a> {
     pub mode: RotatorMode,
+    pub auto_mob_regions: Vec<Bound>,
+    pub auto_mob_region_selection: MobbingRegionSelection,
     pub actions: &'a [Action],
     pub buffs: &'a [(BuffKind, KeyKind)],
     pub familiars: Familiars,
@@ -151,12 +293,20 @@ pub struct RotatorBuildArgs<'a> {
     pub hexa_booster_exchange_condition: ExchangeHexaBoosterCondition,
     pub hexa_booster_exchange_amount: u32,
     pub hexa_booster_exchange_all: bool,
+    pub shop_purchase: ShopPurchase,
+    /// The periodic map-wide attacks of the current map to avoid.
+    pub map_hazards: &'a [MapHazard],
+    /// The jump key used for avoiding [`MapHazardAvoidance::Jump`] hazards.
+    pub jump_key: KeyKind,
     pub enable_panic_mode: bool,
+    pub other_player_reactions: OtherPlayerReactions,
     pub enable_rune_solving: bool,
     pub enable_transparent_shape_solving: bool,
     pub enable_reset_normal_actions_on_erda: bool,
     pub enable_using_generic_booster: bool,
     pub enable_using_hexa_booster: bool,
+    pub enable_level_up_popups_dismissal: bool,
+    pub condition_webhooks: &'a [ConditionWebhook],
 }
 
 /// Handles rotating provided [`PlayerAction`]s.
@@ -179,9 +329,29 @@ pub trait Rotator: Debug + 'static {
 
     /// Rotates actions previously built with [`Self::build_actions`].
     ///
-    /// If [`Operation`] is currently halting, it does not rotate the built actions but only the
-    /// side-loaded actions added by [`Self::inject_action`].
+    /// If [`Operation`] is currently halting or stopping, it does not rotate the built actions but
+    /// only the side-loaded actions added by [`Self::inject_action`].
     fn rotate_action(&mut self, resources: &Resources, world: &mut World);
+
+    /// Gets the names of the actions waiting to be rotated without rotating.
+    ///
+    /// This includes the remaining linked actions, the queued priority and side-loaded actions
+    /// and the next normal action if it can be known ahead.
+    fn queued_actions(&self) -> Vec<String>;
+
+    /// Gets the last queued times of built actions that persist their cooldowns.
+    fn action_cooldowns(&self) -> Vec<ActionCooldown>;
+
+    /// Restores the last queued times of built actions from previously persisted `cooldowns`.
+    ///
+    /// Cooldowns of actions already queued since built or queued in the future (e.g. the system
+    /// clock changed) are ignored.
+    fn restore_action_cooldowns(&mut self, cooldowns: &[ActionCooldown]);
+
+    /// Clears the last queued times of built actions whose cooldown key starts with `key_prefix`.
+    ///
+    /// This allows the actions to be queued again without waiting for their cooldowns.
+    fn reset_action_cooldowns(&mut self, key_prefix: &str);
 }
 
 #[derive(Default, Debug)]
@@ -201,6 +371,35 @@ pub struct DefaultRotator {
     /// This limits the number of detections can be done inside the same quad as to help player
     /// advances to the next quad.
     auto_mob_quadrant_consecutive_count: Option<(Quadrant, u32)>,
+    /// Bounds of the named regions used when [`Self::normal_rotate_mode`] is
+    /// [`RotatorMode::AutoMobbing`].
+    ///
+    /// If not empty, the bound inside [`RotatorMode::AutoMobbing`] is ignored.
+    auto_mob_regions: Vec<Bound>,
+    auto_mob_region_selection: MobbingRegionSelection,
+    /// Index of the currently used region in [`Self::auto_mob_regions`].
+    auto_mob_region_index: usize,
+    /// Whether the region has been switched away from other players.
+    ///
+    /// This is reset once the reaction to other players is no longer
+    /// [`OtherPlayerReaction::MoveToAnotherRegion`] so that the region is only switched once
+    /// per encounter.
+    auto_mob_region_switched_for_other_player: bool,
+    /// The [`Task`] used for detecting loots when auto mobbing loot pickup is enabled.
+    auto_mob_loot_task: Option<Task<Result<Vec<Point>>>>,
+    /// Number of consecutive loot pickups without auto mobbing in between.
+    ///
+    /// This limits walking back and forth between loots that cannot be picked up (e.g. false
+    /// detections).
+    auto_mob_loot_consecutive_count: u32,
+    /// Reactions to other players when panic mode is enabled.
+    other_player_reactions: Option<OtherPlayerReactions>,
+    /// The compiled tree when [`Self::normal_rotate_mode`] is [`RotatorMode::BehaviorTree`].
+    behavior_tree: Option<BehaviorTreeNode>,
+    /// The last [`Instant`] each condition node of [`Self::behavior_tree`] succeeded.
+    behavior_tree_condition_times: Vec<Option<Instant>>,
+    /// Actions from the last tick of [`Self::behavior_tree`] waiting to be rotated.
+    behavior_tree_queue: VecDeque<(u32, PlayerAction)>,
 
     priority_actions: OrderedHashMap<u32, PriorityAction>,
     /// The currently executing [`RotatorAction::Linked`] action
@@ -214,13 +413,33 @@ pub struct DefaultRotator {
     /// These are actions injected externally and to be executed as appropriate with the current
     /// [`Self::priority_actions_queue`]. These actions are run only once and do not have an ID.
     priority_actions_side_queue: VecDeque<RotatorAction>,
+    /// The last [`Instant`] an action in each [`ActionKeyGroup`] was queued.
+    action_groups_last_queued_time: HashMap<ActionKeyGroup, Instant>,
 }
 
 impl DefaultRotator {
+    /// Tracks the time an action with `id` in `group` was queued.
+    ///
+    /// If `group` shares cooldown, other actions in the same group are treated as queued too.
+    fn update_action_group_queued(&mut self, id: u32, group: ActionGroup) {
+        let now = clock::now();
+        self.action_groups_last_queued_time.insert(group.kind, now);
+        if !group.shares_cooldown {
+            return;
+        }
+
+        for (other_id, action) in self.priority_actions.iter_mut() {
+            if *other_id != id && action.group.is_some_and(|other| other.kind == group.kind) {
+                action.queue_info.last_queued_time = Some(now);
+            }
+        }
+    }
+
     #[inline]
     fn reset_normal_actions_queue(&mut self) {
         self.normal_index = 0;
         self.normal_queuing_linked_action = None;
+        self.behavior_tree_queue.clear();
     }
 
     /// Rotates the actions inside the [`Self::priority_actions`]
@@ -369,7 +588,20 @@ impl DefaultRotator {
                 Some(ActionCondition::Any) => unreachable!(),
             };
             if action.queue_info.ignoring {
-                action.queue_info.last_queued_time = Some(Instant::now());
+                action.queue_info.last_queued_time = Some(clock::now());
+                continue;
+            }
+            if let Some(group) = action.group
+                && self
+                    .action_groups_last_queued_time
+                    .get(&group.kind)
+                    .is_some_and(|instant| {
+                        !at_least_millis_passed_since(
+                            Some(*instant),
+                            group.exclusive_millis as u128,
+                        )
+                    })
+            {
                 continue;
             }
 
@@ -393,7 +625,7 @@ impl DefaultRotator {
                             } else {
                                 self.priority_actions_queue.push_back(id);
                             }
-                            action.queue_info.last_queued_time = Some(Instant::now());
+                            action.queue_info.last_queued_time = Some(clock::now());
 
                             if !did_queue_erda_action {
                                 did_queue_erda_action = matches!(
@@ -401,6 +633,9 @@ impl DefaultRotator {
                                     Some(ActionCondition::ErdaShowerOffCooldown)
                                 );
                             }
+                            if let Some(group) = action.group {
+                                self.update_action_group_queued(id, group);
+                            }
                         }
                         ResolveConflict::Replace { id: replace_id } => {
                             if let Some(replace_id) = self
@@ -411,16 +646,16 @@ impl DefaultRotator {
                                 *replace_id = id;
                             }
 
-                            action.queue_info.last_queued_time = Some(Instant::now());
+                            action.queue_info.last_queued_time = Some(clock::now());
                         }
                         ResolveConflict::Ignore => {
-                            action.queue_info.last_queued_time = Some(Instant::now());
+                            action.queue_info.last_queued_time = Some(clock::now());
                         }
                     }
                 }
                 ConditionResult::Skip => (),
                 ConditionResult::Ignore => {
-                    action.queue_info.last_queued_time = Some(Instant::now());
+                    action.queue_info.last_queued_time = Some(clock::now());
                 }
             }
         }
@@ -562,15 +797,45 @@ impl DefaultRotator {
         let Some(pos) = player_context.last_known_pos else {
             return;
         };
-        let bound = if player_context.config.auto_mob_platforms_bound {
-            idle.platforms_bound.unwrap_or(bound.into())
+
+        // Moves on to another region when another player is around
+        let reaction = self
+            .other_player_reactions
+            .and_then(|reactions| reactions.reaction(idle, Some(pos)));
+        if matches!(reaction, Some(OtherPlayerReaction::MoveToAnotherRegion)) {
+            if !self.auto_mob_region_switched_for_other_player && self.auto_mob_regions.len() > 1 {
+                self.auto_mob_region_switched_for_other_player = true;
+                self.auto_mob_region_index = next_auto_mob_region_index(
+                    resources,
+                    self.auto_mob_region_index,
+                    self.auto_mob_regions.len(),
+                    self.auto_mob_region_selection,
+                );
+                let index = self.auto_mob_region_index;
+                debug!(target: "rotator", "auto mob switched to region {index} for other player");
+            }
         } else {
-            bound.into()
+            self.auto_mob_region_switched_for_other_player = false;
+        }
+
+        if player_context.config.auto_mob_loot_pickup
+            && self.rotate_auto_mob_loot_pickup(resources, player_context, minimap_state, idle, pos)
+        {
+            return;
+        }
+
+        let mut bound = match self.auto_mob_regions.get(self.auto_mob_region_index) {
+            Some(region) => (*region).into(),
+            None if player_context.config.auto_mob_platforms_bound => {
+                idle.platforms_bound.unwrap_or(bound.into())
+            }
+            None => bound.into(),
         };
 
+        let detection = player_context.mob_detection.clone();
         let Update::Ok(points) =
             update_detection_task(resources, 0, &mut self.auto_mob_task, move |detector| {
-                detector.detect_mobs(idle.bbox, bound, pos)
+                detector.detect_mobs(idle.bbox, bound, pos, &detection)
             })
         else {
             return;
@@ -597,6 +862,18 @@ impl DefaultRotator {
             .collect::<Vec<_>>();
         let mut use_pathing_point = false;
 
+        // Moves on to another region when the current one runs out of mobs
+        if points.is_empty() && self.auto_mob_regions.len() > 1 {
+            self.auto_mob_region_index = next_auto_mob_region_index(
+                resources,
+                self.auto_mob_region_index,
+                self.auto_mob_regions.len(),
+                self.auto_mob_region_selection,
+            );
+            bound = self.auto_mob_regions[self.auto_mob_region_index].into();
+            debug!(target: "rotator", "auto mob switched to region {}", self.auto_mob_region_index);
+        }
+
         if let Some(last_quad) = player_context.auto_mob_last_quadrant()
             && !points.is_empty()
         {
@@ -630,13 +907,11 @@ impl DefaultRotator {
                     player_context.auto_mob_pathing_point(resources, minimap_state, bound)
                 })
         };
-        let key_hold_ticks = (key.key_hold_millis / MS_PER_TICK) as u32;
-        let wait_before_ticks = (key.wait_before_millis / MS_PER_TICK) as u32;
-        let wait_before_ticks_random_range =
-            (key.wait_before_millis_random_range / MS_PER_TICK) as u32;
-        let wait_after_ticks = (key.wait_after_millis / MS_PER_TICK) as u32;
-        let wait_after_ticks_random_range =
-            (key.wait_after_millis_random_range / MS_PER_TICK) as u32;
+        let key_hold_ticks = ticks_from_millis(key.key_hold_millis);
+        let wait_before_ticks = ticks_from_millis(key.wait_before_millis);
+        let wait_before_ticks_random_range = ticks_from_millis(key.wait_before_millis_random_range);
+        let wait_after_ticks = ticks_from_millis(key.wait_after_millis);
+        let wait_after_ticks_random_range = ticks_from_millis(key.wait_after_millis_random_range);
         let position = Position {
             x: point.x,
             x_random_range: 0,
@@ -662,18 +937,84 @@ impl DefaultRotator {
         );
     }
 
+    /// Detects loots near the player and sets the player to walk over the closest one.
+    ///
+    /// Returns `true` if the player is set to pick up a loot.
+    fn rotate_auto_mob_loot_pickup(
+        &mut self,
+        resources: &Resources,
+        player_context: &mut PlayerContext,
+        minimap_state: Minimap,
+        idle: MinimapIdle,
+        pos: Point,
+    ) -> bool {
+        if self.auto_mob_loot_consecutive_count >= AUTO_MOB_LOOT_MAX_CONSECUTIVE_COUNT {
+            self.auto_mob_loot_consecutive_count = 0;
+            return false;
+        }
+
+        let Update::Ok(points) = update_detection_task(
+            resources,
+            0,
+            &mut self.auto_mob_loot_task,
+            move |detector| detector.detect_loots(idle.bbox, pos),
+        ) else {
+            return false;
+        };
+        let points = points
+            .iter()
+            .map(|point| Point::new(point.x, idle.bbox.height - point.y))
+            .collect::<Vec<_>>();
+        let radius = player_context.config.auto_mob_loot_pickup_radius;
+        let point = auto_mob_loot_point(&points, pos, radius).and_then(|point| {
+            player_context.auto_mob_pick_reachable_y_position(resources, minimap_state, point)
+        });
+        let Some(point) = point else {
+            self.auto_mob_loot_consecutive_count = 0;
+            return false;
+        };
+
+        debug!(target: "rotator", "auto mob picking up loot at {point:?}");
+        self.auto_mob_loot_consecutive_count += 1;
+        player_context.set_normal_action(
+            None,
+            PlayerAction::Move(Move {
+                position: Position {
+                    x: point.x,
+                    x_random_range: 0,
+                    y: point.y,
+                    allow_adjusting: true,
+                },
+                wait_after_move_ticks: 0,
+            }),
+        );
+        true
+    }
+
     fn rotate_ping_pong(
         &mut self,
         player_context: &mut PlayerContext,
         minimap_state: Minimap,
         key: MobbingKey,
         bound: Bound,
+        vertical: bool,
     ) {
         if player_context.has_normal_action() {
             return;
         }
 
         let Minimap::Idle(idle) = minimap_state else {
+            // Bounces off the last direction since the position is unknown
+            if player_context.config.ping_pong_screen_fallback && !vertical {
+                let direction = match player_context.last_known_direction() {
+                    ActionKeyDirection::Left => PingPongDirection::Right,
+                    ActionKeyDirection::Right | ActionKeyDirection::Any => PingPongDirection::Left,
+                };
+                player_context.set_normal_action(
+                    None,
+                    PlayerAction::PingPong(ping_pong_action(key, Rect::default(), direction)),
+                );
+            }
             return;
         };
         let Some(pos) = player_context.last_known_pos else {
@@ -681,37 +1022,33 @@ impl DefaultRotator {
         };
 
         let bbox = idle.bbox;
-        let dist_left = pos.x - bbox.x;
-        let dist_right = (bbox.x + bbox.width) - pos.x;
-        let direction = if dist_left > dist_right {
-            PingPongDirection::Left
-        } else {
-            PingPongDirection::Right
-        };
         let bound = Rect::new(
             bound.x,
             bbox.height - (bound.y + bound.height),
             bound.width,
             bound.height,
         );
+        let direction = if vertical {
+            let dist_bottom = pos.y - bound.y;
+            let dist_top = (bound.y + bound.height) - pos.y;
+            if dist_bottom > dist_top {
+                PingPongDirection::Down
+            } else {
+                PingPongDirection::Up
+            }
+        } else {
+            let dist_left = pos.x - bbox.x;
+            let dist_right = (bbox.x + bbox.width) - pos.x;
+            if dist_left > dist_right {
+                PingPongDirection::Left
+            } else {
+                PingPongDirection::Right
+            }
+        };
 
         player_context.set_normal_action(
             None,
-            PlayerAction::PingPong(PingPong {
-                key: key.key.into(),
-                key_hold_ticks: (key.key_hold_millis / MS_PER_TICK) as u32,
-                link_key: key.link_key.into(),
-                count: key.count.max(1),
-                with: key.with,
-                wait_before_ticks: (key.wait_before_millis / MS_PER_TICK) as u32,
-                wait_before_ticks_random_range: (key.wait_before_millis_random_range / MS_PER_TICK)
-                    as u32,
-                wait_after_ticks: (key.wait_after_millis / MS_PER_TICK) as u32,
-                wait_after_ticks_random_range: (key.wait_after_millis_random_range / MS_PER_TICK)
-                    as u32,
-                bound,
-                direction,
-            }),
+            PlayerAction::PingPong(ping_pong_action(key, bound, direction)),
         );
     }
 
@@ -772,6 +1109,68 @@ impl DefaultRotator {
         }
     }
 
+    fn rotate_behavior_tree(&mut self, world: &mut World) {
+        if world.player.context.has_normal_action() {
+            return;
+        }
+
+        if self.behavior_tree_queue.is_empty() {
+            let Some(tree) = self.behavior_tree.as_ref() else {
+                return;
+            };
+            let mut actions = Vec::new();
+            let mut conditions = Vec::new();
+            tick_behavior_node(
+                tree,
+                world,
+                &self.behavior_tree_condition_times,
+                &mut actions,
+                &mut conditions,
+            );
+
+            let now = clock::now();
+            for index in conditions {
+                self.behavior_tree_condition_times[index] = Some(now);
+            }
+            self.behavior_tree_queue.extend(actions);
+        }
+
+        if let Some((id, action)) = self.behavior_tree_queue.pop_front() {
+            world.player.context.set_normal_action(Some(id), action);
+        }
+    }
+
+    /// Gets the next normal action to be rotated without rotating.
+    ///
+    /// Returns [`None`] when the next normal action is computed on demand (e.g. auto mobbing).
+    fn next_normal_action(&self) -> Option<&RotatorAction> {
+        if self.normal_actions.is_empty() {
+            return None;
+        }
+
+        let len = self.normal_actions.len();
+        let index = match self.normal_rotate_mode {
+            RotatorMode::StartToEnd => self.normal_index,
+            RotatorMode::StartToEndThenReverse => {
+                let (index, backward) = if (self.normal_index + 1) == len {
+                    (0, !self.normal_actions_backward)
+                } else {
+                    (self.normal_index, self.normal_actions_backward)
+                };
+                if backward {
+                    (len - index).saturating_sub(1)
+                } else {
+                    index
+                }
+            }
+            RotatorMode::AutoMobbing(_, _)
+            | RotatorMode::PingPong(_, _, _)
+            | RotatorMode::BehaviorTree(_) => return None,
+        };
+
+        self.normal_actions.get(index).map(|(_, action)| action)
+    }
+
     #[inline]
     fn rotate_queuing_linked_action(
         &mut self,
@@ -819,6 +1218,8 @@ impl Rotator for DefaultRotator {
         info!(target: "rotator", "preparing actions {args:?}");
         let RotatorBuildArgs {
             mode,
+            auto_mob_regions,
+            auto_mob_region_selection,
             actions,
             buffs,
             familiars,
@@ -828,30 +1229,57 @@ impl Rotator for DefaultRotator {
             hexa_booster_exchange_condition,
             hexa_booster_exchange_amount,
             hexa_booster_exchange_all,
+            shop_purchase,
+            map_hazards,
+            jump_key,
             enable_panic_mode,
+            other_player_reactions,
             enable_rune_solving,
             enable_transparent_shape_solving,
             enable_reset_normal_actions_on_erda,
             enable_using_generic_booster,
             enable_using_hexa_booster,
+            enable_level_up_popups_dismissal,
+            condition_webhooks,
         } = args;
         self.reset_queue();
         self.normal_actions.clear();
+        self.behavior_tree_condition_times.clear();
+        self.behavior_tree = match &mode {
+            RotatorMode::BehaviorTree(Some(tree)) => {
+                let mut condition_count = 0;
+                let tree = BehaviorTreeNode::new(tree.clone(), &mut condition_count);
+                self.behavior_tree_condition_times = vec![None; condition_count];
+                Some(tree)
+            }
+            _ => None,
+        };
         self.normal_rotate_mode = mode;
+        self.auto_mob_regions = auto_mob_regions;
+        self.auto_mob_region_selection = auto_mob_region_selection;
+        self.auto_mob_region_index = 0;
+        self.auto_mob_region_switched_for_other_player = false;
+        self.other_player_reactions = enable_panic_mode.then_some(other_player_reactions);
         self.normal_actions_reset_on_erda = enable_reset_normal_actions_on_erda;
         self.priority_actions.clear();
+        self.action_groups_last_queued_time.clear();
 
         // Low priority
         if enable_using_generic_booster {
             self.priority_actions.insert(
                 next_action_id(),
-                use_booster_priority_action(Booster::Generic),
+                with_cooldown_key(
+                    use_booster_priority_action(Booster::Generic),
+                    "booster:generic",
+                ),
             );
         }
 
         if enable_using_hexa_booster {
-            self.priority_actions
-                .insert(next_action_id(), use_booster_priority_action(Booster::Hexa));
+            self.priority_actions.insert(
+                next_action_id(),
+                with_cooldown_key(use_booster_priority_action(Booster::Hexa), "booster:hexa"),
+            );
         }
 
         if !matches!(
@@ -860,10 +1288,23 @@ impl Rotator for DefaultRotator {
         ) {
             self.priority_actions.insert(
                 next_action_id(),
-                exchange_hexa_booster_priority_action(
-                    hexa_booster_exchange_condition,
-                    hexa_booster_exchange_amount,
-                    hexa_booster_exchange_all,
+                with_cooldown_key(
+                    exchange_hexa_booster_priority_action(
+                        hexa_booster_exchange_condition,
+                        hexa_booster_exchange_amount,
+                        hexa_booster_exchange_all,
+                    ),
+                    "booster:hexa-exchange",
+                ),
+            );
+        }
+
+        if shop_purchase.enabled {
+            self.priority_actions.insert(
+                next_action_id(),
+                with_cooldown_key(
+                    shop_purchase_priority_action(shop_purchase),
+                    "shop:purchase",
                 ),
             );
         }
@@ -871,12 +1312,17 @@ impl Rotator for DefaultRotator {
         if familiars.enable_familiars_swapping {
             self.priority_actions.insert(
                 next_action_id(),
-                familiars_swap_priority_action(
-                    FamiliarsSwap {
-                        swappable_slots: familiars.swappable_familiars,
-                        swappable_rarities: Array::from_iter(familiars.swappable_rarities.clone()),
-                    },
-                    familiars.swap_check_millis,
+                with_cooldown_key(
+                    familiars_swap_priority_action(
+                        FamiliarsSwap {
+                            swappable_slots: familiars.swappable_familiars,
+                            swappable_rarities: Array::from_iter(
+                                familiars.swappable_rarities.clone(),
+                            ),
+                        },
+                        familiars.swap_check_millis,
+                    ),
+                    "familiars:swap",
                 ),
             );
         }
@@ -890,6 +1336,57 @@ impl Rotator for DefaultRotator {
                 Action::Move(_) => false,
                 Action::Key(ActionKey { queue_to_front, .. }) => queue_to_front.unwrap_or_default(),
             };
+            let burst = match action {
+                Action::Move(_) => None,
+                Action::Key(ActionKey {
+                    burst_buff,
+                    burst_max_wait_millis,
+                    ..
+                }) => burst_buff_kind(burst_buff).map(|kind| (kind, burst_max_wait_millis)),
+            };
+            let group = match action {
+                Action::Key(ActionKey {
+                    group: kind,
+                    group_shares_cooldown,
+                    group_exclusive_millis,
+                    ..
+                }) if kind != ActionKeyGroup::None => Some(ActionGroup {
+                    kind,
+                    shares_cooldown: group_shares_cooldown,
+                    exclusive_millis: group_exclusive_millis,
+                }),
+                Action::Move(_) | Action::Key(_) => None,
+            };
+            let plugin_detector = match action {
+                Action::Move(_) => None,
+                Action::Key(ActionKey {
+                    plugin_detector, ..
+                }) => plugin_detector,
+            };
+            let resource_gate = match action {
+                Action::Move(_) => None,
+                Action::Key(ActionKey { resource_gate, .. }) => resource_gate,
+            };
+            let condition_webhook = match action {
+                Action::Move(_) => None,
+                Action::Key(ActionKey {
+                    condition_webhook, ..
+                }) => condition_webhook.map(|name| {
+                    let webhook = condition_webhooks
+                        .iter()
+                        .find(|webhook| webhook.name == name.as_str())
+                        .cloned();
+                    if webhook.is_none() {
+                        warn!(target: "rotator", "condition webhook {name} not found, the action will not be queued");
+                    }
+                    webhook
+                }),
+            };
+            let loud_reactions = match action {
+                Action::Key(ActionKey { loud: true, .. }) => self.other_player_reactions,
+                Action::Move(_) | Action::Key(_) => None,
+            };
+            let cooldown_key = action_cooldown_key(&action);
             let (action, offset) = rotator_action(action, i, actions);
             debug_assert!(i != 0 || !matches!(condition, ActionCondition::Linked));
             // Should not move i below the match because it could cause
@@ -899,7 +1396,20 @@ impl Rotator for DefaultRotator {
                 ActionCondition::EveryMillis(_) | ActionCondition::ErdaShowerOffCooldown => {
                     self.priority_actions.insert(
                         next_action_id(),
-                        priority_action(action, condition, queue_to_front),
+                        with_cooldown_key(
+                            priority_action(
+                                action,
+                                condition,
+                                queue_to_front,
+                                burst,
+                                group,
+                                plugin_detector,
+                                resource_gate,
+                                condition_webhook,
+                                loud_reactions,
+                            ),
+                            cooldown_key,
+                        ),
                     );
                 }
                 ActionCondition::Any => {
@@ -939,26 +1449,68 @@ impl Rotator for DefaultRotator {
         }
 
         if enable_panic_mode {
-            self.priority_actions
-                .insert(next_action_id(), panic_priority_action());
+            self.priority_actions.insert(
+                next_action_id(),
+                panic_priority_action(other_player_reactions),
+            );
         }
 
-        if buffs
+        if let Some((_, familiar_key)) = buffs
             .iter()
-            .any(|(buff, _)| matches!(buff, BuffKind::Familiar))
+            .find(|(buff, _)| matches!(buff, BuffKind::Familiar))
         {
             self.priority_actions.insert(
                 next_action_id(),
-                familiar_essence_replenish_priority_action(familiar_essence_key),
+                with_cooldown_key(
+                    familiar_essence_replenish_priority_action(familiar_essence_key),
+                    "familiars:essence",
+                ),
             );
+            if familiars.buff_duration_millis > 0 {
+                self.priority_actions.insert(
+                    next_action_id(),
+                    familiar_resummon_priority_action(
+                        *familiar_key,
+                        familiars.buff_duration_millis,
+                    ),
+                );
+                if familiars.enable_familiars_swapping {
+                    self.priority_actions.insert(
+                        next_action_id(),
+                        familiar_resummon_fallback_priority_action(FamiliarsSwap {
+                            swappable_slots: familiars.swappable_familiars,
+                            swappable_rarities: Array::from_iter(
+                                familiars.swappable_rarities.clone(),
+                            ),
+                        }),
+                    );
+                }
+            }
         }
         for (i, key) in buffs.iter().copied() {
-            self.priority_actions
-                .insert(next_action_id(), buff_priority_action(i, key));
+            self.priority_actions.insert(
+                next_action_id(),
+                with_cooldown_key(buff_priority_action(i, key), format!("buff:{i:?}")),
+            );
+        }
+
+        for hazard in map_hazards
+            .iter()
+            .copied()
+            .filter(|hazard| hazard.enabled && hazard.interval_millis > 0)
+        {
+            self.priority_actions.insert(
+                next_action_id(),
+                map_hazard_priority_action(hazard, jump_key),
+            );
         }
 
         self.priority_actions
             .insert(next_action_id(), unstuck_priority_action());
+        if enable_level_up_popups_dismissal {
+            self.priority_actions
+                .insert(next_action_id(), dismiss_level_up_popup_priority_action());
+        }
     }
 
     #[inline]
@@ -969,6 +1521,8 @@ impl Rotator for DefaultRotator {
         self.priority_queuing_linked_action = None;
         self.auto_mob_task = None;
         self.auto_mob_quadrant_consecutive_count = None;
+        self.auto_mob_loot_task = None;
+        self.auto_mob_loot_consecutive_count = 0;
     }
 
     #[inline]
@@ -979,7 +1533,7 @@ impl Rotator for DefaultRotator {
 
     #[inline]
     fn rotate_action(&mut self, resources: &Resources, world: &mut World) {
-        if resources.operation.halting() {
+        if resources.operation.halting() || matches!(resources.operation, Operation::Stopping) {
             if !has_side_loaded_action_executing(&world.player.context) {
                 self.rotate_side_priority_action(&mut world.player.context);
             }
@@ -1001,25 +1555,190 @@ impl Rotator for DefaultRotator {
                 key,
                 bound,
             ),
-            RotatorMode::PingPong(key, bound) => {
-                self.rotate_ping_pong(&mut world.player.context, world.minimap.state, key, bound)
+            RotatorMode::PingPong(key, bound, vertical) => self.rotate_ping_pong(
+                &mut world.player.context,
+                world.minimap.state,
+                key,
+                bound,
+                vertical,
+            ),
+            RotatorMode::BehaviorTree(_) => self.rotate_behavior_tree(world),
+        }
+    }
+
+    fn queued_actions(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        if let Some((_, action)) = self.priority_queuing_linked_action.as_ref() {
+            names.push(format!("Priority: {action}"));
+        }
+        for id in self.priority_actions_queue.iter() {
+            if let Some(action) = self.priority_actions.get(id) {
+                names.push(format!("Priority: {}", action.inner));
+            }
+        }
+        for action in self.priority_actions_side_queue.iter() {
+            names.push(format!("Side: {action}"));
+        }
+        if let Some((_, action)) = self.normal_queuing_linked_action.as_ref() {
+            names.push(format!("Normal: {action}"));
+        } else if let Some(action) = self.next_normal_action() {
+            names.push(format!("Normal: {action}"));
+        }
+        for (_, action) in self.behavior_tree_queue.iter() {
+            names.push(format!("Normal: {action}"));
+        }
+
+        names
+    }
+
+    fn action_cooldowns(&self) -> Vec<ActionCooldown> {
+        let now = clock::now();
+        let now_millis = unix_millis_now();
+
+        self.priority_actions
+            .iter()
+            .filter_map(|(_, action)| {
+                let key = action.queue_info.cooldown_key.clone()?;
+                let elapsed = now.saturating_duration_since(action.queue_info.last_queued_time?);
+
+                Some(ActionCooldown {
+                    key,
+                    queued_at_millis: now_millis.saturating_sub(elapsed.as_millis() as u64),
+                })
+            })
+            .collect()
+    }
+
+    fn restore_action_cooldowns(&mut self, cooldowns: &[ActionCooldown]) {
+        let now = clock::now();
+        let now_millis = unix_millis_now();
+
+        for (_, action) in self.priority_actions.iter_mut() {
+            let info = &mut action.queue_info;
+            if info.last_queued_time.is_some() {
+                continue;
+            }
+            let Some(cooldown) = cooldowns
+                .iter()
+                .find(|cooldown| info.cooldown_key.as_ref() == Some(&cooldown.key))
+            else {
+                continue;
+            };
+            if cooldown.queued_at_millis > now_millis {
+                continue;
+            }
+
+            let elapsed = Duration::from_millis(now_millis - cooldown.queued_at_millis);
+            info.last_queued_time = now.checked_sub(elapsed);
+        }
+    }
+
+    fn reset_action_cooldowns(&mut self, key_prefix: &str) {
+        for (_, action) in self.priority_actions.iter_mut() {
+            let info = &mut action.queue_info;
+            if info
+                .cooldown_key
+                .as_ref()
+                .is_some_and(|key| key.starts_with(key_prefix))
+            {
+                info.last_queued_time = None;
             }
         }
     }
 }
 
+/// Gets the current system time in milliseconds since the Unix epoch.
+#[inline]
+pub(crate) fn unix_millis_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[inline]
+fn with_cooldown_key(mut action: PriorityAction, key: impl Into<String>) -> PriorityAction {
+    action.queue_info.cooldown_key = Some(key.into());
+    action
+}
+
+/// Creates a key identifying `action` across restarts for persisting its cooldown.
+///
+/// The key changes whenever the action is edited so that an edited action starts off cooldown.
+fn action_cooldown_key(action: &Action) -> String {
+    // FNV-1a because the standard hasher output is not guaranteed to be stable across releases
+    let hash = serde_json::to_string(action)
+        .unwrap_or_default()
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+
+    format!("action:{hash:016x}")
+}
+
 #[inline]
 fn has_side_loaded_action_executing(player_context: &PlayerContext) -> bool {
     player_context.has_priority_action() && player_context.priority_action_id().is_none()
 }
 
-/// Creates a [`RotatorAction`] with `start_action` as the initial action
+#[inline]
+fn ping_pong_action(key: MobbingKey, bound: Rect, direction: PingPongDirection) -> PingPong {
+    PingPong {
+        key: key.key.into(),
+        key_hold_ticks: ticks_from_millis(key.key_hold_millis),
+        link_key: key.link_key.into(),
+        count: key.count.max(1),
+        with: key.with,
+        wait_before_ticks: ticks_from_millis(key.wait_before_millis),
+        wait_before_ticks_random_range: ticks_from_millis(key.wait_before_millis_random_range),
+        wait_after_ticks: ticks_from_millis(key.wait_after_millis),
+        wait_after_ticks_random_range: ticks_from_millis(key.wait_after_millis_random_range),
+        bound,
+        direction,
+    }
+}
+
+/// Finds the closest loot in `points` within `radius` x distance from `pos`.
 ///
-/// If `start_action` is linked, this function returns [`RotatorAction::Linked`] with [`usize`] as
-/// the offset from `start_index` to the next non-linked action.
-/// Otherwise, this returns [`RotatorAction::Single`] with [`usize`] offset of 1.
+/// Loots within [`AUTO_MOB_LOOT_PICKED_X_THRESHOLD`] are considered already picked up. A
+/// `radius` of `0` means no limit.
+fn auto_mob_loot_point(points: &[Point], pos: Point, radius: i32) -> Option<Point> {
+    points
+        .iter()
+        .copied()
+        .filter(|point| {
+            let distance = (point.x - pos.x).abs();
+            distance > AUTO_MOB_LOOT_PICKED_X_THRESHOLD && (radius <= 0 || distance <= radius)
+        })
+        .min_by_key(|point| (point.x - pos.x).abs())
+}
+
+/// Selects the index of the next auto mobbing region other than `current`.
 #[inline]
-fn rotator_action(
+fn next_auto_mob_region_index(
+    resources: &Resources,
+    current: usize,
+    len: usize,
+    selection: MobbingRegionSelection,
+) -> usize {
+    debug_assert!(len > 1);
+    match selection {
+        MobbingRegionSelection::Cycle => (current + 1) % len,
+        MobbingRegionSelection::Random => {
+            let offset = resources.rng.random_range(1..len);
+            (current + offset) % len
+        }
+    }
+}
+
+/// Creates a [`RotatorAction`] with `start_action` as the initial action
+///
+/// If `start_action` is linked, this function returns [`RotatorAction::Linked`] with [`usize`] as
+/// the offset from `start_index` to the next non-linked action.
+/// Otherwise, this returns [`RotatorAction::Single`] with [`usize`] offset of 1.
+#[inline]
+fn rotator_action(
     start_action: Action,
     start_index: usize,
     actions: &[Action],
@@ -1071,28 +1790,91 @@ fn rotator_action(
     (RotatorAction::Linked(head), offset)
 }
 
+/// Creates a [`PriorityAction`] from a user-provided action.
+///
+/// When `burst` is provided, the action is held after its `condition` is satisfied until the
+/// [`BuffKind`] is active or the maximum wait milliseconds has passed. When `plugin_detector` is
+/// provided, the action is additionally held until the plugin detector detects. When
+/// `resource_gate` is provided, the action is also held until its region is satisfied. When
+/// `condition_webhook` is provided, the action is also held until the webhook allows or never
+/// queued if the webhook cannot be resolved ([`None`]). When
+/// `loud_reactions` is provided, the action is held while the reaction to other players is
+/// [`OtherPlayerReaction::PauseLoudSkills`].
 #[inline]
+#[allow(clippy::too_many_arguments)]
 fn priority_action(
     action: RotatorAction,
     condition: ActionCondition,
     queue_to_front: bool,
+    burst: Option<(BuffKind, u64)>,
+    group: Option<ActionGroup>,
+    plugin_detector: Option<PluginDetectorName>,
+    resource_gate: Option<ActionKeyResourceGate>,
+    condition_webhook: Option<Option<ConditionWebhook>>,
+    loud_reactions: Option<OtherPlayerReactions>,
 ) -> PriorityAction {
     debug_assert_matches!(
         condition,
         ActionCondition::EveryMillis(_) | ActionCondition::ErdaShowerOffCooldown
     );
+    let mut burst_wait_start = None;
+    let mut plugin_detector_task: Option<Task<Result<bool>>> = None;
+    let mut resource_gate_task: Option<Task<Result<bool>>> = None;
+    let mut condition_webhook_gate =
+        condition_webhook.map(|webhook| webhook.map(ConditionWebhookGate::new));
+
     PriorityAction {
         inner: action,
-        condition: Condition(Box::new(move |_, world, info| {
-            if should_queue_fixed_action(world, info.last_queued_time, condition) {
-                ConditionResult::Queue
-            } else {
-                ConditionResult::Skip
+        condition: Condition(Box::new(move |resources, world, info| {
+            if !should_queue_fixed_action(world, info.last_queued_time, condition) {
+                return ConditionResult::Skip;
+            }
+            if let Some(reactions) = loud_reactions
+                && let Minimap::Idle(idle) = world.minimap.state
+                && matches!(
+                    reactions.reaction(idle, world.player.context.last_known_pos),
+                    Some(OtherPlayerReaction::PauseLoudSkills)
+                )
+            {
+                return ConditionResult::Skip;
+            }
+            if let Some((kind, max_wait_millis)) = burst
+                && !should_queue_burst_action(world, kind, max_wait_millis, &mut burst_wait_start)
+            {
+                return ConditionResult::Skip;
+            }
+            if let Some(name) = plugin_detector {
+                let task_fn =
+                    move |detector: Arc<dyn Detector>| detector.detect_plugin(name.as_str());
+                if !matches!(
+                    update_detection_task(resources, 1000, &mut plugin_detector_task, task_fn),
+                    Update::Ok(true)
+                ) {
+                    return ConditionResult::Skip;
+                }
+            }
+            if let Some(gate) = resource_gate {
+                let task_fn =
+                    move |detector: Arc<dyn Detector>| detector.detect_resource_gate(gate);
+                if !matches!(
+                    update_detection_task(resources, 1000, &mut resource_gate_task, task_fn),
+                    Update::Ok(true)
+                ) {
+                    return ConditionResult::Skip;
+                }
+            }
+            match condition_webhook_gate.as_mut() {
+                Some(Some(gate)) if !gate.update() => return ConditionResult::Skip,
+                Some(None) => return ConditionResult::Skip,
+                Some(Some(_)) | None => (),
             }
+
+            ConditionResult::Queue
         })),
         condition_kind: Some(condition),
         metadata: None,
         queue_to_front,
+        group,
         queue_info: PriorityActionQueueInfo::default(),
     }
 }
@@ -1136,18 +1918,116 @@ fn familiar_essence_replenish_priority_action(key: KeyKind) -> PriorityAction {
             key,
             key_hold_ticks: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
             count: 1,
             position: None,
             direction: ActionKeyDirection::Any,
             with: ActionKeyWith::Any,
+            double_jump_apex_ticks: 0,
             wait_before_use_ticks: 5,
             wait_before_use_ticks_random_range: 0,
             wait_after_use_ticks: 0,
             wait_after_use_ticks_random_range: 0,
             wait_after_buffered: WaitAfterBuffered::None,
+            cast_verification: None,
+        })),
+        queue_to_front: true,
+        group: None,
+        queue_info: PriorityActionQueueInfo::default(),
+    }
+}
+
+/// Creates a [`PlayerAction::Key`] priority action to re-summon familiars shortly before the
+/// familiar buff expires.
+///
+/// The buff is assumed to be summoned when it is first detected as active, so the action is
+/// marked as [`ConditionResult::Ignore`] to restart the timer while the buff is not active.
+#[inline]
+fn familiar_resummon_priority_action(key: KeyKind, buff_duration_millis: u64) -> PriorityAction {
+    let resummon_millis = buff_duration_millis.saturating_sub(FAMILIAR_RESUMMON_LEAD_MILLIS);
+
+    PriorityAction {
+        condition: Condition(Box::new(move |_, world, info| {
+            if !matches!(world.buffs[BuffKind::Familiar].state, Buff::Yes)
+                || info.last_queued_time.is_none()
+            {
+                return ConditionResult::Ignore;
+            }
+            if !matches!(world.minimap.state, Minimap::Idle(_)) {
+                return ConditionResult::Skip;
+            }
+
+            if at_least_millis_passed_since(info.last_queued_time, resummon_millis.into()) {
+                ConditionResult::Queue
+            } else {
+                ConditionResult::Skip
+            }
+        })),
+        condition_kind: None,
+        metadata: None,
+        inner: RotatorAction::Single(PlayerAction::Key(Key {
+            key,
+            key_hold_ticks: 0,
+            key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
+            link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
+            count: 1,
+            position: None,
+            direction: ActionKeyDirection::Any,
+            with: ActionKeyWith::Stationary,
+            double_jump_apex_ticks: 0,
+            wait_before_use_ticks: 10,
+            wait_before_use_ticks_random_range: 0,
+            wait_after_use_ticks: 10,
+            wait_after_use_ticks_random_range: 0,
+            wait_after_buffered: WaitAfterBuffered::None,
+            cast_verification: None,
+        })),
+        queue_to_front: true,
+        group: None,
+        queue_info: PriorityActionQueueInfo::default(),
+    }
+}
+
+/// Creates a [`PlayerAction::FamiliarsSwap`] priority action to open the familiar menu and save
+/// the setup again when summoning familiars keeps failing.
+///
+/// The action queues once the familiar buff has not been active for
+/// [`FAMILIAR_RESUMMON_FALLBACK_MILLIS`] despite the familiar buff key being used.
+#[inline]
+fn familiar_resummon_fallback_priority_action(swap: FamiliarsSwap) -> PriorityAction {
+    PriorityAction {
+        condition: Condition(Box::new(move |_, world, info| {
+            if !matches!(world.buffs[BuffKind::Familiar].state, Buff::No)
+                || info.last_queued_time.is_none()
+            {
+                return ConditionResult::Ignore;
+            }
+            if world
+                .player
+                .context
+                .is_familiars_swap_fail_count_limit_reached()
+            {
+                return ConditionResult::Skip;
+            }
+
+            if at_least_millis_passed_since(
+                info.last_queued_time,
+                FAMILIAR_RESUMMON_FALLBACK_MILLIS.into(),
+            ) {
+                ConditionResult::Queue
+            } else {
+                ConditionResult::Skip
+            }
         })),
+        condition_kind: None,
+        metadata: None,
+        inner: RotatorAction::Single(PlayerAction::FamiliarsSwap(swap)),
         queue_to_front: true,
+        group: None,
         queue_info: PriorityActionQueueInfo::default(),
     }
 }
@@ -1174,6 +2054,7 @@ fn familiars_swap_priority_action(swap: FamiliarsSwap, swap_check_millis: u64) -
         metadata: None,
         inner: RotatorAction::Single(PlayerAction::FamiliarsSwap(swap)),
         queue_to_front: true,
+        group: None,
         queue_info: PriorityActionQueueInfo::default(),
     }
 }
@@ -1182,6 +2063,7 @@ fn familiars_swap_priority_action(swap: FamiliarsSwap, swap_check_millis: u64) -
 ///
 /// This action queues if all the following conditions are met:
 /// - The player is not currently validating a rune.
+/// - The retry delay of the last aborted solve has passed.
 /// - Enough time has passed since the last queue attempt.
 /// - The minimap is in the [`Minimap::Idle`] state.
 /// - A rune is present on the minimap.
@@ -1189,11 +2071,15 @@ fn familiars_swap_priority_action(swap: FamiliarsSwap, swap_check_millis: u64) -
 #[inline]
 fn solve_rune_priority_action() -> PriorityAction {
     PriorityAction {
-        condition: Condition(Box::new(|_, world, info| {
+        condition: Condition(Box::new(|resources, world, info| {
             if world.player.context.is_validating_rune() {
                 return ConditionResult::Ignore;
             }
 
+            if !world.player.context.can_retry_solving_rune(resources.tick) {
+                return ConditionResult::Skip;
+            }
+
             if !at_least_millis_passed_since(info.last_queued_time, 10000) {
                 return ConditionResult::Skip;
             }
@@ -1211,6 +2097,7 @@ fn solve_rune_priority_action() -> PriorityAction {
         metadata: None,
         inner: RotatorAction::Single(PlayerAction::SolveRune),
         queue_to_front: true,
+        group: None,
         queue_info: PriorityActionQueueInfo::default(),
     }
 }
@@ -1238,6 +2125,7 @@ fn solve_transparent_shape_priority_action() -> PriorityAction {
         metadata: None,
         inner: RotatorAction::Single(PlayerAction::SolveShape),
         queue_to_front: true,
+        group: None,
         queue_info: PriorityActionQueueInfo::default(),
     }
 }
@@ -1308,34 +2196,53 @@ fn buff_priority_action(buff: BuffKind, key: KeyKind) -> PriorityAction {
             key,
             key_hold_ticks: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
             count: 1,
             position: None,
             direction: ActionKeyDirection::Any,
             with: ActionKeyWith::Stationary,
+            double_jump_apex_ticks: 0,
             wait_before_use_ticks: 10,
             wait_before_use_ticks_random_range: 0,
             wait_after_use_ticks: 10,
             wait_after_use_ticks_random_range: 0,
             wait_after_buffered: WaitAfterBuffered::None,
+            cast_verification: None,
         })),
         metadata: Some(ActionMetadata::Buff { kind: buff }),
         queue_to_front: true,
+        group: None,
         queue_info: PriorityActionQueueInfo::default(),
     }
 }
 
+/// Creates a [`PlayerAction::Panic`] priority action to change channel when other players are
+/// around.
+///
+/// The action queues once the reaction to other players has been
+/// [`OtherPlayerReaction::ChangeChannel`] for at least the configured milliseconds.
 #[inline]
-fn panic_priority_action() -> PriorityAction {
+fn panic_priority_action(reactions: OtherPlayerReactions) -> PriorityAction {
     PriorityAction {
-        condition: Condition(Box::new(|_, world, info| match world.minimap.state {
+        condition: Condition(Box::new(move |_, world, info| match world.minimap.state {
             Minimap::Detecting => ConditionResult::Skip,
             Minimap::Idle(idle) => {
-                if !idle.has_any_other_player() || info.last_queued_time.is_none() {
+                let reaction = reactions.reaction(idle, world.player.context.last_known_pos);
+                if !matches!(reaction, Some(OtherPlayerReaction::ChangeChannel))
+                    || info.last_queued_time.is_none()
+                {
                     return ConditionResult::Ignore;
                 }
+                if !world.player.context.can_change_channel() {
+                    return ConditionResult::Skip;
+                }
 
-                if at_least_millis_passed_since(info.last_queued_time, 15000) {
+                if at_least_millis_passed_since(
+                    info.last_queued_time,
+                    reactions.change_channel_millis.into(),
+                ) {
                     ConditionResult::Queue
                 } else {
                     ConditionResult::Skip
@@ -1348,17 +2255,112 @@ fn panic_priority_action() -> PriorityAction {
         })),
         metadata: None,
         queue_to_front: true,
+        group: None,
+        queue_info: PriorityActionQueueInfo::default(),
+    }
+}
+
+/// Creates a priority action to avoid a periodic [`MapHazard`].
+///
+/// The hazard timing starts over each time the minimap is detected (e.g. on entering the map or
+/// changing channel) so that it is anchored to the map entry. The action queues
+/// [`MapHazard::lead_millis`] before each hazard while the minimap is in the [`Minimap::Idle`]
+/// state. Hazards missed (e.g. while the minimap was not detected) are skipped.
+#[inline]
+fn map_hazard_priority_action(hazard: MapHazard, jump_key: KeyKind) -> PriorityAction {
+    let hazard = MapHazard {
+        lead_millis: hazard.lead_millis.min(hazard.interval_millis),
+        ..hazard
+    };
+    let mut started = clock::now();
+    let mut next_millis = hazard.first_millis;
+    let mut was_minimap_idle = false;
+    let inner = match hazard.avoidance {
+        MapHazardAvoidance::Jump => PlayerAction::Key(Key {
+            key: jump_key,
+            key_hold_ticks: 0,
+            key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
+            link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
+            count: 1,
+            position: None,
+            direction: ActionKeyDirection::Any,
+            with: ActionKeyWith::Any,
+            double_jump_apex_ticks: 0,
+            wait_before_use_ticks: 0,
+            wait_before_use_ticks_random_range: 0,
+            wait_after_use_ticks: 0,
+            wait_after_use_ticks_random_range: 0,
+            wait_after_buffered: WaitAfterBuffered::None,
+            cast_verification: None,
+        }),
+        MapHazardAvoidance::MoveToSafeZone => PlayerAction::Move(Move {
+            position: Position {
+                x: hazard.safe_x,
+                x_random_range: 0,
+                y: hazard.safe_y,
+                allow_adjusting: false,
+            },
+            wait_after_move_ticks: ticks_from_millis(hazard.safe_wait_millis),
+        }),
+    };
+
+    PriorityAction {
+        condition: Condition(Box::new(move |_, world, _| {
+            if !matches!(world.minimap.state, Minimap::Idle(_)) {
+                was_minimap_idle = false;
+                return ConditionResult::Skip;
+            }
+            if !was_minimap_idle {
+                was_minimap_idle = true;
+                started = clock::now();
+                next_millis = hazard.first_millis;
+            }
+
+            let elapsed_millis = clock::elapsed(started).as_millis() as u64;
+            if should_avoid_map_hazard(hazard, &mut next_millis, elapsed_millis) {
+                ConditionResult::Queue
+            } else {
+                ConditionResult::Skip
+            }
+        })),
+        condition_kind: None,
+        inner: RotatorAction::Single(inner),
+        metadata: None,
+        queue_to_front: true,
+        group: None,
         queue_info: PriorityActionQueueInfo::default(),
     }
 }
 
+/// Checks whether `hazard` should be avoided `elapsed_millis` after its timing started.
+///
+/// `next_millis` is when the next hazard hits. It is advanced to the following hazard when
+/// returning `true` or past `elapsed_millis` if the hazard was missed.
+#[inline]
+fn should_avoid_map_hazard(hazard: MapHazard, next_millis: &mut u64, elapsed_millis: u64) -> bool {
+    if elapsed_millis > *next_millis {
+        let missed = (elapsed_millis - *next_millis).div_ceil(hazard.interval_millis);
+        *next_millis += missed * hazard.interval_millis;
+    }
+    if elapsed_millis + hazard.lead_millis < *next_millis {
+        return false;
+    }
+
+    *next_millis += hazard.interval_millis;
+    true
+}
+
 #[inline]
 fn elite_boss_change_channel_priority_action() -> PriorityAction {
     let mut condition = elite_boss_condition();
 
     PriorityAction {
-        condition: Condition(Box::new(move |resources, _, info| {
-            if !at_least_millis_passed_since(info.last_queued_time, 15000) {
+        condition: Condition(Box::new(move |resources, world, info| {
+            if !world.player.context.can_change_channel()
+                || !at_least_millis_passed_since(info.last_queued_time, 15000)
+            {
                 return ConditionResult::Skip;
             }
 
@@ -1370,6 +2372,7 @@ fn elite_boss_change_channel_priority_action() -> PriorityAction {
         })),
         metadata: None,
         queue_to_front: true,
+        group: None,
         queue_info: PriorityActionQueueInfo::default(),
     }
 }
@@ -1391,19 +2394,24 @@ fn elite_boss_use_key_priority_action(key: KeyKind) -> PriorityAction {
             key,
             key_hold_ticks: 0,
             key_hold_buffered_to_wait_after: false,
+            key_hold_moving: false,
             link_key: LinkKeyKind::None,
+            link_key_chain: Array::new(),
             count: 1,
             position: None,
             direction: ActionKeyDirection::Any,
             with: ActionKeyWith::Stationary,
+            double_jump_apex_ticks: 0,
             wait_before_use_ticks: 10,
             wait_before_use_ticks_random_range: 0,
             wait_after_use_ticks: 10,
             wait_after_use_ticks_random_range: 0,
             wait_after_buffered: WaitAfterBuffered::None,
+            cast_verification: None,
         })),
         metadata: None,
         queue_to_front: true,
+        group: None,
         queue_info: PriorityActionQueueInfo::default(),
     }
 }
@@ -1460,6 +2468,7 @@ fn use_booster_priority_action(kind: Booster) -> PriorityAction {
         inner: RotatorAction::Single(PlayerAction::UseBooster(UseBooster { kind })),
         metadata: Some(ActionMetadata::UseBooster),
         queue_to_front: true,
+        group: None,
         queue_info: PriorityActionQueueInfo::default(),
     }
 }
@@ -1514,6 +2523,83 @@ fn exchange_hexa_booster_priority_action(
         })),
         metadata: None,
         queue_to_front: true,
+        group: None,
+        queue_info: PriorityActionQueueInfo::default(),
+    }
+}
+
+#[inline]
+fn shop_purchase_priority_action(purchase: ShopPurchase) -> PriorityAction {
+    let region = Rect::new(
+        purchase.count_x,
+        purchase.count_y,
+        purchase.count_width,
+        purchase.count_height,
+    );
+    let threshold = purchase.count_threshold;
+    let mut last_count = None;
+    let mut unchanged_count = 0;
+    let mut task: Option<Task<Result<u32>>> = None;
+    let task_fn = move |detector: Arc<dyn Detector>| -> Result<u32> {
+        detector.detect_quick_slot_count(region)
+    };
+
+    PriorityAction {
+        condition: Condition(Box::new(move |resources, world, info| {
+            if !at_least_millis_passed_since(info.last_queued_time, 30000) {
+                return ConditionResult::Skip;
+            }
+
+            if resources.detector.is_none() {
+                return ConditionResult::Skip;
+            }
+
+            // Still on the way to the NPC from the last purchase
+            if world.player.context.shop_detour().is_some() {
+                return ConditionResult::Skip;
+            }
+
+            if unchanged_count >= SHOP_PURCHASE_MAX_UNCHANGED_COUNT {
+                return ConditionResult::Ignore;
+            }
+
+            match update_detection_task(resources, 10000, &mut task, task_fn) {
+                Update::Ok(count) if count < threshold => {
+                    // The last purchase did not increase the count
+                    if last_count.is_some_and(|last| count <= last) {
+                        unchanged_count += 1;
+                    } else {
+                        unchanged_count = 0;
+                    }
+                    if unchanged_count >= SHOP_PURCHASE_MAX_UNCHANGED_COUNT {
+                        warn!(target: "rotator", "item count did not increase after {unchanged_count} shop purchases, stopping shop purchase");
+                        return ConditionResult::Ignore;
+                    }
+
+                    last_count = Some(count);
+                    ConditionResult::Queue
+                }
+                Update::Ok(_) => {
+                    last_count = None;
+                    unchanged_count = 0;
+                    ConditionResult::Ignore
+                }
+                Update::Err(_) => ConditionResult::Ignore,
+                Update::Pending => ConditionResult::Skip,
+            }
+        })),
+        condition_kind: None,
+        inner: RotatorAction::Single(PlayerAction::Shop(Shop {
+            position: Point::new(purchase.npc_x, purchase.npc_y),
+            paths_id_index: purchase.paths_id_index,
+            open_key: purchase.open_key.into(),
+            tab: Point::new(purchase.tab_x, purchase.tab_y),
+            item: Point::new(purchase.item_x, purchase.item_y),
+            quantity: purchase.quantity,
+        })),
+        metadata: None,
+        queue_to_front: false,
+        group: None,
         queue_info: PriorityActionQueueInfo::default(),
     }
 }
@@ -1556,6 +2642,44 @@ fn unstuck_priority_action() -> PriorityAction {
         inner: RotatorAction::Single(PlayerAction::Unstuck),
         metadata: None,
         queue_to_front: true,
+        group: None,
+        queue_info: PriorityActionQueueInfo::default(),
+    }
+}
+
+fn dismiss_level_up_popup_priority_action() -> PriorityAction {
+    let mut task: Option<Task<Result<bool>>> = None;
+    let task_fn =
+        move |detector: Arc<dyn Detector>| -> Result<bool> { Ok(detector.detect_level_up_popup()) };
+
+    PriorityAction {
+        condition: Condition(Box::new(move |resources, world, info| {
+            if !at_least_millis_passed_since(info.last_queued_time, 3000) {
+                return ConditionResult::Skip;
+            }
+
+            if !world.player.state.can_override_current_state(None) {
+                return ConditionResult::Skip;
+            }
+
+            if resources.detector.is_none() {
+                return ConditionResult::Skip;
+            }
+
+            if world.player.context.is_dead() {
+                return ConditionResult::Skip;
+            }
+
+            match update_detection_task(resources, 3000, &mut task, task_fn) {
+                Update::Ok(true) => ConditionResult::Queue,
+                Update::Ok(false) | Update::Err(_) | Update::Pending => ConditionResult::Skip,
+            }
+        })),
+        condition_kind: None,
+        inner: RotatorAction::Single(PlayerAction::DismissPopup),
+        metadata: None,
+        queue_to_front: true,
+        group: None,
         queue_info: PriorityActionQueueInfo::default(),
     }
 }
@@ -1563,7 +2687,7 @@ fn unstuck_priority_action() -> PriorityAction {
 #[inline]
 fn at_least_millis_passed_since(last_queued_time: Option<Instant>, millis: u128) -> bool {
     last_queued_time
-        .map(|instant| Instant::now().duration_since(instant).as_millis() >= millis)
+        .map(|instant| clock::now().duration_since(instant).as_millis() >= millis)
         .unwrap_or(true)
 }
 
@@ -1589,31 +2713,119 @@ fn should_queue_fixed_action(
     true
 }
 
-fn next_action_id() -> u32 {
-    static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+/// Whether an action waiting for the `kind` buff can be queued.
+///
+/// The action can be queued when the buff is active or when `max_wait_millis` has passed since
+/// `wait_start`. `wait_start` is set on the first wait and cleared once the action can be queued.
+#[inline]
+fn should_queue_burst_action(
+    world: &World,
+    kind: BuffKind,
+    max_wait_millis: u64,
+    wait_start: &mut Option<Instant>,
+) -> bool {
+    let has_buff = matches!(world.buffs[kind].state, Buff::Yes | Buff::Volatile);
+    let started = *wait_start.get_or_insert_with(clock::now);
+    if has_buff || at_least_millis_passed_since(Some(started), max_wait_millis as u128) {
+        *wait_start = None;
+        return true;
+    }
 
-    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    false
 }
 
-#[cfg(test)]
-mod tests {
-    use std::{
-        assert_matches::assert_matches,
-        time::{Duration, Instant},
+/// Ticks `node` and appends the reached actions to `actions`.
+///
+/// Indices of the succeeded conditions are appended to `conditions` so that their last succeeded
+/// times can be updated. Returns whether `node` succeeded. If `node` fails, everything it appended
+/// is discarded.
+fn tick_behavior_node(
+    node: &BehaviorTreeNode,
+    world: &World,
+    condition_times: &[Option<Instant>],
+    actions: &mut Vec<(u32, PlayerAction)>,
+    conditions: &mut Vec<usize>,
+) -> bool {
+    let actions_len = actions.len();
+    let conditions_len = conditions.len();
+    let succeeded = match node {
+        BehaviorTreeNode::Selector(children) => children
+            .iter()
+            .any(|child| tick_behavior_node(child, world, condition_times, actions, conditions)),
+        BehaviorTreeNode::Sequence(children) => children
+            .iter()
+            .all(|child| tick_behavior_node(child, world, condition_times, actions, conditions)),
+        BehaviorTreeNode::Condition { condition, index } => {
+            let satisfied = match *condition {
+                BehaviorCondition::EveryMillis(millis) => {
+                    at_least_millis_passed_since(condition_times[*index], millis as u128)
+                }
+                BehaviorCondition::ErdaShowerOffCooldown => {
+                    matches!(world.skills[SkillKind::ErdaShower].state, Skill::Idle(_, _))
+                }
+                BehaviorCondition::BurstBuffActive(buff) => {
+                    burst_buff_kind(buff).is_none_or(|kind| {
+                        matches!(world.buffs[kind].state, Buff::Yes | Buff::Volatile)
+                    })
+                }
+            };
+            if satisfied {
+                conditions.push(*index);
+            }
+            satisfied
+        }
+        BehaviorTreeNode::Action { id, action } => {
+            actions.push((*id, action.clone()));
+            true
+        }
     };
+    if !succeeded {
+        actions.truncate(actions_len);
+        conditions.truncate(conditions_len);
+    }
 
-    use opencv::core::{Point, Vec4b};
-    use strum::IntoEnumIterator;
+    succeeded
+}
+
+#[inline]
+fn burst_buff_kind(buff: ActionKeyBurstBuff) -> Option<BuffKind> {
+    match buff {
+        ActionKeyBurstBuff::None => None,
+        ActionKeyBurstBuff::ForTheGuild => Some(BuffKind::ForTheGuild),
+        ActionKeyBurstBuff::HardHitter => Some(BuffKind::HardHitter),
+        ActionKeyBurstBuff::ExtremeRedPotion => Some(BuffKind::ExtremeRedPotion),
+        ActionKeyBurstBuff::ExtremeBluePotion => Some(BuffKind::ExtremeBluePotion),
+        ActionKeyBurstBuff::ExtremeGreenPotion => Some(BuffKind::ExtremeGreenPotion),
+        ActionKeyBurstBuff::ExtremeGoldPotion => Some(BuffKind::ExtremeGoldPotion),
+    }
+}
+
+fn next_action_id() -> u32 {
+    static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        assert_matches::assert_matches,
+        time::{Duration, Instant},
+    };
+
+    use opencv::core::{Point, Vec4b};
+    use strum::IntoEnumIterator;
     use tokio::{task::yield_now, time::timeout};
 
     use super::*;
     use crate::{
-        Position,
+        ActionColor, ActionNote, ActionTags, Position,
         buff::{BuffContext, BuffEntity, BuffKind},
         detect::MockDetector,
         minimap::{MinimapContext, MinimapEntity, MinimapIdle},
         player::Player,
         skill::{SkillContext, SkillEntity, SkillKind},
+        task::set_synchronous_tasks,
     };
 
     const COOLDOWN_BETWEEN_QUEUE_MILLIS: u128 = 20_000;
@@ -1626,6 +2838,10 @@ mod tests {
         },
         condition: ActionCondition::Any,
         wait_after_move_millis: 0,
+        note: ActionNote::EMPTY,
+        tags: ActionTags::EMPTY,
+        color: ActionColor::None,
+        enabled: true,
     });
     const PRIORITY_ACTION: Action = Action::Move(ActionMove {
         position: Position {
@@ -1636,6 +2852,10 @@ mod tests {
         },
         condition: ActionCondition::ErdaShowerOffCooldown,
         wait_after_move_millis: 0,
+        note: ActionNote::EMPTY,
+        tags: ActionTags::EMPTY,
+        color: ActionColor::None,
+        enabled: true,
     });
 
     fn mock_world() -> World {
@@ -1723,6 +2943,109 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn rotator_should_queue_burst_action() {
+        let mut world = mock_world();
+        let mut wait_start = None;
+
+        assert!(!should_queue_burst_action(
+            &world,
+            BuffKind::HardHitter,
+            10000,
+            &mut wait_start
+        ));
+        assert!(wait_start.is_some());
+
+        world.buffs[BuffKind::HardHitter].state = Buff::Yes;
+        assert!(should_queue_burst_action(
+            &world,
+            BuffKind::HardHitter,
+            10000,
+            &mut wait_start
+        ));
+        assert!(wait_start.is_none());
+
+        world.buffs[BuffKind::HardHitter].state = Buff::No;
+        wait_start = Some(Instant::now() - Duration::from_millis(10000));
+        assert!(should_queue_burst_action(
+            &world,
+            BuffKind::HardHitter,
+            10000,
+            &mut wait_start
+        ));
+        assert!(wait_start.is_none());
+    }
+
+    #[test]
+    fn rotator_queued_actions() {
+        let mut rotator = DefaultRotator {
+            normal_rotate_mode: RotatorMode::StartToEnd,
+            ..DefaultRotator::default()
+        };
+        rotator.normal_actions = vec![
+            (0, RotatorAction::Single(NORMAL_ACTION.into())),
+            (1, RotatorAction::Single(PlayerAction::SolveRune)),
+        ];
+        rotator.normal_index = 1;
+        rotator.inject_action(PlayerAction::Unstuck);
+
+        assert_eq!(
+            rotator.queued_actions(),
+            vec!["Side: Unstuck".to_string(), "Normal: SolveRune".to_string()]
+        );
+
+        rotator.normal_rotate_mode =
+            RotatorMode::AutoMobbing(MobbingKey::default(), Bound::default());
+        assert_eq!(rotator.queued_actions(), vec!["Side: Unstuck".to_string()]);
+    }
+
+    #[test]
+    fn auto_mob_loot_point_closest_within_radius() {
+        let pos = Point::new(50, 10);
+        let points = [
+            Point::new(51, 10),
+            Point::new(60, 10),
+            Point::new(44, 10),
+            Point::new(80, 10),
+        ];
+
+        assert_eq!(
+            auto_mob_loot_point(&points, pos, 20),
+            Some(Point::new(44, 10))
+        );
+        assert_eq!(auto_mob_loot_point(&points, pos, 5), None);
+        assert_eq!(
+            auto_mob_loot_point(&points[3..], pos, 0),
+            Some(Point::new(80, 10))
+        );
+    }
+
+    #[test]
+    fn next_auto_mob_region_index_cycle() {
+        let resources = Resources::new(None, None);
+
+        assert_eq!(
+            next_auto_mob_region_index(&resources, 0, 3, MobbingRegionSelection::Cycle),
+            1
+        );
+        assert_eq!(
+            next_auto_mob_region_index(&resources, 2, 3, MobbingRegionSelection::Cycle),
+            0
+        );
+    }
+
+    #[test]
+    fn next_auto_mob_region_index_random_not_current() {
+        let resources = Resources::new(None, None);
+
+        for current in 0..3 {
+            let next =
+                next_auto_mob_region_index(&resources, current, 3, MobbingRegionSelection::Random);
+            assert_ne!(next, current);
+            assert!(next < 3);
+        }
+    }
+
     #[test]
     fn rotator_build_actions() {
         let mut rotator = DefaultRotator::default();
@@ -1730,6 +3053,8 @@ mod tests {
         let buffs = vec![(BuffKind::Rune, KeyKind::A); 4];
         let args = RotatorBuildArgs {
             mode: RotatorMode::default(),
+            auto_mob_regions: vec![],
+            auto_mob_region_selection: MobbingRegionSelection::default(),
             actions: &actions,
             buffs: &buffs,
             familiars: Familiars::default(),
@@ -1739,16 +3064,28 @@ mod tests {
             hexa_booster_exchange_condition: ExchangeHexaBoosterCondition::None,
             hexa_booster_exchange_amount: 1,
             hexa_booster_exchange_all: false,
+            shop_purchase: ShopPurchase::default(),
+            map_hazards: &[
+                MapHazard::default(),
+                MapHazard {
+                    enabled: false,
+                    ..MapHazard::default()
+                },
+            ],
+            jump_key: KeyKind::A,
             enable_panic_mode: true,
+            other_player_reactions: OtherPlayerReactions::default(),
             enable_rune_solving: true,
             enable_transparent_shape_solving: true,
             enable_reset_normal_actions_on_erda: false,
             enable_using_generic_booster: false,
             enable_using_hexa_booster: false,
+            enable_level_up_popups_dismissal: false,
+            condition_webhooks: &[],
         };
 
         rotator.build_actions(args);
-        assert_eq!(rotator.priority_actions.len(), 10);
+        assert_eq!(rotator.priority_actions.len(), 11);
         assert_eq!(rotator.normal_actions.len(), 2);
     }
 
@@ -1819,6 +3156,47 @@ mod tests {
         assert_eq!(rotator.normal_index, 0);
     }
 
+    #[test]
+    fn rotator_rotate_action_behavior_tree() {
+        let mut world = mock_world();
+        let mut rotator = DefaultRotator::default();
+        let resources = Resources::new(None, None);
+        let burst = BehaviorNode::Sequence(vec![
+            BehaviorNode::Condition(BehaviorCondition::EveryMillis(10000)),
+            BehaviorNode::Condition(BehaviorCondition::BurstBuffActive(
+                ActionKeyBurstBuff::HardHitter,
+            )),
+            BehaviorNode::Action(NORMAL_ACTION),
+            BehaviorNode::Action(NORMAL_ACTION),
+        ]);
+        let tree = BehaviorNode::Selector(vec![burst, BehaviorNode::Action(NORMAL_ACTION)]);
+        let mut condition_count = 0;
+        rotator.normal_rotate_mode = RotatorMode::BehaviorTree(None);
+        rotator.behavior_tree = Some(BehaviorTreeNode::new(tree, &mut condition_count));
+        rotator.behavior_tree_condition_times = vec![None; condition_count];
+
+        // Falls back without the buff and keeps the failed sequence off cooldown
+        rotator.rotate_action(&resources, &mut world);
+        assert!(world.player.context.has_normal_action());
+        assert!(rotator.behavior_tree_queue.is_empty());
+        assert_eq!(rotator.behavior_tree_condition_times, vec![None, None]);
+
+        world.player.context.clear_actions_aborted(true);
+        world.buffs[BuffKind::HardHitter].state = Buff::Yes;
+        rotator.rotate_action(&resources, &mut world);
+        assert!(world.player.context.has_normal_action());
+        assert_eq!(rotator.behavior_tree_queue.len(), 1);
+        assert!(rotator.behavior_tree_condition_times[0].is_some());
+
+        // Falls back again while the sequence is on cooldown
+        world.player.context.clear_actions_aborted(true);
+        rotator.rotate_action(&resources, &mut world);
+        world.player.context.clear_actions_aborted(true);
+        rotator.rotate_action(&resources, &mut world);
+        assert!(world.player.context.has_normal_action());
+        assert!(rotator.behavior_tree_queue.is_empty());
+    }
+
     #[test]
     fn rotator_priority_actions_queue() {
         let mut rotator = DefaultRotator::default();
@@ -1841,6 +3219,7 @@ mod tests {
                 inner: RotatorAction::Single(PlayerAction::SolveRune),
                 metadata: None,
                 queue_to_front: true,
+                group: None,
                 queue_info: PriorityActionQueueInfo::default(),
             },
         );
@@ -1851,6 +3230,47 @@ mod tests {
         assert_eq!(world.player.context.priority_action_id(), Some(55));
     }
 
+    #[test]
+    fn rotator_priority_actions_group_exclusive_and_shares_cooldown() {
+        let mut rotator = DefaultRotator::default();
+        let mut world = mock_world();
+        let group = ActionGroup {
+            kind: ActionKeyGroup::A,
+            shares_cooldown: true,
+            exclusive_millis: 10000,
+        };
+        for id in 0..2 {
+            rotator.priority_actions.insert(
+                id,
+                PriorityAction {
+                    condition: Condition(Box::new(|_, _, _| ConditionResult::Queue)),
+                    condition_kind: None,
+                    inner: RotatorAction::Single(NORMAL_ACTION.into()),
+                    metadata: None,
+                    queue_to_front: false,
+                    group: Some(group),
+                    queue_info: PriorityActionQueueInfo::default(),
+                },
+            );
+        }
+        let resources = Resources::new(None, None);
+
+        rotator.rotate_priority_actions(&resources, &mut world);
+
+        assert_eq!(rotator.priority_actions_queue.len(), 1);
+        assert!(
+            rotator
+                .action_groups_last_queued_time
+                .contains_key(&ActionKeyGroup::A)
+        );
+        assert!(
+            rotator
+                .priority_actions
+                .values()
+                .all(|action| action.queue_info.last_queued_time.is_some())
+        );
+    }
+
     #[test]
     fn rotator_priority_actions_queue_to_front() {
         let mut rotator = DefaultRotator::default();
@@ -1865,6 +3285,7 @@ mod tests {
                 inner: RotatorAction::Single(NORMAL_ACTION.into()),
                 metadata: None,
                 queue_to_front: false,
+                group: None,
                 queue_info: PriorityActionQueueInfo::default(),
             },
         );
@@ -1876,6 +3297,7 @@ mod tests {
                 inner: RotatorAction::Single(NORMAL_ACTION.into()),
                 metadata: None,
                 queue_to_front: false,
+                group: None,
                 queue_info: PriorityActionQueueInfo::default(),
             },
         );
@@ -1893,6 +3315,7 @@ mod tests {
                 inner: RotatorAction::Single(NORMAL_ACTION.into()),
                 metadata: None,
                 queue_to_front: true,
+                group: None,
                 queue_info: PriorityActionQueueInfo::default(),
             },
         );
@@ -1914,6 +3337,7 @@ mod tests {
                 inner: RotatorAction::Single(NORMAL_ACTION.into()),
                 metadata: None,
                 queue_to_front: true,
+                group: None,
                 queue_info: PriorityActionQueueInfo::default(),
             },
         );
@@ -1947,6 +3371,7 @@ mod tests {
                 }),
                 metadata: None,
                 queue_to_front: false,
+                group: None,
                 queue_info: PriorityActionQueueInfo::default(),
             },
         );
@@ -1966,6 +3391,7 @@ mod tests {
                 inner: RotatorAction::Single(PlayerAction::SolveRune),
                 metadata: None,
                 queue_to_front: true,
+                group: None,
                 queue_info: PriorityActionQueueInfo::default(),
             },
         );
@@ -1999,6 +3425,7 @@ mod tests {
             Minimap::Idle(idle),
             MobbingKey::default(),
             Rect::new(20, 20, 80, 80).into(),
+            false,
         );
 
         assert_matches!(
@@ -2017,6 +3444,7 @@ mod tests {
             Minimap::Idle(idle),
             MobbingKey::default(),
             Rect::new(20, 20, 80, 80).into(),
+            false,
         );
 
         assert_matches!(
@@ -2028,6 +3456,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn rotate_ping_pong_screen_fallback_when_minimap_detecting() {
+        let mut player = PlayerContext::default();
+        let mut rotator = DefaultRotator::default();
+
+        rotator.rotate_ping_pong(
+            &mut player,
+            Minimap::Detecting,
+            MobbingKey::default(),
+            Rect::new(20, 20, 80, 80).into(),
+            false,
+        );
+        assert!(!player.has_normal_action());
+
+        player.config.ping_pong_screen_fallback = true;
+        rotator.rotate_ping_pong(
+            &mut player,
+            Minimap::Detecting,
+            MobbingKey::default(),
+            Rect::new(20, 20, 80, 80).into(),
+            false,
+        );
+        assert_matches!(
+            player.normal_action(),
+            Some(PlayerAction::PingPong(PingPong {
+                direction: PingPongDirection::Left,
+                ..
+            }))
+        );
+    }
+
+    #[test]
+    fn rotate_ping_pong_vertical_direction() {
+        let mut player = PlayerContext::default();
+        let mut rotator = DefaultRotator::default();
+        let mut idle = MinimapIdle::default();
+        idle.bbox = Rect::new(0, 0, 100, 100);
+        // Bound y: [20, 60] in player relative coordinate
+        let bound = Rect::new(20, 40, 60, 40).into();
+
+        // Closer to top, further than bottom -> Go down
+        player.last_known_pos = Some(Point::new(50, 55));
+        rotator.rotate_ping_pong(
+            &mut player,
+            Minimap::Idle(idle),
+            MobbingKey::default(),
+            bound,
+            true,
+        );
+
+        assert_matches!(
+            player.normal_action(),
+            Some(PlayerAction::PingPong(PingPong {
+                direction: PingPongDirection::Down,
+                ..
+            }))
+        );
+
+        // Closer to bottom, further than top -> Go up
+        player.clear_actions_aborted(true);
+        player.last_known_pos = Some(Point::new(50, 25));
+        rotator.rotate_ping_pong(
+            &mut player,
+            Minimap::Idle(idle),
+            MobbingKey::default(),
+            bound,
+            true,
+        );
+
+        assert_matches!(
+            player.normal_action(),
+            Some(PlayerAction::PingPong(PingPong {
+                direction: PingPongDirection::Up,
+                ..
+            }))
+        );
+    }
+
     #[test]
     fn rotator_priority_action_is_ignored_when_executing() {
         let mut rotator = DefaultRotator::default();
@@ -2044,6 +3550,7 @@ mod tests {
                 inner: RotatorAction::Single(NORMAL_ACTION.into()),
                 metadata: None,
                 queue_to_front: false,
+                group: None,
                 queue_info: PriorityActionQueueInfo::default(),
             },
         );
@@ -2084,6 +3591,7 @@ mod tests {
                 }),
                 metadata: None,
                 queue_to_front: false,
+                group: None,
                 queue_info: PriorityActionQueueInfo::default(),
             },
         );
@@ -2103,6 +3611,89 @@ mod tests {
         assert!(!rotator.priority_actions_queue.contains(&action_id));
     }
 
+    #[test]
+    fn rotator_action_cooldowns_restored_by_key() {
+        fn action_with(key: Option<&str>, last_queued_time: Option<Instant>) -> PriorityAction {
+            PriorityAction {
+                condition: Condition(Box::new(|_, _, _| ConditionResult::Skip)),
+                condition_kind: None,
+                inner: RotatorAction::Single(NORMAL_ACTION.into()),
+                metadata: None,
+                queue_to_front: false,
+                group: None,
+                queue_info: PriorityActionQueueInfo {
+                    last_queued_time,
+                    cooldown_key: key.map(str::to_string),
+                    ..Default::default()
+                },
+            }
+        }
+
+        let mut rotator = DefaultRotator::default();
+        let queued = Instant::now().checked_sub(Duration::from_secs(60));
+        rotator
+            .priority_actions
+            .insert(1, action_with(Some("buff:Rune"), queued));
+        rotator
+            .priority_actions
+            .insert(2, action_with(None, queued));
+
+        let cooldowns = rotator.action_cooldowns();
+        assert_eq!(cooldowns.len(), 1);
+        assert_eq!(cooldowns[0].key, "buff:Rune");
+
+        let mut restored = DefaultRotator::default();
+        restored
+            .priority_actions
+            .insert(1, action_with(Some("buff:Rune"), None));
+        restored
+            .priority_actions
+            .insert(2, action_with(Some("buff:Other"), None));
+        restored.priority_actions.insert(3, action_with(None, None));
+
+        restored.restore_action_cooldowns(&cooldowns);
+
+        let queue_info = |id| &restored.priority_actions.get(&id).unwrap().queue_info;
+        assert!(queue_info(1).last_queued_time.unwrap().elapsed() >= Duration::from_secs(59));
+        assert!(queue_info(2).last_queued_time.is_none());
+        assert!(queue_info(3).last_queued_time.is_none());
+    }
+
+    #[test]
+    fn rotator_reset_action_cooldowns_by_key_prefix() {
+        let queued = Some(Instant::now());
+        let mut rotator = DefaultRotator::default();
+        for (id, key) in [
+            (1, Some("booster:generic")),
+            (2, Some("buff:Rune")),
+            (3, None),
+        ] {
+            rotator.priority_actions.insert(
+                id,
+                PriorityAction {
+                    condition: Condition(Box::new(|_, _, _| ConditionResult::Skip)),
+                    condition_kind: None,
+                    inner: RotatorAction::Single(NORMAL_ACTION.into()),
+                    metadata: None,
+                    queue_to_front: false,
+                    group: None,
+                    queue_info: PriorityActionQueueInfo {
+                        last_queued_time: queued,
+                        cooldown_key: key.map(str::to_string),
+                        ..Default::default()
+                    },
+                },
+            );
+        }
+
+        rotator.reset_action_cooldowns("booster:");
+
+        let queue_info = |id| &rotator.priority_actions.get(&id).unwrap().queue_info;
+        assert!(queue_info(1).last_queued_time.is_none());
+        assert_eq!(queue_info(2).last_queued_time, queued);
+        assert_eq!(queue_info(3).last_queued_time, queued);
+    }
+
     #[test]
     fn rotator_erda_shower_action_ignored_if_another_erda_is_queued() {
         let mut rotator = DefaultRotator::default();
@@ -2120,6 +3711,7 @@ mod tests {
                 inner: RotatorAction::Single(NORMAL_ACTION.into()),
                 metadata: None,
                 queue_to_front: false,
+                group: None,
                 queue_info: PriorityActionQueueInfo {
                     last_queued_time: Some(Instant::now()),
                     ..Default::default()
@@ -2135,6 +3727,7 @@ mod tests {
                 inner: RotatorAction::Single(NORMAL_ACTION.into()),
                 metadata: None,
                 queue_to_front: false,
+                group: None,
                 queue_info: PriorityActionQueueInfo::default(),
             },
         );
@@ -2191,6 +3784,21 @@ mod tests {
         queue_or_timeout(|| (action.condition.0)(&resources, &world, &info)).await;
     }
 
+    #[tokio::test]
+    async fn dismiss_level_up_popup_priority_action_triggers_when_popup_detected() {
+        let resources = Resources::new(
+            None,
+            Some(mock_detector(|detector| {
+                detector.expect_detect_level_up_popup().returning(|| true);
+            })),
+        );
+        let world = mock_world();
+        let info = PriorityActionQueueInfo::default();
+        let mut action = dismiss_level_up_popup_priority_action();
+
+        queue_or_timeout(|| (action.condition.0)(&resources, &world, &info)).await;
+    }
+
     #[tokio::test]
     async fn elite_boss_use_key_priority_action_triggers_when_elite_present() {
         let detector = mock_detector(|detector| {
@@ -2205,6 +3813,135 @@ mod tests {
         queue_or_timeout(|| (action.condition.0)(&resources, &world, &info)).await;
     }
 
+    #[tokio::test]
+    async fn shop_purchase_priority_action_triggers_when_count_below_threshold() {
+        let resources = Resources::new(
+            None,
+            Some(mock_detector(|detector| {
+                detector
+                    .expect_detect_quick_slot_count()
+                    .returning(|_| Ok(10));
+            })),
+        );
+        let world = mock_world();
+        let info = PriorityActionQueueInfo::default();
+        let mut action = shop_purchase_priority_action(ShopPurchase {
+            enabled: true,
+            count_threshold: 50,
+            ..ShopPurchase::default()
+        });
+
+        queue_or_timeout(|| (action.condition.0)(&resources, &world, &info)).await;
+    }
+
+    #[test]
+    fn shop_purchase_priority_action_stops_when_count_unchanged() {
+        let resources = Resources::new(
+            None,
+            Some(mock_detector(|detector| {
+                detector
+                    .expect_detect_quick_slot_count()
+                    .returning(|_| Ok(10));
+            })),
+        );
+        let world = mock_world();
+        let info = PriorityActionQueueInfo::default();
+        let mut action = shop_purchase_priority_action(ShopPurchase {
+            enabled: true,
+            count_threshold: 50,
+            ..ShopPurchase::default()
+        });
+
+        set_synchronous_tasks(true);
+        let mut queued = 0;
+        for _ in 0..10000 {
+            match (action.condition.0)(&resources, &world, &info) {
+                ConditionResult::Queue => queued += 1,
+                ConditionResult::Ignore => break,
+                ConditionResult::Skip => (),
+            }
+        }
+        set_synchronous_tasks(false);
+
+        assert_eq!(queued, SHOP_PURCHASE_MAX_UNCHANGED_COUNT);
+        assert_matches!(
+            (action.condition.0)(&resources, &world, &info),
+            ConditionResult::Ignore
+        );
+    }
+
+    #[test]
+    fn should_avoid_map_hazard_lead_and_missed() {
+        let hazard = MapHazard {
+            interval_millis: 10000,
+            first_millis: 5000,
+            lead_millis: 1000,
+            ..MapHazard::default()
+        };
+        let mut next_millis = hazard.first_millis;
+
+        assert!(!should_avoid_map_hazard(hazard, &mut next_millis, 3999));
+        assert!(should_avoid_map_hazard(hazard, &mut next_millis, 4000));
+        assert_eq!(next_millis, 15000);
+        assert!(!should_avoid_map_hazard(hazard, &mut next_millis, 4100));
+
+        // Missed the hazards at 15000 and 25000
+        assert!(!should_avoid_map_hazard(hazard, &mut next_millis, 25001));
+        assert_eq!(next_millis, 35000);
+        assert!(should_avoid_map_hazard(hazard, &mut next_millis, 34500));
+        assert_eq!(next_millis, 45000);
+    }
+
+    #[test]
+    fn priority_action_skips_when_condition_webhook_unresolved() {
+        let resources = Resources::new(None, None);
+        let world = mock_world();
+        let info = PriorityActionQueueInfo::default();
+        let mut action = priority_action(
+            RotatorAction::Single(PlayerAction::SolveRune),
+            ActionCondition::EveryMillis(0),
+            false,
+            None,
+            None,
+            None,
+            None,
+            Some(None),
+            None,
+        );
+
+        assert_matches!(
+            (action.condition.0)(&resources, &world, &info),
+            ConditionResult::Skip
+        );
+    }
+
+    #[tokio::test]
+    async fn priority_action_triggers_when_resource_gate_detected() {
+        let resources = Resources::new(
+            None,
+            Some(mock_detector(|detector| {
+                detector
+                    .expect_detect_resource_gate()
+                    .returning(|_| Ok(true));
+            })),
+        );
+        let world = mock_world();
+        let info = PriorityActionQueueInfo::default();
+        let mut action = priority_action(
+            RotatorAction::Single(PlayerAction::SolveRune),
+            ActionCondition::EveryMillis(0),
+            false,
+            None,
+            None,
+            None,
+            Some(ActionKeyResourceGate::default()),
+            None,
+            None,
+        );
+
+        queue_or_timeout(|| (action.condition.0)(&resources, &world, &info)).await;
+    }
+
     #[tokio::test]
     async fn panic_priority_action_triggers_when_has_other_players() {
         let resources = Resources::new(None, None);
@@ -2213,7 +3950,7 @@ mod tests {
         let mut world = mock_world();
         world.minimap.state = Minimap::Idle(idle);
 
-        let mut action = panic_priority_action();
+        let mut action = panic_priority_action(OtherPlayerReactions::default());
         let info = PriorityActionQueueInfo {
             last_queued_time: Some(Instant::now() - std::time::Duration::from_millis(16000)),
             ..Default::default()
@@ -2222,5 +3959,63 @@ mod tests {
         queue_or_timeout(|| (action.condition.0)(&resources, &world, &info)).await;
     }
 
+    #[test]
+    fn panic_priority_action_ignores_when_reaction_is_not_change_channel() {
+        let resources = Resources::new(None, None);
+        let mut idle = MinimapIdle::default();
+        idle.set_other_player(Point::new(10, 10));
+        let mut world = mock_world();
+        world.minimap.state = Minimap::Idle(idle);
+        world.player.context.last_known_pos = Some(Point::new(20, 10));
+
+        let mut action = panic_priority_action(OtherPlayerReactions {
+            same_platform: OtherPlayerReaction::PauseLoudSkills,
+            far: OtherPlayerReaction::ChangeChannel,
+            change_channel_millis: 0,
+        });
+        let info = PriorityActionQueueInfo {
+            last_queued_time: Some(Instant::now()),
+            ..Default::default()
+        };
+
+        assert_matches!(
+            (action.condition.0)(&resources, &world, &info),
+            ConditionResult::Ignore
+        );
+    }
+
+    #[test]
+    fn familiar_resummon_priority_action_queues_before_expiry() {
+        let resources = Resources::new(None, None);
+        let mut world = mock_world();
+        world.minimap.state = Minimap::Idle(MinimapIdle::default());
+        let mut action = familiar_resummon_priority_action(KeyKind::A, 60000);
+
+        let info = PriorityActionQueueInfo::default();
+        assert_matches!(
+            (action.condition.0)(&resources, &world, &info),
+            ConditionResult::Ignore
+        );
+
+        world.buffs[BuffKind::Familiar].state = Buff::Yes;
+        let info = PriorityActionQueueInfo {
+            last_queued_time: Instant::now().checked_sub(Duration::from_secs(45)),
+            ..Default::default()
+        };
+        assert_matches!(
+            (action.condition.0)(&resources, &world, &info),
+            ConditionResult::Skip
+        );
+
+        let info = PriorityActionQueueInfo {
+            last_queued_time: Instant::now().checked_sub(Duration::from_secs(50)),
+            ..Default::default()
+        };
+        assert_matches!(
+            (action.condition.0)(&resources, &world, &info),
+            ConditionResult::Queue
+        );
+    }
+
     // TODO: more tests
 }
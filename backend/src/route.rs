@@ -0,0 +1,360 @@
+use std::ops::Range;
+
+use opencv::core::Point;
+
+use crate::{
+    Action, ActionCondition, Character,
+    array::Array,
+    models::Platform,
+    pathing::{self, MAX_PLATFORMS_COUNT, PlatformWithNeighbors, find_neighbors, find_points_with},
+    player::{
+        DOUBLE_JUMP_THRESHOLD, GRAPPLING_MAX_THRESHOLD, JUMP_THRESHOLD,
+        REFERENCE_DOUBLE_JUMP_SPEED, REFERENCE_WALK_SPEED, calibrated_threshold,
+    },
+    rng::Rng,
+};
+
+/// Estimated number of ticks at the base tick rate to move up to another platform.
+const UP_MOVEMENT_TICKS: f32 = 25.0;
+
+/// Estimated number of ticks at the base tick rate to fall one pixel.
+const FALL_TICKS_PER_PIXEL: f32 = 0.5;
+
+/// Estimated number of ticks when there is no path between two points.
+///
+/// This is large enough for the optimizer to avoid such pair but still finite so that routes
+/// with unreachable points can be compared.
+const UNREACHABLE_TICKS: f32 = 10_000.0;
+
+/// Number of candidate routes evaluated by the simulated annealing.
+const ANNEALING_ITERATIONS: u32 = 20_000;
+
+/// The starting temperature of the simulated annealing in ticks.
+const ANNEALING_INITIAL_TEMPERATURE: f32 = 100.0;
+
+/// The factor the temperature is multiplied by after each iteration.
+const ANNEALING_COOLING_RATE: f32 = 0.9995;
+
+/// Estimates the travel time between two points using the calibrated movement speeds.
+#[derive(Debug, Clone, Copy)]
+pub struct RouteCostModel {
+    /// Walk speed in pixels per tick at the base tick rate.
+    walk_speed: f32,
+    /// Double jump speed in pixels per tick at the base tick rate.
+    double_jump_speed: f32,
+    /// Minimum x distance to double jump instead of walking.
+    double_jump_threshold: i32,
+}
+
+impl RouteCostModel {
+    /// Creates a model from the calibrated speeds of `character`.
+    ///
+    /// The reference speeds the default movement thresholds are tuned at are used when the
+    /// character is not calibrated.
+    pub fn new(character: Option<&Character>) -> Self {
+        let walk_speed = character.and_then(|character| character.walk_speed);
+        let double_jump_speed = character.and_then(|character| character.double_jump_speed);
+
+        Self {
+            walk_speed: walk_speed
+                .filter(|speed| *speed > 0.0)
+                .unwrap_or(REFERENCE_WALK_SPEED),
+            double_jump_speed: double_jump_speed
+                .filter(|speed| *speed > 0.0)
+                .unwrap_or(REFERENCE_DOUBLE_JUMP_SPEED),
+            double_jump_threshold: calibrated_threshold(
+                DOUBLE_JUMP_THRESHOLD,
+                double_jump_speed,
+                REFERENCE_DOUBLE_JUMP_SPEED,
+            ),
+        }
+    }
+
+    /// Estimates the ticks to travel from `from` to `to` along `platforms`.
+    ///
+    /// Travels in a straight line if there is no platform.
+    fn travel_ticks(
+        &self,
+        platforms: &Array<PlatformWithNeighbors, MAX_PLATFORMS_COUNT>,
+        from: Point,
+        to: Point,
+    ) -> f32 {
+        if platforms.is_empty() {
+            return self.segment_ticks(from, to);
+        }
+
+        let Some(points) = find_points_with(
+            platforms,
+            from,
+            to,
+            false,
+            self.double_jump_threshold,
+            JUMP_THRESHOLD,
+            GRAPPLING_MAX_THRESHOLD,
+        ) else {
+            return UNREACHABLE_TICKS;
+        };
+
+        let mut ticks = 0.0;
+        let mut last = from;
+        for (point, _) in points {
+            ticks += self.segment_ticks(last, point);
+            last = point;
+        }
+        ticks
+    }
+
+    fn segment_ticks(&self, from: Point, to: Point) -> f32 {
+        let x_distance = (to.x - from.x).abs();
+        let x_speed = if x_distance >= self.double_jump_threshold {
+            self.double_jump_speed
+        } else {
+            self.walk_speed
+        };
+        // Minimap coordinates are bottom-left so moving up increases y
+        let y_distance = to.y - from.y;
+        let y_ticks = if y_distance > 0 {
+            UP_MOVEMENT_TICKS
+        } else {
+            -y_distance as f32 * FALL_TICKS_PER_PIXEL
+        };
+
+        x_distance as f32 / x_speed + y_ticks
+    }
+}
+
+/// Reorders the positioned normal actions in `actions` to a near-optimal visiting order.
+///
+/// Only non-linked actions with [`ActionCondition::Any`] and a position are reordered along with
+/// their linked actions. Other actions stay at their original indices. The travel time is
+/// estimated by `model` along `platforms` of the map.
+pub fn optimize_actions_route(
+    actions: &[Action],
+    platforms: &[Platform],
+    model: RouteCostModel,
+    rng: &Rng,
+) -> Vec<Action> {
+    let blocks = action_blocks(actions);
+    let routable = blocks
+        .iter()
+        .enumerate()
+        .filter_map(|(index, block)| Some(index).zip(routable_position(&actions[block.start])))
+        .collect::<Vec<_>>();
+    let points = routable.iter().map(|(_, point)| *point).collect::<Vec<_>>();
+    let platforms = Array::from_iter(find_neighbors(
+        &platforms
+            .iter()
+            .copied()
+            .map(Platform::into)
+            .collect::<Vec<pathing::Platform>>(),
+        model.double_jump_threshold,
+        JUMP_THRESHOLD,
+        GRAPPLING_MAX_THRESHOLD,
+    ));
+    let order = optimize_route(
+        points.len(),
+        |from, to| model.travel_ticks(&platforms, points[from], points[to]),
+        rng,
+    );
+
+    let mut routed_blocks = order.into_iter().map(|i| routable[i].0);
+    let mut vec = Vec::with_capacity(actions.len());
+    for index in 0..blocks.len() {
+        let index = if routable.iter().any(|(routable, _)| *routable == index) {
+            routed_blocks
+                .next()
+                .expect("same number of routable blocks")
+        } else {
+            index
+        };
+        vec.extend_from_slice(&actions[blocks[index].clone()]);
+    }
+
+    vec
+}
+
+/// Computes a near-optimal order to visit `len` points in a repeating cycle using simulated
+/// annealing.
+///
+/// `travel_ticks` estimates the ticks to travel between the two point indices. Returns the
+/// indices of the `len` points in visiting order, always starting from the first point.
+fn optimize_route(len: usize, travel_ticks: impl Fn(usize, usize) -> f32, rng: &Rng) -> Vec<usize> {
+    let mut order = (0..len).collect::<Vec<_>>();
+    if len < 3 {
+        return order;
+    }
+
+    let costs = (0..len)
+        .map(|from| {
+            (0..len)
+                .map(|to| travel_ticks(from, to))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut cost = route_ticks(&order, &costs);
+    let mut best_order = order.clone();
+    let mut best_cost = cost;
+    let mut temperature = ANNEALING_INITIAL_TEMPERATURE;
+
+    for _ in 0..ANNEALING_ITERATIONS {
+        // Reverses a sub-route while keeping the first point fixed
+        let start = rng.random_range(1..len - 1);
+        let end = rng.random_range(start + 1..len);
+        order[start..=end].reverse();
+
+        let new_cost = route_ticks(&order, &costs);
+        let delta = new_cost - cost;
+        if delta <= 0.0 || rng.random_bool((-delta / temperature).exp() as f64) {
+            cost = new_cost;
+            if cost < best_cost {
+                best_cost = cost;
+                best_order.clone_from(&order);
+            }
+        } else {
+            order[start..=end].reverse();
+        }
+        temperature *= ANNEALING_COOLING_RATE;
+    }
+
+    best_order
+}
+
+/// Sums the travel ticks of visiting `order` and returning to the first point.
+#[inline]
+fn route_ticks(order: &[usize], costs: &[Vec<f32>]) -> f32 {
+    order
+        .iter()
+        .zip(order.iter().cycle().skip(1))
+        .map(|(from, to)| costs[*from][*to])
+        .sum()
+}
+
+/// Splits `actions` into ranges of a non-linked action followed by its linked actions.
+pub fn action_blocks(actions: &[Action]) -> Vec<Range<usize>> {
+    let mut blocks = Vec::<Range<usize>>::new();
+    for (index, action) in actions.iter().enumerate() {
+        match blocks.last_mut() {
+            Some(block) if matches!(action.condition(), ActionCondition::Linked) => {
+                block.end = index + 1;
+            }
+            _ => blocks.push(index..index + 1),
+        }
+    }
+
+    blocks
+}
+
+#[inline]
+fn routable_position(action: &Action) -> Option<Point> {
+    if !matches!(action.condition(), ActionCondition::Any) {
+        return None;
+    }
+
+    let position = match action {
+        Action::Move(action) => Some(action.position),
+        Action::Key(action) => action.position,
+    }?;
+    Some(Point::new(position.x, position.y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ActionKey, ActionMove, KeyBinding, Position};
+
+    const SEED: [u8; 32] = [
+        64, 241, 206, 219, 49, 21, 218, 145, 254, 152, 68, 176, 242, 238, 152, 14, 176, 241, 153,
+        64, 44, 192, 172, 191, 191, 157, 107, 206, 193, 55, 115, 68,
+    ];
+
+    fn move_to(x: i32, y: i32) -> Action {
+        Action::Move(ActionMove {
+            position: Position {
+                x,
+                y,
+                ..Position::default()
+            },
+            ..ActionMove::default()
+        })
+    }
+
+    fn linked_key(key: KeyBinding) -> Action {
+        Action::Key(ActionKey {
+            key,
+            condition: ActionCondition::Linked,
+            ..ActionKey::default()
+        })
+    }
+
+    #[test]
+    fn optimize_route_removes_crossing() {
+        let points = [
+            Point::new(0, 0),
+            Point::new(100, 0),
+            Point::new(0, 100),
+            Point::new(100, 100),
+        ];
+        let distance = |from: usize, to: usize| {
+            let (from, to) = (points[from], points[to]);
+            ((from.x - to.x).abs() + (from.y - to.y).abs()) as f32
+        };
+
+        let order = optimize_route(points.len(), distance, &Rng::new(SEED, 1337));
+
+        assert_eq!(order[0], 0);
+        assert_eq!(route_ticks(&order, &cost_matrix(&points, distance)), 400.0);
+    }
+
+    #[test]
+    fn optimize_actions_route_keeps_linked_and_unroutable_actions() {
+        let priority = Action::Key(ActionKey {
+            condition: ActionCondition::EveryMillis(1000),
+            ..ActionKey::default()
+        });
+        let actions = vec![
+            move_to(0, 10),
+            move_to(50, 10),
+            linked_key(KeyBinding::A),
+            priority,
+            move_to(100, 10),
+            move_to(10, 10),
+            linked_key(KeyBinding::B),
+        ];
+
+        let routed = optimize_actions_route(
+            &actions,
+            &[],
+            RouteCostModel::new(None),
+            &Rng::new(SEED, 1337),
+        );
+
+        let linked_after = |action: Action| {
+            let index = routed.iter().position(|routed| *routed == action).unwrap();
+            routed[index + 1]
+        };
+        assert_eq!(routed.len(), actions.len());
+        assert_eq!(routed[0], move_to(0, 10));
+        assert_eq!(routed[3], priority);
+        assert_eq!(linked_after(move_to(50, 10)), linked_key(KeyBinding::A));
+        assert_eq!(linked_after(move_to(10, 10)), linked_key(KeyBinding::B));
+    }
+
+    #[test]
+    fn action_blocks_groups_linked_actions() {
+        let actions = [
+            linked_key(KeyBinding::A),
+            move_to(0, 0),
+            linked_key(KeyBinding::B),
+            linked_key(KeyBinding::C),
+            move_to(1, 1),
+        ];
+
+        assert_eq!(action_blocks(&actions), vec![0..1, 1..4, 4..5]);
+    }
+
+    fn cost_matrix(points: &[Point], distance: impl Fn(usize, usize) -> f32) -> Vec<Vec<f32>> {
+        (0..points.len())
+            .map(|from| (0..points.len()).map(|to| distance(from, to)).collect())
+            .collect()
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     cell::RefCell,
-    env,
+    env, mem,
     rc::Rc,
     sync::{
         Arc,
@@ -11,41 +11,48 @@ use std::{
 };
 
 use anyhow::Result;
+use log::info;
+use opencv::core::{MatTraitConst, Size};
 use platforms::{Error, input::InputKind};
-use strum::IntoEnumIterator;
 use tokio::sync::broadcast::{Sender, channel};
 
 #[cfg(debug_assertions)]
 use crate::ecs::Debug;
 use crate::{
+    AnnouncementKind,
     bridge::{Capture, DefaultCapture, DefaultInput, InputMethod},
-    buff::{self, Buff, BuffContext, BuffEntity, BuffKind},
-    database::{query_and_upsert_seeds, query_or_upsert_localization, query_settings},
+    buff,
+    capability::capability_statuses,
+    database::{
+        query_and_upsert_seeds, query_or_upsert_localization, query_risky_action_counts,
+        query_settings,
+    },
     detect::{DefaultDetector, Detector},
     ecs::{Resources, World, WorldEvent},
+    error::{BackendError, report_error},
+    event_log::SessionEventRecorder,
     mat::OwnedMat,
-    minimap::{self, Minimap, MinimapContext, MinimapEntity},
+    metrics::{TickSystem, profile_system, record_player_transition, record_tick_duration},
+    minimap::{self, Minimap},
     navigator::{DefaultNavigator, Navigator},
     notification::DiscordNotification,
     operation::Operation,
-    player::{self, Player, PlayerContext, PlayerEntity},
-    rng::Rng,
+    player::{self, Player},
+    replay::Replay,
+    rng::{Rng, session_seed},
     rotator::{DefaultRotator, Rotator},
     services::Services,
-    skill::{self, Skill, SkillContext, SkillEntity, SkillKind},
+    skill,
     task::{Task, Update, update_detection_task},
+    tick::{TickActivity, set_adaptive_tick_rate, set_tick_rate, tick_rate, tick_step},
+    timelapse::TimelapseRecorder,
 };
 
-/// The FPS the bot runs at.
-///
-/// This must **not** be changed as it affects other ticking systems.
-pub const FPS: u32 = 30;
-
-/// Milliseconds per tick as an [`u64`].
-pub const MS_PER_TICK: u64 = MS_PER_TICK_F32 as u64;
-
-/// Milliseconds per tick as an [`f32`].
-pub const MS_PER_TICK_F32: f32 = 1000.0 / FPS as f32;
+/// The ONNX Runtime library copied next to the executable by the build script.
+#[cfg(not(feature = "linux"))]
+const ONNX_RUNTIME_LIBRARY: &str = "onnxruntime.dll";
+#[cfg(feature = "linux")]
+const ONNX_RUNTIME_LIBRARY: &str = "libonnxruntime.so";
 
 pub fn init() {
     static LOOPING: AtomicBool = AtomicBool::new(false);
@@ -54,13 +61,9 @@ pub fn init() {
         .compare_exchange(false, true, Ordering::SeqCst, Ordering::Acquire)
         .is_ok()
     {
-        let dll = env::current_exe()
-            .unwrap()
-            .parent()
-            .unwrap()
-            .join("onnxruntime.dll");
-
-        ort::init_from(dll.to_str().unwrap()).commit().unwrap();
+        if let Err(error) = init_onnx_runtime() {
+            report_error("run", error);
+        }
         platforms::init();
         thread::spawn(|| {
             let tokio_rt = tokio::runtime::Builder::new_multi_thread()
@@ -75,14 +78,53 @@ pub fn init() {
     }
 }
 
+/// Loads [`ONNX_RUNTIME_LIBRARY`] from the executable directory.
+fn init_onnx_runtime() -> Result<(), BackendError> {
+    let exe = env::current_exe().map_err(|err| BackendError::RuntimeLoadFailed {
+        path: ONNX_RUNTIME_LIBRARY.to_string(),
+        reason: err.to_string(),
+    })?;
+    let library = exe.parent().unwrap_or(&exe).join(ONNX_RUNTIME_LIBRARY);
+    let path = library
+        .to_str()
+        .ok_or_else(|| BackendError::RuntimeLoadFailed {
+            path: library.to_string_lossy().into_owned(),
+            reason: "path is not valid UTF-8".to_string(),
+        })?;
+
+    ort::init_from(path)
+        .commit()
+        .map_err(|err| BackendError::RuntimeLoadFailed {
+            path: path.to_string(),
+            reason: err.to_string(),
+        })?;
+    Ok(())
+}
+
 fn systems_loop() {
+    for status in capability_statuses() {
+        if !status.is_available() {
+            info!(target: "capability", "{} is disabled due to missing resources {:?}", status.capability, status.missing_resources);
+        }
+    }
+
     let settings = Rc::new(RefCell::new(query_settings()));
-    let localization = Rc::new(RefCell::new(Arc::new(query_or_upsert_localization())));
+    set_tick_rate(settings.borrow().tick_rate);
+    set_adaptive_tick_rate(settings.borrow().adaptive_tick_rate);
+    let localization = Rc::new(RefCell::new(Arc::new(query_or_upsert_localization(
+        settings.borrow().localization_id,
+    ))));
     let seeds = query_and_upsert_seeds();
     let rng = Rng::new(seeds.rng_seed, seeds.perlin_seed);
     let (event_tx, event_rx) = channel::<WorldEvent>(5);
+    info!(target: "rotator", "starting session {}", seeds.session_count);
 
-    let mut service = Services::new(settings.clone(), localization.clone(), event_tx.subscribe());
+    let mut service = Services::new(
+        settings.clone(),
+        localization.clone(),
+        session_seed(seeds.rng_seed, seeds.session_count),
+        event_tx.subscribe(),
+    );
     let window = service.selected_window();
     let mut input = DefaultInput::new(
         InputMethod::Default(window, InputKind::Focused),
@@ -90,6 +132,8 @@ fn systems_loop() {
     );
     let mut capture = DefaultCapture::new(window);
     service.update_window(&mut input, &mut capture);
+    let replay = Replay::default();
+    let input = replay.wrap_input(input);
 
     let mut rotator = DefaultRotator::default();
     let mut navigator = DefaultNavigator::new(event_rx);
@@ -103,42 +147,18 @@ fn systems_loop() {
         detector: None,
         operation: Operation::Halting,
         tick: 0,
+        tick_step: 1,
+        replay,
+        input_paused: false,
     };
-
-    let minimap = MinimapEntity {
-        state: Minimap::Detecting,
-        context: MinimapContext::default(),
-    };
-    let player = PlayerEntity {
-        state: Player::Idle,
-        context: PlayerContext::default(),
-    };
-    let skills = SkillKind::iter()
-        .map(SkillContext::new)
-        .map(|context| SkillEntity {
-            state: Skill::Detecting,
-            context,
-        })
-        .collect::<Vec<_>>()
-        .try_into()
-        .expect("matching size");
-
-    let buffs = BuffKind::iter()
-        .map(BuffContext::new)
-        .map(|context| BuffEntity {
-            state: Buff::No,
-            context,
-        })
-        .collect::<Vec<_>>()
-        .try_into()
-        .expect("matching size");
-    let mut world = World {
-        minimap,
-        player,
-        skills,
-        buffs,
-    };
+    let mut world = World::default();
+    if let Ok(Some(counts)) = query_risky_action_counts() {
+        world.player.context.restore_risky_action_counts(&counts);
+    }
+    let mut event_recorder = SessionEventRecorder::default();
+    let mut timelapse_recorder = TimelapseRecorder::default();
     let mut is_capturing_normally = false;
+    let mut frame_size = None::<Size>;
 
     let mut lie_detector_event_task = event_task(
         WorldEvent::LieDetectorAppeared,
@@ -150,13 +170,47 @@ fn systems_loop() {
         event_tx.clone(),
         |detector| detector.detect_elite_boss_bar(),
     );
+    let mut world_boss_event_task = event_task(
+        WorldEvent::AnnouncementAppeared(AnnouncementKind::WorldBoss),
+        event_tx.clone(),
+        |detector| detector.detect_announcement(AnnouncementKind::WorldBoss),
+    );
+    let mut invasion_event_task = event_task(
+        WorldEvent::AnnouncementAppeared(AnnouncementKind::Invasion),
+        event_tx.clone(),
+        |detector| detector.detect_announcement(AnnouncementKind::Invasion),
+    );
 
-    loop_with_fps(FPS, || {
-        let detector = capture
-            .grab()
-            .and_then(|frame| OwnedMat::new(frame).map_err(|_| Error::WindowInvalidSize))
-            .map(|mat| DefaultDetector::new(mat, localization.borrow().clone()));
+    loop_with_tick_rate(|tick_step| {
+        resources.replay.update_clock();
+        let detector = profile_system(TickSystem::Capture, || {
+            resources
+                .replay
+                .next_frame()
+                .unwrap_or_else(|| {
+                    capture.grab().and_then(|frame| {
+                        OwnedMat::new(frame).map_err(|_| Error::WindowInvalidSize)
+                    })
+                })
+                .map(|mat| DefaultDetector::new(mat, localization.borrow().clone()))
+        });
+        #[cfg(debug_assertions)]
+        let can_tick = resources.debug.consume_tick();
+        #[cfg(not(debug_assertions))]
+        let can_tick = true;
+        // Advances exactly one tick per step regardless of the adaptive tick rate
+        #[cfg(debug_assertions)]
+        let tick_step = if resources.debug.is_stepping() {
+            1
+        } else {
+            tick_step
+        };
+        // Leaves the game to the user while paused from physical input
+        let can_tick = can_tick && !resources.input_paused;
         let was_capturing_normally = is_capturing_normally;
+        let was_halting = resources.operation.halting();
+        let was_player_idle = matches!(world.player.state, Player::Idle | Player::Detecting);
+        let was_player_panicking = matches!(world.player.state, Player::Panicking(_));
         let player_in_cash_shop = matches!(world.player.state, Player::CashShopThenExit(_));
 
         is_capturing_normally = detector.is_ok()
@@ -165,29 +219,67 @@ fn systems_loop() {
                     detector,
                     Err(Error::WindowNotFound | Error::WindowInvalidSize)
                 ));
-        resources.tick += 1;
-        if let Ok(detector) = detector {
+        let has_frame = detector.is_ok();
+        let is_window_lost = matches!(detector, Err(Error::WindowNotFound));
+        resources.tick_step = tick_step;
+        if can_tick {
+            resources.tick += tick_step as u64;
+        }
+        if let Ok(detector) = detector
+            && can_tick
+        {
             let was_running_cycle = matches!(resources.operation, Operation::RunUntil { .. });
             let was_stopping_cycle = matches!(resources.operation, Operation::HaltUntil { .. });
             let was_player_alive = !world.player.context.is_dead();
+
+            // Re-detects the minimap when the game window is resized so that the regions detected
+            // from the previous frame size are not reused. This happens before checking whether
+            // the minimap was idle so that a resize is not mistaken for a map change.
+            let current_frame_size = detector.mat().size().ok();
+            let did_frame_resized = frame_size.is_some()
+                && current_frame_size.is_some()
+                && frame_size != current_frame_size;
+            if current_frame_size.is_some() {
+                frame_size = current_frame_size;
+            }
+            if did_frame_resized && let Some(size) = frame_size {
+                info!(target: "minimap", "game window resized to {}x{}, re-detecting", size.width, size.height);
+                world.minimap.state = Minimap::Detecting;
+                navigator.mark_dirty(true);
+            }
             let was_minimap_idle = matches!(world.minimap.state, Minimap::Idle(_));
 
             resources.detector = Some(Arc::new(detector));
             resources.operation = resources.operation.update_tick();
 
-            minimap::run_system(&resources, &mut world.minimap, world.player.state.clone());
-            player::run_system(&resources, &mut world.player, &world.minimap, &world.buffs);
-            for skill in world.skills.iter_mut() {
-                skill::run_system(&resources, skill, world.player.state.clone());
-            }
-            for buff in world.buffs.iter_mut() {
-                buff::run_system(&resources, buff, world.player.state.clone());
+            profile_system(TickSystem::Minimap, || {
+                minimap::run_system(&resources, &mut world.minimap, world.player.state.clone());
+            });
+            let player_state = mem::discriminant(&world.player.state);
+            profile_system(TickSystem::Player, || {
+                player::run_system(&resources, &mut world.player, &world.minimap, &world.buffs);
+            });
+            if player_state != mem::discriminant(&world.player.state) {
+                record_player_transition(world.player.state.to_string());
             }
+            profile_system(TickSystem::SkillsAndBuffs, || {
+                for skill in world.skills.iter_mut() {
+                    skill::run_system(&resources, skill, world.player.state.clone());
+                }
+                for buff in world.buffs.iter_mut() {
+                    buff::run_system(&resources, buff, world.player.state.clone());
+                }
+            });
 
-            if navigator.navigate_player(&resources, &mut world.player.context, world.minimap.state)
-            {
-                rotator.rotate_action(&resources, &mut world);
-            }
+            profile_system(TickSystem::Rotator, || {
+                if navigator.navigate_player(
+                    &resources,
+                    &mut world.player.context,
+                    world.minimap.state,
+                ) {
+                    rotator.rotate_action(&resources, &mut world);
+                }
+            });
 
             let did_cycled_to_stop = resources.operation.halting();
             let did_cycled_to_run = matches!(resources.operation, Operation::RunUntil { .. });
@@ -203,18 +295,45 @@ fn systems_loop() {
             if player_died {
                 let _ = event_tx.send(WorldEvent::PlayerDied);
             }
+            if world.player.context.take_unstuck_exhausted() {
+                let _ = event_tx.send(WorldEvent::PlayerStuck);
+            }
 
             let minimap_detecting = matches!(world.minimap.state, Minimap::Detecting);
             if was_minimap_idle && minimap_detecting {
                 let _ = event_tx.send(WorldEvent::MinimapChanged);
             }
+            if !was_minimap_idle && !minimap_detecting {
+                let _ = event_tx.send(WorldEvent::MinimapDetected);
+            }
 
             lie_detector_event_task(&resources);
             elite_boss_event_task(&resources);
+            world_boss_event_task(&resources);
+            invasion_event_task(&resources);
+        }
+        if can_tick {
+            let frame = has_frame.then(|| resources.detector().mat());
+            resources
+                .replay
+                .update(frame.as_deref(), &world.player.state);
+            event_recorder.update(resources.tick, &world, resources.operation.halting());
+            timelapse_recorder.update(
+                frame.as_deref(),
+                &world,
+                resources.operation,
+                settings.borrow().session_timelapse,
+            );
         }
 
         if was_capturing_normally && !is_capturing_normally {
             let _ = event_tx.send(WorldEvent::CaptureFailed);
+            let error = if is_window_lost {
+                BackendError::CaptureHandleLost
+            } else {
+                BackendError::CaptureFailed
+            };
+            report_error("run", error);
         }
 
         resources.input.update(resources.tick);
@@ -222,16 +341,79 @@ fn systems_loop() {
             .notification
             .update(resources.detector.as_ref().map(|detector| detector.mat()));
 
-        service.poll(
-            &mut resources,
-            &mut world,
-            &mut rotator,
-            &mut navigator,
-            &mut capture,
-        );
+        profile_system(TickSystem::Services, || {
+            service.poll(
+                &mut resources,
+                &mut world,
+                &mut rotator,
+                &mut navigator,
+                &mut capture,
+            );
+        });
+
+        if should_release_all_keys(
+            was_halting,
+            was_player_idle,
+            was_player_panicking,
+            resources.operation.halting(),
+            &world.player.state,
+            resources.input.any_key_held(),
+        ) {
+            resources.input.send_all_keys_up();
+        }
+
+        tick_activity(
+            resources.operation.halting(),
+            has_frame,
+            resources.input_paused,
+            &world.player.state,
+        )
     });
 }
 
+/// Gets how busy the main loop is after a tick for the adaptive tick rate.
+///
+/// While running, the player only waits when it is idle without an action or stalling. All the
+/// other states such as moving or solving rune need every tick to act on time.
+fn tick_activity(
+    halting: bool,
+    has_frame: bool,
+    input_paused: bool,
+    player: &Player,
+) -> TickActivity {
+    if input_paused {
+        return TickActivity::Idle;
+    }
+
+    match player {
+        Player::Idle | Player::Detecting if halting => TickActivity::Idle,
+        _ if halting && !has_frame => TickActivity::Idle,
+        Player::Idle | Player::Stalling(_, _) if !halting => TickActivity::Waiting,
+        _ => TickActivity::Active,
+    }
+}
+
+/// Whether to force-release all held keys after a tick.
+///
+/// Keys are always released on halting or when the player starts panicking because the player
+/// may be in the middle of holding down keys. When the player becomes idle, keys are only released
+/// if one is still held down by a state that missed its key up so that key presses pending their
+/// press duration are not cut short on every completed action.
+fn should_release_all_keys(
+    was_halting: bool,
+    was_player_idle: bool,
+    was_player_panicking: bool,
+    halting: bool,
+    player: &Player,
+    any_key_held: bool,
+) -> bool {
+    let did_halt = !was_halting && halting;
+    let did_player_panic = !was_player_panicking && matches!(player, Player::Panicking(_));
+    let did_player_idle = !was_player_idle && matches!(player, Player::Idle | Player::Detecting);
+
+    did_halt || did_player_panic || (did_player_idle && any_key_held)
+}
+
 fn event_task(
     event: WorldEvent,
     event_tx: Sender<WorldEvent>,
@@ -259,21 +441,24 @@ fn event_task(
 }
 
 #[inline]
-fn loop_with_fps(fps: u32, mut on_tick: impl FnMut()) {
+fn loop_with_tick_rate(mut on_tick: impl FnMut(u32) -> TickActivity) {
     #[cfg(debug_assertions)]
     const LOG_INTERVAL_SECS: u64 = 5;
 
-    let nanos_per_frame = (1_000_000_000 / fps) as u128;
     #[cfg(debug_assertions)]
     let mut last_logged_instant = Instant::now();
 
+    let mut step = 1;
     loop {
         let start = Instant::now();
-
-        on_tick();
+        let activity = on_tick(step);
+        step = tick_step(activity);
+        // Read every frame as the tick rate can be changed through settings
+        let nanos_per_frame = 1_000_000_000 * step as u128 / tick_rate() as u128;
 
         let now = Instant::now();
         let elapsed_duration = now.duration_since(start);
+        record_tick_duration(elapsed_duration);
         let elapsed_nanos = elapsed_duration.as_nanos();
         if elapsed_nanos <= nanos_per_frame {
             thread::sleep(Duration::new(0, (nanos_per_frame - elapsed_nanos) as u32));
@@ -288,3 +473,130 @@ fn loop_with_fps(fps: u32, mut on_tick: impl FnMut()) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::{PanicTo, Panicking};
+
+    #[test]
+    fn tick_activity_halting() {
+        assert_eq!(
+            tick_activity(true, true, false, &Player::Idle),
+            TickActivity::Idle
+        );
+        assert_eq!(
+            tick_activity(
+                true,
+                false,
+                false,
+                &Player::Panicking(Panicking::new(PanicTo::Town))
+            ),
+            TickActivity::Idle
+        );
+        assert_eq!(
+            tick_activity(
+                true,
+                true,
+                false,
+                &Player::Panicking(Panicking::new(PanicTo::Town))
+            ),
+            TickActivity::Active
+        );
+    }
+
+    #[test]
+    fn tick_activity_running() {
+        assert_eq!(
+            tick_activity(false, true, false, &Player::Idle),
+            TickActivity::Waiting
+        );
+        assert_eq!(
+            tick_activity(
+                false,
+                true,
+                false,
+                &Player::Stalling(Default::default(), 10)
+            ),
+            TickActivity::Waiting
+        );
+        // Does not throttle without a frame while running
+        assert_eq!(
+            tick_activity(false, false, false, &Player::Detecting),
+            TickActivity::Active
+        );
+        assert_eq!(
+            tick_activity(
+                false,
+                true,
+                false,
+                &Player::Panicking(Panicking::new(PanicTo::Town))
+            ),
+            TickActivity::Active
+        );
+        assert_eq!(
+            tick_activity(false, true, true, &Player::Detecting),
+            TickActivity::Idle
+        );
+    }
+
+    #[test]
+    fn should_release_all_keys_on_halt() {
+        assert!(should_release_all_keys(
+            false,
+            false,
+            false,
+            true,
+            &Player::Idle,
+            false
+        ));
+        assert!(!should_release_all_keys(
+            true,
+            true,
+            false,
+            true,
+            &Player::Idle,
+            true
+        ));
+    }
+
+    #[test]
+    fn should_release_all_keys_on_panic() {
+        let panicking = Player::Panicking(Panicking::new(PanicTo::Channel));
+
+        assert!(should_release_all_keys(
+            false, false, false, false, &panicking, false
+        ));
+        assert!(!should_release_all_keys(
+            false, false, true, false, &panicking, true
+        ));
+    }
+
+    #[test]
+    fn should_release_all_keys_on_idle_only_when_key_held() {
+        assert!(!should_release_all_keys(
+            false,
+            false,
+            false,
+            false,
+            &Player::Idle,
+            false
+        ));
+        assert!(should_release_all_keys(
+            false,
+            false,
+            false,
+            false,
+            &Player::Idle,
+            true
+        ));
+        assert!(!should_release_all_keys(
+            false,
+            true,
+            false,
+            false,
+            &Player::Idle,
+            true
+        ));
+    }
+}
@@ -0,0 +1,490 @@
+//! A scriptable fake game for end-to-end tests of the player state machine.
+//!
+//! A [`Sandbox`] renders a synthetic frame containing a minimap with its platforms and the player
+//! marker, detects the player through [`DefaultDetector`] and runs the player system on it. The
+//! inputs sent by the player system are applied to a simple physics stub that moves the player
+//! for the next tick. This closes the loop between detection and inputs without the game.
+//!
+//! The physics only approximates walking, jumping, double jumping and falling at the reference
+//! speeds the movement thresholds are tuned at. It is meant to exercise the state transitions and
+//! not to reproduce the exact game movement.
+
+use std::{cell::RefCell, collections::HashSet, mem, rc::Rc, sync::Arc};
+
+use opencv::core::{CV_8UC4, Mat, MatTrait, Point, Rect, Scalar, Size, Vec4b};
+
+use crate::{
+    array::Array,
+    bridge::{Input, InputKeyDownOptions, InputMethod, KeyKind, MouseKind},
+    detect::DefaultDetector,
+    ecs::{Resources, World},
+    mat::OwnedMat,
+    minimap::{Minimap, MinimapIdle},
+    models::{FocusPolicy, KeyPressDurations, KeyboardLayout, Localization, PlayerMarker},
+    pathing::{Platform, PlatformWithNeighbors, find_neighbors},
+    player::{
+        self, DOUBLE_JUMP_THRESHOLD, GRAPPLING_MAX_THRESHOLD, JUMP_THRESHOLD, Player,
+        REFERENCE_DOUBLE_JUMP_SPEED, REFERENCE_WALK_SPEED,
+    },
+    rng::Rng,
+    task::set_synchronous_tasks,
+};
+
+const SEED: [u8; 32] = [
+    77, 212, 12, 190, 43, 8, 151, 96, 3, 250, 61, 137, 18, 205, 174, 92, 39, 128, 66, 221, 7, 184,
+    115, 50, 243, 29, 160, 88, 201, 14, 135, 57,
+];
+
+/// The size of the synthetic frame.
+const FRAME_SIZE: Size = Size {
+    width: 800,
+    height: 600,
+};
+
+/// The minimap bounding box inside the synthetic frame.
+const MINIMAP_BBOX: Rect = Rect {
+    x: 10,
+    y: 10,
+    width: 200,
+    height: 80,
+};
+
+const MINIMAP_BORDER_COLOR: Vec4b = Vec4b::from_array([255, 255, 255, 255]);
+
+const MINIMAP_BACKGROUND_COLOR: Vec4b = Vec4b::from_array([40, 40, 40, 255]);
+
+const MINIMAP_PLATFORM_COLOR: Vec4b = Vec4b::from_array([180, 180, 180, 255]);
+
+/// Distance in pixels moved by tapping a direction key while standing.
+const TAP_DISTANCE: f32 = 1.0;
+
+/// Upward velocity in pixels per tick when jumping.
+const JUMP_VELOCITY: f32 = 2.0;
+
+/// Velocity in pixels per tick lost every tick while airborne.
+const GRAVITY: f32 = 0.2;
+
+/// Maximum downward velocity in pixels per tick.
+const MAX_FALL_VELOCITY: f32 = 3.0;
+
+/// Keys sent through [`SandboxInput`].
+#[derive(Debug, Default)]
+struct SandboxKeys {
+    /// Keys that were sent a down stroke but not yet an up stroke.
+    held: HashSet<KeyKind>,
+    /// Keys pressed since the last physics update, either tapped or held down.
+    pressed: Vec<KeyKind>,
+}
+
+/// An [`Input`] that records keys for [`SandboxPhysics`] instead of sending them.
+#[derive(Debug)]
+struct SandboxInput {
+    keys: Rc<RefCell<SandboxKeys>>,
+}
+
+impl Input for SandboxInput {
+    fn update(&mut self, _tick: u64) {}
+
+    fn set_method(&mut self, _method: InputMethod) {}
+
+    fn set_focus_policy(&mut self, _policy: FocusPolicy, _grace_period_millis: u64) {}
+
+    fn set_press_durations(&mut self, _durations: KeyPressDurations) {}
+
+    fn set_observer(&mut self, _observer: bool) {}
+
+    fn set_keyboard_layout(&mut self, _layout: KeyboardLayout) {}
+
+    fn set_audit(&mut self, _audit: bool) {}
+
+    fn send_mouse(&self, _x: i32, _y: i32, _kind: MouseKind) {}
+
+    fn send_key(&self, kind: KeyKind) {
+        self.keys.borrow_mut().pressed.push(kind);
+    }
+
+    fn send_key_up(&self, kind: KeyKind) {
+        self.keys.borrow_mut().held.remove(&kind);
+    }
+
+    fn send_paste(&self, _text: &str) -> bool {
+        false
+    }
+
+    fn send_key_down_with_options(&self, kind: KeyKind, _options: InputKeyDownOptions) {
+        let mut keys = self.keys.borrow_mut();
+        if keys.held.insert(kind) {
+            keys.pressed.push(kind);
+        }
+    }
+
+    fn is_key_cleared(&self, kind: KeyKind) -> bool {
+        !self.keys.borrow().held.contains(&kind)
+    }
+
+    fn all_keys_cleared(&self) -> bool {
+        self.keys.borrow().held.is_empty()
+    }
+
+    fn any_key_held(&self) -> bool {
+        !self.keys.borrow().held.is_empty()
+    }
+
+    fn send_all_keys_up(&self) {
+        self.keys.borrow_mut().held.clear();
+    }
+}
+
+/// A physics stub moving the player in response to [`SandboxKeys`].
+///
+/// The position is in player-relative coordinate, which is bottom-left. The bottom of the minimap
+/// is always a floor.
+#[derive(Debug)]
+struct SandboxPhysics {
+    pos: (f32, f32),
+    velocity: (f32, f32),
+    on_ground: bool,
+    double_jumped: bool,
+}
+
+impl SandboxPhysics {
+    fn new(pos: Point) -> Self {
+        Self {
+            pos: (pos.x as f32, pos.y as f32),
+            velocity: (0.0, 0.0),
+            on_ground: true,
+            double_jumped: false,
+        }
+    }
+
+    fn pos(&self) -> Point {
+        Point::new(self.pos.0.round() as i32, self.pos.1.round() as i32)
+    }
+
+    /// Advances the physics by one tick from the keys sent during the tick.
+    fn update(
+        &mut self,
+        keys: &mut SandboxKeys,
+        jump_key: KeyKind,
+        platforms: &[PlatformWithNeighbors],
+    ) {
+        let pressed = mem::take(&mut keys.pressed);
+        let direction = keys.held.contains(&KeyKind::Right) as i32 as f32
+            - keys.held.contains(&KeyKind::Left) as i32 as f32;
+        let jumped = pressed.contains(&jump_key);
+
+        if self.on_ground {
+            self.velocity.0 = direction * REFERENCE_WALK_SPEED;
+            for key in pressed.iter().filter(|key| !keys.held.contains(key)) {
+                match key {
+                    KeyKind::Right => self.pos.0 += TAP_DISTANCE,
+                    KeyKind::Left => self.pos.0 -= TAP_DISTANCE,
+                    _ => (),
+                }
+            }
+            if jumped {
+                self.velocity.1 = JUMP_VELOCITY;
+                self.on_ground = false;
+                self.double_jumped = false;
+            }
+        } else if jumped && !self.double_jumped && direction != 0.0 {
+            self.velocity.0 = direction * REFERENCE_DOUBLE_JUMP_SPEED;
+            self.double_jumped = true;
+        }
+
+        if !self.on_ground {
+            self.velocity.1 = (self.velocity.1 - GRAVITY).max(-MAX_FALL_VELOCITY);
+        }
+        let prev_y = self.pos.1;
+        self.pos.0 = (self.pos.0 + self.velocity.0).clamp(0.0, (MINIMAP_BBOX.width - 1) as f32);
+        self.pos.1 += self.velocity.1;
+
+        let x = self.pos.0.round() as i32;
+        let platform_y = platforms
+            .iter()
+            .filter(|platform| platform.xs().contains(&x))
+            .map(|platform| platform.y() as f32)
+            .filter(|y| *y <= prev_y)
+            .fold(0.0, f32::max);
+        if self.pos.1 <= platform_y {
+            self.pos.1 = platform_y;
+            self.velocity = (direction * REFERENCE_WALK_SPEED, 0.0);
+            self.on_ground = true;
+        } else if self.on_ground {
+            // Walked off the platform edge
+            self.on_ground = false;
+        }
+    }
+}
+
+/// Renders a BGRA frame with the minimap at [`MINIMAP_BBOX`] containing `platforms` and the
+/// default [`PlayerMarker`] at `pos`.
+fn render_frame(platforms: &[PlatformWithNeighbors], pos: Point) -> Mat {
+    let mut mat = Mat::new_rows_cols_with_default(
+        FRAME_SIZE.height,
+        FRAME_SIZE.width,
+        CV_8UC4,
+        Scalar::all(0.0),
+    )
+    .unwrap();
+    fill_rect(&mut mat, MINIMAP_BBOX, MINIMAP_BORDER_COLOR);
+    fill_rect(
+        &mut mat,
+        Rect::new(
+            MINIMAP_BBOX.x + 1,
+            MINIMAP_BBOX.y + 1,
+            MINIMAP_BBOX.width - 2,
+            MINIMAP_BBOX.height - 2,
+        ),
+        MINIMAP_BACKGROUND_COLOR,
+    );
+    for platform in platforms {
+        let xs = platform.xs();
+        fill_rect(
+            &mut mat,
+            Rect::new(
+                MINIMAP_BBOX.x + xs.start,
+                MINIMAP_BBOX.y + MINIMAP_BBOX.height - platform.y(),
+                xs.end - xs.start,
+                1,
+            ),
+            MINIMAP_PLATFORM_COLOR,
+        );
+    }
+
+    // Detection expands the marker bounding box by one pixel and the player `y` is taken from
+    // the expanded bottom edge
+    let marker = PlayerMarker::default();
+    let (red, green, blue) = marker.color;
+    fill_rect(
+        &mut mat,
+        Rect::new(
+            MINIMAP_BBOX.x + pos.x - marker.width / 2,
+            MINIMAP_BBOX.y + MINIMAP_BBOX.height - pos.y - marker.height - 1,
+            marker.width,
+            marker.height,
+        ),
+        Vec4b::from_array([blue, green, red, 255]),
+    );
+
+    mat
+}
+
+fn fill_rect(mat: &mut Mat, rect: Rect, color: Vec4b) {
+    for y in rect.y..rect.y + rect.height {
+        for x in rect.x..rect.x + rect.width {
+            *mat.at_2d_mut::<Vec4b>(y, x).unwrap() = color;
+        }
+    }
+}
+
+/// A fake game running the player system against [`SandboxPhysics`].
+pub struct Sandbox {
+    pub resources: Resources,
+    pub world: World,
+    keys: Rc<RefCell<SandboxKeys>>,
+    physics: SandboxPhysics,
+    localization: Arc<Localization>,
+}
+
+impl Sandbox {
+    /// Creates a sandbox with `platforms` and the player standing at `pos`.
+    ///
+    /// Tasks started from the current thread are made synchronous so that detections complete
+    /// on the same ticks across runs.
+    pub fn new(platforms: &[Platform], pos: Point) -> Self {
+        set_synchronous_tasks(true);
+
+        let keys = Rc::new(RefCell::new(SandboxKeys::default()));
+        let mut resources = Resources::new(None, None);
+        resources.input = Box::new(SandboxInput { keys: keys.clone() });
+        resources.rng = Rng::new(SEED, 1337);
+
+        let mut idle = MinimapIdle::default();
+        idle.bbox = MINIMAP_BBOX;
+        idle.platforms = Array::from_iter(find_neighbors(
+            platforms,
+            DOUBLE_JUMP_THRESHOLD,
+            JUMP_THRESHOLD,
+            GRAPPLING_MAX_THRESHOLD,
+        ));
+        let mut world = World::default();
+        world.minimap.state = Minimap::Idle(idle);
+        world.player.context.config.player_marker = Some(PlayerMarker::default());
+
+        Self {
+            resources,
+            world,
+            keys,
+            physics: SandboxPhysics::new(pos),
+            localization: Arc::new(Localization::default()),
+        }
+    }
+
+    /// The current player position from the physics.
+    pub fn pos(&self) -> Point {
+        self.physics.pos()
+    }
+
+    /// Runs the player system for one tick and applies the sent inputs to the physics.
+    pub fn step(&mut self) {
+        let Minimap::Idle(idle) = self.world.minimap.state else {
+            unreachable!("sandbox minimap is always idle");
+        };
+        let frame = render_frame(idle.platforms.as_slice(), self.physics.pos());
+
+        self.resources.detector = Some(Arc::new(DefaultDetector::new(
+            OwnedMat::from(frame),
+            self.localization.clone(),
+        )));
+        self.resources.tick += 1;
+        player::run_system(
+            &self.resources,
+            &mut self.world.player,
+            &self.world.minimap,
+            &self.world.buffs,
+        );
+        self.physics.update(
+            &mut self.keys.borrow_mut(),
+            self.world.player.context.config.jump_key,
+            idle.platforms.as_slice(),
+        );
+    }
+
+    /// Steps until `predicate` returns true or `max_ticks` has passed.
+    ///
+    /// `predicate` is called with the player state after each step. Returns whether `predicate`
+    /// returned true.
+    pub fn run_until(
+        &mut self,
+        mut predicate: impl FnMut(&Player) -> bool,
+        max_ticks: u32,
+    ) -> bool {
+        for _ in 0..max_ticks {
+            self.step();
+            if predicate(&self.world.player.state) {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        set_synchronous_tasks(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn physics_jumps_onto_platform_above() {
+        let platforms = find_neighbors(&[Platform::new(10..30, 5)], 25, 7, 41);
+        let mut keys = SandboxKeys::default();
+        let mut physics = SandboxPhysics::new(Point::new(20, 0));
+
+        keys.pressed.push(KeyKind::A);
+        physics.update(&mut keys, KeyKind::A, &platforms);
+        assert!(!physics.on_ground);
+
+        for _ in 0..30 {
+            physics.update(&mut keys, KeyKind::A, &platforms);
+        }
+        assert!(physics.on_ground);
+        assert_eq!(physics.pos(), Point::new(20, 5));
+    }
+
+    #[test]
+    fn physics_walks_off_platform_edge() {
+        let platforms = find_neighbors(&[Platform::new(10..30, 20)], 25, 7, 41);
+        let mut keys = SandboxKeys::default();
+        let mut physics = SandboxPhysics::new(Point::new(28, 20));
+
+        keys.held.insert(KeyKind::Right);
+        for _ in 0..10 {
+            physics.update(&mut keys, KeyKind::A, &platforms);
+        }
+        keys.held.clear();
+        for _ in 0..30 {
+            physics.update(&mut keys, KeyKind::A, &platforms);
+        }
+
+        assert!(physics.on_ground);
+        assert_eq!(physics.pos().y, 0);
+        assert!(physics.pos().x >= 30);
+    }
+
+    #[test]
+    fn step_detects_rendered_player_position() {
+        let pos = Point::new(50, 12);
+        let mut sandbox = Sandbox::new(&[Platform::new(40..60, 12)], pos);
+
+        sandbox.step();
+
+        assert_eq!(sandbox.world.player.context.last_known_pos, Some(pos));
+    }
+
+    #[test]
+    fn player_walks_to_destination() {
+        let dest = Point::new(40, 0);
+        let mut sandbox = Sandbox::new(&[], Point::new(20, 0));
+        sandbox.world.player.state = Player::Moving(dest, false, None);
+
+        let mut adjusted = false;
+        let reached = sandbox.run_until(
+            |state| {
+                adjusted |= matches!(state, Player::Adjusting(_));
+                matches!(state, Player::Idle)
+            },
+            300,
+        );
+
+        assert!(reached);
+        assert!(adjusted);
+        assert!(
+            (sandbox.pos().x - dest.x).abs()
+                < sandbox
+                    .world
+                    .player
+                    .context
+                    .config
+                    .adjusting_medium_threshold
+        );
+        assert!(sandbox.resources.input.all_keys_cleared());
+    }
+
+    #[test]
+    fn player_double_jumps_to_far_destination() {
+        let dest = Point::new(150, 0);
+        let mut sandbox = Sandbox::new(&[], Point::new(20, 0));
+        sandbox.world.player.state = Player::Moving(dest, false, None);
+
+        let mut double_jumped = false;
+        let reached = sandbox.run_until(
+            |state| {
+                double_jumped |= matches!(state, Player::DoubleJumping(_));
+                matches!(state, Player::Idle)
+            },
+            1000,
+        );
+
+        assert!(reached);
+        assert!(double_jumped);
+        assert!(
+            (sandbox.pos().x - dest.x).abs()
+                < sandbox
+                    .world
+                    .player
+                    .context
+                    .config
+                    .adjusting_medium_threshold
+        );
+        assert_matches!(sandbox.world.player.state, Player::Idle);
+    }
+}
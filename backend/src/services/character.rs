@@ -1,9 +1,17 @@
 use std::fmt::Debug;
 
+use log::error;
 #[cfg(test)]
 use mockall::automock;
 
-use crate::{Character, PotionMode, player::PlayerContext};
+use crate::{
+    Character, PotionMode,
+    database::upsert_character,
+    player::{
+        MAX_LEVEL_UP_AUTO_ASSIGN_KEYS_COUNT, MovementCalibration, PlayerContext,
+        apply_movement_calibration, apply_teleport_calibration,
+    },
+};
 
 /// A service to handle character-related incoming requests.
 #[cfg_attr(test, automock)]
@@ -17,6 +25,9 @@ pub trait CharacterService: Debug {
 
     /// Updates `player_context` with information from the currently in use `[Character]`.
     fn apply_character(&self, player_context: &mut PlayerContext);
+
+    /// Saves `calibration` to the currently in use [`Character`] and the database.
+    fn save_movement_calibration(&mut self, calibration: MovementCalibration);
 }
 
 #[derive(Debug, Default)]
@@ -43,10 +54,17 @@ impl CharacterService for DefaultCharacterService {
             player_context.config.up_jump_is_flight = character.up_jump_is_flight;
             player_context.config.up_jump_specific_key_should_jump =
                 character.up_jump_specific_key_should_jump;
+            player_context.config.horizontal_teleporting = character.horizontal_teleporting;
+            player_context.config.horizontal_flight = character.horizontal_flight;
             player_context.config.interact_key = character.interact_key.key.into();
             player_context.config.grappling_key = character.ropelift_key.map(|key| key.key.into());
             player_context.config.teleport_key = character.teleport_key.map(|key| key.key.into());
+            player_context.config.flight_key = character.flight_key.map(|key| key.key.into());
             player_context.config.jump_key = character.jump_key.key.into();
+            player_context.config.left_key = character.movement_keys.left.into();
+            player_context.config.right_key = character.movement_keys.right.into();
+            player_context.config.up_key = character.movement_keys.up.into();
+            player_context.config.down_key = character.movement_keys.down.into();
             player_context.config.up_jump_key = character.up_jump_key.map(|key| key.key.into());
             player_context.config.cash_shop_key = character.cash_shop_key.map(|key| key.key.into());
             player_context.config.familiar_key =
@@ -63,6 +81,40 @@ impl CharacterService for DefaultCharacterService {
             player_context.config.update_health_millis = Some(character.health_update_millis);
             player_context.config.generic_booster_key = character.generic_booster_key.key.into();
             player_context.config.hexa_booster_key = character.hexa_booster_key.key.into();
+            player_context.config.level_up_auto_assign_keys = character
+                .level_up_auto_assign_keys
+                .iter()
+                .take(MAX_LEVEL_UP_AUTO_ASSIGN_KEYS_COUNT)
+                .map(|key| (*key).into())
+                .collect();
+            apply_movement_calibration(
+                player_context,
+                character.walk_speed,
+                character.double_jump_speed,
+            );
+            apply_teleport_calibration(
+                player_context,
+                character.teleport_x_distance,
+                character.teleport_y_distance,
+            );
+        }
+    }
+
+    fn save_movement_calibration(&mut self, calibration: MovementCalibration) {
+        let Some(character) = self.character.as_mut() else {
+            return;
+        };
+
+        character.walk_speed = Some(calibration.walk_speed);
+        character.double_jump_speed = Some(calibration.double_jump_speed);
+        if let Some((x_distance, y_distance)) = calibration.teleport_distance {
+            character.teleport_x_distance = Some(x_distance);
+            character.teleport_y_distance = Some(y_distance);
+        }
+        if character.id.is_some() {
+            let _ = upsert_character(character).inspect_err(|err| {
+                error!(target: "character", "failed to save movement calibration {err}");
+            });
         }
     }
 }
@@ -70,7 +122,9 @@ impl CharacterService for DefaultCharacterService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{KeyBinding, KeyBindingConfiguration, bridge::KeyKind, player::PlayerContext};
+    use crate::{
+        KeyBinding, KeyBindingConfiguration, MovementKeys, bridge::KeyKind, player::PlayerContext,
+    };
 
     fn mock_character() -> Character {
         Character {
@@ -80,6 +134,8 @@ mod tests {
             disable_teleport_on_fall: true,
             up_jump_is_flight: true,
             up_jump_specific_key_should_jump: true,
+            horizontal_teleporting: true,
+            horizontal_flight: true,
             interact_key: KeyBindingConfiguration {
                 key: KeyBinding::Z,
                 ..Default::default()
@@ -92,10 +148,20 @@ mod tests {
                 key: KeyBinding::X,
                 ..Default::default()
             }),
+            flight_key: Some(KeyBindingConfiguration {
+                key: KeyBinding::F,
+                ..Default::default()
+            }),
             jump_key: KeyBindingConfiguration {
                 key: KeyBinding::C,
                 ..Default::default()
             },
+            movement_keys: MovementKeys {
+                left: KeyBinding::J,
+                right: KeyBinding::L,
+                up: KeyBinding::I,
+                down: KeyBinding::K,
+            },
             up_jump_key: Some(KeyBindingConfiguration {
                 key: KeyBinding::A,
                 ..Default::default()
@@ -175,10 +241,20 @@ mod tests {
             state.config.up_jump_specific_key_should_jump,
             character.up_jump_specific_key_should_jump
         );
+        assert_eq!(
+            state.config.horizontal_teleporting,
+            character.horizontal_teleporting
+        );
+        assert_eq!(state.config.horizontal_flight, character.horizontal_flight);
         assert_eq!(state.config.interact_key, KeyKind::Z);
         assert_eq!(state.config.grappling_key, Some(KeyKind::V));
         assert_eq!(state.config.teleport_key, Some(KeyKind::X));
+        assert_eq!(state.config.flight_key, Some(KeyKind::F));
         assert_eq!(state.config.jump_key, KeyKind::C);
+        assert_eq!(state.config.left_key, KeyKind::J);
+        assert_eq!(state.config.right_key, KeyKind::L);
+        assert_eq!(state.config.up_key, KeyKind::I);
+        assert_eq!(state.config.down_key, KeyKind::K);
         assert_eq!(state.config.up_jump_key, Some(KeyKind::A));
         assert_eq!(state.config.cash_shop_key, Some(KeyKind::B));
         assert_eq!(state.config.familiar_key, Some(KeyKind::N));
@@ -11,10 +11,12 @@ use tokio::{sync::mpsc::Receiver, task::spawn_blocking};
 use super::EventContext;
 use crate::{
     ActionKeyDirection, ActionKeyWith, BotOperationUpdate, Settings, WaitAfterBuffered,
+    array::Array,
     bridge::{KeyKind, LinkKeyKind},
     control::{BotAction, CommandKind, ControlEvent, DiscordBot},
     ecs::{Resources, World},
-    player::{Chat, ChattingContent, Key, PlayerAction},
+    models::is_undecrypted_secret,
+    player::{Chat, ChattingContent, Key, Player, PlayerAction},
     services::EventHandler,
 };
 
@@ -49,7 +51,9 @@ impl ControlService for DefaultControlService {
     }
 
     fn update(&mut self, settings: &Settings) {
-        if !settings.discord_bot_access_token.is_empty() {
+        if !settings.discord_bot_access_token.is_empty()
+            && !is_undecrypted_secret(&settings.discord_bot_access_token)
+        {
             let _ = self.bot.start(settings.discord_bot_access_token.clone());
         }
     }
@@ -99,6 +103,19 @@ impl EventHandler<ControlEvent> for ControlEventHandler {
                     go_to_town,
                 );
             }
+            CommandKind::Finish => {
+                let _ = event.sender.send(
+                    EditInteractionResponse::new()
+                        .content("Bot will stop after the current action."),
+                );
+                context.operation_service.apply(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    &context.settings_service.settings(),
+                    BotOperationUpdate::SoftHalt,
+                );
+            }
             CommandKind::Suspend => {
                 let _ = event
                     .sender
@@ -150,34 +167,42 @@ impl EventHandler<ControlEvent> for ControlEventHandler {
                         key: context.world.player.context.config.jump_key,
                         key_hold_ticks: 0,
                         key_hold_buffered_to_wait_after: false,
+                        key_hold_moving: false,
                         link_key: LinkKeyKind::None,
+                        link_key_chain: Array::new(),
                         count,
                         position: None,
                         direction: ActionKeyDirection::Any, // Must always be Any
                         with: ActionKeyWith::Any,           // Must always be Any
+                        double_jump_apex_ticks: 0,
                         wait_before_use_ticks: 0,
                         wait_before_use_ticks_random_range: 5,
                         wait_after_use_ticks: 15,
                         wait_after_use_ticks_random_range: 0,
                         wait_after_buffered: WaitAfterBuffered::None,
+                        cast_verification: None,
                     }),
                     BotAction::DoubleJump => {
                         PlayerAction::Key(Key {
                             key: context.world.player.context.config.jump_key,
                             key_hold_ticks: 0,
                             key_hold_buffered_to_wait_after: false,
+                            key_hold_moving: false,
                             link_key: LinkKeyKind::Before(
                                 context.world.player.context.config.jump_key,
                             ),
+                            link_key_chain: Array::new(),
                             count,
                             position: None,
                             direction: ActionKeyDirection::Any, // Must always be Any
                             with: ActionKeyWith::Any,           // Must always be Any
+                            double_jump_apex_ticks: 0,
                             wait_before_use_ticks: 0,
                             wait_before_use_ticks_random_range: 0,
                             wait_after_use_ticks: 0,
                             wait_after_use_ticks_random_range: 55,
                             wait_after_buffered: WaitAfterBuffered::None,
+                            cast_verification: None,
                         })
                     }
                     BotAction::Crouch => {
@@ -185,16 +210,20 @@ impl EventHandler<ControlEvent> for ControlEventHandler {
                             key: KeyKind::Down,
                             key_hold_ticks: 4,
                             key_hold_buffered_to_wait_after: false,
+                            key_hold_moving: false,
                             link_key: LinkKeyKind::None,
+                            link_key_chain: Array::new(),
                             count,
                             position: None,
                             direction: ActionKeyDirection::Any, // Must always be Any
                             with: ActionKeyWith::Any,           // Must always be Any
+                            double_jump_apex_ticks: 0,
                             wait_before_use_ticks: 0,
                             wait_before_use_ticks_random_range: 0,
                             wait_after_use_ticks: 10,
                             wait_after_use_ticks_random_range: 0,
                             wait_after_buffered: WaitAfterBuffered::None,
+                            cast_verification: None,
                         })
                     }
                 };
@@ -206,6 +235,22 @@ impl EventHandler<ControlEvent> for ControlEventHandler {
                         action.get_message().expect("has message")
                     )));
             }
+            CommandKind::RuneArrows { keys } => {
+                let player = &mut context.world.player;
+                if !matches!(&player.state, Player::SolvingRune(solving) if solving.is_waiting_for_keys())
+                {
+                    let _ = event.sender.send(
+                        EditInteractionResponse::new()
+                            .content("Bot is not waiting for rune arrows."),
+                    );
+                    return;
+                }
+
+                player.context.submit_rune_keys(keys);
+                let _ = event
+                    .sender
+                    .send(EditInteractionResponse::new().content("Submitted rune arrows."));
+            }
         }
     }
 }
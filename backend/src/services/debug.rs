@@ -19,9 +19,11 @@ use crate::{
     DebugState,
     debug::save_minimap_for_training,
     detect::{ArrowsCalibrating, ArrowsState, DefaultDetector, Detector},
-    ecs::Resources,
+    ecs::{Resources, World},
     mat::OwnedMat,
     models::Localization,
+    player::{PlayerKind, next_states, planned_transition},
+    rotator::Rotator,
     utils::{self, DatasetDir},
 };
 
@@ -45,7 +47,7 @@ impl Default for DebugService {
 }
 
 impl DebugService {
-    pub fn poll(&mut self, resources: &Resources) {
+    pub fn poll(&mut self, resources: &Resources, world: &World, rotator: &dyn Rotator) {
         if let Some(id) = self.recording_id.clone() {
             utils::save_image_to(
                 &resources.detector().mat(),
@@ -81,6 +83,15 @@ impl DebugService {
             let _ = self.state.send(DebugState {
                 is_recording: self.recording_id.is_some(),
                 is_rune_auto_saving: resources.debug.auto_save_rune(),
+                is_stepping: resources.debug.is_stepping(),
+                tick: resources.tick,
+                player_state: world.player.state.to_string(),
+                planned_transition: planned_transition(&world.player),
+                next_states: next_states(PlayerKind::from(&world.player.state))
+                    .iter()
+                    .map(|kind| kind.to_string())
+                    .collect(),
+                queued_actions: rotator.queued_actions(),
             });
         }
     }
@@ -93,6 +104,21 @@ impl DebugService {
         resources.debug.set_auto_save_rune(auto_save);
     }
 
+    /// Pauses or resumes the world systems ticking.
+    ///
+    /// When paused, held keys are released so the player does not keep moving.
+    pub fn set_stepping(&self, resources: &Resources, stepping: bool) {
+        if stepping {
+            resources.input.send_all_keys_up();
+        }
+        resources.debug.set_stepping(stepping);
+    }
+
+    /// Advances the paused world systems by `ticks`.
+    pub fn step(&self, resources: &Resources, ticks: u32) {
+        resources.debug.step(ticks);
+    }
+
     pub fn record_images(&mut self, start: bool) {
         self.recording_id = if start {
             Some(Alphanumeric.sample_string(&mut rand::rng(), 8))
@@ -0,0 +1,127 @@
+use std::{
+    fmt::Debug,
+    time::{Duration, Instant},
+};
+
+use log::{error, info};
+
+use super::EventContext;
+use crate::{
+    MapFailover,
+    database::query_maps,
+    ecs::Resources,
+    minimap::Minimap,
+    services::{Event, EventHandler, map::preset_for_map},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailoverEvent {
+    SwitchMap(i64),
+}
+
+impl Event for FailoverEvent {}
+
+/// A service to move between farming maps of [`MapFailover`] when the current map is contested.
+pub trait FailoverService: Debug {
+    /// Polls for any pending [`FailoverEvent`] from `failover`.
+    ///
+    /// `map_id` is the id of the map currently in use.
+    fn poll(
+        &mut self,
+        resources: &Resources,
+        minimap_state: Minimap,
+        map_id: Option<i64>,
+        failover: &MapFailover,
+    ) -> Option<FailoverEvent>;
+}
+
+#[derive(Debug, Default)]
+pub struct DefaultFailoverService {
+    /// The id of the map [`Self::contested_since`] is tracked for.
+    map_id: Option<i64>,
+    /// The instant other players started staying in the current map.
+    contested_since: Option<Instant>,
+}
+
+impl FailoverService for DefaultFailoverService {
+    fn poll(
+        &mut self,
+        resources: &Resources,
+        minimap_state: Minimap,
+        map_id: Option<i64>,
+        failover: &MapFailover,
+    ) -> Option<FailoverEvent> {
+        if !failover.enabled || resources.operation.halting() || self.map_id != map_id {
+            self.map_id = map_id;
+            self.contested_since = None;
+            return None;
+        }
+        let Minimap::Idle(idle) = minimap_state else {
+            return None;
+        };
+        if !idle.has_any_other_player() {
+            self.contested_since = None;
+            return None;
+        }
+
+        let contested_since = *self.contested_since.get_or_insert_with(Instant::now);
+        if contested_since.elapsed() < Duration::from_millis(failover.contested_millis) {
+            return None;
+        }
+
+        self.contested_since = None;
+        next_failover_map_id(&failover.map_ids, map_id).map(FailoverEvent::SwitchMap)
+    }
+}
+
+pub struct FailoverEventHandler;
+
+impl EventHandler<FailoverEvent> for FailoverEventHandler {
+    fn handle(&mut self, context: &mut EventContext<'_>, event: FailoverEvent) {
+        match event {
+            FailoverEvent::SwitchMap(id) => {
+                let map = query_maps()
+                    .ok()
+                    .and_then(|maps| maps.into_iter().find(|map| map.id == Some(id)));
+                let Some(map) = map else {
+                    error!(target: "failover", "failover map {id} not found");
+                    return;
+                };
+
+                info!(target: "failover", "map contested, moving to map {}", map.name);
+                let preset = preset_for_map(&map, context.map_service.preset());
+                context.ui_service.queue_update_map(preset, Some(map));
+            }
+        }
+    }
+}
+
+/// Finds the map id after `map_id` in `map_ids`, cycling back to the first.
+///
+/// Returns the first map id if `map_id` is not in `map_ids` and [`None`] if there is no other map
+/// to move to.
+fn next_failover_map_id(map_ids: &[i64], map_id: Option<i64>) -> Option<i64> {
+    let next = map_id
+        .and_then(|id| map_ids.iter().position(|map_id| *map_id == id))
+        .map(|index| (index + 1) % map_ids.len())
+        .unwrap_or_default();
+
+    map_ids.get(next).copied().filter(|id| Some(*id) != map_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_failover_map_id_cycles() {
+        let map_ids = [3, 1, 2];
+
+        assert_eq!(next_failover_map_id(&map_ids, Some(3)), Some(1));
+        assert_eq!(next_failover_map_id(&map_ids, Some(2)), Some(3));
+        assert_eq!(next_failover_map_id(&map_ids, Some(5)), Some(3));
+        assert_eq!(next_failover_map_id(&map_ids, None), Some(3));
+        assert_eq!(next_failover_map_id(&[3], Some(3)), None);
+        assert_eq!(next_failover_map_id(&[], Some(3)), None);
+    }
+}
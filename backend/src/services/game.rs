@@ -1,9 +1,11 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    time::{Duration, Instant},
+};
 
-use log::debug;
+use log::{debug, info};
 #[cfg(test)]
 use mockall::{automock, concretize};
-use opencv::core::{MatTraitConst, MatTraitConstManual, Rect, Vec4b};
 use tokio::{
     spawn,
     sync::broadcast::{self, Receiver, Sender},
@@ -12,13 +14,15 @@ use tokio::{
 use super::EventContext;
 use crate::{
     BotOperation, BotOperationUpdate, BoundQuadrant, Character, DatabaseEvent, GameState,
-    KeyBinding, KeyBindingConfiguration, Localization, Map, Settings,
-    bridge::InputReceiver,
+    InputMethod, KeyBinding, KeyBindingConfiguration, Localization, Map, PauseOnInput, Settings,
+    bridge::{InputReceiver, KeyKind},
+    database::query_or_upsert_localization,
     database_event_receiver,
     ecs::{Resources, World},
     minimap::Minimap,
     operation::Operation,
     player::Quadrant,
+    preview::publish_preview_frame,
     services::{Event, EventHandler},
     skill::SkillKind,
 };
@@ -27,11 +31,16 @@ use crate::{
 #[allow(clippy::large_enum_variant)]
 pub enum GameEvent {
     ToggleOperation,
+    Panic,
+    /// Pauses or resumes the world systems from physical input per [`Settings::pause_on_input`].
+    InputPaused(bool),
     MapUpdated(Option<Map>),
     CharacterUpdated(Option<Character>),
     SettingsUpdated(Settings),
     LocalizationUpdated(Localization),
+    LocalizationDeleted(i64),
     NavigationPathsUpdated,
+    KeyTemplatesUpdated,
 }
 
 impl Event for GameEvent {}
@@ -52,7 +61,13 @@ pub trait GameService: Debug {
 
     /// Broadcasts game state to listeners.
     #[cfg_attr(test, concretize)]
-    fn broadcast_state(&self, resources: &Resources, world: &World, map: Option<&Map>);
+    fn broadcast_state(
+        &self,
+        resources: &Resources,
+        world: &World,
+        map: Option<&Map>,
+        character_id: Option<i64>,
+    );
 
     /// Subscribes to game state.
     fn subscribe_state(&self) -> Receiver<GameState>;
@@ -61,18 +76,35 @@ pub trait GameService: Debug {
     fn subscribe_key(&self) -> Receiver<KeyBinding>;
 }
 
+/// The maximum milliseconds between two presses of the panic key for it to trigger.
+const PANIC_KEY_DOUBLE_PRESS_MILLIS: u128 = 500;
+
 #[derive(Debug)]
 pub struct DefaultGameService {
     input_rx: Box<dyn InputReceiver>,
+    /// Receives keys even when the game or this application is not focused.
+    global_input_rx: Box<dyn InputReceiver>,
+    /// The instant the panic key was first pressed, waiting for the confirming press.
+    panic_key_pressed_instant: Option<Instant>,
+    /// The last physical input made in the game.
+    last_physical_input: Option<Instant>,
+    /// The keys last set to not count as physical input.
+    physical_input_ignored_keys: Vec<KeyKind>,
+    input_paused: bool,
     key_tx: Sender<KeyBinding>,
     database_event_rx: Receiver<DatabaseEvent>,
     game_state_tx: Sender<GameState>,
 }
 
 impl DefaultGameService {
-    pub fn new(input_rx: impl InputReceiver) -> Self {
+    pub fn new(input_rx: impl InputReceiver, global_input_rx: impl InputReceiver) -> Self {
         Self {
             input_rx: Box::new(input_rx),
+            global_input_rx: Box::new(global_input_rx),
+            panic_key_pressed_instant: None,
+            last_physical_input: None,
+            physical_input_ignored_keys: Vec::new(),
+            input_paused: false,
             key_tx: broadcast::channel(1).0,
             database_event_rx: database_event_receiver(),
             game_state_tx: broadcast::channel(1).0,
@@ -88,9 +120,15 @@ impl GameService for DefaultGameService {
         character_id: Option<i64>,
     ) -> Vec<GameEvent> {
         let mut events = Vec::new();
+        if let Some(event) = poll_panic_key(self, settings) {
+            events.push(event);
+        }
         if let Some(event) = poll_key(self, settings) {
             events.push(event);
         }
+        if let Some(event) = poll_physical_input(self, settings) {
+            events.push(event);
+        }
         if let Some(event) = poll_database(self, map_id, character_id) {
             events.push(event);
         }
@@ -102,7 +140,13 @@ impl GameService for DefaultGameService {
     }
 
     #[cfg_attr(test, concretize)]
-    fn broadcast_state(&self, resources: &Resources, world: &World, map_data: Option<&Map>) {
+    fn broadcast_state(
+        &self,
+        resources: &Resources,
+        world: &World,
+        map_data: Option<&Map>,
+        character_id: Option<i64>,
+    ) {
         if self.game_state_tx.is_empty() {
             let position = world
                 .player
@@ -111,6 +155,8 @@ impl GameService for DefaultGameService {
                 .map(|pos| (pos.x, pos.y));
             let state = world.player.state.to_string();
             let health = world.player.context.health();
+            let exp_rates = world.player.context.exp_rates_per_hour(resources.tick);
+            let exp_rate_dropped = world.player.context.exp_rate_dropped();
             let normal_action = world.player.context.normal_action_name();
             let priority_action = world.player.context.priority_action_name();
             let erda_shower_state = world.skills[SkillKind::ErdaShower].state.to_string();
@@ -126,6 +172,18 @@ impl GameService for DefaultGameService {
                         .collect::<Vec<_>>()
                 })
                 .unwrap_or_default();
+            let position_trail = world
+                .player
+                .context
+                .position_trail_visit_counts()
+                .into_iter()
+                .map(|(point, count)| (point.x, point.y, count))
+                .collect::<Vec<_>>();
+            let stuck_pattern = world
+                .player
+                .context
+                .last_stuck_pattern()
+                .map(|pattern| pattern.to_string());
             let operation = match resources.operation {
                 Operation::HaltUntil { instant, .. } => BotOperation::HaltUntil(instant),
                 Operation::TemporaryHalting { resume, .. } => {
@@ -134,6 +192,7 @@ impl GameService for DefaultGameService {
                 Operation::Halting => BotOperation::Halting,
                 Operation::Running => BotOperation::Running,
                 Operation::RunUntil { instant, .. } => BotOperation::RunUntil(instant),
+                Operation::Stopping => BotOperation::Stopping,
             };
             let idle = if let Minimap::Idle(idle) = world.minimap.state {
                 Some(idle)
@@ -165,6 +224,7 @@ impl GameService for DefaultGameService {
                     Quadrant::BottomRight => BoundQuadrant::BottomRight,
                     Quadrant::BottomLeft => BoundQuadrant::BottomLeft,
                 });
+            let map_id = map_data.and_then(|data| data.id);
             let detector = if resources.detector.is_some() {
                 Some(resources.detector_cloned())
             } else {
@@ -173,24 +233,28 @@ impl GameService for DefaultGameService {
             let sender = self.game_state_tx.clone();
 
             spawn(async move {
-                let frame = if let Some((detector, idle)) = detector.zip(idle) {
-                    Some(minimap_frame_from(idle.bbox, &detector.mat()))
-                } else {
-                    None
-                };
+                let frame = detector.zip(idle).and_then(|(detector, idle)| {
+                    publish_preview_frame(&detector.mat(), idle.bbox).ok()
+                });
                 let game_state = GameState {
                     position,
                     health,
+                    exp_rates,
+                    exp_rate_dropped,
                     state,
                     normal_action,
                     priority_action,
                     erda_shower_state,
                     destinations,
+                    position_trail,
+                    stuck_pattern,
                     operation,
                     frame,
                     platforms_bound,
                     portals,
                     auto_mob_quadrant,
+                    map_id,
+                    character_id,
                 };
                 let _ = sender.send(game_state);
             });
@@ -225,6 +289,27 @@ impl EventHandler<GameEvent> for GameEventHandler {
                     update,
                 );
             }
+            GameEvent::Panic => {
+                let go_to_town = context.settings_service.settings().panic_go_to_town;
+                info!(target: "handler", "panic key pressed, halting with go to town {go_to_town}");
+
+                context.resources.input.send_all_keys_up();
+                context.operation_service.halt(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    go_to_town,
+                );
+            }
+            GameEvent::InputPaused(paused) => {
+                if paused {
+                    info!(target: "handler", "physical input detected, pausing");
+                    context.resources.input.send_all_keys_up();
+                } else {
+                    info!(target: "handler", "no more physical input, resuming");
+                }
+                context.resources.input_paused = paused;
+            }
             GameEvent::MapUpdated(map) => context
                 .ui_service
                 .queue_update_map(context.map_service.preset(), map),
@@ -242,36 +327,48 @@ impl EventHandler<GameEvent> for GameEventHandler {
                 );
 
                 context.control_service.update(&settings_service.settings());
+                context.metrics_service.update(&settings_service.settings());
                 context.rotator_service.apply(
                     context.rotator,
                     context.map_service.map(),
                     context.character_service.character(),
                     &settings_service.settings(),
                 );
+
+                let localization_id = settings_service.settings().localization_id;
+                if localization_id.is_some()
+                    && localization_id != context.localization_service.localization_id()
+                {
+                    info!(target: "handler", "switching localization profile to {localization_id:?}");
+                    context
+                        .localization_service
+                        .update_localization(query_or_upsert_localization(localization_id));
+                }
+            }
+            GameEvent::LocalizationUpdated(localization) => {
+                if localization.id == context.localization_service.localization_id() {
+                    context
+                        .localization_service
+                        .update_localization(localization);
+                }
+            }
+            GameEvent::LocalizationDeleted(id) => {
+                if Some(id) == context.localization_service.localization_id() {
+                    let localization_id = context.settings_service.settings().localization_id;
+                    context
+                        .localization_service
+                        .update_localization(query_or_upsert_localization(localization_id));
+                }
             }
-            GameEvent::LocalizationUpdated(localization) => context
-                .localization_service
-                .update_localization(localization),
             GameEvent::NavigationPathsUpdated => context.navigator.mark_dirty(true),
+            GameEvent::KeyTemplatesUpdated => context.ui_service.queue_update_map(
+                context.map_service.preset(),
+                context.map_service.map().cloned(),
+            ),
         }
     }
 }
 
-#[inline]
-fn minimap_frame_from(bbox: Rect, mat: &impl MatTraitConst) -> (Vec<u8>, usize, usize) {
-    let minimap = mat
-        .roi(bbox)
-        .unwrap()
-        .iter::<Vec4b>()
-        .unwrap()
-        .flat_map(|bgra| {
-            let bgra = bgra.1;
-            [bgra[2], bgra[1], bgra[0], 255]
-        })
-        .collect::<Vec<u8>>();
-    (minimap, bbox.width as usize, bbox.height as usize)
-}
-
 // TODO: should only handle a single matched key binding
 #[inline]
 fn poll_key(service: &mut DefaultGameService, settings: &Settings) -> Option<GameEvent> {
@@ -286,6 +383,70 @@ fn poll_key(service: &mut DefaultGameService, settings: &Settings) -> Option<Gam
     None
 }
 
+/// Polls for physical input to pause or resume the world systems.
+///
+/// The world systems are paused while there was physical input in the game within
+/// [`PauseOnInput::grace_millis`]. Pressing the toggle or panic key does not pause.
+#[inline]
+fn poll_physical_input(service: &mut DefaultGameService, settings: &Settings) -> Option<GameEvent> {
+    let PauseOnInput {
+        enabled,
+        grace_millis,
+    } = settings.pause_on_input;
+    // Hardware inputs through RPC (e.g. KMBox, Arduino) cannot be told apart from physical ones
+    let enabled = enabled && !matches!(settings.input_method, InputMethod::Rpc);
+    let ignored_keys = [settings.toggle_actions_key, settings.panic_key]
+        .into_iter()
+        .filter(|key| key.enabled)
+        .map(|key| KeyKind::from(key.key))
+        .collect::<Vec<_>>();
+    if ignored_keys != service.physical_input_ignored_keys {
+        service
+            .input_rx
+            .set_physical_input_ignored_keys(ignored_keys.clone());
+        service.physical_input_ignored_keys = ignored_keys;
+    }
+
+    if let Some(instant) = service.input_rx.last_physical_input() {
+        service.last_physical_input = Some(instant);
+    }
+    let paused = enabled
+        && service
+            .last_physical_input
+            .is_some_and(|instant| instant.elapsed() < Duration::from_millis(grace_millis));
+    if paused == service.input_paused {
+        return None;
+    }
+
+    service.input_paused = paused;
+    Some(GameEvent::InputPaused(paused))
+}
+
+/// Polls the panic key from the global receiver.
+///
+/// The panic key must be pressed twice within [`PANIC_KEY_DOUBLE_PRESS_MILLIS`] to avoid
+/// accidentally halting from a single stray key press.
+#[inline]
+fn poll_panic_key(service: &mut DefaultGameService, settings: &Settings) -> Option<GameEvent> {
+    let received_key = service.global_input_rx.try_recv().ok()?;
+    let KeyBindingConfiguration { key, enabled: true } = settings.panic_key else {
+        return None;
+    };
+    if key != received_key.into() {
+        return None;
+    }
+
+    match service.panic_key_pressed_instant.take() {
+        Some(instant) if instant.elapsed().as_millis() <= PANIC_KEY_DOUBLE_PRESS_MILLIS => {
+            Some(GameEvent::Panic)
+        }
+        _ => {
+            service.panic_key_pressed_instant = Some(Instant::now());
+            None
+        }
+    }
+}
+
 #[inline]
 fn poll_database(
     service: &mut DefaultGameService,
@@ -316,6 +477,9 @@ fn poll_database(
         DatabaseEvent::LocalizationUpdated(localization) => {
             return Some(GameEvent::LocalizationUpdated(localization));
         }
+        DatabaseEvent::LocalizationDeleted(deleted_id) => {
+            return Some(GameEvent::LocalizationDeleted(deleted_id));
+        }
         DatabaseEvent::CharacterUpdated(character) => {
             let updated_id = character
                 .id
@@ -329,7 +493,101 @@ fn poll_database(
                 return Some(GameEvent::CharacterUpdated(None));
             }
         }
+        DatabaseEvent::KeyTemplateUpdated(_) | DatabaseEvent::KeyTemplateDeleted(_) => {
+            return Some(GameEvent::KeyTemplatesUpdated);
+        }
     }
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use super::*;
+    use crate::bridge::MockInputReceiver;
+
+    fn pause_on_input_settings() -> Settings {
+        Settings {
+            pause_on_input: PauseOnInput {
+                enabled: true,
+                grace_millis: 1000,
+            },
+            ..Settings::default()
+        }
+    }
+
+    fn game_service(last_physical_input: Option<Instant>) -> DefaultGameService {
+        let mut input_rx = MockInputReceiver::default();
+        input_rx
+            .expect_last_physical_input()
+            .return_const(last_physical_input);
+        input_rx
+            .expect_set_physical_input_ignored_keys()
+            .return_const(());
+
+        DefaultGameService::new(input_rx, MockInputReceiver::default())
+    }
+
+    #[test]
+    fn poll_physical_input_pauses_once_on_recent_input() {
+        let settings = pause_on_input_settings();
+        let mut service = game_service(Some(Instant::now()));
+
+        assert_matches!(
+            poll_physical_input(&mut service, &settings),
+            Some(GameEvent::InputPaused(true))
+        );
+        assert_matches!(poll_physical_input(&mut service, &settings), None);
+    }
+
+    #[test]
+    fn poll_physical_input_resumes_after_grace_period() {
+        let settings = pause_on_input_settings();
+        let mut service = game_service(Some(Instant::now() - Duration::from_millis(2000)));
+        service.input_paused = true;
+
+        assert_matches!(
+            poll_physical_input(&mut service, &settings),
+            Some(GameEvent::InputPaused(false))
+        );
+    }
+
+    #[test]
+    fn poll_physical_input_ignored_with_rpc_input_method() {
+        let settings = Settings {
+            input_method: InputMethod::Rpc,
+            ..pause_on_input_settings()
+        };
+        let mut service = game_service(Some(Instant::now()));
+
+        assert_matches!(poll_physical_input(&mut service, &settings), None);
+    }
+
+    #[test]
+    fn poll_physical_input_ignores_enabled_toggle_and_panic_keys() {
+        let settings = Settings {
+            toggle_actions_key: KeyBindingConfiguration {
+                key: KeyBinding::Comma,
+                enabled: true,
+            },
+            panic_key: KeyBindingConfiguration {
+                key: KeyBinding::F12,
+                enabled: false,
+            },
+            ..pause_on_input_settings()
+        };
+        let mut input_rx = MockInputReceiver::default();
+        input_rx.expect_last_physical_input().return_const(None);
+        input_rx
+            .expect_set_physical_input_ignored_keys()
+            .withf(|keys| keys == &[KeyKind::Comma])
+            .once()
+            .return_const(());
+        let mut service = DefaultGameService::new(input_rx, MockInputReceiver::default());
+
+        assert_matches!(poll_physical_input(&mut service, &settings), None);
+        assert_matches!(poll_physical_input(&mut service, &settings), None);
+    }
+}
@@ -0,0 +1,205 @@
+use std::{collections::VecDeque, fmt::Debug};
+
+use log::info;
+
+use crate::{GridLayout, bridge::MouseKind, ecs::Resources, tick::ticks_from_millis};
+
+/// The number of mouse moves from the source point to the target cell while dragging.
+const DRAG_MOVES_COUNT: i32 = 4;
+
+/// A service to fill a [`GridLayout`] by mouse drags.
+pub trait GridService: Debug {
+    /// Whether a [`GridLayout`] is being filled.
+    fn running(&self) -> bool;
+
+    /// Starts filling `layout`, replacing any layout currently being filled.
+    fn start(&mut self, resources: &Resources, layout: &GridLayout);
+
+    /// Stops filling the current layout and releases the mouse if it is held.
+    fn stop(&mut self, resources: &Resources);
+
+    /// Sends the next mouse input of the current layout if due.
+    ///
+    /// Filling is stopped once the bot is no longer halting.
+    fn update(&mut self, resources: &Resources);
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct GridStep {
+    x: i32,
+    y: i32,
+    kind: MouseKind,
+}
+
+#[derive(Debug, Default)]
+pub struct DefaultGridService {
+    steps: VecDeque<GridStep>,
+    step_ticks: u32,
+    wait_ticks: u32,
+    /// The point the left mouse button is currently held at.
+    held_at: Option<(i32, i32)>,
+}
+
+impl GridService for DefaultGridService {
+    fn running(&self) -> bool {
+        !self.steps.is_empty()
+    }
+
+    fn start(&mut self, resources: &Resources, layout: &GridLayout) {
+        self.stop(resources);
+        self.steps = grid_steps(layout).into();
+        self.step_ticks = ticks_from_millis(layout.step_millis).max(1);
+        info!(target: "grid", "filling {} grid placements", layout.placements.len());
+    }
+
+    fn stop(&mut self, resources: &Resources) {
+        if let Some((x, y)) = self.held_at.take() {
+            resources.input.send_mouse(x, y, MouseKind::Up);
+        }
+        self.steps.clear();
+        self.wait_ticks = 0;
+    }
+
+    fn update(&mut self, resources: &Resources) {
+        if !self.running() {
+            return;
+        }
+        if !resources.operation.halting() {
+            info!(target: "grid", "grid filling stopped because bot is running");
+            return self.stop(resources);
+        }
+        if self.wait_ticks > 0 {
+            self.wait_ticks -= 1;
+            return;
+        }
+
+        let step = self.steps.pop_front().expect("running");
+        resources.input.send_mouse(step.x, step.y, step.kind);
+        match step.kind {
+            MouseKind::Down => self.held_at = Some((step.x, step.y)),
+            MouseKind::Up => self.held_at = None,
+            MouseKind::Move | MouseKind::Click | MouseKind::Scroll => (),
+        }
+        self.wait_ticks = self.step_ticks - 1;
+
+        if !self.running() {
+            info!(target: "grid", "grid filling completed");
+        }
+    }
+}
+
+/// Expands `layout` into the mouse inputs dragging each placement onto its cell center.
+fn grid_steps(layout: &GridLayout) -> Vec<GridStep> {
+    let step = |x, y, kind| GridStep { x, y, kind };
+    let mut steps = Vec::new();
+
+    for placement in &layout.placements {
+        let (from_x, from_y) = (placement.from_x, placement.from_y);
+        let to_x = layout.x + placement.column as i32 * layout.cell_width + layout.cell_width / 2;
+        let to_y = layout.y + placement.row as i32 * layout.cell_height + layout.cell_height / 2;
+
+        steps.push(step(from_x, from_y, MouseKind::Move));
+        steps.push(step(from_x, from_y, MouseKind::Down));
+        for i in 1..=DRAG_MOVES_COUNT {
+            let x = from_x + (to_x - from_x) * i / DRAG_MOVES_COUNT;
+            let y = from_y + (to_y - from_y) * i / DRAG_MOVES_COUNT;
+            steps.push(step(x, y, MouseKind::Move));
+        }
+        steps.push(step(to_x, to_y, MouseKind::Up));
+    }
+
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use mockall::{Sequence, predicate::eq};
+
+    use super::*;
+    use crate::{GridPlacement, bridge::MockInput, operation::Operation};
+
+    fn layout() -> GridLayout {
+        GridLayout {
+            x: 100,
+            y: 50,
+            cell_width: 10,
+            cell_height: 20,
+            step_millis: 0,
+            placements: vec![GridPlacement {
+                from_x: 20,
+                from_y: 10,
+                column: 2,
+                row: 1,
+            }],
+        }
+    }
+
+    #[test]
+    fn grid_steps_drags_to_cell_center() {
+        let steps = grid_steps(&layout());
+
+        assert_eq!(steps.len(), 2 + DRAG_MOVES_COUNT as usize + 1);
+        assert_eq!(
+            steps[0],
+            GridStep {
+                x: 20,
+                y: 10,
+                kind: MouseKind::Move
+            }
+        );
+        assert_eq!(
+            steps[1],
+            GridStep {
+                x: 20,
+                y: 10,
+                kind: MouseKind::Down
+            }
+        );
+        assert_eq!(
+            steps[2],
+            GridStep {
+                x: 46,
+                y: 27,
+                kind: MouseKind::Move
+            }
+        );
+        assert_eq!(
+            steps[steps.len() - 1],
+            GridStep {
+                x: 125,
+                y: 80,
+                kind: MouseKind::Up
+            }
+        );
+    }
+
+    #[test]
+    fn update_releases_held_mouse_when_bot_runs() {
+        let mut sequence = Sequence::new();
+        let mut keys = MockInput::new();
+        keys.expect_send_mouse()
+            .with(eq(20), eq(10), eq(MouseKind::Move))
+            .times(1)
+            .in_sequence(&mut sequence);
+        keys.expect_send_mouse()
+            .with(eq(20), eq(10), eq(MouseKind::Down))
+            .times(1)
+            .in_sequence(&mut sequence);
+        keys.expect_send_mouse()
+            .with(eq(20), eq(10), eq(MouseKind::Up))
+            .times(1)
+            .in_sequence(&mut sequence);
+        let mut resources = Resources::new(Some(keys), None);
+        resources.operation = Operation::Halting;
+        let mut service = DefaultGridService::default();
+
+        service.start(&resources, &layout());
+        service.update(&resources);
+        service.update(&resources);
+        assert!(service.running());
+
+        resources.operation = Operation::Running;
+        service.update(&resources);
+        assert!(!service.running());
+    }
+}
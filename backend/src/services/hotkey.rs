@@ -0,0 +1,167 @@
+use std::fmt::Debug;
+
+use log::{error, info, warn};
+
+use super::EventContext;
+use crate::{
+    Hotkey, HotkeyKind, KeyBinding, Map, RotationMode,
+    bridge::InputReceiver,
+    capability::{Capability, is_capability_available},
+    database::query_characters,
+    player::PlayerAction,
+    services::{Event, EventHandler},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HotkeyEvent {
+    SwitchCharacter(i64),
+    ToggleAutoMobbing,
+    SolveRune,
+}
+
+impl Event for HotkeyEvent {}
+
+/// A service to listen for global [`Hotkey`]s.
+pub trait HotkeyService: Debug {
+    /// Polls for any pending [`HotkeyEvent`] from `hotkeys`.
+    fn poll(&mut self, hotkeys: &[Hotkey]) -> Option<HotkeyEvent>;
+}
+
+#[derive(Debug)]
+pub struct DefaultHotkeyService {
+    /// Receives keys even when the game or this application is not focused.
+    global_input_rx: Box<dyn InputReceiver>,
+}
+
+impl DefaultHotkeyService {
+    pub fn new(global_input_rx: impl InputReceiver) -> Self {
+        Self {
+            global_input_rx: Box::new(global_input_rx),
+        }
+    }
+}
+
+impl HotkeyService for DefaultHotkeyService {
+    fn poll(&mut self, hotkeys: &[Hotkey]) -> Option<HotkeyEvent> {
+        let received_key = self.global_input_rx.try_recv().ok()?;
+
+        hotkey_event_from(hotkeys, received_key.into())
+    }
+}
+
+#[derive(Default)]
+pub struct HotkeyEventHandler {
+    /// The rotation mode of the current map before toggling to auto-mobbing.
+    rotation_mode_before_auto_mobbing: Option<RotationMode>,
+}
+
+impl EventHandler<HotkeyEvent> for HotkeyEventHandler {
+    fn handle(&mut self, context: &mut EventContext<'_>, event: HotkeyEvent) {
+        match event {
+            HotkeyEvent::SwitchCharacter(id) => {
+                if context
+                    .character_service
+                    .character()
+                    .is_some_and(|character| character.id == Some(id))
+                {
+                    return;
+                }
+
+                let character = query_characters().ok().and_then(|characters| {
+                    characters
+                        .into_iter()
+                        .find(|character| character.id == Some(id))
+                });
+                let Some(character) = character else {
+                    error!(target: "hotkey", "hotkey character {id} not found");
+                    return;
+                };
+
+                info!(target: "hotkey", "switching to character {} on hotkey", character.name);
+                context.ui_service.queue_update_character(Some(character));
+            }
+            HotkeyEvent::ToggleAutoMobbing => {
+                let Some(map) = context.map_service.map().cloned() else {
+                    return;
+                };
+                let rotation_mode = if matches!(map.rotation_mode, RotationMode::AutoMobbing) {
+                    self.rotation_mode_before_auto_mobbing
+                        .take()
+                        .unwrap_or_default()
+                } else {
+                    self.rotation_mode_before_auto_mobbing = Some(map.rotation_mode);
+                    RotationMode::AutoMobbing
+                };
+
+                info!(target: "hotkey", "switching rotation mode to {rotation_mode:?} on hotkey");
+                context.ui_service.queue_update_map(
+                    context.map_service.preset(),
+                    Some(Map {
+                        rotation_mode,
+                        ..map
+                    }),
+                );
+            }
+            HotkeyEvent::SolveRune => {
+                if !is_capability_available(Capability::RuneSolving) {
+                    warn!(target: "hotkey", "rune solving is unavailable, ignoring hotkey");
+                    return;
+                }
+
+                info!(target: "hotkey", "queuing rune solving on hotkey");
+                context.rotator.inject_action(PlayerAction::SolveRune);
+            }
+        }
+    }
+}
+
+/// Finds the [`HotkeyEvent`] of the first enabled hotkey in `hotkeys` bound to `key`.
+fn hotkey_event_from(hotkeys: &[Hotkey], key: KeyBinding) -> Option<HotkeyEvent> {
+    hotkeys
+        .iter()
+        .filter(|hotkey| hotkey.enabled && hotkey.key == key)
+        .find_map(|hotkey| match hotkey.kind {
+            HotkeyKind::SwitchCharacter => hotkey.character_id.map(HotkeyEvent::SwitchCharacter),
+            HotkeyKind::ToggleAutoMobbing => Some(HotkeyEvent::ToggleAutoMobbing),
+            HotkeyKind::SolveRune => Some(HotkeyEvent::SolveRune),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::{KeyKind, MockInputReceiver};
+
+    #[test]
+    fn poll_matches_enabled_hotkey() {
+        let mut input_rx = MockInputReceiver::default();
+        input_rx.expect_try_recv().returning(|| Ok(KeyKind::F1));
+        let mut service = DefaultHotkeyService::new(input_rx);
+        let hotkeys = [
+            Hotkey {
+                enabled: false,
+                key: KeyBinding::F1,
+                kind: HotkeyKind::SolveRune,
+                character_id: None,
+            },
+            Hotkey {
+                enabled: true,
+                key: KeyBinding::F1,
+                kind: HotkeyKind::SwitchCharacter,
+                character_id: None,
+            },
+            Hotkey {
+                enabled: true,
+                key: KeyBinding::F1,
+                kind: HotkeyKind::SwitchCharacter,
+                character_id: Some(2),
+            },
+        ];
+
+        assert_eq!(
+            service.poll(&hotkeys),
+            Some(HotkeyEvent::SwitchCharacter(2))
+        );
+        assert_eq!(service.poll(&hotkeys[..2]), None);
+    }
+}
@@ -19,6 +19,9 @@ pub trait LocalizationService: Debug {
     /// Retrieves the default base64-encoded PNG for template `template`.
     fn template(&self, template: GameTemplate) -> String;
 
+    /// Retrieves the id of the currently in use [`Localization`] profile.
+    fn localization_id(&self) -> Option<i64>;
+
     /// Updates the currently in use [`Localization`] with new `localization`.
     fn update_localization(&mut self, localization: Localization);
 
@@ -62,6 +65,10 @@ impl LocalizationService for DefaultLocalizationService {
         to_base64_from_mat(template).expect("convert successfully")
     }
 
+    fn localization_id(&self) -> Option<i64> {
+        self.localization.borrow().id
+    }
+
     fn update_localization(&mut self, localization: Localization) {
         *self.localization.borrow_mut() = Arc::new(localization);
     }
@@ -1,15 +1,46 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc, time::Instant};
 
+use anyhow::{Result, anyhow};
+use log::{debug, error, info};
 #[cfg(test)]
 use mockall::automock;
+use opencv::core::{Point, Rect};
 
+use super::EventContext;
 use crate::{
-    minimap::{Minimap, MinimapContext, MinimapEntity},
-    models::Map,
+    database::{query_maps, query_navigation_paths, upsert_map},
+    detect::Detector,
+    ecs::Resources,
+    minimap::{MAX_RUNE_SPAWN_HINTS_COUNT, Minimap, MinimapContext, MinimapEntity},
+    models::{Map, Platform as DatabasePlatform, PlayerMarker, RotationMode},
+    navigator::find_best_matching_paths_id_index,
     pathing::Platform,
-    player::PlayerContext,
+    player::{
+        MAX_AUTO_MOB_REACHABLE_YS_COUNT, MAX_DANGER_ZONES_COUNT, MAX_ROPES_COUNT, PlayerContext,
+    },
+    services::{Event, EventHandler, world::queue_halt_on_map_change},
+    task::{Task, Update, update_detection_task},
 };
 
+/// Seconds between each attempt to find the [`Map`] to automatically switch to.
+const AUTO_SWITCH_INTERVAL_SECS: u64 = 2;
+
+/// Maximum number of attempts to find the [`Map`] to automatically switch to.
+const AUTO_SWITCH_MAX_ATTEMPTS: u32 = 3;
+
+/// Maximum y distance for a detected platform to be considered the same as an existing platform.
+const DETECTED_PLATFORM_Y_THRESHOLD: i32 = 2;
+
+#[derive(Debug)]
+pub enum MapEvent {
+    /// The current minimap matches a navigation path linked to this [`Map`].
+    AutoSwitched(Map),
+    /// No [`Map`] linked to a navigation path matches the current minimap.
+    AutoSwitchFailed,
+}
+
+impl Event for MapEvent {}
+
 /// A service to handle map-related incoming requests.
 #[cfg_attr(test, automock)]
 pub trait MapService: Debug {
@@ -32,12 +63,52 @@ pub trait MapService: Debug {
 
     /// Re-detects current minimap.
     fn redetect(&self, minimap: &mut MinimapEntity);
+
+    /// Detects candidate platforms from currently detected minimap.
+    ///
+    /// Platforms overlapping with the ones of the currently in use [`Map`] are excluded.
+    fn detect_platforms(
+        &self,
+        resources: &Resources,
+        minimap_state: Minimap,
+    ) -> Vec<DatabasePlatform>;
+
+    /// Samples a custom [`PlayerMarker`] at `x` and `y` of currently detected minimap.
+    ///
+    /// The `y` is in bottom-left coordinate similar to the player position. The sampled marker is
+    /// not saved to the currently in use [`Map`].
+    fn sample_player_marker(
+        &self,
+        resources: &Resources,
+        minimap_state: Minimap,
+        x: i32,
+        y: i32,
+    ) -> Option<PlayerMarker>;
+
+    /// Saves the solidified auto-mobbing reachable `ys` to the currently in use [`Map`].
+    fn save_auto_mob_reachable_ys(&mut self, ys: Vec<i32>);
+
+    /// Queues an automatic switch to the [`Map`] linked to the navigation path matching the
+    /// current minimap.
+    fn queue_auto_switch(&mut self);
+
+    /// Polls for any pending [`MapEvent`].
+    fn poll(&mut self, resources: &Resources, minimap_state: Minimap) -> Option<MapEvent>;
+}
+
+/// A pending automatic map switch.
+#[derive(Debug)]
+struct AutoSwitch {
+    attempts: u32,
+    last_attempt: Option<Instant>,
+    task: Option<Task<Result<Map>>>,
 }
 
 #[derive(Debug, Default)]
 pub struct DefaultMapService {
     map: Option<Map>,
     preset: Option<String>,
+    auto_switch: Option<AutoSwitch>,
 }
 
 impl MapService for DefaultMapService {
@@ -79,6 +150,17 @@ impl MapService for DefaultMapService {
             })
             .unwrap_or_default();
         minimap_context.set_platforms(platforms);
+        minimap_context.set_rune_spawn_hints(
+            self.map()
+                .map(|data| {
+                    data.rune_spawn_hints
+                        .iter()
+                        .take(MAX_RUNE_SPAWN_HINTS_COUNT)
+                        .map(|hint| Point::new(hint.x, hint.y))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        );
 
         player_context.reset();
         if let Some(minimap) = self.map() {
@@ -91,18 +173,218 @@ impl MapService for DefaultMapService {
                 .auto_mob_platforms_pathing_up_jump_only =
                 minimap.auto_mob_platforms_pathing_up_jump_only;
             player_context.config.auto_mob_platforms_bound = minimap.auto_mob_platforms_bound;
+            player_context.config.platform_edge_margin = minimap.platform_edge_margin;
             player_context.config.auto_mob_use_key_when_pathing =
                 minimap.auto_mob_use_key_when_pathing;
             player_context
                 .config
                 .auto_mob_use_key_when_pathing_update_millis =
                 minimap.auto_mob_use_key_when_pathing_update_millis;
+            player_context.config.auto_mob_loot_pickup = minimap.auto_mob_loot_pickup;
+            player_context.config.auto_mob_loot_pickup_radius = minimap.auto_mob_loot_pickup_radius;
+            player_context.config.ping_pong_screen_fallback = minimap.rotation_mode
+                == RotationMode::PingPong
+                && !minimap.rotation_ping_pong_vertical
+                && minimap.rotation_ping_pong_screen_fallback;
+            player_context.config.can_navigate_back = minimap.paths_id_index.is_some();
+            player_context.mob_detection = Arc::new(minimap.mob_detection.clone());
+            player_context.config.ropes = minimap
+                .ropes
+                .iter()
+                .copied()
+                .take(MAX_ROPES_COUNT)
+                .collect();
+            player_context.config.danger_zones = minimap
+                .danger_zones
+                .iter()
+                .copied()
+                .take(MAX_DANGER_ZONES_COUNT)
+                .collect();
+            player_context.config.auto_mob_reachable_ys = minimap
+                .auto_mob_reachable_ys
+                .iter()
+                .copied()
+                .take(MAX_AUTO_MOB_REACHABLE_YS_COUNT)
+                .collect();
+            player_context.config.player_marker = minimap.player_marker;
         }
     }
 
     fn redetect(&self, minimap: &mut MinimapEntity) {
         minimap.state = Minimap::Detecting;
     }
+
+    fn detect_platforms(
+        &self,
+        resources: &Resources,
+        minimap_state: Minimap,
+    ) -> Vec<DatabasePlatform> {
+        let Minimap::Idle(idle) = minimap_state else {
+            return vec![];
+        };
+        let Some(detector) = resources.detector.as_ref() else {
+            return vec![];
+        };
+        let existing_platforms = self
+            .map()
+            .map(|map| map.platforms.as_slice())
+            .unwrap_or_default();
+
+        detector
+            .detect_minimap_platforms(idle.bbox)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(x_start, x_end, y)| DatabasePlatform {
+                x_start,
+                x_end,
+                y: idle.bbox.height - y,
+            })
+            .filter(|platform| {
+                !existing_platforms.iter().any(|existing| {
+                    (existing.y - platform.y).abs() <= DETECTED_PLATFORM_Y_THRESHOLD
+                        && existing.x_start <= platform.x_end
+                        && platform.x_start <= existing.x_end
+                })
+            })
+            .collect()
+    }
+
+    fn sample_player_marker(
+        &self,
+        resources: &Resources,
+        minimap_state: Minimap,
+        x: i32,
+        y: i32,
+    ) -> Option<PlayerMarker> {
+        let Minimap::Idle(idle) = minimap_state else {
+            return None;
+        };
+        let point = Point::new(x, idle.bbox.height - y);
+        if !Rect::new(0, 0, idle.bbox.width, idle.bbox.height).contains(point) {
+            return None;
+        }
+
+        resources
+            .detector
+            .as_ref()?
+            .sample_player_marker(idle.bbox, point)
+            .inspect_err(|err| info!(target: "map", "failed to sample player marker {err}"))
+            .ok()
+    }
+
+    fn save_auto_mob_reachable_ys(&mut self, ys: Vec<i32>) {
+        let Some(map) = self.map.as_mut() else {
+            return;
+        };
+        if map.auto_mob_reachable_ys == ys {
+            return;
+        }
+
+        map.auto_mob_reachable_ys = ys;
+        if map.id.is_some() {
+            let _ = upsert_map(map).inspect_err(|err| {
+                error!(target: "map", "failed to save auto mob reachable ys {err}");
+            });
+        }
+    }
+
+    fn queue_auto_switch(&mut self) {
+        self.auto_switch = Some(AutoSwitch {
+            attempts: 0,
+            last_attempt: None,
+            task: None,
+        });
+    }
+
+    fn poll(&mut self, resources: &Resources, minimap_state: Minimap) -> Option<MapEvent> {
+        let auto_switch = self.auto_switch.as_mut()?;
+        let Minimap::Idle(idle) = minimap_state else {
+            return None;
+        };
+        if resources.detector.is_none() {
+            return None;
+        }
+        let instant = Instant::now();
+        let has_pending_task = auto_switch
+            .task
+            .as_ref()
+            .is_some_and(|task| !task.completed());
+        if !has_pending_task
+            && auto_switch.last_attempt.is_some_and(|last_attempt| {
+                instant.duration_since(last_attempt).as_secs() < AUTO_SWITCH_INTERVAL_SECS
+            })
+        {
+            return None;
+        }
+
+        let minimap_bbox = idle.bbox;
+        match update_detection_task(resources, 0, &mut auto_switch.task, move |detector| {
+            find_auto_switch_map(detector.as_ref(), minimap_bbox)
+        }) {
+            Update::Ok(map) => {
+                self.auto_switch = None;
+                Some(MapEvent::AutoSwitched(map))
+            }
+            Update::Err(_) => {
+                auto_switch.attempts += 1;
+                auto_switch.last_attempt = Some(instant);
+                if auto_switch.attempts >= AUTO_SWITCH_MAX_ATTEMPTS {
+                    self.auto_switch = None;
+                    return Some(MapEvent::AutoSwitchFailed);
+                }
+
+                None
+            }
+            Update::Pending => None,
+        }
+    }
+}
+
+pub struct MapEventHandler;
+
+impl EventHandler<MapEvent> for MapEventHandler {
+    fn handle(&mut self, context: &mut EventContext<'_>, event: MapEvent) {
+        match event {
+            MapEvent::AutoSwitched(map) => {
+                info!(target: "map", "automatically switching to map {}", map.name);
+                let preset = preset_for_map(&map, context.map_service.preset());
+                context.ui_service.queue_update_map(preset, Some(map));
+            }
+            MapEvent::AutoSwitchFailed => {
+                info!(target: "map", "no map found for automatic switching");
+                queue_halt_on_map_change(context);
+            }
+        }
+    }
+}
+
+/// Finds the [`Map`] linked to the navigation path matching the current minimap at
+/// `minimap_bbox`.
+///
+/// This is expensive and should be called on a background thread.
+fn find_auto_switch_map(detector: &dyn Detector, minimap_bbox: Rect) -> Result<Map> {
+    let minimap_name_bbox = detector.detect_minimap_name(minimap_bbox)?;
+    let paths = query_navigation_paths()?;
+    let paths_id_index =
+        find_best_matching_paths_id_index(&paths, detector, minimap_bbox, minimap_name_bbox)?;
+    debug!(target: "map", "current minimap matched navigation path {paths_id_index:?}");
+
+    map_from_paths_id_index(query_maps()?, paths_id_index).ok_or(anyhow!(
+        "no map linked to navigation path {paths_id_index:?}"
+    ))
+}
+
+/// Finds the [`Map`] linked to the navigation path `paths_id_index`.
+fn map_from_paths_id_index(maps: Vec<Map>, paths_id_index: (i64, usize)) -> Option<Map> {
+    maps.into_iter()
+        .find(|map| map.paths_id_index == Some(paths_id_index))
+}
+
+/// Keeps the `current` preset if `map` has one with the same name or uses its first preset.
+pub fn preset_for_map(map: &Map, current: Option<String>) -> Option<String> {
+    current
+        .filter(|preset| map.actions.contains_key(preset))
+        .or_else(|| map.actions.keys().next().cloned())
 }
 
 #[cfg(test)]
@@ -113,7 +395,8 @@ mod tests {
 
     use super::*;
     use crate::{
-        Platform as DatabasePlatform,
+        MobDetection, MobDetectionKind, Platform as DatabasePlatform,
+        detect::MockDetector,
         minimap::{Minimap, MinimapIdle},
         pathing::Platform,
     };
@@ -133,6 +416,10 @@ mod tests {
             rune_platforms_pathing_up_jump_only: true,
             auto_mob_platforms_pathing: true,
             auto_mob_platforms_bound: true,
+            mob_detection: MobDetection {
+                kind: MobDetectionKind::ColorSignature,
+                ..MobDetection::default()
+            },
             ..Default::default()
         }
     }
@@ -155,6 +442,74 @@ mod tests {
         );
     }
 
+    #[test]
+    fn detect_platforms_excludes_existing_platforms() {
+        let mut detector = MockDetector::new();
+        detector
+            .expect_detect_minimap_platforms()
+            .returning(|_| Ok(vec![(10, 40, 20), (50, 90, 60)]));
+        let resources = Resources::new(None, Some(detector));
+        let mut service = DefaultMapService::default();
+        service.update_map_preset(
+            Some(Map {
+                platforms: vec![DatabasePlatform {
+                    x_start: 0,
+                    x_end: 20,
+                    y: 81,
+                }],
+                ..mock_minimap_data()
+            }),
+            None,
+        );
+
+        let platforms = service.detect_platforms(&resources, mock_idle_minimap());
+
+        assert_eq!(
+            platforms,
+            vec![DatabasePlatform {
+                x_start: 50,
+                x_end: 90,
+                y: 40,
+            }]
+        );
+        assert!(
+            service
+                .detect_platforms(&resources, Minimap::Detecting)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn sample_player_marker_flips_y_and_skips_out_of_bounds() {
+        let marker = PlayerMarker {
+            color: (10, 20, 30),
+            tolerance: 20,
+            width: 4,
+            height: 4,
+        };
+        let mut detector = MockDetector::new();
+        detector
+            .expect_sample_player_marker()
+            .once()
+            .withf(|_, point| *point == Point::new(30, 60))
+            .returning(move |_, _| Ok(marker));
+        let resources = Resources::new(None, Some(detector));
+        let service = DefaultMapService::default();
+
+        assert_eq!(
+            service.sample_player_marker(&resources, mock_idle_minimap(), 30, 40),
+            Some(marker)
+        );
+        assert_eq!(
+            service.sample_player_marker(&resources, mock_idle_minimap(), 30, 101),
+            None
+        );
+        assert_eq!(
+            service.sample_player_marker(&resources, Minimap::Detecting, 30, 40),
+            None
+        );
+    }
+
     #[test]
     fn create_returns_none_when_not_idle_minimap() {
         let service = DefaultMapService::default();
@@ -230,6 +585,7 @@ mod tests {
         let service = DefaultMapService {
             map: Some(mock_minimap_data()),
             preset: Some("preset".to_string()),
+            ..Default::default()
         };
         let mut minimap_context = MinimapContext::default();
         let mut player_state = PlayerContext::default();
@@ -240,5 +596,58 @@ mod tests {
         assert!(player_state.config.rune_platforms_pathing_up_jump_only);
         assert!(player_state.config.auto_mob_platforms_pathing);
         assert!(player_state.config.auto_mob_platforms_bound);
+        assert_eq!(
+            player_state.mob_detection.kind,
+            MobDetectionKind::ColorSignature
+        );
+    }
+
+    #[test]
+    fn poll_none_when_not_queued_or_not_idle_minimap() {
+        let resources = Resources::new(None, None);
+        let mut service = DefaultMapService::default();
+
+        assert!(service.poll(&resources, mock_idle_minimap()).is_none());
+
+        service.queue_auto_switch();
+        assert!(service.poll(&resources, Minimap::Detecting).is_none());
+        assert!(service.auto_switch.is_some());
+    }
+
+    #[test]
+    fn map_from_paths_id_index_finds_linked_map() {
+        let linked = Map {
+            name: "Linked".to_string(),
+            paths_id_index: Some((1, 2)),
+            ..Default::default()
+        };
+        let maps = vec![
+            Map {
+                name: "Other".to_string(),
+                paths_id_index: Some((1, 0)),
+                ..Default::default()
+            },
+            linked.clone(),
+        ];
+
+        assert_eq!(map_from_paths_id_index(maps.clone(), (1, 2)), Some(linked));
+        assert_eq!(map_from_paths_id_index(maps, (2, 0)), None);
+    }
+
+    #[test]
+    fn preset_for_map_keeps_matching_preset() {
+        let mut map = mock_minimap_data();
+        map.actions.insert("first".to_string(), vec![]);
+        map.actions.insert("current".to_string(), vec![]);
+
+        assert_eq!(
+            preset_for_map(&map, Some("current".to_string())),
+            Some("current".to_string())
+        );
+        assert!(
+            preset_for_map(&map, Some("missing".to_string()))
+                .is_some_and(|preset| preset != "missing")
+        );
+        assert_eq!(preset_for_map(&mock_minimap_data(), None), None);
     }
 }
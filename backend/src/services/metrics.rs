@@ -0,0 +1,36 @@
+use std::fmt::Debug;
+
+use tokio::task::JoinHandle;
+
+use crate::{MetricsExport, Settings, metrics::serve_metrics};
+
+/// A service to handle exporting metrics.
+pub trait MetricsService: Debug {
+    /// Updates the currently in use metrics export settings with provided `settings`.
+    ///
+    /// The server is restarted only if the export settings changed.
+    fn update(&mut self, settings: &Settings);
+}
+
+#[derive(Debug, Default)]
+pub struct DefaultMetricsService {
+    export: Option<MetricsExport>,
+    server: Option<JoinHandle<()>>,
+}
+
+impl MetricsService for DefaultMetricsService {
+    fn update(&mut self, settings: &Settings) {
+        let export = settings.metrics_export;
+        if self.export == Some(export) {
+            return;
+        }
+        self.export = Some(export);
+
+        if let Some(server) = self.server.take() {
+            server.abort();
+        }
+        if export.enabled {
+            self.server = Some(serve_metrics(export.port, export.all_interfaces));
+        }
+    }
+}
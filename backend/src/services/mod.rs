@@ -7,27 +7,35 @@ use std::{
     sync::Arc,
 };
 
-use log::debug;
+use log::{debug, error};
 use platforms::{Window, input::InputKind};
 use tokio::sync::broadcast::Receiver;
 
 #[cfg(debug_assertions)]
 use crate::services::debug::DebugService;
 use crate::{
-    Localization, Settings,
+    Localization, RiskyActionCounts, Settings,
     bridge::{Capture, DefaultInputReceiver, Input},
+    database::{query_risky_action_counts, upsert_risky_action_counts},
     ecs::{Resources, World, WorldEvent},
     navigator::Navigator,
+    rng::RngSeed,
     rotator::Rotator,
     services::{
         character::{CharacterService, DefaultCharacterService},
         control::{ControlEventHandler, ControlService, DefaultControlService},
+        failover::{DefaultFailoverService, FailoverEventHandler, FailoverService},
         game::{DefaultGameService, GameEventHandler, GameService},
+        grid::{DefaultGridService, GridService},
+        hotkey::{DefaultHotkeyService, HotkeyEventHandler, HotkeyService},
         localization::{DefaultLocalizationService, LocalizationService},
-        map::{DefaultMapService, MapService},
+        map::{DefaultMapService, MapEventHandler, MapService},
+        metrics::{DefaultMetricsService, MetricsService},
         navigator::{DefaultNavigatorService, NavigatorService},
         operation::{DefaultOperationService, OperationEventHandler, OperationService},
         rotator::{DefaultRotatorService, RotatorService},
+        schedule::{DefaultScheduleService, ScheduleEventHandler, ScheduleService},
+        session::{DefaultSessionService, SessionEventHandler, SessionService},
         settings::{DefaultSettingsService, SettingsService},
         ui::{DefaultUiService, UiEventHandler, UiService},
         world::{DefaultWorldService, WorldEventHandler, WorldService},
@@ -38,12 +46,18 @@ mod character;
 mod control;
 #[cfg(debug_assertions)]
 mod debug;
+mod failover;
 mod game;
+mod grid;
+mod hotkey;
 mod localization;
 mod map;
+mod metrics;
 mod navigator;
 mod operation;
 mod rotator;
+mod schedule;
+mod session;
 mod settings;
 mod ui;
 mod world;
@@ -99,8 +113,10 @@ struct EventContext<'a> {
     pub settings_service: &'a mut Box<dyn SettingsService>,
     pub localization_service: &'a mut Box<dyn LocalizationService>,
     pub control_service: &'a mut Box<dyn ControlService>,
+    pub metrics_service: &'a mut Box<dyn MetricsService>,
     pub operation_service: &'a mut Box<dyn OperationService>,
     pub ui_service: &'a mut Box<dyn UiService>,
+    pub grid_service: &'a mut Box<dyn GridService>,
     #[cfg(debug_assertions)]
     pub debug_service: &'a mut DebugService,
 }
@@ -117,8 +133,14 @@ pub struct Services {
     settings: Box<dyn SettingsService>,
     localization: Box<dyn LocalizationService>,
     control: Box<dyn ControlService>,
+    metrics: Box<dyn MetricsService>,
     operation: Box<dyn OperationService>,
+    schedule: Box<dyn ScheduleService>,
+    session: Box<dyn SessionService>,
+    failover: Box<dyn FailoverService>,
+    hotkey: Box<dyn HotkeyService>,
     ui: Box<dyn UiService>,
+    grid: Box<dyn GridService>,
     #[cfg(debug_assertions)]
     debug: DebugService,
 }
@@ -127,13 +149,18 @@ impl Services {
     pub fn new(
         settings: Rc<RefCell<Settings>>,
         localization: Rc<RefCell<Arc<Localization>>>,
+        session_seed: RngSeed,
         event_rx: Receiver<WorldEvent>,
     ) -> Self {
         let settings_service = DefaultSettingsService::new(settings.clone());
         let window = settings_service.selected_window();
         let input_rx = DefaultInputReceiver::new(window, InputKind::Focused);
+        let global_input_rx = DefaultInputReceiver::new_global();
+        let hotkey_input_rx = DefaultInputReceiver::new_global();
         let mut control = DefaultControlService::default();
         control.update(&settings_service.settings());
+        let mut metrics = DefaultMetricsService::default();
+        metrics.update(&settings_service.settings());
 
         let mut event_bus = EventBus {
             handlers: HashMap::default(),
@@ -141,22 +168,33 @@ impl Services {
         event_bus.subscribe(UiEventHandler);
         event_bus.subscribe(GameEventHandler);
         event_bus.subscribe(ControlEventHandler);
-        event_bus.subscribe(WorldEventHandler);
+        event_bus.subscribe(WorldEventHandler::default());
         event_bus.subscribe(OperationEventHandler);
+        event_bus.subscribe(MapEventHandler);
+        event_bus.subscribe(ScheduleEventHandler);
+        event_bus.subscribe(SessionEventHandler);
+        event_bus.subscribe(FailoverEventHandler);
+        event_bus.subscribe(HotkeyEventHandler::default());
 
         Self {
             event_bus,
             world: Box::new(DefaultWorldService::new(event_rx)),
-            game: Box::new(DefaultGameService::new(input_rx)),
+            game: Box::new(DefaultGameService::new(input_rx, global_input_rx)),
             map: Box::new(DefaultMapService::default()),
             character: Box::new(DefaultCharacterService::default()),
-            rotator: Box::new(DefaultRotatorService::default()),
+            rotator: Box::new(DefaultRotatorService::new(session_seed)),
             navigator: Box::new(DefaultNavigatorService),
             settings: Box::new(settings_service),
             localization: Box::new(DefaultLocalizationService::new(localization)),
             control: Box::new(control),
+            metrics: Box::new(metrics),
             operation: Box::new(DefaultOperationService::default()),
+            schedule: Box::new(DefaultScheduleService::default()),
+            session: Box::new(DefaultSessionService::default()),
+            failover: Box::new(DefaultFailoverService::default()),
+            hotkey: Box::new(DefaultHotkeyService::new(hotkey_input_rx)),
             ui: Box::new(DefaultUiService::default()),
+            grid: Box::new(DefaultGridService::default()),
             #[cfg(debug_assertions)]
             debug: DebugService::default(),
         }
@@ -196,17 +234,68 @@ impl Services {
             .for_each(|event| {
                 events.push(Box::new(event));
             });
-        if let Some(event) = self.operation.poll(navigator) {
+        if let Some(event) = self.operation.poll(resources, world, navigator) {
             events.push(Box::new(event));
         }
         if let Some(event) = self.world.poll() {
             events.push(Box::new(event));
         }
+        if let Some(event) = self.map.poll(resources, world.minimap.state) {
+            events.push(Box::new(event));
+        }
         if let Some(event) = self.control.poll() {
             events.push(Box::new(event));
         }
+        if let Some(event) = self.schedule.poll(
+            &self.settings.settings().schedules,
+            &self.settings.settings().reset_routine,
+        ) {
+            events.push(Box::new(event));
+        }
+        world.player.context.config.avoid_changing_channel = self.schedule.in_reset_window();
+        world.player.context.config.unstuck_limits = self.settings.settings().unstuck_limits;
+        world.player.context.config.risky_action_limits =
+            self.settings.settings().risky_action_limits;
+        world.player.context.config.exp_rate_alert = self.settings.settings().exp_rate_alert;
+        world.player.context.config.remote_rune_solving =
+            self.settings.settings().remote_rune_solving;
+        world.player.context.config.keyboard_layout = self.settings.settings().keyboard_layout;
+        world.player.context.config.chat_clipboard_paste =
+            self.settings.settings().chat_clipboard_paste;
+        if let Some(event) = self.session.poll(
+            resources,
+            &mut world.player.context,
+            &self.settings.settings().session_limits,
+        ) {
+            events.push(Box::new(event));
+        }
+        if let Some(event) = self.failover.poll(
+            resources,
+            world.minimap.state,
+            self.map.map().and_then(|map| map.id),
+            &self.settings.settings().map_failover,
+        ) {
+            events.push(Box::new(event));
+        }
+        if let Some(event) = self.hotkey.poll(&self.settings.settings().hotkeys) {
+            events.push(Box::new(event));
+        }
+        self.grid.update(resources);
         #[cfg(debug_assertions)]
-        self.debug.poll(resources);
+        self.debug.poll(resources, world, rotator);
+        if let Some(calibration) = world.player.context.take_movement_calibration() {
+            self.character.save_movement_calibration(calibration);
+        }
+        if let Some(counts) = world.player.context.take_risky_action_counts() {
+            persist_risky_action_counts(counts);
+        }
+        // Only saves when halting because saving re-applies the map and resets the player
+        if resources.operation.halting()
+            && let Some(ys) = world.player.context.take_auto_mob_reachable_ys()
+        {
+            self.map.save_auto_mob_reachable_ys(ys);
+        }
+        self.rotator.update_cooldowns(rotator);
 
         let mut context = EventContext {
             resources,
@@ -222,8 +311,10 @@ impl Services {
             settings_service: &mut self.settings,
             localization_service: &mut self.localization,
             control_service: &mut self.control,
+            metrics_service: &mut self.metrics,
             operation_service: &mut self.operation,
             ui_service: &mut self.ui,
+            grid_service: &mut self.grid,
             #[cfg(debug_assertions)]
             debug_service: &mut self.debug,
         };
@@ -236,6 +327,21 @@ impl Services {
             context.resources,
             context.world,
             context.map_service.map(),
+            context
+                .character_service
+                .character()
+                .and_then(|character| character.id),
         );
     }
 }
+
+/// Persists the daily risky action `counts` in place of the previously persisted ones.
+fn persist_risky_action_counts(mut counts: RiskyActionCounts) {
+    counts.id = query_risky_action_counts()
+        .ok()
+        .flatten()
+        .and_then(|counts| counts.id);
+    if let Err(err) = upsert_risky_action_counts(&mut counts) {
+        error!(target: "services", "failed to persist risky action counts {err}");
+    }
+}
@@ -8,7 +8,7 @@ use crate::{
     ecs::{Resources, World},
     navigator::Navigator,
     operation::Operation,
-    player::{Panic, PanicTo, PlayerAction},
+    player::{Panic, PanicTo, Player, PlayerAction},
     rotator::Rotator,
     services::{Event, EventHandler},
 };
@@ -18,6 +18,8 @@ const PENDING_HALT_SECS: u64 = 12;
 #[derive(Debug)]
 pub enum OperationEvent {
     Halt,
+    /// The player finished the last action while [`Operation::Stopping`].
+    Stopped,
 }
 
 impl Event for OperationEvent {}
@@ -25,7 +27,12 @@ impl Event for OperationEvent {}
 /// A service to handle operation-related incoming requests.
 pub trait OperationService: Debug {
     /// Polls for any pending [`OperationEvent`].
-    fn poll(&mut self, navigator: &dyn Navigator) -> Option<OperationEvent>;
+    fn poll(
+        &mut self,
+        resources: &Resources,
+        world: &World,
+        navigator: &dyn Navigator,
+    ) -> Option<OperationEvent>;
 
     /// Applies the provided `update` to other arguments.
     fn apply(
@@ -66,7 +73,20 @@ impl DefaultOperationService {
 }
 
 impl OperationService for DefaultOperationService {
-    fn poll(&mut self, navigator: &dyn Navigator) -> Option<OperationEvent> {
+    fn poll(
+        &mut self,
+        resources: &Resources,
+        world: &World,
+        navigator: &dyn Navigator,
+    ) -> Option<OperationEvent> {
+        if matches!(resources.operation, Operation::Stopping)
+            && matches!(world.player.state, Player::Idle)
+            && !world.player.context.has_normal_action()
+            && !world.player.context.has_priority_action()
+        {
+            return Some(OperationEvent::Stopped);
+        }
+
         if self
             .pending_halt
             .as_ref()
@@ -101,11 +121,13 @@ impl OperationService for DefaultOperationService {
             cycle_stop_duration_millis,
         );
 
-        if matches!(
-            update,
-            BotOperationUpdate::Halt | BotOperationUpdate::TemporaryHalt
-        ) {
-            self.clear_states(world, rotator, true);
+        match update {
+            BotOperationUpdate::Halt | BotOperationUpdate::TemporaryHalt => {
+                self.clear_states(world, rotator, true);
+            }
+            // Only drops the queued actions so that the current action can still finish
+            BotOperationUpdate::SoftHalt => rotator.reset_queue(),
+            BotOperationUpdate::Run => (),
         }
     }
 
@@ -145,6 +167,12 @@ impl EventHandler<OperationEvent> for OperationEventHandler {
                 context.rotator,
                 true,
             ),
+            OperationEvent::Stopped => context.operation_service.halt(
+                context.resources,
+                context.world,
+                context.rotator,
+                false,
+            ),
         }
     }
 }
@@ -1,23 +1,41 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    time::{Duration, Instant},
+};
 
+use log::{error, warn};
 #[cfg(test)]
 use mockall::{automock, concretize};
 use strum::IntoEnumIterator;
 
 use crate::bridge::KeyKind;
-use crate::rotator::{Rotator, RotatorMode};
+use crate::capability::{Capability, is_capability_available};
+use crate::rotator::{OtherPlayerReactions, Rotator, RotatorMode, unix_millis_now};
 use crate::{
-    Action, Character, KeyBinding, Map, RotationMode, Settings, buff::BuffKind,
+    Action, ActionCooldown, ActionCooldowns, Character, KeyBinding, KeyTemplate, Map, RotationMode,
+    Settings,
+    buff::BuffKind,
+    database::{query_action_cooldowns, query_key_templates, upsert_action_cooldowns},
+    rng::{Rng, RngSeed},
     rotator::RotatorBuildArgs,
+    route::action_blocks,
 };
 use crate::{
     ActionCondition, ActionConfigurationCondition, ActionKey, KeyBindingConfiguration, PotionMode,
 };
 
+/// Interval between each persisting of the action cooldowns to the database.
+const PERSIST_COOLDOWNS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Persisted cooldowns older than this are discarded when loaded.
+const MAX_COOLDOWN_AGE_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
 /// A service to handle [`Rotator`]-related incoming requests.
 #[cfg_attr(test, automock)]
 pub trait RotatorService: Debug {
     /// Builds a new actions list to be used.
+    ///
+    /// Actions referencing a [`KeyTemplate`] use the latest values of the template.
     fn update_actions<'a>(
         &mut self,
         map: Option<&'a Map>,
@@ -26,9 +44,19 @@ pub trait RotatorService: Debug {
     );
 
     /// Builds a new buffs list to be used.
+    ///
+    /// The persisted action cooldowns of `character` are also loaded if it is a different
+    /// character.
     #[cfg_attr(test, concretize)]
     fn update_buffs(&mut self, character: Option<&Character>);
 
+    /// Tracks the cooldowns of the actions built in `rotator` and periodically persists them for
+    /// the current character so that they survive restarts.
+    fn update_cooldowns(&mut self, rotator: &dyn Rotator);
+
+    /// Persists the tracked cooldowns immediately if they changed since last persisted.
+    fn flush_cooldowns(&mut self);
+
     /// Updates `rotator` with data from `map`, `character`, `settings`, and the currently
     /// in-use actions and buffs.
     fn apply<'a>(
@@ -45,6 +73,46 @@ pub trait RotatorService: Debug {
 pub struct DefaultRotatorService {
     actions: Vec<Action>,
     buffs: Vec<(BuffKind, KeyKind)>,
+    /// The persisted action cooldowns of the current character.
+    ///
+    /// These are restored each time the actions are rebuilt.
+    cooldowns: Option<ActionCooldowns>,
+    /// The last [`Instant`] [`Self::cooldowns`] was persisted.
+    cooldowns_persisted_instant: Option<Instant>,
+    /// Whether [`Self::cooldowns`] changed since last persisted.
+    cooldowns_dirty: bool,
+    /// The seed of the current session used to shuffle interchangeable actions.
+    ///
+    /// The shuffle is seeded anew on each rebuild so that the actions keep the same order within
+    /// the session.
+    session_seed: RngSeed,
+}
+
+impl DefaultRotatorService {
+    pub fn new(session_seed: RngSeed) -> Self {
+        Self {
+            session_seed,
+            ..Self::default()
+        }
+    }
+
+    /// Persists [`Self::cooldowns`] if they changed since last persisted.
+    ///
+    /// The cooldowns stay dirty on failure so that persisting is retried.
+    fn persist_cooldowns(&mut self) {
+        let Some(cooldowns) = self.cooldowns.as_mut() else {
+            return;
+        };
+        if !self.cooldowns_dirty {
+            return;
+        }
+
+        self.cooldowns_persisted_instant = Some(Instant::now());
+        match upsert_action_cooldowns(cooldowns) {
+            Ok(()) => self.cooldowns_dirty = false,
+            Err(err) => error!(target: "rotator", "failed to persist action cooldowns {err}"),
+        }
+    }
 }
 
 impl RotatorService for DefaultRotatorService {
@@ -55,10 +123,20 @@ impl RotatorService for DefaultRotatorService {
         character: Option<&'a Character>,
     ) {
         let character_actions = character.map(actions_from).unwrap_or_default();
-        let map_actions = map
+        let mut map_actions = map
             .zip(preset)
             .and_then(|(minimap, preset)| minimap.actions.get(&preset).cloned())
+            .map(enabled_actions)
             .unwrap_or_default();
+        if map_actions
+            .iter()
+            .any(|action| matches!(action, Action::Key(key) if key.template_id.is_some()))
+        {
+            match query_key_templates() {
+                Ok(templates) => apply_key_templates(&mut map_actions, &templates),
+                Err(err) => error!(target: "rotator", "failed to query key templates {err}"),
+            }
+        }
 
         self.actions = [character_actions, map_actions].concat();
     }
@@ -66,6 +144,48 @@ impl RotatorService for DefaultRotatorService {
     #[cfg_attr(test, concretize)]
     fn update_buffs(&mut self, character: Option<&Character>) {
         self.buffs = character.map(buffs_from).unwrap_or_default();
+
+        let character_id = character.and_then(|character| character.id);
+        if self
+            .cooldowns
+            .as_ref()
+            .map(|cooldowns| cooldowns.character_id)
+            != character_id
+        {
+            self.persist_cooldowns();
+            self.cooldowns_dirty = false;
+            self.cooldowns = character_id.map(|id| {
+                let mut cooldowns =
+                    query_action_cooldowns(id)
+                        .ok()
+                        .flatten()
+                        .unwrap_or(ActionCooldowns {
+                            character_id: id,
+                            ..ActionCooldowns::default()
+                        });
+                retain_recent_cooldowns(&mut cooldowns.cooldowns, unix_millis_now());
+                cooldowns
+            });
+        }
+    }
+
+    fn update_cooldowns(&mut self, rotator: &dyn Rotator) {
+        let Some(cooldowns) = self.cooldowns.as_mut() else {
+            return;
+        };
+        // Merges on every call so that changes made before persisting is due are not lost
+        self.cooldowns_dirty |=
+            merge_cooldowns(&mut cooldowns.cooldowns, rotator.action_cooldowns());
+        let persist_due = self
+            .cooldowns_persisted_instant
+            .is_none_or(|instant| instant.elapsed() >= PERSIST_COOLDOWNS_INTERVAL);
+        if persist_due {
+            self.persist_cooldowns();
+        }
+    }
+
+    fn flush_cooldowns(&mut self) {
+        self.persist_cooldowns();
     }
 
     fn apply<'a>(
@@ -90,6 +210,7 @@ impl RotatorService for DefaultRotatorService {
             .unwrap_or_default();
         let hexa_booster_exchange_condition = character
             .map(|character| character.hexa_booster_exchange_condition)
+            .filter(|_| is_capability_available(Capability::HexaBooster))
             .unwrap_or_default();
         let hexa_booster_exchange_amount = character
             .map(|character| character.hexa_booster_exchange_amount)
@@ -97,18 +218,58 @@ impl RotatorService for DefaultRotatorService {
         let hexa_booster_exchange_all = character
             .map(|character| character.hexa_booster_exchange_all)
             .unwrap_or_default();
+        let mut shop_purchase = character
+            .map(|character| character.shop_purchase)
+            .unwrap_or_default();
+        // Without a path back, the player would be left on the NPC map after purchasing
+        if shop_purchase.enabled
+            && shop_purchase.paths_id_index.is_some()
+            && map.is_none_or(|map| map.paths_id_index.is_none())
+        {
+            warn!(target: "rotator", "shop NPC is on another map but the current map has no navigation path to return to, disabling shop purchase");
+            shop_purchase.enabled = false;
+        }
+        let map_hazards = map.map(|map| map.hazards.as_slice()).unwrap_or_default();
+        let jump_key = character
+            .map(|character| character.jump_key.key)
+            .unwrap_or_default();
         let enable_using_generic_booster = character
             .map(|character| character.generic_booster_key.enabled)
             .unwrap_or_default();
         let enable_using_hexa_booster = character
             .map(|character| character.hexa_booster_key.enabled)
-            .unwrap_or_default();
-        let familiars = character
+            .unwrap_or_default()
+            && is_capability_available(Capability::HexaBooster);
+        let enable_level_up_popups_dismissal = character
+            .map(|character| character.dismiss_level_up_popups)
+            .unwrap_or_default()
+            && is_capability_available(Capability::LevelUpPopupsDismissal);
+        let mut familiars = character
             .map(|character| character.familiars.clone())
             .unwrap_or_default();
+        familiars.enable_familiars_swapping &=
+            is_capability_available(Capability::FamiliarsSwapping);
+        let auto_mob_regions = map
+            .map(|map| {
+                map.rotation_auto_mob_regions
+                    .iter()
+                    .map(|region| region.bound)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let auto_mob_region_selection = map
+            .map(|map| map.rotation_auto_mob_region_selection)
+            .unwrap_or_default();
+        let actions = if settings.jitter_action_order {
+            jitter_actions(&self.actions, &Rng::new(self.session_seed, 0))
+        } else {
+            self.actions.clone()
+        };
         let args = RotatorBuildArgs {
             mode,
-            actions: &self.actions,
+            auto_mob_regions,
+            auto_mob_region_selection,
+            actions: &actions,
             buffs: &self.buffs,
             familiars,
             familiar_essence_key: familiar_essence_key.into(),
@@ -117,16 +278,128 @@ impl RotatorService for DefaultRotatorService {
             hexa_booster_exchange_condition,
             hexa_booster_exchange_amount,
             hexa_booster_exchange_all,
+            shop_purchase,
+            map_hazards,
+            jump_key: jump_key.into(),
             enable_panic_mode: settings.enable_panic_mode,
-            enable_rune_solving: settings.enable_rune_solving,
-            enable_transparent_shape_solving: settings.enable_transparent_shape_solving,
+            other_player_reactions: OtherPlayerReactions {
+                same_platform: settings.other_player_same_platform_reaction,
+                far: settings.other_player_far_reaction,
+                change_channel_millis: settings.other_player_change_channel_millis,
+            },
+            enable_rune_solving: settings.enable_rune_solving
+                && is_capability_available(Capability::RuneSolving),
+            enable_transparent_shape_solving: settings.enable_transparent_shape_solving
+                && is_capability_available(Capability::TransparentShapeSolving),
             enable_reset_normal_actions_on_erda: reset_normal_actions_on_erda,
             enable_using_generic_booster,
             enable_using_hexa_booster,
+            enable_level_up_popups_dismissal,
+            condition_webhooks: &settings.condition_webhooks,
         };
 
         rotator.build_actions(args);
+        if let Some(cooldowns) = self.cooldowns.as_ref()
+            && !cooldowns.cooldowns.is_empty()
+        {
+            rotator.restore_action_cooldowns(&cooldowns.cooldowns);
+        }
+    }
+}
+
+/// Merges `latest` cooldowns into `cooldowns` by key.
+///
+/// Returns `true` if any cooldown was added or changed.
+fn merge_cooldowns(cooldowns: &mut Vec<ActionCooldown>, latest: Vec<ActionCooldown>) -> bool {
+    let mut changed = false;
+    for cooldown in latest {
+        match cooldowns
+            .iter_mut()
+            .find(|current| current.key == cooldown.key)
+        {
+            // Small differences come from converting between instant and system time
+            Some(current) if current.queued_at_millis.abs_diff(cooldown.queued_at_millis) <= 1 => {}
+            Some(current) => {
+                *current = cooldown;
+                changed = true;
+            }
+            None => {
+                cooldowns.push(cooldown);
+                changed = true;
+            }
+        }
+    }
+
+    changed
+}
+
+/// Overwrites the templated fields of each [`Action::Key`] in `actions` referencing one of
+/// `templates`.
+///
+/// Actions referencing a deleted template keep their own values.
+fn apply_key_templates(actions: &mut [Action], templates: &[KeyTemplate]) {
+    for action in actions {
+        let Action::Key(key) = action else {
+            continue;
+        };
+        if let Some(template) = key
+            .template_id
+            .and_then(|id| templates.iter().find(|template| template.id == Some(id)))
+        {
+            template.apply_to(key);
+        }
+    }
+}
+
+/// Shuffles each run of consecutive interchangeable normal actions in `actions` with `rng`.
+///
+/// Normal actions are interchangeable when they are used at the same position or all have no
+/// position. Linked actions move along with the action they are linked to and other actions keep
+/// their original indices.
+fn jitter_actions(actions: &[Action], rng: &Rng) -> Vec<Action> {
+    let blocks = action_blocks(actions);
+    let mut order = (0..blocks.len()).collect::<Vec<_>>();
+    let mut start = 0;
+    while start < blocks.len() {
+        let key = interchangeable_key(&actions[blocks[start].start]);
+        let mut end = start + 1;
+        if key.is_some() {
+            while end < blocks.len() && interchangeable_key(&actions[blocks[end].start]) == key {
+                end += 1;
+            }
+            rng.random_shuffle(&mut order[start..end]);
+        }
+        start = end;
     }
+
+    order
+        .into_iter()
+        .flat_map(|index| actions[blocks[index].clone()].iter().copied())
+        .collect()
+}
+
+/// Gets the key that interchangeable normal actions share.
+///
+/// Returns [`None`] if `action` is not a normal action.
+#[inline]
+fn interchangeable_key(action: &Action) -> Option<Option<(i32, i32)>> {
+    if !matches!(action.condition(), ActionCondition::Any) {
+        return None;
+    }
+
+    let position = match action {
+        Action::Move(action) => Some(action.position),
+        Action::Key(action) => action.position,
+    };
+    Some(position.map(|position| (position.x, position.y)))
+}
+
+/// Removes cooldowns that are too old to matter or queued in the future relative to `now_millis`.
+fn retain_recent_cooldowns(cooldowns: &mut Vec<ActionCooldown>, now_millis: u64) {
+    cooldowns.retain(|cooldown| {
+        cooldown.queued_at_millis <= now_millis
+            && now_millis - cooldown.queued_at_millis <= MAX_COOLDOWN_AGE_MILLIS
+    });
 }
 
 #[inline]
@@ -137,9 +410,12 @@ fn rotator_mode_from(map: Option<&Map>) -> RotatorMode {
         RotationMode::AutoMobbing => {
             RotatorMode::AutoMobbing(map.rotation_mobbing_key, map.rotation_auto_mob_bound)
         }
-        RotationMode::PingPong => {
-            RotatorMode::PingPong(map.rotation_mobbing_key, map.rotation_ping_pong_bound)
-        }
+        RotationMode::PingPong => RotatorMode::PingPong(
+            map.rotation_mobbing_key,
+            map.rotation_ping_pong_bound,
+            map.rotation_ping_pong_vertical,
+        ),
+        RotationMode::BehaviorTree => RotatorMode::BehaviorTree(map.rotation_behavior_tree.clone()),
     })
     .unwrap_or_default()
 }
@@ -192,6 +468,28 @@ fn actions_from(character: &Character) -> Vec<Action> {
     vec
 }
 
+/// Filters out disabled actions from `actions`.
+///
+/// The linked actions following a disabled action are also filtered out.
+fn enabled_actions(actions: Vec<Action>) -> Vec<Action> {
+    let mut vec = Vec::with_capacity(actions.len());
+    let mut is_skipping_linked = false;
+
+    for action in actions {
+        let is_linked = matches!(action.condition(), ActionCondition::Linked);
+        if !is_linked {
+            is_skipping_linked = !action.enabled();
+        }
+        if is_skipping_linked || !action.enabled() {
+            continue;
+        }
+
+        vec.push(action);
+    }
+
+    vec
+}
+
 fn buffs_from(character: &Character) -> Vec<(BuffKind, KeyKind)> {
     BuffKind::iter()
         .filter_map(|kind| {
@@ -289,9 +587,171 @@ mod tests {
     use super::*;
     use crate::{ActionCondition, ActionConfiguration, ActionConfigurationCondition, ActionKey};
     use crate::{
-        Bound, EliteBossBehavior, FamiliarRarity, KeyBindingConfiguration, SwappableFamiliars,
-        rotator::MockRotator,
+        ActionMove, BehaviorNode, Bound, EliteBossBehavior, FamiliarRarity,
+        KeyBindingConfiguration, MapHazard, ShopPurchase, SwappableFamiliars, rotator::MockRotator,
     };
+    use crate::{Position, rng::session_seed};
+
+    fn cooldown(key: &str, queued_at_millis: u64) -> ActionCooldown {
+        ActionCooldown {
+            key: key.to_string(),
+            queued_at_millis,
+        }
+    }
+
+    #[test]
+    fn merge_cooldowns_updates_by_key() {
+        let mut cooldowns = vec![cooldown("a", 1000), cooldown("b", 2000)];
+
+        assert!(!merge_cooldowns(&mut cooldowns, vec![cooldown("a", 1001)]));
+        assert!(merge_cooldowns(
+            &mut cooldowns,
+            vec![cooldown("b", 5000), cooldown("c", 6000)]
+        ));
+        assert_eq!(
+            cooldowns,
+            vec![
+                cooldown("a", 1000),
+                cooldown("b", 5000),
+                cooldown("c", 6000)
+            ]
+        );
+    }
+
+    #[test]
+    fn update_cooldowns_stays_dirty_until_persisted() {
+        let mut rotator = MockRotator::new();
+        rotator
+            .expect_action_cooldowns()
+            .returning(|| vec![cooldown("a", 5000)]);
+        let mut service = DefaultRotatorService {
+            cooldowns: Some(ActionCooldowns {
+                character_id: 1,
+                cooldowns: vec![cooldown("a", 1000)],
+                ..ActionCooldowns::default()
+            }),
+            cooldowns_persisted_instant: Some(Instant::now()),
+            ..DefaultRotatorService::default()
+        };
+
+        service.update_cooldowns(&rotator);
+        assert!(service.cooldowns_dirty);
+
+        // Merging the same cooldowns again must not clear the pending change
+        service.update_cooldowns(&rotator);
+        assert!(service.cooldowns_dirty);
+        assert_eq!(
+            service.cooldowns.unwrap().cooldowns,
+            vec![cooldown("a", 5000)]
+        );
+    }
+
+    #[test]
+    fn retain_recent_cooldowns_drops_stale_and_future() {
+        let now = MAX_COOLDOWN_AGE_MILLIS + 10_000;
+        let mut cooldowns = vec![
+            cooldown("stale", 1000),
+            cooldown("recent", now - 1000),
+            cooldown("future", now + 1000),
+        ];
+
+        retain_recent_cooldowns(&mut cooldowns, now);
+
+        assert_eq!(cooldowns, vec![cooldown("recent", now - 1000)]);
+    }
+
+    #[test]
+    fn apply_key_templates_overwrites_referencing_actions() {
+        let template = KeyTemplate {
+            id: Some(1),
+            key: KeyBinding::C,
+            count: 3,
+            wait_after_use_millis: 500,
+            ..KeyTemplate::default()
+        };
+        let referencing = ActionKey {
+            key: KeyBinding::A,
+            template_id: Some(1),
+            ..ActionKey::default()
+        };
+        let deleted = ActionKey {
+            key: KeyBinding::B,
+            template_id: Some(2),
+            ..ActionKey::default()
+        };
+        let mut actions = vec![
+            Action::Key(referencing),
+            Action::Key(deleted),
+            Action::Move(ActionMove::default()),
+        ];
+
+        apply_key_templates(&mut actions, &[template]);
+
+        assert_matches!(
+            actions[0],
+            Action::Key(ActionKey {
+                key: KeyBinding::C,
+                count: 3,
+                wait_after_use_millis: 500,
+                template_id: Some(1),
+                ..
+            })
+        );
+        assert_eq!(actions[1], Action::Key(deleted));
+        assert_eq!(actions[2], Action::Move(ActionMove::default()));
+    }
+
+    #[test]
+    fn jitter_actions_shuffles_only_interchangeable_actions() {
+        let key = |key: KeyBinding, condition: ActionCondition| {
+            Action::Key(ActionKey {
+                key,
+                condition,
+                ..ActionKey::default()
+            })
+        };
+        let move_to = |x: i32, y: i32| {
+            Action::Move(ActionMove {
+                position: Position {
+                    x,
+                    y,
+                    ..Position::default()
+                },
+                ..ActionMove::default()
+            })
+        };
+        let actions = vec![
+            move_to(0, 0),
+            key(KeyBinding::A, ActionCondition::Any),
+            key(KeyBinding::B, ActionCondition::Any),
+            key(KeyBinding::C, ActionCondition::Linked),
+            key(KeyBinding::D, ActionCondition::Any),
+            key(KeyBinding::E, ActionCondition::EveryMillis(1000)),
+            key(KeyBinding::F, ActionCondition::Any),
+            move_to(10, 10),
+        ];
+        let jittered = (0..10)
+            .map(|session| jitter_actions(&actions, &Rng::new(session_seed([0; 32], session), 0)))
+            .collect::<Vec<_>>();
+
+        assert!(jittered.iter().any(|jittered| *jittered != actions));
+        for jittered in jittered {
+            let linked = jittered
+                .iter()
+                .position(|action| *action == key(KeyBinding::B, ActionCondition::Any))
+                .unwrap();
+            assert_eq!(jittered.len(), actions.len());
+            assert_eq!(jittered[0], actions[0]);
+            assert!(jittered[1..5].contains(&actions[1]));
+            assert!(jittered[1..5].contains(&actions[4]));
+            assert_eq!(jittered[linked + 1], actions[3]);
+            assert_eq!(jittered[5..], actions[5..]);
+        }
+        assert_eq!(
+            jitter_actions(&actions, &Rng::new([1; 32], 0)),
+            jitter_actions(&actions, &Rng::new([1; 32], 0))
+        );
+    }
 
     #[test]
     fn update_rotator_mode() {
@@ -308,6 +768,8 @@ mod tests {
                 width: 1,
                 height: 1,
             },
+            rotation_ping_pong_vertical: true,
+            rotation_behavior_tree: Some(BehaviorNode::Sequence(vec![])),
             ..Default::default()
         };
         let character = Character::default();
@@ -315,6 +777,7 @@ mod tests {
 
         for mode in RotationMode::iter() {
             minimap.rotation_mode = mode;
+            let behavior_tree = minimap.rotation_behavior_tree.clone();
             let mut rotator = MockRotator::new();
             rotator
                 .expect_build_actions()
@@ -327,10 +790,19 @@ mod tests {
                             key_bound = Some((key, bound));
                             RotationMode::AutoMobbing
                         }
-                        RotatorMode::PingPong(key, bound) => {
+                        RotatorMode::PingPong(key, bound, vertical) => {
+                            if vertical != minimap.rotation_ping_pong_vertical {
+                                return false;
+                            }
                             key_bound = Some((key, bound));
                             RotationMode::PingPong
                         }
+                        RotatorMode::BehaviorTree(ref tree) => {
+                            if *tree != behavior_tree {
+                                return false;
+                            }
+                            RotationMode::BehaviorTree
+                        }
                     };
                     let key_bound_match = match key_bound {
                         Some((key, bound)) => {
@@ -459,6 +931,95 @@ mod tests {
         service.apply(&mut rotator, Some(&minimap), None, &Settings::default());
     }
 
+    #[test]
+    fn update_with_map_hazards_and_jump_key() {
+        let minimap = Map {
+            hazards: vec![MapHazard::default()],
+            ..Default::default()
+        };
+        let character = Character {
+            jump_key: KeyBindingConfiguration {
+                key: KeyBinding::C,
+                enabled: true,
+            },
+            ..Default::default()
+        };
+
+        let mut rotator = MockRotator::new();
+        rotator
+            .expect_build_actions()
+            .withf(|args| args.map_hazards == [MapHazard::default()] && args.jump_key == KeyKind::C)
+            .once()
+            .return_const(());
+
+        let service = DefaultRotatorService::default();
+        service.apply(
+            &mut rotator,
+            Some(&minimap),
+            Some(&character),
+            &Settings::default(),
+        );
+    }
+
+    #[test]
+    fn update_with_shop_purchase_detour_without_return_path_disabled() {
+        let character = Character {
+            shop_purchase: ShopPurchase {
+                enabled: true,
+                paths_id_index: Some((1, 0)),
+                ..ShopPurchase::default()
+            },
+            ..Default::default()
+        };
+        let minimap = Map::default();
+
+        let mut rotator = MockRotator::new();
+        rotator
+            .expect_build_actions()
+            .withf(|args| !args.shop_purchase.enabled)
+            .once()
+            .return_const(());
+
+        let service = DefaultRotatorService::default();
+        service.apply(
+            &mut rotator,
+            Some(&minimap),
+            Some(&character),
+            &Settings::default(),
+        );
+    }
+
+    #[test]
+    fn update_with_shop_purchase_detour_with_return_path() {
+        let character = Character {
+            shop_purchase: ShopPurchase {
+                enabled: true,
+                paths_id_index: Some((1, 0)),
+                ..ShopPurchase::default()
+            },
+            ..Default::default()
+        };
+        let minimap = Map {
+            paths_id_index: Some((2, 0)),
+            ..Default::default()
+        };
+
+        let mut rotator = MockRotator::new();
+        rotator
+            .expect_build_actions()
+            .withf(|args| args.shop_purchase.enabled)
+            .once()
+            .return_const(());
+
+        let service = DefaultRotatorService::default();
+        service.apply(
+            &mut rotator,
+            Some(&minimap),
+            Some(&character),
+            &Settings::default(),
+        );
+    }
+
     #[test]
     fn update_with_panic_mode_and_rune_solving() {
         let settings = Settings {
@@ -554,6 +1115,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn update_exclude_disabled_actions_and_their_linked_actions() {
+        let actions = vec![
+            Action::Key(ActionKey {
+                key: KeyBinding::A,
+                enabled: false,
+                ..Default::default()
+            }),
+            Action::Key(ActionKey {
+                key: KeyBinding::B,
+                condition: ActionCondition::Linked,
+                ..Default::default()
+            }),
+            Action::Key(ActionKey {
+                key: KeyBinding::C,
+                ..Default::default()
+            }),
+            Action::Key(ActionKey {
+                key: KeyBinding::D,
+                condition: ActionCondition::Linked,
+                enabled: false,
+                ..Default::default()
+            }),
+            Action::Move(ActionMove {
+                condition: ActionCondition::Linked,
+                ..Default::default()
+            }),
+        ];
+        let mut minimap = Map::default();
+        minimap.actions.insert("preset".to_string(), actions);
+        let mut service = DefaultRotatorService::default();
+
+        service.update_actions(Some(&minimap), Some("preset".to_string()), None);
+
+        assert_matches!(
+            service.actions.as_slice(),
+            [
+                Action::Key(ActionKey {
+                    key: KeyBinding::C,
+                    ..
+                }),
+                Action::Move(ActionMove {
+                    condition: ActionCondition::Linked,
+                    ..
+                }),
+            ]
+        );
+    }
+
     #[test]
     fn update_include_actions_while_fixed_actions_disabled() {
         let actions = vec![
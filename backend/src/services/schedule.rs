@@ -0,0 +1,285 @@
+use std::{collections::VecDeque, fmt::Debug};
+
+use chrono::{Local, Timelike, Utc};
+use log::{error, info};
+
+use super::EventContext;
+use crate::{
+    BotOperationUpdate, ResetRoutine, Schedule, ScheduleKind,
+    database::query_maps,
+    player::Booster,
+    services::{Event, EventHandler, map::preset_for_map},
+};
+
+/// Number of minutes in a day.
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScheduleEvent {
+    Run,
+    Halt,
+    SwitchMap(i64),
+    Reset,
+}
+
+impl Event for ScheduleEvent {}
+
+/// A service to handle time-of-day [`Schedule`]s.
+pub trait ScheduleService: Debug {
+    /// Polls for any pending [`ScheduleEvent`] from `schedules` and `reset_routine`.
+    fn poll(
+        &mut self,
+        schedules: &[Schedule],
+        reset_routine: &ResetRoutine,
+    ) -> Option<ScheduleEvent>;
+
+    /// Whether the current time is within the window around the server reset as of the last
+    /// poll.
+    fn in_reset_window(&self) -> bool;
+}
+
+#[derive(Debug, Default)]
+pub struct DefaultScheduleService {
+    /// The local minutes of day of the last poll.
+    last_minutes: Option<u32>,
+    /// The UTC minutes of day of the last poll.
+    last_utc_minutes: Option<u32>,
+    in_reset_window: bool,
+    pending_events: VecDeque<ScheduleEvent>,
+}
+
+impl ScheduleService for DefaultScheduleService {
+    fn poll(
+        &mut self,
+        schedules: &[Schedule],
+        reset_routine: &ResetRoutine,
+    ) -> Option<ScheduleEvent> {
+        let now = Local::now();
+        let minutes = now.hour() * 60 + now.minute();
+        if let Some(last_minutes) = self.last_minutes
+            && last_minutes != minutes
+        {
+            self.pending_events
+                .extend(schedule_events_between(schedules, last_minutes, minutes));
+        }
+        self.last_minutes = Some(minutes);
+
+        let now = Utc::now();
+        let utc_minutes = now.hour() * 60 + now.minute();
+        let reset_minutes = reset_routine.time.minutes_of_day();
+        if reset_routine.enabled
+            && let Some(last_utc_minutes) = self.last_utc_minutes
+            && crossed(last_utc_minutes, utc_minutes, reset_minutes)
+        {
+            self.pending_events.push_back(ScheduleEvent::Reset);
+        }
+        self.last_utc_minutes = Some(utc_minutes);
+        self.in_reset_window = reset_routine.enabled
+            && is_within_reset_window(reset_minutes, reset_routine.window_minutes, utc_minutes);
+
+        self.pending_events.pop_front()
+    }
+
+    #[inline]
+    fn in_reset_window(&self) -> bool {
+        self.in_reset_window
+    }
+}
+
+pub struct ScheduleEventHandler;
+
+impl EventHandler<ScheduleEvent> for ScheduleEventHandler {
+    fn handle(&mut self, context: &mut EventContext<'_>, event: ScheduleEvent) {
+        match event {
+            ScheduleEvent::Run => {
+                if !context.resources.operation.halting()
+                    || context.map_service.map().is_none()
+                    || context.character_service.character().is_none()
+                {
+                    return;
+                }
+
+                info!(target: "schedule", "running on schedule");
+                context.operation_service.apply(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    &context.settings_service.settings(),
+                    BotOperationUpdate::Run,
+                );
+            }
+            ScheduleEvent::Halt => {
+                if context.resources.operation.halting() {
+                    return;
+                }
+
+                info!(target: "schedule", "halting on schedule");
+                context.operation_service.halt(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    false,
+                );
+            }
+            ScheduleEvent::SwitchMap(id) => {
+                let map = query_maps()
+                    .ok()
+                    .and_then(|maps| maps.into_iter().find(|map| map.id == Some(id)));
+                let Some(map) = map else {
+                    error!(target: "schedule", "scheduled map {id} not found");
+                    return;
+                };
+
+                info!(target: "schedule", "switching to map {} on schedule", map.name);
+                let preset = preset_for_map(&map, context.map_service.preset());
+                context.ui_service.queue_update_map(preset, Some(map));
+            }
+            ScheduleEvent::Reset => {
+                if context.resources.operation.halting() {
+                    return;
+                }
+
+                info!(target: "schedule", "running reset routine");
+                let routine = context.settings_service.settings().reset_routine;
+                let player = &mut context.world.player.context;
+                if routine.reapply_boosters {
+                    player.clear_booster_fail_count(Booster::Generic);
+                    player.clear_booster_fail_count(Booster::Hexa);
+                    context.rotator.reset_action_cooldowns("booster:");
+                }
+                if routine.reopen_familiars {
+                    player.clear_familiars_swap_fail_count();
+                    context.rotator.reset_action_cooldowns("familiars:");
+                }
+                if routine.restart_rotation {
+                    player.clear_actions_aborted(true);
+                    context.rotator.reset_queue();
+                }
+            }
+        }
+    }
+}
+
+/// Whether `minutes` is crossed when the time moves from `from` to `to` minutes of day.
+///
+/// The crossed range is `(from, to]` and wraps around midnight when `to` is before `from`.
+fn crossed(from: u32, to: u32, minutes: u32) -> bool {
+    let offset = |minutes: u32| (minutes + MINUTES_PER_DAY - from) % MINUTES_PER_DAY;
+    let offset_minutes = offset(minutes);
+
+    offset_minutes > 0 && offset_minutes <= offset(to)
+}
+
+/// Whether `minutes` is at most `window_minutes` before or after `reset_minutes` of day.
+fn is_within_reset_window(reset_minutes: u32, window_minutes: u32, minutes: u32) -> bool {
+    let distance = reset_minutes.abs_diff(minutes % MINUTES_PER_DAY);
+
+    distance.min(MINUTES_PER_DAY - distance) <= window_minutes
+}
+
+/// Finds the [`ScheduleEvent`]s of enabled `schedules` whose boundaries are crossed when the
+/// local time moves from `from` to `to` minutes of day.
+///
+/// The crossed range is `(from, to]` and wraps around midnight when `to` is before `from`.
+/// Events are ordered by their boundary time with window ends before window starts.
+fn schedule_events_between(schedules: &[Schedule], from: u32, to: u32) -> Vec<ScheduleEvent> {
+    let offset = |minutes: u32| (minutes + MINUTES_PER_DAY - from) % MINUTES_PER_DAY;
+
+    let mut events = Vec::new();
+    for schedule in schedules.iter().filter(|schedule| schedule.enabled) {
+        let start = schedule.start.minutes_of_day();
+        let end = schedule.end.minutes_of_day();
+        let (start_event, end_event) = match schedule.kind {
+            ScheduleKind::Run => (Some(ScheduleEvent::Run), Some(ScheduleEvent::Halt)),
+            ScheduleKind::Stop => (Some(ScheduleEvent::Halt), Some(ScheduleEvent::Run)),
+            ScheduleKind::SwitchMap => (schedule.map_id.map(ScheduleEvent::SwitchMap), None),
+        };
+
+        if let Some(event) = end_event
+            && crossed(from, to, end)
+        {
+            events.push((offset(end), 0, event));
+        }
+        if let Some(event) = start_event
+            && crossed(from, to, start)
+        {
+            events.push((offset(start), 1, event));
+        }
+    }
+    events.sort_by_key(|(offset, order, _)| (*offset, *order));
+
+    events.into_iter().map(|(_, _, event)| event).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ScheduleTime;
+
+    fn schedule(kind: ScheduleKind, start: (u32, u32), end: (u32, u32)) -> Schedule {
+        Schedule {
+            enabled: true,
+            kind,
+            start: ScheduleTime {
+                hour: start.0,
+                minute: start.1,
+            },
+            end: ScheduleTime {
+                hour: end.0,
+                minute: end.1,
+            },
+            map_id: Some(1),
+        }
+    }
+
+    #[test]
+    fn schedule_events_between_window_boundaries() {
+        let schedules = [
+            schedule(ScheduleKind::Run, (1, 0), (7, 0)),
+            schedule(ScheduleKind::SwitchMap, (5, 0), (0, 0)),
+        ];
+
+        assert_eq!(
+            schedule_events_between(&schedules, 59, 60),
+            vec![ScheduleEvent::Run]
+        );
+        assert_eq!(schedule_events_between(&schedules, 60, 61), vec![]);
+        assert_eq!(
+            schedule_events_between(&schedules, 299, 420),
+            vec![ScheduleEvent::SwitchMap(1), ScheduleEvent::Halt]
+        );
+    }
+
+    #[test]
+    fn schedule_events_between_wraps_midnight_and_orders_end_first() {
+        let schedules = [
+            schedule(ScheduleKind::Stop, (0, 0), (1, 0)),
+            schedule(ScheduleKind::Run, (22, 0), (0, 0)),
+        ];
+
+        assert_eq!(
+            schedule_events_between(&schedules, MINUTES_PER_DAY - 1, 0),
+            vec![ScheduleEvent::Halt, ScheduleEvent::Halt]
+        );
+
+        let mut disabled = schedules;
+        disabled[0].enabled = false;
+        assert_eq!(
+            schedule_events_between(&disabled, MINUTES_PER_DAY - 1, 0),
+            vec![ScheduleEvent::Halt]
+        );
+    }
+
+    #[test]
+    fn reset_crossed_and_window_wrap_midnight() {
+        assert!(crossed(MINUTES_PER_DAY - 1, 0, 0));
+        assert!(!crossed(0, 1, 0));
+        assert!(crossed(MINUTES_PER_DAY - 5, 5, 0));
+
+        assert!(is_within_reset_window(0, 5, MINUTES_PER_DAY - 5));
+        assert!(is_within_reset_window(0, 5, 5));
+        assert!(!is_within_reset_window(0, 5, 6));
+        assert!(is_within_reset_window(60, 5, 57));
+        assert!(!is_within_reset_window(60, 0, 61));
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    fmt::Debug,
+    time::{Duration, Instant},
+};
+
+use log::info;
+use strum::Display;
+
+use super::EventContext;
+use crate::{
+    SessionLimits,
+    ecs::Resources,
+    notification::NotificationKind,
+    operation::Operation,
+    player::PlayerContext,
+    services::{Event, EventHandler},
+};
+
+/// A limit of [`SessionLimits`].
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
+pub enum SessionLimit {
+    Duration,
+    RunesSolved,
+    Deaths,
+    ChannelChanges,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionEvent {
+    LimitReached(SessionLimit),
+}
+
+impl Event for SessionEvent {}
+
+/// A service to track the current bot session against [`SessionLimits`].
+pub trait SessionService: Debug {
+    /// Polls for any pending [`SessionEvent`] from `limits`.
+    ///
+    /// The counts of `player_context` are taken on every poll.
+    fn poll(
+        &mut self,
+        resources: &Resources,
+        player_context: &mut PlayerContext,
+        limits: &SessionLimits,
+    ) -> Option<SessionEvent>;
+}
+
+/// The counts of the current bot session.
+#[derive(Debug, Clone, Copy)]
+struct Session {
+    started: Instant,
+    runes_solved: u32,
+    deaths: u32,
+    channel_changes: u32,
+    /// Whether a limit has been reached so that it is only reported once.
+    limit_reached: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct DefaultSessionService {
+    session: Option<Session>,
+    was_player_dead: bool,
+}
+
+impl SessionService for DefaultSessionService {
+    fn poll(
+        &mut self,
+        resources: &Resources,
+        player_context: &mut PlayerContext,
+        limits: &SessionLimits,
+    ) -> Option<SessionEvent> {
+        let runes_solved = player_context.take_runes_solved_count();
+        let channel_changes = player_context.take_channels_changed_count();
+        let is_player_dead = player_context.is_dead();
+        let player_died = !self.was_player_dead && is_player_dead;
+        self.was_player_dead = is_player_dead;
+
+        // Run/stop cycling halts temporarily so only a full halt ends the session
+        if matches!(resources.operation, Operation::Halting) {
+            self.session = None;
+            return None;
+        }
+
+        let session = self.session.get_or_insert_with(|| Session {
+            started: Instant::now(),
+            runes_solved: 0,
+            deaths: 0,
+            channel_changes: 0,
+            limit_reached: false,
+        });
+        session.runes_solved += runes_solved;
+        session.deaths += player_died as u32;
+        session.channel_changes += channel_changes;
+        if !limits.enabled || session.limit_reached {
+            return None;
+        }
+
+        let limit = reached_session_limit(session, limits, session.started.elapsed())?;
+        session.limit_reached = true;
+        Some(SessionEvent::LimitReached(limit))
+    }
+}
+
+pub struct SessionEventHandler;
+
+impl EventHandler<SessionEvent> for SessionEventHandler {
+    fn handle(&mut self, context: &mut EventContext<'_>, event: SessionEvent) {
+        match event {
+            SessionEvent::LimitReached(limit) => {
+                if context.resources.operation.halting() {
+                    return;
+                }
+
+                let settings = context.settings_service.settings();
+                info!(target: "session", "session limit {limit} reached, halting");
+                context.operation_service.halt(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    settings.session_limits.go_to_town,
+                );
+                let _ = context
+                    .resources
+                    .notification
+                    .schedule_notification(NotificationKind::SessionLimitReached);
+            }
+        }
+    }
+}
+
+/// Finds the first limit of `limits` reached by `session` after `elapsed`.
+fn reached_session_limit(
+    session: &Session,
+    limits: &SessionLimits,
+    elapsed: Duration,
+) -> Option<SessionLimit> {
+    let reached = |count: u64, max: u64| max > 0 && count >= max;
+
+    if reached(elapsed.as_millis() as u64, limits.max_duration_millis) {
+        return Some(SessionLimit::Duration);
+    }
+    if reached(session.runes_solved as u64, limits.max_runes_solved as u64) {
+        return Some(SessionLimit::RunesSolved);
+    }
+    if reached(session.deaths as u64, limits.max_deaths as u64) {
+        return Some(SessionLimit::Deaths);
+    }
+    if reached(
+        session.channel_changes as u64,
+        limits.max_channel_changes as u64,
+    ) {
+        return Some(SessionLimit::ChannelChanges);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(runes_solved: u32, deaths: u32, channel_changes: u32) -> Session {
+        Session {
+            started: Instant::now(),
+            runes_solved,
+            deaths,
+            channel_changes,
+            limit_reached: false,
+        }
+    }
+
+    #[test]
+    fn reached_session_limit_ignores_zero_limits() {
+        let limits = SessionLimits {
+            enabled: true,
+            ..SessionLimits::default()
+        };
+
+        assert_eq!(
+            reached_session_limit(&session(10, 10, 10), &limits, Duration::from_secs(3600)),
+            None
+        );
+    }
+
+    #[test]
+    fn reached_session_limit_counts_and_duration() {
+        let limits = SessionLimits {
+            enabled: true,
+            max_duration_millis: 60_000,
+            max_runes_solved: 3,
+            max_deaths: 1,
+            max_channel_changes: 5,
+            go_to_town: false,
+        };
+
+        assert_eq!(
+            reached_session_limit(&session(2, 0, 4), &limits, Duration::from_secs(59)),
+            None
+        );
+        assert_eq!(
+            reached_session_limit(&session(2, 0, 4), &limits, Duration::from_secs(60)),
+            Some(SessionLimit::Duration)
+        );
+        assert_eq!(
+            reached_session_limit(&session(3, 0, 0), &limits, Duration::ZERO),
+            Some(SessionLimit::RunesSolved)
+        );
+        assert_eq!(
+            reached_session_limit(&session(0, 1, 0), &limits, Duration::ZERO),
+            Some(SessionLimit::Deaths)
+        );
+        assert_eq!(
+            reached_session_limit(&session(0, 0, 5), &limits, Duration::ZERO),
+            Some(SessionLimit::ChannelChanges)
+        );
+    }
+}
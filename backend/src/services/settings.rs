@@ -1,19 +1,30 @@
 use std::{
     cell::{Ref, RefCell},
     fmt::Debug,
+    path::Path,
     rc::Rc,
 };
 
 #[cfg(test)]
 use mockall::automock;
-use platforms::{Window, capture::query_capture_name_window_pairs, input::InputKind};
+use platforms::{
+    Window,
+    capture::{CaptureWindow, query_capture_windows},
+    input::InputKind,
+};
 
 use crate::{
-    CaptureMode, InputMethod as DatabaseInputMethod, Settings,
+    CaptureMode, CaptureWindowSelection, InputMethod as DatabaseInputMethod, Settings,
     bridge::{Capture, Input, InputMethod, InputReceiver},
     operation::Operation,
+    tick::{set_adaptive_tick_rate, set_tick_rate},
 };
 
+#[cfg(not(feature = "linux"))]
+const DEFAULT_WINDOW_CLASS: &str = "MapleStoryClass";
+#[cfg(feature = "linux")]
+const DEFAULT_WINDOW_CLASS: &str = "maplestory";
+
 /// A service to handle [`Settings`]-related incoming requests.
 #[cfg_attr(test, automock)]
 pub trait SettingsService: Debug {
@@ -23,7 +34,7 @@ pub trait SettingsService: Debug {
     /// Updates the currently in use [`Settings`] with new `settings`.
     fn update_settings(&mut self, settings: Settings);
 
-    /// Updates `operation`, `input`, `input_receiver` and `capture` to use the
+    /// Updates the tick rate, `operation`, `input`, `input_receiver` and `capture` to use the
     /// current [`Settings`].
     fn apply_settings(
         &self,
@@ -35,10 +46,13 @@ pub trait SettingsService: Debug {
 
     /// Gets a list of [`Window`] names to be used for selection.
     ///
-    /// The index of a name corresponds to a [`Window`].
+    /// The index of a name corresponds to a [`Window`]. Names of windows with the same title are
+    /// disambiguated by their process and order.
     fn window_names(&self) -> Vec<String>;
 
     /// Updates the list available of [`Window`]s from platform.
+    ///
+    /// The selected [`Window`] is re-matched against the new list.
     fn update_windows(&mut self);
 
     /// Gets the current selected [`Window`] index.
@@ -50,6 +64,9 @@ pub trait SettingsService: Debug {
     fn selected_window(&self) -> Window;
 
     /// Updates the selected [`Window`] specified by `index`.
+    ///
+    /// The selection is also remembered in the current [`Settings`] so that the same window can be
+    /// matched again after restart.
     fn update_selected_window(&mut self, index: Option<usize>);
 
     /// Updates `input`, `input_receiver` and `capture` to use the currently selected [`Window`].
@@ -65,7 +82,7 @@ pub trait SettingsService: Debug {
 pub struct DefaultSettingsService {
     settings: Rc<RefCell<Settings>>,
     capture_default_window: Window,
-    capture_name_window_pairs: Vec<(String, Window)>,
+    capture_windows: Vec<CaptureWindow>,
     capture_selected_window_index: Option<usize>,
 }
 
@@ -74,15 +91,24 @@ impl DefaultSettingsService {
         // MapleStoryClass <- GMS
         // MapleStoryClassSG <- MSEA
         // MapleStoryClassTW <- TMS
-        if cfg!(windows) {
-            let window = Window::new("MapleStoryClass");
+        // maplestory.exe <- Wine on Linux
+        if cfg!(any(windows, feature = "linux")) {
+            let window = Window::new(DEFAULT_WINDOW_CLASS);
+            let capture_windows = query_capture_windows().expect("supported platform");
+            let capture_selected_window_index =
+                settings
+                    .borrow()
+                    .capture_window
+                    .as_ref()
+                    .and_then(|selection| {
+                        capture_window_index_from_selection(&capture_windows, selection)
+                    });
 
             return Self {
                 settings,
                 capture_default_window: window,
-                capture_name_window_pairs: query_capture_name_window_pairs()
-                    .expect("supported platform"),
-                capture_selected_window_index: None,
+                capture_windows,
+                capture_selected_window_index,
             };
         }
 
@@ -111,6 +137,11 @@ impl DefaultSettingsService {
         };
 
         input_rx.set_window_and_input_kind(window, kind);
+        input.set_focus_policy(settings.focus_policy, settings.focus_grace_period_millis);
+        input.set_press_durations(settings.key_press_durations);
+        input.set_observer(settings.observer_mode);
+        input.set_keyboard_layout(settings.keyboard_layout);
+        input.set_audit(settings.input_audit);
         match settings.input_method {
             DatabaseInputMethod::Default => {
                 input.set_method(InputMethod::Default(window, kind));
@@ -121,6 +152,12 @@ impl DefaultSettingsService {
                     settings.input_method_rpc_server_url.clone(),
                 ));
             }
+            DatabaseInputMethod::Gamepad => {
+                input.set_method(InputMethod::Gamepad(
+                    window,
+                    settings.gamepad_mappings.clone(),
+                ));
+            }
         }
     }
 }
@@ -142,6 +179,8 @@ impl SettingsService for DefaultSettingsService {
         capture: &mut dyn Capture,
     ) {
         let settings = self.settings();
+        set_tick_rate(settings.tick_rate);
+        set_adaptive_tick_rate(settings.adaptive_tick_rate);
         *operation = operation.update_from_mode(
             settings.cycle_run_stop,
             settings.cycle_run_duration_millis,
@@ -152,16 +191,18 @@ impl SettingsService for DefaultSettingsService {
     }
 
     fn window_names(&self) -> Vec<String> {
-        self.capture_name_window_pairs
-            .iter()
-            .map(|(name, _)| name)
-            .cloned()
-            .collect::<Vec<_>>()
+        capture_window_names(&self.capture_windows)
     }
 
     fn update_windows(&mut self) {
-        self.capture_name_window_pairs =
-            query_capture_name_window_pairs().expect("supported platform");
+        self.capture_windows = query_capture_windows().expect("supported platform");
+        self.capture_selected_window_index =
+            self.settings()
+                .capture_window
+                .as_ref()
+                .and_then(|selection| {
+                    capture_window_index_from_selection(&self.capture_windows, selection)
+                });
     }
 
     fn selected_window_index(&self) -> Option<usize> {
@@ -171,16 +212,17 @@ impl SettingsService for DefaultSettingsService {
     fn selected_window(&self) -> Window {
         self.capture_selected_window_index
             .and_then(|index| {
-                self.capture_name_window_pairs
+                self.capture_windows
                     .get(index)
-                    .map(|(_, handle)| handle)
-                    .copied()
+                    .map(|capture_window| capture_window.window)
             })
             .unwrap_or(self.capture_default_window)
     }
 
     fn update_selected_window(&mut self, index: Option<usize>) {
         self.capture_selected_window_index = index;
+        self.settings.borrow_mut().capture_window = index
+            .and_then(|index| capture_window_selection_from_index(&self.capture_windows, index));
     }
 
     fn apply_selected_window(
@@ -194,6 +236,83 @@ impl SettingsService for DefaultSettingsService {
     }
 }
 
+#[inline]
+fn is_capture_window_matched(window: &CaptureWindow, selection: &CaptureWindowSelection) -> bool {
+    (selection.process_path.is_none() || window.process_path == selection.process_path)
+        && window.title.contains(&selection.title_pattern)
+}
+
+/// Finds the index of the window in `windows` matching `selection`.
+///
+/// Falls back to the first matched window if there are fewer matched windows than
+/// [`CaptureWindowSelection::index`] (e.g. one of the clients was closed).
+fn capture_window_index_from_selection(
+    windows: &[CaptureWindow],
+    selection: &CaptureWindowSelection,
+) -> Option<usize> {
+    let indices = windows
+        .iter()
+        .enumerate()
+        .filter(|(_, window)| is_capture_window_matched(window, selection))
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+
+    indices.get(selection.index).or(indices.first()).copied()
+}
+
+fn capture_window_selection_from_index(
+    windows: &[CaptureWindow],
+    index: usize,
+) -> Option<CaptureWindowSelection> {
+    let window = windows.get(index)?;
+    let mut selection = CaptureWindowSelection {
+        process_path: window.process_path.clone(),
+        title_pattern: window.title.clone(),
+        index: 0,
+    };
+    selection.index = windows[..index]
+        .iter()
+        .filter(|window| is_capture_window_matched(window, &selection))
+        .count();
+
+    Some(selection)
+}
+
+fn capture_window_names(windows: &[CaptureWindow]) -> Vec<String> {
+    let is_same_window = |a: &CaptureWindow, b: &CaptureWindow| {
+        a.title == b.title && a.process_path == b.process_path
+    };
+
+    windows
+        .iter()
+        .enumerate()
+        .map(|(index, window)| {
+            let process_name = window
+                .process_path
+                .as_deref()
+                .and_then(|path| Path::new(path).file_name())
+                .and_then(|name| name.to_str());
+            let mut name = match process_name {
+                Some(process_name) => format!("{} ({process_name})", window.title),
+                None => window.title.clone(),
+            };
+            let order = windows[..index]
+                .iter()
+                .filter(|other| is_same_window(other, window))
+                .count();
+            let count = windows
+                .iter()
+                .filter(|other| is_same_window(other, window))
+                .count();
+            if count > 1 {
+                name.push_str(&format!(" #{}", order + 1));
+            }
+
+            name
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use std::assert_matches::assert_matches;
@@ -206,6 +325,18 @@ mod tests {
     };
     use crate::{CaptureMode, CycleRunStopMode, InputMethod};
 
+    fn mock_capture_window(
+        title: &str,
+        process_path: Option<&str>,
+        window: Window,
+    ) -> CaptureWindow {
+        CaptureWindow {
+            title: title.to_string(),
+            process_path: process_path.map(str::to_string),
+            window,
+        }
+    }
+
     #[test]
     fn settings_service_initialization() {
         let settings = Rc::new(RefCell::new(Settings::default()));
@@ -233,14 +364,19 @@ mod tests {
             ..Default::default()
         }));
         let mut service = DefaultSettingsService::new(settings.clone());
-        service.capture_name_window_pairs = vec![
-            ("Foo".to_string(), Window::new("Foo")),
-            ("Bar".to_string(), Window::new("Bar")),
+        service.capture_windows = vec![
+            mock_capture_window("Foo", None, Window::new("Foo")),
+            mock_capture_window("Bar", None, Window::new("Bar")),
         ];
 
         let mut mock_keys = MockInput::default();
+        mock_keys.expect_set_focus_policy().return_const(());
+        mock_keys.expect_set_press_durations().return_const(());
+        mock_keys.expect_set_observer().return_const(());
+        mock_keys.expect_set_keyboard_layout().return_const(());
+        mock_keys.expect_set_audit().return_const(());
         mock_keys.expect_set_method().withf(|method| match method {
-            BridgeInputMethod::Rpc(_, _) => false,
+            BridgeInputMethod::Rpc(_, _) | BridgeInputMethod::Gamepad(_, _) => false,
             BridgeInputMethod::Default(window, kind) => {
                 *window == Window::new("Bar") && matches!(kind, InputKind::Focused)
             }
@@ -271,6 +407,44 @@ mod tests {
 
         assert_eq!(service.selected_window_index(), Some(1));
         assert_eq!(service.selected_window(), Window::new("Bar"));
+        assert_eq!(
+            service.settings().capture_window,
+            Some(CaptureWindowSelection {
+                process_path: None,
+                title_pattern: "Bar".to_string(),
+                index: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn capture_window_selection_disambiguates_multiple_clients() {
+        let client = Some("/games/MapleStory/MapleStory.exe");
+        let windows = vec![
+            mock_capture_window("MapleStory", client, Window::new("A")),
+            mock_capture_window("Notepad", None, Window::new("B")),
+            mock_capture_window("MapleStory", client, Window::new("C")),
+        ];
+
+        let selection = capture_window_selection_from_index(&windows, 2).unwrap();
+
+        assert_eq!(selection.index, 1);
+        assert_eq!(
+            capture_window_index_from_selection(&windows, &selection),
+            Some(2)
+        );
+        assert_eq!(
+            capture_window_index_from_selection(&windows[..2], &selection),
+            Some(0)
+        );
+        assert_eq!(
+            capture_window_names(&windows),
+            vec![
+                "MapleStory (MapleStory.exe) #1".to_string(),
+                "Notepad".to_string(),
+                "MapleStory (MapleStory.exe) #2".to_string(),
+            ]
+        );
     }
 
     #[test]
@@ -286,11 +460,16 @@ mod tests {
             ..Default::default()
         };
         let mut mock_keys = MockInput::default();
+        mock_keys.expect_set_focus_policy().return_const(());
+        mock_keys.expect_set_press_durations().return_const(());
+        mock_keys.expect_set_observer().return_const(());
+        mock_keys.expect_set_keyboard_layout().return_const(());
+        mock_keys.expect_set_audit().return_const(());
         mock_keys.expect_set_method().withf(|method| match method {
             BridgeInputMethod::Rpc(window, url) => {
                 *window == Window::new("MapleStoryClass") && url.as_str() == "http://localhost:9000"
             }
-            BridgeInputMethod::Default(_, _) => false,
+            BridgeInputMethod::Default(_, _) | BridgeInputMethod::Gamepad(_, _) => false,
         });
 
         let mut key_receiver = MockInputReceiver::default();
@@ -334,6 +513,11 @@ mod tests {
             ..Default::default()
         };
         let mut mock_keys = MockInput::default();
+        mock_keys.expect_set_focus_policy().return_const(());
+        mock_keys.expect_set_press_durations().return_const(());
+        mock_keys.expect_set_observer().return_const(());
+        mock_keys.expect_set_keyboard_layout().return_const(());
+        mock_keys.expect_set_audit().return_const(());
         mock_keys.expect_set_method().once();
         let mut key_receiver = MockInputReceiver::default();
         key_receiver
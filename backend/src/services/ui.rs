@@ -1,7 +1,8 @@
-use std::{collections::VecDeque, fmt::Debug, ops::DerefMut};
+use std::{collections::VecDeque, fmt::Debug, ops::DerefMut, path::PathBuf};
 
+use log::error;
 use opencv::{
-    core::Vector,
+    core::{Point, Rect, Vector},
     imgcodecs::{IMREAD_COLOR, IMREAD_GRAYSCALE, imdecode},
 };
 use tokio::sync::{broadcast::Receiver, oneshot::Sender};
@@ -9,12 +10,20 @@ use tokio::sync::{broadcast::Receiver, oneshot::Sender};
 #[cfg(debug_assertions)]
 use crate::DebugState;
 use crate::{
-    BotOperationUpdate, Character, GameState, GameTemplate, KeyBinding, NavigationPath, Request,
-    Response,
+    BotOperationUpdate, Character, GameState, GameTemplate, KeyBinding, NavigationPath, Position,
+    Request, Response,
+    database::upsert_settings,
     detect::to_base64_from_mat,
-    models::Map,
+    ecs::World,
+    minimap::Minimap,
+    models::{Map, Platform, PlayerMarker},
+    operation::Operation,
+    player::{Calibrating, Move, Player, PlayerAction},
     poll_request,
+    replay::{ReplayTrace, load_trace},
+    rng::Rng,
     services::{Event, EventContext, EventHandler},
+    tick::tick_rate,
 };
 
 #[derive(Debug)]
@@ -87,6 +96,13 @@ impl EventHandler<UiEvent> for UiEventHandler {
                 update_map(context, preset, map);
                 Response::UpdateMap
             }
+            Request::DetectMapPlatforms => {
+                Response::DetectMapPlatforms(detect_map_platforms(context))
+            }
+            Request::SamplePlayerMarker(x, y) => {
+                Response::SamplePlayerMarker(sample_player_marker(context, x, y))
+            }
+            Request::MovePlayerTo(x, y) => Response::MovePlayerTo(move_player_to(context, x, y)),
             Request::CreateNavigationPath => {
                 Response::CreateNavigationPath(create_navigation_path(context))
             }
@@ -102,6 +118,10 @@ impl EventHandler<UiEvent> for UiEventHandler {
                 update_character(context, character);
                 Response::UpdateCharacter
             }
+            Request::CalibrateMovement => {
+                calibrate_movement(context);
+                Response::CalibrateMovement
+            }
             Request::RedetectMinimap => {
                 redetect_map_minimap(context);
                 Response::RedetectMinimap
@@ -131,6 +151,33 @@ impl EventHandler<UiEvent> for UiEventHandler {
                 save_capture_image(context, is_grayscale);
                 Response::SaveCaptureImage
             }
+            Request::Shutdown => {
+                context.resources.input.send_all_keys_up();
+                context.rotator_service.flush_cooldowns();
+                Response::Shutdown
+            }
+            Request::RunGridLayout(layout) => {
+                let halting = context.resources.operation.halting();
+                if halting {
+                    context.grid_service.start(context.resources, &layout);
+                }
+                Response::RunGridLayout(halting)
+            }
+            Request::StopGridLayout => {
+                context.grid_service.stop(context.resources);
+                Response::StopGridLayout
+            }
+            Request::StartRecordingReplay => {
+                Response::StartRecordingReplay(start_recording_replay(context))
+            }
+            Request::StartReplaying(dir) => Response::StartReplaying(start_replaying(context, dir)),
+            Request::StopReplay => {
+                stop_replay(context);
+                Response::StopReplay
+            }
+            Request::QueryReplayStatus => {
+                Response::QueryReplayStatus(context.resources.replay.status())
+            }
             #[cfg(debug_assertions)]
             Request::DebugStateReceiver => {
                 Response::DebugStateReceiver(subscribe_debug_state(context))
@@ -160,6 +207,16 @@ impl EventHandler<UiEvent> for UiEventHandler {
                 test_spin_rune(context);
                 Response::TestSpinRune
             }
+            #[cfg(debug_assertions)]
+            Request::UpdateStepping(stepping) => {
+                update_stepping(context, stepping);
+                Response::UpdateStepping
+            }
+            #[cfg(debug_assertions)]
+            Request::Step(ticks) => {
+                step(context, ticks);
+                Response::Step
+            }
         };
 
         if let Some(response) = response {
@@ -208,6 +265,46 @@ fn update_map(context: &mut EventContext<'_>, preset: Option<String>, map: Optio
         .mark_dirty_with_destination(map.and_then(|map| map.paths_id_index));
 }
 
+fn detect_map_platforms(context: &mut EventContext<'_>) -> Vec<Platform> {
+    context
+        .map_service
+        .detect_platforms(context.resources, context.world.minimap.state)
+}
+
+fn sample_player_marker(context: &mut EventContext<'_>, x: i32, y: i32) -> Option<PlayerMarker> {
+    context
+        .map_service
+        .sample_player_marker(context.resources, context.world.minimap.state, x, y)
+}
+
+fn move_player_to(context: &mut EventContext<'_>, x: i32, y: i32) -> bool {
+    let Minimap::Idle(idle) = context.world.minimap.state else {
+        return false;
+    };
+    if !Rect::new(0, 0, idle.bbox.width, idle.bbox.height).contains(Point::new(x, y)) {
+        return false;
+    }
+
+    context.rotator.inject_action(PlayerAction::Move(Move {
+        position: Position {
+            x,
+            y,
+            allow_adjusting: true,
+            ..Position::default()
+        },
+        wait_after_move_ticks: 0,
+    }));
+    true
+}
+
+fn calibrate_movement(context: &mut EventContext<'_>) {
+    if !context.resources.operation.halting() || context.character_service.character().is_none() {
+        return;
+    }
+
+    context.world.player.state = Player::Calibrating(Calibrating::default());
+}
+
 fn redetect_map_minimap(context: &mut EventContext<'_>) {
     context.map_service.redetect(&mut context.world.minimap);
     context.navigator.mark_dirty(true);
@@ -262,6 +359,101 @@ fn update_character(context: &mut EventContext<'_>, character: Option<Character>
     rotator_service.apply(context.rotator.deref_mut(), map, character, &settings);
 }
 
+fn start_recording_replay(context: &mut EventContext<'_>) -> Option<String> {
+    if context.resources.replay.is_active() {
+        return None;
+    }
+
+    let trace = ReplayTrace {
+        rng_seed: rand::random(),
+        perlin_seed: rand::random(),
+        tick_rate: tick_rate(),
+        start_tick: context.resources.tick,
+        halting: context.resources.operation.halting(),
+        map_id: context.map_service.map().and_then(|map| map.id),
+        preset: context.map_service.preset(),
+        character_id: context
+            .character_service
+            .character()
+            .and_then(|character| character.id),
+        ticks: vec![],
+    };
+    reset_for_replay(context, &trace);
+
+    context
+        .resources
+        .replay
+        .start_recording(trace)
+        .inspect_err(|err| error!(target: "replay", "failed to start recording {err}"))
+        .ok()
+        .map(|dir| dir.to_string_lossy().into_owned())
+}
+
+fn start_replaying(context: &mut EventContext<'_>, dir: String) -> bool {
+    if context.resources.replay.is_active() {
+        return false;
+    }
+
+    let dir = PathBuf::from(dir);
+    let trace = match load_trace(&dir) {
+        Ok(trace) => trace,
+        Err(err) => {
+            error!(target: "replay", "failed to load recording {} {err}", dir.display());
+            return false;
+        }
+    };
+    let map_id = context.map_service.map().and_then(|map| map.id);
+    let character_id = context
+        .character_service
+        .character()
+        .and_then(|character| character.id);
+    if trace.map_id != map_id
+        || trace.preset != context.map_service.preset()
+        || trace.character_id != character_id
+        || trace.tick_rate != tick_rate()
+    {
+        error!(target: "replay", "selected map, preset, character or tick rate does not match recording");
+        return false;
+    }
+    reset_for_replay(context, &trace);
+
+    context
+        .resources
+        .replay
+        .start_replaying(dir, trace)
+        .inspect_err(|err| error!(target: "replay", "failed to start replaying {err}"))
+        .is_ok()
+}
+
+fn stop_replay(context: &mut EventContext<'_>) {
+    let _ = context
+        .resources
+        .replay
+        .stop()
+        .inspect_err(|err| error!(target: "replay", "failed to save recording {err}"));
+}
+
+/// Resets the systems to the same initial states before recording or replaying `trace`.
+fn reset_for_replay(context: &mut EventContext<'_>, trace: &ReplayTrace) {
+    let resources = &mut context.resources;
+    resources.input.send_all_keys_up();
+    resources.rng = Rng::new(trace.rng_seed, trace.perlin_seed);
+    resources.tick = trace.start_tick;
+    resources.operation = if trace.halting {
+        Operation::Halting
+    } else {
+        Operation::Running
+    };
+    *context.world = World::default();
+    context.rotator.reset_queue();
+
+    let map = context.map_service.map().cloned();
+    let preset = context.map_service.preset();
+    let character = context.character_service.character().cloned();
+    update_map(context, preset, map);
+    update_character(context, character);
+}
+
 fn subscribe_game_state(context: &mut EventContext<'_>) -> Receiver<GameState> {
     context.game_service.subscribe_state()
 }
@@ -271,8 +463,13 @@ fn subscribe_key(context: &mut EventContext<'_>) -> Receiver<KeyBinding> {
 }
 
 fn refresh_capture_handles(context: &mut EventContext<'_>) {
-    context.settings_service.update_windows();
-    select_capture_handle(context, None);
+    let settings_service = &mut context.settings_service;
+    settings_service.update_windows();
+    settings_service.apply_selected_window(
+        context.resources.input.as_mut(),
+        context.game_service.input_receiver_mut(),
+        context.capture.deref_mut(),
+    );
 }
 
 fn query_capture_handles(context: &mut EventContext<'_>) -> (Vec<String>, Option<usize>) {
@@ -292,6 +489,11 @@ fn select_capture_handle(context: &mut EventContext<'_>, index: Option<usize>) {
         context.game_service.input_receiver_mut(),
         context.capture.deref_mut(),
     );
+
+    let mut settings = settings_service.settings().clone();
+    if let Err(err) = upsert_settings(&mut settings) {
+        error!(target: "ui", "failed to save selected window {err}");
+    }
 }
 
 fn query_template(context: &mut EventContext<'_>, template: GameTemplate) -> String {
@@ -347,3 +549,15 @@ fn record_images(context: &mut EventContext<'_>, start: bool) {
 fn test_spin_rune(context: &mut EventContext<'_>) {
     context.debug_service.test_spin_rune();
 }
+
+#[cfg(debug_assertions)]
+fn update_stepping(context: &mut EventContext<'_>, stepping: bool) {
+    context
+        .debug_service
+        .set_stepping(context.resources, stepping);
+}
+
+#[cfg(debug_assertions)]
+fn step(context: &mut EventContext<'_>, ticks: u32) {
+    context.debug_service.step(context.resources, ticks);
+}
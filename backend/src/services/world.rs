@@ -1,13 +1,15 @@
 use std::fmt::Debug;
 
+use log::info;
 use tokio::sync::broadcast::Receiver;
 
 use super::EventContext;
 use crate::{
-    BotOperationUpdate,
+    AnnouncementReaction, BotOperationUpdate, Map,
     ecs::WorldEvent,
+    minimap::Minimap,
     notification::NotificationKind,
-    player::{PanicTo, Panicking, Player},
+    player::{Panic, PanicTo, Panicking, Player, PlayerAction},
     services::EventHandler,
 };
 
@@ -34,7 +36,15 @@ impl WorldService for DefaultWorldService {
     }
 }
 
-pub struct WorldEventHandler;
+#[derive(Default)]
+pub struct WorldEventHandler {
+    /// Whether handling [`WorldEvent::MinimapChanged`] is deferred until the minimap is detected
+    /// again.
+    ///
+    /// The minimap is expected to be hidden in maps using the screen fallback, so the change is
+    /// only handled if the minimap is of a different map when it returns.
+    deferred_minimap_change: bool,
+}
 
 impl EventHandler<WorldEvent> for WorldEventHandler {
     fn handle(&mut self, context: &mut EventContext<'_>, event: WorldEvent) {
@@ -82,36 +92,49 @@ impl EventHandler<WorldEvent> for WorldEventHandler {
                     );
                 }
             }
-            WorldEvent::MinimapChanged => {
+            WorldEvent::PlayerStuck => {
                 if context.resources.operation.halting() {
                     return;
                 }
 
+                info!(target: "world", "player is still stuck after all unstucking stages, halting");
+                context.operation_service.halt(
+                    context.resources,
+                    context.world,
+                    context.rotator,
+                    false,
+                );
                 let _ = context
                     .resources
                     .notification
-                    .schedule_notification(NotificationKind::FailOrMapChange);
-
-                if !context
-                    .settings_service
-                    .settings()
-                    .stop_on_fail_or_change_map
+                    .schedule_notification(NotificationKind::PlayerStuck);
+            }
+            WorldEvent::MinimapChanged => {
+                if context
+                    .world
+                    .player
+                    .context
+                    .config
+                    .ping_pong_screen_fallback
                 {
+                    self.deferred_minimap_change = true;
                     return;
                 }
 
-                let is_panicking = matches!(
-                    context.world.player.state,
-                    Player::Panicking(Panicking {
-                        to: PanicTo::Channel,
-                        ..
-                    })
-                );
-                if is_panicking {
+                handle_minimap_changed(context);
+            }
+            WorldEvent::MinimapDetected => {
+                if !self.deferred_minimap_change {
+                    return;
+                }
+                self.deferred_minimap_change = false;
+
+                if is_same_map(context.map_service.map(), context.world.minimap.state) {
                     return;
                 }
 
-                context.operation_service.queue_halt();
+                info!(target: "world", "minimap returned with a different map");
+                handle_minimap_changed(context);
             }
             WorldEvent::CaptureFailed => {
                 if context.resources.operation.halting() {
@@ -152,6 +175,114 @@ impl EventHandler<WorldEvent> for WorldEventHandler {
                         .schedule_notification(NotificationKind::EliteBossAppear);
                 }
             }
+            WorldEvent::AnnouncementAppeared(kind) => {
+                if context.resources.operation.halting() {
+                    return;
+                }
+
+                let settings = context.settings_service.settings();
+                let reaction = settings.announcement_reactions.get(kind);
+                info!(target: "world", "{kind} announcement appeared, reacting with {reaction}");
+                match reaction {
+                    AnnouncementReaction::Continue => (),
+                    AnnouncementReaction::Pause => {
+                        context.operation_service.apply(
+                            context.resources,
+                            context.world,
+                            context.rotator,
+                            &settings,
+                            BotOperationUpdate::TemporaryHalt,
+                        );
+                    }
+                    AnnouncementReaction::ChangeChannel => {
+                        if context.world.player.context.can_change_channel() {
+                            context.rotator.inject_action(PlayerAction::Panic(Panic {
+                                to: PanicTo::Channel,
+                            }));
+                        }
+                    }
+                }
+            }
         }
     }
 }
+
+fn handle_minimap_changed(context: &mut EventContext<'_>) {
+    if context.resources.operation.halting() {
+        return;
+    }
+
+    let _ = context
+        .resources
+        .notification
+        .schedule_notification(NotificationKind::FailOrMapChange);
+
+    if context.settings_service.settings().auto_switch_map {
+        context.map_service.queue_auto_switch();
+        return;
+    }
+
+    queue_halt_on_map_change(context);
+}
+
+/// Whether the detected `minimap` is of the same size as `map`.
+///
+/// Returns `true` if the minimap is not detected so that the change is not handled while unknown.
+fn is_same_map(map: Option<&Map>, minimap: Minimap) -> bool {
+    match minimap {
+        Minimap::Idle(idle) => {
+            map.is_some_and(|map| map.width == idle.bbox.width && map.height == idle.bbox.height)
+        }
+        Minimap::Detecting => true,
+    }
+}
+
+/// Queues a halt if the bot should stop when the map changed.
+pub fn queue_halt_on_map_change(context: &mut EventContext<'_>) {
+    if !context
+        .settings_service
+        .settings()
+        .stop_on_fail_or_change_map
+    {
+        return;
+    }
+
+    let is_panicking = matches!(
+        context.world.player.state,
+        Player::Panicking(Panicking {
+            to: PanicTo::Channel,
+            ..
+        })
+    );
+    if is_panicking {
+        return;
+    }
+
+    context.operation_service.queue_halt();
+}
+
+#[cfg(test)]
+mod tests {
+    use opencv::core::Rect;
+
+    use super::*;
+    use crate::minimap::MinimapIdle;
+
+    #[test]
+    fn is_same_map_compares_minimap_size() {
+        let map = Map {
+            width: 100,
+            height: 50,
+            ..Map::default()
+        };
+        let mut idle = MinimapIdle::default();
+        idle.bbox = Rect::new(10, 10, 100, 50);
+
+        assert!(is_same_map(Some(&map), Minimap::Idle(idle)));
+        assert!(is_same_map(Some(&map), Minimap::Detecting));
+        assert!(!is_same_map(None, Minimap::Idle(idle)));
+
+        idle.bbox = Rect::new(10, 10, 120, 50);
+        assert!(!is_same_map(Some(&map), Minimap::Idle(idle)));
+    }
+}
@@ -0,0 +1,414 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    sync::LazyLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Result, bail};
+use log::{error, info};
+use reqwest::{
+    StatusCode,
+    blocking::{Client, RequestBuilder, Response},
+    header::{ETAG, IF_MATCH, IF_NONE_MATCH},
+};
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+use crate::{
+    database,
+    models::{ConfigSync, Settings},
+};
+
+/// Maximum time to wait for each request to the server.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+static CLIENT: LazyLock<Client> = LazyLock::new(Client::new);
+
+/// The direction to sync the database in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display)]
+pub enum SyncDirection {
+    /// Uploads the local database to the server.
+    Push,
+    /// Replaces the local database with the one on the server.
+    Pull,
+}
+
+/// The outcome of syncing the database.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display)]
+pub enum SyncStatus {
+    Pushed,
+    Pulled,
+    /// Nothing changed since the last sync in the synced direction.
+    UpToDate,
+    /// Syncing would overwrite changes made since the last sync.
+    Conflict,
+}
+
+/// The state of the last sync on this machine.
+///
+/// This is stored next to the database instead of inside it so that pulling does not overwrite
+/// the state of this machine with the one that pushed.
+#[derive(Clone, PartialEq, Default, Debug, Serialize, Deserialize)]
+struct SyncState {
+    /// The `ETag` of the database on the server after the last sync.
+    etag: Option<String>,
+    /// The [`database::data_fingerprint`] after the last sync.
+    fingerprint: Option<u64>,
+}
+
+/// Syncs the database with the server of `config` in `direction`.
+///
+/// Returns [`SyncStatus::Conflict`] without syncing if it would overwrite changes made on the other
+/// side since the last sync, unless `force` is `true`.
+pub fn sync_database(
+    config: &ConfigSync,
+    direction: SyncDirection,
+    force: bool,
+) -> Result<SyncStatus> {
+    sync_database_inner(config, direction, force).inspect_err(|err| {
+        error!(target: "sync", "{direction} database with {} failed {err}", config.url);
+    })
+}
+
+fn sync_database_inner(
+    config: &ConfigSync,
+    direction: SyncDirection,
+    force: bool,
+) -> Result<SyncStatus> {
+    if config.url.is_empty() {
+        bail!("sync URL is not set");
+    }
+
+    let state = load_state();
+    let remote_etag = remote_etag(config)?;
+    if direction == SyncDirection::Pull && remote_etag.is_none() {
+        bail!("there is no database to pull from {}", config.url);
+    }
+
+    let fingerprint = database::data_fingerprint(shared_settings)?;
+    let status = sync_status(
+        direction,
+        &state,
+        remote_etag.as_deref(),
+        fingerprint,
+        force,
+    );
+    let state = match status {
+        SyncStatus::Pushed => {
+            let path = temp_database_path();
+            database::export_database_with_settings(&path, shared_settings)?;
+            let content = fs::read(&path);
+            let _ = fs::remove_file(&path);
+
+            let expected_etag = (!force).then_some(remote_etag.as_deref());
+            let Some(etag) = push(config, content?, expected_etag)? else {
+                return Ok(SyncStatus::Conflict);
+            };
+            SyncState {
+                etag,
+                fingerprint: Some(fingerprint),
+            }
+        }
+        SyncStatus::Pulled => {
+            let (content, etag) = pull(config)?;
+            let local = database::query_settings();
+            let path = temp_database_path();
+            let result = fs::write(&path, content)
+                .map_err(anyhow::Error::from)
+                .and_then(|_| {
+                    database::import_database_with_settings(&path, |settings| {
+                        with_local_settings(settings, &local)
+                    })
+                });
+            let _ = fs::remove_file(&path);
+            result?;
+
+            SyncState {
+                etag,
+                fingerprint: Some(database::data_fingerprint(shared_settings)?),
+            }
+        }
+        SyncStatus::UpToDate | SyncStatus::Conflict => return Ok(status),
+    };
+    save_state(&state)?;
+    info!(target: "sync", "{direction} database with {} succeeded", config.url);
+
+    Ok(status)
+}
+
+/// Removes the values of `settings` that are not synced.
+#[inline]
+fn shared_settings(settings: Settings) -> Settings {
+    with_local_settings(settings, &Settings::default())
+}
+
+/// Replaces the values of `settings` that are not synced with the ones from `local`.
+///
+/// Secrets are encrypted for the current user and cannot be decrypted on other machines. Capture,
+/// input and sync settings are specific to each machine.
+fn with_local_settings(mut settings: Settings, local: &Settings) -> Settings {
+    settings.capture_mode = local.capture_mode;
+    settings.capture_window = local.capture_window.clone();
+    settings.input_method = local.input_method;
+    settings.input_method_rpc_server_url = local.input_method_rpc_server_url.clone();
+    settings.keyboard_layout = local.keyboard_layout;
+    settings.discord_bot_access_token = local.discord_bot_access_token.clone();
+    settings.notifications.discord_webhook_url = local.notifications.discord_webhook_url.clone();
+    settings.metrics_export = local.metrics_export;
+    settings.config_sync = local.config_sync.clone();
+    settings
+}
+
+/// Determines the outcome of syncing in `direction` from the last sync `state`, the current
+/// `remote_etag` on the server and the current local `fingerprint`.
+fn sync_status(
+    direction: SyncDirection,
+    state: &SyncState,
+    remote_etag: Option<&str>,
+    fingerprint: u64,
+    force: bool,
+) -> SyncStatus {
+    let remote_changed = remote_etag.is_some() && remote_etag != state.etag.as_deref();
+    let local_changed = state.fingerprint != Some(fingerprint);
+
+    match direction {
+        SyncDirection::Push if force => SyncStatus::Pushed,
+        SyncDirection::Push if remote_changed => SyncStatus::Conflict,
+        SyncDirection::Push if remote_etag.is_some() && !local_changed => SyncStatus::UpToDate,
+        SyncDirection::Push => SyncStatus::Pushed,
+        SyncDirection::Pull if force => SyncStatus::Pulled,
+        SyncDirection::Pull if !remote_changed => SyncStatus::UpToDate,
+        SyncDirection::Pull if local_changed => SyncStatus::Conflict,
+        SyncDirection::Pull => SyncStatus::Pulled,
+    }
+}
+
+/// Retrieves the `ETag` of the database on the server or [`None`] if it does not exist.
+fn remote_etag(config: &ConfigSync) -> Result<Option<String>> {
+    let response = request(CLIENT.head(&config.url), config).send()?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let response = response.error_for_status()?;
+    match etag(&response) {
+        Some(etag) => Ok(Some(etag)),
+        None => bail!("server did not respond with an ETag"),
+    }
+}
+
+/// Uploads `content` to the server.
+///
+/// If `expected_etag` is provided, the upload only succeeds if the database on the server still
+/// has the same `ETag` or still does not exist. Returns [`None`] if it no longer does.
+fn push(
+    config: &ConfigSync,
+    content: Vec<u8>,
+    expected_etag: Option<Option<&str>>,
+) -> Result<Option<Option<String>>> {
+    let mut builder = request(CLIENT.put(&config.url), config).body(content);
+    match expected_etag {
+        Some(Some(etag)) => builder = builder.header(IF_MATCH, etag),
+        Some(None) => builder = builder.header(IF_NONE_MATCH, "*"),
+        None => (),
+    }
+
+    let response = builder.send()?;
+    if response.status() == StatusCode::PRECONDITION_FAILED {
+        return Ok(None);
+    }
+
+    let response = response.error_for_status()?;
+    // Not all servers respond with the new ETag when uploading
+    match etag(&response) {
+        Some(etag) => Ok(Some(Some(etag))),
+        None => Ok(Some(remote_etag(config)?)),
+    }
+}
+
+/// Downloads the database from the server along with its `ETag`.
+fn pull(config: &ConfigSync) -> Result<(Vec<u8>, Option<String>)> {
+    let response = request(CLIENT.get(&config.url), config)
+        .send()?
+        .error_for_status()?;
+    let etag = etag(&response);
+    let content = response.bytes()?.to_vec();
+
+    Ok((content, etag))
+}
+
+#[inline]
+fn request(builder: RequestBuilder, config: &ConfigSync) -> RequestBuilder {
+    let builder = builder.timeout(REQUEST_TIMEOUT);
+    if config.username.is_empty() {
+        builder
+    } else {
+        builder.basic_auth(&config.username, Some(&config.password))
+    }
+}
+
+#[inline]
+fn etag(response: &Response) -> Option<String> {
+    response
+        .headers()
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_string)
+}
+
+#[inline]
+fn state_path() -> PathBuf {
+    env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("sync_state.json")
+}
+
+fn temp_database_path() -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    env::temp_dir().join(format!("komari_sync_{timestamp}.db"))
+}
+
+fn load_state() -> SyncState {
+    fs::read_to_string(state_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &SyncState) -> Result<()> {
+    fs::write(state_path(), serde_json::to_string(state)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CaptureMode, InputMethod, KeyboardLayout};
+
+    fn synced_state() -> SyncState {
+        SyncState {
+            etag: Some("\"1\"".to_string()),
+            fingerprint: Some(1),
+        }
+    }
+
+    #[test]
+    fn sync_status_push() {
+        let state = synced_state();
+
+        assert_eq!(
+            sync_status(SyncDirection::Push, &state, Some("\"1\""), 1, false),
+            SyncStatus::UpToDate
+        );
+        assert_eq!(
+            sync_status(SyncDirection::Push, &state, Some("\"1\""), 2, false),
+            SyncStatus::Pushed
+        );
+        assert_eq!(
+            sync_status(SyncDirection::Push, &state, None, 1, false),
+            SyncStatus::Pushed
+        );
+        assert_eq!(
+            sync_status(SyncDirection::Push, &state, Some("\"2\""), 1, false),
+            SyncStatus::Conflict
+        );
+        assert_eq!(
+            sync_status(SyncDirection::Push, &state, Some("\"2\""), 2, true),
+            SyncStatus::Pushed
+        );
+    }
+
+    #[test]
+    fn sync_status_pull() {
+        let state = synced_state();
+
+        assert_eq!(
+            sync_status(SyncDirection::Pull, &state, Some("\"1\""), 2, false),
+            SyncStatus::UpToDate
+        );
+        assert_eq!(
+            sync_status(SyncDirection::Pull, &state, Some("\"2\""), 1, false),
+            SyncStatus::Pulled
+        );
+        assert_eq!(
+            sync_status(SyncDirection::Pull, &state, Some("\"2\""), 2, false),
+            SyncStatus::Conflict
+        );
+        assert_eq!(
+            sync_status(SyncDirection::Pull, &state, Some("\"1\""), 2, true),
+            SyncStatus::Pulled
+        );
+    }
+
+    #[test]
+    fn with_local_settings_keeps_secrets_and_machine_settings() {
+        let mut local = Settings {
+            capture_mode: CaptureMode::BitBltArea,
+            input_method: InputMethod::Rpc,
+            input_method_rpc_server_url: "http://localhost:5001".to_string(),
+            keyboard_layout: KeyboardLayout::Azerty,
+            discord_bot_access_token: "token".to_string(),
+            config_sync: ConfigSync {
+                url: "https://example.com/komari.db".to_string(),
+                username: "user".to_string(),
+                password: "password".to_string(),
+            },
+            ..Settings::default()
+        };
+        local.notifications.discord_webhook_url = "https://discord.com/webhook".to_string();
+        let remote = Settings {
+            id: Some(1),
+            enable_panic_mode: true,
+            tick_rate: 60,
+            ..Settings::default()
+        };
+
+        let settings = with_local_settings(remote, &local);
+        assert_eq!(settings.id, Some(1));
+        assert!(settings.enable_panic_mode);
+        assert_eq!(settings.tick_rate, 60);
+        assert_eq!(settings.capture_mode, local.capture_mode);
+        assert_eq!(settings.input_method, local.input_method);
+        assert_eq!(
+            settings.input_method_rpc_server_url,
+            local.input_method_rpc_server_url
+        );
+        assert_eq!(settings.keyboard_layout, local.keyboard_layout);
+        assert_eq!(settings.discord_bot_access_token, "token");
+        assert_eq!(
+            settings.notifications.discord_webhook_url,
+            "https://discord.com/webhook"
+        );
+        assert_eq!(settings.config_sync, local.config_sync);
+
+        let settings = shared_settings(local);
+        assert!(settings.input_method_rpc_server_url.is_empty());
+        assert!(settings.discord_bot_access_token.is_empty());
+        assert!(settings.notifications.discord_webhook_url.is_empty());
+        assert_eq!(settings.config_sync, ConfigSync::default());
+    }
+
+    #[test]
+    fn sync_status_first_sync() {
+        let state = SyncState::default();
+
+        assert_eq!(
+            sync_status(SyncDirection::Push, &state, None, 1, false),
+            SyncStatus::Pushed
+        );
+        assert_eq!(
+            sync_status(SyncDirection::Push, &state, Some("\"1\""), 1, false),
+            SyncStatus::Conflict
+        );
+        assert_eq!(
+            sync_status(SyncDirection::Pull, &state, Some("\"1\""), 1, false),
+            SyncStatus::Conflict
+        );
+    }
+}
@@ -1,26 +1,96 @@
 use std::{
+    cell::Cell,
     fmt::{self, Debug, Formatter},
-    sync::Arc,
-    time::Duration,
+    panic::{AssertUnwindSafe, catch_unwind},
+    sync::{
+        Arc, LazyLock, Mutex,
+        mpsc::{SyncSender, sync_channel},
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
 use anyhow::{Error, Result, anyhow};
+use log::error;
 use tokio::{
     spawn,
     sync::oneshot::{self, Receiver},
-    task::spawn_blocking,
     time::sleep,
 };
 
-use crate::{detect::Detector, ecs::Resources};
+use crate::{
+    detect::Detector, ecs::Resources, metrics::record_detection_duration, tick::ticks_from_millis,
+};
+
+/// Maximum number of worker threads running task functions.
+const MAX_WORKERS: usize = 4;
+
+/// Maximum number of task functions waiting for a free worker.
+///
+/// A task is not started and retried on the next update when the queue is full so that slow
+/// detections cannot pile up.
+const MAX_PENDING_JOBS: usize = 32;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The bounded pool of worker threads running task functions.
+static WORKERS: LazyLock<SyncSender<Job>> = LazyLock::new(|| {
+    let (tx, rx) = sync_channel::<Job>(MAX_PENDING_JOBS);
+    let rx = Arc::new(Mutex::new(rx));
+    let count = thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+        .clamp(1, MAX_WORKERS);
+
+    for index in 0..count {
+        let rx = rx.clone();
+        thread::Builder::new()
+            .name(format!("task-worker-{index}"))
+            .spawn(move || {
+                loop {
+                    let job = rx.lock().unwrap().recv();
+                    let Ok(job) = job else {
+                        break;
+                    };
+                    if catch_unwind(AssertUnwindSafe(job)).is_err() {
+                        error!(target: "task", "task function panicked");
+                    }
+                }
+            })
+            .expect("failed to spawn task worker");
+    }
+
+    tx
+});
+
+thread_local! {
+    static SYNCHRONOUS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets whether tasks started from the current thread run synchronously.
+///
+/// When synchronous, a task completes on the next update after being started and repeat delays
+/// are counted in updates instead of wall-clock time. This makes tasks complete on the same ticks
+/// across runs at the cost of blocking the caller.
+pub fn set_synchronous_tasks(synchronous: bool) {
+    SYNCHRONOUS.set(synchronous);
+}
+
+#[inline]
+fn synchronous_tasks() -> bool {
+    SYNCHRONOUS.get()
+}
 
 /// An asynchronous task.
 ///
-/// This is a simple wrapper around [`tokio::task::spawn`] and [`tokio::sync::oneshot`] mainly
-/// for using inside synchronous code to do blocking or expensive operation.
+/// This is a simple wrapper around [`tokio::task::spawn`] or the bounded worker pool and
+/// [`tokio::sync::oneshot`] mainly for using inside synchronous code to do blocking or expensive
+/// operation.
 #[derive(Debug)]
 pub struct Task<T> {
     rx: Receiver<T>,
+    /// Number of polls to skip before the result of a synchronous task is available.
+    pending_polls: u32,
 }
 
 impl<T: Debug> Task<T> {
@@ -33,7 +103,37 @@ impl<T: Debug> Task<T> {
         spawn(async move {
             let _ = tx.send(f.await);
         });
-        Task { rx }
+        Task {
+            rx,
+            pending_polls: 0,
+        }
+    }
+
+    /// Runs `f` on the worker pool.
+    ///
+    /// Returns [`None`] if there are already [`MAX_PENDING_JOBS`] waiting for a free worker.
+    fn spawn_worker<F>(f: F) -> Option<Task<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job = Box::new(move || {
+            let _ = tx.send(f());
+        });
+        WORKERS.try_send(job).ok()?;
+
+        Some(Task {
+            rx,
+            pending_polls: 0,
+        })
+    }
+
+    /// Creates an already completed task with `value` available after `pending_polls` polls.
+    fn ready(value: T, pending_polls: u32) -> Task<T> {
+        let (tx, rx) = oneshot::channel();
+        let _ = tx.send(value);
+        Task { rx, pending_polls }
     }
 
     pub fn completed(&self) -> bool {
@@ -44,6 +144,10 @@ impl<T: Debug> Task<T> {
         if self.rx.is_terminated() {
             return None;
         }
+        if self.pending_polls > 0 {
+            self.pending_polls -= 1;
+            return None;
+        }
 
         self.rx.try_recv().ok()
     }
@@ -95,7 +199,16 @@ where
 
     if matches!(update, Update::Pending) && task.as_ref().is_none_or(|task| task.completed()) {
         let should_delay = task.as_ref().is_some_and(|task| task.completed());
-        let spawned = if should_delay && repeat_delay_millis > 0 {
+        let spawned = if synchronous_tasks() {
+            if should_delay && repeat_delay_millis > 0 {
+                Task::ready(
+                    Err(anyhow!(DelayComplete)),
+                    ticks_from_millis(repeat_delay_millis),
+                )
+            } else {
+                Task::ready(task_fn(task_fn_args()), 0)
+            }
+        } else if should_delay && repeat_delay_millis > 0 {
             Task::spawn(async move {
                 sleep(Duration::from_millis(repeat_delay_millis)).await;
 
@@ -103,9 +216,12 @@ where
             })
         } else {
             let args = task_fn_args();
-            let fut = spawn_blocking(move || task_fn(args));
+            let Some(spawned) = Task::spawn_worker(move || task_fn(args)) else {
+                *task = None;
+                return update;
+            };
 
-            Task::spawn(async move { fut.await.unwrap() })
+            spawned
         };
 
         *task = Some(spawned);
@@ -114,6 +230,30 @@ where
     update
 }
 
+/// Updates `task` with the future from `task_fn` running on the tokio runtime.
+///
+/// Unlike [`update_task`], the future does not take a worker from the bounded pool so that slow
+/// network I/O cannot delay detections.
+#[inline]
+pub fn update_async_task<F, Fut, T>(task: &mut Option<Task<Result<T>>>, task_fn: F) -> Update<T>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T>> + Send + 'static,
+    T: Debug + Send + 'static,
+{
+    let update = match task.as_mut().and_then(|task| task.poll_inner()) {
+        Some(Ok(value)) => Update::Ok(value),
+        Some(Err(err)) => Update::Err(err),
+        None => Update::Pending,
+    };
+
+    if matches!(update, Update::Pending) && task.as_ref().is_none_or(|task| task.completed()) {
+        *task = Some(Task::spawn(task_fn()));
+    }
+
+    update
+}
+
 #[inline]
 pub fn update_detection_task<F, T>(
     resources: &Resources,
@@ -129,7 +269,12 @@ where
         repeat_delay_millis,
         task,
         || resources.detector_cloned(),
-        task_fn,
+        move |detector| {
+            let instant = Instant::now();
+            let result = task_fn(detector);
+            record_detection_duration(instant.elapsed());
+            result
+        },
     )
 }
 
@@ -140,7 +285,7 @@ mod tests {
     use anyhow::Result;
     use tokio::task::yield_now;
 
-    use crate::task::{Task, Update, update_task};
+    use crate::task::{Task, Update, set_synchronous_tasks, update_async_task, update_task};
 
     #[tokio::test(start_paused = true)]
     async fn spawn_state() {
@@ -157,6 +302,20 @@ mod tests {
         assert!(task.completed());
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn spawn_worker_state() {
+        let mut task = Task::spawn_worker(|| 1).unwrap();
+
+        while !task.completed() {
+            match task.poll_inner() {
+                Some(value) => assert_eq!(value, 1),
+                None => yield_now().await,
+            };
+        }
+        assert_matches!(task.poll_inner(), None);
+        assert!(task.completed());
+    }
+
     #[tokio::test(start_paused = true)]
     async fn update_task_repeatable_state() {
         let mut task = None::<Task<Result<u32>>>;
@@ -184,4 +343,50 @@ mod tests {
         );
         assert!(!task.as_ref().unwrap().completed());
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn update_async_task_state() {
+        let mut task = None::<Task<Result<u32>>>;
+
+        assert_matches!(
+            update_async_task(&mut task, || async { Ok(0) }),
+            Update::Pending
+        );
+        assert!(task.is_some());
+
+        loop {
+            match update_async_task(&mut task, || async { Ok(0) }) {
+                Update::Ok(value) => {
+                    assert_eq!(value, 0);
+                    break;
+                }
+                Update::Pending => yield_now().await,
+                Update::Err(_) => unreachable!(),
+            }
+        }
+        assert!(task.as_ref().unwrap().completed());
+
+        assert_matches!(
+            update_async_task(&mut task, || async { Ok(0) }),
+            Update::Pending
+        );
+        assert!(!task.as_ref().unwrap().completed());
+    }
+
+    #[test]
+    fn update_task_synchronous_state() {
+        set_synchronous_tasks(true);
+        let mut task = None::<Task<Result<u32>>>;
+
+        assert_matches!(
+            update_task(0, &mut task, || 1, |value| Ok(value + 1)),
+            Update::Pending
+        );
+        assert_matches!(
+            update_task(0, &mut task, || 1, |value| Ok(value + 1)),
+            Update::Ok(2)
+        );
+        assert!(task.as_ref().unwrap().completed());
+        set_synchronous_tasks(false);
+    }
 }
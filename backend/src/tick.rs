@@ -0,0 +1,164 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// The tick rate that all tick-based constants are tuned at.
+///
+/// Constants expressed in ticks should be passed through [`scaled`] before being compared
+/// against a running tick count so that they represent the same duration at other tick rates.
+pub const BASE_TICK_RATE: u32 = 30;
+
+/// The minimum tick rate the main loop can run at.
+pub const MIN_TICK_RATE: u32 = 20;
+
+/// The maximum tick rate the main loop can run at.
+pub const MAX_TICK_RATE: u32 = 60;
+
+/// The rate the main loop is throttled to while idle when the adaptive tick rate is enabled.
+///
+/// This is not the [`tick_rate`] itself so tick-based constants are still scaled with the
+/// configured rate.
+const IDLE_LOOP_RATE: u32 = 10;
+
+/// The rate the main loop is throttled to while waiting when the adaptive tick rate is enabled.
+const WAITING_LOOP_RATE: u32 = 15;
+
+static TICK_RATE: AtomicU32 = AtomicU32::new(BASE_TICK_RATE);
+
+static ADAPTIVE_TICK_RATE: AtomicBool = AtomicBool::new(false);
+
+/// How busy the main loop currently is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TickActivity {
+    /// Nothing depends on timely ticks such as when halting with the player idle or when there is
+    /// no frame to detect from.
+    Idle,
+    /// The player is running but only waiting such as being idle without an action or stalling.
+    Waiting,
+    /// The player is acting on the game such as moving or solving rune.
+    Active,
+}
+
+/// Gets the current tick rate of the main loop.
+#[inline]
+pub fn tick_rate() -> u32 {
+    TICK_RATE.load(Ordering::Relaxed)
+}
+
+/// Sets the tick rate of the main loop clamped to [`MIN_TICK_RATE`] and [`MAX_TICK_RATE`].
+pub fn set_tick_rate(rate: u32) {
+    TICK_RATE.store(rate.clamp(MIN_TICK_RATE, MAX_TICK_RATE), Ordering::Relaxed);
+}
+
+/// Sets whether the main loop is throttled while idle or waiting.
+pub fn set_adaptive_tick_rate(enabled: bool) {
+    ADAPTIVE_TICK_RATE.store(enabled, Ordering::Relaxed);
+}
+
+/// Gets the number of ticks the next iteration of the main loop accounts for during `activity`.
+///
+/// The main loop runs at [`tick_rate`] divided by this step so that a tick count still represents
+/// the same duration while throttled. This is always one unless the adaptive tick rate is enabled
+/// and the loop is not [`TickActivity::Active`].
+#[inline]
+pub fn tick_step(activity: TickActivity) -> u32 {
+    tick_step_at(
+        activity,
+        tick_rate(),
+        ADAPTIVE_TICK_RATE.load(Ordering::Relaxed),
+    )
+}
+
+#[inline]
+fn tick_step_at(activity: TickActivity, rate: u32, adaptive: bool) -> u32 {
+    let loop_rate = match activity {
+        TickActivity::Idle if adaptive => IDLE_LOOP_RATE,
+        TickActivity::Waiting if adaptive => WAITING_LOOP_RATE,
+        TickActivity::Idle | TickActivity::Waiting | TickActivity::Active => return 1,
+    };
+
+    (rate / loop_rate).max(1)
+}
+
+/// Milliseconds per tick as an [`u64`].
+#[inline]
+pub fn millis_per_tick() -> u64 {
+    millis_per_tick_f32() as u64
+}
+
+/// Milliseconds per tick as an [`f32`].
+#[inline]
+pub fn millis_per_tick_f32() -> f32 {
+    1000.0 / tick_rate() as f32
+}
+
+/// Converts `millis` to the number of ticks at the current tick rate.
+#[inline]
+pub fn ticks_from_millis(millis: u64) -> u32 {
+    (millis / millis_per_tick()) as u32
+}
+
+/// Converts `speed` in pixels per tick at the current tick rate to [`BASE_TICK_RATE`].
+#[inline]
+pub fn to_base_tick_rate_speed(speed: f32) -> f32 {
+    to_base_tick_rate_speed_at(speed, tick_rate())
+}
+
+#[inline]
+fn to_base_tick_rate_speed_at(speed: f32, rate: u32) -> f32 {
+    speed * rate as f32 / BASE_TICK_RATE as f32
+}
+
+/// Rescales `ticks` tuned at [`BASE_TICK_RATE`] to the current tick rate.
+#[inline]
+pub fn scaled(ticks: u32) -> u32 {
+    scaled_at(ticks, tick_rate())
+}
+
+#[inline]
+fn scaled_at(ticks: u32, rate: u32) -> u32 {
+    if ticks == 0 || rate == BASE_TICK_RATE {
+        return ticks;
+    }
+
+    let scaled = (ticks as u64 * rate as u64 + BASE_TICK_RATE as u64 / 2) / BASE_TICK_RATE as u64;
+    (scaled as u32).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaled_at_base_tick_rate_unchanged() {
+        for ticks in [0, 1, 3, 10, 35, 100] {
+            assert_eq!(scaled_at(ticks, BASE_TICK_RATE), ticks);
+        }
+    }
+
+    #[test]
+    fn scaled_at_other_tick_rates() {
+        assert_eq!(scaled_at(30, 60), 60);
+        assert_eq!(scaled_at(30, 20), 20);
+        assert_eq!(scaled_at(10, 45), 15);
+        assert_eq!(scaled_at(1, 20), 1);
+        assert_eq!(scaled_at(0, 60), 0);
+    }
+
+    #[test]
+    fn to_base_tick_rate_speed_at_other_tick_rates() {
+        assert_eq!(to_base_tick_rate_speed_at(1.0, BASE_TICK_RATE), 1.0);
+        assert_eq!(to_base_tick_rate_speed_at(0.5, 60), 1.0);
+        assert_eq!(to_base_tick_rate_speed_at(1.5, 20), 1.0);
+    }
+
+    #[test]
+    fn tick_step_at_throttles_only_when_adaptive() {
+        assert_eq!(tick_step_at(TickActivity::Idle, 30, true), 3);
+        assert_eq!(tick_step_at(TickActivity::Idle, 60, true), 6);
+        assert_eq!(tick_step_at(TickActivity::Idle, 30, false), 1);
+        assert_eq!(tick_step_at(TickActivity::Waiting, 30, true), 2);
+        assert_eq!(tick_step_at(TickActivity::Waiting, 20, true), 1);
+        assert_eq!(tick_step_at(TickActivity::Waiting, 60, false), 1);
+        assert_eq!(tick_step_at(TickActivity::Active, 30, true), 1);
+        assert_eq!(tick_step_at(TickActivity::Active, 60, false), 1);
+    }
+}
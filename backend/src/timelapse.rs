@@ -0,0 +1,466 @@
+//! Records a timelapse of each session for reviewing the bot behavior afterward.
+//!
+//! While running, an annotated frame is saved every [`SessionTimelapse::interval_millis`] along
+//! with a frame for each notable event such as solving a rune or the player dying. When the
+//! session ends, the frames are stitched into a timelapse video and a highlights video inside
+//! the session folder. Frames are annotated and saved on a separate thread.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{
+        LazyLock,
+        mpsc::{SyncSender, TrySendError, sync_channel},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, bail};
+use log::{debug, error, info};
+use opencv::{
+    core::{Mat, MatTraitConst, Point, Rect, Scalar, Size},
+    imgcodecs::{IMREAD_COLOR, imread, imwrite_def},
+    imgproc::{
+        COLOR_BGRA2BGR, FILLED, FONT_HERSHEY_SIMPLEX, INTER_AREA, LINE_8, cvt_color_def,
+        put_text_def, rectangle, resize,
+    },
+    videoio::{VideoWriter, VideoWriterTrait, VideoWriterTraitConst},
+};
+use strum::Display;
+
+use crate::{ecs::World, models::SessionTimelapse, operation::Operation, rotator::unix_millis_now};
+
+/// Maximum width of a saved frame, wider frames are downscaled.
+const MAX_FRAME_WIDTH: i32 = 960;
+
+/// Height of the caption bar drawn at the top of a saved frame.
+const CAPTION_HEIGHT: i32 = 24;
+
+/// Frames per second of the stitched videos.
+const VIDEO_FPS: f64 = 10.0;
+
+/// Number of times each highlight frame is repeated so that it stays visible for a while.
+const HIGHLIGHT_FRAME_REPEAT: usize = 20;
+
+/// Maximum number of frames waiting to be saved, frames are skipped while the queue is full.
+const MAX_PENDING_FRAMES: usize = 8;
+
+/// Maximum number of snapshots saved in a session, later snapshots are skipped.
+///
+/// This is 30 minutes of timelapse video at [`VIDEO_FPS`].
+const MAX_SNAPSHOTS: usize = 18_000;
+
+const SNAPSHOT_PREFIX: &str = "snapshot";
+
+const HIGHLIGHT_PREFIX: &str = "highlight";
+
+static TIMELAPSE_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
+    env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("timelapses")
+});
+
+/// A notable event saved as a highlight frame.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display)]
+pub enum TimelapseHighlight {
+    #[strum(to_string = "Rune solved")]
+    RuneSolved,
+    #[strum(to_string = "Player died")]
+    PlayerDied,
+}
+
+/// A captured frame waiting to be annotated and saved.
+#[derive(Debug)]
+struct PendingFrame {
+    frame: Mat,
+    caption: String,
+    path: PathBuf,
+}
+
+/// The frames saved for the current session.
+#[derive(Debug)]
+struct TimelapseSession {
+    dir: PathBuf,
+    started: Instant,
+    last_snapshot: Option<Instant>,
+    snapshots_count: usize,
+    highlights_count: usize,
+    tx: SyncSender<PendingFrame>,
+    writer: JoinHandle<()>,
+}
+
+impl TimelapseSession {
+    fn new() -> Self {
+        Self::new_in(TIMELAPSE_DIR.join(format!("session_{}", unix_millis_now())))
+    }
+
+    fn new_in(dir: PathBuf) -> Self {
+        let (tx, rx) = sync_channel::<PendingFrame>(MAX_PENDING_FRAMES);
+        let writer_dir = dir.clone();
+        let writer = thread::spawn(move || {
+            for PendingFrame {
+                frame,
+                caption,
+                path,
+            } in rx
+            {
+                let result = fs::create_dir_all(&writer_dir)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|_| annotate(&frame, &caption))
+                    .and_then(|mat| Ok(imwrite_def(path.to_str().unwrap(), &mat)?));
+                if let Err(err) = result {
+                    error!(target: "timelapse", "failed to save frame {} {err}", path.display());
+                }
+            }
+        });
+
+        Self {
+            dir,
+            started: Instant::now(),
+            last_snapshot: None,
+            snapshots_count: 0,
+            highlights_count: 0,
+            tx,
+            writer,
+        }
+    }
+
+    /// Queues `frame` of `world` to be saved.
+    ///
+    /// The frame is skipped if the writer is still busy with [`MAX_PENDING_FRAMES`] or if
+    /// [`MAX_SNAPSHOTS`] have already been saved for a snapshot.
+    fn save_frame(&mut self, frame: &Mat, world: &World, highlight: Option<TimelapseHighlight>) {
+        if highlight.is_none() && self.snapshots_count >= MAX_SNAPSHOTS {
+            return;
+        }
+
+        let (prefix, index) = match highlight {
+            Some(_) => (HIGHLIGHT_PREFIX, self.highlights_count + 1),
+            None => (SNAPSHOT_PREFIX, self.snapshots_count + 1),
+        };
+        let caption = caption(
+            self.started.elapsed(),
+            &world.player.state.to_string(),
+            world.player.context.last_known_pos,
+            highlight,
+        );
+        let path = self.dir.join(format!("{prefix}_{index:06}.jpg"));
+        let Ok(frame) = frame.try_clone() else {
+            return;
+        };
+
+        match self.tx.try_send(PendingFrame {
+            frame,
+            caption,
+            path,
+        }) {
+            Ok(()) => match highlight {
+                Some(_) => self.highlights_count = index,
+                None => self.snapshots_count = index,
+            },
+            Err(TrySendError::Full(_)) => {
+                debug!(target: "timelapse", "skipped frame while saving previous frames");
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                error!(target: "timelapse", "frame writer stopped unexpectedly");
+            }
+        }
+    }
+
+    /// Stitches the saved frames into videos in the background after all queued frames are
+    /// saved.
+    fn finish(self) {
+        let TimelapseSession {
+            dir,
+            snapshots_count,
+            highlights_count,
+            tx,
+            writer,
+            ..
+        } = self;
+        drop(tx);
+        if snapshots_count == 0 && highlights_count == 0 {
+            return;
+        }
+
+        thread::spawn(move || {
+            let _ = writer.join();
+            let videos = [
+                (SNAPSHOT_PREFIX, "timelapse.avi", 1),
+                (HIGHLIGHT_PREFIX, "highlights.avi", HIGHLIGHT_FRAME_REPEAT),
+            ];
+            for (prefix, name, repeat) in videos {
+                let output = dir.join(name);
+                match stitch_frames(&dir, prefix, &output, repeat) {
+                    Ok(true) => {
+                        info!(target: "timelapse", "saved {}", output.display());
+                    }
+                    Ok(false) => (),
+                    Err(err) => {
+                        error!(target: "timelapse", "failed to stitch {} {err}", output.display());
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Records a [`TimelapseSession`] from the captured frames.
+#[derive(Debug, Default)]
+pub struct TimelapseRecorder {
+    session: Option<TimelapseSession>,
+    was_player_dead: bool,
+}
+
+impl TimelapseRecorder {
+    /// Saves `frame` of `world` if a snapshot is due or a notable event happened.
+    ///
+    /// Frames are only saved while `timelapse` is enabled and `operation` is running. The session
+    /// ends when `operation` fully halts, so the stop phase of run/stop cycling does not split it.
+    pub fn update(
+        &mut self,
+        frame: Option<&Mat>,
+        world: &World,
+        operation: Operation,
+        timelapse: SessionTimelapse,
+    ) {
+        let is_player_dead = world.player.context.is_dead();
+        let highlight = next_highlight(
+            self.was_player_dead,
+            is_player_dead,
+            world.player.context.runes_solved_count(),
+        );
+        self.was_player_dead = is_player_dead;
+
+        if !timelapse.enabled || matches!(operation, Operation::Halting) {
+            if let Some(session) = self.session.take() {
+                session.finish();
+            }
+            return;
+        }
+
+        let session = self.session.get_or_insert_with(TimelapseSession::new);
+        let Some(frame) = frame else {
+            return;
+        };
+        if operation.halting() {
+            return;
+        }
+
+        let interval = Duration::from_millis(timelapse.interval_millis);
+        if session
+            .last_snapshot
+            .is_none_or(|instant| instant.elapsed() >= interval)
+        {
+            session.last_snapshot = Some(Instant::now());
+            session.save_frame(frame, world, None);
+        }
+        if highlight.is_some() {
+            session.save_frame(frame, world, highlight);
+        }
+    }
+}
+
+/// Determines the notable event that just happened, if any.
+///
+/// `runes_solved_count` is the number of runes solved since the count was last taken.
+#[inline]
+fn next_highlight(
+    was_player_dead: bool,
+    is_player_dead: bool,
+    runes_solved_count: u32,
+) -> Option<TimelapseHighlight> {
+    if !was_player_dead && is_player_dead {
+        Some(TimelapseHighlight::PlayerDied)
+    } else if runes_solved_count > 0 {
+        Some(TimelapseHighlight::RuneSolved)
+    } else {
+        None
+    }
+}
+
+/// Formats the caption of a frame saved `elapsed` into the session.
+fn caption(
+    elapsed: Duration,
+    player_state: &str,
+    position: Option<Point>,
+    highlight: Option<TimelapseHighlight>,
+) -> String {
+    let secs = elapsed.as_secs();
+    let mut caption = format!(
+        "{:02}:{:02}:{:02} | {player_state}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    );
+    if let Some(position) = position {
+        caption.push_str(&format!(" | {}, {}", position.x, position.y));
+    }
+    if let Some(highlight) = highlight {
+        caption.push_str(&format!(" | {highlight}"));
+    }
+
+    caption
+}
+
+/// Converts BGRA `frame` to a downscaled BGR frame with `caption` drawn at the top.
+fn annotate(frame: &Mat, caption: &str) -> Result<Mat> {
+    let mut mat = Mat::default();
+    cvt_color_def(frame, &mut mat, COLOR_BGRA2BGR)?;
+    if mat.cols() > MAX_FRAME_WIDTH {
+        let height = (mat.rows() * MAX_FRAME_WIDTH / mat.cols()).max(1);
+        let mut resized = Mat::default();
+        resize(
+            &mat,
+            &mut resized,
+            Size::new(MAX_FRAME_WIDTH, height),
+            0.0,
+            0.0,
+            INTER_AREA,
+        )?;
+        mat = resized;
+    }
+
+    let width = mat.cols();
+    rectangle(
+        &mut mat,
+        Rect::new(0, 0, width, CAPTION_HEIGHT),
+        Scalar::all(0.0),
+        FILLED,
+        LINE_8,
+        0,
+    )?;
+    put_text_def(
+        &mut mat,
+        caption,
+        Point::new(6, CAPTION_HEIGHT - 7),
+        FONT_HERSHEY_SIMPLEX,
+        0.5,
+        Scalar::new(255.0, 255.0, 255.0, 0.0),
+    )?;
+
+    Ok(mat)
+}
+
+/// Stitches the frames in `dir` with `prefix` into an MJPEG video at `output`.
+///
+/// Each frame is written `repeat` times. The frames are removed after stitching. Returns `false`
+/// if there is no frame to stitch.
+fn stitch_frames(dir: &Path, prefix: &str, output: &Path, repeat: usize) -> Result<bool> {
+    let mut frames = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(prefix) && name.ends_with(".jpg"))
+        })
+        .collect::<Vec<_>>();
+    if frames.is_empty() {
+        return Ok(false);
+    }
+    frames.sort();
+
+    let mut writer = None::<(VideoWriter, Size)>;
+    for path in &frames {
+        let mut mat = imread(path.to_str().unwrap(), IMREAD_COLOR)?;
+        let (writer, size) = match writer.as_mut() {
+            Some(writer) => writer,
+            None => {
+                let size = mat.size()?;
+                let fourcc = VideoWriter::fourcc('M', 'J', 'P', 'G')?;
+                let video =
+                    VideoWriter::new(output.to_str().unwrap(), fourcc, VIDEO_FPS, size, true)?;
+                if !video.is_opened()? {
+                    bail!("video writer cannot be opened");
+                }
+                writer.insert((video, size))
+            }
+        };
+        // The game window may be resized in the middle of a session
+        if mat.size()? != *size {
+            let mut resized = Mat::default();
+            resize(&mat, &mut resized, *size, 0.0, 0.0, INTER_AREA)?;
+            mat = resized;
+        }
+        for _ in 0..repeat {
+            writer.write(&mat)?;
+        }
+    }
+    if let Some((mut writer, _)) = writer {
+        writer.release()?;
+    }
+    for path in frames {
+        let _ = fs::remove_file(path);
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use opencv::core::CV_8UC4;
+
+    use super::*;
+
+    #[test]
+    fn timelapse_session_saves_frames_on_writer_up_to_cap() {
+        let dir = env::temp_dir().join("komari_timelapse_session");
+        let _ = fs::remove_dir_all(&dir);
+        let frame = Mat::new_rows_cols_with_default(100, 200, CV_8UC4, Scalar::all(0.0)).unwrap();
+        let world = World::default();
+        let mut session = TimelapseSession::new_in(dir.clone());
+
+        session.save_frame(&frame, &world, None);
+        session.save_frame(&frame, &world, Some(TimelapseHighlight::RuneSolved));
+        assert_eq!(session.snapshots_count, 1);
+        assert_eq!(session.highlights_count, 1);
+
+        // Snapshots are capped but highlights are still saved
+        session.snapshots_count = MAX_SNAPSHOTS;
+        session.save_frame(&frame, &world, None);
+        session.save_frame(&frame, &world, Some(TimelapseHighlight::PlayerDied));
+        assert_eq!(session.snapshots_count, MAX_SNAPSHOTS);
+        assert_eq!(session.highlights_count, 2);
+
+        drop(session.tx);
+        session.writer.join().unwrap();
+        assert!(dir.join("snapshot_000001.jpg").exists());
+        assert!(dir.join("highlight_000001.jpg").exists());
+        assert!(dir.join("highlight_000002.jpg").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn next_highlight_on_death_or_rune_solved() {
+        assert_eq!(next_highlight(false, false, 0), None);
+        assert_eq!(
+            next_highlight(false, true, 0),
+            Some(TimelapseHighlight::PlayerDied)
+        );
+        assert_eq!(next_highlight(true, true, 0), None);
+        assert_eq!(
+            next_highlight(true, true, 1),
+            Some(TimelapseHighlight::RuneSolved)
+        );
+    }
+
+    #[test]
+    fn caption_includes_elapsed_state_and_highlight() {
+        assert_eq!(
+            caption(Duration::from_secs(3725), "Idle", None, None),
+            "01:02:05 | Idle"
+        );
+        assert_eq!(
+            caption(
+                Duration::from_secs(59),
+                "SolvingRune",
+                Some(Point::new(10, 20)),
+                Some(TimelapseHighlight::RuneSolved)
+            ),
+            "00:00:59 | SolvingRune | 10, 20 | Rune solved"
+        );
+    }
+}
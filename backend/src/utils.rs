@@ -82,7 +82,7 @@ pub fn save_file_to<P: AsRef<Path>, C: AsRef<[u8]>>(contents: C, dir: DatasetDir
     let _ = fs::write(file, contents);
 }
 
-fn epoch_millis_as_string() -> String {
+pub fn epoch_millis_as_string() -> String {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
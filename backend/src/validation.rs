@@ -0,0 +1,749 @@
+use std::{fmt, path::Path};
+
+use opencv::core::Point;
+
+use crate::{
+    Action, ActionCondition, AnnouncementKind, AnnouncementReaction, Character, EliteBossBehavior,
+    IntoEnumIterator, KeyBinding, KeyBindingConfiguration, KeyTemplate, Localization, Map,
+    MobDetectionKind, MovementKeys, OtherPlayerReaction, RotationMode, Settings,
+    array::Array,
+    models::Platform,
+    pathing::{self, find_neighbors, find_points_with},
+    player::{
+        DOUBLE_JUMP_THRESHOLD, GRAPPLING_MAX_THRESHOLD, JUMP_THRESHOLD,
+        REFERENCE_DOUBLE_JUMP_SPEED, calibrated_threshold,
+    },
+};
+
+/// Label of the up jump key that can be bound to the up movement key.
+const UP_JUMP_LABEL: &str = "Up jump";
+
+/// Where a key binding is used in a [`Character`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyBindingUsage {
+    /// A fixed key binding of the character with its label.
+    Key(&'static str),
+    /// An action of the character at the index.
+    Action(usize),
+}
+
+impl fmt::Display for KeyBindingUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyBindingUsage::Key(label) => write!(f, "{label}"),
+            KeyBindingUsage::Action(index) => write!(f, "Action #{}", index + 1),
+        }
+    }
+}
+
+/// A possible misconfiguration of the key bindings of a [`Character`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyBindingWarning {
+    /// The same `key` is bound to multiple unrelated `usages`.
+    Conflict {
+        key: KeyBinding,
+        usages: Vec<KeyBindingUsage>,
+    },
+    /// The link keys of actions form a cycle through `keys`.
+    LinkKeyCycle { keys: Vec<KeyBinding> },
+    /// The `key` of `usage` is also used for moving.
+    MovementKey {
+        key: KeyBinding,
+        usage: KeyBindingUsage,
+    },
+}
+
+impl fmt::Display for KeyBindingWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyBindingWarning::Conflict { key, usages } => {
+                let usages = usages
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "Key {key} is used by multiple bindings: {usages}")
+            }
+            KeyBindingWarning::LinkKeyCycle { keys } => {
+                let keys = keys
+                    .iter()
+                    .chain(keys.first())
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" → ");
+                write!(f, "Link keys form a cycle: {keys}")
+            }
+            KeyBindingWarning::MovementKey { key, usage } => {
+                write!(f, "{usage} uses movement key {key}")
+            }
+        }
+    }
+}
+
+/// A possible misconfiguration of a [`Map`] found before running it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MapWarning {
+    /// The position of the action at `index` is outside of the minimap.
+    OutOfBounds { index: usize },
+    /// There is no path along the platforms to the normal action at `index` from the previous
+    /// positioned normal action at `from`.
+    Unreachable { index: usize, from: usize },
+    /// The action at `index` uses a key template that no longer exists.
+    MissingKeyTemplate { index: usize },
+    /// The action at `index` uses the plugin detector `name` that is not loaded.
+    MissingPluginDetector { index: usize, name: String },
+    /// The action at `index` uses the condition webhook `name` that does not exist.
+    MissingConditionWebhook { index: usize, name: String },
+    /// The enabled `feature` uses the character `key` that is not set.
+    MissingKey {
+        key: &'static str,
+        feature: &'static str,
+    },
+    /// The enabled `feature` uses the localization `template` that has no default.
+    MissingTemplate {
+        template: &'static str,
+        feature: &'static str,
+    },
+    /// The custom mob detection model at `path` does not exist.
+    MissingMobDetectionModel { path: String },
+}
+
+impl fmt::Display for MapWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = |index: usize| KeyBindingUsage::Action(index);
+        match self {
+            MapWarning::OutOfBounds { index } => {
+                write!(f, "{} is outside of the minimap", action(*index))
+            }
+            MapWarning::Unreachable { index, from } => write!(
+                f,
+                "{} cannot be reached from {} along the platforms",
+                action(*index),
+                action(*from)
+            ),
+            MapWarning::MissingKeyTemplate { index } => {
+                write!(f, "{} uses a deleted key template", action(*index))
+            }
+            MapWarning::MissingPluginDetector { index, name } => {
+                write!(
+                    f,
+                    "{} uses plugin detector {name} that is not loaded",
+                    action(*index)
+                )
+            }
+            MapWarning::MissingConditionWebhook { index, name } => write!(
+                f,
+                "{} uses condition webhook {name} that does not exist",
+                action(*index)
+            ),
+            MapWarning::MissingKey { key, feature } => {
+                write!(f, "{feature} needs the {key} key to be set")
+            }
+            MapWarning::MissingTemplate { template, feature } => {
+                write!(f, "{feature} needs the {template} localization template")
+            }
+            MapWarning::MissingMobDetectionModel { path } => {
+                write!(f, "Mob detection model {path} does not exist")
+            }
+        }
+    }
+}
+
+/// The data a [`Map`] is validated against along with the map itself.
+#[derive(Clone, Copy, Debug)]
+pub struct MapValidationArgs<'a> {
+    pub map: &'a Map,
+    /// The actions preset of [`Self::map`] to validate.
+    pub preset: &'a str,
+    pub character: Option<&'a Character>,
+    pub settings: &'a Settings,
+    pub localization: &'a Localization,
+    pub key_templates: &'a [KeyTemplate],
+    /// The names of the loaded plugin detectors.
+    pub plugin_detectors: &'a [String],
+}
+
+/// Validates the map and actions `preset` in `args` for problems found before running.
+///
+/// Reachability is only checked between consecutive positioned normal actions because priority
+/// actions can be queued from anywhere.
+pub fn validate_map(args: MapValidationArgs<'_>) -> Vec<MapWarning> {
+    let MapValidationArgs {
+        map,
+        preset,
+        character,
+        settings,
+        localization,
+        key_templates,
+        plugin_detectors,
+    } = args;
+    let actions = map
+        .actions
+        .get(preset)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let mut warnings = Vec::new();
+
+    for (index, action) in actions.iter().enumerate() {
+        if !action.enabled() {
+            continue;
+        }
+        if action_position(action).is_some_and(|point| {
+            point.x < 0 || point.x > map.width || point.y < 0 || point.y > map.height
+        }) {
+            warnings.push(MapWarning::OutOfBounds { index });
+        }
+
+        let Action::Key(key) = action else {
+            continue;
+        };
+        if key
+            .template_id
+            .is_some_and(|id| !key_templates.iter().any(|template| template.id == Some(id)))
+        {
+            warnings.push(MapWarning::MissingKeyTemplate { index });
+        }
+        if let Some(name) = key.plugin_detector
+            && !plugin_detectors.iter().any(|other| other == name.as_str())
+        {
+            warnings.push(MapWarning::MissingPluginDetector {
+                index,
+                name: name.as_str().to_string(),
+            });
+        }
+        if let Some(name) = key.condition_webhook
+            && !settings
+                .condition_webhooks
+                .iter()
+                .any(|webhook| webhook.name == name.as_str())
+        {
+            warnings.push(MapWarning::MissingConditionWebhook {
+                index,
+                name: name.as_str().to_string(),
+            });
+        }
+    }
+    warnings.extend(find_unreachable_actions(map, actions, character));
+
+    if let Some(character) = character {
+        let changes_channel = (settings.enable_panic_mode
+            && [
+                settings.other_player_same_platform_reaction,
+                settings.other_player_far_reaction,
+            ]
+            .contains(&OtherPlayerReaction::ChangeChannel))
+            || AnnouncementKind::iter().any(|kind| {
+                settings.announcement_reactions.get(kind) == AnnouncementReaction::ChangeChannel
+            });
+        if changes_channel && character.change_channel_key.is_none() {
+            warnings.push(MapWarning::MissingKey {
+                key: "Change channel",
+                feature: "Changing channel",
+            });
+        }
+        if character.familiars.enable_familiars_swapping && character.familiar_menu_key.is_none() {
+            warnings.push(MapWarning::MissingKey {
+                key: "Familiar menu",
+                feature: "Familiars swapping",
+            });
+        }
+    }
+
+    for kind in AnnouncementKind::iter() {
+        if settings.announcement_reactions.get(kind) != AnnouncementReaction::Continue
+            && localization.announcement_base64(kind).is_none()
+        {
+            warnings.push(MapWarning::MissingTemplate {
+                template: match kind {
+                    AnnouncementKind::WorldBoss => "World boss banner",
+                    AnnouncementKind::Invasion => "Invasion banner",
+                },
+                feature: "Announcement reactions",
+            });
+        }
+    }
+    if map.rotation_mode == RotationMode::AutoMobbing
+        && map.mob_detection.kind == MobDetectionKind::Model
+        && !Path::new(&map.mob_detection.model_path).is_file()
+    {
+        warnings.push(MapWarning::MissingMobDetectionModel {
+            path: map.mob_detection.model_path.clone(),
+        });
+    }
+
+    warnings
+}
+
+/// Finds the positioned normal actions in `actions` without a path along the platforms of `map`
+/// from the previous one.
+///
+/// The first action is reached from the last one since normal actions are rotated in a cycle.
+fn find_unreachable_actions(
+    map: &Map,
+    actions: &[Action],
+    character: Option<&Character>,
+) -> Vec<MapWarning> {
+    if map.platforms.is_empty() {
+        return vec![];
+    }
+
+    let double_jump_threshold = calibrated_threshold(
+        DOUBLE_JUMP_THRESHOLD,
+        character.and_then(|character| character.double_jump_speed),
+        REFERENCE_DOUBLE_JUMP_SPEED,
+    );
+    let platforms = Array::from_iter(find_neighbors(
+        &map.platforms
+            .iter()
+            .copied()
+            .map(Platform::into)
+            .collect::<Vec<pathing::Platform>>(),
+        double_jump_threshold,
+        JUMP_THRESHOLD,
+        GRAPPLING_MAX_THRESHOLD,
+    ));
+    let points = actions
+        .iter()
+        .enumerate()
+        .filter(|(_, action)| {
+            action.enabled() && matches!(action.condition(), ActionCondition::Any)
+        })
+        .filter_map(|(index, action)| Some(index).zip(action_position(action)))
+        .collect::<Vec<_>>();
+    if points.len() < 2 {
+        return vec![];
+    }
+
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(points.len() - 1))
+        .filter_map(|((index, point), (from, from_point))| {
+            find_points_with(
+                &platforms,
+                *from_point,
+                *point,
+                false,
+                double_jump_threshold,
+                JUMP_THRESHOLD,
+                GRAPPLING_MAX_THRESHOLD,
+            )
+            .is_none()
+            .then_some(MapWarning::Unreachable {
+                index: *index,
+                from: *from,
+            })
+        })
+        .collect()
+}
+
+#[inline]
+fn action_position(action: &Action) -> Option<Point> {
+    let position = match action {
+        Action::Move(action) => Some(action.position),
+        Action::Key(action) => action.position,
+    }?;
+    Some(Point::new(position.x, position.y))
+}
+
+/// Validates the key bindings of `character` for conflicts.
+///
+/// Link keys are not checked for conflicts because they are commonly the same as other keys (e.g.
+/// jump).
+pub fn validate_key_bindings(character: &Character) -> Vec<KeyBindingWarning> {
+    let bindings = character_key_bindings(character);
+    let mut warnings = Vec::new();
+
+    let mut conflicts = Vec::<(KeyBinding, Vec<KeyBindingUsage>)>::new();
+    for (i, (key, usage)) in bindings.iter().copied().enumerate() {
+        if conflicts
+            .iter()
+            .any(|(conflict_key, _)| *conflict_key == key)
+        {
+            continue;
+        }
+        let usages = bindings[i..]
+            .iter()
+            .filter(|(other_key, _)| *other_key == key)
+            .map(|(_, usage)| *usage)
+            .collect::<Vec<_>>();
+        let has_key_usage = usages
+            .iter()
+            .any(|usage| matches!(usage, KeyBindingUsage::Key(_)));
+        // Multiple actions sharing the same key with different conditions is intended
+        if usages.len() > 1 && has_key_usage {
+            conflicts.push((key, usages));
+        }
+    }
+    warnings.extend(
+        conflicts
+            .into_iter()
+            .map(|(key, usages)| KeyBindingWarning::Conflict { key, usages }),
+    );
+
+    let movement_usages = bindings.iter().copied().filter(|(key, usage)| {
+        is_movement_key(character.movement_keys, *key)
+            && !matches!(usage, KeyBindingUsage::Key(UP_JUMP_LABEL))
+    });
+    warnings
+        .extend(movement_usages.map(|(key, usage)| KeyBindingWarning::MovementKey { key, usage }));
+
+    let links = character
+        .actions
+        .iter()
+        .filter(|action| action.enabled)
+        .filter_map(|action| Some((action.key, action.link_key.key()?)))
+        .collect::<Vec<_>>();
+    warnings.extend(
+        find_link_key_cycles(&links)
+            .into_iter()
+            .map(|keys| KeyBindingWarning::LinkKeyCycle { keys }),
+    );
+
+    warnings
+}
+
+/// Collects the key bindings in use of `character` with where they are used.
+fn character_key_bindings(character: &Character) -> Vec<(KeyBinding, KeyBindingUsage)> {
+    let optional = |label: &'static str, config: Option<KeyBindingConfiguration>| {
+        config.map(|config| (config.key, KeyBindingUsage::Key(label)))
+    };
+    let enabled = |label: &'static str, config: KeyBindingConfiguration| {
+        config
+            .enabled
+            .then_some((config.key, KeyBindingUsage::Key(label)))
+    };
+    let required = |label: &'static str, config: KeyBindingConfiguration| {
+        Some((config.key, KeyBindingUsage::Key(label)))
+    };
+
+    let keys = [
+        optional("Rope lift", character.ropelift_key),
+        optional("Teleport", character.teleport_key),
+        required("Jump", character.jump_key),
+        optional(UP_JUMP_LABEL, character.up_jump_key),
+        required("Interact", character.interact_key),
+        optional("Cash shop", character.cash_shop_key),
+        optional("To town", character.to_town_key),
+        optional("Change channel", character.change_channel_key),
+        optional("Familiar menu", character.familiar_menu_key),
+        enabled("Feed pet", character.feed_pet_key),
+        enabled("Potion", character.potion_key),
+        enabled("Generic booster", character.generic_booster_key),
+        enabled("HEXA booster", character.hexa_booster_key),
+        enabled("Familiar skill", character.familiar_buff_key),
+        required("Familiar essence", character.familiar_essence_key),
+        enabled("Sayram's Elixir", character.sayram_elixir_key),
+        enabled("Aurelia's Elixir", character.aurelia_elixir_key),
+        enabled("2x EXP Coupon", character.exp_x2_key),
+        enabled("3x EXP Coupon", character.exp_x3_key),
+        enabled("4x EXP Coupon", character.exp_x4_key),
+        enabled("50% Bonus EXP Coupon", character.bonus_exp_key),
+        enabled("Legion's Wealth", character.legion_wealth_key),
+        enabled("Legion's Luck", character.legion_luck_key),
+        enabled(
+            "Wealth Acquisition Potion",
+            character.wealth_acquisition_potion_key,
+        ),
+        enabled(
+            "EXP Accumulation Potion",
+            character.exp_accumulation_potion_key,
+        ),
+        enabled(
+            "Small Wealth Acquisition Potion",
+            character.small_wealth_acquisition_potion_key,
+        ),
+        enabled(
+            "Small EXP Accumulation Potion",
+            character.small_exp_accumulation_potion_key,
+        ),
+        enabled("For The Guild", character.for_the_guild_key),
+        enabled("Hard Hitter", character.hard_hitter_key),
+        enabled("Extreme Red Potion", character.extreme_red_potion_key),
+        enabled("Extreme Blue Potion", character.extreme_blue_potion_key),
+        enabled("Extreme Green Potion", character.extreme_green_potion_key),
+        enabled("Extreme Gold Potion", character.extreme_gold_potion_key),
+        (character.elite_boss_behavior == EliteBossBehavior::UseKey).then_some((
+            character.elite_boss_behavior_key,
+            KeyBindingUsage::Key("Elite boss spawns behavior"),
+        )),
+    ];
+    let actions = character
+        .actions
+        .iter()
+        .enumerate()
+        .filter(|(_, action)| action.enabled)
+        .map(|(i, action)| (action.key, KeyBindingUsage::Action(i)));
+
+    keys.into_iter().flatten().chain(actions).collect()
+}
+
+/// Finds the distinct cycles formed by following `links` of `(key, link key)`.
+///
+/// Each cycle starts from its key first appearing in `links`.
+fn find_link_key_cycles(links: &[(KeyBinding, KeyBinding)]) -> Vec<Vec<KeyBinding>> {
+    fn visit(
+        links: &[(KeyBinding, KeyBinding)],
+        path: &mut Vec<KeyBinding>,
+        cycles: &mut Vec<Vec<KeyBinding>>,
+    ) {
+        let key = *path.last().expect("not empty");
+        for (_, next) in links.iter().filter(|(from, _)| *from == key) {
+            if let Some(start) = path.iter().position(|key| key == next) {
+                let cycle = normalize_cycle(links, &path[start..]);
+                if !cycles.contains(&cycle) {
+                    cycles.push(cycle);
+                }
+                continue;
+            }
+
+            path.push(*next);
+            visit(links, path, cycles);
+            path.pop();
+        }
+    }
+
+    let mut cycles = Vec::new();
+    for (key, _) in links {
+        visit(links, &mut vec![*key], &mut cycles);
+    }
+
+    cycles
+}
+
+/// Rotates `cycle` to start from its key first appearing in `links`.
+fn normalize_cycle(links: &[(KeyBinding, KeyBinding)], cycle: &[KeyBinding]) -> Vec<KeyBinding> {
+    let start = cycle
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, key)| links.iter().position(|(from, _)| from == *key))
+        .map(|(i, _)| i)
+        .unwrap_or_default();
+
+    cycle[start..]
+        .iter()
+        .chain(&cycle[..start])
+        .copied()
+        .collect()
+}
+
+#[inline]
+fn is_movement_key(keys: MovementKeys, key: KeyBinding) -> bool {
+    [keys.up, keys.down, keys.left, keys.right].contains(&key)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        ActionConfiguration, ActionKey, ActionMove, ConditionWebhook, ConditionWebhookName,
+        LinkKeyBinding, Position,
+    };
+
+    fn action(key: KeyBinding, link_key: LinkKeyBinding) -> ActionConfiguration {
+        ActionConfiguration {
+            key,
+            link_key,
+            enabled: true,
+            ..ActionConfiguration::default()
+        }
+    }
+
+    #[test]
+    fn validate_key_bindings_conflict_with_fixed_key() {
+        let character = Character {
+            jump_key: KeyBindingConfiguration {
+                key: KeyBinding::C,
+                enabled: true,
+            },
+            interact_key: KeyBindingConfiguration {
+                key: KeyBinding::Y,
+                enabled: true,
+            },
+            familiar_essence_key: KeyBindingConfiguration {
+                key: KeyBinding::F,
+                enabled: true,
+            },
+            actions: vec![
+                action(KeyBinding::C, LinkKeyBinding::None),
+                action(KeyBinding::D, LinkKeyBinding::Before(KeyBinding::C)),
+                action(KeyBinding::D, LinkKeyBinding::None),
+            ],
+            ..Character::default()
+        };
+
+        assert_eq!(
+            validate_key_bindings(&character),
+            vec![KeyBindingWarning::Conflict {
+                key: KeyBinding::C,
+                usages: vec![KeyBindingUsage::Key("Jump"), KeyBindingUsage::Action(0)],
+            }]
+        );
+    }
+
+    #[test]
+    fn validate_key_bindings_movement_key_except_up_jump() {
+        let character = Character {
+            up_jump_key: Some(KeyBindingConfiguration {
+                key: KeyBinding::Up,
+                enabled: true,
+            }),
+            teleport_key: Some(KeyBindingConfiguration {
+                key: KeyBinding::Left,
+                enabled: true,
+            }),
+            familiar_essence_key: KeyBindingConfiguration {
+                key: KeyBinding::F,
+                enabled: true,
+            },
+            ..Character::default()
+        };
+
+        assert_eq!(
+            validate_key_bindings(&character),
+            vec![KeyBindingWarning::MovementKey {
+                key: KeyBinding::Left,
+                usage: KeyBindingUsage::Key("Teleport"),
+            }]
+        );
+    }
+
+    #[test]
+    fn validate_key_bindings_remapped_movement_key() {
+        let character = Character {
+            movement_keys: MovementKeys {
+                left: KeyBinding::A,
+                right: KeyBinding::D,
+                up: KeyBinding::W,
+                down: KeyBinding::S,
+            },
+            teleport_key: Some(KeyBindingConfiguration {
+                key: KeyBinding::Left,
+                enabled: true,
+            }),
+            familiar_essence_key: KeyBindingConfiguration {
+                key: KeyBinding::D,
+                enabled: true,
+            },
+            ..Character::default()
+        };
+
+        assert_eq!(
+            validate_key_bindings(&character),
+            vec![KeyBindingWarning::MovementKey {
+                key: KeyBinding::D,
+                usage: KeyBindingUsage::Key("Familiar essence"),
+            }]
+        );
+    }
+
+    fn move_to(x: i32, y: i32) -> Action {
+        Action::Move(ActionMove {
+            position: Position {
+                x,
+                y,
+                ..Position::default()
+            },
+            ..ActionMove::default()
+        })
+    }
+
+    fn map_with(actions: Vec<Action>, platforms: Vec<Platform>) -> Map {
+        Map {
+            width: 200,
+            height: 200,
+            platforms,
+            actions: HashMap::from([("preset".to_string(), actions)]),
+            ..Map::default()
+        }
+    }
+
+    fn validate(map: &Map, settings: &Settings) -> Vec<MapWarning> {
+        validate_map(MapValidationArgs {
+            map,
+            preset: "preset",
+            character: None,
+            settings,
+            localization: &Localization::default(),
+            key_templates: &[],
+            plugin_detectors: &[],
+        })
+    }
+
+    #[test]
+    fn validate_map_out_of_bounds_and_missing_references() {
+        let webhook = Action::Key(ActionKey {
+            condition_webhook: ConditionWebhookName::new("Market"),
+            template_id: Some(1),
+            position: Some(Position {
+                x: 250,
+                ..Position::default()
+            }),
+            ..ActionKey::default()
+        });
+        let map = map_with(vec![move_to(10, 10), webhook, move_to(-1, 10)], vec![]);
+        let settings = Settings {
+            condition_webhooks: vec![ConditionWebhook {
+                name: "Other".to_string(),
+                ..ConditionWebhook::default()
+            }],
+            ..Settings::default()
+        };
+
+        assert_eq!(
+            validate(&map, &settings),
+            vec![
+                MapWarning::OutOfBounds { index: 1 },
+                MapWarning::MissingKeyTemplate { index: 1 },
+                MapWarning::MissingConditionWebhook {
+                    index: 1,
+                    name: "Market".to_string(),
+                },
+                MapWarning::OutOfBounds { index: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_map_unreachable_upper_platform() {
+        let platforms = vec![
+            Platform {
+                x_start: 0,
+                x_end: 100,
+                y: 10,
+            },
+            Platform {
+                x_start: 0,
+                x_end: 100,
+                y: 150,
+            },
+        ];
+        let map = map_with(vec![move_to(50, 150), move_to(50, 10)], platforms);
+
+        assert_eq!(
+            validate(&map, &Settings::default()),
+            vec![MapWarning::Unreachable { index: 0, from: 1 }]
+        );
+    }
+
+    #[test]
+    fn find_link_key_cycles_distinct() {
+        let links = [
+            (KeyBinding::A, KeyBinding::B),
+            (KeyBinding::B, KeyBinding::C),
+            (KeyBinding::C, KeyBinding::A),
+            (KeyBinding::D, KeyBinding::D),
+            (KeyBinding::E, KeyBinding::A),
+        ];
+
+        assert_eq!(
+            find_link_key_cycles(&links),
+            vec![
+                vec![KeyBinding::A, KeyBinding::B, KeyBinding::C],
+                vec![KeyBinding::D],
+            ]
+        );
+        assert!(find_link_key_cycles(&[(KeyBinding::A, KeyBinding::B)]).is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, bail};
+use log::debug;
+use reqwest::Client;
+
+use crate::{
+    models::ConditionWebhook,
+    task::{Task, Update, update_async_task},
+};
+
+/// Maximum number of bytes read from a response body.
+///
+/// The body only needs to say whether the action is allowed so anything larger is rejected.
+const MAX_RESPONSE_BYTES: usize = 1024;
+
+static CLIENT: LazyLock<Client> = LazyLock::new(Client::new);
+
+/// Gates the use of an action on the response of a [`ConditionWebhook`].
+///
+/// The endpoint is polled in an asynchronous [`Task`] and the last response is reused until
+/// [`ConditionWebhook::cache_millis`] has passed.
+#[derive(Debug)]
+pub struct ConditionWebhookGate {
+    webhook: ConditionWebhook,
+    task: Option<Task<Result<bool>>>,
+    /// The last response along with the [`Instant`] it was received.
+    last_response: Option<(Instant, bool)>,
+}
+
+impl ConditionWebhookGate {
+    pub fn new(webhook: ConditionWebhook) -> Self {
+        Self {
+            webhook,
+            task: None,
+            last_response: None,
+        }
+    }
+
+    /// Whether the gate currently allows the action.
+    ///
+    /// The gate stays closed while waiting for the first response after the cached one expired
+    /// and when the endpoint fails to respond.
+    pub fn update(&mut self) -> bool {
+        if let Some((instant, allowed)) = self.last_response
+            && instant.elapsed() < Duration::from_millis(self.webhook.cache_millis)
+        {
+            return allowed;
+        }
+
+        let url = self.webhook.url.clone();
+        let timeout = Duration::from_millis(self.webhook.timeout_millis);
+        let allowed = match update_async_task(&mut self.task, move || poll(url, timeout)) {
+            Update::Ok(allowed) => allowed,
+            Update::Err(err) => {
+                debug!(target: "rotator", "condition webhook {} failed {err}", self.webhook.name);
+                false
+            }
+            Update::Pending => return false,
+        };
+        self.last_response = Some((Instant::now(), allowed));
+
+        allowed
+    }
+}
+
+async fn poll(url: String, timeout: Duration) -> Result<bool> {
+    let mut response = CLIENT.get(url).timeout(timeout).send().await?;
+    let success = response.status().is_success();
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_RESPONSE_BYTES as u64)
+    {
+        bail!("response body is larger than {MAX_RESPONSE_BYTES} bytes");
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        append_capped(&mut body, &chunk)?;
+    }
+
+    Ok(is_allowed(success, &String::from_utf8_lossy(&body)))
+}
+
+/// Appends `chunk` to `body` or fails if `body` would exceed [`MAX_RESPONSE_BYTES`].
+#[inline]
+fn append_capped(body: &mut Vec<u8>, chunk: &[u8]) -> Result<()> {
+    if body.len() + chunk.len() > MAX_RESPONSE_BYTES {
+        bail!("response body is larger than {MAX_RESPONSE_BYTES} bytes");
+    }
+    body.extend_from_slice(chunk);
+
+    Ok(())
+}
+
+/// Whether a response with `success` status and `body` allows the action.
+#[inline]
+fn is_allowed(success: bool, body: &str) -> bool {
+    success && !matches!(body.trim().to_ascii_lowercase().as_str(), "false" | "0")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_allowed_by_status_and_body() {
+        assert!(is_allowed(true, ""));
+        assert!(is_allowed(true, "true"));
+        assert!(is_allowed(true, "{\"buy\":true}"));
+        assert!(!is_allowed(true, " False\n"));
+        assert!(!is_allowed(true, "0"));
+        assert!(!is_allowed(false, "true"));
+    }
+
+    #[test]
+    fn append_capped_rejects_large_body() {
+        let mut body = Vec::new();
+
+        assert!(append_capped(&mut body, &[b'a'; MAX_RESPONSE_BYTES - 1]).is_ok());
+        assert!(append_capped(&mut body, b"a").is_ok());
+        assert!(append_capped(&mut body, b"a").is_err());
+        assert_eq!(body.len(), MAX_RESPONSE_BYTES);
+    }
+
+    #[test]
+    fn update_reuses_cached_response() {
+        let mut gate = ConditionWebhookGate::new(ConditionWebhook {
+            cache_millis: 60000,
+            ..ConditionWebhook::default()
+        });
+        gate.last_response = Some((Instant::now(), true));
+
+        assert!(gate.update());
+        assert!(gate.task.is_none());
+    }
+}
@@ -0,0 +1,404 @@
+//! Headless command-line frontend for driving the backend without the Dioxus UI.
+//!
+//! Useful when the webview fails to initialize (e.g. under Wine or some VM setups).
+
+use std::{env, io::stdin, pin::pin, thread, time::Instant};
+
+use backend::{BotOperation, BotOperationUpdate, Character, GameState, Map};
+use fern::Dispatch;
+use log::LevelFilter;
+use tokio::{
+    signal,
+    sync::{broadcast::error::RecvError, mpsc},
+    task::spawn_blocking,
+};
+
+const HELP: &str = "\
+Commands:
+  characters                List all characters
+  maps                      List all maps and their presets
+  character <name>          Select a character
+  map <name> [preset]       Select a map and optionally a preset
+  preset <name>             Select a preset of the current map
+  start                     Start the bot
+  stop                      Stop the bot
+  finish                    Stop the bot after the current action
+  status                    Print the current status
+  tail [on|off]             Toggle printing of state changes
+  help                      Print this help
+  quit                      Release held keys and exit (also Ctrl+C)";
+
+const USAGE: &str = "\
+Usage: komari-cli [options]
+
+Options:
+  --character <name>   Select a character on startup
+  --map <name>         Select a map on startup
+  --preset <name>      Select a preset of the map on startup
+  --start              Start the bot after selecting
+  --tail               Print state changes on startup
+  --debug              Enable debug logging
+  --help               Print this help";
+
+#[derive(Default, Debug)]
+struct Args {
+    character: Option<String>,
+    map: Option<String>,
+    preset: Option<String>,
+    start: bool,
+    tail: bool,
+    debug: bool,
+}
+
+#[derive(Default, Debug)]
+struct Cli {
+    map: Option<Map>,
+    preset: Option<String>,
+    tail: bool,
+    state: Option<GameState>,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("{message}");
+            }
+            println!("{USAGE}");
+            return;
+        }
+    };
+
+    init_logging(args.debug);
+    backend::init();
+
+    let mut cli = Cli {
+        tail: args.tail,
+        ..Cli::default()
+    };
+    if let Some(name) = args.character.as_deref() {
+        select_character(name).await;
+    }
+    if let Some(name) = args.map.as_deref() {
+        select_map(&mut cli, name, args.preset.as_deref()).await;
+    } else if args.preset.is_some() {
+        println!("--preset requires --map");
+    }
+    if args.start {
+        backend::update_operation(BotOperationUpdate::Run).await;
+    }
+
+    let (command_tx, mut command_rx) = mpsc::channel::<String>(8);
+    thread::spawn(move || {
+        for line in stdin().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if command_tx.blocking_send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut state_rx = backend::game_state_receiver().await;
+    let mut ctrl_c = pin!(signal::ctrl_c());
+    let mut ctrl_c_listening = true;
+    println!("Type `help` for a list of commands");
+    loop {
+        tokio::select! {
+            result = &mut ctrl_c, if ctrl_c_listening => {
+                ctrl_c_listening = false;
+                match result {
+                    Ok(()) => break,
+                    Err(err) => eprintln!("Failed to listen for Ctrl+C {err}"),
+                }
+            }
+            command = command_rx.recv() => {
+                let Some(command) = command else {
+                    break;
+                };
+                if !handle_command(&mut cli, command.trim()).await {
+                    break;
+                }
+            }
+            state = state_rx.recv() => {
+                match state {
+                    Ok(state) => update_state(&mut cli, state),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+
+    backend::update_operation(BotOperationUpdate::Halt).await;
+    // Releases the keys held by the bot so that none stays held down in the game after exiting
+    let _ = spawn_blocking(backend::shutdown).await;
+}
+
+fn init_logging(debug: bool) {
+    let level = if debug || cfg!(debug_assertions) {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Info
+    };
+    let mut dispatch = Dispatch::new()
+        .format(|out, message, record| {
+            out.finish(format_args!(
+                "[{} {} {}] {}",
+                humantime::format_rfc3339(std::time::SystemTime::now()),
+                record.level(),
+                record.target(),
+                message
+            ))
+        })
+        .level(level)
+        .chain(std::io::stderr());
+    if let Some(dir) = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()))
+        && let Ok(file) = fern::log_file(dir.join("log.txt"))
+    {
+        dispatch = dispatch.chain(file);
+    }
+    dispatch.apply().unwrap();
+    log_panics::init();
+}
+
+fn parse_args(mut iter: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args::default();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--character" => args.character = Some(arg_value(&mut iter, &arg)?),
+            "--map" => args.map = Some(arg_value(&mut iter, &arg)?),
+            "--preset" => args.preset = Some(arg_value(&mut iter, &arg)?),
+            "--start" => args.start = true,
+            "--tail" => args.tail = true,
+            "--debug" => args.debug = true,
+            "--help" | "-h" => return Err(String::new()),
+            _ => return Err(format!("Unknown option `{arg}`")),
+        }
+    }
+
+    Ok(args)
+}
+
+fn arg_value(iter: &mut impl Iterator<Item = String>, arg: &str) -> Result<String, String> {
+    iter.next()
+        .filter(|value| !value.starts_with("--"))
+        .ok_or_else(|| format!("Missing value for `{arg}`"))
+}
+
+/// Handles a single command line and returns `false` if the CLI should exit.
+async fn handle_command(cli: &mut Cli, line: &str) -> bool {
+    let (command, rest) = line
+        .split_once(char::is_whitespace)
+        .map(|(command, rest)| (command, rest.trim()))
+        .unwrap_or((line, ""));
+
+    match command {
+        "" => (),
+        "characters" => {
+            for character in backend::query_characters().await.unwrap_or_default() {
+                println!("{}", character.name);
+            }
+        }
+        "maps" => {
+            for map in backend::query_maps().await.unwrap_or_default() {
+                let mut presets = map.actions.keys().cloned().collect::<Vec<_>>();
+                presets.sort();
+                println!("{} [{}]", map.name, presets.join(", "));
+            }
+        }
+        "character" if !rest.is_empty() => select_character(rest).await,
+        "map" if !rest.is_empty() => {
+            let (name, preset) = split_map_preset(rest);
+            select_map(cli, name, preset).await;
+        }
+        "preset" if !rest.is_empty() => {
+            let Some(map) = cli.map.clone() else {
+                println!("No map selected");
+                return true;
+            };
+            if !map.actions.contains_key(rest) {
+                println!("Preset `{rest}` not found in map `{}`", map.name);
+                return true;
+            }
+            cli.preset = Some(rest.to_string());
+            backend::update_map(cli.preset.clone(), Some(map)).await;
+            println!("Selected preset `{rest}`");
+        }
+        "start" => backend::update_operation(BotOperationUpdate::Run).await,
+        "stop" => backend::update_operation(BotOperationUpdate::Halt).await,
+        "finish" => backend::update_operation(BotOperationUpdate::SoftHalt).await,
+        "status" => print_status(cli),
+        "tail" => {
+            cli.tail = match rest {
+                "on" => true,
+                "off" => false,
+                _ => !cli.tail,
+            };
+            println!("Tail {}", if cli.tail { "on" } else { "off" });
+        }
+        "help" => println!("{HELP}"),
+        "quit" | "exit" => return false,
+        _ => println!("Unknown command `{line}`, type `help` for a list of commands"),
+    }
+
+    true
+}
+
+/// Splits `<name> [preset]` where `name` can be quoted to contain spaces.
+fn split_map_preset(rest: &str) -> (&str, Option<&str>) {
+    if let Some(quoted) = rest.strip_prefix('"')
+        && let Some((name, preset)) = quoted.split_once('"')
+    {
+        let preset = preset.trim();
+        return (name, (!preset.is_empty()).then_some(preset));
+    }
+
+    match rest.split_once(char::is_whitespace) {
+        Some((name, preset)) => (name, Some(preset.trim())),
+        None => (rest, None),
+    }
+}
+
+async fn select_character(name: &str) {
+    let character = backend::query_characters()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .find(|character: &Character| character.name == name);
+    if character.is_none() {
+        println!("Character `{name}` not found");
+        return;
+    }
+
+    backend::update_character(character).await;
+    println!("Selected character `{name}`");
+}
+
+async fn select_map(cli: &mut Cli, name: &str, preset: Option<&str>) {
+    let Some(map) = backend::query_maps()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .find(|map| map.name == name)
+    else {
+        println!("Map `{name}` not found");
+        return;
+    };
+
+    let preset = match preset {
+        Some(preset) if !map.actions.contains_key(preset) => {
+            println!("Preset `{preset}` not found in map `{name}`");
+            return;
+        }
+        Some(preset) => Some(preset.to_string()),
+        None => {
+            let mut presets = map.actions.keys().cloned().collect::<Vec<_>>();
+            presets.sort();
+            presets.into_iter().next()
+        }
+    };
+
+    println!(
+        "Selected map `{name}` with preset `{}`",
+        preset.as_deref().unwrap_or("None")
+    );
+    cli.map = Some(map.clone());
+    cli.preset = preset.clone();
+    backend::update_map(preset, Some(map)).await;
+}
+
+fn update_state(cli: &mut Cli, state: GameState) {
+    if cli.tail
+        && cli.state.as_ref().is_none_or(|prev| {
+            prev.state != state.state
+                || prev.normal_action != state.normal_action
+                || prev.priority_action != state.priority_action
+                || prev.operation != state.operation
+        })
+    {
+        println!("{}", format_state(&state));
+    }
+    cli.state = Some(state);
+}
+
+fn print_status(cli: &Cli) {
+    println!(
+        "Map: {}, preset: {}",
+        cli.map
+            .as_ref()
+            .map(|map| map.name.as_str())
+            .unwrap_or("None"),
+        cli.preset.as_deref().unwrap_or("None")
+    );
+    match cli.state.as_ref() {
+        Some(state) => {
+            println!("{}", format_state(state));
+            if let Some((x, y)) = state.position {
+                println!("Position: ({x}, {y})");
+            }
+            if let Some((current, max)) = state.health {
+                println!("Health: {current} / {max}");
+            }
+        }
+        None => println!("No state received yet"),
+    }
+}
+
+fn format_state(state: &GameState) -> String {
+    format!(
+        "[{}] State: {}, normal action: {}, priority action: {}",
+        format_operation(state.operation),
+        state.state,
+        state.normal_action.as_deref().unwrap_or("None"),
+        state.priority_action.as_deref().unwrap_or("None"),
+    )
+}
+
+fn format_operation(operation: BotOperation) -> String {
+    let now = Instant::now();
+    match operation {
+        BotOperation::Halting => "Halting".to_string(),
+        BotOperation::TemporaryHalting(duration) => {
+            format!("Temporary halting for {}s", duration.as_secs())
+        }
+        BotOperation::HaltUntil(instant) => format!(
+            "Halting, running in {}s",
+            instant.saturating_duration_since(now).as_secs()
+        ),
+        BotOperation::Running => "Running".to_string(),
+        BotOperation::Stopping => "Stopping after current action".to_string(),
+        BotOperation::RunUntil(instant) => format!(
+            "Running, halting in {}s",
+            instant.saturating_duration_since(now).as_secs()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn format_operation_cycle() {
+        let instant = Instant::now() + Duration::from_secs(30);
+
+        assert!(
+            format_operation(BotOperation::HaltUntil(instant)).starts_with("Halting, running in")
+        );
+        assert!(
+            format_operation(BotOperation::RunUntil(instant)).starts_with("Running, halting in")
+        );
+        assert_eq!(
+            format_operation(BotOperation::TemporaryHalting(Duration::from_secs(5))),
+            "Temporary halting for 5s"
+        );
+    }
+}
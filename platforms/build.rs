@@ -1,4 +1,12 @@
 fn main() {
+    // Enables `cfg(linux)` when building for Linux with the `linux` feature
+    println!("cargo::rustc-check-cfg=cfg(linux)");
+    if std::env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os == "linux")
+        && std::env::var_os("CARGO_FEATURE_LINUX").is_some()
+    {
+        println!("cargo::rustc-cfg=linux");
+    }
+
     #[cfg(windows)]
     {
         let mut res = tauri_winres::WindowsResource::new();
@@ -1,6 +1,12 @@
+#[cfg(not(any(windows, linux)))]
+use crate::Error;
+#[cfg(linux)]
+use crate::linux::{X11Capture, query_capture_handles};
 #[cfg(windows)]
-use crate::windows::{BitBltCapture, WgcCapture, WindowBoxCapture, WindowsCapture};
-use crate::{Error, Result, Window, windows::query_capture_name_handle_pairs};
+use crate::windows::{
+    BitBltCapture, WgcCapture, WindowBoxCapture, WindowsCapture, query_capture_handles,
+};
+use crate::{Result, Window};
 
 #[derive(Debug, Clone)]
 pub struct Frame {
@@ -10,6 +16,15 @@ pub struct Frame {
     // TODO: Color format? Currently always BGRA
 }
 
+/// A visible window that can be captured.
+#[derive(Debug, Clone)]
+pub struct CaptureWindow {
+    pub title: String,
+    /// The executable path of the process owning the window if it can be queried.
+    pub process_path: Option<String>,
+    pub window: Window,
+}
+
 #[cfg(windows)]
 #[derive(Debug, Clone, Copy)]
 pub enum WindowsCaptureKind {
@@ -26,51 +41,63 @@ pub struct Capture {
     windows: WindowsCapture,
     #[cfg(windows)]
     windows_kind: WindowsCaptureKind,
+
+    #[cfg(linux)]
+    linux: X11Capture,
 }
 
 impl Capture {
     pub fn new(window: Window) -> Result<Self> {
-        if cfg!(windows) {
-            return Ok(Self {
-                window,
-                windows: WindowsCapture::BitBlt(BitBltCapture::new(window.windows, false)),
-                windows_kind: WindowsCaptureKind::BitBlt,
-            });
-        }
-
-        Err(Error::PlatformNotSupported)
+        #[cfg(windows)]
+        return Ok(Self {
+            window,
+            windows: WindowsCapture::BitBlt(BitBltCapture::new(window.windows, false)),
+            windows_kind: WindowsCaptureKind::BitBlt,
+        });
+        #[cfg(linux)]
+        return Ok(Self {
+            window,
+            linux: X11Capture::new(window.linux),
+        });
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
     }
 
     #[inline]
     pub fn grab(&mut self) -> Result<Frame> {
-        if cfg!(windows) {
-            return self.windows.grab();
-        }
-
-        Err(Error::PlatformNotSupported)
+        #[cfg(windows)]
+        return self.windows.grab();
+        #[cfg(linux)]
+        return self.linux.grab();
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
     }
 
     #[inline]
     pub fn window(&self) -> Result<Window> {
-        if cfg!(windows) {
-            return match &self.windows {
-                WindowsCapture::Wgc(_) | WindowsCapture::BitBlt(_) => Ok(self.window),
-                WindowsCapture::BitBltArea(capture) => Ok(capture.handle().into()),
-            };
-        }
-
-        Err(Error::PlatformNotSupported)
+        #[cfg(windows)]
+        return match &self.windows {
+            WindowsCapture::Wgc(_) | WindowsCapture::BitBlt(_) => Ok(self.window),
+            WindowsCapture::BitBltArea(capture) => Ok(capture.handle().into()),
+        };
+        #[cfg(linux)]
+        return Ok(self.window);
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
     }
 
     #[inline]
     pub fn set_window(&mut self, window: Window) -> Result<()> {
         self.window = window;
 
-        if cfg!(windows) {
-            return self.windows_capture_kind(self.windows_kind);
-        }
-
-        Err(Error::PlatformNotSupported)
+        #[cfg(windows)]
+        return self.windows_capture_kind(self.windows_kind);
+        #[cfg(linux)]
+        self.linux = X11Capture::new(window.linux);
+        #[cfg(linux)]
+        return Ok(());
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
     }
 
     #[cfg(windows)]
@@ -90,13 +117,16 @@ impl Capture {
     }
 }
 
-pub fn query_capture_name_window_pairs() -> Result<Vec<(String, Window)>> {
-    if cfg!(windows) {
-        return Ok(query_capture_name_handle_pairs()
-            .into_iter()
-            .map(|(name, handle)| (name, handle.into()))
-            .collect::<Vec<_>>());
-    }
-
-    Err(Error::PlatformNotSupported)
+pub fn query_capture_windows() -> Result<Vec<CaptureWindow>> {
+    #[cfg(any(windows, linux))]
+    return Ok(query_capture_handles()
+        .into_iter()
+        .map(|(title, process_path, handle)| CaptureWindow {
+            title,
+            process_path,
+            window: handle.into(),
+        })
+        .collect::<Vec<_>>());
+    #[cfg(not(any(windows, linux)))]
+    return Err(Error::PlatformNotSupported);
 }
@@ -0,0 +1,26 @@
+#[cfg(not(windows))]
+use crate::Error;
+use crate::Result;
+#[cfg(windows)]
+use crate::windows;
+
+/// Gets the text content of the system clipboard.
+///
+/// Returns [`None`] if the clipboard does not contain any text.
+#[inline]
+pub fn text() -> Result<Option<String>> {
+    #[cfg(windows)]
+    return windows::clipboard_text();
+    #[cfg(not(windows))]
+    return Err(Error::PlatformNotSupported);
+}
+
+/// Replaces the content of the system clipboard with `text`.
+#[inline]
+#[cfg_attr(not(windows), allow(unused_variables))]
+pub fn set_text(text: &str) -> Result<()> {
+    #[cfg(windows)]
+    return windows::set_clipboard_text(text);
+    #[cfg(not(windows))]
+    return Err(Error::PlatformNotSupported);
+}
@@ -0,0 +1,27 @@
+#[cfg(not(windows))]
+use crate::Error;
+use crate::Result;
+#[cfg(windows)]
+use crate::windows;
+
+/// Encrypts `data` so that it can only be decrypted by the current user on the current machine.
+///
+/// On Windows, this uses the Data Protection API (DPAPI).
+#[inline]
+#[cfg_attr(not(windows), allow(unused_variables))]
+pub fn protect(data: &[u8]) -> Result<Vec<u8>> {
+    #[cfg(windows)]
+    return windows::protect(data);
+    #[cfg(not(windows))]
+    return Err(Error::PlatformNotSupported);
+}
+
+/// Decrypts `data` previously encrypted by [`protect`].
+#[inline]
+#[cfg_attr(not(windows), allow(unused_variables))]
+pub fn unprotect(data: &[u8]) -> Result<Vec<u8>> {
+    #[cfg(windows)]
+    return windows::unprotect(data);
+    #[cfg(not(windows))]
+    return Err(Error::PlatformNotSupported);
+}
@@ -0,0 +1,73 @@
+#[cfg(not(windows))]
+use crate::Error;
+#[cfg(windows)]
+use crate::windows::WindowsGamepad;
+use crate::{Result, input::KeyState};
+
+/// A button on a virtual Xbox 360 controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    A,
+    B,
+    X,
+    Y,
+    LeftShoulder,
+    RightShoulder,
+    /// Fully pulls the left trigger.
+    LeftTrigger,
+    /// Fully pulls the right trigger.
+    RightTrigger,
+    Back,
+    Start,
+    LeftThumb,
+    RightThumb,
+    DpadUp,
+    DpadDown,
+    DpadLeft,
+    DpadRight,
+}
+
+/// A virtual controller plugged in through the ViGEm bus driver.
+///
+/// The controller is unplugged when dropped.
+#[derive(Debug)]
+pub struct Gamepad {
+    #[cfg(windows)]
+    windows: WindowsGamepad,
+}
+
+impl Gamepad {
+    /// Connects to the ViGEm bus driver and plugs in a new virtual controller.
+    pub fn new() -> Result<Self> {
+        #[cfg(windows)]
+        return Ok(Self {
+            windows: WindowsGamepad::new()?,
+        });
+        #[cfg(not(windows))]
+        return Err(Error::PlatformNotSupported);
+    }
+
+    /// Retrieves the current state of `button`.
+    pub fn button_state(&self, button: GamepadButton) -> Result<KeyState> {
+        #[cfg(windows)]
+        return Ok(self.windows.button_state(button));
+        #[cfg(not(windows))]
+        return Err(Error::PlatformNotSupported);
+    }
+
+    /// Holds down `button`.
+    pub fn send_button_down(&self, button: GamepadButton) -> Result<()> {
+        #[cfg(windows)]
+        return self.windows.send_button(button, true);
+        #[cfg(not(windows))]
+        return Err(Error::PlatformNotSupported);
+    }
+
+    /// Releases `button`.
+    pub fn send_button_up(&self, button: GamepadButton) -> Result<()> {
+        #[cfg(windows)]
+        return self.windows.send_button(button, false);
+        #[cfg(not(windows))]
+        return Err(Error::PlatformNotSupported);
+    }
+}
@@ -1,3 +1,7 @@
+use std::{sync::Mutex, time::Instant};
+
+#[cfg(linux)]
+use crate::linux::{LinuxInput, LinuxInputReceiver};
 use crate::{Error, Result, Window};
 #[cfg(windows)]
 use crate::{windows::WindowsInput, windows::WindowsInputReceiver};
@@ -7,6 +11,10 @@ pub enum MouseKind {
     Move,
     Click,
     Scroll,
+    /// Presses and holds the left mouse button.
+    Down,
+    /// Releases the left mouse button.
+    Up,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -15,7 +23,22 @@ pub enum KeyState {
     Released,
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Keys that do not count as physical input when used.
+static PHYSICAL_INPUT_IGNORED_KEYS: Mutex<Vec<KeyKind>> = Mutex::new(Vec::new());
+
+/// Sets the `keys` that do not update [`InputReceiver::last_physical_input`] when used.
+///
+/// Useful for hotkeys that control the bot itself.
+pub fn set_physical_input_ignored_keys(keys: Vec<KeyKind>) {
+    *PHYSICAL_INPUT_IGNORED_KEYS.lock().unwrap() = keys;
+}
+
+#[inline]
+pub(crate) fn is_physical_input_ignored(key: KeyKind) -> bool {
+    PHYSICAL_INPUT_IGNORED_KEYS.lock().unwrap().contains(&key)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KeyKind {
     A,
     B,
@@ -88,6 +111,7 @@ pub enum KeyKind {
     Comma,
     Period,
     Slash,
+    Minus,
     Esc,
     Shift,
     Alt,
@@ -109,44 +133,52 @@ pub enum InputKind {
 pub struct Input {
     #[cfg(windows)]
     windows: WindowsInput,
+    #[cfg(linux)]
+    linux: LinuxInput,
 }
 
 impl Input {
     pub fn new(window: Window, kind: InputKind) -> Result<Self> {
-        if cfg!(windows) {
-            return Ok(Self {
-                windows: WindowsInput::new(window.windows, kind),
-            });
-        }
-
-        Err(Error::PlatformNotSupported)
+        #[cfg(windows)]
+        return Ok(Self {
+            windows: WindowsInput::new(window.windows, kind),
+        });
+        #[cfg(linux)]
+        return Ok(Self {
+            linux: LinuxInput::new(window.linux, kind),
+        });
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
     }
 
     /// Sends mouse `kind` with coordinates `x`, `y` in relative to the provided [`Window`].
     pub fn send_mouse(&self, x: i32, y: i32, kind: MouseKind) -> Result<()> {
-        if cfg!(windows) {
-            return self.windows.send_mouse(x, y, kind);
-        }
-
-        Err(Error::PlatformNotSupported)
+        #[cfg(windows)]
+        return self.windows.send_mouse(x, y, kind);
+        #[cfg(linux)]
+        return self.linux.send_mouse(x, y, kind);
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
     }
 
     /// Retrieves the current state of key `kind`.
     pub fn key_state(&self, kind: KeyKind) -> Result<KeyState> {
-        if cfg!(windows) {
-            return self.windows.key_state(kind);
-        }
-
-        Err(Error::PlatformNotSupported)
+        #[cfg(windows)]
+        return self.windows.key_state(kind);
+        #[cfg(linux)]
+        return self.linux.key_state(kind);
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
     }
 
     /// Sends a single key press `kind`.
     pub fn send_key(&self, kind: KeyKind) -> Result<()> {
-        if cfg!(windows) {
-            return self.windows.send_key(kind);
-        }
-
-        Err(Error::PlatformNotSupported)
+        #[cfg(windows)]
+        return self.windows.send_key(kind);
+        #[cfg(linux)]
+        return self.linux.send_key(kind);
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
     }
 
     /// Holds down key `kind`.
@@ -154,20 +186,22 @@ impl Input {
     /// If `repeatable` is `true`, consecutive calls will continue to send the down stroke even if
     /// the key is already down.
     pub fn send_key_down(&self, kind: KeyKind, repeatable: bool) -> Result<()> {
-        if cfg!(windows) {
-            return self.windows.send_key_down(kind, repeatable);
-        }
-
-        Ok(())
+        #[cfg(windows)]
+        return self.windows.send_key_down(kind, repeatable);
+        #[cfg(linux)]
+        return self.linux.send_key_down(kind, repeatable);
+        #[cfg(not(any(windows, linux)))]
+        return Ok(());
     }
 
     /// Releases key `kind`.
     pub fn send_key_up(&self, kind: KeyKind) -> Result<()> {
-        if cfg!(windows) {
-            return self.windows.send_key_up(kind);
-        }
-
-        Err(Error::PlatformNotSupported)
+        #[cfg(windows)]
+        return self.windows.send_key_up(kind);
+        #[cfg(linux)]
+        return self.linux.send_key_up(kind);
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
     }
 }
 
@@ -175,25 +209,58 @@ impl Input {
 pub struct InputReceiver {
     #[cfg(windows)]
     windows: WindowsInputReceiver,
+    #[cfg(linux)]
+    linux: LinuxInputReceiver,
 }
 
 impl InputReceiver {
     pub fn new(window: Window, input_kind: InputKind) -> Result<Self> {
-        if cfg!(windows) {
-            return Ok(Self {
-                windows: WindowsInputReceiver::new(window.windows, input_kind),
-            });
-        }
+        #[cfg(windows)]
+        return Ok(Self {
+            windows: WindowsInputReceiver::new(window.windows, input_kind),
+        });
+        #[cfg(linux)]
+        return Ok(Self {
+            linux: LinuxInputReceiver::new(window.linux, input_kind),
+        });
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
+    }
 
-        Err(Error::PlatformNotSupported)
+    /// Creates a receiver that receives key strokes regardless of the foreground window.
+    pub fn new_global() -> Result<Self> {
+        #[cfg(windows)]
+        return Ok(Self {
+            windows: WindowsInputReceiver::new_global(),
+        });
+        #[cfg(linux)]
+        return Ok(Self {
+            linux: LinuxInputReceiver::new_global(),
+        });
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
     }
 
     /// Attempts to receive a key stroke previously sent from the OS.
     pub fn try_recv(&mut self) -> Result<KeyKind> {
-        if cfg!(windows) {
-            return self.windows.try_recv().ok_or(Error::KeyNotReceived);
-        }
+        #[cfg(windows)]
+        return self.windows.try_recv().ok_or(Error::KeyNotReceived);
+        #[cfg(linux)]
+        return self.linux.try_recv().ok_or(Error::KeyNotReceived);
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
+    }
 
-        Err(Error::PlatformNotSupported)
+    /// Gets the last time a key or the mouse was physically used, excluding injected inputs.
+    ///
+    /// Returns [`None`] for a global receiver or when its window is not in the foreground. Mouse
+    /// inputs are only tracked on Windows.
+    pub fn last_physical_input(&self) -> Option<Instant> {
+        #[cfg(windows)]
+        return self.windows.last_physical_input();
+        #[cfg(linux)]
+        return self.linux.last_physical_input();
+        #[cfg(not(any(windows, linux)))]
+        return None;
     }
 }
@@ -2,12 +2,19 @@
 
 use thiserror::Error;
 
+#[cfg(linux)]
+use crate::linux::{Handle, HandleKind};
 #[cfg(windows)]
 use crate::windows::{Handle, HandleKind};
 
 pub mod capture;
+pub mod clipboard;
+pub mod crypto;
+pub mod gamepad;
 pub mod input;
 
+#[cfg(linux)]
+mod linux;
 #[cfg(windows)]
 mod windows;
 
@@ -24,9 +31,13 @@ pub enum Error {
     KeyNotReceived,
     #[error("mouse was not sent due to the window not focused or other error")]
     MouseNotSent,
+    #[error("virtual gamepad could not be connected, make sure ViGEmBus is installed")]
+    GamepadNotConnected,
 
     #[error("window not found")]
     WindowNotFound,
+    #[error("window could not be brought to the foreground")]
+    WindowNotFocused,
     #[error("the current window size is invalid")]
     WindowInvalidSize,
 
@@ -36,6 +47,10 @@ pub enum Error {
     #[cfg(windows)]
     #[error("win32 API error {0}: {1}")]
     Win32(u32, String),
+
+    #[cfg(linux)]
+    #[error("linux error {0}")]
+    Linux(String),
 }
 
 /// Relativeness of a point to be converted to.
@@ -65,9 +80,15 @@ pub struct ConvertedCoordinates {
 pub struct Window {
     #[cfg(windows)]
     windows: Handle,
+    #[cfg(linux)]
+    linux: Handle,
 }
 
 impl Window {
+    /// Creates a [`Window`] matching the window `class`.
+    ///
+    /// On Linux, `class` is matched against the X11 `WM_CLASS` of the window (e.g. the executable
+    /// name for Wine windows).
     #[cfg(windows)]
     pub fn new(class: &'static str) -> Self {
         Self {
@@ -75,6 +96,35 @@ impl Window {
         }
     }
 
+    #[cfg(linux)]
+    pub fn new(class: &'static str) -> Self {
+        Self {
+            linux: Handle::new(HandleKind::Dynamic(class)),
+        }
+    }
+
+    /// Whether this [`Window`] is currently the foreground window.
+    #[inline]
+    pub fn is_foreground(&self) -> Result<bool> {
+        #[cfg(windows)]
+        return self.windows.is_foreground();
+        #[cfg(linux)]
+        return self.linux.is_foreground();
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
+    }
+
+    /// Attempts to bring this [`Window`] to the foreground, restoring it if minimized.
+    #[inline]
+    pub fn focus(&self) -> Result<()> {
+        #[cfg(windows)]
+        return self.windows.focus();
+        #[cfg(linux)]
+        return self.linux.focus();
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
+    }
+
     #[inline]
     pub fn convert_coordinate(
         &self,
@@ -82,15 +132,13 @@ impl Window {
         y: i32,
         relative: CoordinateRelative,
     ) -> Result<ConvertedCoordinates> {
-        if cfg!(windows) {
-            return self.windows.convert_coordinate(
-                x,
-                y,
-                matches!(relative, CoordinateRelative::Monitor),
-            );
-        }
-
-        Err(Error::PlatformNotSupported)
+        let monitor_coordinate = matches!(relative, CoordinateRelative::Monitor);
+        #[cfg(windows)]
+        return self.windows.convert_coordinate(x, y, monitor_coordinate);
+        #[cfg(linux)]
+        return self.linux.convert_coordinate(x, y, monitor_coordinate);
+        #[cfg(not(any(windows, linux)))]
+        return Err(Error::PlatformNotSupported);
     }
 }
 
@@ -101,8 +149,16 @@ impl From<Handle> for Window {
     }
 }
 
-pub fn init() {
-    if cfg!(windows) {
-        windows::init();
+#[cfg(linux)]
+impl From<Handle> for Window {
+    fn from(value: Handle) -> Self {
+        Self { linux: value }
     }
 }
+
+pub fn init() {
+    #[cfg(windows)]
+    windows::init();
+    #[cfg(linux)]
+    linux::init();
+}
@@ -0,0 +1,63 @@
+use x11rb::protocol::xproto::{ConnectionExt as _, ImageFormat};
+
+use super::{HandleCell, connection, handle::Handle};
+use crate::{Error, Frame, Result};
+
+/// Captures a window through the X server.
+#[derive(Debug)]
+pub struct X11Capture {
+    handle: HandleCell,
+}
+
+impl X11Capture {
+    pub fn new(handle: Handle) -> Self {
+        Self {
+            handle: HandleCell::new(handle),
+        }
+    }
+
+    pub fn grab(&mut self) -> Result<Frame> {
+        let window = self.handle.as_inner().ok_or(Error::WindowNotFound)?;
+        let (conn, _) = connection()?;
+        let geometry = conn.get_geometry(window)?.reply()?;
+        let width = geometry.width as usize;
+        let height = geometry.height as usize;
+        if width == 0 || height == 0 {
+            return Err(Error::WindowInvalidSize);
+        }
+
+        let image = conn
+            .get_image(
+                ImageFormat::Z_PIXMAP,
+                window,
+                0,
+                0,
+                geometry.width,
+                geometry.height,
+                u32::MAX,
+            )?
+            .reply()?;
+        // 24 and 32 bits depth are both stored as BGRX with 4 bytes per pixel
+        if image.depth != 24 && image.depth != 32 {
+            return Err(Error::Linux(format!(
+                "unsupported window depth {}",
+                image.depth
+            )));
+        }
+
+        let mut data = image.data;
+        if data.len() < width * height * 4 {
+            return Err(Error::WindowInvalidSize);
+        }
+        data.truncate(width * height * 4);
+        for pixel in data.chunks_exact_mut(4) {
+            pixel[3] = 255;
+        }
+
+        Ok(Frame {
+            width: width as i32,
+            height: height as i32,
+            data,
+        })
+    }
+}
@@ -0,0 +1,306 @@
+use std::{cell::Cell, fs};
+
+use x11rb::{
+    CURRENT_TIME,
+    connection::Connection,
+    protocol::{
+        randr::ConnectionExt as _,
+        xproto::{self, AtomEnum, ClientMessageEvent, ConnectionExt as _, EventMask, MapState},
+    },
+    rust_connection::RustConnection,
+};
+
+use super::connection;
+use crate::{ConvertedCoordinates, Error, Result};
+
+/// The EWMH source indication for requests sent from a pager-like application.
+const SOURCE_INDICATION_PAGER: u32 = 2;
+
+#[derive(Clone, Debug)]
+pub struct HandleCell {
+    inner: Handle,
+    inner_cell: Cell<Option<xproto::Window>>,
+}
+
+impl HandleCell {
+    pub fn new(handle: Handle) -> Self {
+        Self {
+            inner: handle,
+            inner_cell: Cell::new(None),
+        }
+    }
+
+    #[inline]
+    pub fn as_inner(&self) -> Option<xproto::Window> {
+        match self.inner.kind {
+            HandleKind::Fixed(window) => Some(window),
+            HandleKind::Dynamic(class) => {
+                if self.inner_cell.get().is_none() {
+                    self.inner_cell.set(query_handle(class));
+                }
+
+                let window = self.inner_cell.get()?;
+                if is_class_matched(window, class) {
+                    Some(window)
+                } else {
+                    self.inner_cell.set(None);
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandleKind {
+    Fixed(xproto::Window),
+    Dynamic(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handle {
+    kind: HandleKind,
+}
+
+impl Handle {
+    pub fn new(kind: HandleKind) -> Self {
+        Self { kind }
+    }
+
+    pub fn as_inner(&self) -> Option<xproto::Window> {
+        match self.kind {
+            HandleKind::Fixed(window) => Some(window),
+            HandleKind::Dynamic(class) => query_handle(class),
+        }
+    }
+
+    pub fn is_foreground(&self) -> Result<bool> {
+        let window = self.as_inner().ok_or(Error::WindowNotFound)?;
+
+        Ok(active_window() == Some(window))
+    }
+
+    pub fn focus(&self) -> Result<()> {
+        let window = self.as_inner().ok_or(Error::WindowNotFound)?;
+        let (conn, root) = connection()?;
+        let event = ClientMessageEvent::new(
+            32,
+            window,
+            intern_atom(conn, b"_NET_ACTIVE_WINDOW")?,
+            [SOURCE_INDICATION_PAGER, CURRENT_TIME, 0, 0, 0],
+        );
+
+        // Maps the window in case it is minimized before asking the window manager to activate
+        conn.map_window(window)?;
+        conn.send_event(
+            false,
+            root,
+            EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+            event,
+        )?;
+        conn.flush()?;
+
+        Ok(())
+    }
+
+    pub fn convert_coordinate(
+        &self,
+        x: i32,
+        y: i32,
+        monitor_coordinate: bool,
+    ) -> Result<ConvertedCoordinates> {
+        let window = self.as_inner().ok_or(Error::WindowNotFound)?;
+        let (conn, root) = connection()?;
+
+        if !monitor_coordinate {
+            let geometry = conn.get_geometry(window)?.reply()?;
+
+            return Ok(ConvertedCoordinates {
+                width: geometry.width as i32,
+                height: geometry.height as i32,
+                x,
+                y,
+            });
+        }
+
+        let (root_x, root_y) = client_to_root_coordinate(conn, root, window, x, y)?;
+        let monitors = conn.randr_get_monitors(root, true)?.reply()?.monitors;
+        let monitor = monitors
+            .into_iter()
+            .find(|monitor| {
+                let (left, top) = (monitor.x as i32, monitor.y as i32);
+                (left..left + monitor.width as i32).contains(&root_x)
+                    && (top..top + monitor.height as i32).contains(&root_y)
+            })
+            .ok_or(Error::WindowNotFound)?;
+
+        Ok(ConvertedCoordinates {
+            width: monitor.width as i32,
+            height: monitor.height as i32,
+            x: root_x - monitor.x as i32,
+            y: root_y - monitor.y as i32,
+        })
+    }
+}
+
+/// Queries the viewable windows as `(title, process path, handle)` tuples.
+pub fn query_capture_handles() -> Vec<(String, Option<String>, Handle)> {
+    let Ok((conn, _)) = connection() else {
+        return vec![];
+    };
+
+    client_windows()
+        .into_iter()
+        .filter(|window| {
+            conn.get_window_attributes(*window)
+                .ok()
+                .and_then(|cookie| cookie.reply().ok())
+                .is_some_and(|attributes| attributes.map_state == MapState::VIEWABLE)
+        })
+        .filter_map(|window| {
+            let name = window_name(conn, window)?;
+            let process_path = window_pid(window).and_then(|pid| {
+                fs::read_link(format!("/proc/{pid}/exe"))
+                    .ok()
+                    .and_then(|path| path.into_os_string().into_string().ok())
+            });
+            (!name.is_empty()).then_some((
+                name,
+                process_path,
+                Handle::new(HandleKind::Fixed(window)),
+            ))
+        })
+        .collect()
+}
+
+/// Retrieves the window currently activated by the window manager.
+pub fn active_window() -> Option<xproto::Window> {
+    let (conn, root) = connection().ok()?;
+    let atom = intern_atom(conn, b"_NET_ACTIVE_WINDOW").ok()?;
+    let reply = conn
+        .get_property(false, root, atom, AtomEnum::WINDOW, 0, 1)
+        .ok()?
+        .reply()
+        .ok()?;
+
+    reply.value32()?.next().filter(|window| *window != 0)
+}
+
+/// Retrieves the process id owning `window` if the window manager provides one.
+pub fn window_pid(window: xproto::Window) -> Option<u32> {
+    let (conn, _) = connection().ok()?;
+    let atom = intern_atom(conn, b"_NET_WM_PID").ok()?;
+    let reply = conn
+        .get_property(false, window, atom, AtomEnum::CARDINAL, 0, 1)
+        .ok()?
+        .reply()
+        .ok()?;
+
+    reply.value32()?.next()
+}
+
+/// Retrieves the bounding rectangle of `window` in root coordinates as `(x, y, width, height)`.
+pub fn window_rect(window: xproto::Window) -> Option<(i32, i32, i32, i32)> {
+    let (conn, root) = connection().ok()?;
+    let geometry = conn.get_geometry(window).ok()?.reply().ok()?;
+    let (x, y) = client_to_root_coordinate(conn, root, window, 0, 0).ok()?;
+
+    Some((x, y, geometry.width as i32, geometry.height as i32))
+}
+
+/// Converts client `x`, `y` coordinates of `window` to root coordinates.
+pub fn client_to_root_coordinate(
+    conn: &RustConnection,
+    root: xproto::Window,
+    window: xproto::Window,
+    x: i32,
+    y: i32,
+) -> Result<(i32, i32)> {
+    let translated = conn
+        .translate_coordinates(window, root, x as i16, y as i16)?
+        .reply()?;
+
+    Ok((translated.dst_x as i32, translated.dst_y as i32))
+}
+
+#[inline]
+fn query_handle(class: &'static str) -> Option<xproto::Window> {
+    client_windows()
+        .into_iter()
+        .find(|window| is_class_matched(*window, class))
+}
+
+/// Retrieves the top-level client windows managed by the window manager.
+///
+/// Falls back to the children of the root window if the window manager does not support EWMH.
+fn client_windows() -> Vec<xproto::Window> {
+    let Ok((conn, root)) = connection() else {
+        return vec![];
+    };
+    let clients = intern_atom(conn, b"_NET_CLIENT_LIST")
+        .ok()
+        .and_then(|atom| {
+            conn.get_property(false, root, atom, AtomEnum::WINDOW, 0, u32::MAX)
+                .ok()
+        })
+        .and_then(|cookie| cookie.reply().ok())
+        .and_then(|reply| reply.value32().map(|windows| windows.collect::<Vec<_>>()));
+    if let Some(clients) = clients
+        && !clients.is_empty()
+    {
+        return clients;
+    }
+
+    conn.query_tree(root)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+        .map(|tree| tree.children)
+        .unwrap_or_default()
+}
+
+#[inline]
+fn is_class_matched(window: xproto::Window, class: &'static str) -> bool {
+    let Ok((conn, _)) = connection() else {
+        return false;
+    };
+    let Some(reply) = conn
+        .get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+    else {
+        return false;
+    };
+
+    // WM_CLASS consists of the null-terminated instance and class names
+    let class = class.to_ascii_lowercase();
+    reply
+        .value
+        .split(|byte| *byte == 0)
+        .filter_map(|name| std::str::from_utf8(name).ok())
+        .any(|name| !name.is_empty() && name.to_ascii_lowercase().starts_with(&class))
+}
+
+fn window_name(conn: &RustConnection, window: xproto::Window) -> Option<String> {
+    let utf8_string = intern_atom(conn, b"UTF8_STRING").ok()?;
+    let net_wm_name = intern_atom(conn, b"_NET_WM_NAME").ok()?;
+    let name = [
+        (net_wm_name, utf8_string),
+        (AtomEnum::WM_NAME.into(), AtomEnum::STRING.into()),
+    ]
+    .into_iter()
+    .find_map(|(property, kind)| {
+        let reply = conn
+            .get_property(false, window, property, kind, 0, 256)
+            .ok()?
+            .reply()
+            .ok()?;
+        (!reply.value.is_empty()).then_some(reply.value)
+    })?;
+
+    Some(String::from_utf8_lossy(&name).into_owned())
+}
+
+#[inline]
+fn intern_atom(conn: &RustConnection, name: &[u8]) -> Result<xproto::Atom> {
+    Ok(conn.intern_atom(false, name)?.reply()?.atom)
+}
@@ -0,0 +1,608 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::CStr,
+    io, mem,
+    sync::{LazyLock, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use bit_vec::BitVec;
+use tokio::sync::broadcast::{self, Receiver, Sender};
+use x11rb::{
+    CURRENT_TIME,
+    connection::Connection,
+    protocol::{
+        xproto::{
+            self, BUTTON_PRESS_EVENT, BUTTON_RELEASE_EVENT, ConnectionExt as _, MOTION_NOTIFY_EVENT,
+        },
+        xtest::ConnectionExt as _,
+    },
+};
+
+use super::{
+    HandleCell, active_window, client_to_root_coordinate, connection, handle::Handle, window_pid,
+    window_rect,
+};
+use crate::{
+    Error, Result,
+    input::{InputKind, KeyKind, KeyState, MouseKind, is_physical_input_ignored},
+};
+
+const UI_SET_EVBIT: u64 = 0x40045564;
+const UI_SET_KEYBIT: u64 = 0x40045565;
+const UI_DEV_SETUP: u64 = 0x405c5503;
+const UI_DEV_CREATE: u64 = 0x5501;
+const UI_DEV_DESTROY: u64 = 0x5502;
+
+const EV_SYN: u16 = 0;
+const EV_KEY: u16 = 1;
+const SYN_REPORT: u16 = 0;
+const BUS_VIRTUAL: u16 = 0x06;
+
+/// The offset between evdev key codes and X key codes.
+const X_KEYCODE_OFFSET: u8 = 8;
+
+const LEFT_BUTTON: u8 = 1;
+const SCROLL_DOWN_BUTTON: u8 = 5;
+
+/// Duration within which a key released by [`VirtualKeyboard`] is not reported as a user key.
+const INJECTED_KEY_IGNORE_DURATION: Duration = Duration::from_millis(200);
+
+const KEY_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+static KEY_CHANNEL: LazyLock<Sender<KeyKind>> = LazyLock::new(|| broadcast::channel(1).0);
+static KEYBOARD: LazyLock<Option<VirtualKeyboard>> = LazyLock::new(|| VirtualKeyboard::new().ok());
+/// The last time each evdev key code was released by [`VirtualKeyboard`].
+static INJECTED_KEY_RELEASES: LazyLock<Mutex<HashMap<u16, Instant>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+/// The last time a key was physically released, excluding releases by [`VirtualKeyboard`].
+static LAST_PHYSICAL_INPUT: Mutex<Option<Instant>> = Mutex::new(None);
+
+#[repr(C)]
+struct InputId {
+    bustype: u16,
+    vendor: u16,
+    product: u16,
+    version: u16,
+}
+
+#[repr(C)]
+struct UinputSetup {
+    id: InputId,
+    name: [u8; 80],
+    ff_effects_max: u32,
+}
+
+#[repr(C)]
+struct InputEvent {
+    time: libc::timeval,
+    type_: u16,
+    code: u16,
+    value: i32,
+}
+
+/// A `uinput` virtual keyboard for injecting key inputs.
+///
+/// Key inputs are injected at the kernel level so they are delivered to the focused window the
+/// same way a physical keyboard is.
+#[derive(Debug)]
+struct VirtualKeyboard {
+    fd: libc::c_int,
+}
+
+impl VirtualKeyboard {
+    fn new() -> Result<Self> {
+        let path = c"/dev/uinput";
+        let fd = unsafe { libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_NONBLOCK) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        // Constructs first so the file descriptor is closed on error
+        let keyboard = Self { fd };
+
+        keyboard.ioctl(UI_SET_EVBIT, EV_KEY as libc::c_ulong)?;
+        for code in ALL_KEYS.iter().copied().map(to_key_code) {
+            keyboard.ioctl(UI_SET_KEYBIT, code as libc::c_ulong)?;
+        }
+
+        let mut setup = UinputSetup {
+            id: InputId {
+                bustype: BUS_VIRTUAL,
+                vendor: 0x1,
+                product: 0x1,
+                version: 1,
+            },
+            name: [0; 80],
+            ff_effects_max: 0,
+        };
+        let name = CStr::to_bytes(c"komari virtual keyboard");
+        setup.name[..name.len()].copy_from_slice(name);
+        keyboard.ioctl(UI_DEV_SETUP, &raw const setup as libc::c_ulong)?;
+        keyboard.ioctl(UI_DEV_CREATE, 0)?;
+
+        // Gives the display server time to pick up the new device
+        thread::sleep(Duration::from_millis(200));
+        Ok(keyboard)
+    }
+
+    fn send(&self, code: u16, is_down: bool) -> Result<()> {
+        self.write(EV_KEY, code, is_down as i32)?;
+        self.write(EV_SYN, SYN_REPORT, 0)?;
+        if !is_down {
+            INJECTED_KEY_RELEASES
+                .lock()
+                .unwrap()
+                .insert(code, Instant::now());
+        }
+
+        Ok(())
+    }
+
+    fn write(&self, type_: u16, code: u16, value: i32) -> Result<()> {
+        let event = InputEvent {
+            time: libc::timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+            type_,
+            code,
+            value,
+        };
+        let size = mem::size_of::<InputEvent>();
+        let written = unsafe { libc::write(self.fd, (&raw const event).cast(), size) };
+        if written != size as isize {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn ioctl(&self, request: u64, arg: libc::c_ulong) -> Result<()> {
+        if unsafe { libc::ioctl(self.fd, request as _, arg) } < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for VirtualKeyboard {
+    fn drop(&mut self) {
+        unsafe {
+            libc::ioctl(self.fd, UI_DEV_DESTROY as _);
+            libc::close(self.fd);
+        }
+    }
+}
+
+/// Polls the X server keymap and broadcasts released keys that were not injected.
+pub(super) fn poll_keys() {
+    let Ok((conn, _)) = connection() else {
+        return;
+    };
+    let mut previous = [0u8; 32];
+
+    loop {
+        thread::sleep(KEY_POLL_INTERVAL);
+        let Some(keys) = conn
+            .query_keymap()
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| reply.keys)
+        else {
+            continue;
+        };
+
+        for keycode in 0..=u8::MAX {
+            let was_down = is_key_down(&previous, keycode);
+            if !was_down || is_key_down(&keys, keycode) {
+                continue;
+            }
+            let Some(code) = keycode.checked_sub(X_KEYCODE_OFFSET).map(u16::from) else {
+                continue;
+            };
+            let Ok(key) = KeyKind::try_from(code) else {
+                continue;
+            };
+
+            let is_injected = INJECTED_KEY_RELEASES
+                .lock()
+                .unwrap()
+                .get(&code)
+                .is_some_and(|instant| instant.elapsed() < INJECTED_KEY_IGNORE_DURATION);
+            if !is_injected {
+                if !is_physical_input_ignored(key) {
+                    *LAST_PHYSICAL_INPUT.lock().unwrap() = Some(Instant::now());
+                }
+                let _ = KEY_CHANNEL.send(key);
+            }
+        }
+        previous = keys;
+    }
+}
+
+#[derive(Debug)]
+pub struct LinuxInputReceiver {
+    /// The handle to check for foreground before processing a key.
+    ///
+    /// If [`None`], keys are always processed regardless of the foreground window.
+    handle: Option<HandleCell>,
+    input_kind: InputKind,
+    rx: Receiver<KeyKind>,
+}
+
+impl LinuxInputReceiver {
+    pub fn new(handle: Handle, input_kind: InputKind) -> Self {
+        Self {
+            handle: Some(HandleCell::new(handle)),
+            input_kind,
+            rx: KEY_CHANNEL.subscribe(),
+        }
+    }
+
+    pub fn new_global() -> Self {
+        Self {
+            handle: None,
+            input_kind: InputKind::Focused,
+            rx: KEY_CHANNEL.subscribe(),
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Option<KeyKind> {
+        self.rx
+            .try_recv()
+            .ok()
+            .and_then(|key| self.can_process_key().then_some(key))
+    }
+
+    /// Gets the last time a key was physically released if the window of this receiver is in the
+    /// foreground.
+    ///
+    /// Mouse inputs are not tracked.
+    pub fn last_physical_input(&self) -> Option<Instant> {
+        let window = self.handle.as_ref()?.as_inner()?;
+        if !is_foreground(window, self.input_kind) {
+            return None;
+        }
+
+        *LAST_PHYSICAL_INPUT.lock().unwrap()
+    }
+
+    fn can_process_key(&self) -> bool {
+        let Some(handle) = self.handle.as_ref() else {
+            return true;
+        };
+
+        if active_window()
+            .and_then(window_pid)
+            .is_some_and(|pid| pid == std::process::id())
+        {
+            return true;
+        }
+
+        handle
+            .as_inner()
+            .map(|window| is_foreground(window, self.input_kind))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+enum InputKeyStroke {
+    Up,
+    Down,
+    DownRepeatable,
+}
+
+#[derive(Debug)]
+pub struct LinuxInput {
+    handle: HandleCell,
+    input_kind: InputKind,
+    key_down: RefCell<BitVec>,
+}
+
+impl LinuxInput {
+    pub fn new(handle: Handle, kind: InputKind) -> Self {
+        Self {
+            handle: HandleCell::new(handle),
+            input_kind: kind,
+            key_down: RefCell::new(BitVec::from_elem(256, false)),
+        }
+    }
+
+    pub fn send_mouse(&self, x: i32, y: i32, kind: MouseKind) -> Result<()> {
+        let mut window = self.get_handle()?;
+        if !is_foreground(window, self.input_kind) {
+            return Err(Error::WindowNotFound);
+        }
+        if matches!(self.input_kind, InputKind::Foreground) {
+            window = active_window().ok_or(Error::WindowNotFound)?;
+        }
+
+        let (conn, root) = connection()?;
+        let (x, y) = client_to_root_coordinate(conn, root, window, x, y)?;
+        let fake_input = |kind: u8, detail: u8| -> Result<()> {
+            conn.xtest_fake_input(kind, detail, CURRENT_TIME, root, x as i16, y as i16, 0)?;
+            conn.flush()?;
+            Ok(())
+        };
+
+        fake_input(MOTION_NOTIFY_EVENT, 0)?;
+        match kind {
+            MouseKind::Move => Ok(()),
+            MouseKind::Click => {
+                fake_input(BUTTON_PRESS_EVENT, LEFT_BUTTON)?;
+                // Same as Windows, double-click won't work without a delay
+                thread::sleep(Duration::from_millis(80));
+                fake_input(BUTTON_RELEASE_EVENT, LEFT_BUTTON)
+            }
+            MouseKind::Scroll => {
+                fake_input(BUTTON_PRESS_EVENT, SCROLL_DOWN_BUTTON)?;
+                fake_input(BUTTON_RELEASE_EVENT, SCROLL_DOWN_BUTTON)
+            }
+            MouseKind::Down => fake_input(BUTTON_PRESS_EVENT, LEFT_BUTTON),
+            MouseKind::Up => fake_input(BUTTON_RELEASE_EVENT, LEFT_BUTTON),
+        }
+    }
+
+    pub fn key_state(&self, kind: KeyKind) -> Result<KeyState> {
+        let (conn, _) = connection()?;
+        let keys = conn.query_keymap()?.reply()?.keys;
+        let keycode = to_key_code(kind) as u8 + X_KEYCODE_OFFSET;
+        let state = if is_key_down(&keys, keycode) {
+            KeyState::Pressed
+        } else {
+            KeyState::Released
+        };
+
+        Ok(state)
+    }
+
+    pub fn send_key(&self, kind: KeyKind) -> Result<()> {
+        self.send_key_down(kind, false)?;
+        self.send_key_up(kind)?;
+        Ok(())
+    }
+
+    pub fn send_key_up(&self, kind: KeyKind) -> Result<()> {
+        self.send_input(kind, InputKeyStroke::Up)
+    }
+
+    pub fn send_key_down(&self, kind: KeyKind, repeatable: bool) -> Result<()> {
+        let stroke = if repeatable {
+            InputKeyStroke::DownRepeatable
+        } else {
+            InputKeyStroke::Down
+        };
+
+        self.send_input(kind, stroke)
+    }
+
+    #[inline]
+    fn send_input(&self, kind: KeyKind, stroke: InputKeyStroke) -> Result<()> {
+        let window = self.get_handle()?;
+        let is_down = matches!(
+            stroke,
+            InputKeyStroke::Down | InputKeyStroke::DownRepeatable
+        );
+        if is_down && !is_foreground(window, self.input_kind) {
+            return Err(Error::KeyNotSent);
+        }
+        let keyboard = KEYBOARD.as_ref().ok_or(Error::KeyNotSent)?;
+
+        let code = to_key_code(kind);
+        let mut key_down = self.key_down.borrow_mut();
+        let was_key_down = key_down[code as usize];
+        match (is_down, was_key_down) {
+            (true, true) => {
+                if !matches!(stroke, InputKeyStroke::DownRepeatable) {
+                    return Err(Error::KeyNotSent);
+                }
+            }
+            (false, false) => return Err(Error::KeyNotSent),
+            _ => {
+                key_down.set(code as usize, is_down);
+            }
+        }
+        keyboard.send(code, is_down)
+    }
+
+    #[inline]
+    fn get_handle(&self) -> Result<xproto::Window> {
+        self.handle.as_inner().ok_or(Error::WindowNotFound)
+    }
+}
+
+#[inline]
+fn is_key_down(keys: &[u8; 32], keycode: u8) -> bool {
+    keys[keycode as usize / 8] & (1 << (keycode % 8)) != 0
+}
+
+#[inline]
+fn is_foreground(window: xproto::Window, kind: InputKind) -> bool {
+    let Some(active) = active_window() else {
+        return false;
+    };
+    match kind {
+        InputKind::Focused => active == window,
+        InputKind::Foreground => {
+            if active == window {
+                return false;
+            }
+            let (Some(active_rect), Some(rect)) = (window_rect(active), window_rect(window)) else {
+                return false;
+            };
+            let (ax, ay, aw, ah) = active_rect;
+            let (x, y, w, h) = rect;
+
+            ax < x + w && x < ax + aw && ay < y + h && y < ay + ah
+        }
+    }
+}
+
+const ALL_KEYS: [KeyKind; 72] = [
+    KeyKind::A,
+    KeyKind::B,
+    KeyKind::C,
+    KeyKind::D,
+    KeyKind::E,
+    KeyKind::F,
+    KeyKind::G,
+    KeyKind::H,
+    KeyKind::I,
+    KeyKind::J,
+    KeyKind::K,
+    KeyKind::L,
+    KeyKind::M,
+    KeyKind::N,
+    KeyKind::O,
+    KeyKind::P,
+    KeyKind::Q,
+    KeyKind::R,
+    KeyKind::S,
+    KeyKind::T,
+    KeyKind::U,
+    KeyKind::V,
+    KeyKind::W,
+    KeyKind::X,
+    KeyKind::Y,
+    KeyKind::Z,
+    KeyKind::Zero,
+    KeyKind::One,
+    KeyKind::Two,
+    KeyKind::Three,
+    KeyKind::Four,
+    KeyKind::Five,
+    KeyKind::Six,
+    KeyKind::Seven,
+    KeyKind::Eight,
+    KeyKind::Nine,
+    KeyKind::F1,
+    KeyKind::F2,
+    KeyKind::F3,
+    KeyKind::F4,
+    KeyKind::F5,
+    KeyKind::F6,
+    KeyKind::F7,
+    KeyKind::F8,
+    KeyKind::F9,
+    KeyKind::F10,
+    KeyKind::F11,
+    KeyKind::F12,
+    KeyKind::Up,
+    KeyKind::Down,
+    KeyKind::Left,
+    KeyKind::Right,
+    KeyKind::Home,
+    KeyKind::End,
+    KeyKind::PageUp,
+    KeyKind::PageDown,
+    KeyKind::Insert,
+    KeyKind::Delete,
+    KeyKind::Ctrl,
+    KeyKind::Enter,
+    KeyKind::Space,
+    KeyKind::Tilde,
+    KeyKind::Quote,
+    KeyKind::Semicolon,
+    KeyKind::Comma,
+    KeyKind::Period,
+    KeyKind::Slash,
+    KeyKind::Minus,
+    KeyKind::Esc,
+    KeyKind::Shift,
+    KeyKind::Alt,
+    KeyKind::Backspace,
+];
+
+impl TryFrom<u16> for KeyKind {
+    type Error = Error;
+
+    fn try_from(value: u16) -> Result<Self> {
+        ALL_KEYS
+            .into_iter()
+            .find(|key| to_key_code(*key) == value)
+            .ok_or(Error::KeyNotFound)
+    }
+}
+
+/// Converts `kind` to its evdev key code.
+fn to_key_code(kind: KeyKind) -> u16 {
+    match kind {
+        KeyKind::A => 30,
+        KeyKind::B => 48,
+        KeyKind::C => 46,
+        KeyKind::D => 32,
+        KeyKind::E => 18,
+        KeyKind::F => 33,
+        KeyKind::G => 34,
+        KeyKind::H => 35,
+        KeyKind::I => 23,
+        KeyKind::J => 36,
+        KeyKind::K => 37,
+        KeyKind::L => 38,
+        KeyKind::M => 50,
+        KeyKind::N => 49,
+        KeyKind::O => 24,
+        KeyKind::P => 25,
+        KeyKind::Q => 16,
+        KeyKind::R => 19,
+        KeyKind::S => 31,
+        KeyKind::T => 20,
+        KeyKind::U => 22,
+        KeyKind::V => 47,
+        KeyKind::W => 17,
+        KeyKind::X => 45,
+        KeyKind::Y => 21,
+        KeyKind::Z => 44,
+        KeyKind::Zero => 11,
+        KeyKind::One => 2,
+        KeyKind::Two => 3,
+        KeyKind::Three => 4,
+        KeyKind::Four => 5,
+        KeyKind::Five => 6,
+        KeyKind::Six => 7,
+        KeyKind::Seven => 8,
+        KeyKind::Eight => 9,
+        KeyKind::Nine => 10,
+        KeyKind::F1 => 59,
+        KeyKind::F2 => 60,
+        KeyKind::F3 => 61,
+        KeyKind::F4 => 62,
+        KeyKind::F5 => 63,
+        KeyKind::F6 => 64,
+        KeyKind::F7 => 65,
+        KeyKind::F8 => 66,
+        KeyKind::F9 => 67,
+        KeyKind::F10 => 68,
+        KeyKind::F11 => 87,
+        KeyKind::F12 => 88,
+        KeyKind::Up => 103,
+        KeyKind::Down => 108,
+        KeyKind::Left => 105,
+        KeyKind::Right => 106,
+        KeyKind::Home => 102,
+        KeyKind::End => 107,
+        KeyKind::PageUp => 104,
+        KeyKind::PageDown => 109,
+        KeyKind::Insert => 110,
+        KeyKind::Delete => 111,
+        KeyKind::Ctrl => 29,
+        KeyKind::Enter => 28,
+        KeyKind::Space => 57,
+        KeyKind::Tilde => 41,
+        KeyKind::Quote => 40,
+        KeyKind::Semicolon => 39,
+        KeyKind::Comma => 51,
+        KeyKind::Period => 52,
+        KeyKind::Slash => 53,
+        KeyKind::Minus => 12,
+        KeyKind::Esc => 1,
+        KeyKind::Shift => 42,
+        KeyKind::Alt => 56,
+        KeyKind::Backspace => 14,
+    }
+}
@@ -0,0 +1,75 @@
+//! Capture and input backends for running the game under Wine on Linux.
+//!
+//! Wine windows are X11 clients so this works on both X11 and Wayland (through XWayland)
+//! sessions. Windows are captured and tracked through the X server, mouse inputs are sent through
+//! the XTest extension and key inputs are injected through a `uinput` virtual keyboard, which
+//! requires write access to `/dev/uinput`.
+
+use std::{
+    io,
+    sync::{
+        LazyLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+};
+
+use x11rb::{
+    connection::Connection,
+    errors::{ConnectionError, ReplyError},
+    protocol::xproto,
+    rust_connection::RustConnection,
+};
+
+mod capture;
+mod handle;
+mod input;
+
+pub use {capture::*, handle::*, input::*};
+
+use crate::{Error, Result};
+
+/// The shared connection to the X server and the root window of its default screen.
+static CONNECTION: LazyLock<Option<(RustConnection, xproto::Window)>> = LazyLock::new(|| {
+    let (conn, screen) = x11rb::connect(None).ok()?;
+    let root = conn.setup().roots[screen].root;
+    Some((conn, root))
+});
+
+/// Retrieves the shared X server connection and its root window.
+#[inline]
+fn connection() -> Result<(&'static RustConnection, xproto::Window)> {
+    CONNECTION
+        .as_ref()
+        .map(|(conn, root)| (conn, *root))
+        .ok_or(Error::PlatformNotSupported)
+}
+
+pub fn init() {
+    static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+    if INITIALIZED
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::Acquire)
+        .is_ok()
+    {
+        thread::spawn(input::poll_keys);
+    }
+}
+
+impl From<ConnectionError> for Error {
+    fn from(error: ConnectionError) -> Self {
+        Error::Linux(error.to_string())
+    }
+}
+
+impl From<ReplyError> for Error {
+    fn from(error: ReplyError) -> Self {
+        Error::Linux(error.to_string())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Linux(error.to_string())
+    }
+}
@@ -0,0 +1,91 @@
+use std::ptr;
+
+use windows::Win32::{
+    Foundation::{HANDLE, HGLOBAL},
+    System::{
+        DataExchange::{
+            CloseClipboard, EmptyClipboard, GetClipboardData, IsClipboardFormatAvailable,
+            OpenClipboard, SetClipboardData,
+        },
+        Memory::{GMEM_MOVEABLE, GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock},
+        Ole::CF_UNICODETEXT,
+    },
+};
+
+use crate::{Error, Result};
+
+pub fn set_clipboard_text(text: &str) -> Result<()> {
+    let data = text.encode_utf16().chain([0]).collect::<Vec<u16>>();
+    let memory = unsafe { GlobalAlloc(GMEM_MOVEABLE, data.len() * size_of::<u16>())? };
+    if let Err(err) = copy_to_global(memory, &data) {
+        unsafe {
+            let _ = GlobalFree(Some(memory));
+        }
+        return Err(err);
+    }
+
+    unsafe {
+        if let Err(err) = OpenClipboard(None) {
+            let _ = GlobalFree(Some(memory));
+            return Err(err.into());
+        }
+        let result = EmptyClipboard()
+            .and_then(|_| SetClipboardData(CF_UNICODETEXT.0 as u32, Some(HANDLE(memory.0))));
+        // The system owns the memory once the clipboard data is set
+        if result.is_err() {
+            let _ = GlobalFree(Some(memory));
+        }
+        let _ = CloseClipboard();
+
+        result.map(|_| ()).map_err(Error::from)
+    }
+}
+
+pub fn clipboard_text() -> Result<Option<String>> {
+    unsafe {
+        if IsClipboardFormatAvailable(CF_UNICODETEXT.0 as u32).is_err() {
+            return Ok(None);
+        }
+        OpenClipboard(None)?;
+        let result = GetClipboardData(CF_UNICODETEXT.0 as u32)
+            .map_err(Error::from)
+            .and_then(|handle| copy_from_global(HGLOBAL(handle.0)));
+        let _ = CloseClipboard();
+
+        result.map(Some)
+    }
+}
+
+#[inline]
+fn copy_from_global(memory: HGLOBAL) -> Result<String> {
+    unsafe {
+        let pointer = GlobalLock(memory).cast::<u16>();
+        if pointer.is_null() {
+            return Err(windows::core::Error::from_win32().into());
+        }
+        let mut len = 0;
+        while *pointer.add(len) != 0 {
+            len += 1;
+        }
+        let text = String::from_utf16_lossy(std::slice::from_raw_parts(pointer, len));
+        // Returns an error with no failure code when the memory is unlocked
+        let _ = GlobalUnlock(memory);
+
+        Ok(text)
+    }
+}
+
+#[inline]
+fn copy_to_global(memory: HGLOBAL, data: &[u16]) -> Result<()> {
+    unsafe {
+        let pointer = GlobalLock(memory).cast::<u16>();
+        if pointer.is_null() {
+            return Err(windows::core::Error::from_win32().into());
+        }
+        ptr::copy_nonoverlapping(data.as_ptr(), pointer, data.len());
+        // Returns an error with no failure code when the memory is unlocked
+        let _ = GlobalUnlock(memory);
+    }
+
+    Ok(())
+}
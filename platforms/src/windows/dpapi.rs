@@ -0,0 +1,70 @@
+use std::slice;
+
+use windows::Win32::{
+    Foundation::{HLOCAL, LocalFree},
+    Security::Cryptography::{
+        CRYPT_INTEGER_BLOB, CRYPTPROTECT_UI_FORBIDDEN, CryptProtectData, CryptUnprotectData,
+    },
+};
+
+use crate::Result;
+
+pub fn protect(data: &[u8]) -> Result<Vec<u8>> {
+    let input = blob_from(data);
+    let mut output = CRYPT_INTEGER_BLOB::default();
+
+    unsafe {
+        CryptProtectData(
+            &raw const input,
+            None,
+            None,
+            None,
+            None,
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &raw mut output,
+        )?;
+    }
+
+    Ok(take_blob(output))
+}
+
+pub fn unprotect(data: &[u8]) -> Result<Vec<u8>> {
+    let input = blob_from(data);
+    let mut output = CRYPT_INTEGER_BLOB::default();
+
+    unsafe {
+        CryptUnprotectData(
+            &raw const input,
+            None,
+            None,
+            None,
+            None,
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &raw mut output,
+        )?;
+    }
+
+    Ok(take_blob(output))
+}
+
+#[inline]
+fn blob_from(data: &[u8]) -> CRYPT_INTEGER_BLOB {
+    CRYPT_INTEGER_BLOB {
+        cbData: data.len() as u32,
+        pbData: data.as_ptr().cast_mut(),
+    }
+}
+
+/// Copies the output `blob` allocated by DPAPI and frees it.
+#[inline]
+fn take_blob(blob: CRYPT_INTEGER_BLOB) -> Vec<u8> {
+    if blob.pbData.is_null() {
+        return Vec::new();
+    }
+
+    let data = unsafe { slice::from_raw_parts(blob.pbData, blob.cbData as usize) }.to_vec();
+    unsafe {
+        LocalFree(Some(HLOCAL(blob.pbData.cast())));
+    }
+    data
+}
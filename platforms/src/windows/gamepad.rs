@@ -0,0 +1,90 @@
+use std::{cell::RefCell, fmt};
+
+use vigem_client::{Client, TargetId, XButtons, XGamepad, Xbox360Wired};
+
+use crate::{Error, Result, gamepad::GamepadButton, input::KeyState};
+
+pub struct WindowsGamepad {
+    target: RefCell<Xbox360Wired<Client>>,
+    report: RefCell<XGamepad>,
+}
+
+impl fmt::Debug for WindowsGamepad {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WindowsGamepad")
+            .field("report", &self.report)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WindowsGamepad {
+    pub fn new() -> Result<Self> {
+        let client = Client::connect().map_err(|_| Error::GamepadNotConnected)?;
+        let mut target = Xbox360Wired::new(client, TargetId::XBOX360_WIRED);
+        target.plugin().map_err(|_| Error::GamepadNotConnected)?;
+        target
+            .wait_ready()
+            .map_err(|_| Error::GamepadNotConnected)?;
+
+        Ok(Self {
+            target: RefCell::new(target),
+            report: RefCell::new(XGamepad::default()),
+        })
+    }
+
+    pub fn button_state(&self, button: GamepadButton) -> KeyState {
+        let report = self.report.borrow();
+        let pressed = match button {
+            GamepadButton::LeftTrigger => report.left_trigger > 0,
+            GamepadButton::RightTrigger => report.right_trigger > 0,
+            button => report.buttons.raw & to_raw_button(button) != 0,
+        };
+
+        if pressed {
+            KeyState::Pressed
+        } else {
+            KeyState::Released
+        }
+    }
+
+    pub fn send_button(&self, button: GamepadButton, is_down: bool) -> Result<()> {
+        let mut report = self.report.borrow_mut();
+        match button {
+            GamepadButton::LeftTrigger => report.left_trigger = if is_down { u8::MAX } else { 0 },
+            GamepadButton::RightTrigger => report.right_trigger = if is_down { u8::MAX } else { 0 },
+            button => {
+                if is_down {
+                    report.buttons.raw |= to_raw_button(button);
+                } else {
+                    report.buttons.raw &= !to_raw_button(button);
+                }
+            }
+        }
+
+        self.target
+            .borrow_mut()
+            .update(&report)
+            .map_err(|_| Error::KeyNotSent)
+    }
+}
+
+#[inline]
+fn to_raw_button(button: GamepadButton) -> u16 {
+    match button {
+        GamepadButton::A => XButtons::A,
+        GamepadButton::B => XButtons::B,
+        GamepadButton::X => XButtons::X,
+        GamepadButton::Y => XButtons::Y,
+        GamepadButton::LeftShoulder => XButtons::LB,
+        GamepadButton::RightShoulder => XButtons::RB,
+        GamepadButton::Back => XButtons::BACK,
+        GamepadButton::Start => XButtons::START,
+        GamepadButton::LeftThumb => XButtons::LTHUMB,
+        GamepadButton::RightThumb => XButtons::RTHUMB,
+        GamepadButton::DpadUp => XButtons::UP,
+        GamepadButton::DpadDown => XButtons::DOWN,
+        GamepadButton::DpadLeft => XButtons::LEFT,
+        GamepadButton::DpadRight => XButtons::RIGHT,
+        GamepadButton::LeftTrigger | GamepadButton::RightTrigger => 0,
+    }
+}
@@ -2,7 +2,7 @@ use std::{cell::Cell, ffi::OsString, os::windows::ffi::OsStringExt, ptr, str};
 
 use windows::{
     Win32::{
-        Foundation::{HWND, LPARAM, POINT, RECT},
+        Foundation::{CloseHandle, HWND, LPARAM, POINT, RECT},
         Graphics::{
             Dwm::{DWMWA_CLOAKED, DwmGetWindowAttribute},
             Gdi::{
@@ -10,12 +10,18 @@ use windows::{
                 MonitorFromWindow,
             },
         },
+        System::Threading::{
+            OpenProcess, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+            QueryFullProcessImageNameW,
+        },
         UI::WindowsAndMessaging::{
-            EnumWindows, GWL_EXSTYLE, GWL_STYLE, GetClassNameW, GetWindowLongPtrW, GetWindowRect,
-            GetWindowTextW, IsWindowVisible, WS_DISABLED, WS_EX_TOOLWINDOW,
+            EnumWindows, GWL_EXSTYLE, GWL_STYLE, GetClassNameW, GetForegroundWindow,
+            GetWindowLongPtrW, GetWindowRect, GetWindowTextW, GetWindowThreadProcessId, IsIconic,
+            IsWindowVisible, SW_RESTORE, SetForegroundWindow, ShowWindow, WS_DISABLED,
+            WS_EX_TOOLWINDOW,
         },
     },
-    core::BOOL,
+    core::{BOOL, PWSTR},
 };
 
 use crate::{ConvertedCoordinates, Error, Result};
@@ -78,6 +84,26 @@ impl Handle {
         }
     }
 
+    pub fn is_foreground(&self) -> Result<bool> {
+        let handle = self.as_inner().ok_or(Error::WindowNotFound)?;
+        let handle_fg = unsafe { GetForegroundWindow() };
+
+        Ok(!handle_fg.is_invalid() && handle_fg == handle)
+    }
+
+    pub fn focus(&self) -> Result<()> {
+        let handle = self.as_inner().ok_or(Error::WindowNotFound)?;
+        if unsafe { IsIconic(handle) }.as_bool() {
+            let _ = unsafe { ShowWindow(handle, SW_RESTORE) };
+        }
+        // Can fail when the calling process is not allowed to set the foreground window
+        if !unsafe { SetForegroundWindow(handle) }.as_bool() {
+            return Err(Error::WindowNotFocused);
+        }
+
+        Ok(())
+    }
+
     pub fn convert_coordinate(
         &self,
         x: i32,
@@ -131,7 +157,8 @@ impl Handle {
     }
 }
 
-pub fn query_capture_name_handle_pairs() -> Vec<(String, Handle)> {
+/// Queries the visible windows as `(title, process path, handle)` tuples.
+pub fn query_capture_handles() -> Vec<(String, Option<String>, Handle)> {
     unsafe extern "system" fn callback(handle: HWND, params: LPARAM) -> BOOL {
         if !unsafe { IsWindowVisible(handle) }.as_bool() {
             return true.into();
@@ -163,9 +190,13 @@ pub fn query_capture_name_handle_pairs() -> Vec<(String, Handle)> {
             return true.into();
         }
 
-        let vec = unsafe { &mut *(params.0 as *mut Vec<(String, Handle)>) };
+        let vec = unsafe { &mut *(params.0 as *mut Vec<(String, Option<String>, Handle)>) };
         if let Some(name) = OsString::from_wide(&buf[..count]).to_str() {
-            vec.push((name.to_string(), Handle::new(HandleKind::Fixed(handle))));
+            vec.push((
+                name.to_string(),
+                query_process_path(handle),
+                Handle::new(HandleKind::Fixed(handle)),
+            ));
         }
         true.into()
     }
@@ -175,6 +206,33 @@ pub fn query_capture_name_handle_pairs() -> Vec<(String, Handle)> {
     vec
 }
 
+/// Queries the executable path of the process owning `handle`.
+fn query_process_path(handle: HWND) -> Option<String> {
+    let mut pid = 0u32;
+    unsafe { GetWindowThreadProcessId(handle, Some(&raw mut pid)) };
+    if pid == 0 {
+        return None;
+    }
+
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }.ok()?;
+    let mut buf = [0u16; 1024];
+    let mut count = buf.len() as u32;
+    let result = unsafe {
+        QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buf.as_mut_ptr()),
+            &raw mut count,
+        )
+    };
+    let _ = unsafe { CloseHandle(process) };
+    result.ok()?;
+
+    OsString::from_wide(&buf[..count as usize])
+        .into_string()
+        .ok()
+}
+
 #[inline]
 fn query_handle(class: &'static str) -> Option<HWND> {
     struct Params {
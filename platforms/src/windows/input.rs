@@ -1,9 +1,9 @@
 use std::{
     cell::RefCell,
     mem::{self, size_of},
-    sync::LazyLock,
+    sync::{LazyLock, Mutex},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use bit_vec::BitVec;
@@ -24,15 +24,16 @@ use windows::{
                 VK_E, VK_END, VK_ESCAPE, VK_F, VK_F1, VK_F2, VK_F3, VK_F4, VK_F5, VK_F6, VK_F7,
                 VK_F8, VK_F9, VK_F10, VK_F11, VK_F12, VK_G, VK_H, VK_HOME, VK_I, VK_INSERT, VK_J,
                 VK_K, VK_L, VK_LEFT, VK_M, VK_MENU, VK_N, VK_NEXT, VK_O, VK_OEM_1, VK_OEM_2,
-                VK_OEM_3, VK_OEM_7, VK_OEM_COMMA, VK_OEM_PERIOD, VK_P, VK_PRIOR, VK_Q, VK_R,
-                VK_RETURN, VK_RIGHT, VK_S, VK_SHIFT, VK_SPACE, VK_T, VK_U, VK_UP, VK_V, VK_W, VK_X,
-                VK_Y, VK_Z,
+                VK_OEM_3, VK_OEM_7, VK_OEM_COMMA, VK_OEM_MINUS, VK_OEM_PERIOD, VK_P, VK_PRIOR,
+                VK_Q, VK_R, VK_RETURN, VK_RIGHT, VK_S, VK_SHIFT, VK_SPACE, VK_T, VK_U, VK_UP, VK_V,
+                VK_W, VK_X, VK_Y, VK_Z,
             },
             WindowsAndMessaging::{
                 CallNextHookEx, GetForegroundWindow, GetSystemMetrics, GetWindowRect,
                 GetWindowThreadProcessId, HC_ACTION, HHOOK, KBDLLHOOKSTRUCT, LLKHF_INJECTED,
-                LLKHF_LOWER_IL_INJECTED, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN,
-                SM_YVIRTUALSCREEN, SetWindowsHookExW, WH_KEYBOARD_LL, WM_KEYDOWN, WM_KEYUP,
+                LLKHF_LOWER_IL_INJECTED, LLMHF_INJECTED, MSLLHOOKSTRUCT, SM_CXVIRTUALSCREEN,
+                SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN, SetWindowsHookExW,
+                WH_KEYBOARD_LL, WH_MOUSE_LL, WM_KEYDOWN, WM_KEYUP,
             },
         },
     },
@@ -42,13 +43,15 @@ use windows::{
 use super::{HandleCell, handle::Handle};
 use crate::{
     Error, Result,
-    input::{InputKind, KeyKind, KeyState, MouseKind},
+    input::{InputKind, KeyKind, KeyState, MouseKind, is_physical_input_ignored},
 };
 
 static KEY_CHANNEL: LazyLock<Sender<KeyKind>> = LazyLock::new(|| broadcast::channel(1).0);
 static PROCESS_ID: LazyLock<u32> = LazyLock::new(|| unsafe { GetCurrentProcessId() });
+/// The last time a key or the mouse was physically used, excluding injected inputs.
+static LAST_PHYSICAL_INPUT: Mutex<Option<Instant>> = Mutex::new(None);
 
-pub fn init() -> Owned<HHOOK> {
+pub fn init() -> (Owned<HHOOK>, Owned<HHOOK>) {
     unsafe extern "system" fn keyboard_ll(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
         let msg = wparam.0 as u32;
         if code as u32 == HC_ACTION && (msg == WM_KEYUP || msg == WM_KEYDOWN) {
@@ -57,6 +60,12 @@ pub fn init() -> Owned<HHOOK> {
             let vkey = unsafe { mem::transmute::<u16, VIRTUAL_KEY>(key.vkCode as u16) };
             let key_kind = KeyKind::try_from(vkey);
             let ignore = key.dwExtraInfo == *PROCESS_ID as usize;
+            let is_ignored_key = key_kind
+                .as_ref()
+                .is_ok_and(|kind| is_physical_input_ignored(*kind));
+            if !ignore && !is_ignored_key && (key.flags & LLKHF_INJECTED).0 == 0 {
+                *LAST_PHYSICAL_INPUT.lock().unwrap() = Some(Instant::now());
+            }
             if !ignore
                 && msg == WM_KEYUP
                 && let Ok(key) = key_kind
@@ -73,12 +82,31 @@ pub fn init() -> Owned<HHOOK> {
         }
         unsafe { CallNextHookEx(None, code, wparam, lparam) }
     }
-    unsafe { Owned::new(SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_ll), None, 0).unwrap()) }
+
+    unsafe extern "system" fn mouse_ll(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code as u32 == HC_ACTION {
+            let mouse = unsafe { (lparam.0 as *const MSLLHOOKSTRUCT).read() };
+            if mouse.flags & LLMHF_INJECTED == 0 {
+                *LAST_PHYSICAL_INPUT.lock().unwrap() = Some(Instant::now());
+            }
+        }
+        unsafe { CallNextHookEx(None, code, wparam, lparam) }
+    }
+
+    unsafe {
+        (
+            Owned::new(SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_ll), None, 0).unwrap()),
+            Owned::new(SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_ll), None, 0).unwrap()),
+        )
+    }
 }
 
 #[derive(Debug)]
 pub struct WindowsInputReceiver {
-    handle: HandleCell,
+    /// The handle to check for foreground before processing a key.
+    ///
+    /// If [`None`], keys are always processed regardless of the foreground window.
+    handle: Option<HandleCell>,
     input_kind: InputKind,
     rx: Receiver<KeyKind>,
 }
@@ -86,12 +114,20 @@ pub struct WindowsInputReceiver {
 impl WindowsInputReceiver {
     pub fn new(handle: Handle, input_kind: InputKind) -> Self {
         Self {
-            handle: HandleCell::new(handle),
+            handle: Some(HandleCell::new(handle)),
             input_kind,
             rx: KEY_CHANNEL.subscribe(),
         }
     }
 
+    pub fn new_global() -> Self {
+        Self {
+            handle: None,
+            input_kind: InputKind::Focused,
+            rx: KEY_CHANNEL.subscribe(),
+        }
+    }
+
     pub fn try_recv(&mut self) -> Option<KeyKind> {
         self.rx
             .try_recv()
@@ -99,8 +135,23 @@ impl WindowsInputReceiver {
             .and_then(|key| self.can_process_key().then_some(key))
     }
 
+    /// Gets the last time a key or the mouse was physically used if the window of this receiver
+    /// is in the foreground.
+    pub fn last_physical_input(&self) -> Option<Instant> {
+        let handle = self.handle.as_ref()?.as_inner()?;
+        if !is_foreground(handle, self.input_kind) {
+            return None;
+        }
+
+        *LAST_PHYSICAL_INPUT.lock().unwrap()
+    }
+
     // TODO: Is this good?
     fn can_process_key(&self) -> bool {
+        let Some(handle) = self.handle.as_ref() else {
+            return true;
+        };
+
         let fg = unsafe { GetForegroundWindow() };
         let mut fg_pid = 0;
         unsafe { GetWindowThreadProcessId(fg, Some(&raw mut fg_pid)) };
@@ -108,7 +159,7 @@ impl WindowsInputReceiver {
             return true;
         }
 
-        self.handle
+        handle
             .as_inner()
             .map(|handle| is_foreground(handle, self.input_kind))
             .unwrap_or_default()
@@ -177,6 +228,10 @@ impl WindowsInput {
             MouseKind::Scroll => {
                 send_input(mouse_input(dx, dy, base_flags | MOUSEEVENTF_WHEEL, -150))
             }
+            MouseKind::Down => {
+                send_input(mouse_input(dx, dy, base_flags | MOUSEEVENTF_LEFTDOWN, 0))
+            }
+            MouseKind::Up => send_input(mouse_input(dx, dy, base_flags | MOUSEEVENTF_LEFTUP, 0)),
         }
     }
 
@@ -321,6 +376,7 @@ impl TryFrom<VIRTUAL_KEY> for KeyKind {
             VK_OEM_COMMA => KeyKind::Comma,
             VK_OEM_PERIOD => KeyKind::Period,
             VK_OEM_2 => KeyKind::Slash,
+            VK_OEM_MINUS => KeyKind::Minus,
             VK_ESCAPE => KeyKind::Esc,
             VK_SHIFT => KeyKind::Shift,
             VK_MENU => KeyKind::Alt,
@@ -399,6 +455,7 @@ impl From<KeyKind> for VIRTUAL_KEY {
             KeyKind::Comma => VK_OEM_COMMA,
             KeyKind::Period => VK_OEM_PERIOD,
             KeyKind::Slash => VK_OEM_2,
+            KeyKind::Minus => VK_OEM_MINUS,
             KeyKind::Esc => VK_ESCAPE,
             KeyKind::Shift => VK_SHIFT,
             KeyKind::Alt => VK_MENU,
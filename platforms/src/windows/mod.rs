@@ -11,12 +11,17 @@ use windows::Win32::UI::WindowsAndMessaging::{
 };
 
 mod bitblt;
+mod clipboard;
+mod dpapi;
+mod gamepad;
 mod handle;
 mod input;
 mod wgc;
 mod window_box;
 
-pub use {bitblt::*, handle::*, input::*, wgc::*, window_box::*};
+pub use {
+    bitblt::*, clipboard::*, dpapi::*, gamepad::*, handle::*, input::*, wgc::*, window_box::*,
+};
 
 use crate::{Error, Result, capture::Frame};
 
@@ -48,7 +53,7 @@ pub fn init() {
         let barrier = Arc::new(Barrier::new(2));
         let keys_barrier = barrier.clone();
         thread::spawn(move || {
-            let _hook = input::init();
+            let _hooks = input::init();
             let mut msg = MSG::default();
             keys_barrier.wait();
             while unsafe { GetMessageW(&raw mut msg, None, 0, 0) }.as_bool() {
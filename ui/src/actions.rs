@@ -5,9 +5,16 @@ use std::{
 };
 
 use backend::{
-    Action, ActionCondition, ActionKey, ActionKeyDirection, ActionKeyWith, ActionMove, Bound,
-    IntoEnumIterator, KeyBinding, LinkKeyBinding, Map, MobbingKey, Platform, Position,
-    RotationMode, WaitAfterBuffered, key_receiver, update_map, upsert_map,
+    Action, ActionColor, ActionCondition, ActionKey, ActionKeyBurstBuff, ActionKeyCastVerification,
+    ActionKeyDirection, ActionKeyGroup, ActionKeyResourceGate, ActionKeyResourceGateKind,
+    ActionKeyWith, ActionMove, ActionNote, ActionTags, BehaviorNode, Bound, ConditionWebhookName,
+    DangerZone, IntoEnumIterator, KeyBinding, KeyTemplate, LinkKeyBinding, MAX_DANGER_ZONES_COUNT,
+    MAX_LINK_KEY_CHAIN_STEPS, MAX_ROPES_COUNT, MAX_RUNE_SPAWN_HINTS_COUNT, Map, MapHazard,
+    MapHazardAvoidance, MobColorSignature, MobDetection, MobDetectionKind, MobbingKey,
+    MobbingRegion, MobbingRegionSelection, Platform, PlayerMarker, PluginDetectorName, Position,
+    Rope, RotationMode, RuneSpawnHint, WaitAfterBuffered, delete_key_template,
+    detect_map_platforms, key_receiver, optimize_actions_route, query_key_templates,
+    query_plugin_detectors, update_map, upsert_key_template, upsert_map,
 };
 use dioxus::{html::FileData, prelude::*};
 use futures_util::StreamExt;
@@ -20,7 +27,7 @@ use crate::{
         button::{Button, ButtonStyle},
         checkbox::Checkbox,
         file::{FileInput, FileOutput},
-        icons::{DownArrowIcon, UpArrowIcon, XIcon},
+        icons::{DownArrowIcon, EyePasswordHideIcon, EyePasswordShowIcon, UpArrowIcon, XIcon},
         key::KeyInput,
         labeled::Labeled,
         named_select::NamedSelect,
@@ -29,6 +36,7 @@ use crate::{
         position::PositionInput,
         section::Section,
         select::{Select, SelectOption},
+        text::TextInput,
     },
 };
 
@@ -164,11 +172,23 @@ pub fn ActionsScreen() -> Element {
     rsx! {
         div { class: "flex flex-col pb-15 h-full gap-3 overflow-y-auto pr-2",
             SectionRotation { disabled: map().is_none() }
+            SectionBehaviorTree {
+                disabled: map().is_none_or(|map| map.rotation_mode != RotationMode::BehaviorTree),
+            }
+            SectionMobbingRegions {
+                disabled: map().is_none_or(|map| map.rotation_mode != RotationMode::AutoMobbing),
+            }
             SectionPlatforms { disabled: map().is_none() }
+            SectionRopes { disabled: map().is_none() }
+            SectionDangerZones { disabled: map().is_none() }
+            SectionMapHazards { disabled: map().is_none() }
+            SectionRuneSpawnHints { disabled: map().is_none() }
+            SectionPlayerMarker { disabled: map().is_none() }
             SectionActions {
                 actions: map_preset_actions,
                 disabled: map().is_none() || map_preset().is_none(),
             }
+            SectionKeyTemplates {}
             SectionLegends {}
         }
 
@@ -234,7 +254,9 @@ fn SectionRotation(disabled: bool) -> Element {
         let mut map = map();
 
         match map.rotation_mode {
-            RotationMode::StartToEnd | RotationMode::StartToEndThenReverse => return,
+            RotationMode::StartToEnd
+            | RotationMode::StartToEndThenReverse
+            | RotationMode::BehaviorTree => return,
             RotationMode::AutoMobbing => {
                 map.rotation_auto_mob_bound = bound;
             }
@@ -245,6 +267,19 @@ fn SectionRotation(disabled: bool) -> Element {
         save_map(map);
     });
 
+    let save_mob_detection = use_callback(move |mob_detection: MobDetection| {
+        save_map(Map {
+            mob_detection,
+            ..map.peek().clone()
+        });
+    });
+    let save_mob_color_signature = use_callback(move |color_signature: MobColorSignature| {
+        save_mob_detection(MobDetection {
+            color_signature,
+            ..map.peek().mob_detection.clone()
+        });
+    });
+
     let mut popup_content = use_signal(|| PopupContent::None);
     let mut popup_open = use_signal(|| false);
 
@@ -267,7 +302,31 @@ fn SectionRotation(disabled: bool) -> Element {
                         },
                         selected: map().rotation_mode,
                     }
-                    div {}
+                    ActionsCheckbox {
+                        label: "Ping pong bounces vertically",
+                        tooltip: "Bounces between the top and bottom of the mobbing bound by up jumping and falling instead of moving horizontally.",
+                        disabled: disabled || map().rotation_mode != RotationMode::PingPong,
+                        on_checked: move |rotation_ping_pong_vertical| {
+                            save_map(Map {
+                                rotation_ping_pong_vertical,
+                                ..map.peek().clone()
+                            })
+                        },
+                        checked: map().rotation_ping_pong_vertical,
+                    }
+                    ActionsCheckbox {
+                        label: "Ping pong from screen when minimap hidden",
+                        tooltip: "Keeps horizontal ping pong running by estimating the movement from the screen scrolling when the minimap cannot be detected.",
+                        disabled: disabled || map().rotation_mode != RotationMode::PingPong
+                            || map().rotation_ping_pong_vertical,
+                        on_checked: move |rotation_ping_pong_screen_fallback| {
+                            save_map(Map {
+                                rotation_ping_pong_screen_fallback,
+                                ..map.peek().clone()
+                            })
+                        },
+                        checked: map().rotation_ping_pong_screen_fallback,
+                    }
                     PopupTrigger {
                         Button {
                             style: ButtonStyle::Primary,
@@ -276,7 +335,9 @@ fn SectionRotation(disabled: bool) -> Element {
                             on_click: move |_| {
                                 let map = map.peek();
                                 let key = match map.rotation_mode {
-                                    RotationMode::StartToEnd | RotationMode::StartToEndThenReverse => {
+                                    RotationMode::StartToEnd
+                                    | RotationMode::StartToEndThenReverse
+                                    | RotationMode::BehaviorTree => {
                                         unreachable!()
                                     }
                                     RotationMode::AutoMobbing | RotationMode::PingPong => {
@@ -297,7 +358,9 @@ fn SectionRotation(disabled: bool) -> Element {
                             on_click: move |_| {
                                 let map = map.peek();
                                 let bound = match map.rotation_mode {
-                                    RotationMode::StartToEnd | RotationMode::StartToEndThenReverse => {
+                                    RotationMode::StartToEnd
+                                    | RotationMode::StartToEndThenReverse
+                                    | RotationMode::BehaviorTree => {
                                         unreachable!()
                                     }
                                     RotationMode::AutoMobbing => map.rotation_auto_mob_bound,
@@ -332,6 +395,29 @@ fn SectionRotation(disabled: bool) -> Element {
                         },
                         value: map().auto_mob_use_key_when_pathing_update_millis,
                     }
+                    ActionsCheckbox {
+                        label: "Auto mobbing picks up loots",
+                        tooltip: "Walks over nearby detected loots before continuing auto mobbing. Loots are detected by color and may have false detections.",
+                        disabled,
+                        on_checked: move |auto_mob_loot_pickup| {
+                            save_map(Map {
+                                auto_mob_loot_pickup,
+                                ..map.peek().clone()
+                            })
+                        },
+                        checked: map().auto_mob_loot_pickup,
+                    }
+                    ActionsNumberInputI32 {
+                        label: "Loot pickup radius (0 for no limit)",
+                        disabled,
+                        on_value: move |auto_mob_loot_pickup_radius| {
+                            save_map(Map {
+                                auto_mob_loot_pickup_radius,
+                                ..map.peek().clone()
+                            })
+                        },
+                        value: map().auto_mob_loot_pickup_radius,
+                    }
                     ActionsCheckbox {
                         label: "Reset normal actions on Erda Shower resets",
                         disabled,
@@ -345,6 +431,111 @@ fn SectionRotation(disabled: bool) -> Element {
                     }
                 }
             }
+            Section { title: "Mob detection",
+                div { class: "grid grid-cols-2 gap-3",
+                    ActionsSelect::<MobDetectionKind> {
+                        label: "Detection",
+                        tooltip: "How mobs are detected for auto mobbing. A custom model or color signature can target specific mob types in this map.",
+                        disabled,
+                        on_selected: move |kind| {
+                            save_mob_detection(MobDetection {
+                                kind,
+                                ..map.peek().mob_detection.clone()
+                            })
+                        },
+                        selected: map().mob_detection.kind,
+                    }
+                    Labeled { label: "Model path",
+                        TextInput {
+                            class: "h-6",
+                            disabled: disabled || map().mob_detection.kind != MobDetectionKind::Model,
+                            on_value: move |model_path| {
+                                save_mob_detection(MobDetection {
+                                    model_path,
+                                    ..map.peek().mob_detection.clone()
+                                })
+                            },
+                            value: map().mob_detection.model_path,
+                        }
+                    }
+                }
+                if map().mob_detection.kind == MobDetectionKind::ColorSignature {
+                    div { class: "grid grid-cols-3 gap-3 mt-3",
+                        Labeled { label: "Hue min",
+                            PrimitiveIntegerInput {
+                                disabled,
+                                on_value: move |hue_min| {
+                                    save_mob_color_signature(MobColorSignature {
+                                        hue_min,
+                                        ..map.peek().mob_detection.color_signature
+                                    })
+                                },
+                                value: map().mob_detection.color_signature.hue_min,
+                            }
+                        }
+                        Labeled { label: "Hue max",
+                            PrimitiveIntegerInput {
+                                disabled,
+                                on_value: move |hue_max| {
+                                    save_mob_color_signature(MobColorSignature {
+                                        hue_max,
+                                        ..map.peek().mob_detection.color_signature
+                                    })
+                                },
+                                value: map().mob_detection.color_signature.hue_max,
+                            }
+                        }
+                        div {}
+                        Labeled { label: "Saturation min",
+                            PrimitiveIntegerInput {
+                                disabled,
+                                on_value: move |saturation_min| {
+                                    save_mob_color_signature(MobColorSignature {
+                                        saturation_min,
+                                        ..map.peek().mob_detection.color_signature
+                                    })
+                                },
+                                value: map().mob_detection.color_signature.saturation_min,
+                            }
+                        }
+                        Labeled { label: "Value min",
+                            PrimitiveIntegerInput {
+                                disabled,
+                                on_value: move |value_min| {
+                                    save_mob_color_signature(MobColorSignature {
+                                        value_min,
+                                        ..map.peek().mob_detection.color_signature
+                                    })
+                                },
+                                value: map().mob_detection.color_signature.value_min,
+                            }
+                        }
+                        div {}
+                        ActionsNumberInputI32 {
+                            label: "Area min",
+                            disabled,
+                            on_value: move |area_min| {
+                                save_mob_color_signature(MobColorSignature {
+                                    area_min,
+                                    ..map.peek().mob_detection.color_signature
+                                })
+                            },
+                            value: map().mob_detection.color_signature.area_min,
+                        }
+                        ActionsNumberInputI32 {
+                            label: "Area max",
+                            disabled,
+                            on_value: move |area_max| {
+                                save_mob_color_signature(MobColorSignature {
+                                    area_max,
+                                    ..map.peek().mob_detection.color_signature
+                                })
+                            },
+                            value: map().mob_detection.color_signature.area_max,
+                        }
+                    }
+                }
+            }
 
             match popup_content() {
                 #[allow(clippy::double_parens)]
@@ -378,6 +569,243 @@ fn SectionRotation(disabled: bool) -> Element {
     }
 }
 
+#[component]
+fn SectionBehaviorTree(disabled: bool) -> Element {
+    let context = use_context::<ActionsContext>();
+    let map = context.map;
+    let save_map = context.save_map;
+
+    let export_name = use_memo(move || format!("{}-behavior-tree.json", map().name));
+    let export_content =
+        move |_| serde_json::to_vec_pretty(&map.peek().rotation_behavior_tree).unwrap_or_default();
+    let import_tree = use_callback(move |file: FileData| async move {
+        let Ok(bytes) = file.read_bytes().await else {
+            return;
+        };
+        let Ok(rotation_behavior_tree) = serde_json::from_slice::<'_, Option<BehaviorNode>>(&bytes)
+        else {
+            return;
+        };
+
+        save_map(Map {
+            rotation_behavior_tree,
+            ..map.peek().clone()
+        });
+    });
+    let clear_tree = use_callback(move |_| {
+        save_map(Map {
+            rotation_behavior_tree: None,
+            ..map.peek().clone()
+        });
+    });
+
+    rsx! {
+        Section { title: "Behavior tree",
+            p { class: "text-xxs text-secondary-text pb-2",
+                if map().rotation_behavior_tree.is_some() {
+                    "A behavior tree is used instead of the normal actions."
+                } else {
+                    "No behavior tree, import one to use instead of the normal actions."
+                }
+            }
+            div { class: "flex gap-2",
+                FileInput {
+                    class: "flex-grow",
+                    on_file: move |file| async move {
+                        import_tree(file).await;
+                    },
+                    disabled,
+                    Button {
+                        class: "w-full",
+                        style: ButtonStyle::Primary,
+                        disabled,
+                        "Import"
+                    }
+                }
+                FileOutput {
+                    class: "flex-grow",
+                    on_file: export_content,
+                    download: export_name(),
+                    disabled: disabled || map().rotation_behavior_tree.is_none(),
+                    Button {
+                        class: "w-full",
+                        style: ButtonStyle::Primary,
+                        disabled: disabled || map().rotation_behavior_tree.is_none(),
+                        "Export"
+                    }
+                }
+                Button {
+                    class: "flex-grow",
+                    style: ButtonStyle::Secondary,
+                    disabled: disabled || map().rotation_behavior_tree.is_none(),
+                    on_click: move |_| {
+                        clear_tree(());
+                    },
+                    "Clear"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionMobbingRegions(disabled: bool) -> Element {
+    #[component]
+    fn RegionItem(
+        region: MobbingRegion,
+        on_item_click: Callback,
+        on_item_delete: Callback,
+    ) -> Element {
+        const ICON_CONTAINER_CLASS: &str = "w-4 h-6 flex justify-center items-center";
+        const ICON_CLASS: &str = "size-3";
+
+        let bound = region.bound;
+
+        rsx! {
+            div { class: "flex group",
+                div {
+                    class: "flex-grow grid grid-cols-2 h-6 text-xxs gap-2 text-secondary-text group-hover:bg-secondary-surface",
+                    onclick: move |_| {
+                        on_item_click(());
+                    },
+                    div { class: "{ITEM_BORDER_CLASS} {ITEM_TEXT_CLASS}", {region.name} }
+                    div { class: "{ITEM_TEXT_CLASS}",
+                        {format!("{}, {} / {} x {}", bound.x, bound.y, bound.width, bound.height)}
+                    }
+                }
+                div { class: "self-stretch invisible group-hover:visible group-hover:bg-secondary-surface flex items-center pr-1",
+                    div {
+                        class: ICON_CONTAINER_CLASS,
+                        onclick: move |e| {
+                            e.stop_propagation();
+                            on_item_delete(());
+                        },
+                        XIcon { class: "{ICON_CLASS}" }
+                    }
+                }
+            }
+        }
+    }
+
+    #[derive(PartialEq, Clone)]
+    enum PopupContent {
+        None,
+        Edit { region: MobbingRegion, index: usize },
+        Add,
+    }
+
+    let context = use_context::<ActionsContext>();
+    let map = context.map;
+    let save_map = context.save_map;
+
+    let add_region = use_callback(move |region| {
+        let mut map = map();
+
+        map.rotation_auto_mob_regions.push(region);
+        save_map(map);
+    });
+    let edit_region = use_callback(move |(new_region, index): (MobbingRegion, usize)| {
+        let mut map = map();
+        let Some(region) = map.rotation_auto_mob_regions.get_mut(index) else {
+            return;
+        };
+
+        *region = new_region;
+        save_map(map);
+    });
+    let delete_region = use_callback(move |index| {
+        let mut map = map();
+
+        map.rotation_auto_mob_regions.remove(index);
+        save_map(map);
+    });
+
+    let mut popup_content = use_signal(|| PopupContent::None);
+    let mut popup_open = use_signal(|| false);
+
+    rsx! {
+        PopupContext {
+            open: popup_open,
+            on_open: move |open: bool| {
+                popup_open.set(open);
+            },
+            Section { title: "Auto-mobbing regions",
+                div { class: "grid grid-cols-2 gap-3",
+                    ActionsSelect::<MobbingRegionSelection> {
+                        label: "Next region",
+                        tooltip: "How the next region is selected when the current region runs out of mobs. Regions are used instead of the mobbing bound when not empty.",
+                        disabled,
+                        on_selected: move |rotation_auto_mob_region_selection| {
+                            save_map(Map {
+                                rotation_auto_mob_region_selection,
+                                ..map.peek().clone()
+                            })
+                        },
+                        selected: map().rotation_auto_mob_region_selection,
+                    }
+                    div {}
+                }
+                if !map().rotation_auto_mob_regions.is_empty() {
+                    div { class: "mt-2" }
+                }
+                for (index , region) in map().rotation_auto_mob_regions.into_iter().enumerate() {
+                    PopupTrigger {
+                        RegionItem {
+                            region: region.clone(),
+                            on_item_click: move |_| {
+                                popup_content
+                                    .set(PopupContent::Edit {
+                                        region: region.clone(),
+                                        index,
+                                    });
+                            },
+                            on_item_delete: move |_| {
+                                delete_region(index);
+                            },
+                        }
+                    }
+                }
+
+                PopupTrigger {
+                    Button {
+                        style: ButtonStyle::Secondary,
+                        on_click: move |_| {
+                            popup_content.set(PopupContent::Add);
+                        },
+                        disabled,
+                        class: "mt-2 w-full",
+
+                        "Add region"
+                    }
+                }
+
+                PopupMobbingRegionInputContent {
+                    modifying: match popup_content() {
+                        PopupContent::None | PopupContent::Add => false,
+                        PopupContent::Edit { .. } => true,
+                    },
+                    on_cancel: move |_| {
+                        popup_open.set(false);
+                    },
+                    on_value: move |region| {
+                        let content = popup_content.peek().clone();
+                        match content {
+                            PopupContent::None => unreachable!(),
+                            PopupContent::Add => add_region(region),
+                            PopupContent::Edit { index, .. } => edit_region((region, index)),
+                        }
+                        popup_open.set(false);
+                    },
+                    value: match popup_content() {
+                        PopupContent::None | PopupContent::Add => MobbingRegion::default(),
+                        PopupContent::Edit { region, .. } => region,
+                    },
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn SectionPlatforms(disabled: bool) -> Element {
     #[component]
@@ -452,6 +880,18 @@ fn SectionPlatforms(disabled: bool) -> Element {
         coroutine.send(ActionsUpdate::UpdateMinimap(map));
     });
 
+    let mut detected_platforms = use_signal(Vec::<Platform>::new);
+    let accept_detected_platform = use_callback(move |index: usize| {
+        let platform = detected_platforms.write().remove(index);
+        add_platform(platform);
+    });
+    let accept_all_detected_platforms = use_callback(move |_| {
+        let mut map = map();
+
+        map.platforms.append(&mut detected_platforms.write());
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
+    });
+
     let mut popup_content = use_signal(|| PopupContent::None);
     let mut popup_open = use_signal(|| false);
 
@@ -555,6 +995,17 @@ fn SectionPlatforms(disabled: bool) -> Element {
                         },
                         checked: map().auto_mob_platforms_bound,
                     }
+                    ActionsNumberInputI32 {
+                        label: "Edge safety margin",
+                        disabled,
+                        on_value: move |platform_edge_margin| {
+                            save_map(Map {
+                                platform_edge_margin,
+                                ..map.peek().clone()
+                            })
+                        },
+                        value: map().platform_edge_margin,
+                    }
                 }
                 if !map().platforms.is_empty() {
                     div { class: "mt-2" }
@@ -589,20 +1040,56 @@ fn SectionPlatforms(disabled: bool) -> Element {
                         "Add platform"
                     }
                 }
+                div { class: "grid grid-cols-2 gap-3 mt-2",
+                    Button {
+                        style: ButtonStyle::Secondary,
+                        on_click: move |_| async move {
+                            detected_platforms.set(detect_map_platforms().await);
+                        },
+                        disabled,
 
-                PopupPlatformInputContent {
-                    modifying: match popup_content() {
-                        PopupContent::None | PopupContent::Add => false,
-                        PopupContent::Edit { .. } => true,
-                    },
-                    on_cancel: move |_| {
-                        popup_open.set(false);
-                    },
-                    on_value: move |mut platform| {
-                        update_valid_platform_end(&mut platform);
-                        let content = *popup_content.peek();
-                        match content {
-                            PopupContent::None => unreachable!(),
+                        "Detect platforms"
+                    }
+                    Button {
+                        style: ButtonStyle::Secondary,
+                        on_click: move |_| {
+                            accept_all_detected_platforms(());
+                        },
+                        disabled: disabled || detected_platforms().is_empty(),
+
+                        "Accept all detected"
+                    }
+                }
+                if !detected_platforms().is_empty() {
+                    div { class: "mt-2 text-xxs text-secondary-text",
+                        "Detected platforms (click to accept)"
+                    }
+                }
+                for (index , platform) in detected_platforms().into_iter().enumerate() {
+                    PlatformItem {
+                        platform,
+                        on_item_click: move |_| {
+                            accept_detected_platform(index);
+                        },
+                        on_item_delete: move |_| {
+                            detected_platforms.write().remove(index);
+                        },
+                    }
+                }
+
+                PopupPlatformInputContent {
+                    modifying: match popup_content() {
+                        PopupContent::None | PopupContent::Add => false,
+                        PopupContent::Edit { .. } => true,
+                    },
+                    on_cancel: move |_| {
+                        popup_open.set(false);
+                    },
+                    on_value: move |mut platform| {
+                        update_valid_platform_end(&mut platform);
+                        let content = *popup_content.peek();
+                        match content {
+                            PopupContent::None => unreachable!(),
                             PopupContent::Add => add_platform(platform),
                             PopupContent::Edit { index, .. } => edit_platform((platform, index)),
                         }
@@ -619,222 +1106,359 @@ fn SectionPlatforms(disabled: bool) -> Element {
 }
 
 #[component]
-fn SectionLegends() -> Element {
-    rsx! {
-        Section { title: "Action legends", class: "text-xs text-primary-text",
-            p { "⟳ - Repeat" }
-            p { "⏱︎  - Wait" }
-            p { "ㄨ - No position" }
-            p { "⇈ - Queue to front" }
-            p { "⇆ - Any direction" }
-            p { "← - Left direction" }
-            p { "→ - Right direction" }
-            p { "A ~ B - Random range between A and B" }
-            p { "A ↝ B - Use A key then B key" }
-            p { "A ↜ B - Use B key then A key" }
-            p { "A ↭ B - Use A and B keys at the same time" }
-            p { "A ↷ B - Use A key then B key while A is held down" }
+fn SectionRopes(disabled: bool) -> Element {
+    #[component]
+    fn RopeItem(rope: Rope, on_item_click: Callback, on_item_delete: Callback) -> Element {
+        const ICON_CONTAINER_CLASS: &str = "w-4 h-6 flex justify-center items-center";
+        const ICON_CLASS: &str = "size-3";
+
+        rsx! {
+            div { class: "flex group",
+                div {
+                    class: "flex-grow grid grid-cols-2 h-6 text-xxs gap-2 text-secondary-text group-hover:bg-secondary-surface",
+                    onclick: move |_| {
+                        on_item_click(());
+                    },
+                    div { class: "{ITEM_BORDER_CLASS} {ITEM_TEXT_CLASS}", {format!("X / {}", rope.x)} }
+                    div { class: "{ITEM_TEXT_CLASS}",
+                        {format!("Y / {} - {}", rope.y_start, rope.y_end)}
+                    }
+                }
+                div { class: "self-stretch invisible group-hover:visible group-hover:bg-secondary-surface flex items-center pr-1",
+                    div {
+                        class: ICON_CONTAINER_CLASS,
+                        onclick: move |e| {
+                            e.stop_propagation();
+                            on_item_delete(());
+                        },
+                        XIcon { class: "{ICON_CLASS}" }
+                    }
+                }
+            }
         }
     }
-}
 
-#[component]
-fn SectionActions(actions: Memo<Vec<Action>>, disabled: bool) -> Element {
-    #[derive(Clone, Copy, PartialEq)]
+    #[derive(PartialEq, Clone, Copy)]
     enum PopupContent {
         None,
-        Add(Action),
-        Edit { action: Action, index: usize },
+        Edit { rope: Rope, index: usize },
+        Add,
     }
 
     let coroutine = use_coroutine_handle::<ActionsUpdate>();
-    let map = use_context::<ActionsContext>().map;
+    let context = use_context::<ActionsContext>();
 
-    let export_name = use_memo(move || format!("{}.json", map().name));
-    let export_content = move |_| serde_json::to_vec_pretty(&*actions.peek()).unwrap_or_default();
+    let map = context.map;
 
-    let import_actions = use_callback(move |file: FileData| async move {
-        let mut actions = actions();
+    let add_rope = use_callback(move |rope| {
+        let mut map = map();
 
-        let Ok(bytes) = file.read_bytes().await else {
-            return;
-        };
-        let Ok(import_actions) = serde_json::from_slice::<'_, Vec<Action>>(&bytes) else {
+        map.ropes.push(rope);
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
+    });
+    let edit_rope = use_callback(move |(new_rope, index): (Rope, usize)| {
+        let mut map = map();
+        let Some(rope) = map.ropes.get_mut(index) else {
             return;
         };
 
-        let mut i = 0;
-        while i < import_actions.len() {
-            let action = import_actions[i];
-            if matches!(action.condition(), ActionCondition::Linked) {
-                // Malformed
-                i += 1;
-                continue;
+        *rope = new_rope;
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
+    });
+    let delete_rope = use_callback(move |index| {
+        let mut map = map();
+
+        map.ropes.remove(index);
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
+    });
+
+    let mut popup_content = use_signal(|| PopupContent::None);
+    let mut popup_open = use_signal(|| false);
+
+    rsx! {
+        PopupContext {
+            open: popup_open,
+            on_open: move |open: bool| {
+                popup_open.set(open);
+            },
+            Section { title: "Ropes",
+                for (index , rope) in map().ropes.into_iter().enumerate() {
+                    PopupTrigger {
+                        RopeItem {
+                            rope,
+                            on_item_click: move |_| {
+                                popup_content.set(PopupContent::Edit { rope, index });
+                            },
+                            on_item_delete: move |_| {
+                                delete_rope(index);
+                            },
+                        }
+                    }
+                }
+
+                PopupTrigger {
+                    Button {
+                        style: ButtonStyle::Secondary,
+                        on_click: move |_| {
+                            popup_content.set(PopupContent::Add);
+                        },
+                        disabled: disabled || map().ropes.len() >= MAX_ROPES_COUNT,
+                        class: "mt-2 w-full",
+
+                        "Add rope"
+                    }
+                }
+
+                PopupRopeInputContent {
+                    modifying: match popup_content() {
+                        PopupContent::None | PopupContent::Add => false,
+                        PopupContent::Edit { .. } => true,
+                    },
+                    on_cancel: move |_| {
+                        popup_open.set(false);
+                    },
+                    on_value: move |mut rope| {
+                        update_valid_rope_end(&mut rope);
+                        let content = *popup_content.peek();
+                        match content {
+                            PopupContent::None => unreachable!(),
+                            PopupContent::Add => add_rope(rope),
+                            PopupContent::Edit { index, .. } => edit_rope((rope, index)),
+                        }
+                        popup_open.set(false);
+                    },
+                    value: match popup_content() {
+                        PopupContent::None | PopupContent::Add => Rope::default(),
+                        PopupContent::Edit { rope, .. } => rope,
+                    },
+                }
             }
+        }
+    }
+}
 
-            actions.push(action);
-            if let Some(range) = find_linked_action_range(&import_actions, i) {
-                actions.extend(import_actions[range.clone()].iter().copied());
-                i += range.count();
+#[component]
+fn SectionDangerZones(disabled: bool) -> Element {
+    #[component]
+    fn DangerZoneItem(
+        zone: DangerZone,
+        on_item_click: Callback,
+        on_item_delete: Callback,
+    ) -> Element {
+        const ICON_CONTAINER_CLASS: &str = "w-4 h-6 flex justify-center items-center";
+        const ICON_CLASS: &str = "size-3";
+
+        rsx! {
+            div { class: "flex group",
+                div {
+                    class: "flex-grow grid grid-cols-2 h-6 text-xxs gap-2 text-secondary-text group-hover:bg-secondary-surface",
+                    onclick: move |_| {
+                        on_item_click(());
+                    },
+                    div { class: "{ITEM_BORDER_CLASS} {ITEM_TEXT_CLASS}",
+                        {format!("X / {} - {}", zone.x_start, zone.x_end)}
+                    }
+                    div { class: "{ITEM_TEXT_CLASS}", {format!("Y / {}", zone.y)} }
+                }
+                div { class: "self-stretch invisible group-hover:visible group-hover:bg-secondary-surface flex items-center pr-1",
+                    div {
+                        class: ICON_CONTAINER_CLASS,
+                        onclick: move |e| {
+                            e.stop_propagation();
+                            on_item_delete(());
+                        },
+                        XIcon { class: "{ICON_CLASS}" }
+                    }
+                }
             }
-            i += 1;
         }
+    }
 
-        coroutine.send(ActionsUpdate::Update(actions));
-    });
+    #[derive(PartialEq, Clone, Copy)]
+    enum PopupContent {
+        None,
+        Edit { zone: DangerZone, index: usize },
+        Add,
+    }
 
-    let add_action = use_callback(move |(action, condition): (Action, ActionCondition)| {
-        let mut actions = actions();
-        let index = if matches!(action.condition(), ActionCondition::Linked) {
-            find_last_linked_action_index(&actions, condition)
-                .map(|index| index + 1)
-                .unwrap_or(actions.len())
-        } else {
-            actions.len()
-        };
+    let coroutine = use_coroutine_handle::<ActionsUpdate>();
+    let context = use_context::<ActionsContext>();
 
-        actions.insert(index, action);
-        coroutine.send(ActionsUpdate::Update(actions));
-    });
+    let map = context.map;
 
-    let edit_action = use_callback(move |(new_action, index): (Action, usize)| {
-        let mut actions = actions();
-        let Some(action) = actions.get_mut(index) else {
-            return;
-        };
+    let add_zone = use_callback(move |zone| {
+        let mut map = map();
 
-        *action = new_action;
-        coroutine.send(ActionsUpdate::Update(actions));
+        map.danger_zones.push(zone);
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
     });
-
-    let delete_action = use_callback(move |index: usize| {
-        let mut actions = actions();
-        let Some(condition) = actions.get(index).map(|action| action.condition()) else {
+    let edit_zone = use_callback(move |(new_zone, index): (DangerZone, usize)| {
+        let mut map = map();
+        let Some(zone) = map.danger_zones.get_mut(index) else {
             return;
         };
 
-        // Replaces the first linked action to this `action` condition
-        // TODO: Maybe replace find_linked_action_range with a simple lookahead
-        if !matches!(condition, ActionCondition::Linked)
-            && find_linked_action_range(&actions, index).is_some()
-        {
-            actions[index + 1] = actions[index + 1].with_condition(condition);
-        }
-        actions.remove(index);
-        coroutine.send(ActionsUpdate::Update(actions));
+        *zone = new_zone;
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
     });
+    let delete_zone = use_callback(move |index| {
+        let mut map = map();
 
-    let move_action = use_callback(
-        move |(index, condition, up): (usize, ActionCondition, bool)| {
-            let mut actions = actions();
-            let filtered = filter_actions(actions.clone(), condition);
-            if (up && index <= filtered.first().expect("cannot be empty").1)
-                || (!up && index >= filtered.last().expect("cannot be empty").1)
-            {
-                return;
-            }
+        map.danger_zones.remove(index);
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
+    });
 
-            // Finds the action index of `filtered` before or after `index`
-            let filtered_index = filtered
-                .iter()
-                .enumerate()
-                .find_map(|(filtered_index, (_, actions_index))| {
-                    if *actions_index == index {
-                        if up {
-                            Some(filtered_index - 1)
-                        } else {
-                            Some(filtered_index + 1)
-                        }
-                    } else {
-                        None
+    let mut popup_content = use_signal(|| PopupContent::None);
+    let mut popup_open = use_signal(|| false);
+
+    rsx! {
+        PopupContext {
+            open: popup_open,
+            on_open: move |open: bool| {
+                popup_open.set(open);
+            },
+            Section { title: "Danger zones",
+                for (index , zone) in map().danger_zones.into_iter().enumerate() {
+                    PopupTrigger {
+                        DangerZoneItem {
+                            zone,
+                            on_item_click: move |_| {
+                                popup_content.set(PopupContent::Edit { zone, index });
+                            },
+                            on_item_delete: move |_| {
+                                delete_zone(index);
+                            },
+                        }
                     }
-                })
-                .expect("must be valid index");
-            let filtered_condition = filtered[filtered_index].0.condition();
-            let action_condition = actions[index].condition();
-            match (action_condition, filtered_condition) {
-                // Simple case - swapping two linked actions
-                (ActionCondition::Linked, ActionCondition::Linked) => {
-                    actions.swap(index, filtered[filtered_index].1);
-                    coroutine.send(ActionsUpdate::Update(actions));
-                    return;
                 }
-                // Disallows moving up/down if `index` is a linked action and
-                // `filtered_index` is a non-linked action
-                (ActionCondition::Linked, _) => return,
-                _ => (),
-            }
 
-            // Finds the first non-linked action index of `filtered` before or after `index`
-            let mut filtered_non_linked_index = filtered_index;
-            while (up && filtered_non_linked_index > 0)
-                || (!up && filtered_non_linked_index < filtered.len() - 1)
-            {
-                let condition = filtered[filtered_non_linked_index].0.condition();
-                if !matches!(condition, ActionCondition::Linked) {
-                    break;
+                PopupTrigger {
+                    Button {
+                        style: ButtonStyle::Secondary,
+                        on_click: move |_| {
+                            popup_content.set(PopupContent::Add);
+                        },
+                        disabled: disabled || map().danger_zones.len() >= MAX_DANGER_ZONES_COUNT,
+                        class: "mt-2 w-full",
+
+                        "Add danger zone"
+                    }
                 }
-                if up {
-                    filtered_non_linked_index -= 1;
-                } else {
-                    filtered_non_linked_index += 1;
+
+                PopupDangerZoneInputContent {
+                    modifying: match popup_content() {
+                        PopupContent::None | PopupContent::Add => false,
+                        PopupContent::Edit { .. } => true,
+                    },
+                    on_cancel: move |_| {
+                        popup_open.set(false);
+                    },
+                    on_value: move |mut zone| {
+                        update_valid_danger_zone_end(&mut zone);
+                        let content = *popup_content.peek();
+                        match content {
+                            PopupContent::None => unreachable!(),
+                            PopupContent::Add => add_zone(zone),
+                            PopupContent::Edit { index, .. } => edit_zone((zone, index)),
+                        }
+                        popup_open.set(false);
+                    },
+                    value: match popup_content() {
+                        PopupContent::None | PopupContent::Add => DangerZone::default(),
+                        PopupContent::Edit { zone, .. } => zone,
+                    },
                 }
             }
-            let condition = filtered[filtered_non_linked_index].0.condition();
-            if matches!(condition, ActionCondition::Linked) {
-                return;
-            }
-
-            let actions_non_linked_index = filtered[filtered_non_linked_index].1;
-            let first_range = find_linked_action_range(&actions, actions_non_linked_index);
-            let mut first_range = if let Some(range) = first_range {
-                actions_non_linked_index..range.end
-            } else {
-                actions_non_linked_index..actions_non_linked_index + 1
-            };
+        }
+    }
+}
 
-            let second_range = find_linked_action_range(&actions, index);
-            let mut second_range = if let Some(range) = second_range {
-                index..range.end
-            } else {
-                index..index + 1
-            };
+#[component]
+fn SectionMapHazards(disabled: bool) -> Element {
+    #[component]
+    fn MapHazardItem(
+        hazard: MapHazard,
+        on_item_click: Callback,
+        on_item_delete: Callback,
+    ) -> Element {
+        const ICON_CONTAINER_CLASS: &str = "w-4 h-6 flex justify-center items-center";
+        const ICON_CLASS: &str = "size-3";
 
-            if !up {
-                swap(&mut first_range, &mut second_range);
+        let mut avoidance = match hazard.avoidance {
+            MapHazardAvoidance::Jump => hazard.avoidance.to_string(),
+            MapHazardAvoidance::MoveToSafeZone => {
+                format!(
+                    "{} / {}, {}",
+                    hazard.avoidance, hazard.safe_x, hazard.safe_y
+                )
             }
+        };
+        if !hazard.enabled {
+            avoidance.push_str(" (disabled)");
+        }
 
-            debug_assert!(
-                first_range.end <= second_range.start || second_range.end <= first_range.start
-            );
-            let second_start = second_range.start;
-            let second_actions = actions.drain(second_range).collect::<Vec<_>>();
-            let first_actions = actions[first_range.clone()].to_vec();
-            for action in first_actions.into_iter().rev() {
-                actions.insert(second_start, action);
+        rsx! {
+            div { class: "flex group",
+                div {
+                    class: "flex-grow grid grid-cols-2 h-6 text-xxs gap-2 text-secondary-text group-hover:bg-secondary-surface",
+                    onclick: move |_| {
+                        on_item_click(());
+                    },
+                    div { class: "{ITEM_BORDER_CLASS} {ITEM_TEXT_CLASS}",
+                        {format!("Every {}ms", hazard.interval_millis)}
+                    }
+                    div { class: "{ITEM_TEXT_CLASS}", {avoidance} }
+                }
+                div { class: "self-stretch invisible group-hover:visible group-hover:bg-secondary-surface flex items-center pr-1",
+                    div {
+                        class: ICON_CONTAINER_CLASS,
+                        onclick: move |e| {
+                            e.stop_propagation();
+                            on_item_delete(());
+                        },
+                        XIcon { class: "{ICON_CLASS}" }
+                    }
+                }
             }
+        }
+    }
 
-            let first_start = first_range.start;
-            let _ = actions.drain(first_range);
-            for action in second_actions.into_iter().rev() {
-                actions.insert(first_start, action);
-            }
-            coroutine.send(ActionsUpdate::Update(actions));
-        },
-    );
+    #[derive(PartialEq, Clone, Copy)]
+    enum PopupContent {
+        None,
+        Edit { hazard: MapHazard, index: usize },
+        Add,
+    }
 
-    let mut popup_content = use_signal(|| PopupContent::None);
-    let mut popup_open = use_signal(|| false);
+    let coroutine = use_coroutine_handle::<ActionsUpdate>();
+    let context = use_context::<ActionsContext>();
 
-    let mut handle_add_action_click = move |condition: ActionCondition| {
-        let action = Action::Key(ActionKey::default()).with_condition(condition);
-        let content = PopupContent::Add(action);
-        popup_content.set(content);
-    };
+    let map = context.map;
 
-    let mut handle_edit_action_click = move |action: Action, index: usize| {
-        popup_content.set(PopupContent::Edit { action, index });
-    };
+    let add_hazard = use_callback(move |hazard| {
+        let mut map = map();
+
+        map.hazards.push(hazard);
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
+    });
+    let edit_hazard = use_callback(move |(new_hazard, index): (MapHazard, usize)| {
+        let mut map = map();
+        let Some(hazard) = map.hazards.get_mut(index) else {
+            return;
+        };
+
+        *hazard = new_hazard;
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
+    });
+    let delete_hazard = use_callback(move |index| {
+        let mut map = map();
+
+        map.hazards.remove(index);
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
+    });
+
+    let mut popup_content = use_signal(|| PopupContent::None);
+    let mut popup_open = use_signal(|| false);
 
     rsx! {
         PopupContext {
@@ -842,182 +1466,1257 @@ fn SectionActions(actions: Memo<Vec<Action>>, disabled: bool) -> Element {
             on_open: move |open: bool| {
                 popup_open.set(open);
             },
-            Section { title: "Normal actions",
-                ActionList {
-                    on_add_click: move |_| {
-                        handle_add_action_click(ActionCondition::Any);
+            Section { title: "Map hazards",
+                for (index , hazard) in map().hazards.into_iter().enumerate() {
+                    PopupTrigger {
+                        MapHazardItem {
+                            hazard,
+                            on_item_click: move |_| {
+                                popup_content.set(PopupContent::Edit { hazard, index });
+                            },
+                            on_item_delete: move |_| {
+                                delete_hazard(index);
+                            },
+                        }
+                    }
+                }
+
+                PopupTrigger {
+                    Button {
+                        style: ButtonStyle::Secondary,
+                        on_click: move |_| {
+                            popup_content.set(PopupContent::Add);
+                        },
+                        disabled,
+                        class: "mt-2 w-full",
+
+                        "Add map hazard"
+                    }
+                }
+
+                PopupMapHazardInputContent {
+                    modifying: match popup_content() {
+                        PopupContent::None | PopupContent::Add => false,
+                        PopupContent::Edit { .. } => true,
                     },
-                    on_item_click: move |(action, index)| {
-                        handle_edit_action_click(action, index);
+                    on_cancel: move |_| {
+                        popup_open.set(false);
                     },
-                    on_item_move: move |(index, condition, up)| {
-                        move_action((index, condition, up));
+                    on_value: move |hazard| {
+                        let content = *popup_content.peek();
+                        match content {
+                            PopupContent::None => unreachable!(),
+                            PopupContent::Add => add_hazard(hazard),
+                            PopupContent::Edit { index, .. } => edit_hazard((hazard, index)),
+                        }
+                        popup_open.set(false);
                     },
-                    on_item_delete: move |index| {
-                        delete_action(index);
+                    value: match popup_content() {
+                        PopupContent::None | PopupContent::Add => MapHazard::default(),
+                        PopupContent::Edit { hazard, .. } => hazard,
+                    },
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionRuneSpawnHints(disabled: bool) -> Element {
+    #[component]
+    fn RuneSpawnHintItem(
+        hint: RuneSpawnHint,
+        on_item_click: Callback,
+        on_item_delete: Callback,
+    ) -> Element {
+        const ICON_CONTAINER_CLASS: &str = "w-4 h-6 flex justify-center items-center";
+        const ICON_CLASS: &str = "size-3";
+
+        rsx! {
+            div { class: "flex group",
+                div {
+                    class: "flex-grow grid grid-cols-2 h-6 text-xxs gap-2 text-secondary-text group-hover:bg-secondary-surface",
+                    onclick: move |_| {
+                        on_item_click(());
+                    },
+                    div { class: "{ITEM_BORDER_CLASS} {ITEM_TEXT_CLASS}", {format!("X / {}", hint.x)} }
+                    div { class: "{ITEM_TEXT_CLASS}", {format!("Y / {}", hint.y)} }
+                }
+                div { class: "self-stretch invisible group-hover:visible group-hover:bg-secondary-surface flex items-center pr-1",
+                    div {
+                        class: ICON_CONTAINER_CLASS,
+                        onclick: move |e| {
+                            e.stop_propagation();
+                            on_item_delete(());
+                        },
+                        XIcon { class: "{ICON_CLASS}" }
+                    }
+                }
+            }
+        }
+    }
+
+    #[derive(PartialEq, Clone, Copy)]
+    enum PopupContent {
+        None,
+        Edit { hint: RuneSpawnHint, index: usize },
+        Add,
+    }
+
+    let coroutine = use_coroutine_handle::<ActionsUpdate>();
+    let context = use_context::<ActionsContext>();
+
+    let map = context.map;
+
+    let add_hint = use_callback(move |hint| {
+        let mut map = map();
+
+        map.rune_spawn_hints.push(hint);
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
+    });
+    let edit_hint = use_callback(move |(new_hint, index): (RuneSpawnHint, usize)| {
+        let mut map = map();
+        let Some(hint) = map.rune_spawn_hints.get_mut(index) else {
+            return;
+        };
+
+        *hint = new_hint;
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
+    });
+    let delete_hint = use_callback(move |index| {
+        let mut map = map();
+
+        map.rune_spawn_hints.remove(index);
+        coroutine.send(ActionsUpdate::UpdateMinimap(map));
+    });
+
+    let mut popup_content = use_signal(|| PopupContent::None);
+    let mut popup_open = use_signal(|| false);
+
+    rsx! {
+        PopupContext {
+            open: popup_open,
+            on_open: move |open: bool| {
+                popup_open.set(open);
+            },
+            Section { title: "Rune spawn hints",
+                for (index , hint) in map().rune_spawn_hints.into_iter().enumerate() {
+                    PopupTrigger {
+                        RuneSpawnHintItem {
+                            hint,
+                            on_item_click: move |_| {
+                                popup_content.set(PopupContent::Edit { hint, index });
+                            },
+                            on_item_delete: move |_| {
+                                delete_hint(index);
+                            },
+                        }
+                    }
+                }
+
+                PopupTrigger {
+                    Button {
+                        style: ButtonStyle::Secondary,
+                        on_click: move |_| {
+                            popup_content.set(PopupContent::Add);
+                        },
+                        disabled: disabled || map().rune_spawn_hints.len() >= MAX_RUNE_SPAWN_HINTS_COUNT,
+                        class: "mt-2 w-full",
+
+                        "Add rune spawn hint"
+                    }
+                }
+
+                PopupRuneSpawnHintInputContent {
+                    modifying: match popup_content() {
+                        PopupContent::None | PopupContent::Add => false,
+                        PopupContent::Edit { .. } => true,
+                    },
+                    on_cancel: move |_| {
+                        popup_open.set(false);
+                    },
+                    on_value: move |hint| {
+                        let content = *popup_content.peek();
+                        match content {
+                            PopupContent::None => unreachable!(),
+                            PopupContent::Add => add_hint(hint),
+                            PopupContent::Edit { index, .. } => edit_hint((hint, index)),
+                        }
+                        popup_open.set(false);
+                    },
+                    value: match popup_content() {
+                        PopupContent::None | PopupContent::Add => RuneSpawnHint::default(),
+                        PopupContent::Edit { hint, .. } => hint,
+                    },
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionPlayerMarker(disabled: bool) -> Element {
+    let context = use_context::<ActionsContext>();
+    let map = context.map;
+    let save_map = context.save_map;
+    let mut sampling = use_context::<AppState>().player_marker_sampling;
+
+    let save_player_marker = use_callback(move |player_marker: PlayerMarker| {
+        save_map(Map {
+            player_marker: Some(player_marker),
+            ..map.peek().clone()
+        });
+    });
+    let marker = use_memo(move || map().player_marker);
+
+    rsx! {
+        Section { title: "Player marker",
+            div { class: "grid grid-cols-2 gap-3",
+                ActionsCheckbox {
+                    label: "Custom player marker",
+                    tooltip: "Detects the player on the minimap by the marker color instead of the default marker. Useful for servers with a different minimap player marker.",
+                    disabled,
+                    on_checked: move |enabled: bool| {
+                        save_map(Map {
+                            player_marker: enabled.then_some(PlayerMarker::default()),
+                            ..map.peek().clone()
+                        })
+                    },
+                    checked: marker().is_some(),
+                }
+                Button {
+                    style: ButtonStyle::Secondary,
+                    on_click: move |_| {
+                        let value = !*sampling.peek();
+                        sampling.set(value);
+                    },
+                    disabled,
+                    class: "self-end",
+
+                    if sampling() {
+                        "Click the marker on the minimap..."
+                    } else {
+                        "Sample from minimap"
+                    }
+                }
+            }
+            if let Some(marker) = marker() {
+                div { class: "grid grid-cols-2 gap-3 mt-3",
+                    Labeled { label: "Color",
+                        div { class: "flex items-center gap-2 h-6",
+                            div {
+                                class: "size-4 border border-primary-border",
+                                style: "background-color: rgb({marker.color.0}, {marker.color.1}, {marker.color.2});",
+                            }
+                            p { class: "text-xxs text-secondary-text",
+                                {format!("{:?}", marker.color)}
+                            }
+                        }
+                    }
+                    Labeled { label: "Tolerance",
+                        PrimitiveIntegerInput {
+                            disabled,
+                            on_value: move |tolerance| {
+                                save_player_marker(PlayerMarker { tolerance, ..marker })
+                            },
+                            value: marker.tolerance,
+                        }
+                    }
+                    ActionsNumberInputI32 {
+                        label: "Width",
+                        disabled,
+                        on_value: move |width| {
+                            save_player_marker(PlayerMarker { width, ..marker })
+                        },
+                        value: marker.width,
+                    }
+                    ActionsNumberInputI32 {
+                        label: "Height",
+                        disabled,
+                        on_value: move |height| {
+                            save_player_marker(PlayerMarker { height, ..marker })
+                        },
+                        value: marker.height,
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionKeyTemplates() -> Element {
+    let mut templates = use_resource(async || query_key_templates().await.unwrap_or_default());
+    let template_names = use_memo(move || {
+        templates()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|template| template.name)
+            .collect::<Vec<_>>()
+    });
+    let mut template_index = use_signal(|| None::<usize>);
+    let template = use_memo(move || {
+        template_index()
+            .and_then(|index| templates().unwrap_or_default().get(index).cloned())
+            .unwrap_or_default()
+    });
+    let disabled = use_memo(move || template().id.is_none());
+
+    let save_template = use_callback(move |template: KeyTemplate| {
+        spawn(async move {
+            upsert_key_template(template).await;
+            templates.restart();
+        });
+    });
+
+    rsx! {
+        Section { title: "Key templates",
+            div { class: "grid grid-cols-3 gap-3",
+                div { class: "col-span-3",
+                    NamedSelect {
+                        on_create: move |name| {
+                            spawn(async move {
+                                let created = upsert_key_template(KeyTemplate {
+                                    name,
+                                    ..KeyTemplate::default()
+                                })
+                                .await;
+                                if created.id.is_some() {
+                                    template_index.set(Some(template_names.peek().len()));
+                                }
+                                templates.restart();
+                            });
+                        },
+                        on_delete: move |_| {
+                            let template = template.peek().clone();
+                            spawn(async move {
+                                if delete_key_template(template).await {
+                                    template_index.set(None);
+                                    templates.restart();
+                                }
+                            });
+                        },
+                        delete_disabled: disabled(),
+
+                        Select::<usize> {
+                            class: "w-full",
+                            placeholder: "Create a key template shared by actions of all maps...",
+                            disabled: template_names().is_empty(),
+                            on_selected: move |index| {
+                                template_index.set(Some(index));
+                            },
+
+                            for (i , name) in template_names().into_iter().enumerate() {
+                                SelectOption::<usize> {
+                                    value: i,
+                                    selected: template_index() == Some(i),
+                                    label: name,
+                                }
+                            }
+                        }
+                    }
+                }
+
+                ActionsKeyBindingInput {
+                    label: "Key",
+                    disabled: disabled(),
+                    on_value: move |key: Option<KeyBinding>| {
+                        save_template(KeyTemplate {
+                            key: key.expect("not optional"),
+                            ..template.peek().clone()
+                        });
+                    },
+                    value: Some(template().key),
+                }
+                ActionsNumberInputU32 {
+                    label: "Use count",
+                    disabled: disabled(),
+                    on_value: move |count| {
+                        save_template(KeyTemplate {
+                            count,
+                            ..template.peek().clone()
+                        });
+                    },
+                    value: template().count,
+                }
+                div {} // Spacer
+
+                ActionsKeyBindingInput {
+                    label: "Link key",
+                    disabled: disabled() || matches!(template().link_key, LinkKeyBinding::None),
+                    on_value: move |key: Option<KeyBinding>| {
+                        let template = template.peek().clone();
+                        save_template(KeyTemplate {
+                            link_key: template.link_key.with_key(key.expect("not optional")),
+                            ..template
+                        });
+                    },
+                    value: template().link_key.key().unwrap_or_default(),
+                }
+                ActionsSelect::<LinkKeyBinding> {
+                    label: "Link key type",
+                    disabled: disabled(),
+                    on_selected: move |link_key| {
+                        save_template(KeyTemplate {
+                            link_key,
+                            ..template.peek().clone()
+                        });
+                    },
+                    selected: template().link_key,
+                }
+                div {} // Spacer
+
+                for index in 0..MAX_LINK_KEY_CHAIN_STEPS {
+                    ActionsKeyBindingInput {
+                        label: "Chain key",
+                        disabled: disabled()
+                            || matches!(template().link_key_chain[index].link_key, LinkKeyBinding::None),
+                        on_value: move |key: Option<KeyBinding>| {
+                            let mut template = template.peek().clone();
+                            let step = &mut template.link_key_chain[index];
+                            step.link_key = step.link_key.with_key(key.expect("not optional"));
+                            save_template(template);
+                        },
+                        value: template().link_key_chain[index].link_key.key().unwrap_or_default(),
+                    }
+                    ActionsSelect::<LinkKeyBinding> {
+                        label: "Chain key type",
+                        disabled: disabled(),
+                        on_selected: move |link_key| {
+                            let mut template = template.peek().clone();
+                            template.link_key_chain[index].link_key = link_key;
+                            save_template(template);
+                        },
+                        selected: template().link_key_chain[index].link_key,
+                    }
+                    ActionsMillisInput {
+                        label: "Chain key delay",
+                        disabled: disabled()
+                            || matches!(template().link_key_chain[index].link_key, LinkKeyBinding::None),
+                        on_value: move |millis| {
+                            let mut template = template.peek().clone();
+                            template.link_key_chain[index].delay_millis = millis;
+                            save_template(template);
+                        },
+                        value: template().link_key_chain[index].delay_millis,
+                    }
+                }
+
+                ActionsMillisInput {
+                    label: "Wait before use",
+                    disabled: disabled(),
+                    on_value: move |millis| {
+                        save_template(KeyTemplate {
+                            wait_before_use_millis: millis,
+                            ..template.peek().clone()
+                        });
+                    },
+                    value: template().wait_before_use_millis,
+                }
+                ActionsMillisInput {
+                    label: "Wait random range",
+                    disabled: disabled(),
+                    on_value: move |millis| {
+                        save_template(KeyTemplate {
+                            wait_before_use_millis_random_range: millis,
+                            ..template.peek().clone()
+                        });
+                    },
+                    value: template().wait_before_use_millis_random_range,
+                }
+                div {} // Spacer
+
+                ActionsMillisInput {
+                    label: "Wait after use",
+                    disabled: disabled(),
+                    on_value: move |millis| {
+                        save_template(KeyTemplate {
+                            wait_after_use_millis: millis,
+                            ..template.peek().clone()
+                        });
+                    },
+                    value: template().wait_after_use_millis,
+                }
+                ActionsMillisInput {
+                    label: "Wait random range",
+                    disabled: disabled(),
+                    on_value: move |millis| {
+                        save_template(KeyTemplate {
+                            wait_after_use_millis_random_range: millis,
+                            ..template.peek().clone()
+                        });
+                    },
+                    value: template().wait_after_use_millis_random_range,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionLegends() -> Element {
+    rsx! {
+        Section { title: "Action legends", class: "text-xs text-primary-text",
+            p { "⟳ - Repeat" }
+            p { "⏱︎  - Wait" }
+            p { "ㄨ - No position" }
+            p { "⇈ - Queue to front" }
+            p { "⇆ - Any direction" }
+            p { "← - Left direction" }
+            p { "→ - Right direction" }
+            p { "A ~ B - Random range between A and B" }
+            p { "A ↝ B - Use A key then B key" }
+            p { "A ↜ B - Use B key then A key" }
+            p { "A ↭ B - Use A and B keys at the same time" }
+            p { "A ↷ B - Use A key then B key while A is held down" }
+        }
+    }
+}
+
+#[component]
+fn SectionActions(actions: Memo<Vec<Action>>, disabled: bool) -> Element {
+    #[derive(Clone, Copy, PartialEq)]
+    enum PopupContent {
+        None,
+        Add(Action),
+        Edit { action: Action, index: usize },
+    }
+
+    let coroutine = use_coroutine_handle::<ActionsUpdate>();
+    let map = use_context::<ActionsContext>().map;
+
+    let export_name = use_memo(move || format!("{}.json", map().name));
+    let export_content = move |_| serde_json::to_vec_pretty(&*actions.peek()).unwrap_or_default();
+
+    let import_actions = use_callback(move |file: FileData| async move {
+        let mut actions = actions();
+
+        let Ok(bytes) = file.read_bytes().await else {
+            return;
+        };
+        let Ok(import_actions) = serde_json::from_slice::<'_, Vec<Action>>(&bytes) else {
+            return;
+        };
+
+        let mut i = 0;
+        while i < import_actions.len() {
+            let action = import_actions[i];
+            if matches!(action.condition(), ActionCondition::Linked) {
+                // Malformed
+                i += 1;
+                continue;
+            }
+
+            actions.push(action);
+            if let Some(range) = find_linked_action_range(&import_actions, i) {
+                actions.extend(import_actions[range.clone()].iter().copied());
+                i += range.count();
+            }
+            i += 1;
+        }
+
+        coroutine.send(ActionsUpdate::Update(actions));
+    });
+
+    let add_action = use_callback(move |(action, condition): (Action, ActionCondition)| {
+        let mut actions = actions();
+        let index = if matches!(action.condition(), ActionCondition::Linked) {
+            find_last_linked_action_index(&actions, condition)
+                .map(|index| index + 1)
+                .unwrap_or(actions.len())
+        } else {
+            actions.len()
+        };
+
+        actions.insert(index, action);
+        coroutine.send(ActionsUpdate::Update(actions));
+    });
+
+    let edit_action = use_callback(move |(new_action, index): (Action, usize)| {
+        let mut actions = actions();
+        let Some(action) = actions.get_mut(index) else {
+            return;
+        };
+
+        *action = new_action;
+        coroutine.send(ActionsUpdate::Update(actions));
+    });
+
+    let delete_action = use_callback(move |index: usize| {
+        let mut actions = actions();
+        let Some(condition) = actions.get(index).map(|action| action.condition()) else {
+            return;
+        };
+
+        // Replaces the first linked action to this `action` condition
+        // TODO: Maybe replace find_linked_action_range with a simple lookahead
+        if !matches!(condition, ActionCondition::Linked)
+            && find_linked_action_range(&actions, index).is_some()
+        {
+            actions[index + 1] = actions[index + 1].with_condition(condition);
+        }
+        actions.remove(index);
+        coroutine.send(ActionsUpdate::Update(actions));
+    });
+
+    let toggle_action = use_callback(move |index: usize| {
+        let mut actions = actions();
+        let Some(action) = actions.get_mut(index) else {
+            return;
+        };
+
+        *action = action.with_enabled(!action.enabled());
+        coroutine.send(ActionsUpdate::Update(actions));
+    });
+
+    let move_action = use_callback(
+        move |(index, condition, up): (usize, ActionCondition, bool)| {
+            let mut actions = actions();
+            let filtered = filter_actions(actions.clone(), condition);
+            if (up && index <= filtered.first().expect("cannot be empty").1)
+                || (!up && index >= filtered.last().expect("cannot be empty").1)
+            {
+                return;
+            }
+
+            // Finds the action index of `filtered` before or after `index`
+            let filtered_index = filtered
+                .iter()
+                .enumerate()
+                .find_map(|(filtered_index, (_, actions_index))| {
+                    if *actions_index == index {
+                        if up {
+                            Some(filtered_index - 1)
+                        } else {
+                            Some(filtered_index + 1)
+                        }
+                    } else {
+                        None
+                    }
+                })
+                .expect("must be valid index");
+            let filtered_condition = filtered[filtered_index].0.condition();
+            let action_condition = actions[index].condition();
+            match (action_condition, filtered_condition) {
+                // Simple case - swapping two linked actions
+                (ActionCondition::Linked, ActionCondition::Linked) => {
+                    actions.swap(index, filtered[filtered_index].1);
+                    coroutine.send(ActionsUpdate::Update(actions));
+                    return;
+                }
+                // Disallows moving up/down if `index` is a linked action and
+                // `filtered_index` is a non-linked action
+                (ActionCondition::Linked, _) => return,
+                _ => (),
+            }
+
+            // Finds the first non-linked action index of `filtered` before or after `index`
+            let mut filtered_non_linked_index = filtered_index;
+            while (up && filtered_non_linked_index > 0)
+                || (!up && filtered_non_linked_index < filtered.len() - 1)
+            {
+                let condition = filtered[filtered_non_linked_index].0.condition();
+                if !matches!(condition, ActionCondition::Linked) {
+                    break;
+                }
+                if up {
+                    filtered_non_linked_index -= 1;
+                } else {
+                    filtered_non_linked_index += 1;
+                }
+            }
+            let condition = filtered[filtered_non_linked_index].0.condition();
+            if matches!(condition, ActionCondition::Linked) {
+                return;
+            }
+
+            let actions_non_linked_index = filtered[filtered_non_linked_index].1;
+            let first_range = find_linked_action_range(&actions, actions_non_linked_index);
+            let mut first_range = if let Some(range) = first_range {
+                actions_non_linked_index..range.end
+            } else {
+                actions_non_linked_index..actions_non_linked_index + 1
+            };
+
+            let second_range = find_linked_action_range(&actions, index);
+            let mut second_range = if let Some(range) = second_range {
+                index..range.end
+            } else {
+                index..index + 1
+            };
+
+            if !up {
+                swap(&mut first_range, &mut second_range);
+            }
+
+            debug_assert!(
+                first_range.end <= second_range.start || second_range.end <= first_range.start
+            );
+            let second_start = second_range.start;
+            let second_actions = actions.drain(second_range).collect::<Vec<_>>();
+            let first_actions = actions[first_range.clone()].to_vec();
+            for action in first_actions.into_iter().rev() {
+                actions.insert(second_start, action);
+            }
+
+            let first_start = first_range.start;
+            let _ = actions.drain(first_range);
+            for action in second_actions.into_iter().rev() {
+                actions.insert(first_start, action);
+            }
+            coroutine.send(ActionsUpdate::Update(actions));
+        },
+    );
+
+    let mut popup_content = use_signal(|| PopupContent::None);
+    let mut popup_open = use_signal(|| false);
+
+    let mut handle_add_action_click = move |condition: ActionCondition| {
+        let action = Action::Key(ActionKey::default()).with_condition(condition);
+        let content = PopupContent::Add(action);
+        popup_content.set(content);
+    };
+
+    let mut handle_edit_action_click = move |action: Action, index: usize| {
+        popup_content.set(PopupContent::Edit { action, index });
+    };
+
+    let character = use_context::<AppState>().character;
+    let mut optimizing_route = use_signal(|| false);
+    let optimize_route = use_callback(move |_: ()| {
+        optimizing_route.set(true);
+        spawn(async move {
+            let optimized = optimize_actions_route(
+                map.peek().clone(),
+                actions.peek().clone(),
+                character.peek().clone(),
+            )
+            .await;
+            coroutine.send(ActionsUpdate::Update(optimized));
+            optimizing_route.set(false);
+        });
+    });
+
+    rsx! {
+        PopupContext {
+            open: popup_open,
+            on_open: move |open: bool| {
+                popup_open.set(open);
+            },
+            Section { title: "Normal actions",
+                ActionList {
+                    on_add_click: move |_| {
+                        handle_add_action_click(ActionCondition::Any);
+                    },
+                    on_item_click: move |(action, index)| {
+                        handle_edit_action_click(action, index);
+                    },
+                    on_item_move: move |(index, condition, up)| {
+                        move_action((index, condition, up));
+                    },
+                    on_item_delete: move |index| {
+                        delete_action(index);
+                    },
+                    on_item_toggle: move |index| {
+                        toggle_action(index);
+                    },
+                    condition_filter: ActionCondition::Any,
+                    disabled,
+                    actions: actions(),
+                }
+                Button {
+                    class: "w-full mt-2",
+                    style: ButtonStyle::Secondary,
+                    disabled: disabled || optimizing_route(),
+                    on_click: move |_| {
+                        optimize_route(());
+                    },
+
+                    if optimizing_route() {
+                        "Optimizing route..."
+                    } else {
+                        "Optimize route"
+                    }
+                }
+            }
+            Section { title: "Erda Shower off cooldown priority actions",
+                ActionList {
+                    on_add_click: move |_| {
+                        handle_add_action_click(ActionCondition::ErdaShowerOffCooldown);
+                    },
+                    on_item_click: move |(action, index)| {
+                        handle_edit_action_click(action, index);
+                    },
+                    on_item_move: move |(index, condition, up)| {
+                        move_action((index, condition, up));
+                    },
+                    on_item_delete: move |index| {
+                        delete_action(index);
+                    },
+                    on_item_toggle: move |index| {
+                        toggle_action(index);
+                    },
+                    condition_filter: ActionCondition::ErdaShowerOffCooldown,
+                    disabled,
+                    actions: actions(),
+                }
+            }
+            Section { title: "Every milliseconds priority actions",
+                ActionList {
+                    on_add_click: move |_| {
+                        handle_add_action_click(ActionCondition::EveryMillis(0));
+                    },
+                    on_item_click: move |(action, index)| {
+                        handle_edit_action_click(action, index);
+                    },
+                    on_item_move: move |(index, condition, up)| {
+                        move_action((index, condition, up));
+                    },
+                    on_item_delete: move |index| {
+                        delete_action(index);
+                    },
+                    on_item_toggle: move |index| {
+                        toggle_action(index);
+                    },
+                    condition_filter: ActionCondition::EveryMillis(0),
+                    disabled,
+                    actions: actions(),
+                }
+            }
+            Section { title: "Import/export actions",
+                div { class: "flex gap-2",
+                    FileInput {
+                        class: "flex-grow",
+                        on_file: move |file| async move {
+                            import_actions(file).await;
+                        },
+                        disabled,
+                        Button {
+                            class: "w-full",
+                            style: ButtonStyle::Primary,
+                            disabled,
+                            "Import"
+                        }
+                    }
+                    FileOutput {
+                        class: "flex-grow",
+                        on_file: export_content,
+                        download: export_name(),
+                        disabled,
+                        Button {
+                            class: "w-full",
+                            style: ButtonStyle::Primary,
+                            disabled,
+                            "Export"
+                        }
+                    }
+                }
+            }
+
+            match popup_content() {
+                #[allow(clippy::double_parens)]
+                PopupContent::None => rsx! {},
+                PopupContent::Add(action) => rsx! {
+                    PopupActionInputContent {
+                        modifying: false,
+                        linkable: !filter_actions(actions(), action.condition()).is_empty(),
+                        on_cancel: move |_| {
+                            popup_open.set(false);
+                            popup_content.set(PopupContent::None);
+                        },
+                        on_value: move |args| {
+                            add_action(args);
+                            popup_open.set(false);
+                            popup_content.set(PopupContent::None);
+                        },
+                        value: action,
+                    }
+                },
+                PopupContent::Edit { action, index } => rsx! {
+                    PopupActionInputContent {
+                        modifying: true,
+                        linkable: filter_actions(actions(), action.condition())
+                            .into_iter()
+                            .next()
+                            .map(|first| first.1 != index)
+                            .unwrap_or_default(),
+                        on_copy: move |_| {
+                            popup_content.set(PopupContent::Add(action));
+                        },
+                        on_cancel: move |_| {
+                            popup_open.set(false);
+                            popup_content.set(PopupContent::None);
+                        },
+                        on_value: move |(action, _)| {
+                            edit_action((action, index));
+                            popup_open.set(false);
+                            popup_content.set(PopupContent::None);
+                        },
+                        value: action,
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[component]
+fn PopupPlatformInputContent(
+    modifying: bool,
+    on_cancel: Callback,
+    on_value: Callback<Platform>,
+    value: Platform,
+) -> Element {
+    let position = use_context::<AppState>().position;
+    let mut platform = use_signal(|| value);
+
+    rsx! {
+        PopupContent { title: if modifying { "Modify platform" } else { "Add platform" },
+            div { class: "grid grid-cols-3 gap-3 pb-10 overflow-y-auto",
+                ActionsPositionInput {
+                    label: "X start",
+                    on_icon_click: move |_| {
+                        platform.write().x_start = position.peek().0;
+                    },
+                    on_value: move |x| {
+                        platform.write().x_start = x;
+                    },
+                    value: platform().x_start,
+                }
+                ActionsPositionInput {
+                    label: "X end",
+                    on_icon_click: move |_| {
+                        platform.write().x_end = position.peek().0;
+                    },
+                    on_value: move |x| {
+                        platform.write().x_end = x;
+                    },
+                    value: platform().x_end,
+                }
+                ActionsPositionInput {
+                    label: "Y",
+                    on_icon_click: move |_| {
+                        platform.write().y = position.peek().1;
+                    },
+                    on_value: move |y| {
+                        platform.write().y = y;
+                    },
+                    value: platform().y,
+                }
+            }
+
+            div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
+                Button {
+                    class: "flex-grow",
+                    style: ButtonStyle::OutlinePrimary,
+                    on_click: move |_| {
+                        on_value(*platform.peek());
+                    },
+
+                    if modifying {
+                        "Save"
+                    } else {
+                        "Add"
+                    }
+                }
+                Button {
+                    class: "flex-grow",
+                    style: ButtonStyle::OutlineSecondary,
+                    on_click: move |_| {
+                        on_cancel(());
+                    },
+                    "Cancel"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PopupRopeInputContent(
+    modifying: bool,
+    on_cancel: Callback,
+    on_value: Callback<Rope>,
+    value: Rope,
+) -> Element {
+    let position = use_context::<AppState>().position;
+    let mut rope = use_signal(|| value);
+
+    rsx! {
+        PopupContent { title: if modifying { "Modify rope" } else { "Add rope" },
+            div { class: "grid grid-cols-3 gap-3 pb-10 overflow-y-auto",
+                ActionsPositionInput {
+                    label: "X",
+                    on_icon_click: move |_| {
+                        rope.write().x = position.peek().0;
+                    },
+                    on_value: move |x| {
+                        rope.write().x = x;
+                    },
+                    value: rope().x,
+                }
+                ActionsPositionInput {
+                    label: "Y start",
+                    on_icon_click: move |_| {
+                        rope.write().y_start = position.peek().1;
+                    },
+                    on_value: move |y| {
+                        rope.write().y_start = y;
+                    },
+                    value: rope().y_start,
+                }
+                ActionsPositionInput {
+                    label: "Y end",
+                    on_icon_click: move |_| {
+                        rope.write().y_end = position.peek().1;
+                    },
+                    on_value: move |y| {
+                        rope.write().y_end = y;
+                    },
+                    value: rope().y_end,
+                }
+            }
+
+            div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
+                Button {
+                    class: "flex-grow",
+                    style: ButtonStyle::OutlinePrimary,
+                    on_click: move |_| {
+                        on_value(*rope.peek());
+                    },
+
+                    if modifying {
+                        "Save"
+                    } else {
+                        "Add"
+                    }
+                }
+                Button {
+                    class: "flex-grow",
+                    style: ButtonStyle::OutlineSecondary,
+                    on_click: move |_| {
+                        on_cancel(());
+                    },
+                    "Cancel"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PopupDangerZoneInputContent(
+    modifying: bool,
+    on_cancel: Callback,
+    on_value: Callback<DangerZone>,
+    value: DangerZone,
+) -> Element {
+    let position = use_context::<AppState>().position;
+    let mut zone = use_signal(|| value);
+
+    rsx! {
+        PopupContent { title: if modifying { "Modify danger zone" } else { "Add danger zone" },
+            div { class: "grid grid-cols-3 gap-3 pb-10 overflow-y-auto",
+                ActionsPositionInput {
+                    label: "X start",
+                    on_icon_click: move |_| {
+                        zone.write().x_start = position.peek().0;
+                    },
+                    on_value: move |x| {
+                        zone.write().x_start = x;
+                    },
+                    value: zone().x_start,
+                }
+                ActionsPositionInput {
+                    label: "X end",
+                    on_icon_click: move |_| {
+                        zone.write().x_end = position.peek().0;
+                    },
+                    on_value: move |x| {
+                        zone.write().x_end = x;
+                    },
+                    value: zone().x_end,
+                }
+                ActionsPositionInput {
+                    label: "Y",
+                    on_icon_click: move |_| {
+                        zone.write().y = position.peek().1;
+                    },
+                    on_value: move |y| {
+                        zone.write().y = y;
+                    },
+                    value: zone().y,
+                }
+            }
+
+            div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
+                Button {
+                    class: "flex-grow",
+                    style: ButtonStyle::OutlinePrimary,
+                    on_click: move |_| {
+                        on_value(*zone.peek());
+                    },
+
+                    if modifying {
+                        "Save"
+                    } else {
+                        "Add"
+                    }
+                }
+                Button {
+                    class: "flex-grow",
+                    style: ButtonStyle::OutlineSecondary,
+                    on_click: move |_| {
+                        on_cancel(());
+                    },
+                    "Cancel"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PopupMapHazardInputContent(
+    modifying: bool,
+    on_cancel: Callback,
+    on_value: Callback<MapHazard>,
+    value: MapHazard,
+) -> Element {
+    let position = use_context::<AppState>().position;
+    let mut hazard = use_signal(|| value);
+    let safe_zone_disabled = hazard().avoidance != MapHazardAvoidance::MoveToSafeZone;
+
+    rsx! {
+        PopupContent { title: if modifying { "Modify map hazard" } else { "Add map hazard" },
+            div { class: "grid grid-cols-3 gap-3 pb-10 overflow-y-auto",
+                ActionsMillisInput {
+                    label: "Interval",
+                    on_value: move |millis| {
+                        hazard.write().interval_millis = millis;
                     },
-                    condition_filter: ActionCondition::Any,
-                    disabled,
-                    actions: actions(),
+                    value: hazard().interval_millis,
                 }
-            }
-            Section { title: "Erda Shower off cooldown priority actions",
-                ActionList {
-                    on_add_click: move |_| {
-                        handle_add_action_click(ActionCondition::ErdaShowerOffCooldown);
+                ActionsMillisInput {
+                    label: "First hazard after",
+                    on_value: move |millis| {
+                        hazard.write().first_millis = millis;
                     },
-                    on_item_click: move |(action, index)| {
-                        handle_edit_action_click(action, index);
+                    value: hazard().first_millis,
+                }
+                ActionsMillisInput {
+                    label: "Avoid before",
+                    on_value: move |millis| {
+                        hazard.write().lead_millis = millis;
                     },
-                    on_item_move: move |(index, condition, up)| {
-                        move_action((index, condition, up));
+                    value: hazard().lead_millis,
+                }
+                ActionsSelect::<MapHazardAvoidance> {
+                    label: "Avoidance",
+                    disabled: false,
+                    on_selected: move |avoidance| {
+                        hazard.write().avoidance = avoidance;
                     },
-                    on_item_delete: move |index| {
-                        delete_action(index);
+                    selected: hazard().avoidance,
+                }
+                ActionsCheckbox {
+                    label: "Enabled",
+                    on_checked: move |enabled| {
+                        hazard.write().enabled = enabled;
                     },
-                    condition_filter: ActionCondition::ErdaShowerOffCooldown,
-                    disabled,
-                    actions: actions(),
+                    checked: hazard().enabled,
                 }
-            }
-            Section { title: "Every milliseconds priority actions",
-                ActionList {
-                    on_add_click: move |_| {
-                        handle_add_action_click(ActionCondition::EveryMillis(0));
+                ActionsPositionInput {
+                    label: "Safe zone X",
+                    disabled: safe_zone_disabled,
+                    on_icon_click: move |_| {
+                        hazard.write().safe_x = position.peek().0;
                     },
-                    on_item_click: move |(action, index)| {
-                        handle_edit_action_click(action, index);
+                    on_value: move |x| {
+                        hazard.write().safe_x = x;
                     },
-                    on_item_move: move |(index, condition, up)| {
-                        move_action((index, condition, up));
+                    value: hazard().safe_x,
+                }
+                ActionsPositionInput {
+                    label: "Safe zone Y",
+                    disabled: safe_zone_disabled,
+                    on_icon_click: move |_| {
+                        hazard.write().safe_y = position.peek().1;
                     },
-                    on_item_delete: move |index| {
-                        delete_action(index);
+                    on_value: move |y| {
+                        hazard.write().safe_y = y;
                     },
-                    condition_filter: ActionCondition::EveryMillis(0),
-                    disabled,
-                    actions: actions(),
+                    value: hazard().safe_y,
                 }
-            }
-            Section { title: "Import/export actions",
-                div { class: "flex gap-2",
-                    FileInput {
-                        class: "flex-grow",
-                        on_file: move |file| async move {
-                            import_actions(file).await;
-                        },
-                        disabled,
-                        Button {
-                            class: "w-full",
-                            style: ButtonStyle::Primary,
-                            disabled,
-                            "Import"
-                        }
-                    }
-                    FileOutput {
-                        class: "flex-grow",
-                        on_file: export_content,
-                        download: export_name(),
-                        disabled,
-                        Button {
-                            class: "w-full",
-                            style: ButtonStyle::Primary,
-                            disabled,
-                            "Export"
-                        }
-                    }
+                ActionsMillisInput {
+                    label: "Wait in safe zone",
+                    disabled: safe_zone_disabled,
+                    on_value: move |millis| {
+                        hazard.write().safe_wait_millis = millis;
+                    },
+                    value: hazard().safe_wait_millis,
                 }
             }
 
-            match popup_content() {
-                #[allow(clippy::double_parens)]
-                PopupContent::None => rsx! {},
-                PopupContent::Add(action) => rsx! {
-                    PopupActionInputContent {
-                        modifying: false,
-                        linkable: !filter_actions(actions(), action.condition()).is_empty(),
-                        on_cancel: move |_| {
-                            popup_open.set(false);
-                            popup_content.set(PopupContent::None);
-                        },
-                        on_value: move |args| {
-                            add_action(args);
-                            popup_open.set(false);
-                            popup_content.set(PopupContent::None);
-                        },
-                        value: action,
-                    }
-                },
-                PopupContent::Edit { action, index } => rsx! {
-                    PopupActionInputContent {
-                        modifying: true,
-                        linkable: filter_actions(actions(), action.condition())
-                            .into_iter()
-                            .next()
-                            .map(|first| first.1 != index)
-                            .unwrap_or_default(),
-                        on_copy: move |_| {
-                            popup_content.set(PopupContent::Add(action));
-                        },
-                        on_cancel: move |_| {
-                            popup_open.set(false);
-                            popup_content.set(PopupContent::None);
-                        },
-                        on_value: move |(action, _)| {
-                            edit_action((action, index));
-                            popup_open.set(false);
-                            popup_content.set(PopupContent::None);
-                        },
-                        value: action,
+            div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
+                Button {
+                    class: "flex-grow",
+                    style: ButtonStyle::OutlinePrimary,
+                    on_click: move |_| {
+                        on_value(*hazard.peek());
+                    },
+
+                    if modifying {
+                        "Save"
+                    } else {
+                        "Add"
                     }
-                },
+                }
+                Button {
+                    class: "flex-grow",
+                    style: ButtonStyle::OutlineSecondary,
+                    on_click: move |_| {
+                        on_cancel(());
+                    },
+                    "Cancel"
+                }
             }
         }
     }
 }
 
 #[component]
-fn PopupPlatformInputContent(
+fn PopupRuneSpawnHintInputContent(
     modifying: bool,
     on_cancel: Callback,
-    on_value: Callback<Platform>,
-    value: Platform,
+    on_value: Callback<RuneSpawnHint>,
+    value: RuneSpawnHint,
 ) -> Element {
     let position = use_context::<AppState>().position;
-    let mut platform = use_signal(|| value);
+    let mut hint = use_signal(|| value);
 
     rsx! {
-        PopupContent { title: if modifying { "Modify platform" } else { "Add platform" },
-            div { class: "grid grid-cols-3 gap-3 pb-10 overflow-y-auto",
-                ActionsPositionInput {
-                    label: "X start",
-                    on_icon_click: move |_| {
-                        platform.write().x_start = position.peek().0;
-                    },
-                    on_value: move |x| {
-                        platform.write().x_start = x;
-                    },
-                    value: platform().x_start,
-                }
+        PopupContent { title: if modifying { "Modify rune spawn hint" } else { "Add rune spawn hint" },
+            div { class: "grid grid-cols-2 gap-3 pb-10 overflow-y-auto",
                 ActionsPositionInput {
-                    label: "X end",
+                    label: "X",
                     on_icon_click: move |_| {
-                        platform.write().x_end = position.peek().0;
+                        hint.write().x = position.peek().0;
                     },
                     on_value: move |x| {
-                        platform.write().x_end = x;
+                        hint.write().x = x;
                     },
-                    value: platform().x_end,
+                    value: hint().x,
                 }
                 ActionsPositionInput {
                     label: "Y",
                     on_icon_click: move |_| {
-                        platform.write().y = position.peek().1;
+                        hint.write().y = position.peek().1;
                     },
                     on_value: move |y| {
-                        platform.write().y = y;
+                        hint.write().y = y;
                     },
-                    value: platform().y,
+                    value: hint().y,
                 }
             }
 
@@ -1026,7 +2725,7 @@ fn PopupPlatformInputContent(
                     class: "flex-grow",
                     style: ButtonStyle::OutlinePrimary,
                     on_click: move |_| {
-                        on_value(*platform.peek());
+                        on_value(*hint.peek());
                     },
 
                     if modifying {
@@ -1112,6 +2811,85 @@ fn PopupMobbingBoundInputContent(
     }
 }
 
+#[component]
+fn PopupMobbingRegionInputContent(
+    modifying: bool,
+    on_cancel: Callback,
+    on_value: Callback<MobbingRegion>,
+    value: MobbingRegion,
+) -> Element {
+    let mut region = use_signal(|| value);
+
+    rsx! {
+        PopupContent { title: if modifying { "Modify region" } else { "Add region" },
+            div { class: "grid grid-cols-2 gap-3 pb-10 overflow-y-auto",
+                Labeled { label: "Name",
+                    TextInput {
+                        class: "h-6",
+                        on_value: move |name| {
+                            region.write().name = name;
+                        },
+                        value: region().name,
+                    }
+                }
+                div {}
+                ActionsNumberInputI32 {
+                    label: "X offset",
+                    on_value: move |x| {
+                        region.write().bound.x = x;
+                    },
+                    value: region().bound.x,
+                }
+                ActionsNumberInputI32 {
+                    label: "Y offset",
+                    on_value: move |y| {
+                        region.write().bound.y = y;
+                    },
+                    value: region().bound.y,
+                }
+                ActionsNumberInputI32 {
+                    label: "Width",
+                    on_value: move |width| {
+                        region.write().bound.width = width;
+                    },
+                    value: region().bound.width,
+                }
+                ActionsNumberInputI32 {
+                    label: "Height",
+                    on_value: move |height| {
+                        region.write().bound.height = height;
+                    },
+                    value: region().bound.height,
+                }
+            }
+
+            div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
+                Button {
+                    class: "flex-grow",
+                    style: ButtonStyle::OutlinePrimary,
+                    on_click: move |_| {
+                        on_value(region.peek().clone());
+                    },
+
+                    if modifying {
+                        "Save"
+                    } else {
+                        "Add"
+                    }
+                }
+                Button {
+                    class: "flex-grow",
+                    style: ButtonStyle::OutlineSecondary,
+                    on_click: move |_| {
+                        on_cancel(());
+                    },
+                    "Cancel"
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn PopupMobbingKeyInputContent(
     on_cancel: Callback,
@@ -1389,6 +3167,46 @@ fn ActionMoveInput(
                 }
             }
         }
+        div { class: "grid grid-cols-3 gap-3 mt-3",
+            ActionsLimitedTextInput {
+                label: "Note",
+                max_len: ActionNote::MAX_LEN,
+                on_value: move |note: String| {
+                    if let Some(note) = ActionNote::new(&note) {
+                        action.write().note = note;
+                    }
+                },
+                value: action().note.to_string(),
+            }
+            ActionsSelect::<ActionColor> {
+                label: "Color",
+                disabled: false,
+                on_selected: move |color| {
+                    let mut action = action.write();
+                    action.color = color;
+                },
+                selected: action().color,
+            }
+            ActionsCheckbox {
+                label: "Enabled",
+                tooltip: "Disabled actions are kept but not used by the rotation.",
+                on_checked: move |enabled: bool| {
+                    let mut action = action.write();
+                    action.enabled = enabled;
+                },
+                checked: action().enabled,
+            }
+            ActionsLimitedTextInput {
+                label: "Tags",
+                max_len: ActionNote::MAX_LEN,
+                on_value: move |tags: String| {
+                    if let Some(tags) = ActionTags::new(&tags) {
+                        action.write().tags = tags;
+                    }
+                },
+                value: action().tags.to_string(),
+            }
+        }
         div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
             Button {
                 class: "flex-grow",
@@ -1428,6 +3246,23 @@ fn ActionKeyInput(
     let position = use_context::<AppState>().position;
     let mut action = use_signal(&*value);
     let action_condition = value().condition;
+    let plugin_detectors = use_resource(query_plugin_detectors);
+    let plugin_detectors = use_memo(move || plugin_detectors().unwrap_or_default());
+    let settings = use_context::<AppState>().settings;
+    let condition_webhooks = use_memo(move || {
+        settings()
+            .map(|settings| {
+                settings
+                    .condition_webhooks
+                    .into_iter()
+                    .map(|webhook| webhook.name)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    });
+    let key_templates = use_resource(async || query_key_templates().await.unwrap_or_default());
+    let key_templates = use_memo(move || key_templates().unwrap_or_default());
+    let templated = use_memo(move || action().template_id.is_some());
 
     use_effect(move || {
         action.set(value());
@@ -1515,10 +3350,43 @@ fn ActionKeyInput(
                 }
             }
 
+            // Template
+            Labeled {
+                label: "Template",
+                tooltip: "When selected, the key, link keys, use count and waits are taken from the template. Updating the template updates all actions using it.",
+                Select::<usize> {
+                    on_selected: move |index: usize| {
+                        let templates = key_templates.peek();
+                        let mut action = action.write();
+                        match index.checked_sub(1).and_then(|index| templates.get(index)) {
+                            Some(template) => {
+                                action.template_id = template.id;
+                                template.apply_to(&mut action);
+                            }
+                            None => action.template_id = None,
+                        }
+                    },
+
+                    SelectOption::<usize> {
+                        value: 0,
+                        selected: action().template_id.is_none(),
+                        label: "None",
+                    }
+                    for (i , template) in key_templates().into_iter().enumerate() {
+                        SelectOption::<usize> {
+                            value: i + 1,
+                            selected: templated() && action().template_id == template.id,
+                            label: template.name,
+                        }
+                    }
+                }
+            }
+            div { class: "col-span-2" }
+
             // Key, count and link key
             ActionsKeyBindingInput {
                 label: "Key",
-                disabled: false,
+                disabled: templated(),
                 on_value: move |key: Option<KeyBinding>| {
                     let mut action = action.write();
                     action.key = key.expect("not optional");
@@ -1528,6 +3396,7 @@ fn ActionKeyInput(
             div { class: "grid grid-cols-2 gap-3",
                 ActionsNumberInputU32 {
                     label: "Use count",
+                    disabled: templated(),
                     on_value: move |count| {
                         let mut action = action.write();
                         action.count = count;
@@ -1561,7 +3430,7 @@ fn ActionKeyInput(
 
             ActionsKeyBindingInput {
                 label: "Link key",
-                disabled: matches!(action().link_key, LinkKeyBinding::None),
+                disabled: templated() || matches!(action().link_key, LinkKeyBinding::None),
                 on_value: move |key: Option<KeyBinding>| {
                     let mut action = action.write();
                     action.link_key = action.link_key.with_key(key.expect("not optional"));
@@ -1570,7 +3439,7 @@ fn ActionKeyInput(
             }
             ActionsSelect::<LinkKeyBinding> {
                 label: "Link key type",
-                disabled: false,
+                disabled: templated(),
                 on_selected: move |link_key: LinkKeyBinding| {
                     let mut action = action.write();
                     action.link_key = link_key;
@@ -1588,12 +3457,51 @@ fn ActionKeyInput(
                             action_condition
                         };
                         action.queue_to_front = None;
+                        // Only priority actions are gated
+                        if is_linked {
+                            action.condition_webhook = None;
+                        }
                     },
                     checked: matches!(action().condition, ActionCondition::Linked),
                 }
             } else {
                 div {} // Spacer
             }
+            if positionable {
+                for index in 0..MAX_LINK_KEY_CHAIN_STEPS {
+                    ActionsKeyBindingInput {
+                        label: "Chain key",
+                        disabled: templated()
+                            || matches!(action().link_key_chain[index].link_key, LinkKeyBinding::None),
+                        on_value: move |key: Option<KeyBinding>| {
+                            let mut action = action.write();
+                            let step = &mut action.link_key_chain[index];
+                            step.link_key = step.link_key.with_key(key.expect("not optional"));
+                        },
+                        value: action().link_key_chain[index].link_key.key().unwrap_or_default(),
+                    }
+                    ActionsSelect::<LinkKeyBinding> {
+                        label: "Chain key type",
+                        tooltip: "Used after [Link key] for combos with more than one link key. Before keys are used first, then at the same keys with the actual key and after keys last. Along is not supported.",
+                        disabled: templated(),
+                        on_selected: move |link_key: LinkKeyBinding| {
+                            let mut action = action.write();
+                            action.link_key_chain[index].link_key = link_key;
+                        },
+                        selected: action().link_key_chain[index].link_key,
+                    }
+                    ActionsMillisInput {
+                        label: "Chain key delay",
+                        disabled: templated()
+                            || matches!(action().link_key_chain[index].link_key, LinkKeyBinding::None),
+                        on_value: move |millis| {
+                            let mut action = action.write();
+                            action.link_key_chain[index].delay_millis = millis;
+                        },
+                        value: action().link_key_chain[index].delay_millis,
+                    }
+                }
+            }
 
             // Use with, direction
 
@@ -1607,14 +3515,28 @@ fn ActionKeyInput(
                 selected: action().with,
             }
             if directionable {
-                ActionsSelect::<ActionKeyDirection> {
-                    label: "Use direction",
-                    disabled: false,
-                    on_selected: move |direction| {
-                        let mut action = action.write();
-                        action.direction = direction;
-                    },
-                    selected: action().direction,
+                div { class: "grid grid-cols-2 gap-3",
+                    ActionsSelect::<ActionKeyDirection> {
+                        label: "Use direction",
+                        disabled: false,
+                        on_selected: move |direction| {
+                            let mut action = action.write();
+                            action.direction = direction;
+                        },
+                        selected: action().direction,
+                    }
+                    ActionsCheckbox {
+                        label: "Move holding",
+                        tooltip: "Require [Hold for] and [Use direction] other than any. When enabled, the player moves in [Use direction] while holding down the key. Useful for charge skills.",
+                        tooltip_side: ContentSide::Bottom,
+                        disabled: action().key_hold_millis == 0
+                            || matches!(action().direction, ActionKeyDirection::Any),
+                        on_checked: move |checked| {
+                            let mut action = action.write();
+                            action.key_hold_moving = checked;
+                        },
+                        checked: action().key_hold_moving,
+                    }
                 }
             } else {
                 div {} // Spacer
@@ -1635,6 +3557,17 @@ fn ActionKeyInput(
             } else {
                 div {} // Spacer
             }
+            if matches!(action().with, ActionKeyWith::DoubleJump) {
+                ActionsMillisInput {
+                    label: "Cast at apex after",
+                    on_value: move |millis| {
+                        let mut action = action.write();
+                        action.double_jump_apex_millis = millis;
+                    },
+                    value: action().double_jump_apex_millis,
+                }
+                div { class: "col-span-2" }
+            }
             if let ActionCondition::EveryMillis(millis) = action().condition {
                 ActionsMillisInput {
                     label: "Use every",
@@ -1646,10 +3579,132 @@ fn ActionKeyInput(
                 }
                 div { class: "col-span-2" }
             }
+            if matches!(
+                action().condition,
+                ActionCondition::EveryMillis(_) | ActionCondition::ErdaShowerOffCooldown
+            )
+            {
+                ActionsSelect::<ActionKeyBurstBuff> {
+                    label: "Align with buff",
+                    tooltip: "Holds the action until the selected buff is active or the max wait has passed. The buff must be enabled in the character to be detected.",
+                    disabled: false,
+                    on_selected: move |burst_buff| {
+                        let mut action = action.write();
+                        action.burst_buff = burst_buff;
+                    },
+                    selected: action().burst_buff,
+                }
+                ActionsMillisInput {
+                    label: "Max wait",
+                    disabled: matches!(action().burst_buff, ActionKeyBurstBuff::None),
+                    on_value: move |millis| {
+                        let mut action = action.write();
+                        action.burst_max_wait_millis = millis;
+                    },
+                    value: action().burst_max_wait_millis,
+                }
+                div {} // Spacer
+            }
+            ActionsSelect::<ActionKeyGroup> {
+                label: "Group",
+                tooltip: "Actions in the same group can share cooldown and block each other from being used within the exclusive duration.",
+                disabled: false,
+                on_selected: move |group| {
+                    let mut action = action.write();
+                    action.group = group;
+                },
+                selected: action().group,
+            }
+            ActionsCheckbox {
+                label: "Share group cooldown",
+                disabled: matches!(action().group, ActionKeyGroup::None),
+                on_checked: move |shares_cooldown: bool| {
+                    let mut action = action.write();
+                    action.group_shares_cooldown = shares_cooldown;
+                },
+                checked: action().group_shares_cooldown,
+            }
+            ActionsMillisInput {
+                label: "Group exclusive",
+                disabled: matches!(action().group, ActionKeyGroup::None),
+                on_value: move |millis| {
+                    let mut action = action.write();
+                    action.group_exclusive_millis = millis;
+                },
+                value: action().group_exclusive_millis,
+            }
+            if matches!(
+                action().condition,
+                ActionCondition::EveryMillis(_) | ActionCondition::ErdaShowerOffCooldown
+            )
+            {
+                Labeled { label: "Plugin detector",
+                    Select::<usize> {
+                        on_selected: move |index: usize| {
+                            let name = index.checked_sub(1).and_then(|index| {
+                                PluginDetectorName::new(&plugin_detectors()[index])
+                            });
+                            let mut action = action.write();
+                            action.plugin_detector = name;
+                        },
+
+                        SelectOption::<usize> {
+                            value: 0,
+                            label: "None",
+                            selected: action().plugin_detector.is_none(),
+                        }
+                        for (i , name) in plugin_detectors().into_iter().enumerate() {
+                            SelectOption::<usize> {
+                                value: i + 1,
+                                selected: action()
+                                    .plugin_detector
+                                    .is_some_and(|detector| detector.as_str() == name),
+                                label: name,
+                            }
+                        }
+                    }
+                }
+                ActionsCheckbox {
+                    label: "Loud skill",
+                    tooltip: "Holds the action while other players are around and the reaction in settings is to pause loud skills.",
+                    on_checked: move |loud: bool| {
+                        let mut action = action.write();
+                        action.loud = loud;
+                    },
+                    checked: action().loud,
+                }
+                Labeled { label: "Condition webhook",
+                    Select::<usize> {
+                        on_selected: move |index: usize| {
+                            let name = index.checked_sub(1).and_then(|index| {
+                                ConditionWebhookName::new(&condition_webhooks()[index])
+                            });
+                            let mut action = action.write();
+                            action.condition_webhook = name;
+                        },
+
+                        SelectOption::<usize> {
+                            value: 0,
+                            label: "None",
+                            selected: action().condition_webhook.is_none(),
+                        }
+                        for (i , name) in condition_webhooks().into_iter().enumerate() {
+                            SelectOption::<usize> {
+                                value: i + 1,
+                                selected: action()
+                                    .condition_webhook
+                                    .is_some_and(|webhook| webhook.as_str() == name),
+                                label: name,
+                            }
+                        }
+                    }
+                }
+            }
 
             // Wait before use
             ActionsMillisInput {
                 label: "Wait before use",
+                disabled: templated(),
                 on_value: move |millis| {
                     let mut action = action.write();
                     action.wait_before_use_millis = millis;
@@ -1658,6 +3713,7 @@ fn ActionKeyInput(
             }
             ActionsMillisInput {
                 label: "Wait random range",
+                disabled: templated(),
                 on_value: move |millis| {
                     let mut action = action.write();
                     action.wait_before_use_millis_random_range = millis;
@@ -1669,6 +3725,7 @@ fn ActionKeyInput(
             // Wait after use
             ActionsMillisInput {
                 label: "Wait after use",
+                disabled: templated(),
                 on_value: move |millis| {
                     let mut action = action.write();
                     action.wait_after_use_millis = millis;
@@ -1677,6 +3734,7 @@ fn ActionKeyInput(
             }
             ActionsMillisInput {
                 label: "Wait random range",
+                disabled: templated(),
                 on_value: move |millis| {
                     let mut action = action.write();
                     action.wait_after_use_millis_random_range = millis;
@@ -1694,6 +3752,241 @@ fn ActionKeyInput(
                     },
                     selected: action().wait_after_buffered,
                 }
+            } else {
+                div {} // Spacer
+            }
+
+            // Cast verification
+            ActionsCheckbox {
+                label: "Verify cast",
+                tooltip: "After the last key use, checks whether the region changed on screen. If the region stays the same, the key is assumed to be dropped and is used again.",
+                on_checked: move |verify: bool| {
+                    let mut action = action.write();
+                    action.cast_verification = verify.then(ActionKeyCastVerification::default);
+                },
+                checked: action().cast_verification.is_some(),
+            }
+            ActionsNumberInputU32 {
+                label: "Max re-casts",
+                disabled: action().cast_verification.is_none(),
+                on_value: move |count| {
+                    let mut action = action.write();
+                    if let Some(verification) = action.cast_verification.as_mut() {
+                        verification.max_recast_count = count;
+                    }
+                },
+                value: action().cast_verification.unwrap_or_default().max_recast_count,
+            }
+            div {} // Spacer
+            ActionsNumberInputI32 {
+                label: "Region X",
+                disabled: action().cast_verification.is_none(),
+                on_value: move |x| {
+                    let mut action = action.write();
+                    if let Some(verification) = action.cast_verification.as_mut() {
+                        verification.x = x;
+                    }
+                },
+                value: action().cast_verification.unwrap_or_default().x,
+            }
+            ActionsNumberInputI32 {
+                label: "Region Y",
+                disabled: action().cast_verification.is_none(),
+                on_value: move |y| {
+                    let mut action = action.write();
+                    if let Some(verification) = action.cast_verification.as_mut() {
+                        verification.y = y;
+                    }
+                },
+                value: action().cast_verification.unwrap_or_default().y,
+            }
+            div {} // Spacer
+            ActionsNumberInputI32 {
+                label: "Region width",
+                disabled: action().cast_verification.is_none(),
+                on_value: move |width| {
+                    let mut action = action.write();
+                    if let Some(verification) = action.cast_verification.as_mut() {
+                        verification.width = width;
+                    }
+                },
+                value: action().cast_verification.unwrap_or_default().width,
+            }
+            ActionsNumberInputI32 {
+                label: "Region height",
+                disabled: action().cast_verification.is_none(),
+                on_value: move |height| {
+                    let mut action = action.write();
+                    if let Some(verification) = action.cast_verification.as_mut() {
+                        verification.height = height;
+                    }
+                },
+                value: action().cast_verification.unwrap_or_default().height,
+            }
+            div {} // Spacer
+            if matches!(
+                action().condition,
+                ActionCondition::EveryMillis(_) | ActionCondition::ErdaShowerOffCooldown
+            )
+            {
+                // Resource gate
+                ActionsCheckbox {
+                    label: "Resource gate",
+                    tooltip: "Before queuing, checks whether the region on screen contains the resource this action consumes (e.g. bullets or arrows). The action is held until the region is satisfied.",
+                    on_checked: move |gate: bool| {
+                        let mut action = action.write();
+                        action.resource_gate = gate.then(ActionKeyResourceGate::default);
+                    },
+                    checked: action().resource_gate.is_some(),
+                }
+                ActionsSelect::<ActionKeyResourceGateKind> {
+                    label: "Gate kind",
+                    disabled: action().resource_gate.is_none(),
+                    on_selected: move |kind| {
+                        let mut action = action.write();
+                        if let Some(gate) = action.resource_gate.as_mut() {
+                            gate.kind = kind;
+                        }
+                    },
+                    selected: action().resource_gate.unwrap_or_default().kind,
+                }
+                div {} // Spacer
+                ActionsNumberInputI32 {
+                    label: "Gate region X",
+                    disabled: action().resource_gate.is_none(),
+                    on_value: move |x| {
+                        let mut action = action.write();
+                        if let Some(gate) = action.resource_gate.as_mut() {
+                            gate.x = x;
+                        }
+                    },
+                    value: action().resource_gate.unwrap_or_default().x,
+                }
+                ActionsNumberInputI32 {
+                    label: "Gate region Y",
+                    disabled: action().resource_gate.is_none(),
+                    on_value: move |y| {
+                        let mut action = action.write();
+                        if let Some(gate) = action.resource_gate.as_mut() {
+                            gate.y = y;
+                        }
+                    },
+                    value: action().resource_gate.unwrap_or_default().y,
+                }
+                div {} // Spacer
+                ActionsNumberInputI32 {
+                    label: "Gate region width",
+                    disabled: action().resource_gate.is_none(),
+                    on_value: move |width| {
+                        let mut action = action.write();
+                        if let Some(gate) = action.resource_gate.as_mut() {
+                            gate.width = width;
+                        }
+                    },
+                    value: action().resource_gate.unwrap_or_default().width,
+                }
+                ActionsNumberInputI32 {
+                    label: "Gate region height",
+                    disabled: action().resource_gate.is_none(),
+                    on_value: move |height| {
+                        let mut action = action.write();
+                        if let Some(gate) = action.resource_gate.as_mut() {
+                            gate.height = height;
+                        }
+                    },
+                    value: action().resource_gate.unwrap_or_default().height,
+                }
+                div {} // Spacer
+                Labeled { label: "Gate color red",
+                    PrimitiveIntegerInput {
+                        disabled: action().resource_gate.is_none(),
+                        on_value: move |red: u8| {
+                            let mut action = action.write();
+                            if let Some(gate) = action.resource_gate.as_mut() {
+                                gate.color.0 = red;
+                            }
+                        },
+                        value: action().resource_gate.unwrap_or_default().color.0,
+                    }
+                }
+                Labeled { label: "Gate color green",
+                    PrimitiveIntegerInput {
+                        disabled: action().resource_gate.is_none(),
+                        on_value: move |green: u8| {
+                            let mut action = action.write();
+                            if let Some(gate) = action.resource_gate.as_mut() {
+                                gate.color.1 = green;
+                            }
+                        },
+                        value: action().resource_gate.unwrap_or_default().color.1,
+                    }
+                }
+                Labeled { label: "Gate color blue",
+                    PrimitiveIntegerInput {
+                        disabled: action().resource_gate.is_none(),
+                        on_value: move |blue: u8| {
+                            let mut action = action.write();
+                            if let Some(gate) = action.resource_gate.as_mut() {
+                                gate.color.2 = blue;
+                            }
+                        },
+                        value: action().resource_gate.unwrap_or_default().color.2,
+                    }
+                }
+                Labeled { label: "Gate color tolerance",
+                    PrimitiveIntegerInput {
+                        disabled: action().resource_gate.is_none(),
+                        on_value: move |tolerance: u8| {
+                            let mut action = action.write();
+                            if let Some(gate) = action.resource_gate.as_mut() {
+                                gate.tolerance = tolerance;
+                            }
+                        },
+                        value: action().resource_gate.unwrap_or_default().tolerance,
+                    }
+                }
+                div {} // Spacer
+                div {} // Spacer
+            }
+        }
+        div { class: "grid grid-cols-3 gap-3 mt-3",
+            ActionsLimitedTextInput {
+                label: "Note",
+                max_len: ActionNote::MAX_LEN,
+                on_value: move |note: String| {
+                    if let Some(note) = ActionNote::new(&note) {
+                        action.write().note = note;
+                    }
+                },
+                value: action().note.to_string(),
+            }
+            ActionsSelect::<ActionColor> {
+                label: "Color",
+                disabled: false,
+                on_selected: move |color| {
+                    let mut action = action.write();
+                    action.color = color;
+                },
+                selected: action().color,
+            }
+            ActionsCheckbox {
+                label: "Enabled",
+                tooltip: "Disabled actions are kept but not used by the rotation.",
+                on_checked: move |enabled: bool| {
+                    let mut action = action.write();
+                    action.enabled = enabled;
+                },
+                checked: action().enabled,
+            }
+            ActionsLimitedTextInput {
+                label: "Tags",
+                max_len: ActionNote::MAX_LEN,
+                on_value: move |tags: String| {
+                    if let Some(tags) = ActionTags::new(&tags) {
+                        action.write().tags = tags;
+                    }
+                },
+                value: action().tags.to_string(),
             }
         }
         div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
@@ -1727,6 +4020,7 @@ fn ActionList(
     on_item_click: Callback<(Action, usize)>,
     on_item_move: Callback<(usize, ActionCondition, bool)>,
     on_item_delete: Callback<usize>,
+    on_item_toggle: Callback<usize>,
     condition_filter: ActionCondition,
     disabled: bool,
     actions: Vec<Action>,
@@ -1738,6 +4032,7 @@ fn ActionList(
         index: usize,
         on_item_move: Callback<(usize, ActionCondition, bool)>,
         on_item_delete: Callback<usize>,
+        on_item_toggle: Callback<usize>,
     ) -> Element {
         const ICON_CONTAINER_CLASS: &str = "size-fit";
         const ICON_CLASS: &str = "size-3";
@@ -1765,6 +4060,18 @@ fn ActionList(
                     },
                     DownArrowIcon { class: ICON_CLASS }
                 }
+                div {
+                    class: ICON_CONTAINER_CLASS,
+                    onclick: move |e| {
+                        e.stop_propagation();
+                        on_item_toggle(index);
+                    },
+                    if action.enabled() {
+                        EyePasswordShowIcon { class: ICON_CLASS }
+                    } else {
+                        EyePasswordHideIcon { class: ICON_CLASS }
+                    }
+                }
                 div {
                     class: ICON_CONTAINER_CLASS,
                     onclick: move |e| {
@@ -1784,12 +4091,18 @@ fn ActionList(
             for (action , index) in filtered {
                 div {
                     class: "flex group flex-grow",
+                    title: action.note().to_string(),
                     onclick: move |e| {
                         e.stop_propagation();
                         on_item_click((action, index));
                     },
 
-                    PopupTrigger { class: "flex-grow",
+                    PopupTrigger {
+                        class: format!(
+                            "flex-grow {} {}",
+                            action_color_class(action.color()),
+                            if action.enabled() { "" } else { "opacity-50" },
+                        ),
                         match action {
                             Action::Move(action) => rsx! {
                                 ActionMoveItem { action }
@@ -1806,6 +4119,7 @@ fn ActionList(
                         index,
                         on_item_move,
                         on_item_delete,
+                        on_item_toggle,
                     }
                 }
             }
@@ -1838,6 +4152,7 @@ fn ActionMoveItem(action: ActionMove) -> Element {
             },
         condition,
         wait_after_move_millis,
+        ..
     } = action;
 
     let x_min = (x - x_random_range).max(0);
@@ -2058,6 +4373,48 @@ fn ActionsMillisInput(
     }
 }
 
+/// A text input limited to `max_len` bytes.
+///
+/// Text longer than `max_len` bytes is kept in the input but not passed to `on_value`. The number
+/// of bytes used is shown below the input along with an error while the text is too long.
+#[component]
+fn ActionsLimitedTextInput(
+    label: &'static str,
+    max_len: usize,
+    on_value: Callback<String>,
+    value: ReadSignal<String>,
+) -> Element {
+    let mut text = use_signal(|| value.cloned());
+    let len = use_memo(move || text.read().len());
+    let too_long = use_memo(move || len() > max_len);
+
+    use_effect(move || {
+        text.set(value.cloned());
+    });
+
+    rsx! {
+        Labeled { label,
+            TextInput {
+                class: "h-6",
+                on_value: move |new_text: String| {
+                    if new_text.len() <= max_len {
+                        on_value(new_text.clone());
+                    }
+                    text.set(new_text);
+                },
+                value: text(),
+            }
+            p { class: if too_long() { "text-xs text-danger-text" } else { "text-xs text-secondary-text" },
+                if too_long() {
+                    "{len}/{max_len} bytes, too long to be saved"
+                } else {
+                    "{len}/{max_len} bytes"
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn ActionsCheckbox(
     label: &'static str,
@@ -2101,6 +4458,19 @@ fn ActionsKeyBindingInput(
 }
 
 /// Finds the linked action index range where `action_index` is a non-linked action.
+/// Gets the left border class for the action list item of `color`.
+fn action_color_class(color: ActionColor) -> &'static str {
+    match color {
+        ActionColor::None => "",
+        ActionColor::Red => "border-l-2 border-red-500",
+        ActionColor::Orange => "border-l-2 border-orange-500",
+        ActionColor::Yellow => "border-l-2 border-yellow-500",
+        ActionColor::Green => "border-l-2 border-green-500",
+        ActionColor::Blue => "border-l-2 border-blue-500",
+        ActionColor::Purple => "border-l-2 border-purple-500",
+    }
+}
+
 fn find_linked_action_range(actions: &[Action], action_index: usize) -> Option<Range<usize>> {
     if action_index + 1 >= actions.len() {
         return None;
@@ -2175,3 +4545,19 @@ fn update_valid_platform_end(platform: &mut Platform) {
         platform.x_end
     };
 }
+
+fn update_valid_rope_end(rope: &mut Rope) {
+    rope.y_end = if rope.y_end <= rope.y_start {
+        rope.y_start + 1
+    } else {
+        rope.y_end
+    };
+}
+
+fn update_valid_danger_zone_end(zone: &mut DangerZone) {
+    zone.x_end = if zone.x_end <= zone.x_start {
+        zone.x_start + 1
+    } else {
+        zone.x_end
+    };
+}
@@ -1,13 +1,16 @@
-use std::{fmt::Display, mem};
+use std::{fmt::Display, mem, time::Duration};
 
 use backend::{
     ActionConfiguration, ActionConfigurationCondition, ActionKeyWith, Character, EliteBossBehavior,
     ExchangeHexaBoosterCondition, FamiliarRarity, Familiars, IntoEnumIterator, KeyBinding,
-    KeyBindingConfiguration, LinkKeyBinding, PotionMode, SwappableFamiliars, WaitAfterBuffered,
-    delete_character, query_characters, update_character, upsert_character,
+    KeyBindingConfiguration, LinkKeyBinding, MAX_LEVEL_UP_AUTO_ASSIGN_KEYS_COUNT, MovementKeys,
+    PotionMode, ShopPurchase, SwappableFamiliars, WaitAfterBuffered, calibrate_movement,
+    delete_character, query_characters, query_maps, update_character, upsert_character,
+    validate_key_bindings,
 };
 use dioxus::{html::FileData, prelude::*};
 use futures_util::StreamExt;
+use tokio::time::sleep;
 
 use crate::{
     AppState,
@@ -146,10 +149,12 @@ pub fn CharactersScreen() -> Element {
             SectionKeyBindings {}
             SectionUsePotionAndFeedPet {}
             SectionUseBooster {}
+            SectionShopPurchase {}
             SectionMovement {}
             SectionFamiliars {}
             SectionBuffs {}
             SectionFixedActions {}
+            SectionLevelUpPopups {}
             SectionOthers {}
         }
 
@@ -190,6 +195,12 @@ fn SectionKeyBindings() -> Element {
     let context = use_context::<CharactersContext>();
     let character = context.character;
     let save_character = context.save_character;
+    let warnings = use_memo(move || {
+        validate_key_bindings(&character())
+            .into_iter()
+            .map(|warning| warning.to_string())
+            .collect::<Vec<_>>()
+    });
 
     rsx! {
         Section { title: "Key bindings",
@@ -218,6 +229,19 @@ fn SectionKeyBindings() -> Element {
                     },
                     value: character().teleport_key,
                 }
+                CharactersKeyBindingConfigurationInput {
+                    label: "Flight",
+                    optional: true,
+                    tooltip: "This is meant for classes that can glide horizontally by holding a key.",
+                    disabled: character().id.is_none(),
+                    on_value: move |flight_key| {
+                        save_character(Character {
+                            flight_key,
+                            ..character.peek().clone()
+                        });
+                    },
+                    value: character().flight_key,
+                }
                 CharactersKeyBindingConfigurationInput {
                     label: "Jump",
                     disabled: character().id.is_none(),
@@ -232,7 +256,7 @@ fn SectionKeyBindings() -> Element {
                 CharactersKeyBindingConfigurationInput {
                     label: "Up jump",
                     optional: true,
-                    tooltip: "This is meant for classes that have a separate skill to up jump. Classes that use up arrow should set this key to up arrow.",
+                    tooltip: "This is meant for classes that have a separate skill to up jump. Classes that use up arrow should set this key to the move up key.",
                     disabled: character().id.is_none(),
                     on_value: move |up_jump_key| {
                         save_character(Character {
@@ -242,6 +266,63 @@ fn SectionKeyBindings() -> Element {
                     },
                     value: character().up_jump_key,
                 }
+                CharactersKeyInput {
+                    label: "Move left",
+                    tooltip: "Only change the movement keys if they are rebound in-game. Menus are still navigated with the arrow keys.",
+                    disabled: character().id.is_none(),
+                    on_value: move |key: Option<KeyBinding>| {
+                        save_character(Character {
+                            movement_keys: MovementKeys {
+                                left: key.expect("not optional"),
+                                ..character.peek().movement_keys
+                            },
+                            ..character.peek().clone()
+                        });
+                    },
+                    value: Some(character().movement_keys.left),
+                }
+                CharactersKeyInput {
+                    label: "Move right",
+                    disabled: character().id.is_none(),
+                    on_value: move |key: Option<KeyBinding>| {
+                        save_character(Character {
+                            movement_keys: MovementKeys {
+                                right: key.expect("not optional"),
+                                ..character.peek().movement_keys
+                            },
+                            ..character.peek().clone()
+                        });
+                    },
+                    value: Some(character().movement_keys.right),
+                }
+                CharactersKeyInput {
+                    label: "Move up",
+                    disabled: character().id.is_none(),
+                    on_value: move |key: Option<KeyBinding>| {
+                        save_character(Character {
+                            movement_keys: MovementKeys {
+                                up: key.expect("not optional"),
+                                ..character.peek().movement_keys
+                            },
+                            ..character.peek().clone()
+                        });
+                    },
+                    value: Some(character().movement_keys.up),
+                }
+                CharactersKeyInput {
+                    label: "Move down",
+                    disabled: character().id.is_none(),
+                    on_value: move |key: Option<KeyBinding>| {
+                        save_character(Character {
+                            movement_keys: MovementKeys {
+                                down: key.expect("not optional"),
+                                ..character.peek().movement_keys
+                            },
+                            ..character.peek().clone()
+                        });
+                    },
+                    value: Some(character().movement_keys.down),
+                }
                 CharactersKeyBindingConfigurationInput {
                     label: "Interact",
                     disabled: character().id.is_none(),
@@ -306,6 +387,11 @@ fn SectionKeyBindings() -> Element {
                     value: character().familiar_menu_key,
                 }
             }
+            if character().id.is_some() {
+                for warning in warnings() {
+                    p { class: "mt-2 text-xs text-danger-text", {warning} }
+                }
+            }
         }
     }
 }
@@ -576,12 +662,235 @@ fn SectionUseBooster() -> Element {
     }
 }
 
+#[component]
+fn SectionShopPurchase() -> Element {
+    let context = use_context::<CharactersContext>();
+    let character = context.character;
+    let save_character = context.save_character;
+    let maps = use_resource(async || query_maps().await.unwrap_or_default());
+    let npc_maps = use_memo(move || {
+        maps()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|map| Some((map.name, map.paths_id_index?)))
+            .collect::<Vec<_>>()
+    });
+
+    let save_shop_purchase = move |shop_purchase: ShopPurchase| {
+        save_character(Character {
+            shop_purchase,
+            ..character.peek().clone()
+        });
+    };
+    let shop_purchase = use_memo(move || character().shop_purchase);
+    let disabled = use_memo(move || character().id.is_none() || !shop_purchase().enabled);
+
+    rsx! {
+        Section { title: "Shop purchase",
+            div { class: "grid grid-cols-3 gap-4",
+                CharactersCheckbox {
+                    label: "Enabled",
+                    tooltip: "Buys the item from an NPC shop when its count in the quick slots drops below the threshold. If the NPC is on another map, the map must be reachable through navigation paths.",
+                    checked: shop_purchase().enabled,
+                    on_checked: move |enabled| {
+                        save_shop_purchase(ShopPurchase {
+                            enabled,
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: character().id.is_none(),
+                }
+                CharactersKeyInput {
+                    label: "Open shop key",
+                    value: Some(shop_purchase().open_key),
+                    on_value: move |key: Option<KeyBinding>| {
+                        save_shop_purchase(ShopPurchase {
+                            open_key: key.expect("not optional"),
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: disabled(),
+                }
+                Labeled { label: "NPC map",
+                    Select::<Option<(i64, usize)>> {
+                        on_selected: move |paths_id_index| {
+                            save_shop_purchase(ShopPurchase {
+                                paths_id_index,
+                                ..*shop_purchase.peek()
+                            });
+                        },
+                        disabled: disabled(),
+
+                        SelectOption::<Option<(i64, usize)>> {
+                            value: None,
+                            label: "Current map".to_string(),
+                            selected: shop_purchase().paths_id_index.is_none(),
+                        }
+                        for (name , paths_id_index) in npc_maps() {
+                            SelectOption::<Option<(i64, usize)>> {
+                                value: Some(paths_id_index),
+                                label: name,
+                                selected: shop_purchase().paths_id_index == Some(paths_id_index),
+                            }
+                        }
+                    }
+                }
+                CharactersNumberI32Input {
+                    label: "NPC X",
+                    value: shop_purchase().npc_x,
+                    on_value: move |npc_x| {
+                        save_shop_purchase(ShopPurchase {
+                            npc_x,
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: disabled(),
+                }
+                CharactersNumberI32Input {
+                    label: "NPC Y",
+                    value: shop_purchase().npc_y,
+                    on_value: move |npc_y| {
+                        save_shop_purchase(ShopPurchase {
+                            npc_y,
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: disabled(),
+                }
+                CharactersNumberU32Input {
+                    label: "Quantity",
+                    max_value: 9999,
+                    value: shop_purchase().quantity,
+                    on_value: move |quantity| {
+                        save_shop_purchase(ShopPurchase {
+                            quantity,
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: disabled(),
+                }
+                CharactersNumberI32Input {
+                    label: "Shop tab X",
+                    value: shop_purchase().tab_x,
+                    on_value: move |tab_x| {
+                        save_shop_purchase(ShopPurchase {
+                            tab_x,
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: disabled(),
+                }
+                CharactersNumberI32Input {
+                    label: "Shop tab Y",
+                    value: shop_purchase().tab_y,
+                    on_value: move |tab_y| {
+                        save_shop_purchase(ShopPurchase {
+                            tab_y,
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: disabled(),
+                }
+                div {}
+                CharactersNumberI32Input {
+                    label: "Item X",
+                    value: shop_purchase().item_x,
+                    on_value: move |item_x| {
+                        save_shop_purchase(ShopPurchase {
+                            item_x,
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: disabled(),
+                }
+                CharactersNumberI32Input {
+                    label: "Item Y",
+                    value: shop_purchase().item_y,
+                    on_value: move |item_y| {
+                        save_shop_purchase(ShopPurchase {
+                            item_y,
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: disabled(),
+                }
+                div {}
+                CharactersNumberI32Input {
+                    label: "Count region X",
+                    value: shop_purchase().count_x,
+                    on_value: move |count_x| {
+                        save_shop_purchase(ShopPurchase {
+                            count_x,
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: disabled(),
+                }
+                CharactersNumberI32Input {
+                    label: "Count region Y",
+                    value: shop_purchase().count_y,
+                    on_value: move |count_y| {
+                        save_shop_purchase(ShopPurchase {
+                            count_y,
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: disabled(),
+                }
+                CharactersNumberU32Input {
+                    label: "Buy below count",
+                    value: shop_purchase().count_threshold,
+                    on_value: move |count_threshold| {
+                        save_shop_purchase(ShopPurchase {
+                            count_threshold,
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: disabled(),
+                }
+                CharactersNumberI32Input {
+                    label: "Count region width",
+                    value: shop_purchase().count_width,
+                    on_value: move |count_width| {
+                        save_shop_purchase(ShopPurchase {
+                            count_width,
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: disabled(),
+                }
+                CharactersNumberI32Input {
+                    label: "Count region height",
+                    value: shop_purchase().count_height,
+                    on_value: move |count_height| {
+                        save_shop_purchase(ShopPurchase {
+                            count_height,
+                            ..*shop_purchase.peek()
+                        });
+                    },
+                    disabled: disabled(),
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn SectionMovement() -> Element {
     let context = use_context::<CharactersContext>();
     let character = context.character;
     let save_character = context.save_character;
+    let app_character = use_context::<AppState>().character;
     let disabled = use_memo(move || character().id.is_none());
+    let mut calibrating = use_signal(|| false);
+    let walk_speed = use_memo(move || format_speed(character().walk_speed));
+    let double_jump_speed = use_memo(move || format_speed(character().double_jump_speed));
+    let teleport_distance = use_memo(move || {
+        format_distance(
+            character().teleport_x_distance,
+            character().teleport_y_distance,
+        )
+    });
 
     rsx! {
         Section { title: "Movement",
@@ -622,6 +931,30 @@ fn SectionMovement() -> Element {
                     tooltip: "Applicable only to mage class.",
                     disabled,
                 }
+                CharactersCheckbox {
+                    label: "Teleport horizontally",
+                    on_checked: move |horizontal_teleporting| {
+                        save_character(Character {
+                            horizontal_teleporting,
+                            ..character.peek().clone()
+                        });
+                    },
+                    checked: character().horizontal_teleporting,
+                    tooltip: "Applicable only to mage class. Replaces double jumping with repeated teleports.",
+                    disabled,
+                }
+                CharactersCheckbox {
+                    label: "Fly horizontally",
+                    on_checked: move |horizontal_flight| {
+                        save_character(Character {
+                            horizontal_flight,
+                            ..character.peek().clone()
+                        });
+                    },
+                    checked: character().horizontal_flight,
+                    tooltip: "Applicable only when flight key is set. Replaces double jumping with gliding while holding the flight key.",
+                    disabled,
+                }
                 CharactersCheckbox {
                     label: "Disable double jumping",
                     on_checked: move |disable_double_jumping| {
@@ -647,6 +980,84 @@ fn SectionMovement() -> Element {
                     disabled,
                 }
             }
+            div { class: "grid grid-cols-3 gap-4 mt-2 items-center",
+                div { class: "text-xs text-secondary-text", "Walk speed: {walk_speed}" }
+                div { class: "text-xs text-secondary-text", "Double jump speed: {double_jump_speed}" }
+                div { class: "text-xs text-secondary-text", "Teleport distance: {teleport_distance}" }
+                div { class: "flex gap-2",
+                    Button {
+                        style: ButtonStyle::Secondary,
+                        class: "flex-grow",
+                        on_click: move |_| {
+                            let current = character.peek().clone();
+                            spawn(async move {
+                                calibrating.set(true);
+                                calibrate(app_character, current).await;
+                                calibrating.set(false);
+                            });
+                        },
+                        disabled: disabled() || calibrating(),
+                        if calibrating() {
+                            "Calibrating..."
+                        } else {
+                            "Calibrate"
+                        }
+                    }
+                    Button {
+                        style: ButtonStyle::Secondary,
+                        class: "flex-grow",
+                        on_click: move |_| {
+                            save_character(Character {
+                                walk_speed: None,
+                                double_jump_speed: None,
+                                teleport_x_distance: None,
+                                teleport_y_distance: None,
+                                ..character.peek().clone()
+                            });
+                        },
+                        disabled: disabled() || calibrating(),
+                        "Reset"
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn format_speed(speed: Option<f32>) -> String {
+    speed
+        .map(|speed| format!("{speed:.2} px/tick"))
+        .unwrap_or_else(|| "Not calibrated".to_string())
+}
+
+fn format_distance(x_distance: Option<f32>, y_distance: Option<f32>) -> String {
+    match (x_distance, y_distance) {
+        (Some(x), Some(y)) => format!("{x:.0} px horizontal, {y:.0} px vertical"),
+        _ => "Not calibrated".to_string(),
+    }
+}
+
+/// Starts calibrating movement and waits for the result to be saved to `current`.
+async fn calibrate(mut app_character: Signal<Option<Character>>, current: Character) {
+    const POLL_COUNT: u32 = 15;
+
+    calibrate_movement().await;
+    for _ in 0..POLL_COUNT {
+        sleep(Duration::from_secs(1)).await;
+
+        let updated = query_characters()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .find(|character| character.id == current.id);
+        if let Some(updated) = updated
+            && (updated.walk_speed != current.walk_speed
+                || updated.double_jump_speed != current.double_jump_speed
+                || updated.teleport_x_distance != current.teleport_x_distance
+                || updated.teleport_y_distance != current.teleport_y_distance)
+        {
+            app_character.set(Some(updated));
+            break;
         }
     }
 }
@@ -743,6 +1154,19 @@ fn SectionFamiliars() -> Element {
                     },
                     checked: familiars().swappable_rarities.contains(&FamiliarRarity::Epic),
                 }
+                CharactersMillisInput {
+                    label: "Buff duration (0 to disable re-summon)",
+                    on_value: move |buff_duration_millis| {
+                        save_character(Character {
+                            familiars: Familiars {
+                                buff_duration_millis,
+                                ..familiars.peek().clone()
+                            },
+                            ..character.peek().clone()
+                        });
+                    },
+                    value: familiars().buff_duration_millis,
+                }
             }
         }
     }
@@ -1083,6 +1507,64 @@ fn SectionFixedActions() -> Element {
     }
 }
 
+#[component]
+fn SectionLevelUpPopups() -> Element {
+    let context = use_context::<CharactersContext>();
+    let character = context.character;
+    let save_character = context.save_character;
+
+    let set_auto_assign_key = move |index: usize, key: Option<KeyBinding>| {
+        let mut character = character.peek().clone();
+        match key {
+            Some(key) if index < character.level_up_auto_assign_keys.len() => {
+                character.level_up_auto_assign_keys[index] = key;
+            }
+            Some(key) => character.level_up_auto_assign_keys.push(key),
+            None if index < character.level_up_auto_assign_keys.len() => {
+                character.level_up_auto_assign_keys.remove(index);
+            }
+            None => return,
+        }
+
+        save_character(character);
+    };
+    let keys_count = use_memo(move || {
+        (character().level_up_auto_assign_keys.len() + 1).min(MAX_LEVEL_UP_AUTO_ASSIGN_KEYS_COUNT)
+    });
+
+    rsx! {
+        Section { title: "Level up popups",
+            div { class: "grid grid-cols-3 gap-4",
+                CharactersCheckbox {
+                    label: "Dismiss level up popups",
+                    tooltip: "Presses the auto-assign keys in order and then ESC when a level up or new skill popup is detected.",
+                    checked: character().dismiss_level_up_popups,
+                    on_checked: move |dismiss_level_up_popups| {
+                        save_character(Character {
+                            dismiss_level_up_popups,
+                            ..character.peek().clone()
+                        });
+                    },
+                    disabled: character().id.is_none(),
+                }
+                div {}
+                div {}
+                for index in 0..keys_count() {
+                    CharactersKeyInput {
+                        label: format!("Auto-assign key {}", index + 1),
+                        value: character().level_up_auto_assign_keys.get(index).copied(),
+                        on_value: move |key: Option<KeyBinding>| {
+                            set_auto_assign_key(index, key);
+                        },
+                        optional: true,
+                        disabled: character().id.is_none() || !character().dismiss_level_up_popups,
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn SectionOthers() -> Element {
     let context = use_context::<CharactersContext>();
@@ -1758,6 +2240,20 @@ fn CharactersMillisInput(
     }
 }
 
+#[component]
+fn CharactersNumberI32Input(
+    label: &'static str,
+    value: i32,
+    on_value: Callback<i32>,
+    #[props(default)] disabled: bool,
+) -> Element {
+    rsx! {
+        Labeled { label,
+            PrimitiveIntegerInput { value, on_value, disabled }
+        }
+    }
+}
+
 #[component]
 fn CharactersNumberU32Input(
     label: &'static str,
@@ -147,6 +147,7 @@ fn map_key(key: Key) -> Option<KeyBinding> {
             "," => KeyBinding::Comma,
             "." => KeyBinding::Period,
             "/" => KeyBinding::Slash,
+            "-" => KeyBinding::Minus,
             " " => KeyBinding::Space,
             _ => return None,
         },
@@ -23,6 +23,7 @@ pub mod position;
 pub mod section;
 pub mod select;
 pub mod text;
+pub mod toast;
 pub mod tooltip;
 
 fn use_unique_id() -> Memo<String> {
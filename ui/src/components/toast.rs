@@ -0,0 +1,31 @@
+use dioxus::prelude::*;
+
+use crate::components::icons::XIcon;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ToastProps {
+    title: String,
+    message: String,
+    on_close: EventHandler,
+}
+
+#[component]
+pub fn Toast(props: ToastProps) -> Element {
+    let on_close = props.on_close;
+
+    rsx! {
+        div { class: "flex gap-2 w-xs p-2 bg-secondary-surface border border-primary-border",
+            div { class: "flex flex-col flex-grow gap-1",
+                p { class: "text-xs text-primary-text font-medium", {props.title} }
+                p { class: "text-xxs text-secondary-text", {props.message} }
+            }
+            div {
+                class: "flex-none w-fit h-fit p-1 hover:bg-primary-surface",
+                onclick: move |_| {
+                    on_close(());
+                },
+                XIcon { class: "size-3" }
+            }
+        }
+    }
+}
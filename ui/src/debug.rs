@@ -1,18 +1,28 @@
+use std::time::Duration;
+
 use backend::{
-    DebugState, auto_save_rune, debug_state_receiver, infer_minimap, infer_rune, record_images,
-    test_spin_rune,
+    DebugState, TemplateTestReport, TickSystemProfile, auto_save_rune, debug_state_receiver,
+    infer_minimap, infer_rune, query_tick_system_profiles, record_images, step, test_spin_rune,
+    test_templates, update_stepping,
 };
 use dioxus::prelude::*;
-use tokio::sync::broadcast::error::RecvError;
+use tokio::{sync::broadcast::error::RecvError, time::sleep};
 
 use crate::components::{
     button::{Button, ButtonStyle},
     section::Section,
+    text::TextInput,
 };
 
+const TICK_PROFILES_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 #[component]
 pub fn DebugScreen() -> Element {
     let mut state = use_signal(DebugState::default);
+    let mut template_test_dir = use_signal(String::default);
+    let mut template_testing = use_signal(|| false);
+    let mut template_test_report = use_signal(|| None::<Option<TemplateTestReport>>);
+    let mut tick_profiles = use_signal(Vec::<TickSystemProfile>::new);
 
     use_future(move || async move {
         let mut rx = debug_state_receiver().await;
@@ -28,6 +38,13 @@ pub fn DebugScreen() -> Element {
         }
     });
 
+    use_future(move || async move {
+        loop {
+            tick_profiles.set(query_tick_system_profiles().await);
+            sleep(TICK_PROFILES_POLL_INTERVAL).await;
+        }
+    });
+
     rsx! {
         div { class: "flex flex-col h-full overflow-y-auto",
             Section { title: "Debug",
@@ -82,6 +99,128 @@ pub fn DebugScreen() -> Element {
                     }
                 }
             }
+            Section { title: "Stepping",
+                div { class: "grid grid-cols-3 gap-3",
+                    Button {
+                        style: ButtonStyle::Secondary,
+                        on_click: move |_| async move {
+                            update_stepping(!state.peek().is_stepping).await;
+                        },
+
+                        if state().is_stepping {
+                            "Resume"
+                        } else {
+                            "Pause"
+                        }
+                    }
+                    Button {
+                        style: ButtonStyle::Secondary,
+                        disabled: !state().is_stepping,
+                        on_click: move |_| async {
+                            step(1).await;
+                        },
+
+                        "Step 1 tick"
+                    }
+                    Button {
+                        style: ButtonStyle::Secondary,
+                        disabled: !state().is_stepping,
+                        on_click: move |_| async {
+                            step(30).await;
+                        },
+
+                        "Step 30 ticks"
+                    }
+                }
+                div { class: "flex flex-col gap-1 mt-2 text-xs text-primary-text",
+                    p { "Tick: {state().tick}" }
+                    p { "State: {state().player_state}" }
+                    p {
+                        {
+                            format!(
+                                "Planned: {}",
+                                state().planned_transition.unwrap_or("None".to_string()),
+                            )
+                        }
+                    }
+                    p { {format!("Next states: {}", state().next_states.join(", "))} }
+                    p { "Queued actions:" }
+                    for (i , action) in state().queued_actions.into_iter().enumerate() {
+                        p { class: "pl-2", "{i + 1}. {action}" }
+                    }
+                }
+            }
+            Section { title: "Tick profile",
+                div { class: "flex flex-col gap-1 text-xs text-primary-text",
+                    for (system , average , max) in tick_profiles()
+                        .into_iter()
+                        .map(|profile| {
+                            (
+                                profile.system,
+                                profile.average.as_secs_f64() * 1000.0,
+                                profile.max.as_secs_f64() * 1000.0,
+                            )
+                        })
+                    {
+                        p { "{system}: average {average:.2}ms, max {max:.2}ms" }
+                    }
+                }
+            }
+            Section { title: "Template tester",
+                div { class: "flex gap-3 h-6",
+                    TextInput {
+                        class: "flex-grow",
+                        placeholder: "Enter a screenshots folder path...",
+                        value: template_test_dir(),
+                        disabled: template_testing(),
+                        on_value: move |dir| {
+                            template_test_dir.set(dir);
+                        },
+                    }
+                    Button {
+                        class: "w-24",
+                        style: ButtonStyle::Secondary,
+                        disabled: template_testing() || template_test_dir().is_empty(),
+                        on_click: move |_| async move {
+                            template_testing.set(true);
+                            let report = test_templates(template_test_dir.peek().clone()).await;
+                            template_test_report.set(Some(report));
+                            template_testing.set(false);
+                        },
+
+                        if template_testing() {
+                            "Testing..."
+                        } else {
+                            "Run"
+                        }
+                    }
+                }
+                div { class: "flex flex-col gap-1 mt-2 text-xs text-primary-text",
+                    match template_test_report() {
+                        Some(Some(report)) => rsx! {
+                            p { "Tested {report.images_count} screenshots" }
+                            p { "Annotated screenshots: {report.annotated_dir}" }
+                            for (template , images) in report
+                                .results
+                                .into_iter()
+                                .map(|result| (result.template, result.detected_images.join(", ")))
+                            {
+                                p { class: "pl-2",
+                                    if images.is_empty() {
+                                        "FAIL {template}"
+                                    } else {
+                                        "PASS {template}: {images}"
+                                    }
+                                }
+                            }
+                        },
+                        Some(None) => rsx! {
+                            p { "Failed to test templates against the folder" }
+                        },
+                        None => rsx! {},
+                    }
+                }
+            }
         }
     }
 }
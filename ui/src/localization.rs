@@ -1,9 +1,13 @@
+use std::time::Duration;
+
 use backend::{
-    GameTemplate, Localization, convert_image_to_base64, query_localization, query_template,
+    GameTemplate, Localization, TemplateSuggestion, convert_image_to_base64,
+    dismiss_template_suggestion, query_localization, query_template, query_template_suggestions,
     save_capture_image, upsert_localization,
 };
 use dioxus::{html::FileData, prelude::*};
 use futures_util::{StreamExt, future::OptionFuture};
+use tokio::time::sleep;
 
 use crate::{
     AppState,
@@ -15,6 +19,8 @@ use crate::{
     },
 };
 
+const SUGGESTIONS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
 #[derive(Debug)]
 enum LocalizationUpdate {
     Update(Localization),
@@ -61,9 +67,11 @@ pub fn LocalizationScreen() -> Element {
     rsx! {
         div { class: "flex flex-col h-full overflow-y-auto",
             SectionInfo {}
+            SectionSuggestions {}
             SectionPopups {}
             SectionFamiliars {}
             SectionHexa {}
+            SectionAnnouncements {}
             SectionOthers {}
         }
     }
@@ -141,6 +149,15 @@ fn SectionInfo() -> Element {
                         Data { description: "Convert Sol Erda to HEXA Booster." }
                         Data { description: "Convert button." }
                     }
+                    tr {
+                        Data { description: "Announcements", rowspan: 2 }
+                        Data { description: "React to world boss announcement." }
+                        Data { description: "World boss banner." }
+                    }
+                    tr {
+                        Data { description: "React to invasion announcement." }
+                        Data { description: "Invasion banner." }
+                    }
                     tr {
                         Data { description: "Others", rowspan: 3 }
                         Data { description: "Detect whether change channel menu is opened." }
@@ -176,6 +193,101 @@ fn SectionInfo() -> Element {
     }
 }
 
+#[component]
+fn SectionSuggestions() -> Element {
+    let context = use_context::<LocalizationContext>();
+    let localization = context.localization;
+    let save_localization = context.save_localization;
+    let mut suggestions = use_signal(Vec::<TemplateSuggestion>::new);
+
+    let accept_suggestion = use_callback(move |suggestion: TemplateSuggestion| async move {
+        let mut new_localization = localization();
+        new_localization.set_template_base64(suggestion.template, Some(suggestion.base64));
+        save_localization(new_localization);
+        dismiss_template_suggestion(suggestion.template).await;
+        suggestions.set(query_template_suggestions().await);
+    });
+    let dismiss_suggestion = use_callback(move |template: GameTemplate| async move {
+        dismiss_template_suggestion(template).await;
+        suggestions.set(query_template_suggestions().await);
+    });
+
+    use_future(move || async move {
+        loop {
+            suggestions.set(query_template_suggestions().await);
+            sleep(SUGGESTIONS_POLL_INTERVAL).await;
+        }
+    });
+
+    rsx! {
+        if !suggestions().is_empty() {
+            Section { title: "Suggestions",
+                p { class: "text-xs text-secondary-text mb-2",
+                    "These templates repeatedly failed to match. The closest regions from the game are suggested as replacements."
+                }
+                div { class: "grid grid-cols-2 gap-4",
+                    for suggestion in suggestions() {
+                        TemplateSuggestionItem {
+                            suggestion,
+                            on_accept: move |suggestion: TemplateSuggestion| async move {
+                                accept_suggestion(suggestion).await;
+                            },
+                            on_dismiss: move |template: GameTemplate| async move {
+                                dismiss_suggestion(template).await;
+                            },
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn TemplateSuggestionItem(
+    suggestion: TemplateSuggestion,
+    on_accept: Callback<TemplateSuggestion>,
+    on_dismiss: Callback<GameTemplate>,
+) -> Element {
+    let template = suggestion.template;
+    let label = template.to_string();
+    let src = format!("data:image/png;base64,{}", suggestion.base64);
+
+    rsx! {
+        div { class: "flex gap-2",
+            div { class: "flex-grow",
+                Labeled { label,
+                    div { class: "h-6 border-b border-primary-border pb-0.5",
+                        img { src, class: "h-full" }
+                    }
+                }
+            }
+            div { class: "flex items-end",
+                Button {
+                    class: "w-14",
+                    style: ButtonStyle::Primary,
+                    on_click: move |_| {
+                        on_accept(suggestion.clone());
+                    },
+
+                    "Accept"
+                }
+            }
+            div { class: "flex items-end",
+                Button {
+                    class: "w-14",
+                    style: ButtonStyle::Secondary,
+                    on_click: move |_| {
+                        on_dismiss(template);
+                    },
+
+                    "Dismiss"
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn SectionPopups() -> Element {
     let context = use_context::<LocalizationContext>();
@@ -372,6 +484,42 @@ fn SectionFamiliars() -> Element {
     }
 }
 
+#[component]
+fn SectionAnnouncements() -> Element {
+    let context = use_context::<LocalizationContext>();
+    let localization = context.localization;
+    let save_localization = context.save_localization;
+
+    rsx! {
+        Section { title: "Announcements",
+            div { class: "grid grid-cols-2 gap-4",
+                LocalizationTemplateInput {
+                    label: "World boss banner",
+                    tooltip: "This template is in grayscale and has no default.",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            announcement_world_boss_base64: to_base64(image, true).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().announcement_world_boss_base64,
+                }
+                LocalizationTemplateInput {
+                    label: "Invasion banner",
+                    tooltip: "This template is in grayscale and has no default.",
+                    on_value: move |image: Option<Vec<u8>>| async move {
+                        save_localization(Localization {
+                            announcement_invasion_base64: to_base64(image, true).await,
+                            ..localization()
+                        });
+                    },
+                    value: localization().announcement_invasion_base64,
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn SectionOthers() -> Element {
     let context = use_context::<LocalizationContext>();
@@ -424,7 +572,7 @@ fn SectionOthers() -> Element {
 #[component]
 fn LocalizationTemplateInput(
     label: &'static str,
-    template: GameTemplate,
+    #[props(default)] template: Option<GameTemplate>,
     #[props(default)] tooltip: Option<String>,
     on_value: Callback<Option<Vec<u8>>>,
     value: ReadSignal<Option<String>>,
@@ -437,10 +585,12 @@ fn LocalizationTemplateInput(
     use_effect(move || {
         if let Some(value) = value() {
             base64.set(value);
-        } else {
+        } else if let Some(template) = template {
             spawn(async move {
                 base64.set(query_template(template).await);
             });
+        } else {
+            base64.set(String::default());
         }
     });
 
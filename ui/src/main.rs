@@ -3,11 +3,15 @@
 #![feature(map_try_insert)]
 #![feature(iter_intersperse)]
 
-use std::{env::current_exe, io::stdout, string::ToString, sync::LazyLock};
+use std::{env::current_exe, io::stdout, string::ToString, sync::LazyLock, time::Duration};
 
 use actions::ActionsScreen;
-use backend::{Character, Localization, Map, Settings};
+use backend::{
+    BackendError, Character, Localization, Map, Settings, error_receiver, game_state_receiver,
+    query_characters, take_undelivered_errors,
+};
 use characters::CharactersScreen;
+use components::toast::Toast;
 #[cfg(debug_assertions)]
 use debug::DebugScreen;
 use dioxus::{
@@ -24,6 +28,7 @@ use minimap::MinimapScreen;
 use navigation::NavigationScreen;
 use rand::distr::{Alphanumeric, SampleString};
 use settings::SettingsScreen;
+use tokio::{sync::broadcast::error::RecvError, time::sleep};
 
 use crate::localization::LocalizationScreen;
 
@@ -46,6 +51,7 @@ const TAB_SETTINGS: &str = "Settings";
 const TAB_LOCALIZATION: &str = "Localization";
 #[cfg(debug_assertions)]
 const TAB_DEBUG: &str = "Debug";
+const TOAST_DURATION: Duration = Duration::from_secs(10);
 
 static TABS: LazyLock<Vec<String>> = LazyLock::new(|| {
     vec![
@@ -102,6 +108,8 @@ pub struct AppState {
     settings: Signal<Option<Settings>>,
     localization: Signal<Option<Localization>>,
     position: Signal<(i32, i32)>,
+    /// Whether the next click on the minimap samples the custom player marker.
+    player_marker_sampling: Signal<bool>,
 }
 
 #[component]
@@ -109,13 +117,48 @@ fn App() -> Element {
     let mut selected_tab = use_signal(|| TAB_CHARACTERS.to_string());
     let mut script_loaded = use_signal(|| false);
 
-    use_context_provider(|| AppState {
+    let app_state = use_context_provider(|| AppState {
         map: Signal::new(None),
         map_preset: Signal::new(None),
         character: Signal::new(None),
         settings: Signal::new(None),
         localization: Signal::new(None),
         position: Signal::new((0, 0)),
+        player_marker_sampling: Signal::new(false),
+    });
+
+    // Releases held keys when the window is closed so that no key is left stuck after exit
+    use_drop(backend::shutdown);
+
+    // Follows the character automatically switched by the backend (e.g. through hotkeys)
+    use_future(move || async move {
+        let mut character = app_state.character;
+        let mut receiver = game_state_receiver().await;
+        let mut last_character_id = None;
+        loop {
+            let Ok(current_state) = receiver.recv().await else {
+                continue;
+            };
+            let character_id = current_state.character_id;
+            if character_id == last_character_id {
+                continue;
+            }
+            last_character_id = character_id;
+            if character_id.is_none()
+                || character.peek().as_ref().and_then(|character| character.id) == character_id
+            {
+                continue;
+            }
+
+            let switched = query_characters()
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .find(|character| character.id == character_id);
+            if let Some(switched) = switched {
+                character.set(Some(switched));
+            }
+        }
     });
 
     // Thanks dioxus
@@ -175,6 +218,53 @@ fn App() -> Element {
                     }
                 }
             }
+            ErrorToasts {}
+        }
+    }
+}
+
+#[component]
+fn ErrorToasts() -> Element {
+    let mut toasts = use_signal(Vec::<(usize, BackendError)>::new);
+    let mut next_id = use_signal(|| 0);
+
+    use_future(move || async move {
+        let mut push_toast = move |error: BackendError| {
+            let id = next_id();
+            next_id += 1;
+            toasts.write().push((id, error));
+            spawn(async move {
+                sleep(TOAST_DURATION).await;
+                toasts.write().retain(|(toast_id, _)| *toast_id != id);
+            });
+        };
+        let mut rx = error_receiver();
+        // Errors reported before subscribing such as a failed database migration on startup
+        for error in take_undelivered_errors() {
+            push_toast(error);
+        }
+        loop {
+            let error = match rx.recv().await {
+                Ok(value) => value,
+                Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(_)) => continue,
+            };
+            push_toast(error);
+        }
+    });
+
+    rsx! {
+        div { class: "fixed bottom-2 right-2 z-1000 flex flex-col gap-2",
+            for (id, error) in toasts() {
+                Toast {
+                    key: "{id}",
+                    title: error.to_string(),
+                    message: error.action().to_string(),
+                    on_close: move |_| {
+                        toasts.write().retain(|(toast_id, _)| *toast_id != id);
+                    },
+                }
+            }
         }
     }
 }
@@ -1,14 +1,21 @@
 use std::{
     ops::Deref,
+    rc::Rc,
     time::{Duration, Instant},
 };
 
 use backend::{
     Action, ActionKey, ActionMove, BotOperation, BotOperationUpdate, DatabaseEvent, Map, Position,
-    RotationMode, create_map, database_event_receiver, delete_map, game_state_receiver, query_maps,
-    redetect_minimap, update_map, update_operation, upsert_map,
+    RotationMode, create_map, database_event_receiver, delete_map, game_state_receiver,
+    move_player_to, preview_frame, query_maps, redetect_minimap, sample_player_marker, update_map,
+    update_operation, upsert_map, validate_map,
+};
+use dioxus::{
+    desktop::{use_asset_handler, wry::http::Response},
+    document::EvalError,
+    html::FileData,
+    prelude::*,
 };
-use dioxus::{document::EvalError, html::FileData, prelude::*};
 use futures_util::StreamExt;
 use serde::Serialize;
 use tokio::{sync::broadcast::error::RecvError, time::sleep};
@@ -33,13 +40,35 @@ const MINIMAP_JS: &str = r#"
     const canvasCtx = canvas.getContext("2d");
 
     while (true) {
-        const [buffer, width, height, destinations, bound, quadrant, portals] = await dioxus.recv();
-        const data = new ImageData(new Uint8ClampedArray(buffer), width, height);
+        const [generation, frameWidth, frameHeight, width, height, destinations, trail, bound, quadrant, portals] = await dioxus.recv();
+        // The frame pixels are fetched as raw bytes from the shared preview frame
+        const response = await fetch(`/preview?generation=${generation}`);
+        const buffer = await response.arrayBuffer();
+        if (buffer.byteLength !== frameWidth * frameHeight * 4) {
+            continue;
+        }
+        const data = new ImageData(new Uint8ClampedArray(buffer), frameWidth, frameHeight);
         const bitmap = await createImageBitmap(data);
 
+        canvasCtx.drawImage(bitmap, 0, 0, frameWidth, frameHeight, 0, 0, canvas.width, canvas.height);
+
+        // More visited positions are drawn more opaque as a heat trail
+        const trailSize = 2;
+        for (let i = 0; i < trail.length; i++) {
+            const [x, y, count] = trail[i];
+            const alpha = Math.min(0.2 + count * 0.1, 1);
+
+            canvasCtx.fillStyle = `rgba(255, 170, 60, ${alpha})`;
+            canvasCtx.fillRect(
+                (x / width) * canvas.width - trailSize / 2,
+                ((height - y) / height) * canvas.height - trailSize / 2,
+                trailSize,
+                trailSize,
+            );
+        }
+
         canvasCtx.fillStyle = "rgb(128, 255, 204)";
         canvasCtx.strokeStyle = "rgb(128, 255, 204)";
-        canvasCtx.drawImage(bitmap, 0, 0, width, height, 0, 0, canvas.width, canvas.height);
 
         const destinationSize = 4;
         const destinationSizeHalf = destinationSize / 2;
@@ -288,10 +317,13 @@ struct ActionView {
 struct MinimapState {
     position: Option<(i32, i32)>,
     health: Option<(u32, u32)>,
+    exp_rates: Option<(u64, u64)>,
+    exp_rate_dropped: bool,
     state: String,
     normal_action: Option<String>,
     priority_action: Option<String>,
     erda_shower_state: String,
+    stuck_pattern: Option<String>,
     operation: BotOperation,
     detected_size: Option<(usize, usize)>,
 }
@@ -384,6 +416,39 @@ pub fn MinimapScreen() -> Element {
             coroutine.send(MinimapUpdate::Set);
         }
     });
+    // Follows the map automatically switched by the backend
+    use_future(move || async move {
+        let mut receiver = game_state_receiver().await;
+        let mut last_map_id = None;
+        loop {
+            let Ok(current_state) = receiver.recv().await else {
+                continue;
+            };
+            let map_id = current_state.map_id;
+            if map_id == last_map_id {
+                continue;
+            }
+            last_map_id = map_id;
+            if map_id.is_none() || map.peek().as_ref().and_then(|map| map.id) == map_id {
+                continue;
+            }
+
+            let Some(switched) = maps
+                .peek()
+                .as_ref()
+                .and_then(|maps| maps.iter().find(|map| map.id == map_id).cloned())
+            else {
+                continue;
+            };
+            let preset = map_preset
+                .peek()
+                .clone()
+                .filter(|preset| switched.actions.contains_key(preset))
+                .or_else(|| switched.actions.keys().next().cloned());
+            map_preset.set(preset);
+            map.set(Some(switched));
+        }
+    });
     // External modification checking
     use_future(move || async move {
         let mut rx = database_event_receiver();
@@ -469,13 +534,18 @@ fn Canvas(
     map_preset: ReadSignal<Option<String>>,
     position: Signal<(i32, i32)>,
 ) -> Element {
+    let mut app_map = use_context::<AppState>().map;
+    let mut player_marker_sampling = use_context::<AppState>().player_marker_sampling;
+    let mut canvas_element = use_signal(|| None::<Rc<MountedData>>);
     let mut platforms_bound = use_signal(|| None);
     let rotation_bound_and_type = use_memo(move || {
         let platforms_bound = platforms_bound();
         let map = map()?;
 
         match map.rotation_mode {
-            RotationMode::StartToEnd | RotationMode::StartToEndThenReverse => None,
+            RotationMode::StartToEnd
+            | RotationMode::StartToEndThenReverse
+            | RotationMode::BehaviorTree => None,
             RotationMode::AutoMobbing => Some((
                 platforms_bound.unwrap_or(map.rotation_auto_mob_bound),
                 "AutoMobbing",
@@ -524,6 +594,13 @@ fn Canvas(
             ));
         });
     });
+    // Serve the latest preview frame pixels without encoding
+    use_asset_handler("preview", |_, responder| {
+        let bytes = preview_frame()
+            .map(|frame| frame.data.to_vec())
+            .unwrap_or_default();
+        responder.respond(Response::new(bytes));
+    });
     // Draw map and update game state
     use_future(move || async move {
         let mut canvas = document::eval(MINIMAP_JS);
@@ -533,6 +610,7 @@ fn Canvas(
                 continue;
             };
             let destinations = current_state.destinations;
+            let trail = current_state.position_trail;
             let bound = current_state.platforms_bound;
             let quadrant = current_state
                 .auto_mob_quadrant
@@ -542,12 +620,17 @@ fn Canvas(
             let current_state = MinimapState {
                 position: current_state.position,
                 health: current_state.health,
+                exp_rates: current_state.exp_rates,
+                exp_rate_dropped: current_state.exp_rate_dropped,
                 state: current_state.state,
                 normal_action: current_state.normal_action,
                 priority_action: current_state.priority_action,
                 erda_shower_state: current_state.erda_shower_state,
+                stuck_pattern: current_state.stuck_pattern,
                 operation: current_state.operation,
-                detected_size: frame.as_ref().map(|(_, width, height)| (*width, *height)),
+                detected_size: frame
+                    .as_ref()
+                    .map(|frame| (frame.source_width, frame.source_height)),
             };
 
             if *platforms_bound.peek() != bound {
@@ -563,12 +646,21 @@ fn Canvas(
                 .peek()
                 .deref()
                 .map(|(bound, _)| bound);
-            let Some((frame, width, height)) = frame else {
+            let Some(frame) = frame else {
                 continue;
             };
-            let Err(error) =
-                canvas.send((frame, width, height, destinations, bound, quadrant, portals))
-            else {
+            let Err(error) = canvas.send((
+                frame.generation,
+                frame.width,
+                frame.height,
+                frame.source_width,
+                frame.source_height,
+                destinations,
+                trail,
+                bound,
+                quadrant,
+                portals,
+            )) else {
                 continue;
             };
             if matches!(error, EvalError::Finished) {
@@ -578,8 +670,53 @@ fn Canvas(
         }
     });
 
+    // Samples the custom player marker or moves the player to the clicked minimap position
+    let click_minimap = use_callback(move |e: Event<MouseData>| async move {
+        let Some((width, height)) = state.peek().as_ref().and_then(|state| state.detected_size)
+        else {
+            return;
+        };
+        let Some(element) = canvas_element.peek().clone() else {
+            return;
+        };
+        let Ok(rect) = element.get_client_rect().await else {
+            return;
+        };
+        let coordinates = e.element_coordinates();
+        let x = (coordinates.x / rect.width() * width as f64) as i32;
+        let y = height as i32 - (coordinates.y / rect.height() * height as f64) as i32;
+
+        if !*player_marker_sampling.peek() {
+            move_player_to(x, y).await;
+            return;
+        }
+        player_marker_sampling.set(false);
+        let Some(player_marker) = sample_player_marker(x, y).await else {
+            return;
+        };
+        let Some(current_map) = app_map.peek().clone() else {
+            return;
+        };
+        if let Some(current_map) = upsert_map(Map {
+            player_marker: Some(player_marker),
+            ..current_map
+        })
+        .await
+        {
+            app_map.set(Some(current_map));
+        }
+    });
+
     rsx! {
-        div { class: "relative h-31 xl:h-38 rounded-2xl bg-secondary-surface",
+        div {
+            class: "relative h-31 xl:h-38 rounded-2xl bg-secondary-surface",
+            class: if player_marker_sampling() { "cursor-crosshair" } else { "cursor-pointer" },
+            onmounted: move |e| {
+                canvas_element.set(Some(e.data()));
+            },
+            onclick: move |e| async move {
+                click_minimap(e).await;
+            },
             canvas {
                 class: "absolute inset-0 rounded-2xl w-full h-full",
                 id: "canvas-map",
@@ -598,10 +735,13 @@ fn Info(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>) -
     struct GameStateInfo {
         position: String,
         health: String,
+        exp_rate: String,
+        exp_rate_dropped: bool,
         state: String,
         normal_action: String,
         priority_action: String,
         erda_shower_state: String,
+        stuck_pattern: String,
         detected_map_size: String,
         selected_map_size: String,
         cycle_duration: String,
@@ -611,10 +751,13 @@ fn Info(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>) -
         let mut info = GameStateInfo {
             position: "Unknown".to_string(),
             health: "Unknown".to_string(),
+            exp_rate: "Unknown".to_string(),
+            exp_rate_dropped: false,
             state: "Unknown".to_string(),
             normal_action: "None".to_string(),
             priority_action: "None".to_string(),
             erda_shower_state: "Unknown".to_string(),
+            stuck_pattern: "None".to_string(),
             detected_map_size: "Unknown".to_string(),
             selected_map_size: "Unknown".to_string(),
             cycle_duration: "None".to_string(),
@@ -628,7 +771,9 @@ fn Info(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>) -
             info.state = state.state;
             info.erda_shower_state = state.erda_shower_state;
             info.cycle_duration = match state.operation {
-                BotOperation::Halting | BotOperation::Running => "None".to_string(),
+                BotOperation::Halting | BotOperation::Running | BotOperation::Stopping => {
+                    "None".to_string()
+                }
                 BotOperation::TemporaryHalting(duration) => duration_from(duration),
                 BotOperation::HaltUntil(instant) | BotOperation::RunUntil(instant) => {
                     duration_from(instant.saturating_duration_since(Instant::now()))
@@ -640,12 +785,19 @@ fn Info(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>) -
             if let Some((current, max)) = state.health {
                 info.health = format!("{current} / {max}");
             }
+            if let Some((recent, session)) = state.exp_rates {
+                info.exp_rate = format!("{recent} / {session}");
+            }
+            info.exp_rate_dropped = state.exp_rate_dropped;
             if let Some(action) = state.normal_action {
                 info.normal_action = action;
             }
             if let Some(action) = state.priority_action {
                 info.priority_action = action;
             }
+            if let Some(pattern) = state.stuck_pattern {
+                info.stuck_pattern = pattern;
+            }
             if let Some((width, height)) = state.detected_size {
                 info.detected_map_size = format!("{width}px x {height}px")
             }
@@ -659,13 +811,20 @@ fn Info(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>) -
             InfoItem { name: "State", value: info().state }
             InfoItem { name: "Position", value: info().position }
             InfoItem { name: "Health", value: info().health }
+            InfoItem { name: "EXP/h (recent / session)", value: info().exp_rate }
             InfoItem { name: "Priority action", value: info().priority_action }
             InfoItem { name: "Normal action", value: info().normal_action }
             InfoItem { name: "Erda Shower", value: info().erda_shower_state }
+            InfoItem { name: "Stuck pattern", value: info().stuck_pattern }
             InfoItem { name: "Detected size", value: info().detected_map_size }
             InfoItem { name: "Selected size", value: info().selected_map_size }
             InfoItem { name: "Run/stop cycle", value: info().cycle_duration }
         }
+        if info().exp_rate_dropped {
+            p { class: "px-4 pb-3 text-xs text-danger-text",
+                "EXP rate dropped below the session average"
+            }
+        }
     }
 }
 
@@ -684,19 +843,39 @@ fn Buttons(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>
             .map(|state| match state.operation {
                 BotOperation::Halting => BotOperationUpdate::Halt,
                 BotOperation::TemporaryHalting(_) => BotOperationUpdate::TemporaryHalt,
-                BotOperation::HaltUntil(_) | BotOperation::Running | BotOperation::RunUntil(_) => {
-                    BotOperationUpdate::Run
-                }
+                BotOperation::HaltUntil(_)
+                | BotOperation::Running
+                | BotOperation::RunUntil(_)
+                | BotOperation::Stopping => BotOperationUpdate::Run,
             })
             .unwrap_or(BotOperationUpdate::Halt)
     });
     let character = use_context::<AppState>().character;
+    let map_preset = use_context::<AppState>().map_preset;
+    let settings = use_context::<AppState>().settings;
+    let localization = use_context::<AppState>().localization;
     let disabled = use_memo(move || map().is_none() || character().is_none());
+    let warnings = use_resource(move || {
+        let args = map().zip(map_preset()).zip(settings()).zip(localization());
+        let character = character();
+        async move {
+            let Some((((map, preset), settings), localization)) = args else {
+                return vec![];
+            };
+            validate_map(map, preset, character, settings, localization)
+                .await
+                .into_iter()
+                .map(|warning| warning.to_string())
+                .collect::<Vec<_>>()
+        }
+    });
 
     let start_stop_text = use_memo(move || {
         if matches!(
             kind(),
-            BotOperationUpdate::Run | BotOperationUpdate::TemporaryHalt
+            BotOperationUpdate::Run
+                | BotOperationUpdate::TemporaryHalt
+                | BotOperationUpdate::SoftHalt
         ) {
             "Stop"
         } else {
@@ -710,7 +889,8 @@ fn Buttons(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>
                 BotOperation::Halting
                 | BotOperation::HaltUntil(_)
                 | BotOperation::Running
-                | BotOperation::RunUntil(_) => "Suspend",
+                | BotOperation::RunUntil(_)
+                | BotOperation::Stopping => "Suspend",
             })
             .unwrap_or("Suspend")
     });
@@ -727,6 +907,19 @@ fn Buttons(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>
             })
             .unwrap_or_default()
     });
+    let finish_disabled = use_memo(move || {
+        if disabled() {
+            return true;
+        }
+        state()
+            .map(|state| {
+                !matches!(
+                    state.operation,
+                    BotOperation::Running | BotOperation::RunUntil(_)
+                )
+            })
+            .unwrap_or(true)
+    });
 
     rsx! {
         div { class: "flex h-10 justify-center items-center gap-4",
@@ -737,9 +930,9 @@ fn Buttons(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>
                 on_click: move || async move {
                     let kind = match *kind.peek() {
                         BotOperationUpdate::Halt => BotOperationUpdate::Run,
-                        BotOperationUpdate::TemporaryHalt | BotOperationUpdate::Run => {
-                            BotOperationUpdate::Halt
-                        }
+                        BotOperationUpdate::TemporaryHalt
+                        | BotOperationUpdate::SoftHalt
+                        | BotOperationUpdate::Run => BotOperationUpdate::Halt,
                     };
                     update_operation(kind).await;
                 },
@@ -752,14 +945,23 @@ fn Buttons(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>
                 on_click: move || async move {
                     let kind = match *kind.peek() {
                         BotOperationUpdate::Run => BotOperationUpdate::TemporaryHalt,
-                        BotOperationUpdate::TemporaryHalt | BotOperationUpdate::Halt => {
-                            BotOperationUpdate::Run
-                        }
+                        BotOperationUpdate::TemporaryHalt
+                        | BotOperationUpdate::SoftHalt
+                        | BotOperationUpdate::Halt => BotOperationUpdate::Run,
                     };
                     update_operation(kind).await;
                 },
                 {suspend_resume_text()}
             }
+            Button {
+                class: "w-20",
+                style: ButtonStyle::Primary,
+                disabled: finish_disabled(),
+                on_click: move |_| async move {
+                    update_operation(BotOperationUpdate::SoftHalt).await;
+                },
+                "Finish"
+            }
             Button {
                 class: "w-20",
                 style: ButtonStyle::Primary,
@@ -769,6 +971,11 @@ fn Buttons(state: ReadSignal<Option<MinimapState>>, map: ReadSignal<Option<Map>>
                 "Re-detect"
             }
         }
+        if matches!(kind(), BotOperationUpdate::Halt) {
+            for warning in warnings().unwrap_or_default() {
+                p { class: "px-4 pb-1 text-xs text-danger-text", {warning} }
+            }
+        }
     }
 }
 
@@ -1,8 +1,8 @@
 use std::fmt::Display;
 
 use backend::{
-    DatabaseEvent, NavigationPath, NavigationPaths, NavigationPoint, NavigationTransition,
-    create_navigation_path, database_event_receiver, delete_navigation_paths,
+    DatabaseEvent, IntoEnumIterator, NavigationPath, NavigationPaths, NavigationPoint,
+    NavigationTransition, create_navigation_path, database_event_receiver, delete_navigation_paths,
     navigation_snapshot_as_grayscale, query_navigation_paths, recapture_navigation_path,
     upsert_map, upsert_navigation_paths,
 };
@@ -18,6 +18,7 @@ use crate::{
         icons::{DetailsIcon, XIcon},
         labeled::Labeled,
         named_select::NamedSelect,
+        numbers::MillisInput,
         popup::{PopupContent, PopupContext, PopupTrigger},
         position::PositionInput,
         section::Section,
@@ -420,6 +421,7 @@ fn SectionPaths() -> Element {
                                                 x: position.peek().0,
                                                 y: position.peek().1,
                                                 transition: NavigationTransition::Portal,
+                                                transition_window_millis: 0,
                                             },
                                         });
                                 },
@@ -631,6 +633,28 @@ fn PopupPointContent(
                     },
                     value: xy().y,
                 }
+                Labeled { label: "Transition",
+                    NavigationSelect::<NavigationTransition> {
+                        options: NavigationTransition::iter().collect::<Vec<_>>(),
+                        on_selected: move |index| {
+                            if let Some(transition) = NavigationTransition::iter().nth(index) {
+                                xy.write().transition = transition;
+                            }
+                        },
+                        selected: NavigationTransition::iter()
+                            .position(|transition| transition == xy().transition)
+                            .unwrap_or_default(),
+                    }
+                }
+                Labeled { label: "Longest closed time",
+                    MillisInput {
+                        disabled: !matches!(xy().transition, NavigationTransition::TimedPortal),
+                        on_value: move |millis| {
+                            xy.write().transition_window_millis = millis;
+                        },
+                        value: xy().transition_window_millis,
+                    }
+                }
             }
 
             div { class: "flex w-full gap-3 absolute bottom-0 py-2 bg-secondary-surface",
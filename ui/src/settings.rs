@@ -1,9 +1,24 @@
-use std::{fmt::Display, mem};
+use std::{
+    fmt::Display,
+    mem,
+    time::{Duration, UNIX_EPOCH},
+};
 
 use backend::{
-    CaptureMode, CycleRunStopMode, InputMethod, IntoEnumIterator, KeyBinding,
-    KeyBindingConfiguration, Notifications, Settings, query_capture_handles, query_settings,
-    refresh_capture_handles, select_capture_handle, upsert_settings,
+    AnnouncementReaction, AnnouncementReactions, CaptureMode, ConditionWebhook,
+    ConditionWebhookName, ConfigSync, CycleRunStopMode, EventLogFormat, ExpRateAlert, FocusPolicy,
+    GamepadButton, GamepadMapping, GridLayout, Hotkey, HotkeyKind, InputMethod, IntoEnumIterator,
+    KeyBinding, KeyBindingConfiguration, KeyPressClass, KeyPressDuration, KeyboardLayout,
+    LegacyImportReport, Localization, MAX_TICK_RATE, MIN_TICK_RATE, MapFailover, MetricsExport,
+    Notifications, OtherPlayerReaction, PauseOnInput, RemoteRuneSolving, ReplayStatus,
+    ResetRoutine, RiskyActionLimit, RiskyActionLimits, Schedule, ScheduleKind, ScheduleTime,
+    SessionLimits, SessionTimelapse, Settings, StateMachineFormat, SyncDirection, SyncStatus,
+    UnstuckLimits, backup_database, delete_localization, export_event_log,
+    export_player_state_machine, import_legacy_records, query_capabilities, query_capture_handles,
+    query_characters, query_database_backups, query_input_audits, query_localizations, query_maps,
+    query_replay_status, query_settings, refresh_capture_handles, restore_database_backup,
+    run_grid_layout, select_capture_handle, start_recording_replay, start_replaying,
+    stop_grid_layout, stop_replay, sync_database, upsert_localization, upsert_settings,
 };
 use dioxus::{html::FileData, prelude::*};
 use futures_util::StreamExt;
@@ -17,7 +32,8 @@ use crate::{
         icons::{EyePasswordHideIcon, EyePasswordShowIcon},
         key::KeyInput,
         labeled::Labeled,
-        numbers::MillisInput,
+        named_select::NamedSelect,
+        numbers::{MillisInput, PrimitiveIntegerInput},
         section::Section,
         select::{Select, SelectOption},
         text::TextInput,
@@ -72,10 +88,38 @@ pub fn SettingsScreen() -> Element {
         div { class: "flex flex-col h-full overflow-y-auto",
             SectionCapture {}
             SectionInput {}
+            if matches!(settings_view().input_method, InputMethod::Gamepad) {
+                SectionGamepadMappings {}
+            }
+            SectionKeyPressDurations {}
             SectionControlAndNotifications {}
             SectionHotkeys {}
+            SectionGlobalHotkeys {}
             SectionRunStopCycle {}
+            SectionPauseOnInput {}
+            SectionSchedules {}
+            SectionResetRoutine {}
+            SectionMapFailover {}
+            SectionSessionLimits {}
+            SectionUnstuckLimits {}
+            SectionRiskyActionLimits {}
+            SectionExpRateAlert {}
+            SectionAnnouncements {}
+            SectionRemoteRuneSolving {}
+            SectionMetricsExport {}
+            SectionConditionWebhooks {}
             SectionOthers {}
+            SectionLocalization {}
+            SectionGridLayout {}
+            SectionInputAudit {}
+            SectionEventLog {}
+            SectionSessionTimelapse {}
+            SectionStateMachine {}
+            SectionReplay {}
+            SectionConfigSync {}
+            SectionDatabaseBackups {}
+            SectionLegacyImport {}
+            SectionCapabilities {}
         }
     }
 }
@@ -86,6 +130,7 @@ fn SectionCapture() -> Element {
     let settings = context.settings;
     let save_settings = context.save_settings;
 
+    let mut app_settings = use_context::<AppState>().settings;
     let mut selected_handle_index = use_signal(|| None);
     let mut handle_names = use_resource(move || async move {
         let (names, selected) = query_capture_handles().await;
@@ -113,6 +158,8 @@ fn SectionCapture() -> Element {
                             selected_handle_index.set(Some(index - 1));
                             select_capture_handle(Some(index - 1)).await;
                         }
+                        // The selected handle is saved to settings by the backend
+                        app_settings.set(Some(query_settings().await));
                     },
                     selected: selected_handle_index().map(|index| index + 1).unwrap_or_default(),
                 }
@@ -171,6 +218,205 @@ fn SectionInput() -> Element {
                     },
                     value: settings().input_method_rpc_server_url,
                 }
+                SettingsEnumSelect::<FocusPolicy> {
+                    label: "Focus policy",
+                    on_selected: move |focus_policy| {
+                        save_settings(Settings {
+                            focus_policy,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    selected: settings().focus_policy,
+                }
+                SettingsMillisInput {
+                    label: "Focus grace period",
+                    disabled: matches!(settings().focus_policy, FocusPolicy::None),
+                    on_value: move |focus_grace_period_millis| {
+                        save_settings(Settings {
+                            focus_grace_period_millis,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().focus_grace_period_millis,
+                }
+                SettingsEnumSelect::<KeyboardLayout> {
+                    label: "Keyboard layout",
+                    on_selected: move |keyboard_layout| {
+                        save_settings(Settings {
+                            keyboard_layout,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    selected: settings().keyboard_layout,
+                }
+                SettingsCheckbox {
+                    label: "Paste chat messages",
+                    on_checked: move |chat_clipboard_paste| {
+                        save_settings(Settings {
+                            chat_clipboard_paste,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().chat_clipboard_paste,
+                }
+                SettingsCheckbox {
+                    label: "Input audit",
+                    on_checked: move |input_audit| {
+                        save_settings(Settings {
+                            input_audit,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().input_audit,
+                }
+                SettingsCheckbox {
+                    label: "Observer mode",
+                    on_checked: move |observer_mode| {
+                        save_settings(Settings {
+                            observer_mode,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().observer_mode,
+                }
+            }
+            if settings().observer_mode {
+                p { class: "mt-2 text-xs text-primary-text",
+                    "Observer mode is on. Detection runs as usual but no key or mouse input is sent."
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionGamepadMappings() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+
+    let add_mapping = use_callback(move |_| {
+        let mut settings = settings.peek().clone();
+
+        settings.gamepad_mappings.push(GamepadMapping::default());
+        save_settings(settings);
+    });
+    let edit_mapping = use_callback(move |(new_mapping, index): (GamepadMapping, usize)| {
+        let mut settings = settings.peek().clone();
+        let Some(mapping) = settings.gamepad_mappings.get_mut(index) else {
+            return;
+        };
+
+        *mapping = new_mapping;
+        save_settings(settings);
+    });
+    let delete_mapping = use_callback(move |index| {
+        let mut settings = settings.peek().clone();
+
+        settings.gamepad_mappings.remove(index);
+        save_settings(settings);
+    });
+
+    rsx! {
+        Section { title: "Gamepad mappings",
+            p { class: "text-xxs text-secondary-text mb-3",
+                "Requires ViGEmBus driver. Keys without a mapping are not sent and mouse inputs are ignored."
+            }
+            for (index , mapping) in settings().gamepad_mappings.into_iter().enumerate() {
+                div { class: "grid grid-cols-3 gap-3 mb-3",
+                    SettingsKeyInput {
+                        label: "Key",
+                        class: "",
+                        on_value: move |key| {
+                            edit_mapping((GamepadMapping { key, ..mapping }, index));
+                        },
+                        value: mapping.key,
+                    }
+                    SettingsEnumSelect::<GamepadButton> {
+                        label: "Button",
+                        on_selected: move |button| {
+                            edit_mapping((GamepadMapping { button, ..mapping }, index));
+                        },
+                        selected: mapping.button,
+                    }
+                    div { class: "flex items-end",
+                        Button {
+                            class: "w-full mb-[1px]",
+                            style: ButtonStyle::Secondary,
+                            on_click: move |_| {
+                                delete_mapping(index);
+                            },
+
+                            "Remove"
+                        }
+                    }
+                }
+            }
+            Button {
+                class: "w-full",
+                style: ButtonStyle::Secondary,
+                on_click: move |_| {
+                    add_mapping(());
+                },
+
+                "Add mapping"
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionKeyPressDurations() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let durations = use_memo(move || settings().key_press_durations);
+
+    let save_duration = move |class: KeyPressClass, duration: KeyPressDuration| {
+        let mut key_press_durations = *durations.peek();
+        *key_press_durations.get_mut(class) = duration;
+        save_settings(Settings {
+            key_press_durations,
+            ..settings.peek().clone()
+        });
+    };
+
+    rsx! {
+        Section { title: "Key press durations",
+            div { class: "grid grid-cols-2 gap-3",
+                for class in KeyPressClass::iter() {
+                    Labeled { label: format!("{class} key mean"),
+                        MillisInput {
+                            on_value: move |mean_millis| {
+                                save_duration(
+                                    class,
+                                    KeyPressDuration {
+                                        mean_millis,
+                                        ..durations.peek().get(class)
+                                    },
+                                );
+                            },
+                            value: durations().get(class).mean_millis,
+                        }
+                    }
+                    Labeled { label: format!("{class} key standard deviation"),
+                        MillisInput {
+                            on_value: move |std_millis| {
+                                save_duration(
+                                    class,
+                                    KeyPressDuration {
+                                        std_millis,
+                                        ..durations.peek().get(class)
+                                    },
+                                );
+                            },
+                            value: durations().get(class).std_millis,
+                        }
+                    }
+                }
+            }
+            p { class: "mt-2 text-xxs text-secondary-text",
+                "How long a key is held down for each press is drawn within one standard deviation of the mean."
             }
         }
     }
@@ -346,6 +592,45 @@ fn SectionControlAndNotifications() -> Element {
                     },
                     checked: notifications().notify_on_cycle_run_stop,
                 }
+                SettingsCheckbox {
+                    label: "Session limit reached",
+                    on_checked: move |notify_on_session_limit| {
+                        save_settings(Settings {
+                            notifications: Notifications {
+                                notify_on_session_limit,
+                                ..notifications.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: notifications().notify_on_session_limit,
+                }
+                SettingsCheckbox {
+                    label: "Player stuck",
+                    on_checked: move |notify_on_player_stuck| {
+                        save_settings(Settings {
+                            notifications: Notifications {
+                                notify_on_player_stuck,
+                                ..notifications.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: notifications().notify_on_player_stuck,
+                }
+                SettingsCheckbox {
+                    label: "EXP rate dropped",
+                    on_checked: move |notify_on_exp_rate_drop| {
+                        save_settings(Settings {
+                            notifications: Notifications {
+                                notify_on_exp_rate_drop,
+                                ..notifications.peek().clone()
+                            },
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: notifications().notify_on_exp_rate_drop,
+                }
             }
         }
     }
@@ -433,6 +718,149 @@ fn SectionHotkeys() -> Element {
                     },
                     value: settings().platform_end_key,
                 }
+                Hotkey {
+                    label: "Panic stop (press twice)",
+                    on_value: move |panic_key| {
+                        save_settings(Settings {
+                            panic_key,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().panic_key,
+                }
+                SettingsCheckbox {
+                    label: "Go to town on panic stop",
+                    on_checked: move |panic_go_to_town| {
+                        save_settings(Settings {
+                            panic_go_to_town,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().panic_go_to_town,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionGlobalHotkeys() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let characters = use_resource(async || query_characters().await.unwrap_or_default());
+    let character_names = use_memo(move || {
+        characters()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|character| character.name)
+            .collect::<Vec<_>>()
+    });
+
+    let add_hotkey = use_callback(move |_| {
+        let mut settings = settings.peek().clone();
+
+        settings.hotkeys.push(Hotkey {
+            enabled: true,
+            character_id: characters
+                .peek()
+                .as_ref()
+                .and_then(|characters| characters.first())
+                .and_then(|character| character.id),
+            ..Hotkey::default()
+        });
+        save_settings(settings);
+    });
+    let edit_hotkey = use_callback(move |(new_hotkey, index): (Hotkey, usize)| {
+        let mut settings = settings.peek().clone();
+        let Some(hotkey) = settings.hotkeys.get_mut(index) else {
+            return;
+        };
+
+        *hotkey = new_hotkey;
+        save_settings(settings);
+    });
+    let delete_hotkey = use_callback(move |index| {
+        let mut settings = settings.peek().clone();
+
+        settings.hotkeys.remove(index);
+        save_settings(settings);
+    });
+
+    rsx! {
+        Section { title: "Global hotkeys",
+            p { class: "text-xxs text-secondary-text mb-3",
+                "Global hotkeys work even when the game or this application is not focused."
+            }
+            for (index , hotkey) in settings().hotkeys.into_iter().enumerate() {
+                div { class: "grid grid-cols-3 gap-3 mb-3",
+                    SettingsEnumSelect::<HotkeyKind> {
+                        label: "Action",
+                        on_selected: move |kind| {
+                            edit_hotkey((Hotkey { kind, ..hotkey }, index));
+                        },
+                        selected: hotkey.kind,
+                    }
+                    SettingsKeyInput {
+                        label: "Key",
+                        class: "",
+                        on_value: move |key| {
+                            edit_hotkey((Hotkey { key, ..hotkey }, index));
+                        },
+                        value: hotkey.key,
+                    }
+                    if matches!(hotkey.kind, HotkeyKind::SwitchCharacter) {
+                        SettingsSelect::<String> {
+                            label: "Character",
+                            options: character_names(),
+                            on_selected: move |character_index: usize| {
+                                let character_id = characters
+                                    .peek()
+                                    .as_ref()
+                                    .and_then(|characters| characters.get(character_index))
+                                    .and_then(|character| character.id);
+                                edit_hotkey((Hotkey { character_id, ..hotkey }, index));
+                            },
+                            selected: characters()
+                                .unwrap_or_default()
+                                .into_iter()
+                                .position(|character| {
+                                    character.id.is_some() && character.id == hotkey.character_id
+                                })
+                                .unwrap_or_default(),
+                        }
+                    } else {
+                        div {}
+                    }
+                    SettingsCheckbox {
+                        label: "Enabled",
+                        on_checked: move |enabled| {
+                            edit_hotkey((Hotkey { enabled, ..hotkey }, index));
+                        },
+                        checked: hotkey.enabled,
+                    }
+                    div {}
+                    div { class: "flex items-end",
+                        Button {
+                            class: "w-full mb-[1px]",
+                            style: ButtonStyle::Secondary,
+                            on_click: move |_| {
+                                delete_hotkey(index);
+                            },
+
+                            "Remove"
+                        }
+                    }
+                }
+            }
+            Button {
+                class: "w-full",
+                style: ButtonStyle::Secondary,
+                on_click: move |_| {
+                    add_hotkey(());
+                },
+
+                "Add global hotkey"
             }
         }
     }
@@ -483,77 +911,1235 @@ fn SectionRunStopCycle() -> Element {
 }
 
 #[component]
-fn SectionOthers() -> Element {
+fn SectionPauseOnInput() -> Element {
     let context = use_context::<SettingsContext>();
     let settings = context.settings;
     let save_settings = context.save_settings;
+    let pause_on_input = use_memo(move || settings().pause_on_input);
 
-    let import_settings = use_callback(move |file: FileData| async move {
-        let Some(id) = settings.peek().id else {
-            return;
-        };
-        let Ok(bytes) = file.read_bytes().await else {
-            return;
-        };
-        let Ok(mut settings) = serde_json::from_slice::<'_, Settings>(&bytes) else {
-            return;
-        };
-        settings.id = Some(id);
-        save_settings(settings);
-    });
+    let save_pause_on_input = move |pause_on_input: PauseOnInput| {
+        save_settings(Settings {
+            pause_on_input,
+            ..settings.peek().clone()
+        });
+    };
 
     rsx! {
-        Section { title: "Others",
+        Section { title: "Pause on input",
             div { class: "grid grid-cols-2 gap-3",
                 SettingsCheckbox {
-                    label: "Enable rune solving",
-                    on_checked: move |enable_rune_solving| {
-                        save_settings(Settings {
-                            enable_rune_solving,
-                            ..settings.peek().clone()
+                    label: "Enabled",
+                    on_checked: move |enabled| {
+                        save_pause_on_input(PauseOnInput {
+                            enabled,
+                            ..*pause_on_input.peek()
                         });
                     },
-                    checked: settings().enable_rune_solving,
+                    checked: pause_on_input().enabled,
                 }
-                SettingsCheckbox {
-                    label: "Enable transparent shape solving",
-                    on_checked: move |enable_transparent_shape_solving| {
-                        save_settings(Settings {
-                            enable_transparent_shape_solving,
-                            ..settings.peek().clone()
+                SettingsMillisInput {
+                    label: "Resume after",
+                    disabled: !pause_on_input().enabled,
+                    on_value: move |grace_millis| {
+                        save_pause_on_input(PauseOnInput {
+                            grace_millis,
+                            ..*pause_on_input.peek()
                         });
                     },
-                    checked: settings().enable_transparent_shape_solving,
+                    value: pause_on_input().grace_millis,
                 }
+            }
+            p { class: "mt-2 text-xxs text-secondary-text",
+                "Pauses while you use the keyboard or mouse in the game. Mouse is only detected on Windows and this does nothing with RPC input method."
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionSessionLimits() -> Element {
+    #[component]
+    fn CountInput(
+        label: &'static str,
+        disabled: bool,
+        on_value: Callback<u32>,
+        value: u32,
+    ) -> Element {
+        rsx! {
+            Labeled { label,
+                PrimitiveIntegerInput { disabled, on_value, value }
+            }
+        }
+    }
+
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let limits = use_memo(move || settings().session_limits);
+    let disabled = use_memo(move || !limits().enabled);
+
+    let save_limits = move |session_limits: SessionLimits| {
+        save_settings(Settings {
+            session_limits,
+            ..settings.peek().clone()
+        });
+    };
+
+    rsx! {
+        Section { title: "Session limits",
+            div { class: "grid grid-cols-3 gap-3",
                 SettingsCheckbox {
-                    label: "Enable panic mode",
-                    on_checked: move |enable_panic_mode| {
-                        save_settings(Settings {
-                            enable_panic_mode,
-                            ..settings.peek().clone()
+                    label: "Enabled",
+                    on_checked: move |enabled| {
+                        save_limits(SessionLimits {
+                            enabled,
+                            ..*limits.peek()
                         });
                     },
-                    checked: settings().enable_panic_mode,
+                    checked: limits().enabled,
                 }
                 SettingsCheckbox {
-                    label: "Stop actions on fail or map changed",
-                    on_checked: move |stop_on_fail_or_change_map| {
-                        save_settings(Settings {
-                            stop_on_fail_or_change_map,
-                            ..settings.peek().clone()
+                    label: "Go to town on limit",
+                    disabled: disabled(),
+                    on_checked: move |go_to_town| {
+                        save_limits(SessionLimits {
+                            go_to_town,
+                            ..*limits.peek()
                         });
                     },
-                    checked: settings().stop_on_fail_or_change_map,
+                    checked: limits().go_to_town,
                 }
-                SettingsCheckbox {
-                    label: "Stop actions on player dies",
-                    on_checked: move |stop_on_player_die| {
-                        save_settings(Settings {
-                            stop_on_player_die,
-                            ..settings.peek().clone()
+                div {}
+                SettingsMillisInput {
+                    label: "Max duration (0 = no limit)",
+                    disabled: disabled(),
+                    on_value: move |max_duration_millis| {
+                        save_limits(SessionLimits {
+                            max_duration_millis,
+                            ..*limits.peek()
                         });
                     },
-                    checked: settings().stop_on_player_die,
+                    value: limits().max_duration_millis,
+                }
+                CountInput {
+                    label: "Max runes solved (0 = no limit)",
+                    disabled: disabled(),
+                    on_value: move |max_runes_solved| {
+                        save_limits(SessionLimits {
+                            max_runes_solved,
+                            ..*limits.peek()
+                        });
+                    },
+                    value: limits().max_runes_solved,
+                }
+                CountInput {
+                    label: "Max deaths (0 = no limit)",
+                    disabled: disabled(),
+                    on_value: move |max_deaths| {
+                        save_limits(SessionLimits {
+                            max_deaths,
+                            ..*limits.peek()
+                        });
+                    },
+                    value: limits().max_deaths,
+                }
+                CountInput {
+                    label: "Max channel changes (0 = no limit)",
+                    disabled: disabled(),
+                    on_value: move |max_channel_changes| {
+                        save_limits(SessionLimits {
+                            max_channel_changes,
+                            ..*limits.peek()
+                        });
+                    },
+                    value: limits().max_channel_changes,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionUnstuckLimits() -> Element {
+    #[component]
+    fn AttemptsInput(label: &'static str, on_value: Callback<u32>, value: u32) -> Element {
+        rsx! {
+            Labeled { label,
+                PrimitiveIntegerInput { on_value, value }
+            }
+        }
+    }
+
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let limits = use_memo(move || settings().unstuck_limits);
+
+    let save_limits = move |unstuck_limits: UnstuckLimits| {
+        save_settings(Settings {
+            unstuck_limits,
+            ..settings.peek().clone()
+        });
+    };
+
+    rsx! {
+        Section { title: "Unstuck attempts",
+            div { class: "grid grid-cols-2 gap-3",
+                AttemptsInput {
+                    label: "Reposition (0 = skip)",
+                    on_value: move |reposition_attempts| {
+                        save_limits(UnstuckLimits {
+                            reposition_attempts,
+                            ..*limits.peek()
+                        });
+                    },
+                    value: limits().reposition_attempts,
+                }
+                AttemptsInput {
+                    label: "Press ESC (0 = skip)",
+                    on_value: move |esc_attempts| {
+                        save_limits(UnstuckLimits {
+                            esc_attempts,
+                            ..*limits.peek()
+                        });
+                    },
+                    value: limits().esc_attempts,
+                }
+                AttemptsInput {
+                    label: "Random jumps (0 = skip)",
+                    on_value: move |random_jump_attempts| {
+                        save_limits(UnstuckLimits {
+                            random_jump_attempts,
+                            ..*limits.peek()
+                        });
+                    },
+                    value: limits().random_jump_attempts,
+                }
+                AttemptsInput {
+                    label: "Return to town (0 = skip)",
+                    on_value: move |return_to_town_attempts| {
+                        save_limits(UnstuckLimits {
+                            return_to_town_attempts,
+                            ..*limits.peek()
+                        });
+                    },
+                    value: limits().return_to_town_attempts,
+                }
+                SettingsCheckbox {
+                    label: "Unstuck on stuck patterns",
+                    on_checked: move |detect_stuck_patterns| {
+                        save_limits(UnstuckLimits {
+                            detect_stuck_patterns,
+                            ..*limits.peek()
+                        });
+                    },
+                    checked: limits().detect_stuck_patterns,
+                }
+            }
+            p { class: "mt-2 text-xxs text-secondary-text",
+                "The bot stops once all attempts are used without getting unstuck."
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionRiskyActionLimits() -> Element {
+    #[component]
+    fn LimitInputs(
+        label: &'static str,
+        disabled: bool,
+        on_limit: Callback<RiskyActionLimit>,
+        limit: RiskyActionLimit,
+    ) -> Element {
+        rsx! {
+            SettingsMillisInput {
+                label,
+                disabled,
+                on_value: move |min_interval_millis| {
+                    on_limit(RiskyActionLimit {
+                        min_interval_millis,
+                        ..limit
+                    });
+                },
+                value: limit.min_interval_millis,
+            }
+            SettingsMillisInput {
+                label: "Random extra interval",
+                disabled,
+                on_value: move |interval_jitter_millis| {
+                    on_limit(RiskyActionLimit {
+                        interval_jitter_millis,
+                        ..limit
+                    });
+                },
+                value: limit.interval_jitter_millis,
+            }
+            Labeled { label: "Per day (0 = no limit)",
+                PrimitiveIntegerInput {
+                    disabled,
+                    on_value: move |max_per_day| {
+                        on_limit(RiskyActionLimit {
+                            max_per_day,
+                            ..limit
+                        });
+                    },
+                    value: limit.max_per_day,
+                }
+            }
+        }
+    }
+
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let limits = use_memo(move || settings().risky_action_limits);
+    let disabled = use_memo(move || !limits().enabled);
+
+    let save_limits = move |risky_action_limits: RiskyActionLimits| {
+        save_settings(Settings {
+            risky_action_limits,
+            ..settings.peek().clone()
+        });
+    };
+
+    rsx! {
+        Section { title: "Risky action limits",
+            div { class: "grid grid-cols-3 gap-3",
+                SettingsCheckbox {
+                    label: "Enabled",
+                    on_checked: move |enabled| {
+                        save_limits(RiskyActionLimits {
+                            enabled,
+                            ..*limits.peek()
+                        });
+                    },
+                    checked: limits().enabled,
+                }
+                div {}
+                div {}
+                LimitInputs {
+                    label: "Change channel interval",
+                    disabled: disabled(),
+                    on_limit: move |change_channel| {
+                        save_limits(RiskyActionLimits {
+                            change_channel,
+                            ..*limits.peek()
+                        });
+                    },
+                    limit: limits().change_channel,
+                }
+                LimitInputs {
+                    label: "Go to town interval",
+                    disabled: disabled(),
+                    on_limit: move |go_to_town| {
+                        save_limits(RiskyActionLimits {
+                            go_to_town,
+                            ..*limits.peek()
+                        });
+                    },
+                    limit: limits().go_to_town,
+                }
+                LimitInputs {
+                    label: "Cash shop interval",
+                    disabled: disabled(),
+                    on_limit: move |cash_shop| {
+                        save_limits(RiskyActionLimits {
+                            cash_shop,
+                            ..*limits.peek()
+                        });
+                    },
+                    limit: limits().cash_shop,
+                }
+            }
+            p { class: "mt-2 text-xxs text-secondary-text",
+                "Limited actions are skipped no matter what triggers them. Daily counts reset when the bot restarts."
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionExpRateAlert() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let alert = use_memo(move || settings().exp_rate_alert);
+    let disabled = use_memo(move || !alert().enabled);
+
+    let save_alert = move |exp_rate_alert: ExpRateAlert| {
+        save_settings(Settings {
+            exp_rate_alert,
+            ..settings.peek().clone()
+        });
+    };
+
+    rsx! {
+        Section { title: "EXP rate alert",
+            div { class: "grid grid-cols-3 gap-3",
+                SettingsCheckbox {
+                    label: "Enabled",
+                    on_checked: move |enabled| {
+                        save_alert(ExpRateAlert {
+                            enabled,
+                            ..*alert.peek()
+                        });
+                    },
+                    checked: alert().enabled,
+                }
+                Labeled { label: "Minimum rate (% of session average)",
+                    PrimitiveIntegerInput {
+                        disabled: disabled(),
+                        on_value: move |min_rate_percent| {
+                            save_alert(ExpRateAlert {
+                                min_rate_percent,
+                                ..*alert.peek()
+                            });
+                        },
+                        value: alert().min_rate_percent,
+                    }
+                }
+                SettingsMillisInput {
+                    label: "Recent window",
+                    disabled: disabled(),
+                    on_value: move |window_millis| {
+                        save_alert(ExpRateAlert {
+                            window_millis,
+                            ..*alert.peek()
+                        });
+                    },
+                    value: alert().window_millis,
+                }
+            }
+            p { class: "mt-2 text-xxs text-secondary-text",
+                "Only EXP is tracked because mesos are not visible without opening the inventory."
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionAnnouncements() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let reactions = use_memo(move || settings().announcement_reactions);
+
+    let save_reactions = move |announcement_reactions: AnnouncementReactions| {
+        save_settings(Settings {
+            announcement_reactions,
+            ..settings.peek().clone()
+        });
+    };
+
+    rsx! {
+        Section { title: "Announcements",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsEnumSelect::<AnnouncementReaction> {
+                    label: "World boss reaction",
+                    on_selected: move |world_boss| {
+                        save_reactions(AnnouncementReactions {
+                            world_boss,
+                            ..*reactions.peek()
+                        });
+                    },
+                    selected: reactions().world_boss,
+                }
+                SettingsEnumSelect::<AnnouncementReaction> {
+                    label: "Invasion reaction",
+                    on_selected: move |invasion| {
+                        save_reactions(AnnouncementReactions {
+                            invasion,
+                            ..*reactions.peek()
+                        });
+                    },
+                    selected: reactions().invasion,
+                }
+            }
+            p { class: "mt-2 text-xxs text-secondary-text",
+                "Banners are only detected after providing their templates in the localization tab."
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionRemoteRuneSolving() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let remote = use_memo(move || settings().remote_rune_solving);
+    let disabled = use_memo(move || !remote().enabled);
+
+    let save_remote = move |remote_rune_solving: RemoteRuneSolving| {
+        save_settings(Settings {
+            remote_rune_solving,
+            ..settings.peek().clone()
+        });
+    };
+
+    rsx! {
+        Section { title: "Remote rune solving",
+            div { class: "grid grid-cols-3 gap-3",
+                SettingsCheckbox {
+                    label: "Enabled",
+                    on_checked: move |enabled| {
+                        save_remote(RemoteRuneSolving {
+                            enabled,
+                            ..*remote.peek()
+                        });
+                    },
+                    checked: remote().enabled,
+                }
+                Labeled { label: "Request after failed attempts",
+                    PrimitiveIntegerInput {
+                        disabled: disabled(),
+                        min_value: 1,
+                        max_value: 7,
+                        on_value: move |failed_count| {
+                            save_remote(RemoteRuneSolving {
+                                failed_count,
+                                ..*remote.peek()
+                            });
+                        },
+                        value: remote().failed_count,
+                    }
+                }
+                SettingsMillisInput {
+                    label: "Wait for arrows",
+                    disabled: disabled(),
+                    on_value: move |wait_millis| {
+                        save_remote(RemoteRuneSolving {
+                            wait_millis,
+                            ..*remote.peek()
+                        });
+                    },
+                    value: remote().wait_millis,
+                }
+            }
+            p { class: "mt-2 text-xxs text-secondary-text",
+                "Requires the Discord bot. Submit the arrows with the /rune command when notified."
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionMetricsExport() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let export = use_memo(move || settings().metrics_export);
+    let disabled = use_memo(move || !export().enabled);
+
+    let save_export = move |metrics_export: MetricsExport| {
+        save_settings(Settings {
+            metrics_export,
+            ..settings.peek().clone()
+        });
+    };
+
+    rsx! {
+        Section { title: "Metrics export",
+            div { class: "grid grid-cols-3 gap-3",
+                SettingsCheckbox {
+                    label: "Enabled",
+                    on_checked: move |enabled| {
+                        save_export(MetricsExport {
+                            enabled,
+                            ..*export.peek()
+                        });
+                    },
+                    checked: export().enabled,
+                }
+                SettingsCheckbox {
+                    label: "Listen on all interfaces",
+                    disabled: disabled(),
+                    on_checked: move |all_interfaces| {
+                        save_export(MetricsExport {
+                            all_interfaces,
+                            ..*export.peek()
+                        });
+                    },
+                    checked: export().all_interfaces,
+                }
+                Labeled { label: "Port",
+                    PrimitiveIntegerInput {
+                        disabled: disabled(),
+                        on_value: move |port| {
+                            save_export(MetricsExport {
+                                port,
+                                ..*export.peek()
+                            });
+                        },
+                        value: export().port,
+                        min_value: 1,
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionConditionWebhooks() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+
+    let add_webhook = use_callback(move |_| {
+        let mut settings = settings.peek().clone();
+
+        settings.condition_webhooks.push(ConditionWebhook {
+            name: format!("Webhook {}", settings.condition_webhooks.len() + 1),
+            ..ConditionWebhook::default()
+        });
+        save_settings(settings);
+    });
+    let edit_webhook = use_callback(move |(new_webhook, index): (ConditionWebhook, usize)| {
+        let mut settings = settings.peek().clone();
+        let Some(webhook) = settings.condition_webhooks.get_mut(index) else {
+            return;
+        };
+
+        *webhook = new_webhook;
+        save_settings(settings);
+    });
+    let delete_webhook = use_callback(move |index| {
+        let mut settings = settings.peek().clone();
+
+        settings.condition_webhooks.remove(index);
+        save_settings(settings);
+    });
+
+    rsx! {
+        Section { title: "Condition webhooks",
+            for (index , webhook) in settings().condition_webhooks.into_iter().enumerate() {
+                div { class: "grid grid-cols-2 gap-3 mb-3",
+                    SettingsTextInput {
+                        text_label: "Name",
+                        button_label: "Update",
+                        max_len: ConditionWebhookName::MAX_LEN,
+                        on_value: {
+                            let webhook = webhook.clone();
+                            move |name| {
+                                edit_webhook((
+                                    ConditionWebhook {
+                                        name,
+                                        ..webhook.clone()
+                                    },
+                                    index,
+                                ));
+                            }
+                        },
+                        value: webhook.name.clone(),
+                    }
+                    SettingsTextInput {
+                        text_label: "URL",
+                        button_label: "Update",
+                        sensitive: true,
+                        on_value: {
+                            let webhook = webhook.clone();
+                            move |url| {
+                                edit_webhook((
+                                    ConditionWebhook {
+                                        url,
+                                        ..webhook.clone()
+                                    },
+                                    index,
+                                ));
+                            }
+                        },
+                        value: webhook.url.clone(),
+                    }
+                    SettingsMillisInput {
+                        label: "Timeout",
+                        on_value: {
+                            let webhook = webhook.clone();
+                            move |timeout_millis| {
+                                edit_webhook((
+                                    ConditionWebhook {
+                                        timeout_millis,
+                                        ..webhook.clone()
+                                    },
+                                    index,
+                                ));
+                            }
+                        },
+                        value: webhook.timeout_millis,
+                    }
+                    SettingsMillisInput {
+                        label: "Cache for",
+                        on_value: {
+                            let webhook = webhook.clone();
+                            move |cache_millis| {
+                                edit_webhook((
+                                    ConditionWebhook {
+                                        cache_millis,
+                                        ..webhook.clone()
+                                    },
+                                    index,
+                                ));
+                            }
+                        },
+                        value: webhook.cache_millis,
+                    }
+                    div {}
+                    div { class: "flex items-end",
+                        Button {
+                            class: "w-full mb-[1px]",
+                            style: ButtonStyle::Secondary,
+                            on_click: move |_| {
+                                delete_webhook(index);
+                            },
+
+                            "Remove"
+                        }
+                    }
+                }
+            }
+            Button {
+                class: "w-full",
+                style: ButtonStyle::Secondary,
+                on_click: move |_| {
+                    add_webhook(());
+                },
+
+                "Add webhook"
+            }
+            p { class: "mt-2 text-xxs text-secondary-text",
+                "Priority actions referencing a webhook are only used when its URL responds with a success status and a body other than false or 0."
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionSchedules() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let maps = use_resource(async || query_maps().await.unwrap_or_default());
+    let map_names = use_memo(move || {
+        maps()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|map| map.name)
+            .collect::<Vec<_>>()
+    });
+
+    let add_schedule = use_callback(move |_| {
+        let mut settings = settings.peek().clone();
+
+        settings.schedules.push(Schedule {
+            enabled: true,
+            ..Schedule::default()
+        });
+        save_settings(settings);
+    });
+    let edit_schedule = use_callback(move |(new_schedule, index): (Schedule, usize)| {
+        let mut settings = settings.peek().clone();
+        let Some(schedule) = settings.schedules.get_mut(index) else {
+            return;
+        };
+
+        *schedule = new_schedule;
+        save_settings(settings);
+    });
+    let delete_schedule = use_callback(move |index| {
+        let mut settings = settings.peek().clone();
+
+        settings.schedules.remove(index);
+        save_settings(settings);
+    });
+
+    rsx! {
+        Section { title: "Schedules",
+            for (index , schedule) in settings().schedules.into_iter().enumerate() {
+                div { class: "grid grid-cols-3 gap-3 mb-3",
+                    SettingsEnumSelect::<ScheduleKind> {
+                        label: "Kind",
+                        on_selected: move |kind| {
+                            let map_id = schedule
+                                .map_id
+                                .or_else(|| {
+                                    maps.peek()
+                                        .as_ref()
+                                        .and_then(|maps| maps.first())
+                                        .and_then(|map| map.id)
+                                });
+                            edit_schedule((Schedule { kind, map_id, ..schedule }, index));
+                        },
+                        selected: schedule.kind,
+                    }
+                    ScheduleTimeInput {
+                        label: "Start",
+                        on_value: move |start| {
+                            edit_schedule((Schedule { start, ..schedule }, index));
+                        },
+                        value: schedule.start,
+                    }
+                    if matches!(schedule.kind, ScheduleKind::SwitchMap) {
+                        SettingsSelect::<String> {
+                            label: "Map",
+                            options: map_names(),
+                            on_selected: move |map_index: usize| {
+                                let map_id = maps
+                                    .peek()
+                                    .as_ref()
+                                    .and_then(|maps| maps.get(map_index))
+                                    .and_then(|map| map.id);
+                                edit_schedule((Schedule { map_id, ..schedule }, index));
+                            },
+                            selected: maps()
+                                .unwrap_or_default()
+                                .into_iter()
+                                .position(|map| map.id.is_some() && map.id == schedule.map_id)
+                                .unwrap_or_default(),
+                        }
+                    } else {
+                        ScheduleTimeInput {
+                            label: "End",
+                            on_value: move |end| {
+                                edit_schedule((Schedule { end, ..schedule }, index));
+                            },
+                            value: schedule.end,
+                        }
+                    }
+                    SettingsCheckbox {
+                        label: "Enabled",
+                        on_checked: move |enabled| {
+                            edit_schedule((Schedule { enabled, ..schedule }, index));
+                        },
+                        checked: schedule.enabled,
+                    }
+                    div {}
+                    div { class: "flex items-end",
+                        Button {
+                            class: "w-full mb-[1px]",
+                            style: ButtonStyle::Secondary,
+                            on_click: move |_| {
+                                delete_schedule(index);
+                            },
+
+                            "Remove"
+                        }
+                    }
+                }
+            }
+            Button {
+                class: "w-full",
+                style: ButtonStyle::Secondary,
+                on_click: move |_| {
+                    add_schedule(());
+                },
+
+                "Add schedule"
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionResetRoutine() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let routine = use_memo(move || settings().reset_routine);
+    let disabled = use_memo(move || !routine().enabled);
+
+    let save_routine = move |reset_routine: ResetRoutine| {
+        save_settings(Settings {
+            reset_routine,
+            ..settings.peek().clone()
+        });
+    };
+
+    rsx! {
+        Section { title: "Reset routine",
+            div { class: "grid grid-cols-3 gap-3",
+                SettingsCheckbox {
+                    label: "Enabled",
+                    on_checked: move |enabled| {
+                        save_routine(ResetRoutine {
+                            enabled,
+                            ..*routine.peek()
+                        });
+                    },
+                    checked: routine().enabled,
+                }
+                ScheduleTimeInput {
+                    label: "Server reset (UTC)",
+                    disabled: disabled(),
+                    on_value: move |time| {
+                        save_routine(ResetRoutine {
+                            time,
+                            ..*routine.peek()
+                        });
+                    },
+                    value: routine().time,
+                }
+                Labeled { label: "Avoid changing channel within",
+                    PrimitiveIntegerInput {
+                        disabled: disabled(),
+                        max_value: 60,
+                        suffix: "m",
+                        on_value: move |window_minutes| {
+                            save_routine(ResetRoutine {
+                                window_minutes,
+                                ..*routine.peek()
+                            });
+                        },
+                        value: routine().window_minutes,
+                    }
+                }
+                SettingsCheckbox {
+                    label: "Reapply boosters",
+                    disabled: disabled(),
+                    on_checked: move |reapply_boosters| {
+                        save_routine(ResetRoutine {
+                            reapply_boosters,
+                            ..*routine.peek()
+                        });
+                    },
+                    checked: routine().reapply_boosters,
+                }
+                SettingsCheckbox {
+                    label: "Redo familiars setup",
+                    disabled: disabled(),
+                    on_checked: move |reopen_familiars| {
+                        save_routine(ResetRoutine {
+                            reopen_familiars,
+                            ..*routine.peek()
+                        });
+                    },
+                    checked: routine().reopen_familiars,
+                }
+                SettingsCheckbox {
+                    label: "Restart rotation",
+                    disabled: disabled(),
+                    on_checked: move |restart_rotation| {
+                        save_routine(ResetRoutine {
+                            restart_rotation,
+                            ..*routine.peek()
+                        });
+                    },
+                    checked: routine().restart_rotation,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ScheduleTimeInput(
+    label: &'static str,
+    #[props(default)] disabled: bool,
+    on_value: Callback<ScheduleTime>,
+    value: ScheduleTime,
+) -> Element {
+    rsx! {
+        Labeled { label,
+            div { class: "flex gap-1",
+                PrimitiveIntegerInput {
+                    max_value: 23,
+                    suffix: "h",
+                    disabled,
+                    on_value: move |hour| {
+                        on_value(ScheduleTime { hour, ..value });
+                    },
+                    value: value.hour,
+                }
+                PrimitiveIntegerInput {
+                    max_value: 59,
+                    suffix: "m",
+                    disabled,
+                    on_value: move |minute| {
+                        on_value(ScheduleTime { minute, ..value });
+                    },
+                    value: value.minute,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionMapFailover() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let failover = use_memo(move || settings().map_failover);
+    let disabled = use_memo(move || !failover().enabled);
+    let maps = use_resource(async || query_maps().await.unwrap_or_default());
+    let map_names = use_memo(move || {
+        maps()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|map| map.name)
+            .collect::<Vec<_>>()
+    });
+
+    let save_failover = use_callback(move |map_failover: MapFailover| {
+        save_settings(Settings {
+            map_failover,
+            ..settings.peek().clone()
+        });
+    });
+    let add_map = use_callback(move |_| {
+        let Some(map_id) = maps
+            .peek()
+            .as_ref()
+            .and_then(|maps| maps.first())
+            .and_then(|map| map.id)
+        else {
+            return;
+        };
+        let mut failover = failover.peek().clone();
+
+        failover.map_ids.push(map_id);
+        save_failover(failover);
+    });
+    let edit_map = use_callback(move |(map_index, index): (usize, usize)| {
+        let Some(map_id) = maps
+            .peek()
+            .as_ref()
+            .and_then(|maps| maps.get(map_index))
+            .and_then(|map| map.id)
+        else {
+            return;
+        };
+        let mut failover = failover.peek().clone();
+        let Some(id) = failover.map_ids.get_mut(index) else {
+            return;
+        };
+
+        *id = map_id;
+        save_failover(failover);
+    });
+    let delete_map = use_callback(move |index| {
+        let mut failover = failover.peek().clone();
+
+        failover.map_ids.remove(index);
+        save_failover(failover);
+    });
+
+    rsx! {
+        Section { title: "Map failover",
+            div { class: "grid grid-cols-3 gap-3 mb-3",
+                SettingsCheckbox {
+                    label: "Enabled",
+                    on_checked: move |enabled| {
+                        save_failover(MapFailover {
+                            enabled,
+                            ..failover.peek().clone()
+                        });
+                    },
+                    checked: failover().enabled,
+                }
+                SettingsMillisInput {
+                    label: "Contested duration",
+                    disabled: disabled(),
+                    on_value: move |contested_millis| {
+                        save_failover(MapFailover {
+                            contested_millis,
+                            ..failover.peek().clone()
+                        });
+                    },
+                    value: failover().contested_millis,
+                }
+                div {}
+            }
+            for (index , map_id) in failover().map_ids.into_iter().enumerate() {
+                div { class: "grid grid-cols-3 gap-3 mb-3",
+                    SettingsSelect::<String> {
+                        label: "Map",
+                        options: map_names(),
+                        on_selected: move |map_index| {
+                            edit_map((map_index, index));
+                        },
+                        selected: maps()
+                            .unwrap_or_default()
+                            .into_iter()
+                            .position(|map| map.id == Some(map_id))
+                            .unwrap_or_default(),
+                    }
+                    div {}
+                    div { class: "flex items-end",
+                        Button {
+                            class: "w-full mb-[1px]",
+                            style: ButtonStyle::Secondary,
+                            on_click: move |_| {
+                                delete_map(index);
+                            },
+
+                            "Remove"
+                        }
+                    }
+                }
+            }
+            Button {
+                class: "w-full",
+                style: ButtonStyle::Secondary,
+                on_click: move |_| {
+                    add_map(());
+                },
+
+                "Add map"
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionOthers() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+
+    let import_settings = use_callback(move |file: FileData| async move {
+        let Some(id) = settings.peek().id else {
+            return;
+        };
+        let Ok(bytes) = file.read_bytes().await else {
+            return;
+        };
+        let Ok(mut settings) = serde_json::from_slice::<'_, Settings>(&bytes) else {
+            return;
+        };
+        settings.id = Some(id);
+        save_settings(settings);
+    });
+
+    rsx! {
+        Section { title: "Others",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsCheckbox {
+                    label: "Enable rune solving",
+                    on_checked: move |enable_rune_solving| {
+                        save_settings(Settings {
+                            enable_rune_solving,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_rune_solving,
+                }
+                SettingsCheckbox {
+                    label: "Enable transparent shape solving",
+                    on_checked: move |enable_transparent_shape_solving| {
+                        save_settings(Settings {
+                            enable_transparent_shape_solving,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_transparent_shape_solving,
+                }
+                SettingsCheckbox {
+                    label: "Enable panic mode",
+                    on_checked: move |enable_panic_mode| {
+                        save_settings(Settings {
+                            enable_panic_mode,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().enable_panic_mode,
+                }
+                SettingsEnumSelect::<OtherPlayerReaction> {
+                    label: "Same platform player reaction",
+                    disabled: !settings().enable_panic_mode,
+                    on_selected: move |other_player_same_platform_reaction| {
+                        save_settings(Settings {
+                            other_player_same_platform_reaction,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    selected: settings().other_player_same_platform_reaction,
+                }
+                SettingsEnumSelect::<OtherPlayerReaction> {
+                    label: "Far player reaction",
+                    disabled: !settings().enable_panic_mode,
+                    on_selected: move |other_player_far_reaction| {
+                        save_settings(Settings {
+                            other_player_far_reaction,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    selected: settings().other_player_far_reaction,
+                }
+                SettingsMillisInput {
+                    label: "Change channel after",
+                    disabled: !settings().enable_panic_mode,
+                    on_value: move |other_player_change_channel_millis| {
+                        save_settings(Settings {
+                            other_player_change_channel_millis,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    value: settings().other_player_change_channel_millis,
+                }
+                SettingsCheckbox {
+                    label: "Stop actions on fail or map changed",
+                    on_checked: move |stop_on_fail_or_change_map| {
+                        save_settings(Settings {
+                            stop_on_fail_or_change_map,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().stop_on_fail_or_change_map,
+                }
+                SettingsCheckbox {
+                    label: "Auto switch map on map changed",
+                    on_checked: move |auto_switch_map| {
+                        save_settings(Settings {
+                            auto_switch_map,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().auto_switch_map,
+                }
+                SettingsCheckbox {
+                    label: "Stop actions on player dies",
+                    on_checked: move |stop_on_player_die| {
+                        save_settings(Settings {
+                            stop_on_player_die,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().stop_on_player_die,
+                }
+                Labeled { label: "Tick rate",
+                    PrimitiveIntegerInput {
+                        min_value: MIN_TICK_RATE,
+                        max_value: MAX_TICK_RATE,
+                        suffix: "Hz",
+                        on_value: move |tick_rate| {
+                            save_settings(Settings {
+                                tick_rate,
+                                ..settings.peek().clone()
+                            });
+                        },
+                        value: settings().tick_rate,
+                    }
+                }
+                SettingsCheckbox {
+                    label: "Lower tick rate while idle or stalling",
+                    on_checked: move |adaptive_tick_rate| {
+                        save_settings(Settings {
+                            adaptive_tick_rate,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().adaptive_tick_rate,
+                }
+                SettingsCheckbox {
+                    label: "Shuffle interchangeable actions per session",
+                    on_checked: move |jitter_action_order| {
+                        save_settings(Settings {
+                            jitter_action_order,
+                            ..settings.peek().clone()
+                        });
+                    },
+                    checked: settings().jitter_action_order,
                 }
                 div {}
                 FileInput {
@@ -573,6 +2159,722 @@ fn SectionOthers() -> Element {
     }
 }
 
+#[component]
+fn SectionGridLayout() -> Element {
+    let mut layout = use_signal(|| None::<GridLayout>);
+    let mut status = use_signal(String::default);
+
+    let import_layout = use_callback(move |file: FileData| async move {
+        let Ok(bytes) = file.read_bytes().await else {
+            return;
+        };
+        match serde_json::from_slice::<'_, GridLayout>(&bytes) {
+            Ok(imported) => {
+                status.set(format!("Loaded {} placements", imported.placements.len()));
+                layout.set(Some(imported));
+            }
+            Err(_) => status.set("Invalid grid layout file".to_string()),
+        }
+    });
+
+    rsx! {
+        Section { title: "Grid layout",
+            div { class: "grid grid-cols-3 gap-3",
+                FileInput {
+                    on_file: move |file| async move {
+                        import_layout(file).await;
+                    },
+                    Button { class: "w-full", style: ButtonStyle::Primary, "Import" }
+                }
+                Button {
+                    class: "w-full",
+                    style: ButtonStyle::Primary,
+                    disabled: layout().is_none(),
+                    on_click: move |_| async move {
+                        let Some(layout) = layout.peek().clone() else {
+                            return;
+                        };
+                        if !run_grid_layout(layout).await {
+                            status.set("Bot must be halted to fill grid layout".to_string());
+                        }
+                    },
+
+                    "Start"
+                }
+                Button {
+                    class: "w-full",
+                    style: ButtonStyle::Secondary,
+                    on_click: move |_| async move {
+                        stop_grid_layout().await;
+                    },
+
+                    "Stop"
+                }
+            }
+            p { class: "mt-2 text-xs text-primary-text", {status()} }
+        }
+    }
+}
+
+#[component]
+fn SectionInputAudit() -> Element {
+    const AUDITS_LIMIT: usize = 200;
+
+    let mut key = use_signal(|| None);
+    let mut audits =
+        use_resource(move || async move { query_input_audits(key(), AUDITS_LIMIT).await });
+
+    rsx! {
+        Section { title: "Input audit",
+            div { class: "grid grid-cols-2 gap-3",
+                Labeled { label: "Key",
+                    KeyInput {
+                        class: "border border-primary-border",
+                        optional: true,
+                        on_value: move |value: Option<KeyBinding>| {
+                            key.set(value);
+                        },
+                        value: Some(key()),
+                    }
+                }
+                div { class: "flex items-end",
+                    Button {
+                        class: "w-full mb-[1px]",
+                        style: ButtonStyle::Secondary,
+                        on_click: move |_| {
+                            audits.restart();
+                        },
+
+                        "Refresh"
+                    }
+                }
+            }
+            div { class: "flex flex-col gap-1 mt-2 max-h-80 overflow-y-auto text-xs text-primary-text",
+                for audit in audits().unwrap_or_default() {
+                    p {
+                        {
+                            format!(
+                                "{} | Tick {} | {} {}",
+                                humantime::format_rfc3339_millis(
+                                    UNIX_EPOCH + Duration::from_millis(audit.timestamp_millis),
+                                ),
+                                audit.tick,
+                                audit.key,
+                                audit.stroke,
+                            )
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionLocalization() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let mut localization = use_context::<AppState>().localization;
+    let mut localizations = use_resource(async || query_localizations().await.unwrap_or_default());
+    let localization_names = use_memo(move || {
+        localizations()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|localization| localization.name)
+            .collect::<Vec<_>>()
+    });
+    // Maps `Settings::localization_id` to the index in `localizations` with the first profile
+    // as fallback
+    let localization_index = use_memo(move || {
+        let localizations = localizations().unwrap_or_default();
+        let id = settings().localization_id;
+        localizations
+            .iter()
+            .position(|localization| id.is_some() && localization.id == id)
+            .or((!localizations.is_empty()).then_some(0))
+    });
+
+    let select_localization = use_callback(move |id: Option<i64>| {
+        save_settings(Settings {
+            localization_id: id,
+            ..settings.peek().clone()
+        });
+        // Re-queries the in use profile when the localization tab is opened
+        localization.set(None);
+    });
+
+    rsx! {
+        Section { title: "Localization",
+            Labeled { label: "Profile",
+                NamedSelect {
+                    on_create: move |name| {
+                        spawn(async move {
+                            let created = upsert_localization(Localization {
+                                name,
+                                ..Localization::default()
+                            })
+                            .await;
+                            localizations.restart();
+                            select_localization(created.id);
+                        });
+                    },
+                    on_delete: move |_| {
+                        let current = localization_index()
+                            .and_then(|index| localizations.peek().as_ref()?.get(index).cloned());
+                        let Some(current) = current else {
+                            return;
+                        };
+                        spawn(async move {
+                            if delete_localization(current).await {
+                                localizations.restart();
+                                select_localization(None);
+                            }
+                        });
+                    },
+                    delete_disabled: localization_names().len() <= 1,
+
+                    Select::<usize> {
+                        class: "w-full",
+                        on_selected: move |index: usize| {
+                            let id = localizations
+                                .peek()
+                                .as_ref()
+                                .and_then(|localizations| localizations.get(index))
+                                .and_then(|localization| localization.id);
+                            select_localization(id);
+                        },
+
+                        for (i , name) in localization_names().into_iter().enumerate() {
+                            SelectOption::<usize> {
+                                value: i,
+                                selected: localization_index() == Some(i),
+                                label: name,
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionReplay() -> Element {
+    let mut dir = use_signal(String::default);
+    let mut error = use_signal(|| None::<&'static str>);
+    let mut status = use_resource(move || async move { query_replay_status().await });
+    let status_text = use_memo(move || {
+        if let Some(error) = error() {
+            return error.to_string();
+        }
+
+        match status().unwrap_or_default() {
+            ReplayStatus::Idle => "Not recording or replaying".to_string(),
+            ReplayStatus::Recording { dir, ticks } => {
+                format!("Recording {ticks} ticks to {dir}")
+            }
+            ReplayStatus::Replaying { dir, tick, ticks } => {
+                format!("Replaying tick {tick} of {ticks} from {dir}")
+            }
+            ReplayStatus::Finished {
+                dir,
+                ticks,
+                mismatch,
+            } => match mismatch {
+                Some(mismatch) => format!(
+                    "Finished {ticks} ticks of {dir}, tick {} diverged from {} to {}",
+                    mismatch.tick, mismatch.expected, mismatch.actual
+                ),
+                None => format!("Finished {ticks} ticks of {dir} without divergence"),
+            },
+        }
+    });
+
+    rsx! {
+        Section { title: "Replay",
+            div { class: "grid grid-cols-3 gap-3",
+                Button {
+                    class: "w-full",
+                    style: ButtonStyle::Primary,
+                    on_click: move |_| async move {
+                        let started = start_recording_replay().await.is_some();
+                        error.set((!started).then_some("Already recording or replaying"));
+                        status.restart();
+                    },
+
+                    "Record"
+                }
+                Button {
+                    class: "w-full",
+                    style: ButtonStyle::Secondary,
+                    on_click: move |_| async move {
+                        stop_replay().await;
+                        error.set(None);
+                        status.restart();
+                    },
+
+                    "Stop"
+                }
+                Button {
+                    class: "w-full",
+                    style: ButtonStyle::Secondary,
+                    on_click: move |_| {
+                        status.restart();
+                    },
+
+                    "Refresh"
+                }
+                TextInput {
+                    class: "col-span-2 h-6",
+                    placeholder: "Enter a recording folder path...",
+                    value: dir(),
+                    on_value: move |value| {
+                        dir.set(value);
+                    },
+                }
+                Button {
+                    class: "w-full",
+                    style: ButtonStyle::Primary,
+                    disabled: dir().is_empty(),
+                    on_click: move |_| async move {
+                        let started = start_replaying(dir.peek().clone()).await;
+                        error
+                            .set(
+                                (!started)
+                                    .then_some(
+                                        "Recording cannot be replayed with the current map, character or tick rate",
+                                    ),
+                            );
+                        status.restart();
+                    },
+
+                    "Replay"
+                }
+            }
+            p { class: "mt-2 text-xs text-primary-text", {status_text()} }
+        }
+    }
+}
+
+#[component]
+fn SectionEventLog() -> Element {
+    let mut format = use_signal(EventLogFormat::default);
+    let mut status = use_signal(String::default);
+
+    rsx! {
+        Section { title: "Event log",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsEnumSelect::<EventLogFormat> {
+                    label: "Format",
+                    on_selected: move |selected| {
+                        format.set(selected);
+                    },
+                    selected: format(),
+                }
+                div { class: "flex items-end",
+                    Button {
+                        class: "w-full mb-[1px]",
+                        style: ButtonStyle::Secondary,
+                        on_click: move |_| async move {
+                            match export_event_log(*format.peek()).await {
+                                Some(path) => status.set(format!("Exported to {path}")),
+                                None => status.set("Failed to export".to_string()),
+                            }
+                        },
+
+                        "Export session"
+                    }
+                }
+            }
+            if !status().is_empty() {
+                p { class: "mt-2 text-xs text-primary-text", {status()} }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionSessionTimelapse() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let timelapse = use_memo(move || settings().session_timelapse);
+
+    let save_timelapse = move |session_timelapse: SessionTimelapse| {
+        save_settings(Settings {
+            session_timelapse,
+            ..settings.peek().clone()
+        });
+    };
+
+    rsx! {
+        Section { title: "Session timelapse",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsCheckbox {
+                    label: "Enabled",
+                    on_checked: move |enabled| {
+                        save_timelapse(SessionTimelapse {
+                            enabled,
+                            ..*timelapse.peek()
+                        });
+                    },
+                    checked: timelapse().enabled,
+                }
+                SettingsMillisInput {
+                    label: "Snapshot interval",
+                    disabled: !timelapse().enabled,
+                    on_value: move |interval_millis| {
+                        save_timelapse(SessionTimelapse {
+                            interval_millis,
+                            ..*timelapse.peek()
+                        });
+                    },
+                    value: timelapse().interval_millis,
+                }
+            }
+            p { class: "mt-2 text-xxs text-secondary-text",
+                "Videos are saved to the timelapses folder next to the executable when the bot halts."
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionStateMachine() -> Element {
+    let mut format = use_signal(StateMachineFormat::default);
+    let diagram = use_resource(move || async move { export_player_state_machine(format()).await });
+    let extension = use_memo(move || match format() {
+        StateMachineFormat::Dot => "dot",
+        StateMachineFormat::Mermaid => "mmd",
+    });
+
+    rsx! {
+        Section { title: "Player state machine",
+            div { class: "grid grid-cols-2 gap-3",
+                SettingsEnumSelect::<StateMachineFormat> {
+                    label: "Format",
+                    on_selected: move |selected| {
+                        format.set(selected);
+                    },
+                    selected: format(),
+                }
+                div { class: "flex items-end",
+                    FileOutput {
+                        class: "w-full",
+                        file_type: "text/plain",
+                        download: format!("player_states.{}", extension()),
+                        on_file: move |_| { diagram.peek().clone().unwrap_or_default().into_bytes() },
+                        Button {
+                            class: "w-full mb-[1px]",
+                            style: ButtonStyle::Secondary,
+                            "Export diagram"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionConfigSync() -> Element {
+    let context = use_context::<SettingsContext>();
+    let settings = context.settings;
+    let save_settings = context.save_settings;
+    let mut app_settings = use_context::<AppState>().settings;
+    let config = use_memo(move || settings().config_sync);
+    let mut force = use_signal(|| false);
+    let mut syncing = use_signal(|| false);
+    let mut status = use_signal(String::default);
+
+    let save_config = move |config_sync: ConfigSync| {
+        save_settings(Settings {
+            config_sync,
+            ..settings.peek().clone()
+        });
+    };
+    let sync = move |direction: SyncDirection| async move {
+        syncing.set(true);
+        let message = match sync_database(direction, *force.peek()).await {
+            Some(SyncStatus::Pushed) => "Pushed to server".to_string(),
+            Some(SyncStatus::Pulled) => {
+                app_settings.set(Some(query_settings().await));
+                "Pulled from server".to_string()
+            }
+            Some(SyncStatus::UpToDate) => "Already up to date".to_string(),
+            Some(SyncStatus::Conflict) => {
+                "Both sides changed since the last sync, check Overwrite conflicts to continue"
+                    .to_string()
+            }
+            None => format!("Failed to {}", direction.to_string().to_lowercase()),
+        };
+        status.set(message);
+        force.set(false);
+        syncing.set(false);
+    };
+
+    rsx! {
+        Section { title: "Configuration sync",
+            div { class: "grid grid-cols-3 gap-3",
+                SettingsTextInput {
+                    text_label: "WebDAV file URL",
+                    button_label: "Update",
+                    sensitive: true,
+                    on_value: move |url| {
+                        save_config(ConfigSync {
+                            url,
+                            ..config.peek().clone()
+                        });
+                    },
+                    value: config().url,
+                }
+                SettingsTextInput {
+                    text_label: "Username",
+                    button_label: "Update",
+                    on_value: move |username| {
+                        save_config(ConfigSync {
+                            username,
+                            ..config.peek().clone()
+                        });
+                    },
+                    value: config().username,
+                }
+                SettingsTextInput {
+                    text_label: "Password",
+                    button_label: "Update",
+                    sensitive: true,
+                    on_value: move |password| {
+                        save_config(ConfigSync {
+                            password,
+                            ..config.peek().clone()
+                        });
+                    },
+                    value: config().password,
+                }
+                SettingsCheckbox {
+                    label: "Overwrite conflicts",
+                    disabled: syncing(),
+                    on_checked: move |checked| {
+                        force.set(checked);
+                    },
+                    checked: force(),
+                }
+                div { class: "flex items-end",
+                    Button {
+                        class: "w-full mb-[1px]",
+                        style: ButtonStyle::Secondary,
+                        disabled: syncing() || config().url.is_empty(),
+                        on_click: move |_| sync(SyncDirection::Push),
+
+                        "Push"
+                    }
+                }
+                div { class: "flex items-end",
+                    Button {
+                        class: "w-full mb-[1px]",
+                        style: ButtonStyle::Primary,
+                        disabled: syncing() || config().url.is_empty(),
+                        on_click: move |_| sync(SyncDirection::Pull),
+
+                        "Pull"
+                    }
+                }
+            }
+            if !status().is_empty() {
+                p { class: "mt-2 text-xs text-primary-text", {status()} }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionDatabaseBackups() -> Element {
+    let mut settings = use_context::<AppState>().settings;
+    let mut backups = use_resource(async || query_database_backups().await);
+    let mut selected = use_signal(|| 0usize);
+    let mut status = use_signal(String::default);
+
+    rsx! {
+        Section { title: "Database backups",
+            div { class: "grid grid-cols-3 gap-3",
+                SettingsSelect::<String> {
+                    label: "Backup",
+                    options: backups().unwrap_or_default(),
+                    on_selected: move |index| {
+                        selected.set(index);
+                    },
+                    selected: selected(),
+                }
+                div { class: "flex items-end",
+                    Button {
+                        class: "w-full mb-[1px]",
+                        style: ButtonStyle::Secondary,
+                        on_click: move |_| async move {
+                            match backup_database().await {
+                                Some(name) => status.set(format!("Backed up to {name}")),
+                                None => status.set("Failed to back up".to_string()),
+                            }
+                            selected.set(0);
+                            backups.restart();
+                        },
+
+                        "Back up now"
+                    }
+                }
+                div { class: "flex items-end",
+                    Button {
+                        class: "w-full mb-[1px]",
+                        style: ButtonStyle::Primary,
+                        disabled: backups().unwrap_or_default().is_empty(),
+                        on_click: move |_| async move {
+                            let Some(name) = backups
+                                .peek()
+                                .as_ref()
+                                .and_then(|backups| backups.get(*selected.peek()).cloned())
+                            else {
+                                return;
+                            };
+                            if restore_database_backup(name.clone()).await {
+                                status.set(format!("Restored from {name}"));
+                                settings.set(Some(query_settings().await));
+                            } else {
+                                status.set(format!("Failed to restore from {name}"));
+                            }
+                            selected.set(0);
+                            backups.restart();
+                        },
+
+                        "Restore"
+                    }
+                }
+            }
+            if !status().is_empty() {
+                p { class: "mt-2 text-xs text-primary-text", {status()} }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionLegacyImport() -> Element {
+    let mut content = use_signal(|| None::<String>);
+    let mut report = use_signal(|| None::<LegacyImportReport>);
+    let mut status = use_signal(String::default);
+    let can_import = use_memo(move || {
+        report().is_some_and(|report| {
+            !report.records.is_empty() && report.records.iter().all(|record| record.error.is_none())
+        })
+    });
+
+    rsx! {
+        Section { title: "Legacy import",
+            p { class: "text-xxs text-secondary-text pb-2",
+                "Imports maps and characters from legacy exports. Records replace the existing ones with the same name. The changes are previewed before importing."
+            }
+            div { class: "grid grid-cols-2 gap-3",
+                FileInput {
+                    on_file: move |file: FileData| async move {
+                        report.set(None);
+                        let Ok(bytes) = file.read_bytes().await else {
+                            status.set("Failed to read file".to_string());
+                            return;
+                        };
+                        let Ok(text) = String::from_utf8(bytes.to_vec()) else {
+                            status.set("File is not a JSON export".to_string());
+                            return;
+                        };
+                        match import_legacy_records(text.clone(), true).await {
+                            Some(dry_run) => {
+                                status.set(String::default());
+                                content.set(Some(text));
+                                report.set(Some(dry_run));
+                            }
+                            None => status.set("File is not a JSON export".to_string()),
+                        }
+                    },
+                    Button { class: "w-full", style: ButtonStyle::Secondary, "Preview file" }
+                }
+                Button {
+                    class: "w-full",
+                    style: ButtonStyle::Primary,
+                    disabled: !can_import(),
+                    on_click: move |_| async move {
+                        let Some(text) = content.peek().clone() else {
+                            return;
+                        };
+                        match import_legacy_records(text, false).await {
+                            Some(imported) if imported.committed => {
+                                status.set(format!("Imported {} records", imported.records.len()));
+                                content.set(None);
+                                report.set(None);
+                            }
+                            Some(_) | None => status.set("Failed to import".to_string()),
+                        }
+                    },
+
+                    "Import"
+                }
+            }
+            if let Some(report) = report() {
+                div { class: "flex flex-col gap-1 mt-2 max-h-80 overflow-y-auto text-xs text-primary-text",
+                    for record in report.records {
+                        p {
+                            {
+                                format!(
+                                    "{} {} ({})",
+                                    record
+                                        .kind
+                                        .map(|kind| kind.to_string())
+                                        .unwrap_or("Unknown".to_string()),
+                                    record.name,
+                                    if record.replacing { "replaces existing" } else { "new" },
+                                )
+                            }
+                        }
+                        if let Some(error) = record.error {
+                            p { class: "pl-2 text-danger-text", {error} }
+                        }
+                        for change in record.changes {
+                            p { class: "pl-2 text-secondary-text", {change} }
+                        }
+                    }
+                }
+            }
+            if !status().is_empty() {
+                p { class: "mt-2 text-xs text-primary-text", {status()} }
+            }
+        }
+    }
+}
+
+#[component]
+fn SectionCapabilities() -> Element {
+    let statuses = use_resource(async || query_capabilities().await);
+
+    rsx! {
+        Section { title: "Features",
+            div { class: "flex flex-col gap-1",
+                for status in statuses().unwrap_or_default() {
+                    p { class: "text-xs text-primary-text",
+                        if status.is_available() {
+                            {format!("{}: available", status.capability)}
+                        } else {
+                            {
+                                format!(
+                                    "{}: disabled, missing {}",
+                                    status.capability,
+                                    status.missing_resources.join(", "),
+                                )
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[component]
 fn SettingsSelect<T: 'static + Clone + PartialEq + Display>(
     label: &'static str,
@@ -675,6 +2977,7 @@ fn SettingsTextInput(
     text_label: String,
     button_label: String,
     #[props(default)] sensitive: bool,
+    #[props(default)] max_len: Option<usize>,
     on_value: Callback<String>,
     value: String,
 ) -> Element {
@@ -684,10 +2987,14 @@ fn SettingsTextInput(
     let mut hidden = use_signal(|| sensitive);
 
     use_effect(use_reactive!(|value| text.set(value)));
+    let too_long = max_len.is_some_and(|max_len| text().len() > max_len);
+    let tooltip = max_len
+        .filter(|_| too_long)
+        .map(|max_len| format!("Must be at most {max_len} bytes."));
 
     rsx! {
         div { class: "relative group",
-            Labeled { label: text_label,
+            Labeled { label: text_label, tooltip,
                 TextInput {
                     class: "h-6",
                     sensitive: hidden(),
@@ -715,6 +3022,7 @@ fn SettingsTextInput(
             Button {
                 class: "w-full mb-[1px]",
                 style: ButtonStyle::Primary,
+                disabled: too_long,
                 on_click: move |_| {
                     on_value(text.peek().clone());
                 },